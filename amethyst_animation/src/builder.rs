@@ -0,0 +1,450 @@
+//! Builders for creating animations in code.
+
+use std::f32::consts::PI;
+
+use amethyst_assets::{Handle, Loader, ProcessingQueue, ProgressCounter};
+use amethyst_core::ecs::Resources;
+use amethyst_error::{format_err, Error};
+use minterpolate::{InterpolationFunction, InterpolationPrimitive};
+
+use crate::{
+    resources::{Animation, AnimationSampling, Sampler},
+    util::SamplerPrimitive,
+};
+
+/// Number of keyframes an eased segment is baked into.
+const EASE_KEYFRAMES: usize = 16;
+/// Number of keyframes for the curves bouncing around their target.
+const OSCILLATING_EASE_KEYFRAMES: usize = 32;
+
+/// Easing curves for [`SamplerBuilder::ease_to`], baked into linearly interpolated keyframes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Ease {
+    /// Constant speed
+    Linear,
+    /// Quadratic, accelerating from zero velocity
+    QuadIn,
+    /// Quadratic, decelerating to zero velocity
+    QuadOut,
+    /// Quadratic, accelerating until halfway, then decelerating
+    QuadInOut,
+    /// Cubic, accelerating from zero velocity
+    CubicIn,
+    /// Cubic, decelerating to zero velocity
+    CubicOut,
+    /// Cubic, accelerating until halfway, then decelerating
+    CubicInOut,
+    /// Sinusoidal, accelerating until halfway, then decelerating
+    SineInOut,
+    /// Overshoots the target before settling on it
+    BackOut,
+    /// Springs around the target before settling on it
+    ElasticOut,
+    /// Bounces off the target with decreasing height, like a dropped ball
+    BounceOut,
+}
+
+impl Ease {
+    /// Eased progress of the segment for the linear progress `t`, both in the range of 0.0 to 1.0.
+    ///
+    /// `BackOut` and `ElasticOut` temporarily leave that range.
+    #[must_use]
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.max(0.).min(1.);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1. - (1. - t) * (1. - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2. * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(2) / 2.
+                }
+            }
+            Ease::CubicIn => t * t * t,
+            Ease::CubicOut => 1. - (1. - t).powi(3),
+            Ease::CubicInOut => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(3) / 2.
+                }
+            }
+            Ease::SineInOut => -((PI * t).cos() - 1.) / 2.,
+            Ease::BackOut => {
+                let overshoot = 1.701_58;
+                1. + (overshoot + 1.) * (t - 1.).powi(3) + overshoot * (t - 1.).powi(2)
+            }
+            Ease::ElasticOut => {
+                if t <= 0. || t >= 1. {
+                    t
+                } else {
+                    2_f32.powf(-10. * t) * ((t * 10. - 0.75) * (2. * PI / 3.)).sin() + 1.
+                }
+            }
+            Ease::BounceOut => {
+                let (n, d) = (7.5625, 2.75);
+                if t < 1. / d {
+                    n * t * t
+                } else if t < 2. / d {
+                    let t = t - 1.5 / d;
+                    n * t * t + 0.75
+                } else if t < 2.5 / d {
+                    let t = t - 2.25 / d;
+                    n * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d;
+                    n * t * t + 0.984_375
+                }
+            }
+        }
+    }
+
+    fn keyframes(self) -> usize {
+        match self {
+            Ease::Linear => 1,
+            Ease::ElasticOut | Ease::BounceOut => OSCILLATING_EASE_KEYFRAMES,
+            _ => EASE_KEYFRAMES,
+        }
+    }
+}
+
+/// Builds a [`Sampler`] from keyframes.
+///
+/// Keyframes are kept ordered by time, a keyframe added at the time of an existing one replaces
+/// it. Eased segments and the other helpers are baked into keyframes, so they are best used with
+/// the default `Linear` interpolation.
+///
+/// Spline interpolation functions get their tangents generated on `build`: Catmull-Rom splines
+/// repeat the first and last keyframes as control points, cubic splines use flat tangents.
+///
+/// ```
+/// use amethyst_animation::{Ease, SamplerBuilder, SamplerPrimitive};
+///
+/// // Raise by half a unit, then drop back with a bounce.
+/// let sampler = SamplerBuilder::<SamplerPrimitive<f32>>::new()
+///     .with_keyframe(0., SamplerPrimitive::Vec3([0., 0., 0.]))
+///     .ease_to(0.4, SamplerPrimitive::Vec3([0., 0.5, 0.]), Ease::QuadOut)
+///     .ease_to(1.2, SamplerPrimitive::Vec3([0., 0., 0.]), Ease::BounceOut)
+///     .build();
+/// assert_eq!(sampler.input.last(), Some(&1.2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SamplerBuilder<T>
+where
+    T: InterpolationPrimitive,
+{
+    keyframes: Vec<(f32, T)>,
+    function: InterpolationFunction<T>,
+}
+
+impl<T> SamplerBuilder<T>
+where
+    T: InterpolationPrimitive + Clone,
+{
+    /// Create an empty sampler builder, using linear interpolation.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            function: InterpolationFunction::Linear,
+        }
+    }
+
+    /// Interpolate between the keyframes using the given function.
+    #[must_use]
+    pub fn with_function(mut self, function: InterpolationFunction<T>) -> Self {
+        self.function = function;
+        self
+    }
+
+    /// Add a keyframe with the value at the time, in seconds.
+    #[must_use]
+    pub fn with_keyframe(mut self, time: f32, value: T) -> Self {
+        self.insert(time, value);
+        self
+    }
+
+    /// Time and value of the last keyframe.
+    #[must_use]
+    pub fn last(&self) -> Option<&(f32, T)> {
+        self.keyframes.last()
+    }
+
+    /// Keep the value of the last keyframe until the time, in seconds.
+    #[must_use]
+    pub fn hold(mut self, time: f32) -> Self {
+        if let Some((_, value)) = self.keyframes.last().cloned() {
+            self.insert(time, value);
+        }
+        self
+    }
+
+    /// Move from the last keyframe to the value at the time, in seconds, following the easing
+    /// curve. Without a previous keyframe this adds a keyframe with the value.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ease_to(mut self, time: f32, value: T, ease: Ease) -> Self {
+        let (start_time, start) = match self.keyframes.last().cloned() {
+            Some(last) if last.0 < time => last,
+            _ => return self.with_keyframe(time, value),
+        };
+        let delta = value.sub(&start);
+        let steps = ease.keyframes();
+        for step in 1..steps {
+            let t = step as f32 / steps as f32;
+            self.insert(
+                start_time + (time - start_time) * t,
+                start.add(&delta.mul(ease.apply(t))),
+            );
+        }
+        self.with_keyframe(time, value)
+    }
+
+    /// Drop from the last keyframe to the value at the time, in seconds, bouncing off it.
+    #[must_use]
+    pub fn bounce(self, time: f32, value: T) -> Self {
+        self.ease_to(time, value, Ease::BounceOut)
+    }
+
+    /// Swing smoothly from the last keyframe to the value and back, `cycles` times with the given
+    /// period in seconds, e.g. for breathing or hovering idle motion.
+    #[must_use]
+    pub fn oscillate(mut self, value: T, period: f32, cycles: u32) -> Self {
+        let (mut time, rest) = match self.keyframes.last().cloned() {
+            Some(last) => last,
+            None => return self,
+        };
+        for _ in 0..cycles {
+            self = self
+                .ease_to(time + period / 2., value.clone(), Ease::SineInOut)
+                .ease_to(time + period, rest.clone(), Ease::SineInOut);
+            time += period;
+        }
+        self
+    }
+
+    /// Build the sampler.
+    #[must_use]
+    pub fn build(self) -> Sampler<T> {
+        let (input, mut output): (Vec<f32>, Vec<T>) = self.keyframes.into_iter().unzip();
+        match self.function {
+            InterpolationFunction::CatmullRomSpline => {
+                if let (Some(first), Some(last)) = (output.first().cloned(), output.last().cloned())
+                {
+                    output.insert(0, first);
+                    output.push(last);
+                }
+            }
+            InterpolationFunction::CubicSpline => {
+                output = output
+                    .into_iter()
+                    .flat_map(|value| {
+                        let flat = value.mul(0.);
+                        vec![flat.clone(), value, flat]
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+        Sampler {
+            input,
+            output,
+            function: self.function,
+        }
+    }
+
+    fn insert(&mut self, time: f32, value: T) {
+        match self
+            .keyframes
+            .iter()
+            .position(|(keyframe_time, _)| *keyframe_time >= time)
+        {
+            Some(index) if (self.keyframes[index].0 - time).abs() <= f32::EPSILON => {
+                self.keyframes[index].1 = value;
+            }
+            Some(index) => self.keyframes.insert(index, (time, value)),
+            None => self.keyframes.push((time, value)),
+        }
+    }
+}
+
+impl SamplerBuilder<SamplerPrimitive<f32>> {
+    /// Shake around the last keyframe for the duration in seconds, displacing every component of
+    /// the value by up to `amplitude`, `frequency` times per second. The shake fades out over the
+    /// duration and ends on the last keyframe value.
+    ///
+    /// The displacement is pseudo random, the same `seed` always gives the same shake.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn shake(mut self, duration: f32, amplitude: f32, frequency: f32, seed: u32) -> Self {
+        let (start_time, rest) = match self.keyframes.last().cloned() {
+            Some(last) => last,
+            None => return self,
+        };
+        let steps = (duration * frequency).ceil().max(1.) as u32;
+        for step in 1..steps {
+            let t = step as f32 / steps as f32;
+            let strength = amplitude * (1. - t);
+            let jitter = |axis: u32| noise(seed, step * 4 + axis) * strength;
+            let offset = match rest {
+                SamplerPrimitive::Scalar(_) => SamplerPrimitive::Scalar(jitter(0)),
+                SamplerPrimitive::Vec2(_) => SamplerPrimitive::Vec2([jitter(0), jitter(1)]),
+                SamplerPrimitive::Vec3(_) => {
+                    SamplerPrimitive::Vec3([jitter(0), jitter(1), jitter(2)])
+                }
+                SamplerPrimitive::Vec4(_) => {
+                    SamplerPrimitive::Vec4([jitter(0), jitter(1), jitter(2), jitter(3)])
+                }
+            };
+            self.insert(start_time + duration * t, rest.add(&offset));
+        }
+        self.with_keyframe(start_time + duration, rest)
+    }
+}
+
+impl<T> From<SamplerBuilder<T>> for Sampler<T>
+where
+    T: InterpolationPrimitive + Clone,
+{
+    fn from(builder: SamplerBuilder<T>) -> Self {
+        builder.build()
+    }
+}
+
+/// Pseudo random value in the range of -1.0 to 1.0.
+#[allow(clippy::cast_precision_loss)]
+fn noise(seed: u32, index: u32) -> f32 {
+    let mut x = seed ^ index.wrapping_mul(0x9E37_79B9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846C_A68B);
+    x ^= x >> 16;
+    (x as f32 / u32::MAX as f32) * 2. - 1.
+}
+
+/// Builds an [`Animation`] from samplers created in code, and loads it and its samplers through
+/// the processing queues of the asset types, like animations loaded from files.
+///
+/// ```no_run
+/// use amethyst_animation::{
+///     AnimationBuilder, SamplerBuilder, SamplerPrimitive, TransformChannel,
+/// };
+/// use amethyst_assets::DefaultLoader;
+/// use amethyst_core::{ecs::Resources, Transform};
+///
+/// # fn build(resources: &Resources) -> Result<(), amethyst_error::Error> {
+/// let loader = resources.get::<DefaultLoader>().unwrap();
+/// let animation = AnimationBuilder::<Transform>::new()
+///     .with_sampler(
+///         0,
+///         TransformChannel::Translation,
+///         SamplerBuilder::new()
+///             .with_keyframe(0., SamplerPrimitive::Vec3([0., 0., 0.]))
+///             .oscillate(SamplerPrimitive::Vec3([0., 0.1, 0.]), 2., 1),
+///     )
+///     .build(&*loader, resources)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AnimationBuilder<T>
+where
+    T: AnimationSampling,
+{
+    nodes: Vec<(usize, T::Channel, Sampler<T::Primitive>)>,
+}
+
+impl<T> AnimationBuilder<T>
+where
+    T: AnimationSampling,
+{
+    /// Create an empty animation builder.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Animate the channel of the node with the sampler.
+    #[must_use]
+    pub fn with_sampler(
+        mut self,
+        node_index: usize,
+        channel: T::Channel,
+        sampler: impl Into<Sampler<T::Primitive>>,
+    ) -> Self {
+        self.nodes.push((node_index, channel, sampler.into()));
+        self
+    }
+
+    /// Load the samplers and the animation.
+    ///
+    /// Fails if a sampler has no keyframes, or if the animation or sampler asset types aren't
+    /// registered.
+    pub fn build<L: Loader>(
+        self,
+        loader: &L,
+        resources: &Resources,
+    ) -> Result<Handle<Animation<T>>, Error> {
+        self.load(loader, resources, None)
+    }
+
+    /// Load the samplers and the animation, tracking them in the progress counter.
+    ///
+    /// Fails if a sampler has no keyframes, or if the animation or sampler asset types aren't
+    /// registered.
+    pub fn build_with_progress<L: Loader>(
+        self,
+        loader: &L,
+        resources: &Resources,
+        progress: &mut ProgressCounter,
+    ) -> Result<Handle<Animation<T>>, Error> {
+        self.load(loader, resources, Some(progress))
+    }
+
+    fn load<L: Loader>(
+        self,
+        loader: &L,
+        resources: &Resources,
+        mut progress: Option<&mut ProgressCounter>,
+    ) -> Result<Handle<Animation<T>>, Error> {
+        if let Some((node_index, channel, _)) = self
+            .nodes
+            .iter()
+            .find(|(_, _, sampler)| sampler.input.is_empty())
+        {
+            return Err(format_err!(
+                "Sampler for node {} channel {:?} has no keyframes",
+                node_index,
+                channel
+            ));
+        }
+
+        let sampler_queue = resources
+            .get::<ProcessingQueue<Sampler<T::Primitive>>>()
+            .ok_or_else(|| format_err!("Sampler asset type isn't registered"))?;
+        let animation_queue = resources
+            .get::<ProcessingQueue<Animation<T>>>()
+            .ok_or_else(|| format_err!("Animation asset type isn't registered"))?;
+
+        let mut animation = Animation::new();
+        for (node_index, channel, sampler) in self.nodes {
+            let handle = match progress.as_deref_mut() {
+                Some(progress) => loader.load_from_data(sampler, progress, &sampler_queue),
+                None => loader.load_from_data(sampler, (), &sampler_queue),
+            };
+            animation.add(node_index, channel, handle);
+        }
+
+        Ok(match progress {
+            Some(progress) => loader.load_from_data(animation, progress, &animation_queue),
+            None => loader.load_from_data(animation, (), &animation_queue),
+        })
+    }
+}
@@ -62,6 +62,7 @@
 pub use minterpolate::{InterpolationFunction, InterpolationPrimitive};
 
 pub use self::{
+    builder::{AnimationBuilder, Ease, SamplerBuilder},
     bundle::{AnimationBundle, SamplingBundle, VertexSkinningBundle},
     material::{MaterialChannel, MaterialPrimitive},
     resources::{
@@ -75,6 +76,7 @@ pub use self::{
     util::{get_animation_set, SamplerPrimitive},
 };
 
+mod builder;
 mod bundle;
 mod material;
 mod resources;
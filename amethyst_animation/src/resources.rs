@@ -363,6 +363,8 @@ where
     pub after: T::Primitive,
     /// Control the rate of animation, default is 1.0
     pub rate_multiplier: f32,
+    /// Sync group of the sampler, see `SamplerControlSet::synchronize`
+    pub sync_group: Option<u32>,
 }
 
 /// Sampler control set, containing a set of sampler controllers for a single component.
@@ -371,6 +373,9 @@ where
 /// samplers. The target component specifies if it can be blended, if it can't, the last added
 /// sampler wins.
 ///
+/// Running samplers sharing a sync group play at the same normalized phase, so that blended
+/// clips of different lengths (like a walk and a run cycle) stay aligned.
+///
 /// ### Type parameters:
 ///
 /// - `T`: the component type that the sampling should be applied to
@@ -472,6 +477,65 @@ where
             .for_each(|sampler| sampler.rate_multiplier = rate_multiplier);
     }
 
+    /// Update sync group
+    pub fn set_sync_group(&mut self, control_id: u64, sync_group: Option<u32>) {
+        self.samplers
+            .iter_mut()
+            .filter(|t| t.control_id == control_id)
+            .for_each(|sampler| sampler.sync_group = sync_group);
+    }
+
+    /// Align the running samplers of each sync group on the normalized phase of the group leader,
+    /// the sampler with the highest blend weight. On equal weights the sampler added first leads,
+    /// and samplers that haven't advanced yet only lead when no other sampler of the group runs.
+    ///
+    /// Followers keep their own duration, so a follower twice as long as the leader plays at
+    /// twice the speed, and a sampler joining the group starts at the phase of the group.
+    pub fn synchronize(&mut self, samplers: &AssetStorage<Sampler<T::Primitive>>) {
+        let length = |control: &SamplerControl<T>| {
+            samplers
+                .get(&control.sampler)
+                .and_then(|sampler| sampler.input.last().copied())
+                .filter(|length| *length > 0.)
+        };
+
+        // (group, (advanced, blend weight), phase)
+        let mut leaders: Vec<(u32, (bool, f32), f32)> = Vec::new();
+        for control in &self.samplers {
+            let (group, dur) = match (control.sync_group, &control.state) {
+                (Some(group), ControlState::Running(dur)) => (group, dur.as_secs_f32()),
+                _ => continue,
+            };
+            let length = match length(control) {
+                Some(length) => length,
+                None => continue,
+            };
+            let phase = (dur / length).min(1.);
+            let priority = (dur > 0., control.blend_weight);
+            match leaders.iter_mut().find(|leader| leader.0 == group) {
+                Some(leader) if priority > leader.1 => *leader = (group, priority, phase),
+                Some(_) => {}
+                None => leaders.push((group, priority, phase)),
+            }
+        }
+
+        for control in &mut self.samplers {
+            if !control.state.is_running() {
+                continue;
+            }
+            let phase = match control
+                .sync_group
+                .and_then(|group| leaders.iter().find(|leader| leader.0 == group))
+            {
+                Some(leader) => leader.2,
+                None => continue,
+            };
+            if let Some(length) = length(control) {
+                control.state = ControlState::Running(Duration::from_secs_f32(phase * length));
+            }
+        }
+    }
+
     /// Forcibly set the input value (point of interpolation)
     pub fn set_input(&mut self, control_id: u64, input: f32)
     where
@@ -626,6 +690,8 @@ where
     pub command: AnimationCommand<T>,
     /// Control the rate of animation, default is 1.0
     pub rate_multiplier: f32,
+    /// Sync group the samplers of the animation play in, `None` by default
+    pub sync_group: Option<u32>,
     m: marker::PhantomData<T>,
 }
 
@@ -649,6 +715,7 @@ where
             state,
             command,
             rate_multiplier,
+            sync_group: None,
             m: marker::PhantomData,
         }
     }
@@ -781,6 +848,23 @@ where
         self
     }
 
+    /// Set the sync group of the animation, keeping its normalized phase aligned with the other
+    /// animations of the group, e.g. a walk and a run cycle blended on the same entity.
+    pub fn set_sync_group(&mut self, id: &I, sync_group: Option<u32>) -> &mut Self {
+        if let Some(&mut (_, ref mut control)) = self.animations.iter_mut().find(|a| a.0 == *id) {
+            control.sync_group = sync_group;
+        }
+        if let Some(ref mut control) = self
+            .deferred_animations
+            .iter_mut()
+            .find(|a| a.animation_id == *id)
+        {
+            control.control.sync_group = sync_group;
+        }
+
+        self
+    }
+
     /// Step animation
     pub fn step(&mut self, id: &I, direction: StepDirection) -> &mut Self {
         self.set_command(id, AnimationCommand::Step(direction))
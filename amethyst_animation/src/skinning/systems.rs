@@ -18,13 +18,17 @@ use super::resources::{Joint, Skin};
 /// System for performing vertex skinning.
 ///
 /// Needs to run after global transforms have been updated for the current frame.
+///
+/// Only skins with a moved joint, and meshes of those skins or that moved themselves, are
+/// recomputed. `JointTransforms` of other meshes are left untouched.
 #[derive(Debug, Default)]
 pub struct VertexSkinningSystem;
 
 impl System for VertexSkinningSystem {
     fn build(self) -> Box<dyn ParallelRunnable> {
-        let mut updated = HashSet::new();
         let mut updated_skins = HashSet::new();
+        let mut updated_meshes = HashSet::new();
+        let mut palette = Vec::new();
 
         Box::new(
             SystemBuilder::new("VertexSkinningSystem")
@@ -33,97 +37,108 @@ impl System for VertexSkinningSystem {
                 .write_component::<Skin>()
                 .write_component::<JointTransforms>()
                 .with_query(
-                    <(Entity, Read<Transform>, Read<Joint>)>::query()
+                    <(Read<Transform>, Read<Joint>)>::query().filter(maybe_changed::<Transform>()),
+                )
+                .with_query(
+                    <(Entity, Read<Transform>, Read<JointTransforms>)>::query()
                         .filter(maybe_changed::<Transform>()),
                 )
-                .build(move |_, world, _, global_transforms| {
+                .build(move |_, world, _, (global_transforms, mesh_transforms)| {
                     #[cfg(feature = "profiler")]
                     profile_scope!("vertex_skinning_system");
 
-                    updated.clear();
                     updated_skins.clear();
+                    updated_meshes.clear();
 
-                    global_transforms.for_each(world, |(entity, _, joint)| {
-                        updated.insert(*entity);
-                        for skin in &joint.skins {
-                            updated_skins.insert(*skin);
-                        }
+                    global_transforms.for_each(world, |(_, joint)| {
+                        updated_skins.extend(joint.skins.iter().copied());
+                    });
+                    mesh_transforms.for_each(world, |(entity, _, _)| {
+                        updated_meshes.insert(*entity);
                     });
 
-                    let mut q = <(Entity, &Transform, &mut JointTransforms)>::query();
-                    let (mut left, mut right) = world.split_for_query(&q);
+                    for skin_entity in &updated_skins {
+                        let skin = match world
+                            .entry_ref(*skin_entity)
+                            .ok()
+                            .and_then(|entry| entry.into_component::<Skin>().ok())
+                        {
+                            Some(skin) => skin,
+                            None => continue,
+                        };
 
-                    for entity in &updated_skins {
-                        if let Ok(mut entry) = right.entry_mut(*entity) {
-                            if let Ok(skin) = entry.get_component_mut::<Skin>() {
-                                // Compute the joint global_transforms
-                                skin.joint_matrices.clear();
-                                let bind_shape = skin.bind_shape_matrix;
-                                skin.joint_matrices.extend(
-                                    skin.joints
-                                        .iter()
-                                        .zip(skin.inverse_bind_matrices.iter())
-                                        .filter_map(|(joint_entity, inverse_bind_matrix)| {
-                                            if let Ok(transform) =
-                                                global_transforms.get(&left, *joint_entity)
-                                            {
-                                                Some((transform, inverse_bind_matrix))
-                                            } else {
-                                                error!("Missing `Transform` Component for join entity {:?}",joint_entity );
-                                                None
-                                            }
-                                        })
-                                        .map(|(global, inverse_bind_matrix)| {
-                                            global.1.global_matrix()
+                        // Compute the joint global_transforms
+                        let bind_shape = skin.bind_shape_matrix;
+                        palette.clear();
+                        palette.extend(
+                            skin.joints
+                                .iter()
+                                .zip(skin.inverse_bind_matrices.iter())
+                                .filter_map(|(joint_entity, inverse_bind_matrix)| {
+                                    if let Some(transform) = world
+                                        .entry_ref(*joint_entity)
+                                        .ok()
+                                        .and_then(|entry| entry.into_component::<Transform>().ok())
+                                    {
+                                        Some(
+                                            transform.global_matrix()
                                                 * inverse_bind_matrix
-                                                * bind_shape
-                                        }),
-                                );
-
-                                // update the joint matrices in all referenced mesh entities
-                                for (entity, mesh_global, matrix) in q.iter_mut(&mut left) {
-                                    if skin.meshes.contains(entity) {
-                                        if let Some(global_inverse) =
-                                            mesh_global.global_matrix().try_inverse()
-                                        {
-                                            matrix.matrices.clear();
-                                            matrix.matrices.extend(skin.joint_matrices.iter().map(
-                                                |joint_matrix| {
-                                                    convert::<_, Matrix4<f32>>(
-                                                        global_inverse * joint_matrix,
-                                                    )
-                                                },
-                                            ));
-                                        }
+                                                * bind_shape,
+                                        )
+                                    } else {
+                                        error!(
+                                            "Missing `Transform` Component for join entity {:?}",
+                                            joint_entity
+                                        );
+                                        None
                                     }
-                                }
+                                }),
+                        );
+                        // the joint matrices of all referenced mesh entities need an update
+                        updated_meshes.extend(skin.meshes.iter().copied());
+
+                        if let Ok(mut entry) = world.entry_mut(*skin_entity) {
+                            if let Ok(skin) = entry.get_component_mut::<Skin>() {
+                                std::mem::swap(&mut skin.joint_matrices, &mut palette);
                             }
                         }
                     }
 
-                    let mut q = <(Entity, &Transform, &mut JointTransforms)>::query();
-                    let (mut left, right) = world.split_for_query(&q);
+                    for mesh_entity in &updated_meshes {
+                        let (global_inverse, skin_entity) =
+                            match world.entry_ref(*mesh_entity).ok().and_then(|entry| {
+                                let global = entry.get_component::<Transform>().ok()?;
+                                let joints = entry.get_component::<JointTransforms>().ok()?;
+                                Some((global.global_matrix().try_inverse(), joints.skin))
+                            }) {
+                                Some((Some(global_inverse), skin_entity)) => {
+                                    (global_inverse, skin_entity)
+                                }
+                                _ => continue,
+                            };
 
-                    for (entity, mesh_global, joint_transform) in q.iter_mut(&mut left) {
-                        if updated.contains(entity) {
-                            if let Some(global_inverse) = mesh_global.global_matrix().try_inverse()
+                        palette.clear();
+                        if let Some(skin) = world
+                            .entry_ref(skin_entity)
+                            .ok()
+                            .and_then(|entry| entry.into_component::<Skin>().ok())
+                        {
+                            palette.extend(skin.joint_matrices.iter().map(|joint_matrix| {
+                                convert::<_, Matrix4<f32>>(global_inverse * joint_matrix)
+                            }));
+                        } else {
+                            error!(
+                                "Missing `Skin` Component for join transform entity {:?}",
+                                skin_entity
+                            );
+                            continue;
+                        }
+
+                        if let Ok(mut entry) = world.entry_mut(*mesh_entity) {
+                            if let Ok(joint_transforms) =
+                                entry.get_component_mut::<JointTransforms>()
                             {
-                                if let Ok(skin) = <&Skin>::query().get(&right, joint_transform.skin)
-                                {
-                                    joint_transform.matrices.clear();
-                                    joint_transform.matrices.extend(
-                                        skin.joint_matrices.iter().map(|joint_matrix| {
-                                            convert::<_, Matrix4<f32>>(
-                                                global_inverse * joint_matrix,
-                                            )
-                                        }),
-                                    );
-                                } else {
-                                    error!(
-                                        "Missing `Skin` Component for join transform entity {:?}",
-                                        joint_transform.skin
-                                    );
-                                }
+                                std::mem::swap(&mut joint_transforms.matrices, &mut palette);
                             }
                         }
                     }
@@ -377,6 +377,7 @@ where
             } else {
                 debug!("Animation Playing: {:?}", control.id);
                 update_animation_rate(control.id, hierarchy, world, control.rate_multiplier);
+                update_animation_sync_group(control.id, hierarchy, world, control.sync_group);
             }
             None
         }
@@ -461,6 +462,7 @@ where
                     after: component.current_sample(channel),
                     rate_multiplier: control.rate_multiplier,
                     blend_weight: 1.0,
+                    sync_group: control.sync_group,
                 };
                 if let Ok(set) = entry.get_component_mut::<SamplerControlSet<T>>() {
                     debug!("Adding SamplerControl to existing SamplerControlSet");
@@ -592,6 +594,23 @@ fn update_animation_rate<T>(
     }
 }
 
+fn update_animation_sync_group<T>(
+    control_id: u64,
+    hierarchy: &AnimationHierarchy<T>,
+    world: &mut SubWorld<'_>,
+    sync_group: Option<u32>,
+) where
+    T: AnimationSampling,
+{
+    for node_entity in hierarchy.nodes.values() {
+        if let Ok(mut entry) = world.entry_mut(*node_entity) {
+            if let Ok(ref mut s) = entry.get_component_mut::<SamplerControlSet<T>>() {
+                s.set_sync_group(control_id, sync_group);
+            }
+        }
+    }
+}
+
 /// Check if all nodes in an `AnimationHierarchy` are ready for termination, if so remove all
 /// `SamplerControlSet`s for the hierarchy, if not request termination on all sampler controls
 fn check_and_terminate_animation<T>(
@@ -3,8 +3,7 @@ use std::{collections::HashSet, time::Duration};
 use amethyst_assets::AssetStorage;
 use amethyst_core::{
     ecs::{system, CommandBuffer},
-    legion,
-    Time,
+    legion, Time,
 };
 use log::debug;
 use minterpolate::InterpolationPrimitive;
@@ -46,7 +45,14 @@ pub(crate) fn sampler_interpolation<T: AnimationSampling + std::fmt::Debug>(
 
     for control in &mut control_set.samplers {
         if let Some(sampler) = samplers.get(&control.sampler) {
-            process_sampler(control, sampler, time, inner);
+            update_sampler(control, sampler, time);
+        }
+    }
+    // Realign sync groups after progression, before any of their samplers is sampled
+    control_set.synchronize(samplers);
+    for control in &control_set.samplers {
+        if let Some(sampler) = samplers.get(&control.sampler) {
+            process_sampler(control, sampler, inner);
         }
     }
     if !inner.is_empty() {
@@ -75,24 +81,17 @@ pub(crate) fn sampler_interpolation<T: AnimationSampling + std::fmt::Debug>(
     }
 }
 
-/// Process a single `SamplerControl` object.
+/// Progress the state of a single `SamplerControl` object.
 ///
 /// ## Parameters:
 ///
 /// - `control`: sampler control object
 /// - `sampler`: the sampler reference from the control object
-/// - `component`: the component to update
 /// - `now`: synchronized `Instant` for the current frame
-fn process_sampler<T>(
-    control: &mut SamplerControl<T>,
-    sampler: &Sampler<T::Primitive>,
-    time: &Time,
-    output: &mut Vec<(f32, T::Channel, T::Primitive)>,
-) where
+fn update_sampler<T>(control: &mut SamplerControl<T>, sampler: &Sampler<T::Primitive>, time: &Time)
+where
     T: AnimationSampling,
 {
-    use crate::resources::ControlState::{Done, Paused, Running};
-
     let (new_state, new_end) = update_duration_and_check(control, sampler, time);
 
     // If a new end condition has been computed, update in control state
@@ -100,8 +99,28 @@ fn process_sampler<T>(
         control.end = end;
     }
 
+    // Update state for next iteration
+    control.state = new_state;
+}
+
+/// Sample a single `SamplerControl` object at its current state.
+///
+/// ## Parameters:
+///
+/// - `control`: sampler control object
+/// - `sampler`: the sampler reference from the control object
+/// - `output`: the samples to blend into the component
+fn process_sampler<T>(
+    control: &SamplerControl<T>,
+    sampler: &Sampler<T::Primitive>,
+    output: &mut Vec<(f32, T::Channel, T::Primitive)>,
+) where
+    T: AnimationSampling,
+{
+    use crate::resources::ControlState::{Done, Paused, Running};
+
     // Do sampling
-    match new_state {
+    match control.state {
         Running(duration) | Paused(duration) => {
             output.push((
                 control.blend_weight,
@@ -139,9 +158,6 @@ fn process_sampler<T>(
         }
         _ => {}
     }
-
-    // Update state for next iteration
-    control.state = new_state;
 }
 
 /// Update durations, check if the sampler is finished, start new samplers, and check for aborted
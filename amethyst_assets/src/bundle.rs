@@ -3,7 +3,7 @@ use std::default::Default;
 use amethyst_core::ecs::{DispatcherBuilder, Resources, SystemBundle, World};
 use amethyst_error::Error;
 
-use crate::{prefab::ComponentRegistryBuilder, DefaultLoader, Loader};
+use crate::{prefab::ComponentRegistryBuilder, DefaultLoader, Loader, LoaderSource};
 
 fn asset_loading_tick(_: &mut World, resources: &mut Resources) {
     let mut loader = resources
@@ -16,6 +16,9 @@ fn asset_loading_tick(_: &mut World, resources: &mut Resources) {
 }
 
 /// Bundle that initializes Loader as well as related processing systems and resources
+///
+/// The assets are read from the `LoaderSource` resource when it's inserted, or from the asset
+/// daemon at the default address otherwise.
 pub struct LoaderBundle;
 
 impl SystemBundle for LoaderBundle {
@@ -29,7 +32,11 @@ impl SystemBundle for LoaderBundle {
             .auto_register_components()
             .build();
         resources.insert(component_registry);
-        let mut loader = DefaultLoader::default();
+        let source = resources
+            .get::<LoaderSource>()
+            .map(|source| source.clone())
+            .unwrap_or_default();
+        let mut loader = DefaultLoader::new(&source)?;
         loader.init_world(resources);
         loader.init_dispatcher(builder);
         resources.insert(loader);
@@ -103,6 +103,26 @@ impl AssetDaemon {
             state: AssetDaemonState::Initialized(InitializedDaemon { opt }),
         }
     }
+
+    /// Sets the address the daemon listens at, which must match the `LoaderSource` of the
+    /// `DefaultLoader`.
+    #[must_use]
+    pub fn with_address(mut self, address: SocketAddr) -> Self {
+        if let AssetDaemonState::Initialized(daemon) = &mut self.state {
+            daemon.opt.address = address;
+        }
+        self
+    }
+
+    /// Sets the directory of the asset metadata database.
+    #[must_use]
+    pub fn with_db_dir(mut self, db_dir: PathBuf) -> Self {
+        if let AssetDaemonState::Initialized(daemon) = &mut self.state {
+            daemon.opt.db_dir = db_dir;
+        }
+        self
+    }
+
     /// Starts the asset daemon on a new thread.
     pub fn start_on_new_thread(&mut self) {
         if let AssetDaemonState::Initialized(daemon) = &self.state {
@@ -0,0 +1,251 @@
+//! Diagnostics of the assets kept in memory.
+
+use std::time::{Duration, Instant};
+
+use amethyst_core::ecs::{DispatcherBuilder, Resources, SystemBundle, World};
+use amethyst_error::Error;
+use fnv::FnvHashMap;
+
+use crate::{DefaultLoader, LoadHandle};
+
+/// Loaded assets of an asset type, as reported by the `AssetDiagnostics`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetTypeDiagnostics {
+    /// Rust type name of the asset.
+    pub name: &'static str,
+    /// Number of assets in the `AssetStorage`.
+    pub loaded: usize,
+    /// Number of loaded assets without any strong handle.
+    pub unreferenced: usize,
+    /// Memory used by the loaded assets, counting the inline size of the asset type only, so
+    /// not the heap allocations of the assets nor the GPU memory of textures.
+    pub bytes: usize,
+}
+
+/// A loaded asset without any strong handle, which stays in memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnreferencedAsset {
+    /// Rust type name of the asset.
+    pub type_name: &'static str,
+    /// Load handle of the asset.
+    pub handle: LoadHandle,
+    /// Path of the asset source, for assets loaded by the asset daemon.
+    pub path: Option<String>,
+    /// Time since the last strong handle was dropped, or since the asset was first reported.
+    pub unreferenced_for: Duration,
+}
+
+/// An asset in a storage, with the number of strong handles to it if it's known.
+#[derive(Debug)]
+pub(crate) struct AssetSample {
+    pub handle: LoadHandle,
+    pub refs: Option<u32>,
+    pub path: Option<String>,
+}
+
+/// The assets in the storage of an asset type.
+#[derive(Debug)]
+pub(crate) struct AssetTypeSample {
+    pub name: &'static str,
+    pub size: usize,
+    pub assets: Vec<AssetSample>,
+}
+
+/// Resource reporting the loaded assets of each asset type, and the assets staying in memory
+/// while no strong handle refers to them.
+///
+/// Assets are freed once their last strong `Handle` is dropped, so an asset type whose count only
+/// grows usually means handles are cloned into resources or components and never dropped. Assets
+/// loaded with `Loader::load_from_data` are never freed, and are reported as unreferenced once
+/// their handles are gone.
+///
+/// It's updated by the `AssetDiagnosticsBundle`.
+#[derive(Debug, Default)]
+pub struct AssetDiagnostics {
+    types: Vec<AssetTypeDiagnostics>,
+    unreferenced: Vec<UnreferencedAsset>,
+    unreferenced_since: FnvHashMap<LoadHandle, Instant>,
+    log_interval: Option<Duration>,
+    last_log: Option<Instant>,
+}
+
+impl AssetDiagnostics {
+    /// Creates diagnostics which aren't logged.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs a report of the diagnostics every `interval`.
+    #[must_use]
+    pub fn with_log_interval(mut self, interval: Duration) -> Self {
+        self.log_interval = Some(interval);
+        self
+    }
+
+    /// Returns the loaded assets of each asset type, by decreasing memory use.
+    #[must_use]
+    pub fn types(&self) -> &[AssetTypeDiagnostics] {
+        &self.types
+    }
+
+    /// Returns the loaded assets without any strong handle, the longest unreferenced first.
+    #[must_use]
+    pub fn unreferenced(&self) -> &[UnreferencedAsset] {
+        &self.unreferenced
+    }
+
+    /// Returns at most `count` of the longest unreferenced assets.
+    #[must_use]
+    pub fn oldest_unreferenced(&self, count: usize) -> &[UnreferencedAsset] {
+        &self.unreferenced[..count.min(self.unreferenced.len())]
+    }
+
+    pub(crate) fn update(&mut self, samples: Vec<AssetTypeSample>, now: Instant) {
+        let mut since = FnvHashMap::default();
+        self.types.clear();
+        self.unreferenced.clear();
+
+        for sample in samples {
+            let mut unreferenced = 0;
+            for asset in &sample.assets {
+                if asset.refs != Some(0) {
+                    continue;
+                }
+                unreferenced += 1;
+                let first = self
+                    .unreferenced_since
+                    .get(&asset.handle)
+                    .copied()
+                    .unwrap_or(now);
+                since.insert(asset.handle, first);
+                self.unreferenced.push(UnreferencedAsset {
+                    type_name: sample.name,
+                    handle: asset.handle,
+                    path: asset.path.clone(),
+                    unreferenced_for: now.saturating_duration_since(first),
+                });
+            }
+            self.types.push(AssetTypeDiagnostics {
+                name: sample.name,
+                loaded: sample.assets.len(),
+                unreferenced,
+                bytes: sample.assets.len() * sample.size,
+            });
+        }
+
+        self.unreferenced_since = since;
+        self.types.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(b.name)));
+        self.unreferenced
+            .sort_by(|a, b| b.unreferenced_for.cmp(&a.unreferenced_for));
+    }
+
+    fn log(&mut self, now: Instant) {
+        let interval = match self.log_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        if let Some(last_log) = self.last_log {
+            if now.saturating_duration_since(last_log) < interval {
+                return;
+            }
+        }
+        self.last_log = Some(now);
+
+        for stats in &self.types {
+            log::info!(
+                "{}: {} loaded, {} unreferenced, {} bytes",
+                stats.name,
+                stats.loaded,
+                stats.unreferenced,
+                stats.bytes
+            );
+        }
+        for asset in self.oldest_unreferenced(10) {
+            log::warn!(
+                "{} {:?} ({}) unreferenced for {:?}",
+                asset.type_name,
+                asset.handle,
+                asset.path.as_deref().unwrap_or("from data"),
+                asset.unreferenced_for
+            );
+        }
+    }
+}
+
+fn asset_diagnostics_tick(_: &mut World, resources: &mut Resources) {
+    let samples = resources
+        .get::<DefaultLoader>()
+        .expect("Could not get DefaultLoader")
+        .sample_assets(resources);
+    let now = Instant::now();
+    let mut diagnostics = resources
+        .get_mut::<AssetDiagnostics>()
+        .expect("Could not get_mut AssetDiagnostics");
+    diagnostics.update(samples, now);
+    diagnostics.log(now);
+}
+
+/// Bundle updating the `AssetDiagnostics` resource every frame, added after the `LoaderBundle`.
+///
+/// It keeps an `AssetDiagnostics` resource inserted beforehand, to configure its logging.
+#[derive(Debug, Default)]
+pub struct AssetDiagnosticsBundle;
+
+impl SystemBundle for AssetDiagnosticsBundle {
+    fn load(
+        &mut self,
+        _: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_insert_with(AssetDiagnostics::default);
+        builder.add_thread_local_fn(asset_diagnostics_tick);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(handles: &[(u64, Option<u32>)]) -> AssetTypeSample {
+        AssetTypeSample {
+            name: "Texture",
+            size: 8,
+            assets: handles
+                .iter()
+                .map(|(handle, refs)| AssetSample {
+                    handle: LoadHandle(*handle),
+                    refs: *refs,
+                    path: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn unreferenced_assets_are_aged() {
+        let mut diagnostics = AssetDiagnostics::new();
+        let start = Instant::now();
+        diagnostics.update(vec![sample(&[(1, Some(0)), (2, Some(1))])], start);
+        diagnostics.update(
+            vec![sample(&[(1, Some(0)), (2, Some(0)), (3, None)])],
+            start + Duration::from_secs(5),
+        );
+
+        assert_eq!(
+            vec![AssetTypeDiagnostics {
+                name: "Texture",
+                loaded: 3,
+                unreferenced: 2,
+                bytes: 24,
+            }],
+            diagnostics.types()
+        );
+        let oldest = diagnostics.oldest_unreferenced(1);
+        assert_eq!(LoadHandle(1), oldest[0].handle);
+        assert_eq!(Duration::from_secs(5), oldest[0].unreferenced_for);
+        assert_eq!(Duration::from_secs(0), diagnostics.unreferenced()[1].unreferenced_for);
+    }
+}
@@ -22,6 +22,7 @@ mod bundle;
 mod cache;
 #[cfg(feature = "asset-daemon")]
 mod daemon;
+mod diagnostics;
 /// asset loading specific errors
 pub mod error;
 #[cfg(feature = "json")]
@@ -29,6 +30,7 @@ mod json;
 mod loader;
 /// helpers for registering prefab components
 pub mod prefab;
+mod preprocessor;
 mod processor;
 mod progress;
 mod simple_importer;
@@ -47,7 +49,7 @@ pub use distill::{
 pub use type_uuid::TypeUuid;
 // used in macros. Private API otherwise.
 #[doc(hidden)]
-pub use {erased_serde, inventory, lazy_static};
+pub use {amethyst_error, erased_serde, inventory, lazy_static};
 
 #[cfg(feature = "asset-daemon")]
 /// internal `AssetDaemon` control
@@ -58,9 +60,15 @@ pub use crate::{
     asset::{Asset, Format, FormatValue, ProcessableAsset, SerializableFormat},
     bundle::LoaderBundle,
     cache::Cache,
-    loader::{create_asset_type, AssetUuid, DefaultLoader, LoadStatus, Loader},
+    diagnostics::{
+        AssetDiagnostics, AssetDiagnosticsBundle, AssetTypeDiagnostics, UnreferencedAsset,
+    },
+    loader::{create_asset_type, AssetUuid, DefaultLoader, LoadStatus, Loader, LoaderSource},
+    preprocessor::{preprocess_with, AssetPreprocessor, AssetPreprocessorEntry},
     processor::{AssetProcessorSystem, ProcessingQueue, ProcessingState},
-    progress::{Completion, Progress, ProgressCounter, Tracker},
+    progress::{
+        AssetProgress, AssetProgressState, Completion, Progress, ProgressCounter, Tracker,
+    },
     simple_importer::{SimpleImporter, SourceFileImporter},
     source::{Directory, Source},
     storage::AssetStorage,
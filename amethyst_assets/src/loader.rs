@@ -1,10 +1,14 @@
-use std::{cell::RefCell, collections::HashMap, error::Error, fs::File, path::PathBuf, sync::Arc};
+use std::{
+    cell::RefCell, collections::HashMap, error::Error, fs::File, net::SocketAddr, path::PathBuf,
+    sync::Arc,
+};
 
 use amethyst_core::{
     dispatcher::System,
     ecs::{DispatcherBuilder, Resources},
 };
-use amethyst_error::Error as AmethystError;
+use amethyst_error::{format_err, Error as AmethystError, ResultExt};
+use fnv::FnvHashMap;
 use distill::{
     importer::AssetMetadata, loader as distill_loader, loader::storage::IndirectionResolver,
 };
@@ -21,10 +25,13 @@ use distill_loader::{
 };
 pub use distill_loader::{storage::LoadStatus, AssetUuid};
 use log::debug;
+use parking_lot::Mutex;
 use serde::de::Deserialize;
 
 use crate::{
-    loader, processor::ProcessingQueue, progress::Progress, storage::AssetStorage, Asset, TypeUuid,
+    diagnostics::{AssetSample, AssetTypeSample},
+    loader,
+    processor::ProcessingQueue, progress::Progress, storage::AssetStorage, Asset, TypeUuid,
 };
 
 /// Manages asset loading and storage for an application.
@@ -146,46 +153,109 @@ pub struct DefaultLoader {
     ref_sender: Sender<RefOp>,
     ref_receiver: Receiver<RefOp>,
     handle_allocator: Arc<AtomicHandleAllocator>,
+    /// Strong handle counts of the assets loaded from data, which `distill` doesn't track.
+    data_refs: Mutex<FnvHashMap<LoadHandle, u32>>,
     pub(crate) indirection_table: IndirectionTable,
 }
 
+/// Where the `DefaultLoader` reads the imported assets from.
+///
+/// Inserted as a resource before the `LoaderBundle` is loaded, it replaces the default daemon
+/// address.
+///
+/// The loader always talks to the daemon through its RPC server, even when the daemon runs
+/// in-process: distill doesn't expose the asset hub of the daemon to a loader IO. Asset packs
+/// are written by the distill CLI, as distill has no library API producing them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoaderSource {
+    /// Connects to an asset daemon listening at the address, running in-process or not.
+    Daemon(SocketAddr),
+    /// Reads the read-only asset pack at the path, precomputed from the asset database with the
+    /// distill `pack` command, so no daemon runs and no asset is imported at startup.
+    Pack(PathBuf),
+}
+
+impl Default for LoaderSource {
+    fn default() -> Self {
+        LoaderSource::Daemon(SocketAddr::from(([127, 0, 0, 1], 9999)))
+    }
+}
+
 impl Default for DefaultLoader {
     fn default() -> Self {
-        Self::new(true, None, None)
+        Self::new(&LoaderSource::default()).expect("Connecting to the asset daemon can't fail")
     }
 }
 
 impl DefaultLoader {
-    fn new(
-        loader_io_use_rpc: bool,
-        connect_string: Option<String>,
-        packfile_path: Option<PathBuf>,
-    ) -> Self {
+    /// Creates a loader reading the assets from `source`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the asset pack can't be opened or read.
+    pub fn new(source: &LoaderSource) -> Result<Self, AmethystError> {
         let (tx, rx) = unbounded();
         let handle_allocator = Arc::new(AtomicHandleAllocator::default());
-        let loader_io: Box<dyn LoaderIO> = if loader_io_use_rpc {
-            log::info!("Using RpcIO");
-            let rpc_io = connect_string
-                .and_then(|cs| RpcIO::new(cs).ok())
-                .unwrap_or_default();
-            Box::new(rpc_io)
-        } else {
-            log::info!("Using PackfileIO");
-            let packfile_io = packfile_path
-                .map(|pfp| File::open(pfp).expect("Could not open packfile"))
-                .and_then(|pf| PackfileReader::new(pf).ok())
-                .expect("packfile not found");
-            Box::new(packfile_io)
+        let loader_io: Box<dyn LoaderIO> = match source {
+            LoaderSource::Daemon(address) => {
+                log::info!("Using RpcIO");
+                let rpc_io = RpcIO::new(address.to_string()).unwrap_or_default();
+                Box::new(rpc_io)
+            }
+            LoaderSource::Pack(path) => {
+                log::info!("Using PackfileIO");
+                let packfile = File::open(path).with_context(|_| {
+                    format_err!("Failed to open asset pack {}", path.display())
+                })?;
+                let packfile_io = PackfileReader::new(packfile).map_err(|e| {
+                    format_err!("Failed to read asset pack {}: {}", path.display(), e)
+                })?;
+                Box::new(packfile_io)
+            }
         };
         let loader = DistillLoader::new_with_handle_allocator(loader_io, handle_allocator.clone());
-        Self {
+        Ok(Self {
             indirection_table: loader.indirection_table(),
             loader,
             storage_map: loader::AssetStorageMap::default(),
             ref_sender: tx,
             ref_receiver: rx,
             handle_allocator,
+            data_refs: Mutex::new(FnvHashMap::default()),
+        })
+    }
+
+    /// Returns the number of strong handles to a loaded asset, or `None` if it's unknown.
+    fn ref_count(&self, handle: LoadHandle) -> Option<(u32, Option<String>)> {
+        if let Some(refs) = self.data_refs.lock().get(&handle) {
+            return Some((*refs, None));
         }
+        self.loader
+            .get_load_info(handle)
+            .map(|info| (info.refs, info.path))
+    }
+
+    /// Samples the assets in the storage of each registered asset type.
+    pub(crate) fn sample_assets(&self, resources: &Resources) -> Vec<AssetTypeSample> {
+        self.storage_map
+            .storages_by_asset_uuid
+            .values()
+            .map(|asset_type| AssetTypeSample {
+                name: asset_type.asset_name,
+                size: asset_type.asset_size,
+                assets: (asset_type.load_handles)(resources)
+                    .into_iter()
+                    .map(|handle| {
+                        let info = self.ref_count(handle);
+                        AssetSample {
+                            handle,
+                            refs: info.as_ref().map(|(refs, _)| *refs),
+                            path: info.and_then(|(_, path)| path),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
     }
 }
 
@@ -228,6 +298,7 @@ impl Loader for DefaultLoader {
         let tracker = progress.create_tracker();
         let tracker = Box::new(tracker);
         let handle = self.handle_allocator.alloc();
+        self.data_refs.lock().insert(handle, 1);
         let version = 0;
         processing_queue.enqueue_from_data(handle, data, tracker, version);
         Handle::<A>::new(self.ref_sender.clone(), handle)
@@ -250,9 +321,15 @@ impl Loader for DefaultLoader {
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => panic!("RefOp receiver disconnected"),
                 Ok(RefOp::Decrease(handle)) => {
+                    if let Some(refs) = self.data_refs.get_mut().get_mut(&handle) {
+                        *refs = refs.saturating_sub(1);
+                    }
                     self.loader.remove_ref(handle);
                 }
                 Ok(RefOp::Increase(handle)) => {
+                    if let Some(refs) = self.data_refs.get_mut().get_mut(&handle) {
+                        *refs += 1;
+                    }
                     self.loader.add_ref_handle(handle);
                 }
                 Ok(RefOp::IncreaseUuid(uuid)) => {
@@ -494,6 +571,12 @@ pub struct AssetType {
     pub register_system: fn(&mut DispatcherBuilder),
     /// Function that runs another function, passing in the `AssetTypeStorage`.
     pub with_storage: fn(&Resources, &mut dyn FnMut(&mut dyn AssetTypeStorage)),
+    /// Rust type name of the asset.
+    pub asset_name: &'static str,
+    /// Inline size of the asset type.
+    pub asset_size: usize,
+    /// Function returning the load handles of the assets in the `AssetStorage`.
+    pub load_handles: fn(&Resources) -> Vec<LoadHandle>,
 }
 
 impl std::fmt::Debug for AssetType {
@@ -542,6 +625,13 @@ where
                     .expect("Could not get_mut AssetStorage"),
             ));
         },
+        asset_name: std::any::type_name::<Asset>(),
+        asset_size: std::mem::size_of::<Asset>(),
+        load_handles: |res| {
+            res.get::<AssetStorage<Asset>>()
+                .map(|storage| storage.load_handles().collect())
+                .unwrap_or_default()
+        },
     }
 }
 
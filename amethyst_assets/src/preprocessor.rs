@@ -0,0 +1,205 @@
+use std::{
+    any::{Any, TypeId},
+    fmt,
+};
+
+use amethyst_error::{format_err, Error, ResultExt};
+
+/// A build step transforming an imported asset before it's stored in the asset database, such as
+/// generating distance fields from fonts or downscaling textures.
+///
+/// Preprocessors run in the asset daemon right after the `Format` imported the data, in the order
+/// of their names. Their names and versions are part of the importer version, so the asset
+/// database re-imports the assets whenever a preprocessor is added, removed or its `VERSION`
+/// bumped, and serves cached artifacts otherwise.
+///
+/// Register a preprocessor with [`register_preprocessor!`].
+pub trait AssetPreprocessor<A>: Default {
+    /// Unique name of the preprocessor.
+    const NAME: &'static str;
+    /// Version of the preprocessor, to bump whenever its output changes.
+    const VERSION: u32 = 1;
+
+    /// Transforms the imported data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the data can't be processed, failing the import.
+    fn process(&self, data: &mut A) -> Result<(), Error>;
+}
+
+/// Preprocessor of an asset data type registered with [`register_preprocessor!`].
+pub struct AssetPreprocessorEntry {
+    /// Type of the asset data the preprocessor transforms.
+    pub data_type: fn() -> TypeId,
+    /// Name of the preprocessor.
+    pub name: &'static str,
+    /// Version of the preprocessor.
+    pub version: u32,
+    /// Function running the preprocessor on the data.
+    pub process: fn(&mut dyn Any) -> Result<(), Error>,
+}
+inventory::collect!(AssetPreprocessorEntry);
+
+impl fmt::Debug for AssetPreprocessorEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssetPreprocessorEntry")
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+/// Runs the preprocessor `P` on data of type `A`. Private API used by the
+/// `register_preprocessor!` macro.
+#[doc(hidden)]
+pub fn preprocess_with<A: 'static, P: AssetPreprocessor<A>>(
+    data: &mut dyn Any,
+) -> Result<(), Error> {
+    let data = data
+        .downcast_mut::<A>()
+        .ok_or_else(|| format_err!("Preprocessor {} got data of the wrong type", P::NAME))?;
+    P::default().process(data)
+}
+
+/// Returns the preprocessors registered for the data type `A`, in the order they run.
+fn preprocessors<A: 'static>() -> Vec<&'static AssetPreprocessorEntry> {
+    let mut entries: Vec<_> = inventory::iter::<AssetPreprocessorEntry>
+        .into_iter()
+        .filter(|entry| (entry.data_type)() == TypeId::of::<A>())
+        .collect();
+    entries.sort_by_key(|entry| entry.name);
+    entries
+}
+
+/// Runs the preprocessors registered for the data type `A` on the imported data.
+pub(crate) fn preprocess<A: 'static>(data: &mut A) -> Result<(), Error> {
+    apply(&preprocessors::<A>(), data)
+}
+
+/// Version of the preprocessors registered for the data type `A`, combined with the importer
+/// version so the assets are re-imported when they change.
+pub(crate) fn preprocessors_version<A: 'static>() -> u32 {
+    combined_version(&preprocessors::<A>())
+}
+
+fn apply(entries: &[&AssetPreprocessorEntry], data: &mut dyn Any) -> Result<(), Error> {
+    for entry in entries {
+        (entry.process)(data)
+            .with_context(|_| format_err!("Failed to run preprocessor {}", entry.name))?;
+    }
+    Ok(())
+}
+
+fn combined_version(entries: &[&AssetPreprocessorEntry]) -> u32 {
+    entries.iter().fold(0_u32, |hash, entry| {
+        entry
+            .name
+            .bytes()
+            .fold(hash, |hash, byte| hash.wrapping_mul(31).wrapping_add(u32::from(byte)))
+            .wrapping_mul(31)
+            .wrapping_add(entry.version)
+    })
+}
+
+/// Registers an [`AssetPreprocessor`] transforming the data type of imported assets.
+///
+/// # Parameters
+///
+/// * `data`: Type of the asset data, as produced by a `Format`.
+/// * `preprocessor`: Type that implements the `AssetPreprocessor` trait for the data.
+///
+/// # Examples
+///
+/// ```
+/// use amethyst::{assets::AssetPreprocessor, error::Error};
+///
+/// pub struct TextureData(pub Vec<u8>);
+///
+/// #[derive(Default)]
+/// pub struct Downscale;
+/// impl AssetPreprocessor<TextureData> for Downscale {
+///     const NAME: &'static str = "downscale";
+///
+///     fn process(&self, data: &mut TextureData) -> Result<(), Error> {
+///         data.0 = data.0.iter().step_by(2).copied().collect();
+///         Ok(())
+///     }
+/// }
+/// amethyst_assets::register_preprocessor!(TextureData, Downscale);
+/// ```
+#[macro_export]
+macro_rules! register_preprocessor {
+    ($data:ty, $preprocessor:ty) => {
+        $crate::register_preprocessor!(amethyst_assets; $data, $preprocessor);
+    };
+    ($krate:ident; $data:ty, $preprocessor:ty) => {
+        $crate::inventory::submit!{
+            #![crate = $krate]
+            $crate::AssetPreprocessorEntry {
+                data_type: std::any::TypeId::of::<$data>,
+                name: <$preprocessor as $crate::AssetPreprocessor<$data>>::NAME,
+                version: <$preprocessor as $crate::AssetPreprocessor<$data>>::VERSION,
+                process: $crate::preprocess_with::<$data, $preprocessor>,
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Double;
+    impl AssetPreprocessor<u32> for Double {
+        const NAME: &'static str = "double";
+
+        fn process(&self, data: &mut u32) -> Result<(), Error> {
+            *data *= 2;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Increment;
+    impl AssetPreprocessor<u32> for Increment {
+        const NAME: &'static str = "increment";
+        const VERSION: u32 = 2;
+
+        fn process(&self, data: &mut u32) -> Result<(), Error> {
+            *data += 1;
+            Ok(())
+        }
+    }
+
+    fn entry<P: AssetPreprocessor<u32>>() -> AssetPreprocessorEntry {
+        AssetPreprocessorEntry {
+            data_type: TypeId::of::<u32>,
+            name: P::NAME,
+            version: P::VERSION,
+            process: preprocess_with::<u32, P>,
+        }
+    }
+
+    #[test]
+    fn preprocessors_run_in_order() {
+        let (double, increment) = (entry::<Double>(), entry::<Increment>());
+        let mut data = 3_u32;
+        apply(&[&double, &increment], &mut data).unwrap();
+        assert_eq!(7, data);
+        assert!(apply(&[&double], &mut 3_i32).is_err());
+    }
+
+    #[test]
+    fn version_tracks_preprocessors() {
+        let (double, increment) = (entry::<Double>(), entry::<Increment>());
+        let mut bumped = entry::<Increment>();
+        bumped.version = 3;
+
+        let version = combined_version(&[&double, &increment]);
+        assert_ne!(version, combined_version(&[&double]));
+        assert_ne!(version, combined_version(&[&double, &bumped]));
+        assert_eq!(version, combined_version(&[&double, &increment]));
+    }
+}
@@ -33,6 +33,16 @@ pub trait Progress {
 
     /// Creates a `Tracker`.
     fn create_tracker(self) -> Self::Tracker;
+
+    /// Creates a `Tracker` for the asset `name`, counting `weight` times as much as an asset of
+    /// weight `1.0` in the overall progress.
+    fn create_named_tracker(self, name: &str, weight: f32) -> Self::Tracker
+    where
+        Self: Sized,
+    {
+        let _ = (name, weight);
+        self.create_tracker()
+    }
 }
 
 impl Progress for () {
@@ -43,6 +53,48 @@ impl Progress for () {
     fn create_tracker(self) {}
 }
 
+/// Loading state of an asset tracked by a `ProgressCounter`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AssetProgressState {
+    /// The asset is still loading.
+    Loading,
+    /// The asset is loaded.
+    Loaded,
+    /// The asset failed to load.
+    Failed,
+}
+
+/// Progress of an asset tracked by a `ProgressCounter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetProgress {
+    /// Name of the asset, empty when the tracker was created without a name.
+    pub name: String,
+    /// Weight of the asset in the overall progress.
+    pub weight: f32,
+    /// Bytes or items of the asset done, as reported by its tracker.
+    pub done: u64,
+    /// Bytes or items of the asset in total, or `0` when unknown.
+    pub total: u64,
+    /// Loading state of the asset.
+    pub state: AssetProgressState,
+}
+
+impl AssetProgress {
+    /// Fraction of the asset done, from `0.0` to `1.0`.
+    #[must_use]
+    pub fn fraction(&self) -> f32 {
+        match self.state {
+            AssetProgressState::Loaded | AssetProgressState::Failed => 1.0,
+            AssetProgressState::Loading if self.total > 0 => {
+                #[allow(clippy::cast_precision_loss)]
+                let fraction = self.done as f32 / self.total as f32;
+                fraction.min(1.0)
+            }
+            AssetProgressState::Loading => 0.0,
+        }
+    }
+}
+
 /// A progress tracker which is passed to the `Loader`
 /// in order to check how many assets are loaded.
 #[derive(Default, Debug)]
@@ -51,6 +103,7 @@ pub struct ProgressCounter {
     num_assets: usize,
     num_failed: Arc<AtomicUsize>,
     num_loading: Arc<AtomicUsize>,
+    assets: Arc<Mutex<Vec<AssetProgress>>>,
 }
 
 impl ProgressCounter {
@@ -109,6 +162,42 @@ impl ProgressCounter {
     pub fn is_complete(&self) -> bool {
         self.complete() == Completion::Complete
     }
+
+    /// Returns the progress of each tracked asset, in the order their trackers were created.
+    #[must_use]
+    pub fn assets(&self) -> Vec<AssetProgress> {
+        self.assets.lock().clone()
+    }
+
+    /// Returns the names of the assets still loading, for display on loading screens.
+    #[must_use]
+    pub fn loading_names(&self) -> Vec<String> {
+        self.assets
+            .lock()
+            .iter()
+            .filter(|asset| asset.state == AssetProgressState::Loading && !asset.name.is_empty())
+            .map(|asset| asset.name.clone())
+            .collect()
+    }
+
+    /// Returns the overall progress from `0.0` to `1.0`, where each asset counts by its weight and
+    /// the partial progress reported by its tracker.
+    ///
+    /// Returns `1.0` when no asset is tracked.
+    #[must_use]
+    pub fn weighted_progress(&self) -> f32 {
+        let assets = self.assets.lock();
+        let total: f32 = assets.iter().map(|asset| asset.weight).sum();
+        if total > 0.0 {
+            assets
+                .iter()
+                .map(|asset| asset.weight * asset.fraction())
+                .sum::<f32>()
+                / total
+        } else {
+            1.0
+        }
+    }
 }
 
 impl<'a> Progress for &'a mut ProgressCounter {
@@ -119,15 +208,34 @@ impl<'a> Progress for &'a mut ProgressCounter {
     }
 
     fn create_tracker(self) -> Self::Tracker {
+        self.create_named_tracker("", 1.0)
+    }
+
+    fn create_named_tracker(self, name: &str, weight: f32) -> Self::Tracker {
         let errors = self.errors.clone();
         let num_failed = self.num_failed.clone();
         let num_loading = self.num_loading.clone();
         num_loading.fetch_add(1, Ordering::Relaxed);
 
+        let assets = self.assets.clone();
+        let index = {
+            let mut assets = assets.lock();
+            assets.push(AssetProgress {
+                name: name.to_string(),
+                weight,
+                done: 0,
+                total: 0,
+                state: AssetProgressState::Loading,
+            });
+            assets.len() - 1
+        };
+
         ProgressCounterTracker {
             errors,
             num_failed,
             num_loading,
+            assets,
+            index,
         }
     }
 }
@@ -138,13 +246,31 @@ pub struct ProgressCounterTracker {
     errors: Arc<Mutex<Vec<AssetErrorMeta>>>,
     num_failed: Arc<AtomicUsize>,
     num_loading: Arc<AtomicUsize>,
+    assets: Arc<Mutex<Vec<AssetProgress>>>,
+    index: usize,
+}
+
+impl ProgressCounterTracker {
+    fn set_state(&self, state: AssetProgressState) {
+        if let Some(asset) = self.assets.lock().get_mut(self.index) {
+            asset.state = state;
+        }
+    }
 }
 
 impl Tracker for ProgressCounterTracker {
     fn success(self: Box<Self>) {
+        self.set_state(AssetProgressState::Loaded);
         self.num_loading.fetch_sub(1, Ordering::Relaxed);
     }
 
+    fn report(&self, done: u64, total: u64) {
+        if let Some(asset) = self.assets.lock().get_mut(self.index) {
+            asset.done = done;
+            asset.total = total;
+        }
+    }
+
     fn fail(
         self: Box<Self>,
         handle_id: u64,
@@ -160,6 +286,7 @@ impl Tracker for ProgressCounterTracker {
             asset_name,
         });
         self.num_failed.fetch_add(1, Ordering::Relaxed);
+        self.set_state(AssetProgressState::Failed);
 
         // Failed assets are not requeue for loading, so we subtract it from the number that tracks
         // the assets that are still loading.
@@ -189,6 +316,11 @@ pub trait Tracker: Send + 'static {
         asset_name: String,
         error: Error,
     );
+    /// Reports the bytes or items of the asset `done` out of `total`, for assets loading in
+    /// several steps.
+    fn report(&self, done: u64, total: u64) {
+        let _ = (done, total);
+    }
 }
 
 impl Tracker for () {
@@ -298,4 +430,23 @@ mod tests {
         tracker_2.success();
         assert_eq!(2, progress.num_finished());
     }
+
+    #[test]
+    fn progress_counter_weighs_named_assets() {
+        let mut progress_counter = ProgressCounter::new();
+        let mut progress = &mut progress_counter;
+        progress.add_assets(2);
+        let level = Box::new(progress.create_named_tracker("level.glb", 3.0));
+        let icon = Box::new(progress.create_named_tracker("icon.png", 1.0));
+
+        assert_eq!(vec!["level.glb", "icon.png"], progress.loading_names());
+        assert!(progress.weighted_progress().abs() < f32::EPSILON);
+
+        level.report(50, 100);
+        assert!((progress.weighted_progress() - 0.375).abs() < f32::EPSILON);
+
+        icon.success();
+        assert_eq!(vec!["level.glb"], progress.loading_names());
+        assert!((progress.weighted_progress() - 0.625).abs() < f32::EPSILON);
+    }
 }
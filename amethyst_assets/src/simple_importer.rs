@@ -6,7 +6,10 @@ use distill::importer::{
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 
-use crate::{AssetUuid, Format};
+use crate::{
+    preprocessor::{preprocess, preprocessors_version},
+    AssetUuid, Format,
+};
 
 /// A simple state for Importer to retain the same UUID between imports
 /// for all single-asset source files
@@ -42,7 +45,7 @@ where
     where
         Self: Sized,
     {
-        1
+        1_u32.wrapping_add(preprocessors_version::<A>())
     }
     fn version(&self) -> u32 {
         Self::version_static()
@@ -63,9 +66,10 @@ where
         }
         let mut bytes = Vec::new();
         source.read_to_end(&mut bytes)?;
-        let import_result = options
+        let mut import_result = options
             .import_simple(bytes)
             .map_err(|e| importer::Error::Boxed(e.into_error()))?;
+        preprocess(&mut import_result).map_err(|e| importer::Error::Boxed(e.into_error()))?;
         Ok(ImporterValue {
             assets: vec![ImportedAsset {
                 id: state.id.expect("AssetUUID not generated"),
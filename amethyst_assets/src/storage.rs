@@ -99,6 +99,11 @@ impl<A> AssetStorage<A> {
         self.assets.contains_key(&load_handle)
     }
 
+    /// Returns the load handles of the loaded assets.
+    pub fn load_handles(&self) -> impl Iterator<Item = LoadHandle> + '_ {
+        self.assets.keys().copied()
+    }
+
     fn get_asset_state(&self, load_handle: LoadHandle) -> Option<&AssetState<A>> {
         let load_handle = if load_handle.is_indirect() {
             self.indirection_table.resolve(load_handle)?
@@ -1,11 +1,16 @@
 //! ECS audio bundles
 
 //use amethyst_assets::AssetProcessorSystemBundle;
-use amethyst_core::ecs::{DispatcherBuilder, Resources, SystemBundle, World};
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::EventChannel,
+    Lifecycle,
+};
 use amethyst_error::Error;
 
 use crate::{
-    output::OutputWrapper,
+    captions::{CaptionEvent, CaptionLocale, CaptionSystem},
+    output::{init_output, OutputWrapper},
     systems::{AudioSystem, SelectedListener},
 };
 
@@ -14,6 +19,9 @@ use crate::{
 /// This will add an empty `SelectedListener`, `OutputWrapper`, add the audio system and the asset processor for `Source`.
 ///
 /// `DjSystem` must be added separately if you want to use our background music system.
+///
+/// While the application is suspended, see [`Lifecycle`], the output is released, and the default
+/// output is acquired again when it resumes.
 #[derive(Default, Debug)]
 pub struct AudioBundle;
 
@@ -27,7 +35,52 @@ impl SystemBundle for AudioBundle {
         resources.get_or_default::<OutputWrapper>();
         resources.get_or_default::<SelectedListener>();
 
-        builder.add_system(AudioSystem);
+        // Whether there was an output before the application was suspended, while it is.
+        let mut released: Option<bool> = None;
+        builder
+            .add_platform_thread_local_fn(move |_world, resources| {
+                let suspended = resources
+                    .get::<Lifecycle>()
+                    .map_or(false, |lifecycle| lifecycle.is_suspended());
+                match (suspended, released) {
+                    (true, None) => {
+                        let mut wrapper = resources.get_mut::<OutputWrapper>().unwrap();
+                        released = Some(wrapper.output.is_some());
+                        wrapper.audio_sink = None;
+                        wrapper.output = None;
+                    }
+                    (false, Some(had_output)) => {
+                        released = None;
+                        if had_output {
+                            init_output(resources);
+                        }
+                    }
+                    _ => {}
+                }
+            })
+            .add_system(AudioSystem);
+        Ok(())
+    }
+}
+
+/// Caption bundle
+///
+/// This will add an empty `CaptionLocale`, an `EventChannel<CaptionEvent>` and the caption system,
+/// see the [`captions`](crate::captions) module. The `AssetStorage<Locale>` resource must be
+/// available, e.g. by adding the `LoaderBundle` first.
+#[derive(Default, Debug)]
+pub struct CaptionBundle;
+
+impl SystemBundle for CaptionBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_default::<CaptionLocale>();
+        resources.get_or_insert_with(EventChannel::<CaptionEvent>::new);
+        builder.add_system(CaptionSystem);
         Ok(())
     }
 }
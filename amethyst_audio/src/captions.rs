@@ -0,0 +1,319 @@
+//! Captions of the sounds played by an [`AudioEmitter`], for players who can't hear them.
+//!
+//! A [`Captions`] track is attached to a sound with [`AudioEmitter::play_with_captions`]. While
+//! the sound plays, the [`CaptionSystem`] sends a [`CaptionEvent::Show`] when the playback
+//! reaches the start of a [`CaptionCue`] and a [`CaptionEvent::Hide`] when it reaches its end or
+//! the sound ends. The keys of the cues are resolved through the [`Locale`] of the
+//! [`CaptionLocale`] resource, so the game can display them in the language of the player.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder, Write},
+    shrev::EventChannel,
+};
+use amethyst_locale::Locale;
+use rodio::{Sample, Source as RodioSource};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::components::AudioEmitter;
+
+/// Text shown during a time range of a sound.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CaptionCue {
+    /// Time in seconds from the start of the sound the text is shown at.
+    pub start: f32,
+    /// Time in seconds from the start of the sound the text is hidden at.
+    pub end: f32,
+    /// Key of the message in the locale, also shown as is when it can't be resolved.
+    pub key: String,
+}
+
+/// Track of captions of a sound, see the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Captions {
+    /// Cues of the track, which may overlap.
+    pub cues: Vec<CaptionCue>,
+}
+
+impl Captions {
+    /// Creates an empty caption track.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shows the message with given key from `start` to `end` seconds into the sound.
+    #[must_use]
+    pub fn with_cue(mut self, start: f32, end: f32, key: impl Into<String>) -> Self {
+        self.cues.push(CaptionCue {
+            start,
+            end,
+            key: key.into(),
+        });
+        self
+    }
+}
+
+/// Event sent by the [`CaptionSystem`] when a caption should be shown or hidden.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptionEvent {
+    /// The playback of a sound reached the start of a cue.
+    Show {
+        /// Entity of the `AudioEmitter` playing the sound.
+        emitter: Entity,
+        /// Key of the cue.
+        key: String,
+        /// Message of the key in the [`CaptionLocale`], or the key itself when it has none.
+        text: String,
+    },
+    /// The playback of a sound reached the end of a shown cue, or the sound ended.
+    Hide {
+        /// Entity of the `AudioEmitter` playing the sound.
+        emitter: Entity,
+        /// Key of the cue.
+        key: String,
+    },
+}
+
+/// Resource selecting the locale the keys of the captions are resolved through.
+///
+/// When it's `None` or the locale isn't loaded yet, the keys are sent as the text of the
+/// captions.
+#[derive(Debug, Default)]
+pub struct CaptionLocale(pub Option<Handle<Locale>>);
+
+/// Captions of a sound being played, with the number of its samples played so far.
+#[derive(Debug)]
+pub(crate) struct PlayingCaptions {
+    captions: Arc<Captions>,
+    shown: Vec<bool>,
+    samples: Arc<AtomicU64>,
+    samples_per_second: u64,
+    ended: Arc<AtomicBool>,
+}
+
+impl PlayingCaptions {
+    pub(crate) fn new(
+        captions: Arc<Captions>,
+        channels: u16,
+        sample_rate: u32,
+        samples: Arc<AtomicU64>,
+        ended: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            shown: vec![false; captions.cues.len()],
+            captions,
+            samples,
+            samples_per_second: u64::from(channels) * u64::from(sample_rate),
+            ended,
+        }
+    }
+
+    /// Playback position of the sound.
+    #[allow(clippy::cast_precision_loss)]
+    fn position(&self) -> Duration {
+        if self.samples_per_second == 0 {
+            return Duration::default();
+        }
+        let samples = self.samples.load(Ordering::Relaxed);
+        Duration::from_secs_f64(samples as f64 / self.samples_per_second as f64)
+    }
+
+    /// Calls `f` with the cues that were shown or hidden since the last update, and returns
+    /// whether the sound is still playing.
+    fn update(&mut self, mut f: impl FnMut(&CaptionCue, bool)) -> bool {
+        let ended = self.ended.load(Ordering::Relaxed);
+        let position = self.position().as_secs_f32();
+        for (cue, shown) in self.captions.cues.iter().zip(&mut self.shown) {
+            let active = !ended && cue.start <= position && position < cue.end;
+            if active != *shown {
+                *shown = active;
+                f(cue, active);
+            }
+        }
+        !ended
+    }
+}
+
+/// Wraps a source and counts the samples played from it.
+pub(crate) struct CountingSource<I> {
+    input: I,
+    samples: Arc<AtomicU64>,
+}
+
+impl<I> CountingSource<I> {
+    pub(crate) fn new(input: I, samples: Arc<AtomicU64>) -> Self {
+        Self { input, samples }
+    }
+}
+
+impl<I: RodioSource> Iterator for CountingSource<I>
+where
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.input.next();
+        if next.is_some() {
+            self.samples.fetch_add(1, Ordering::Relaxed);
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I: RodioSource> RodioSource for CountingSource<I>
+where
+    I::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+fn resolve(locale: Option<&Locale>, key: &str) -> String {
+    locale
+        .and_then(|locale| {
+            let message = locale.bundle.get_message(key)?;
+            let pattern = message.value?;
+            let mut errors = Vec::new();
+            Some(
+                locale
+                    .bundle
+                    .format_pattern(pattern, None, &mut errors)
+                    .into_owned(),
+            )
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Sends the [`CaptionEvent`]s of the sounds played with captions, see the
+/// [module documentation](self).
+///
+/// Added by the [`CaptionBundle`](crate::CaptionBundle), which inserts the resources it needs.
+#[derive(Debug)]
+pub struct CaptionSystem;
+
+impl System for CaptionSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("CaptionSystem")
+                .read_resource::<CaptionLocale>()
+                .read_resource::<AssetStorage<Locale>>()
+                .write_resource::<EventChannel<CaptionEvent>>()
+                .with_query(<(Entity, Write<AudioEmitter>)>::query())
+                .build(|_, world, (locale, locales, events), query| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("caption_system");
+
+                    let locale = locale.0.as_ref().and_then(|handle| locales.get(handle));
+                    query.for_each_mut(world, |(entity, emitter)| {
+                        let mut i = 0;
+                        while i < emitter.captions.len() {
+                            let playing = emitter.captions[i].update(|cue, shown| {
+                                events.single_write(if shown {
+                                    CaptionEvent::Show {
+                                        emitter: *entity,
+                                        key: cue.key.clone(),
+                                        text: resolve(locale, &cue.key),
+                                    }
+                                } else {
+                                    CaptionEvent::Hide {
+                                        emitter: *entity,
+                                        key: cue.key.clone(),
+                                    }
+                                });
+                            });
+                            if playing {
+                                i += 1;
+                            } else {
+                                emitter.captions.swap_remove(i);
+                            }
+                        }
+                    });
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing(captions: Captions) -> (PlayingCaptions, Arc<AtomicU64>, Arc<AtomicBool>) {
+        let samples = Arc::new(AtomicU64::new(0));
+        let ended = Arc::new(AtomicBool::new(false));
+        let playing =
+            PlayingCaptions::new(Arc::new(captions), 2, 100, samples.clone(), ended.clone());
+        (playing, samples, ended)
+    }
+
+    fn changes(playing: &mut PlayingCaptions) -> (Vec<(String, bool)>, bool) {
+        let mut changes = Vec::new();
+        let playing = playing.update(|cue, shown| changes.push((cue.key.clone(), shown)));
+        (changes, playing)
+    }
+
+    #[test]
+    fn cues_follow_playback_position() {
+        let (mut playing, samples, _) = playing(
+            Captions::new()
+                .with_cue(0.5, 1.5, "first")
+                .with_cue(1.0, 2.0, "second"),
+        );
+
+        assert_eq!(changes(&mut playing), (vec![], true));
+
+        // 200 samples per second.
+        samples.store(120, Ordering::Relaxed);
+        assert_eq!(changes(&mut playing), (vec![("first".into(), true)], true));
+
+        samples.store(220, Ordering::Relaxed);
+        assert_eq!(changes(&mut playing), (vec![("second".into(), true)], true));
+
+        samples.store(320, Ordering::Relaxed);
+        assert_eq!(changes(&mut playing), (vec![("first".into(), false)], true));
+    }
+
+    #[test]
+    fn shown_cues_are_hidden_when_the_sound_ends() {
+        let (mut playing, samples, ended) = playing(Captions::new().with_cue(0.0, 10.0, "long"));
+
+        samples.store(20, Ordering::Relaxed);
+        assert_eq!(changes(&mut playing), (vec![("long".into(), true)], true));
+
+        ended.store(true, Ordering::Relaxed);
+        assert_eq!(changes(&mut playing), (vec![("long".into(), false)], false));
+    }
+
+    #[test]
+    fn unresolved_keys_are_shown_as_is() {
+        assert_eq!(resolve(None, "caption-door"), "caption-door");
+    }
+}
@@ -6,7 +6,12 @@ use std::{
 use rodio::{Decoder, SpatialSink};
 use smallvec::SmallVec;
 
-use crate::{components, source::Source, DecoderError};
+use crate::{
+    captions::{Captions, PlayingCaptions},
+    components,
+    source::Source,
+    DecoderError,
+};
 
 /// An audio source, add this component to anything that emits sound.
 /// TODO: This should get a proper Debug impl parsing the sinks and sound queue
@@ -14,7 +19,8 @@ use crate::{components, source::Source, DecoderError};
 #[derive(Default)]
 pub struct AudioEmitter {
     pub(crate) sinks: SmallVec<[(SpatialSink, Arc<AtomicBool>); 4]>,
-    pub(crate) sound_queue: SmallVec<[Decoder<Cursor<Source>>; 4]>,
+    pub(crate) sound_queue: SmallVec<[(Decoder<Cursor<Source>>, Option<Arc<Captions>>); 4]>,
+    pub(crate) captions: Vec<PlayingCaptions>,
     pub(crate) picker: Option<Box<dyn FnMut(&mut AudioEmitter) -> bool + Send + Sync>>,
 }
 
@@ -29,8 +35,24 @@ impl AudioEmitter {
 
     /// Plays an audio source from this emitter.
     pub fn play(&mut self, source: &Source) -> Result<(), DecoderError> {
-        self.sound_queue
-            .push(Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?);
+        self.sound_queue.push((
+            Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?,
+            None,
+        ));
+        Ok(())
+    }
+
+    /// Plays an audio source from this emitter, showing the given captions while it plays.
+    /// See the [`captions`](crate::captions) module.
+    pub fn play_with_captions(
+        &mut self,
+        source: &Source,
+        captions: impl Into<Arc<Captions>>,
+    ) -> Result<(), DecoderError> {
+        self.sound_queue.push((
+            Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?,
+            Some(captions.into()),
+        ));
         Ok(())
     }
 
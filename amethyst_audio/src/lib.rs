@@ -20,14 +20,17 @@ use std::{
 };
 
 pub use self::{
-    bundle::AudioBundle,
+    bundle::{AudioBundle, CaptionBundle},
+    captions::{CaptionEvent, CaptionLocale, CaptionSystem, Captions},
     components::*,
     formats::{FlacFormat, Mp3Format, OggFormat, WavFormat},
     sink::AudioSink,
     source::{Source, SourceHandle},
     systems::*,
+    track::AudioTrack,
 };
 
+pub mod captions;
 pub mod output;
 
 mod bundle;
@@ -37,6 +40,7 @@ mod formats;
 mod sink;
 mod source;
 mod systems;
+mod track;
 
 /// An error occurred while decoding the source.
 #[derive(Debug)]
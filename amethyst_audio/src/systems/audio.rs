@@ -2,7 +2,7 @@ use std::{
     iter::Iterator,
     mem::replace,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
 };
@@ -12,11 +12,12 @@ use amethyst_core::{
     math::convert,
     transform::Transform,
 };
-use rodio::SpatialSink;
+use rodio::{Source as _, SpatialSink};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
 use crate::{
+    captions::{CountingSource, PlayingCaptions},
     components::{AudioEmitter, AudioListener},
     end_signal::EndSignalSource,
     output::OutputWrapper,
@@ -112,7 +113,9 @@ impl System for AudioSystem {
                                                 }
                                             }
                                         }
-                                        while let Some(source) = audio_emitter.sound_queue.pop() {
+                                        while let Some((source, captions)) =
+                                            audio_emitter.sound_queue.pop()
+                                        {
                                             if let Some(output) = &wrapper.output {
                                                 let sink = SpatialSink::new(
                                                     &output.device,
@@ -122,12 +125,27 @@ impl System for AudioSystem {
                                                 );
                                                 let atomic_bool = Arc::new(AtomicBool::new(false));
                                                 let clone = atomic_bool.clone();
-                                                sink.append(EndSignalSource::new(
-                                                    source,
-                                                    move || {
-                                                        clone.store(true, Ordering::Relaxed);
-                                                    },
-                                                ));
+                                                let end = move || {
+                                                    clone.store(true, Ordering::Relaxed);
+                                                };
+                                                if let Some(captions) = captions {
+                                                    let samples = Arc::new(AtomicU64::new(0));
+                                                    audio_emitter.captions.push(
+                                                        PlayingCaptions::new(
+                                                            captions,
+                                                            source.channels(),
+                                                            source.sample_rate(),
+                                                            samples.clone(),
+                                                            atomic_bool.clone(),
+                                                        ),
+                                                    );
+                                                    sink.append(EndSignalSource::new(
+                                                        CountingSource::new(source, samples),
+                                                        end,
+                                                    ));
+                                                } else {
+                                                    sink.append(EndSignalSource::new(source, end));
+                                                }
                                                 audio_emitter.sinks.push((sink, atomic_bool));
                                             }
                                         }
@@ -0,0 +1,151 @@
+//! Sounds played on a sink of their own, reporting their playback position.
+
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    io::Cursor,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rodio::{Decoder, Sink, Source as RodioSource};
+
+use crate::{
+    captions::CountingSource, end_signal::EndSignalSource, output::Output, source::Source,
+    DecoderError,
+};
+
+/// A sound played on its own, which can be paused and moved to any position.
+///
+/// The playback position is counted from the samples sent to the output, so other media like
+/// the frames of a video can be synchronized with it.
+pub struct AudioTrack {
+    output: Output,
+    source: Source,
+    sink: Sink,
+    samples: Arc<AtomicU64>,
+    samples_per_second: u64,
+    ended: Arc<AtomicBool>,
+    volume: f32,
+}
+
+impl AudioTrack {
+    /// Creates a paused track playing `source` on `output` from its start.
+    ///
+    /// # Errors
+    /// This will return an Error if the loaded audio file in source could not be decoded.
+    pub fn new(output: &Output, source: &Source) -> Result<Self, DecoderError> {
+        let mut track = Self {
+            output: output.clone(),
+            source: source.clone(),
+            sink: Sink::new(&output.device),
+            samples: Arc::new(AtomicU64::new(0)),
+            samples_per_second: 0,
+            ended: Arc::new(AtomicBool::new(false)),
+            volume: 1.0,
+        };
+        track.sink.pause();
+        track.seek(Duration::from_secs(0))?;
+        Ok(track)
+    }
+
+    /// Resumes the playback.
+    pub fn play(&self) {
+        self.sink.play();
+    }
+
+    /// Pauses the playback, this can be resumed with `AudioTrack::play`.
+    pub fn pause(&self) {
+        self.sink.pause();
+    }
+
+    /// Returns true if the track is currently paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    /// Returns true once the whole sound was played.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.ended.load(Ordering::Relaxed)
+    }
+
+    /// Retrieves the volume of the track, between 0.0 and 1.0.
+    #[must_use]
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Sets the volume of the track.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.sink.set_volume(volume);
+    }
+
+    /// Time from the start of the sound played so far.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn position(&self) -> Duration {
+        if self.samples_per_second == 0 {
+            return Duration::from_secs(0);
+        }
+        let samples = self.samples.load(Ordering::Relaxed);
+        Duration::from_secs_f64(samples as f64 / self.samples_per_second as f64)
+    }
+
+    /// Moves the playback to `position` from the start of the sound, keeping it paused or
+    /// playing.
+    ///
+    /// The sound is decoded again up to `position`, so seeking far into long sounds takes a
+    /// while.
+    ///
+    /// # Errors
+    /// This will return an Error if the loaded audio file in source could not be decoded.
+    pub fn seek(&mut self, position: Duration) -> Result<(), DecoderError> {
+        let mut decoder = Decoder::new(Cursor::new(self.source.clone()))?;
+        let channels = u64::from(decoder.channels().max(1));
+        self.samples_per_second = channels * u64::from(decoder.sample_rate());
+
+        // Whole frames are skipped, so the samples stay on their channels.
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let skipped =
+            (position.as_secs_f64() * self.samples_per_second as f64) as u64 / channels * channels;
+        #[allow(clippy::cast_possible_truncation)]
+        let skipped = decoder.by_ref().take(skipped as usize).count() as u64;
+
+        let sink = Sink::new(&self.output.device);
+        sink.set_volume(self.volume);
+        if self.sink.is_paused() {
+            sink.pause();
+        }
+        self.samples = Arc::new(AtomicU64::new(skipped));
+        self.ended = Arc::new(AtomicBool::new(false));
+        let ended = self.ended.clone();
+        sink.append(EndSignalSource::new(
+            CountingSource::new(decoder, self.samples.clone()),
+            move || ended.store(true, Ordering::Relaxed),
+        ));
+        // Dropping the previous sink stops its sound.
+        self.sink = sink;
+        Ok(())
+    }
+}
+
+impl Debug for AudioTrack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("AudioTrack")
+            .field("output", &self.output)
+            .field("position", &self.position())
+            .field("paused", &self.is_paused())
+            .field("finished", &self.is_finished())
+            .field("volume", &self.volume)
+            .finish()
+    }
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
 use amethyst_core::{
     ecs::{DispatcherBuilder, Resources, SystemBundle, World},
@@ -9,8 +9,8 @@ use amethyst_error::Error;
 use winit::event::Event;
 
 use super::{
-    ArcBallRotationSystem, CursorHideSystem, FlyMovementSystem, FreeRotationSystem, HideCursor,
-    MouseFocusUpdateSystem, WindowFocus,
+    ArcBallRotationSystem, CursorHideSystem, FlyCollision, FlyMovementSystem, FreeRotationSystem,
+    HideCursor, MouseFocusUpdateSystem, WindowFocus,
 };
 
 /// The bundle that creates a flying movement system.
@@ -30,6 +30,9 @@ use super::{
 /// * `FreeRotationSystem`
 /// * `MouseFocusUpdateSystem`
 /// * `CursorHideSystem`
+///
+/// It also adds an empty `FlyCollision` resource, which a physics integration can set to keep
+/// the cameras from flying through geometry.
 #[derive(Debug)]
 pub struct FlyControlBundle {
     sensitivity_x: f32,
@@ -38,6 +41,9 @@ pub struct FlyControlBundle {
     horizontal_axis: Option<Cow<'static, str>>,
     vertical_axis: Option<Cow<'static, str>>,
     longitudinal_axis: Option<Cow<'static, str>>,
+    sprint: Option<(Cow<'static, str>, f32)>,
+    slow: Option<(Cow<'static, str>, f32)>,
+    acceleration: Option<(f32, f32)>,
 }
 
 impl FlyControlBundle {
@@ -55,6 +61,9 @@ impl FlyControlBundle {
             horizontal_axis,
             vertical_axis,
             longitudinal_axis,
+            sprint: None,
+            slow: None,
+            acceleration: None,
         }
     }
 
@@ -72,6 +81,32 @@ impl FlyControlBundle {
         self.speed = speed;
         self
     }
+
+    /// Multiplies the speed by `multiplier` while `action` is down, usually bound to shift.
+    #[must_use]
+    pub fn with_sprint(mut self, action: impl Into<Cow<'static, str>>, multiplier: f32) -> Self {
+        self.sprint = Some((action.into(), multiplier));
+        self
+    }
+
+    /// Multiplies the speed by `multiplier`, usually below 1, while `action` is down, for
+    /// precise placement.
+    #[must_use]
+    pub fn with_slow(mut self, action: impl Into<Cow<'static, str>>, multiplier: f32) -> Self {
+        self.slow = Some((action.into(), multiplier));
+        self
+    }
+
+    /// Smooths the movement: the speed changes by at most `acceleration` units per second
+    /// squared when speeding up, and `deceleration` when slowing down.
+    ///
+    /// Without it, the cameras move at full speed as soon as an axis is pressed and stop as
+    /// soon as it's released.
+    #[must_use]
+    pub fn with_acceleration(mut self, acceleration: f32, deceleration: f32) -> Self {
+        self.acceleration = Some((acceleration, deceleration));
+        self
+    }
 }
 
 impl SystemBundle for FlyControlBundle {
@@ -86,7 +121,12 @@ impl SystemBundle for FlyControlBundle {
             horizontal_axis: self.horizontal_axis.clone(),
             vertical_axis: self.vertical_axis.clone(),
             longitudinal_axis: self.longitudinal_axis.clone(),
+            sprint: self.sprint.clone(),
+            slow: self.slow.clone(),
+            acceleration: self.acceleration,
+            velocities: HashMap::new(),
         });
+        resources.get_or_insert_with(FlyCollision::default);
 
         let reader = resources
             .get_mut::<EventChannel<Event<'static, ()>>>()
@@ -1,4 +1,4 @@
-use amethyst_core::ecs::Entity;
+use amethyst_core::{ecs::Entity, math::Vector3};
 use derive_new::new;
 use serde::{Deserialize, Serialize};
 
@@ -14,4 +14,67 @@ pub struct ArcBallControl {
     pub target: Entity,
     /// The distance from the target entity that the camera should orbit at.
     pub distance: f32,
+    #[new(default)]
+    pub(crate) transition: Option<ArcBallTransition>,
+}
+
+/// Move of an arc ball camera from its previous orbit to the current one.
+#[derive(Debug, Clone)]
+pub(crate) struct ArcBallTransition {
+    /// Point orbited before the transition, known once the `ArcBallRotationSystem` sees the
+    /// camera.
+    pub(crate) from_center: Option<Vector3<f32>>,
+    pub(crate) from_distance: f32,
+    pub(crate) elapsed: f32,
+    pub(crate) duration: f32,
+}
+
+impl ArcBallTransition {
+    /// Eased progress of the transition, from 0 to 1.
+    pub(crate) fn progress(&self) -> f32 {
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        };
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl ArcBallControl {
+    /// Orbits `target` at `distance`, moving there smoothly over `duration` seconds, like
+    /// focusing the selection in a 3D editor.
+    pub fn focus(&mut self, target: Entity, distance: f32, duration: f32) {
+        let from_distance = self.transition.as_ref().map_or(self.distance, |transition| {
+            // Carries on from where an unfinished transition got.
+            let progress = transition.progress();
+            transition.from_distance + (self.distance - transition.from_distance) * progress
+        });
+        self.target = target;
+        self.distance = distance;
+        self.transition = Some(ArcBallTransition {
+            from_center: None,
+            from_distance,
+            elapsed: 0.0,
+            duration,
+        });
+    }
+
+    /// Focuses `target` at the distance framing a sphere of `radius` around it, such as its
+    /// bounding sphere, with a perspective camera of vertical field of view `fovy` in radians.
+    pub fn frame(&mut self, target: Entity, radius: f32, fovy: f32, duration: f32) {
+        self.focus(target, Self::framing_distance(radius, fovy), duration);
+    }
+
+    /// Distance at which a sphere of `radius` fits the vertical field of view `fovy` in radians.
+    #[must_use]
+    pub fn framing_distance(radius: f32, fovy: f32) -> f32 {
+        radius / (fovy / 2.0).sin()
+    }
+
+    /// Returns whether the camera is still moving to the target set by `focus` or `frame`.
+    #[must_use]
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
 }
@@ -16,7 +16,7 @@
 pub use self::{
     bundles::{ArcBallControlBundle, FlyControlBundle},
     components::{ArcBallControl, FlyControl},
-    resources::{HideCursor, WindowFocus},
+    resources::{FlyCollision, HideCursor, WindowFocus},
     systems::{
         ArcBallRotationSystem, CursorHideSystem, FlyMovementSystem, FreeRotationSystem,
         MouseFocusUpdateSystem,
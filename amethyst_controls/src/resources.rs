@@ -1,3 +1,6 @@
+use std::{fmt, sync::Arc};
+
+use amethyst_core::math::Vector3;
 use serde::{Deserialize, Serialize};
 
 /// Struct which holds information about whether the window is focused.
@@ -23,3 +26,57 @@ pub struct HideCursor {
     /// If true this system will take control of the cursor.
     pub hide: bool,
 }
+
+/// Collision query stopping the `FlyControl` cameras at geometry.
+///
+/// It's empty by default, so the cameras fly through everything. A physics integration sets it
+/// to a sweep of the camera's collider, given the position a camera moves from and the one it
+/// would reach, and returning the position it stops at. Positions are in the coordinates of the
+/// `Transform` of the camera, usually the world as cameras seldom have a parent.
+#[derive(Clone, Default)]
+pub struct FlyCollision {
+    query: Option<Arc<FlyCollisionQuery>>,
+}
+
+type FlyCollisionQuery = dyn Fn(&Vector3<f32>, &Vector3<f32>) -> Vector3<f32> + Send + Sync;
+
+impl FlyCollision {
+    /// Creates an empty collision query.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the cameras at the position returned by `query`.
+    pub fn set_query<F>(&mut self, query: F)
+    where
+        F: Fn(&Vector3<f32>, &Vector3<f32>) -> Vector3<f32> + Send + Sync + 'static,
+    {
+        self.query = Some(Arc::new(query));
+    }
+
+    /// Removes the collision query, so the cameras fly through everything again.
+    pub fn clear(&mut self) {
+        self.query = None;
+    }
+
+    /// Returns whether a collision query is set.
+    #[must_use]
+    pub fn is_set(&self) -> bool {
+        self.query.is_some()
+    }
+
+    /// Returns the position reached moving from `from` to `to`.
+    #[must_use]
+    pub fn sweep(&self, from: &Vector3<f32>, to: &Vector3<f32>) -> Vector3<f32> {
+        self.query.as_ref().map_or(*to, |query| query(from, to))
+    }
+}
+
+impl fmt::Debug for FlyCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlyCollision")
+            .field("query", &self.query.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
@@ -18,26 +18,46 @@ use winit::{
 
 use crate::{
     components::{ArcBallControl, FlyControl},
-    resources::{HideCursor, WindowFocus},
+    resources::{FlyCollision, HideCursor, WindowFocus},
 };
 
 /// The system that manages the fly movement.
+///
+/// The cameras accelerate towards the speed given by the input when an acceleration is set, and
+/// are stopped by the `FlyCollision` query when one is set.
 #[derive(Debug)]
 pub struct FlyMovementSystem {
     pub(crate) speed: f32,
     pub(crate) horizontal_axis: Option<Cow<'static, str>>,
     pub(crate) vertical_axis: Option<Cow<'static, str>>,
     pub(crate) longitudinal_axis: Option<Cow<'static, str>>,
+    pub(crate) sprint: Option<(Cow<'static, str>, f32)>,
+    pub(crate) slow: Option<(Cow<'static, str>, f32)>,
+    pub(crate) acceleration: Option<(f32, f32)>,
+    pub(crate) velocities: HashMap<Entity, Vector3<f32>>,
+}
+
+impl FlyMovementSystem {
+    /// Speed multiplier of the sprint and slow actions held.
+    fn speed_multiplier(&self, input: &InputHandler) -> f32 {
+        [&self.sprint, &self.slow]
+            .iter()
+            .filter_map(|modifier| modifier.as_ref())
+            .filter(|(action, _)| input.action_is_down(action).unwrap_or(false))
+            .map(|(_, multiplier)| multiplier)
+            .product()
+    }
 }
 
 impl System for FlyMovementSystem {
-    fn build(self) -> Box<dyn systems::ParallelRunnable> {
+    fn build(mut self) -> Box<dyn systems::ParallelRunnable> {
         Box::new(
             SystemBuilder::new("FlyMovementSystem")
                 .read_resource::<Time>()
                 .read_resource::<InputHandler>()
-                .with_query(<(&FlyControl, &mut Transform)>::query())
-                .build(move |_commands, world, (time, input), controls| {
+                .read_resource::<FlyCollision>()
+                .with_query(<(Entity, &FlyControl, &mut Transform)>::query())
+                .build(move |_commands, world, (time, input, collision), controls| {
                     #[cfg(feature = "profiler")]
                     profile_scope!("fly_movement_system");
 
@@ -45,23 +65,66 @@ impl System for FlyMovementSystem {
                     let y = get_input_axis_simple(&self.vertical_axis, input);
                     let z = get_input_axis_simple(&self.longitudinal_axis, input);
 
-                    if let Some(dir) = Unit::try_new(Vector3::new(x, y, z), convert(1.0e-6)) {
-                        for (_, transform) in controls.iter_mut(world) {
-                            let delta_sec = time.delta_time().as_secs_f32();
-                            transform.append_translation_along(dir, delta_sec * self.speed);
+                    // Velocity in the local space of the cameras.
+                    let target = Unit::try_new(Vector3::new(x, y, z), convert(1.0e-6))
+                        .map_or_else(Vector3::zeros, |dir| {
+                            dir.into_inner() * self.speed * self.speed_multiplier(input)
+                        });
+                    let delta_sec = time.delta_time().as_secs_f32();
+
+                    let mut velocities = HashMap::new();
+                    for (entity, _, transform) in controls.iter_mut(world) {
+                        let velocity = match self.acceleration {
+                            Some((acceleration, deceleration)) => {
+                                let current = self
+                                    .velocities
+                                    .get(entity)
+                                    .copied()
+                                    .unwrap_or_else(Vector3::zeros);
+                                let rate = if target.norm() >= current.norm() {
+                                    acceleration
+                                } else {
+                                    deceleration
+                                };
+                                approach(current, target, rate * delta_sec)
+                            }
+                            None => target,
+                        };
+                        if velocity == Vector3::zeros() {
+                            continue;
                         }
+                        velocities.insert(*entity, velocity);
+
+                        let from = *transform.translation();
+                        let to = from + transform.rotation() * velocity * delta_sec;
+                        *transform.translation_mut() = collision.sweep(&from, &to);
                     }
+                    self.velocities = velocities;
                 }),
         )
     }
 }
 
+/// Moves `current` towards `target` by at most `max_delta`.
+fn approach(current: Vector3<f32>, target: Vector3<f32>, max_delta: f32) -> Vector3<f32> {
+    let difference = target - current;
+    let distance = difference.norm();
+    if distance <= max_delta {
+        target
+    } else {
+        current + difference * (max_delta / distance)
+    }
+}
+
 /// The system that manages the arc ball movement;
 /// In essence, the system will align the camera with its target while keeping the distance to it
 /// and while keeping the orientation of the camera.
 ///
 /// To modify the orientation of the camera in accordance with the mouse input, please use the
 /// `FreeRotationSystem`.
+///
+/// After `ArcBallControl::focus` or `ArcBallControl::frame`, the camera moves to its new orbit
+/// over the duration of the transition.
 #[derive(Debug)]
 pub struct ArcBallRotationSystem;
 
@@ -69,10 +132,11 @@ impl System for ArcBallRotationSystem {
     fn build(self) -> Box<dyn systems::ParallelRunnable> {
         Box::new(
             SystemBuilder::new("ArcBallRotationSystem")
+                .read_resource::<Time>()
                 .with_query(<&ArcBallControl>::query())
-                .with_query(<(&ArcBallControl, &mut Transform)>::query())
+                .with_query(<(&mut ArcBallControl, &mut Transform)>::query())
                 .read_component::<Transform>()
-                .build(move |_commands, world, (), queries| {
+                .build(move |_commands, world, time, queries| {
                     #[cfg(feature = "profiler")]
                     profile_scope!("arc_ball_rotation_system");
 
@@ -88,14 +152,36 @@ impl System for ArcBallRotationSystem {
                         })
                         .collect();
 
+                    let delta_sec = time.delta_time().as_secs_f32();
                     for (control, transform) in queries.1.iter_mut(world) {
-                        let pos_vec = transform.rotation() * -Vector3::z() * control.distance;
-                        match targets.get(&control.target) {
-                            Some(target_trans) => {
-                                *transform.translation_mut() = target_trans.translation() - pos_vec;
-                            }
+                        let target = match targets.get(&control.target) {
+                            Some(target_trans) => *target_trans.translation(),
                             None => continue,
+                        };
+                        let forward = transform.rotation() * -Vector3::z();
+                        let (center, distance) = match &mut control.transition {
+                            Some(transition) => {
+                                let from_center = *transition.from_center.get_or_insert_with(|| {
+                                    transform.translation() + forward * transition.from_distance
+                                });
+                                transition.elapsed += delta_sec;
+                                let progress = transition.progress();
+                                (
+                                    from_center.lerp(&target, progress),
+                                    transition.from_distance
+                                        + (control.distance - transition.from_distance) * progress,
+                                )
+                            }
+                            None => (target, control.distance),
+                        };
+                        if control
+                            .transition
+                            .as_ref()
+                            .map_or(false, |transition| transition.elapsed >= transition.duration)
+                        {
+                            control.transition = None;
                         }
+                        *transform.translation_mut() = center - forward * distance;
                     }
                 }),
         )
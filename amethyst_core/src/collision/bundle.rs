@@ -0,0 +1,43 @@
+//! ECS collision bundle
+
+use amethyst_error::Error;
+
+use crate::{
+    collision::{CollisionEvent, CollisionSystem},
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::EventChannel,
+};
+
+/// Adds the [`CollisionSystem`] and the `EventChannel<CollisionEvent>` it writes to.
+///
+/// Add this bundle after the [`TransformBundle`](crate::transform::TransformBundle).
+#[derive(Debug, Default)]
+pub struct CollisionBundle {
+    cell_size: Option<f32>,
+}
+
+impl CollisionBundle {
+    /// Sets the cell size of the broadphase grid, see [`SpatialHash`](super::SpatialHash).
+    #[must_use]
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = Some(cell_size);
+        self
+    }
+}
+
+impl SystemBundle for CollisionBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_insert_with(EventChannel::<CollisionEvent>::new);
+        builder.add_system(
+            self.cell_size
+                .map_or_else(CollisionSystem::default, CollisionSystem::new),
+        );
+
+        Ok(())
+    }
+}
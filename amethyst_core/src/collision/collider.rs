@@ -0,0 +1,282 @@
+//! Collider component and shape overlap tests.
+
+use crate::math::{Matrix4, Vector2};
+
+/// Shape of a 2D collider, centered on the position of the collider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    /// Axis-aligned box given by its half width and half height.
+    Aabb {
+        /// Half of the width and height of the box.
+        half_extents: Vector2<f32>,
+    },
+    /// Circle given by its radius.
+    Circle {
+        /// Radius of the circle.
+        radius: f32,
+    },
+}
+
+/// A 2D collider attached to an entity with a [`Transform`](crate::transform::Transform).
+///
+/// Colliders are positioned by the global matrix of the transform, on the XY plane. The scale
+/// of the transform is applied to the shape, while the rotation is ignored: boxes always stay
+/// aligned with the axes.
+///
+/// Two colliders are only tested against each other when the `layer` of each one intersects the
+/// `mask` of the other.
+///
+/// # Examples
+///
+/// ```
+/// use amethyst::core::{collision::Collider, ecs::*, math::Vector2, transform::Transform};
+///
+/// const PLAYER: u32 = 1;
+/// const PICKUP: u32 = 1 << 1;
+///
+/// let mut world = World::default();
+/// world.push((
+///     Transform::default(),
+///     Collider::aabb(Vector2::new(8.0, 16.0)).with_layer(PLAYER),
+/// ));
+/// world.push((
+///     Transform::default(),
+///     Collider::circle(4.0).with_layer(PICKUP).with_mask(PLAYER),
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collider {
+    /// Shape of the collider.
+    pub shape: ColliderShape,
+    /// Offset of the shape from the origin of the transform, before scaling.
+    pub offset: Vector2<f32>,
+    /// Layers this collider belongs to, as a bit set.
+    pub layer: u32,
+    /// Layers this collider collides with, as a bit set.
+    pub mask: u32,
+}
+
+impl Collider {
+    /// Creates a collider with given shape, belonging to and colliding with all layers.
+    #[must_use]
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            offset: Vector2::zeros(),
+            layer: u32::MAX,
+            mask: u32::MAX,
+        }
+    }
+
+    /// Creates an axis-aligned box collider.
+    #[must_use]
+    pub fn aabb(half_extents: Vector2<f32>) -> Self {
+        Self::new(ColliderShape::Aabb { half_extents })
+    }
+
+    /// Creates a circle collider.
+    #[must_use]
+    pub fn circle(radius: f32) -> Self {
+        Self::new(ColliderShape::Circle { radius })
+    }
+
+    /// Moves the shape away from the origin of the transform.
+    #[must_use]
+    pub fn with_offset(mut self, offset: Vector2<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the layers this collider belongs to.
+    #[must_use]
+    pub fn with_layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Sets the layers this collider collides with.
+    #[must_use]
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Returns `true` if the layers and masks of both colliders allow them to collide.
+    #[must_use]
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+
+    /// Places the collider in world space using the global matrix of its transform.
+    #[must_use]
+    pub fn to_world(&self, global_matrix: &Matrix4<f32>) -> WorldShape {
+        let scale = Vector2::new(
+            global_matrix.column(0).xy().norm(),
+            global_matrix.column(1).xy().norm(),
+        );
+        let center = global_matrix.column(3).xy() + self.offset.component_mul(&scale);
+        match self.shape {
+            ColliderShape::Aabb { half_extents } => {
+                WorldShape::Aabb {
+                    center,
+                    half_extents: half_extents.component_mul(&scale),
+                }
+            }
+            ColliderShape::Circle { radius } => {
+                WorldShape::Circle {
+                    center,
+                    radius: radius * scale.x.max(scale.y),
+                }
+            }
+        }
+    }
+}
+
+/// A collider shape placed in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldShape {
+    /// Axis-aligned box.
+    Aabb {
+        /// Center of the box.
+        center: Vector2<f32>,
+        /// Half of the width and height of the box.
+        half_extents: Vector2<f32>,
+    },
+    /// Circle.
+    Circle {
+        /// Center of the circle.
+        center: Vector2<f32>,
+        /// Radius of the circle.
+        radius: f32,
+    },
+}
+
+impl WorldShape {
+    /// Returns the minimum and maximum corners of the bounding box of this shape.
+    #[must_use]
+    pub fn bounds(&self) -> (Vector2<f32>, Vector2<f32>) {
+        match *self {
+            WorldShape::Aabb {
+                center,
+                half_extents,
+            } => (center - half_extents, center + half_extents),
+            WorldShape::Circle { center, radius } => {
+                let extents = Vector2::repeat(radius);
+                (center - extents, center + extents)
+            }
+        }
+    }
+
+    /// Returns `true` if both shapes overlap. Shapes that only touch do not overlap.
+    #[must_use]
+    pub fn overlaps(&self, other: &WorldShape) -> bool {
+        match (*self, *other) {
+            (
+                WorldShape::Aabb {
+                    center: a,
+                    half_extents: a_extents,
+                },
+                WorldShape::Aabb {
+                    center: b,
+                    half_extents: b_extents,
+                },
+            ) => {
+                let distance = (a - b).abs();
+                let extents = a_extents + b_extents;
+                distance.x < extents.x && distance.y < extents.y
+            }
+            (
+                WorldShape::Circle {
+                    center: a,
+                    radius: a_radius,
+                },
+                WorldShape::Circle {
+                    center: b,
+                    radius: b_radius,
+                },
+            ) => (a - b).norm_squared() < (a_radius + b_radius).powi(2),
+            (
+                WorldShape::Aabb {
+                    center,
+                    half_extents,
+                },
+                WorldShape::Circle {
+                    center: circle,
+                    radius,
+                },
+            )
+            | (
+                WorldShape::Circle {
+                    center: circle,
+                    radius,
+                },
+                WorldShape::Aabb {
+                    center,
+                    half_extents,
+                },
+            ) => {
+                let closest = circle
+                    .sup(&(center - half_extents))
+                    .inf(&(center + half_extents));
+                (circle - closest).norm_squared() < radius * radius
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vector3;
+
+    fn at(x: f32, y: f32) -> Matrix4<f32> {
+        Matrix4::new_translation(&Vector3::new(x, y, 0.0))
+    }
+
+    #[test]
+    fn aabb_overlap() {
+        let a = Collider::aabb(Vector2::new(1.0, 1.0)).to_world(&at(0.0, 0.0));
+        let b = Collider::aabb(Vector2::new(1.0, 1.0)).to_world(&at(1.5, 1.5));
+        let c = Collider::aabb(Vector2::new(1.0, 1.0)).to_world(&at(2.0, 0.0));
+
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c));
+    }
+
+    #[test]
+    fn circle_overlap() {
+        let a = Collider::circle(1.0).to_world(&at(0.0, 0.0));
+        let b = Collider::circle(1.0).to_world(&at(1.0, 1.0));
+        let c = Collider::circle(1.0).to_world(&at(1.5, 1.5));
+
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c));
+    }
+
+    #[test]
+    fn aabb_circle_overlap() {
+        let aabb = Collider::aabb(Vector2::new(1.0, 1.0)).to_world(&at(0.0, 0.0));
+        let side = Collider::circle(1.0).to_world(&at(1.5, 0.0));
+        let corner = Collider::circle(1.0).to_world(&at(1.8, 1.8));
+
+        assert!(aabb.overlaps(&side));
+        assert!(side.overlaps(&aabb));
+        assert!(!aabb.overlaps(&corner));
+    }
+
+    #[test]
+    fn scale_and_offset() {
+        let matrix = at(10.0, 0.0) * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 3.0, 1.0));
+        let shape = Collider::aabb(Vector2::new(1.0, 1.0))
+            .with_offset(Vector2::new(1.0, 0.0))
+            .to_world(&matrix);
+
+        assert_eq!(
+            shape,
+            WorldShape::Aabb {
+                center: Vector2::new(12.0, 0.0),
+                half_extents: Vector2::new(2.0, 3.0),
+            }
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! System detecting overlapping colliders.
+
+use std::collections::HashSet;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use super::{Collider, CollisionEvent, SpatialHash};
+use crate::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::EventChannel,
+    transform::Transform,
+};
+
+/// System that tests all [`Collider`]s against each other and emits a [`CollisionEvent`] when two
+/// of them start or stop overlapping.
+///
+/// Colliders are placed using the global matrix of their `Transform`, so this system should run
+/// after the [`TransformSystem`](crate::transform::TransformSystem).
+#[derive(Debug)]
+pub struct CollisionSystem {
+    cell_size: f32,
+}
+
+impl CollisionSystem {
+    /// Creates the system using a broadphase grid with cells of given size.
+    ///
+    /// See [`SpatialHash`] for how to choose the cell size.
+    #[must_use]
+    pub fn new(cell_size: f32) -> Self {
+        Self { cell_size }
+    }
+}
+
+impl Default for CollisionSystem {
+    fn default() -> Self {
+        Self::new(SpatialHash::default().cell_size())
+    }
+}
+
+impl System for CollisionSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        let mut grid = SpatialHash::new(self.cell_size);
+        let mut shapes = Vec::new();
+        let mut overlapping = HashSet::<(Entity, Entity)>::new();
+
+        Box::new(
+            SystemBuilder::new("CollisionSystem")
+                .with_query(<(Entity, &Transform, &Collider)>::query())
+                .write_resource::<EventChannel<CollisionEvent>>()
+                .build(move |_commands, world, events, query| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("collision_system");
+
+                    grid.clear();
+                    shapes.clear();
+                    for (entity, transform, collider) in query.iter(world) {
+                        let shape = collider.to_world(transform.global_matrix());
+                        let (min, max) = shape.bounds();
+                        grid.insert(shapes.len(), min, max);
+                        shapes.push((*entity, *collider, shape));
+                    }
+
+                    let current = grid
+                        .candidate_pairs()
+                        .into_iter()
+                        .map(|(a, b)| (&shapes[a], &shapes[b]))
+                        .filter(|((_, a, a_shape), (_, b, b_shape))| {
+                            a.interacts_with(b) && a_shape.overlaps(b_shape)
+                        })
+                        .map(|((a, _, _), (b, _, _))| (*a.min(b), *a.max(b)))
+                        .collect::<HashSet<_>>();
+
+                    events.iter_write(
+                        current
+                            .difference(&overlapping)
+                            .map(|(a, b)| CollisionEvent::Enter(*a, *b)),
+                    );
+                    events.iter_write(
+                        overlapping
+                            .difference(&current)
+                            .map(|(a, b)| CollisionEvent::Exit(*a, *b)),
+                    );
+                    overlapping = current;
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collision::CollisionBundle, ecs::*, math::Vector2, shrev::ReaderId,
+        transform::TransformBundle,
+    };
+
+    fn collision_world() -> (Resources, World, Dispatcher, ReaderId<CollisionEvent>) {
+        let mut resources = Resources::default();
+        let mut world = World::default();
+
+        let dispatcher = DispatcherBuilder::default()
+            .add_bundle(TransformBundle)
+            .add_bundle(CollisionBundle::default())
+            .build(&mut world, &mut resources)
+            .unwrap();
+        let reader = resources
+            .get_mut::<EventChannel<CollisionEvent>>()
+            .unwrap()
+            .register_reader();
+
+        (resources, world, dispatcher, reader)
+    }
+
+    fn read_events(
+        resources: &Resources,
+        reader: &mut ReaderId<CollisionEvent>,
+    ) -> Vec<CollisionEvent> {
+        resources
+            .get::<EventChannel<CollisionEvent>>()
+            .unwrap()
+            .read(reader)
+            .copied()
+            .collect()
+    }
+
+    fn set_x(world: &mut World, entity: Entity, x: f32) {
+        world
+            .entry(entity)
+            .unwrap()
+            .into_component_mut::<Transform>()
+            .unwrap()
+            .set_translation_x(x);
+    }
+
+    #[test]
+    fn enter_and_exit() {
+        let (mut resources, mut world, mut dispatcher, mut reader) = collision_world();
+
+        let a = world.push((Transform::default(), Collider::circle(1.0)));
+        let b = world.push((Transform::default(), Collider::aabb(Vector2::new(1.0, 1.0))));
+        set_x(&mut world, b, 5.0);
+        let (first, second) = (a.min(b), a.max(b));
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert!(read_events(&resources, &mut reader).is_empty());
+
+        set_x(&mut world, b, 1.5);
+        dispatcher.execute(&mut world, &mut resources);
+        assert_eq!(
+            read_events(&resources, &mut reader),
+            vec![CollisionEvent::Enter(first, second)]
+        );
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert!(read_events(&resources, &mut reader).is_empty());
+
+        world.remove(a);
+        dispatcher.execute(&mut world, &mut resources);
+        assert_eq!(
+            read_events(&resources, &mut reader),
+            vec![CollisionEvent::Exit(first, second)]
+        );
+    }
+
+    #[test]
+    fn layers_filter_pairs() {
+        let (mut resources, mut world, mut dispatcher, mut reader) = collision_world();
+
+        world.push((
+            Transform::default(),
+            Collider::circle(1.0).with_layer(1).with_mask(1),
+        ));
+        world.push((
+            Transform::default(),
+            Collider::circle(1.0).with_layer(2).with_mask(2),
+        ));
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert!(read_events(&resources, &mut reader).is_empty());
+    }
+}
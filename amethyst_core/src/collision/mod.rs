@@ -0,0 +1,30 @@
+//! Minimal 2D collision detection between axis-aligned boxes and circles.
+//!
+//! This is meant for games that only need to know which entities overlap, for example to pick
+//! up items or trigger areas, without the cost of a full physics engine. Attach a [`Collider`] to
+//! entities with a `Transform`, add the [`CollisionBundle`] and read [`CollisionEvent`]s from
+//! the `EventChannel<CollisionEvent>` resource.
+
+pub use self::{
+    bundle::CollisionBundle,
+    collider::{Collider, ColliderShape, WorldShape},
+    collision_system::CollisionSystem,
+    spatial_hash::SpatialHash,
+};
+use crate::ecs::Entity;
+
+pub mod bundle;
+pub mod collider;
+pub mod collision_system;
+pub mod spatial_hash;
+
+/// Event emitted by the [`CollisionSystem`] when two colliders start or stop overlapping.
+///
+/// The entities are ordered, the first one being the lower of both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionEvent {
+    /// The colliders of both entities started overlapping.
+    Enter(Entity, Entity),
+    /// The colliders of both entities stopped overlapping, or one of them was removed.
+    Exit(Entity, Entity),
+}
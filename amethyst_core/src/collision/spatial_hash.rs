@@ -0,0 +1,139 @@
+//! Uniform grid used as broadphase for collision detection.
+
+use std::collections::{HashMap, HashSet};
+
+use smallvec::SmallVec;
+
+use crate::math::Vector2;
+
+/// Sparse uniform grid bucketing bounding boxes by the cells they cover.
+///
+/// Only items sharing at least one cell are reported as candidate pairs. The cell size should be
+/// around the size of a typical collider: much smaller cells make large colliders cover many
+/// cells, while much larger cells put many unrelated colliders in the same bucket.
+///
+/// Items covering more than [`MAX_CELLS`](SpatialHash::MAX_CELLS) cells, or with non-finite
+/// bounds, are kept in a separate list and paired with every other item instead.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), SmallVec<[usize; 4]>>,
+    items: Vec<usize>,
+    oversized: Vec<usize>,
+}
+
+impl SpatialHash {
+    /// Maximum number of cells an item is inserted into.
+    pub const MAX_CELLS: i64 = 256;
+
+    /// Creates an empty grid with square cells of given size.
+    #[must_use]
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Cell size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            items: Vec::new(),
+            oversized: Vec::new(),
+        }
+    }
+
+    /// Size of the side of a cell.
+    #[must_use]
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.items.clear();
+        self.oversized.clear();
+    }
+
+    /// Inserts item `index` into all cells covered by the bounding box from `min` to `max`.
+    pub fn insert(&mut self, index: usize, min: Vector2<f32>, max: Vector2<f32>) {
+        self.items.push(index);
+
+        let (min_x, min_y) = self.cell(min);
+        let (max_x, max_y) = self.cell(max);
+        let covered = (i64::from(max_x) - i64::from(min_x) + 1)
+            .saturating_mul(i64::from(max_y) - i64::from(min_y) + 1);
+        let finite = min.iter().chain(max.iter()).all(|f| f.is_finite());
+        if !finite || covered > Self::MAX_CELLS {
+            self.oversized.push(index);
+            return;
+        }
+
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Returns every pair of items sharing a cell once, with the lower index first.
+    #[must_use]
+    pub fn candidate_pairs(&self) -> HashSet<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        for bucket in self.cells.values() {
+            for (i, a) in bucket.iter().enumerate() {
+                for b in &bucket[i + 1..] {
+                    pairs.insert((*a.min(b), *a.max(b)));
+                }
+            }
+        }
+        for a in &self.oversized {
+            for b in self.items.iter().filter(|b| *b != a) {
+                pairs.insert((*a.min(b), *a.max(b)));
+            }
+        }
+        pairs
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cell(&self, position: Vector2<f32>) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(64.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_share_cells() {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(0, Vector2::new(0.0, 0.0), Vector2::new(5.0, 5.0));
+        hash.insert(1, Vector2::new(8.0, 8.0), Vector2::new(12.0, 12.0));
+        hash.insert(2, Vector2::new(-30.0, 0.0), Vector2::new(-25.0, 5.0));
+
+        let pairs = hash.candidate_pairs();
+        assert_eq!(pairs.len(), 1);
+        assert!(pairs.contains(&(0, 1)));
+
+        hash.clear();
+        assert!(hash.candidate_pairs().is_empty());
+    }
+    #[test]
+    fn oversized_items_pair_with_everything() {
+        let mut hash = SpatialHash::new(1.0);
+        hash.insert(0, Vector2::new(0.0, 0.0), Vector2::new(0.5, 0.5));
+        hash.insert(1, Vector2::new(-1.0e9, -1.0e9), Vector2::new(1.0e9, 1.0e9));
+        hash.insert(2, Vector2::new(f32::NEG_INFINITY, 0.0), Vector2::new(0.0, 0.0));
+        hash.insert(3, Vector2::new(100.0, 100.0), Vector2::new(100.5, 100.5));
+
+        let pairs = hash.candidate_pairs();
+        assert_eq!(pairs.len(), 5);
+        assert!(!pairs.contains(&(0, 3)));
+    }
+}
@@ -1,8 +1,11 @@
 use amethyst_error::Error;
 
-use crate::ecs::{
-    systems::{Executor, ParallelRunnable, Step},
-    Resources, Runnable, Schedule, World,
+use crate::{
+    ecs::{
+        systems::{Executor, ParallelRunnable, Step},
+        Resources, Runnable, Schedule, World,
+    },
+    Lifecycle,
 };
 
 /// A `SystemBundle` is a structure that adds multiple systems to the [Dispatcher] and loads/unloads all required resources.
@@ -63,6 +66,8 @@ where
 pub struct DispatcherData<'a> {
     /// Holds all steps that can be executed by [Schedule].
     steps: Vec<Step>,
+    /// Holds the steps executed even while the application is suspended, see [Lifecycle].
+    platform_steps: Vec<Step>,
     /// Temporarily holds systems which are later combined into [Executor].
     accumulator: Vec<Box<dyn ParallelRunnable + 'static>>,
     /// Bundles that can be later used for cleanup by calling [SystemBundle::unload].
@@ -120,6 +125,36 @@ impl<'a> DispatcherBuilder {
         self
     }
 
+    /// Adds a thread local system to the platform stage of the schedule. Platform systems are
+    /// executed on the main thread before all other systems, and keep running while the
+    /// application is suspended, see [Lifecycle].
+    pub fn add_platform_thread_local<T: ThreadLocalSystem<'a> + 'a>(
+        &mut self,
+        system: T,
+    ) -> &mut Self {
+        self.items
+            .push(DispatcherItem::PlatformThreadLocalSystem(system.build()));
+        self
+    }
+
+    /// Adds a thread local function to the platform stage of the schedule, see
+    /// [`DispatcherBuilder::add_platform_thread_local`].
+    pub fn add_platform_thread_local_fn<F: FnMut(&mut World, &mut Resources) + 'static>(
+        &mut self,
+        f: F,
+    ) -> &mut Self {
+        self.items.push(DispatcherItem::PlatformThreadLocalFn(
+            Box::new(f) as Box<dyn FnMut(&mut World, &mut Resources) + 'static>
+        ));
+        self
+    }
+
+    /// Moves the systems and bundles of `other` to the end of this builder, leaving it empty.
+    pub fn append(&mut self, other: &mut DispatcherBuilder) -> &mut Self {
+        self.items.append(&mut other.items);
+        self
+    }
+
     /// Adds [`SystemBundle`] to the dispatcher. System bundles allow inserting multiple systems
     /// and initialize any required entities or resources.
     pub fn add_bundle<T: SystemBundle + 'static>(&mut self, bundle: T) -> &mut Self {
@@ -150,6 +185,12 @@ impl<'a> DispatcherBuilder {
                     data.finalize_executor();
                     data.steps.push(Step::ThreadLocalSystem(s));
                 }
+                DispatcherItem::PlatformThreadLocalFn(f) => {
+                    data.platform_steps.push(Step::ThreadLocalFn(f));
+                }
+                DispatcherItem::PlatformThreadLocalSystem(s) => {
+                    data.platform_steps.push(Step::ThreadLocalSystem(s));
+                }
                 DispatcherItem::SystemBundle(mut bundle) => {
                     {
                         let mut builder = DispatcherBuilder::default();
@@ -175,6 +216,7 @@ impl<'a> DispatcherBuilder {
         self.flush().load(world, resources, &mut data)?;
 
         Ok(Dispatcher {
+            platform: Schedule::from(data.platform_steps),
             schedule: Schedule::from(data.steps),
             bundles: data.bundles,
         })
@@ -192,6 +234,10 @@ pub enum DispatcherItem {
     ThreadLocalFn(Box<dyn FnMut(&mut World, &mut Resources) + 'static>),
     /// A thread local system.
     ThreadLocalSystem(Box<dyn Runnable + 'static>),
+    /// A thread local function of the platform stage.
+    PlatformThreadLocalFn(Box<dyn FnMut(&mut World, &mut Resources) + 'static>),
+    /// A thread local system of the platform stage.
+    PlatformThreadLocalSystem(Box<dyn Runnable + 'static>),
     /// A system bundle
     SystemBundle(Box<dyn SystemBundle + 'static>),
 }
@@ -201,14 +247,25 @@ pub enum DispatcherItem {
 pub struct Dispatcher {
     // Used to execute unload on system bundles once dispatcher is disposed.
     bundles: Vec<Box<dyn SystemBundle>>,
+    platform: Schedule,
     schedule: Schedule,
 }
 
 impl Dispatcher {
     /// Executes systems according to the [Schedule].
+    ///
+    /// The platform systems are executed first. The other systems are skipped while the
+    /// [Lifecycle] resource is `Suspended`.
     pub fn execute(&mut self, world: &mut World, resources: &mut Resources) {
-        // TODO: use ArcThreadPool from resources to dispatch legion
-        self.schedule.execute(world, resources);
+        self.platform.execute(world, resources);
+
+        let suspended = resources
+            .get::<Lifecycle>()
+            .map_or(false, |lifecycle| lifecycle.is_suspended());
+        if !suspended {
+            // TODO: use ArcThreadPool from resources to dispatch legion
+            self.schedule.execute(world, resources);
+        }
     }
 
     /// Unloads any resources by calling [`SystemBundle::unload`] for stored system bundles and returns [`DispatcherBuilder`]
@@ -224,9 +281,10 @@ impl Dispatcher {
 
 #[cfg(test)]
 pub mod tests {
-    use super::*;
     use legion::SystemBuilder;
 
+    use super::*;
+
     struct MyResource(bool);
 
     struct MySystem;
@@ -303,4 +361,35 @@ pub mod tests {
 
         assert!(resources.get::<MyResource>().unwrap().0, true);
     }
+
+    #[test]
+    fn dispatcher_pauses_while_suspended() {
+        struct Polls(u32);
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        resources.insert(MyResource(false));
+        resources.insert(Polls(0));
+        resources.insert(Lifecycle::Suspended);
+
+        let mut dispatcher = DispatcherBuilder::default()
+            .add_system(MySystem)
+            .add_platform_thread_local_fn(|_, resources| {
+                resources.get_mut::<Polls>().unwrap().0 += 1;
+            })
+            .build(&mut world, &mut resources)
+            .unwrap();
+
+        dispatcher.execute(&mut world, &mut resources);
+
+        assert_eq!(resources.get::<Polls>().unwrap().0, 1);
+        assert!(!resources.get::<MyResource>().unwrap().0);
+
+        resources.insert(Lifecycle::Running);
+        dispatcher.execute(&mut world, &mut resources);
+
+        assert_eq!(resources.get::<Polls>().unwrap().0, 2);
+        assert!(resources.get::<MyResource>().unwrap().0);
+    }
 }
@@ -73,12 +73,10 @@
 //! [`thread::yield_now`]: https://doc.rust-lang.org/std/thread/fn.yield_now.html
 //! [`thread::sleep`]: https://doc.rust-lang.org/stable/std/thread/fn.sleep.html
 
-use std::{
-    thread::yield_now,
-    time::{Duration, Instant},
-};
+use std::{thread::yield_now, time::Duration};
 
 use derive_new::new;
+use instant::Instant;
 use serde::{Deserialize, Serialize};
 
 use crate::frame_limiter;
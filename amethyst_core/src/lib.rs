@@ -15,6 +15,10 @@
 
 #[cfg(all(target_os = "emscripten", not(no_threading)))]
 compile_error!("the cfg flag \"no_threading\" is required when building for emscripten");
+#[cfg(all(target_arch = "wasm32", not(no_threading)))]
+compile_error!("the cfg flag \"no_threading\" is required when building for wasm32");
+#[cfg(all(target_arch = "wasm32", feature = "parallel"))]
+compile_error!("the feature \"parallel\" is not supported when building for wasm32");
 
 /// Convenience alias for use in main functions that uses Amethyst.
 pub type Result<T> = std::result::Result<T, amethyst_error::Error>;
@@ -35,10 +39,13 @@ pub use self::{
     axis::{Axis2, Axis3},
     event::EventReader,
     hidden::{Hidden, HiddenPropagate},
+    lifecycle::Lifecycle,
     logger::{start_logger, LevelFilter as LogLevelFilter, Logger, LoggerConfig, StdoutLog},
     named::Named,
+    random::Random,
     shrev::EventChannel,
     timing::Stopwatch,
+    tracked::{Tracked, VersionTracker},
     transform::Transform,
 };
 
@@ -59,12 +66,21 @@ pub use ecs as legion;
 /// Dispatcher module.
 pub mod dispatcher;
 
+/// The 2D collision module.
+pub mod collision;
+
 /// The frame limiter module.
 pub mod frame_limiter;
 
 /// The geometry module.
 pub mod geometry;
 
+/// The random number module.
+pub mod random;
+
+/// The timers module.
+pub mod timers;
+
 /// The transformation module.
 pub mod transform;
 
@@ -77,6 +93,8 @@ pub mod logger;
 mod axis;
 mod event;
 mod hidden;
+mod lifecycle;
 mod named;
 pub mod system_ext;
 mod timing;
+mod tracked;
@@ -0,0 +1,32 @@
+/// Resource telling whether the application is running or was suspended by the platform.
+///
+/// Mobile platforms suspend applications moved to the background, Android also destroying their
+/// window surface meanwhile. While the application is suspended, the [`Dispatcher`] only runs the
+/// systems added with [`DispatcherBuilder::add_platform_thread_local`] or
+/// [`DispatcherBuilder::add_platform_thread_local_fn`], which release and re-acquire the resources
+/// depending on the platform.
+///
+/// [`Dispatcher`]: crate::dispatcher::Dispatcher
+/// [`DispatcherBuilder::add_platform_thread_local`]: crate::dispatcher::DispatcherBuilder::add_platform_thread_local
+/// [`DispatcherBuilder::add_platform_thread_local_fn`]: crate::dispatcher::DispatcherBuilder::add_platform_thread_local_fn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lifecycle {
+    /// The application is in the foreground.
+    Running,
+    /// The application is in the background, gameplay systems and rendering are paused.
+    Suspended,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle::Running
+    }
+}
+
+impl Lifecycle {
+    /// Whether the application is suspended.
+    #[must_use]
+    pub fn is_suspended(self) -> bool {
+        self == Lifecycle::Suspended
+    }
+}
@@ -0,0 +1,210 @@
+//! Random number generation with independently seeded named streams.
+//!
+//! The [`Random`] resource hands out a separate generator per stream name, so drawing numbers for
+//! visual effects doesn't change the numbers gameplay code gets. Each stream is seeded from the
+//! master seed and its name only, making the sequence of a stream independent of which other
+//! streams are used and in which order they were created.
+//!
+//! ```
+//! use amethyst_core::random::{rand::Rng, Random};
+//!
+//! let mut random = Random::with_seed(42);
+//! let damage = random.gameplay().gen_range(10..20);
+//! let sparks = random.vfx().gen_range(0..100);
+//!
+//! let mut replay = Random::with_seed(42);
+//! assert_eq!(replay.gameplay().gen_range(10..20), damage);
+//! ```
+//!
+//! `Random` implements `Serialize` and `Deserialize`, storing it in a save game restores the state
+//! of every stream and whether it runs in deterministic mode.
+
+use std::collections::BTreeMap;
+
+pub use rand;
+use rand::{RngCore, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+/// Resource holding the random number streams of the application.
+///
+/// In deterministic mode, created with [`Random::with_seed`], every run produces the same
+/// numbers. Otherwise the master seed is picked from system entropy, and can be read with
+/// [`Random::seed`] to reproduce the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Random {
+    seed: u64,
+    deterministic: bool,
+    streams: BTreeMap<String, RandomStream>,
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl Random {
+    /// Name of the stream used by gameplay logic.
+    pub const GAMEPLAY: &'static str = "gameplay";
+    /// Name of the stream used by visual effects.
+    pub const VFX: &'static str = "vfx";
+    /// Name of the stream used by AI.
+    pub const AI: &'static str = "ai";
+
+    /// Creates streams in deterministic mode, seeded from `seed`.
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            deterministic: true,
+            streams: BTreeMap::new(),
+        }
+    }
+
+    /// Creates streams seeded from system entropy.
+    #[must_use]
+    pub fn from_entropy() -> Self {
+        Self {
+            seed: rand::random(),
+            deterministic: false,
+            streams: BTreeMap::new(),
+        }
+    }
+
+    /// Master seed all streams are derived from.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Whether the master seed was given explicitly instead of picked from system entropy.
+    #[must_use]
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Restarts all streams from a new master seed and switches to deterministic mode.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::with_seed(seed);
+    }
+
+    /// Returns the stream with given name, creating it on first use.
+    pub fn stream(&mut self, name: &str) -> &mut RandomStream {
+        let seed = self.seed;
+        self.streams
+            .entry(name.to_owned())
+            .or_insert_with(|| RandomStream::new(seed, name))
+    }
+
+    /// Restarts the stream with given name from its initial state.
+    pub fn reset_stream(&mut self, name: &str) {
+        self.streams.remove(name);
+    }
+
+    /// The [`Random::GAMEPLAY`] stream.
+    pub fn gameplay(&mut self) -> &mut RandomStream {
+        self.stream(Self::GAMEPLAY)
+    }
+
+    /// The [`Random::VFX`] stream.
+    pub fn vfx(&mut self) -> &mut RandomStream {
+        self.stream(Self::VFX)
+    }
+
+    /// The [`Random::AI`] stream.
+    pub fn ai(&mut self) -> &mut RandomStream {
+        self.stream(Self::AI)
+    }
+}
+
+/// Random number generator of a single named stream.
+///
+/// Implements [`RngCore`], use it through the [`rand::Rng`] trait.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RandomStream(Pcg64Mcg);
+
+impl RandomStream {
+    fn new(seed: u64, name: &str) -> Self {
+        Self(Pcg64Mcg::seed_from_u64(seed ^ fnv1a(name)))
+    }
+}
+
+impl RngCore for RandomStream {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+/// Hash of a stream name that is stable across platforms and releases, unlike `std` hashers.
+fn fnv1a(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn draw(stream: &mut RandomStream) -> Vec<u32> {
+        (0..8).map(|_| stream.gen()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = Random::with_seed(7);
+        let mut b = Random::with_seed(7);
+
+        assert_eq!(draw(a.gameplay()), draw(b.gameplay()));
+        assert_ne!(draw(a.gameplay()), draw(Random::with_seed(8).gameplay()));
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut quiet = Random::with_seed(7);
+        let mut noisy = Random::with_seed(7);
+        draw(noisy.vfx());
+        draw(noisy.ai());
+
+        assert_eq!(draw(quiet.gameplay()), draw(noisy.gameplay()));
+        assert_ne!(draw(quiet.vfx()), draw(quiet.gameplay()));
+    }
+
+    #[test]
+    fn reset_stream_restarts_sequence() {
+        let mut random = Random::with_seed(7);
+        let first = draw(random.ai());
+        random.reset_stream(Random::AI);
+
+        assert_eq!(draw(random.ai()), first);
+    }
+
+    #[test]
+    fn serialization_restores_streams() {
+        let mut random = Random::from_entropy();
+        draw(random.gameplay());
+        let saved = ron::ser::to_string(&random).unwrap();
+        let mut loaded: Random = ron::de::from_str(&saved).unwrap();
+
+        assert!(!loaded.is_deterministic());
+        assert_eq!(loaded.seed(), random.seed());
+        assert_eq!(draw(loaded.gameplay()), draw(random.gameplay()));
+        assert_eq!(draw(loaded.vfx()), draw(random.vfx()));
+    }
+}
@@ -0,0 +1,29 @@
+//! ECS timer bundle
+
+use amethyst_error::Error;
+
+use crate::{
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::EventChannel,
+    timers::{TimerChannels, TimerEvent, TimerSystem},
+};
+
+/// Adds the [`TimerSystem`], the [`TimerChannels`] resource and the `EventChannel<TimerEvent>`
+/// it writes to.
+#[derive(Debug, Default)]
+pub struct TimerBundle;
+
+impl SystemBundle for TimerBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_default::<TimerChannels>();
+        resources.get_or_insert_with(EventChannel::<TimerEvent>::new);
+        builder.add_system(TimerSystem);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Named groups of timers sharing pause state and time scale.
+
+use std::{collections::HashMap, time::Duration};
+
+/// Pause state and time scale of a group of timers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimerChannel {
+    /// Timers of a paused channel don't advance.
+    pub paused: bool,
+    /// Multiplier applied to the delta time of the channel's timers.
+    pub time_scale: f32,
+}
+
+impl Default for TimerChannel {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            time_scale: 1.0,
+        }
+    }
+}
+
+impl TimerChannel {
+    /// Time scale the channel's timers are advanced with, `0.0` when paused.
+    ///
+    /// Negative, infinite and NaN time scales are treated as `0.0`.
+    #[must_use]
+    pub fn effective_scale(&self) -> f32 {
+        if self.paused || !self.time_scale.is_finite() {
+            0.0
+        } else {
+            self.time_scale.max(0.0)
+        }
+    }
+
+    /// Scales `delta` by the [`effective_scale`](TimerChannel::effective_scale), saturating
+    /// instead of overflowing.
+    #[must_use]
+    pub fn scale(&self, delta: Duration) -> Duration {
+        let secs = delta.as_secs_f64() * f64::from(self.effective_scale());
+        if secs < Duration::MAX.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            Duration::MAX
+        }
+    }
+}
+
+/// Resource holding the settings of every timer channel.
+///
+/// Timers and cooldowns without a channel use the default channel. Channels are created on first
+/// use, e.g. pausing `"gameplay"` while a menu is open leaves `"ui"` timers running:
+///
+/// ```
+/// use amethyst_core::timers::TimerChannels;
+///
+/// let mut channels = TimerChannels::default();
+/// channels.pause("gameplay");
+/// assert!(channels.is_paused(Some("gameplay")));
+/// assert!(!channels.is_paused(Some("ui")));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TimerChannels {
+    default: TimerChannel,
+    channels: HashMap<&'static str, TimerChannel>,
+}
+
+impl TimerChannels {
+    /// Settings of given channel, `None` being the default channel.
+    #[must_use]
+    pub fn get(&self, channel: Option<&'static str>) -> TimerChannel {
+        match channel {
+            Some(name) => self.channels.get(name).copied().unwrap_or_default(),
+            None => self.default,
+        }
+    }
+
+    /// Mutable settings of given channel, `None` being the default channel.
+    pub fn get_mut(&mut self, channel: Option<&'static str>) -> &mut TimerChannel {
+        match channel {
+            Some(name) => self.channels.entry(name).or_default(),
+            None => &mut self.default,
+        }
+    }
+
+    /// Pauses all timers of the named channel.
+    pub fn pause(&mut self, channel: &'static str) {
+        self.get_mut(Some(channel)).paused = true;
+    }
+
+    /// Resumes all timers of the named channel.
+    pub fn resume(&mut self, channel: &'static str) {
+        self.get_mut(Some(channel)).paused = false;
+    }
+
+    /// Checks if given channel is paused, `None` being the default channel.
+    #[must_use]
+    pub fn is_paused(&self, channel: Option<&'static str>) -> bool {
+        self.get(channel).paused
+    }
+
+    /// Sets the time scale of the named channel.
+    ///
+    /// Infinite and NaN time scales are rejected, leaving the channel unchanged.
+    pub fn set_time_scale(&mut self, channel: &'static str, time_scale: f32) {
+        if time_scale.is_finite() {
+            self.get_mut(Some(channel)).time_scale = time_scale;
+        } else {
+            log::warn!(
+                "Ignoring time scale {} of timer channel {:?}",
+                time_scale,
+                channel
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_time_scales_are_rejected() {
+        let mut channels = TimerChannels::default();
+        channels.set_time_scale("fx", 2.0);
+        channels.set_time_scale("fx", f32::INFINITY);
+        channels.set_time_scale("fx", f32::NAN);
+        assert_eq!(channels.get(Some("fx")).time_scale, 2.0);
+
+        let channel = TimerChannel {
+            paused: false,
+            time_scale: f32::NAN,
+        };
+        assert_eq!(channel.scale(Duration::from_secs(1)), Duration::default());
+    }
+
+    #[test]
+    fn huge_time_scales_saturate() {
+        let channel = TimerChannel {
+            paused: false,
+            time_scale: f32::MAX,
+        };
+        assert_eq!(channel.scale(Duration::from_secs(1)), Duration::MAX);
+    }
+}
@@ -0,0 +1,104 @@
+//! Cooldown component.
+
+use std::time::Duration;
+
+/// Component limiting how often an action can be performed, e.g. firing a weapon.
+///
+/// A cooldown starts ready. [`Cooldown::trigger`] starts it if it's ready, and the
+/// [`TimerSystem`](super::TimerSystem) emits a
+/// [`TimerEvent::CooldownReady`](super::TimerEvent::CooldownReady) once it elapsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cooldown {
+    duration: Duration,
+    remaining: Duration,
+    channel: Option<&'static str>,
+}
+
+impl Cooldown {
+    /// Creates a ready cooldown of given duration.
+    #[must_use]
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            remaining: Duration::default(),
+            channel: None,
+        }
+    }
+
+    /// Advances the cooldown with the named channel of [`TimerChannels`](super::TimerChannels).
+    #[must_use]
+    pub fn with_channel(mut self, channel: &'static str) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Channel the cooldown belongs to, `None` being the default channel.
+    #[must_use]
+    pub fn channel(&self) -> Option<&'static str> {
+        self.channel
+    }
+
+    /// Duration the cooldown lasts once triggered.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Changes the duration used by the next triggers.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    /// Time left until the cooldown is ready.
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// Checks if the action can be performed.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.remaining == Duration::default()
+    }
+
+    /// Starts the cooldown if it's ready, returning whether the action can be performed.
+    pub fn trigger(&mut self) -> bool {
+        if self.is_ready() {
+            self.remaining = self.duration;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Makes the cooldown ready immediately.
+    pub fn reset(&mut self) {
+        self.remaining = Duration::default();
+    }
+
+    /// Advances the cooldown by `delta`, returning `true` if it became ready.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        if self.is_ready() {
+            return false;
+        }
+        self.remaining = self.remaining.checked_sub(delta).unwrap_or_default();
+        self.is_ready()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_only_when_ready() {
+        let mut cooldown = Cooldown::new(Duration::from_millis(100));
+
+        assert!(cooldown.trigger());
+        assert!(!cooldown.trigger());
+        assert!(!cooldown.tick(Duration::from_millis(60)));
+        assert!(cooldown.tick(Duration::from_millis(60)));
+        assert!(!cooldown.tick(Duration::from_millis(60)));
+        assert!(cooldown.trigger());
+    }
+}
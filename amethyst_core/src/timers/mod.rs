@@ -0,0 +1,31 @@
+//! Frame-rate independent timers and cooldowns.
+//!
+//! Attach a [`Timer`] or a [`Cooldown`] to an entity instead of counting down `f32` fields by
+//! hand. Both are advanced by the [`TimerSystem`] using the scaled delta time of the `Time`
+//! resource, and can be grouped into named channels which are paused or slowed down together
+//! through the [`TimerChannels`] resource. Expiries are reported as [`TimerEvent`]s in the
+//! `EventChannel<TimerEvent>` resource added by the [`TimerBundle`].
+
+pub use self::{
+    bundle::TimerBundle,
+    channels::{TimerChannel, TimerChannels},
+    cooldown::Cooldown,
+    timer::{Timer, TimerMode},
+    timer_system::TimerSystem,
+};
+use crate::ecs::Entity;
+
+pub mod bundle;
+pub mod channels;
+pub mod cooldown;
+pub mod timer;
+pub mod timer_system;
+
+/// Event emitted by the [`TimerSystem`] when a timer or cooldown expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimerEvent {
+    /// The [`Timer`] of the entity finished. Repeating timers emit this once per completed cycle.
+    Finished(Entity),
+    /// The [`Cooldown`] of the entity elapsed and can be triggered again.
+    CooldownReady(Entity),
+}
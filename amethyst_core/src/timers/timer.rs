@@ -0,0 +1,188 @@
+//! Countdown timer component.
+
+use std::{convert::TryFrom, time::Duration};
+
+/// Whether a [`Timer`] stops or restarts when it finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerMode {
+    /// The timer stops once finished.
+    Once,
+    /// The timer restarts when finished, carrying over the excess time.
+    Repeating,
+}
+
+/// Component counting down a duration, advanced by the [`TimerSystem`](super::TimerSystem).
+///
+/// A [`TimerEvent::Finished`](super::TimerEvent::Finished) is emitted every time it finishes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    paused: bool,
+    channel: Option<&'static str>,
+}
+
+impl Timer {
+    /// Creates a timer finishing once after `duration`.
+    #[must_use]
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::default(),
+            mode: TimerMode::Once,
+            paused: false,
+            channel: None,
+        }
+    }
+
+    /// Creates a timer finishing every `duration`.
+    #[must_use]
+    pub fn repeating(duration: Duration) -> Self {
+        Self {
+            mode: TimerMode::Repeating,
+            ..Self::new(duration)
+        }
+    }
+
+    /// Advances the timer with the named channel of [`TimerChannels`](super::TimerChannels).
+    #[must_use]
+    pub fn with_channel(mut self, channel: &'static str) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Channel the timer belongs to, `None` being the default channel.
+    #[must_use]
+    pub fn channel(&self) -> Option<&'static str> {
+        self.channel
+    }
+
+    /// Duration of a single cycle of the timer.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Time elapsed in the current cycle.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Time left until the timer finishes.
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        self.duration.checked_sub(self.elapsed).unwrap_or_default()
+    }
+
+    /// Elapsed part of the current cycle, from `0.0` to `1.0`.
+    #[must_use]
+    pub fn fraction(&self) -> f32 {
+        if self.duration == Duration::default() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+        }
+    }
+
+    /// Mode of the timer.
+    #[must_use]
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    /// Checks if a non repeating timer has finished.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.mode == TimerMode::Once && self.elapsed >= self.duration
+    }
+
+    /// Stops advancing the timer until resumed.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes advancing a paused timer.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Checks if the timer itself is paused, regardless of its channel.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Restarts the timer from zero.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::default();
+    }
+
+    /// Advances the timer by `delta`, returning how many times it finished.
+    ///
+    /// Paused and finished non repeating timers are not advanced.
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        if self.paused || self.is_finished() {
+            return 0;
+        }
+
+        self.elapsed = self.elapsed.saturating_add(delta);
+        if self.elapsed < self.duration {
+            return 0;
+        }
+
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                1
+            }
+            TimerMode::Repeating if self.duration == Duration::default() => {
+                self.elapsed = Duration::default();
+                1
+            }
+            TimerMode::Repeating => {
+                let (elapsed, duration) = (self.elapsed.as_nanos(), self.duration.as_nanos());
+                self.elapsed = Duration::from_nanos(u64::try_from(elapsed % duration).unwrap());
+                u32::try_from(elapsed / duration).unwrap_or(u32::MAX)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn once_finishes_once() {
+        let mut timer = Timer::new(Duration::from_millis(100));
+
+        assert_eq!(timer.tick(Duration::from_millis(60)), 0);
+        assert_eq!(timer.remaining(), Duration::from_millis(40));
+        assert_eq!(timer.tick(Duration::from_millis(60)), 1);
+        assert!(timer.is_finished());
+        assert_eq!(timer.tick(Duration::from_millis(60)), 0);
+
+        timer.reset();
+        assert!(!timer.is_finished());
+    }
+
+    #[test]
+    fn repeating_carries_excess_time() {
+        let mut timer = Timer::repeating(Duration::from_millis(100));
+
+        assert_eq!(timer.tick(Duration::from_millis(250)), 2);
+        assert_eq!(timer.elapsed(), Duration::from_millis(50));
+        assert!(!timer.is_finished());
+    }
+
+    #[test]
+    fn paused_does_not_advance() {
+        let mut timer = Timer::new(Duration::from_millis(100));
+        timer.pause();
+
+        assert_eq!(timer.tick(Duration::from_millis(200)), 0);
+        assert_eq!(timer.elapsed(), Duration::default());
+    }
+}
@@ -0,0 +1,141 @@
+//! System advancing timers and cooldowns.
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use super::{Cooldown, Timer, TimerChannels, TimerEvent};
+use crate::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::EventChannel,
+    Time,
+};
+
+/// System advancing every [`Timer`] and [`Cooldown`] by the delta time of the `Time` resource,
+/// scaled by the settings of their channel in [`TimerChannels`].
+///
+/// Writes a [`TimerEvent`] for each timer that finished or cooldown that became ready.
+#[derive(Debug, Default)]
+pub struct TimerSystem;
+
+impl System for TimerSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("TimerSystem")
+                .read_resource::<Time>()
+                .read_resource::<TimerChannels>()
+                .write_resource::<EventChannel<TimerEvent>>()
+                .with_query(<(Entity, &mut Timer)>::query())
+                .with_query(<(Entity, &mut Cooldown)>::query())
+                .build(
+                    move |_commands, world, (time, channels, events), (timers, cooldowns)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("timer_system");
+
+                        let delta = time.delta_time();
+
+                        for (entity, timer) in timers.iter_mut(world) {
+                            let delta = channels.get(timer.channel()).scale(delta);
+                            let finished = timer.tick(delta);
+                            events.iter_write((0..finished).map(|_| TimerEvent::Finished(*entity)));
+                        }
+
+                        for (entity, cooldown) in cooldowns.iter_mut(world) {
+                            let delta = channels.get(cooldown.channel()).scale(delta);
+                            if cooldown.tick(delta) {
+                                events.single_write(TimerEvent::CooldownReady(*entity));
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{ecs::*, shrev::ReaderId, timers::TimerBundle};
+
+    fn timer_world() -> (Resources, World, Dispatcher, ReaderId<TimerEvent>) {
+        let mut resources = Resources::default();
+        let mut world = World::default();
+
+        let mut time = Time::default();
+        time.set_delta_time(Duration::from_millis(100));
+        resources.insert(time);
+
+        let dispatcher = DispatcherBuilder::default()
+            .add_bundle(TimerBundle)
+            .build(&mut world, &mut resources)
+            .unwrap();
+        let reader = resources
+            .get_mut::<EventChannel<TimerEvent>>()
+            .unwrap()
+            .register_reader();
+
+        (resources, world, dispatcher, reader)
+    }
+
+    fn read_events(resources: &Resources, reader: &mut ReaderId<TimerEvent>) -> Vec<TimerEvent> {
+        resources
+            .get::<EventChannel<TimerEvent>>()
+            .unwrap()
+            .read(reader)
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn emits_expiry_events() {
+        let (mut resources, mut world, mut dispatcher, mut reader) = timer_world();
+
+        let timer = world.push((Timer::new(Duration::from_millis(150)),));
+        let mut cooldown = Cooldown::new(Duration::from_millis(50));
+        cooldown.trigger();
+        let cooldown = world.push((cooldown,));
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert_eq!(
+            read_events(&resources, &mut reader),
+            vec![TimerEvent::CooldownReady(cooldown)]
+        );
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert_eq!(
+            read_events(&resources, &mut reader),
+            vec![TimerEvent::Finished(timer)]
+        );
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert!(read_events(&resources, &mut reader).is_empty());
+    }
+
+    #[test]
+    fn channels_pause_and_scale() {
+        let (mut resources, mut world, mut dispatcher, mut reader) = timer_world();
+
+        let paused = world.push((Timer::new(Duration::from_millis(100)).with_channel("paused"),));
+        let fast = world.push((Timer::repeating(Duration::from_millis(100)).with_channel("fast"),));
+        {
+            let mut channels = resources.get_mut::<TimerChannels>().unwrap();
+            channels.pause("paused");
+            channels.set_time_scale("fast", 2.0);
+        }
+
+        dispatcher.execute(&mut world, &mut resources);
+        assert_eq!(
+            read_events(&resources, &mut reader),
+            vec![TimerEvent::Finished(fast), TimerEvent::Finished(fast)]
+        );
+
+        resources
+            .get_mut::<TimerChannels>()
+            .unwrap()
+            .resume("paused");
+        dispatcher.execute(&mut world, &mut resources);
+        let events = read_events(&resources, &mut reader);
+        assert!(events.contains(&TimerEvent::Finished(paused)));
+    }
+}
@@ -0,0 +1,193 @@
+use std::ops::{Deref, DerefMut};
+
+/// A resource wrapper that counts mutable accesses to the inner value.
+///
+/// Systems that derive expensive state from configuration-like resources (ambient color,
+/// display settings, key bindings, ...) can store the last [`version`](Tracked::version) they
+/// observed and skip their work while it stays the same, instead of comparing the value itself
+/// every frame.
+///
+/// Every call to [`DerefMut::deref_mut`] or [`Tracked::get_mut`] bumps the version, whether or
+/// not the value was actually changed. Use [`Tracked::set_if_neq`] when the new value may be equal
+/// to the old one and spurious bumps should be avoided.
+///
+/// # Examples
+///
+/// ```
+/// use amethyst::core::{Tracked, VersionTracker};
+///
+/// let mut ambient = Tracked::new([0.1f32, 0.1, 0.1]);
+/// let mut tracker = VersionTracker::default();
+///
+/// assert!(tracker.update(&ambient));
+/// assert!(!tracker.update(&ambient));
+///
+/// ambient[0] = 0.5;
+/// assert!(tracker.update(&ambient));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    value: T,
+    version: u64,
+}
+
+impl<T> Tracked<T> {
+    /// Wraps `value`, starting at version `1`.
+    pub fn new(value: T) -> Self {
+        Self { value, version: 1 }
+    }
+
+    /// Returns the current version. It is incremented on every mutable access.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns `true` if the value was mutably accessed after `version` was observed.
+    #[must_use]
+    pub fn changed_since(&self, version: u64) -> bool {
+        self.version != version
+    }
+
+    /// Mutably borrows the inner value and bumps the version.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.version = self.version.wrapping_add(1);
+        &mut self.value
+    }
+
+    /// Mutably borrows the inner value without bumping the version.
+    ///
+    /// Use this for bookkeeping changes that readers don't need to react to.
+    pub fn get_mut_untracked(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Replaces the inner value, bumping the version.
+    pub fn set(&mut self, value: T) {
+        *self.get_mut() = value;
+    }
+
+    /// Unwraps the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: PartialEq> Tracked<T> {
+    /// Replaces the inner value only if it differs from the current one.
+    ///
+    /// Returns `true` if the value was replaced and the version bumped.
+    pub fn set_if_neq(&mut self, value: T) -> bool {
+        if self.value == value {
+            false
+        } else {
+            self.set(value);
+            true
+        }
+    }
+}
+
+impl<T: Default> Default for Tracked<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for Tracked<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+}
+
+/// Remembers the last observed version of a [`Tracked`] resource.
+///
+/// Versions start at `1`, so a freshly created tracker always reports the first
+/// [`update`](VersionTracker::update) as a change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VersionTracker {
+    last_seen: u64,
+}
+
+impl VersionTracker {
+    /// Returns `true` if `tracked` changed since the last call, and records its current version.
+    pub fn update<T>(&mut self, tracked: &Tracked<T>) -> bool {
+        let changed = tracked.changed_since(self.last_seen);
+        self.last_seen = tracked.version();
+        changed
+    }
+
+    /// Forgets the last observed version, so the next `update` reports a change.
+    pub fn reset(&mut self) {
+        self.last_seen = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutable_access_bumps_version() {
+        let mut tracked = Tracked::new(5);
+        let version = tracked.version();
+
+        let _ = *tracked;
+        assert!(!tracked.changed_since(version));
+
+        *tracked += 1;
+        assert!(tracked.changed_since(version));
+        assert_eq!(*tracked, 6);
+    }
+
+    #[test]
+    fn set_if_neq_skips_equal_values() {
+        let mut tracked = Tracked::new("a");
+        let version = tracked.version();
+
+        assert!(!tracked.set_if_neq("a"));
+        assert_eq!(tracked.version(), version);
+
+        assert!(tracked.set_if_neq("b"));
+        assert_ne!(tracked.version(), version);
+    }
+
+    #[test]
+    fn tracker_reports_each_change_once() {
+        let mut tracked = Tracked::new(0);
+        let mut tracker = VersionTracker::default();
+
+        assert!(tracker.update(&tracked));
+        assert!(!tracker.update(&tracked));
+
+        tracked.get_mut_untracked();
+        assert!(!tracker.update(&tracked));
+
+        tracked.set(3);
+        assert!(tracker.update(&tracked));
+
+        tracker.reset();
+        assert!(tracker.update(&tracked));
+    }
+    #[test]
+    fn default_is_seen_by_new_tracker() {
+        let tracked = Tracked::<u32>::default();
+        let mut tracker = VersionTracker::default();
+
+        assert_eq!(tracked.version(), 1);
+        assert!(tracker.update(&tracked));
+    }
+}
@@ -4,7 +4,9 @@ use amethyst_error::Error;
 
 use crate::{
     ecs::{DispatcherBuilder, Resources, SystemBundle, World},
-    transform::{MissingPreviousParentSystem, ParentUpdateSystem, TransformSystem},
+    transform::{
+        ConstraintSystem, MissingPreviousParentSystem, ParentUpdateSystem, TransformSystem,
+    },
 };
 
 /// Transform bundle
@@ -22,7 +24,8 @@ impl SystemBundle for TransformBundle {
         builder
             .add_system(MissingPreviousParentSystem)
             .add_system(ParentUpdateSystem)
-            .add_system(TransformSystem);
+            .add_system(TransformSystem)
+            .add_system(ConstraintSystem);
 
         Ok(())
     }
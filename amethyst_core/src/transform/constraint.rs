@@ -0,0 +1,214 @@
+//! Constraints applied to global transforms after the hierarchy has been resolved.
+
+use smallvec::SmallVec;
+
+use crate::{
+    ecs::Entity,
+    math::{Matrix3, Matrix4, Rotation3, UnitQuaternion, Vector3, U3},
+};
+
+/// A single relationship constraining the global transform of an entity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstraintKind {
+    /// Copy the global position of the target entity.
+    CopyTranslation(Entity),
+    /// Copy the global rotation of the target entity.
+    CopyRotation(Entity),
+    /// Rotate so that the forward axis (-Z) points towards the target entity.
+    LookAt {
+        /// Entity to look at.
+        target: Entity,
+        /// Up direction used to resolve the roll around the forward axis.
+        up: Vector3<f32>,
+    },
+    /// Clamp the global position into an axis-aligned box.
+    LimitTranslation {
+        /// Minimum coordinate on every axis.
+        min: Vector3<f32>,
+        /// Maximum coordinate on every axis.
+        max: Vector3<f32>,
+    },
+    /// Keep the global position within a distance range from the target entity.
+    LimitDistance {
+        /// Entity the distance is measured from.
+        target: Entity,
+        /// Minimum distance.
+        min: f32,
+        /// Maximum distance.
+        max: f32,
+    },
+}
+
+/// A constraint together with the weight it is blended in with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraint {
+    /// What the constraint does.
+    pub kind: ConstraintKind,
+    /// How much the constraint affects the result, from `0.0` (not at all) to `1.0` (fully).
+    pub influence: f32,
+}
+
+impl Constraint {
+    /// Creates a constraint with full influence.
+    #[must_use]
+    pub fn new(kind: ConstraintKind) -> Self {
+        Self {
+            kind,
+            influence: 1.0,
+        }
+    }
+
+    /// Sets the influence of this constraint. The value is clamped to `[0.0, 1.0]`.
+    #[must_use]
+    pub fn with_influence(mut self, influence: f32) -> Self {
+        self.influence = influence.max(0.0).min(1.0);
+        self
+    }
+
+    /// Returns the entity this constraint depends on, if any.
+    #[must_use]
+    pub fn target(&self) -> Option<Entity> {
+        match self.kind {
+            ConstraintKind::CopyTranslation(target)
+            | ConstraintKind::CopyRotation(target)
+            | ConstraintKind::LookAt { target, .. }
+            | ConstraintKind::LimitDistance { target, .. } => Some(target),
+            ConstraintKind::LimitTranslation { .. } => None,
+        }
+    }
+}
+
+/// Stack of constraints applied in order to the global transform of an entity by the
+/// [`ConstraintSystem`](super::ConstraintSystem).
+///
+/// Constraints operate in world space and read the global transforms of their targets as
+/// computed by the [`TransformSystem`](super::TransformSystem), without the constraints of the
+/// targets themselves. The local `Transform` is left untouched, while children of a constrained
+/// entity follow its constrained global transform.
+///
+/// # Examples
+///
+/// ```
+/// use amethyst::core::{
+///     ecs::*,
+///     math::Vector3,
+///     transform::{Constraint, ConstraintKind, Constraints, Transform},
+/// };
+///
+/// let mut world = World::default();
+/// let target = world.push((Transform::default(),));
+/// let turret = world.push((
+///     Transform::default(),
+///     Constraints::default()
+///         .with(Constraint::new(ConstraintKind::LookAt {
+///             target,
+///             up: Vector3::y(),
+///         }))
+///         .with(
+///             Constraint::new(ConstraintKind::LimitTranslation {
+///                 min: Vector3::new(-10.0, 0.0, -10.0),
+///                 max: Vector3::new(10.0, 0.0, 10.0),
+///             })
+///             .with_influence(0.5),
+///         ),
+/// ));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Constraints(pub SmallVec<[Constraint; 2]>);
+
+impl Constraints {
+    /// Appends a constraint to the stack.
+    #[must_use]
+    pub fn with(mut self, constraint: Constraint) -> Self {
+        self.0.push(constraint);
+        self
+    }
+
+    /// Appends a constraint to the stack.
+    pub fn push(&mut self, constraint: Constraint) {
+        self.0.push(constraint);
+    }
+}
+
+/// Decomposed global transform the constraints are evaluated on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Pose {
+    pub(crate) translation: Vector3<f32>,
+    pub(crate) rotation: UnitQuaternion<f32>,
+    pub(crate) scale: Vector3<f32>,
+}
+
+impl Pose {
+    pub(crate) fn from_matrix(matrix: &Matrix4<f32>) -> Self {
+        let translation = matrix.column(3).xyz();
+        let basis: Matrix3<f32> = matrix.fixed_slice::<U3, U3>(0, 0).clone_owned();
+        let scale = Vector3::new(
+            basis.column(0).norm(),
+            basis.column(1).norm(),
+            basis.column(2).norm(),
+        );
+        let mut rotation = basis;
+        for i in 0..3 {
+            if scale[i] > f32::EPSILON {
+                rotation.column_mut(i).unscale_mut(scale[i]);
+            }
+        }
+        Self {
+            translation,
+            rotation: UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(
+                rotation,
+            )),
+            scale,
+        }
+    }
+
+    pub(crate) fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::new_translation(&self.translation)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+
+    /// Applies `constraint`. `target` is the pose of the constraint target, if it has one.
+    pub(crate) fn apply(&mut self, constraint: &Constraint, target: Option<&Pose>) {
+        let influence = constraint.influence;
+        match (constraint.kind, target) {
+            (ConstraintKind::CopyTranslation(_), Some(target)) => {
+                self.translation = self.translation.lerp(&target.translation, influence);
+            }
+            (ConstraintKind::CopyRotation(_), Some(target)) => {
+                self.rotation = blend_rotation(&self.rotation, &target.rotation, influence);
+            }
+            (ConstraintKind::LookAt { up, .. }, Some(target)) => {
+                let direction = self.translation - target.translation;
+                if direction.norm_squared() > f32::EPSILON {
+                    let look = UnitQuaternion::face_towards(&direction, &up);
+                    self.rotation = blend_rotation(&self.rotation, &look, influence);
+                }
+            }
+            (ConstraintKind::LimitTranslation { min, max }, _) => {
+                let clamped = self.translation.sup(&min).inf(&max);
+                self.translation = self.translation.lerp(&clamped, influence);
+            }
+            (ConstraintKind::LimitDistance { min, max, .. }, Some(target)) => {
+                let offset = self.translation - target.translation;
+                let distance = offset.norm();
+                if distance > f32::EPSILON {
+                    let clamped = distance.max(min).min(max);
+                    let limited = target.translation + offset * (clamped / distance);
+                    self.translation = self.translation.lerp(&limited, influence);
+                }
+            }
+            // The target is missing or has no transform, nothing to constrain against.
+            _ => {}
+        }
+    }
+}
+
+fn blend_rotation(
+    from: &UnitQuaternion<f32>,
+    to: &UnitQuaternion<f32>,
+    influence: f32,
+) -> UnitQuaternion<f32> {
+    // Opposite rotations have no unique interpolation path, snap to the constrained one.
+    from.try_slerp(to, influence, f32::EPSILON).unwrap_or(*to)
+}
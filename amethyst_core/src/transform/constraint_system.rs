@@ -0,0 +1,310 @@
+//! System applying [`Constraints`] to global transforms.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use super::{
+    components::{Children, Parent, Transform},
+    constraint::{Constraints, Pose},
+};
+use crate::{
+    ecs::{Entity, EntityStore, IntoQuery, ParallelRunnable, SubWorld, System, SystemBuilder},
+    math::Matrix4,
+};
+
+/// System that applies [`Constraints`] to the global matrices computed by the
+/// [`TransformSystem`](super::TransformSystem).
+///
+/// Constraints are evaluated against the unconstrained global transform of the entity, and the
+/// constrained matrix is propagated to its descendants so children follow their constrained
+/// parent. Local transforms are left untouched.
+///
+/// Must run after the `TransformSystem`, which the [`TransformBundle`](super::TransformBundle)
+/// takes care of.
+#[derive(Debug)]
+pub struct ConstraintSystem;
+
+impl System for ConstraintSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("ConstraintSystem")
+                .with_query(<(Entity, &Constraints)>::query())
+                .read_component::<Parent>()
+                .read_component::<Children>()
+                .write_component::<Transform>()
+                .build(move |_commands, world, _resources, query| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("constraint_system");
+
+                    // Evaluate all constraints against the unconstrained poses of the targets, so
+                    // the result does not depend on iteration order.
+                    let mut targets = HashMap::new();
+                    let mut solved = Vec::new();
+                    for (entity, constraints) in query.iter(world) {
+                        for target in constraints.0.iter().filter_map(|c| c.target()) {
+                            targets.entry(target).or_insert(None);
+                        }
+                        solved.push((*entity, constraints.clone()));
+                    }
+
+                    for (target, pose) in &mut targets {
+                        *pose = unconstrained_matrix(world, *target)
+                            .map(|matrix| Pose::from_matrix(&matrix));
+                    }
+
+                    // Ancestors first, so constrained descendants start from the propagated
+                    // matrix of their constrained parents.
+                    solved.sort_by_cached_key(|(entity, _)| depth(world, *entity));
+
+                    for (entity, constraints) in solved {
+                        // The global matrix may still hold the result of the last frame when
+                        // the `TransformSystem` skipped this entity, so start from the local
+                        // transform instead.
+                        let mut pose = match unconstrained_matrix(world, entity) {
+                            Some(matrix) => Pose::from_matrix(&matrix),
+                            None => continue,
+                        };
+                        for constraint in &constraints.0 {
+                            let target = constraint
+                                .target()
+                                .and_then(|target| targets.get(&target))
+                                .and_then(Option::as_ref);
+                            pose.apply(constraint, target);
+                        }
+
+                        let global_matrix = pose.to_matrix();
+                        if let Some(transform) = world
+                            .entry_mut(entity)
+                            .ok()
+                            .and_then(|entry| entry.into_component_mut::<Transform>().ok())
+                        {
+                            transform.global_matrix = global_matrix;
+                        }
+                        propagate(world, entity, global_matrix);
+                    }
+                }),
+        )
+    }
+}
+
+/// Global matrix of `entity` from its local transform and the matrix of its parent.
+fn unconstrained_matrix(world: &SubWorld<'_>, entity: Entity) -> Option<Matrix4<f32>> {
+    world
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Transform>()
+                .ok()
+                .map(|t| t.parent_matrix * t.matrix())
+        })
+}
+
+fn parent_of(world: &SubWorld<'_>, entity: Entity) -> Option<Entity> {
+    world
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Parent>().ok().map(|parent| parent.0))
+}
+
+/// Number of ancestors of `entity`.
+fn depth(world: &SubWorld<'_>, entity: Entity) -> usize {
+    let mut depth = 0;
+    let mut current = entity;
+    while let Some(parent) = parent_of(world, current) {
+        if parent == entity {
+            break;
+        }
+        depth += 1;
+        current = parent;
+    }
+    depth
+}
+
+/// Recomputes the matrices of the descendants of `root` from its constrained global matrix.
+fn propagate(world: &mut SubWorld<'_>, root: Entity, root_matrix: Matrix4<f32>) {
+    let mut stack = vec![(root, root_matrix)];
+    while let Some((parent, parent_matrix)) = stack.pop() {
+        let children = world
+            .entry_ref(parent)
+            .ok()
+            .and_then(|entry| entry.get_component::<Children>().ok().map(|c| c.0.clone()))
+            .unwrap_or_default();
+
+        for child in children {
+            // `Children` lag behind `Parent` changes until `ParentUpdateSystem` catches up.
+            if child == root || parent_of(world, child) != Some(parent) {
+                continue;
+            }
+            if let Some(transform) = world
+                .entry_mut(child)
+                .ok()
+                .and_then(|entry| entry.into_component_mut::<Transform>().ok())
+            {
+                transform.parent_matrix = parent_matrix;
+                transform.global_matrix = parent_matrix * transform.matrix();
+                stack.push((child, transform.global_matrix));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use crate::{
+        ecs::*,
+        math::Vector3,
+        transform::{Constraint, ConstraintKind, Constraints, Parent, Transform, TransformBundle},
+    };
+
+    fn global_translation(world: &World, entity: Entity) -> Vector3<f32> {
+        world
+            .entry_ref(entity)
+            .unwrap()
+            .into_component::<Transform>()
+            .unwrap()
+            .global_matrix()
+            .column(3)
+            .xyz()
+    }
+
+    fn run(world: &mut World) {
+        run_frames(world, 1);
+    }
+
+    fn run_frames(world: &mut World, frames: usize) {
+        let mut resources = Resources::default();
+        let mut dispatcher = DispatcherBuilder::default()
+            .add_bundle(TransformBundle)
+            .build(world, &mut resources)
+            .unwrap();
+        for _ in 0..frames {
+            dispatcher.execute(world, &mut resources);
+        }
+    }
+
+    #[test]
+    fn copy_translation_with_influence() {
+        let mut world = World::default();
+
+        let mut target = Transform::default();
+        target.set_translation_xyz(10.0, 0.0, 0.0);
+        let target = world.push((target,));
+
+        let follower = world.push((
+            Transform::default(),
+            Constraints::default()
+                .with(Constraint::new(ConstraintKind::CopyTranslation(target)).with_influence(0.5)),
+        ));
+
+        run(&mut world);
+
+        assert_relative_eq!(
+            global_translation(&world, follower),
+            Vector3::new(5.0, 0.0, 0.0)
+        );
+        // The local transform is left untouched.
+        assert_relative_eq!(
+            *world
+                .entry_ref(follower)
+                .unwrap()
+                .into_component::<Transform>()
+                .unwrap()
+                .translation(),
+            Vector3::zeros()
+        );
+    }
+
+    #[test]
+    fn look_at_faces_target() {
+        let mut world = World::default();
+
+        let mut target = Transform::default();
+        target.set_translation_xyz(0.0, 0.0, -5.0);
+        let target = world.push((target,));
+
+        let mut turret = Transform::default();
+        turret.set_rotation_y_axis(1.0);
+        let turret = world.push((
+            turret,
+            Constraints::default().with(Constraint::new(ConstraintKind::LookAt {
+                target,
+                up: Vector3::y(),
+            })),
+        ));
+
+        run(&mut world);
+
+        let entry = world.entry_ref(turret).unwrap();
+        let global = entry.into_component::<Transform>().unwrap().global_matrix();
+        let forward = -global.column(2).xyz();
+        assert_relative_eq!(forward, Vector3::new(0.0, 0.0, -1.0), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn limits_are_applied_in_order() {
+        let mut world = World::default();
+
+        let mut anchor = Transform::default();
+        anchor.set_translation_xyz(0.0, 0.0, 0.0);
+        let anchor = world.push((anchor,));
+
+        let mut local = Transform::default();
+        local.set_translation_xyz(20.0, -3.0, 0.0);
+        let entity = world.push((
+            local,
+            Constraints::default()
+                .with(Constraint::new(ConstraintKind::LimitTranslation {
+                    min: Vector3::new(-10.0, 0.0, -10.0),
+                    max: Vector3::new(10.0, 10.0, 10.0),
+                }))
+                .with(Constraint::new(ConstraintKind::LimitDistance {
+                    target: anchor,
+                    min: 0.0,
+                    max: 5.0,
+                })),
+        ));
+
+        run(&mut world);
+
+        assert_relative_eq!(
+            global_translation(&world, entity),
+            Vector3::new(5.0, 0.0, 0.0),
+            epsilon = 1e-5
+        );
+    }
+    #[test]
+    fn children_follow_constrained_parent() {
+        let mut world = World::default();
+
+        let mut target = Transform::default();
+        target.set_translation_xyz(10.0, 0.0, 0.0);
+        let target = world.push((target,));
+
+        let parent = world.push((
+            Transform::default(),
+            Constraints::default()
+                .with(Constraint::new(ConstraintKind::CopyTranslation(target)).with_influence(0.5)),
+        ));
+
+        let mut local = Transform::default();
+        local.set_translation_xyz(0.0, 1.0, 0.0);
+        let child = world.push((local, Parent(parent)));
+
+        // Constraints must not compound over frames where the hierarchy is unchanged.
+        run_frames(&mut world, 3);
+
+        assert_relative_eq!(
+            global_translation(&world, parent),
+            Vector3::new(5.0, 0.0, 0.0)
+        );
+        assert_relative_eq!(
+            global_translation(&world, child),
+            Vector3::new(5.0, 1.0, 0.0)
+        );
+    }
+}
@@ -1,13 +1,19 @@
 //! `amethyst` transform ecs module
 
 pub use self::{
-    bundle::TransformBundle, components::*,
+    bundle::TransformBundle,
+    components::*,
+    constraint::{Constraint, ConstraintKind, Constraints},
+    constraint_system::ConstraintSystem,
     missing_previous_parent_system::MissingPreviousParentSystem,
-    parent_update_system::ParentUpdateSystem, transform_system::TransformSystem,
+    parent_update_system::ParentUpdateSystem,
+    transform_system::TransformSystem,
 };
 
 pub mod bundle;
 pub mod components;
+pub mod constraint;
+pub mod constraint_system;
 pub mod missing_previous_parent_system;
 pub mod parent_update_system;
 pub mod transform_system;
@@ -1,12 +1,26 @@
 //! System that updates global transform matrices based on hierarchy relations.
 
-use super::components::{Parent, Transform};
-use crate::ecs::{
-    component, maybe_changed, Entity, EntityStore, IntoQuery, ParallelRunnable, System,
-    SystemBuilder,
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use super::components::{Children, Parent, Transform};
+use crate::{
+    ecs::{
+        maybe_changed, Entity, EntityStore, IntoQuery, ParallelRunnable, SubWorld, System,
+        SystemBuilder,
+    },
+    math::Matrix4,
 };
 
+/// Computed `(entity, parent_matrix, global_matrix)` of a single transform.
+type Update = (Entity, Matrix4<f32>, Matrix4<f32>);
+
 /// System that updates global transform matrices based on hierarchy relations.
+///
+/// Only entities whose `Transform` or `Parent` may have changed are considered dirty. The
+/// topmost dirty entities of the hierarchy are collected and their subtrees are updated in
+/// parallel, while subtrees without any dirty entity are skipped entirely.
 #[derive(Debug)]
 pub struct TransformSystem;
 
@@ -14,80 +28,173 @@ impl System for TransformSystem {
     fn build(self) -> Box<dyn ParallelRunnable> {
         Box::new(
             SystemBuilder::new("TransformSystem")
-                // Entities at the hierarchy root (no parent component)
-                .with_query(
-                    <(Entity, &mut Transform)>::query()
-                        .filter(maybe_changed::<Transform>() & !component::<Parent>()),
-                )
-                // Entities that are children of some entity
+                // Entities with a changed local transform or a changed parent
                 .with_query(
-                    <(Entity, &mut Transform)>::query()
-                        .filter(maybe_changed::<Transform>() & component::<Parent>()),
+                    <(Entity, &Transform)>::query()
+                        .filter(maybe_changed::<Transform>() | maybe_changed::<Parent>()),
                 )
-                .with_query(<(Entity, &Parent)>::query())
+                .read_component::<Parent>()
+                .read_component::<Children>()
                 .write_component::<Transform>()
-                .build(
-                    move |_commands,
-                          world,
-                          _resource,
-                          (query_root, query_children, query_parent)| {
-                        // Update global transform for entities that are root of the hierarchy
-                        for (entity, transform) in query_root.iter_mut(world) {
-                            transform.global_matrix = transform.matrix();
-                            debug_assert!(
-                                transform.is_finite(),
-                                "Entity {:?} had a non-finite `Transform` {:?}",
-                                entity,
-                                transform
-                            );
+                .build(move |_commands, world, _resource, query_dirty| {
+                    let mut dirty = query_dirty
+                        .iter(world)
+                        .map(|(entity, _)| *entity)
+                        .collect::<HashSet<_>>();
+
+                    // `Children` of freshly parented entities are only added once commands are
+                    // flushed, so dirty entities missed by the traversal of their ancestors are
+                    // picked up as roots in the next round.
+                    while !dirty.is_empty() {
+                        let roots = dirty
+                            .iter()
+                            .copied()
+                            .filter(|entity| !has_dirty_ancestor(world, *entity, &dirty))
+                            .collect::<Vec<_>>();
+                        if roots.is_empty() {
+                            log::error!("Hierarchy cycle between entities {:?}", dirty);
+                            break;
                         }
-
-                        // Update parent transforms for entities in the hierarchy
-                        // Ideally we would check if the linked parent entity exists before
-                        // accessing it's Transform component, but as the parent_update_system panics
-                        // in both cases it is ok to just unwrap/expect here.
-                        let (left, mut right) = world.split_for_query(query_parent);
-                        for (entity, parent) in query_parent.iter(&left) {
-                            let parent_has_transform = right
-                                .entry_ref(parent.0)
-                                .expect("Invalid entity in Parent component")
-                                .into_component::<Transform>()
-                                .is_ok();
-
-                            if parent_has_transform {
-                                let parent_matrix = right
-                                    .entry_ref(parent.0)
-                                    .expect("Invalid entity in Parent component")
-                                    .into_component::<Transform>()
-                                    .unwrap()
-                                    .global_matrix;
-
-                                if let Some(transform) = right
-                                    .entry_mut(*entity)
-                                    .ok()
-                                    .and_then(|entry| entry.into_component_mut::<Transform>().ok())
-                                {
-                                    transform.parent_matrix = parent_matrix;
-                                }
-                            }
+                        for root in &roots {
+                            dirty.remove(root);
                         }
 
-                        // Update global transform for entities that are children of some entity
-                        for (entity, transform) in query_children.iter_mut(world) {
-                            transform.global_matrix = transform.parent_matrix * transform.matrix();
-                            debug_assert!(
-                                transform.is_finite(),
-                                "Entity {:?} had a non-finite `Transform` {:?}",
-                                entity,
-                                transform
-                            );
+                        // Subtrees of distinct roots are disjoint, so they can be computed
+                        // independently.
+                        let subtrees = {
+                            let world: &SubWorld<'_> = world;
+                            roots
+                                .par_iter()
+                                .map(|root| compute_subtree(world, *root))
+                                .collect::<Vec<_>>()
+                        };
+
+                        for (entity, parent_matrix, global_matrix) in subtrees.into_iter().flatten()
+                        {
+                            dirty.remove(&entity);
+                            write_transform(world, entity, parent_matrix, global_matrix);
                         }
-                    },
-                ),
+                    }
+                }),
         )
     }
 }
 
+/// Returns `true` if any ancestor of `entity` is contained in `dirty`.
+fn has_dirty_ancestor(world: &SubWorld<'_>, entity: Entity, dirty: &HashSet<Entity>) -> bool {
+    let mut current = entity;
+    while let Some(parent) = parent_of(world, current) {
+        if parent == entity {
+            return false;
+        }
+        if dirty.contains(&parent) {
+            return true;
+        }
+        current = parent;
+    }
+    false
+}
+
+fn parent_of(world: &SubWorld<'_>, entity: Entity) -> Option<Entity> {
+    world
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Parent>().ok().map(|parent| parent.0))
+}
+
+fn read_transform<R>(
+    world: &SubWorld<'_>,
+    entity: Entity,
+    read: impl FnOnce(&Transform) -> R,
+) -> Option<R> {
+    world
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Transform>().ok().map(read))
+}
+
+/// Computes the global matrices of `root` and all of its descendants.
+fn compute_subtree(world: &SubWorld<'_>, root: Entity) -> Vec<Update> {
+    let mut updates = Vec::new();
+
+    let root_parent_matrix = match parent_of(world, root) {
+        // When the parent has no transform (yet), keep the last known parent matrix.
+        Some(parent) => {
+            read_transform(world, parent, |parent| parent.global_matrix)
+                .or_else(|| read_transform(world, root, |root| root.parent_matrix))
+        }
+        None => Some(Matrix4::identity()),
+    };
+
+    let mut stack = match root_parent_matrix {
+        Some(parent_matrix) => vec![(root, None, parent_matrix)],
+        None => return updates,
+    };
+
+    while let Some((entity, expected_parent, parent_matrix)) = stack.pop() {
+        let entry = match world.entry_ref(entity) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+
+        // `Children` lag behind `Parent` changes until `ParentUpdateSystem` catches up.
+        if expected_parent.is_some()
+            && expected_parent != entry.get_component::<Parent>().ok().map(|parent| parent.0)
+        {
+            continue;
+        }
+
+        let transform = match entry.get_component::<Transform>() {
+            Ok(transform) => transform,
+            Err(_) => continue,
+        };
+        let global_matrix = parent_matrix * transform.matrix();
+        debug_assert!(
+            global_matrix.iter().all(|f| f.is_finite()),
+            "Entity {:?} had a non-finite `Transform` {:?}",
+            entity,
+            transform
+        );
+        updates.push((entity, parent_matrix, global_matrix));
+
+        if let Ok(children) = entry.get_component::<Children>() {
+            stack.extend(
+                children
+                    .0
+                    .iter()
+                    .map(|child| (*child, Some(entity), global_matrix)),
+            );
+        }
+    }
+
+    updates
+}
+
+/// Stores the computed matrices, without touching transforms that are already up to date.
+fn write_transform(
+    world: &mut SubWorld<'_>,
+    entity: Entity,
+    parent_matrix: Matrix4<f32>,
+    global_matrix: Matrix4<f32>,
+) {
+    let up_to_date = read_transform(world, entity, |transform| {
+        transform.parent_matrix == parent_matrix && transform.global_matrix == global_matrix
+    })
+    .unwrap_or(true);
+    if up_to_date {
+        return;
+    }
+
+    if let Some(transform) = world
+        .entry_mut(entity)
+        .ok()
+        .and_then(|entry| entry.into_component_mut::<Transform>().ok())
+    {
+        transform.parent_matrix = parent_matrix;
+        transform.global_matrix = global_matrix;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -198,8 +305,6 @@ mod tests {
 
         let e3 = world.push((local3, Parent(e2)));
 
-        // Current transform system is inefficient and needs multiple passed for deep hierarchy
-        dispatcher.execute(&mut world, &mut res);
         dispatcher.execute(&mut world, &mut res);
 
         let e1_transform = *world
@@ -258,8 +363,6 @@ mod tests {
         world.entry(e2).unwrap().add_component(Parent(e1));
         world.entry(e3).unwrap().add_component(Parent(e2));
 
-        // Current transform system is inefficient and needs multiple passed for deep hierarchy
-        dispatcher.execute(&mut world, &mut res);
         dispatcher.execute(&mut world, &mut res);
 
         let global_matrix1 = {
@@ -302,6 +405,83 @@ mod tests {
         };
     }
 
+    // Moving the root of a deep hierarchy updates all descendants within the same frame, and
+    // leaves unrelated hierarchies alone.
+    #[test]
+    fn dirty_subtree() {
+        let (mut res, mut world, mut dispatcher) = transform_world();
+
+        let mut local = Transform::default();
+        local.set_translation_xyz(1.0, 0.0, 0.0);
+
+        let root = world.push((local,));
+        let mut leaf = root;
+        for _ in 0..10 {
+            leaf = world.push((local, Parent(leaf)));
+        }
+        let other = world.push((local,));
+
+        dispatcher.execute(&mut world, &mut res);
+        let global_translation = |world: &World, entity| {
+            world
+                .entry_ref(entity)
+                .unwrap()
+                .into_component::<Transform>()
+                .unwrap()
+                .global_matrix()
+                .column(3)
+                .xyz()
+        };
+        assert_eq!(
+            global_translation(&world, leaf),
+            Vector3::new(11.0, 0.0, 0.0)
+        );
+
+        world
+            .entry(root)
+            .unwrap()
+            .into_component_mut::<Transform>()
+            .unwrap()
+            .set_translation_xyz(-1.0, 2.0, 0.0);
+        dispatcher.execute(&mut world, &mut res);
+
+        assert_eq!(
+            global_translation(&world, leaf),
+            Vector3::new(9.0, 2.0, 0.0)
+        );
+        assert_eq!(
+            global_translation(&world, other),
+            Vector3::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    // Moving an entity to another parent updates its global matrix with the new parent's.
+    #[test]
+    fn reparent() {
+        let (mut res, mut world, mut dispatcher) = transform_world();
+
+        let mut local = Transform::default();
+        local.set_translation_xyz(0.0, 1.0, 0.0);
+        let first = world.push((local,));
+        local.set_translation_xyz(0.0, 0.0, 1.0);
+        let second = world.push((local,));
+        let child = world.push((local, Parent(first)));
+
+        dispatcher.execute(&mut world, &mut res);
+        world.entry(child).unwrap().add_component(Parent(second));
+        dispatcher.execute(&mut world, &mut res);
+
+        let transform = world
+            .entry(child)
+            .unwrap()
+            .into_component::<Transform>()
+            .unwrap();
+        assert_eq!(
+            transform.global_matrix().column(3).xyz(),
+            Vector3::new(0.0, 0.0, 2.0)
+        );
+    }
+
     #[test]
     #[should_panic]
     #[cfg(debug_assertions)]
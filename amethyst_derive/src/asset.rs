@@ -0,0 +1,147 @@
+//! `Asset` Implementation
+
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{DeriveInput, Lit, Meta, NestedMeta, Path, Type};
+
+pub fn impl_asset(ast: &DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+
+    let mut uuid: Option<String> = None;
+    let mut asset_name = name.to_string();
+    let mut data: Option<Type> = None;
+    let mut processor: Option<Type> = None;
+    let mut from_data = false;
+    let mut assets: Path = syn::parse_str("amethyst::assets").unwrap();
+
+    for attr in &ast.attrs {
+        if attr.path.is_ident("uuid") {
+            match attr.parse_meta() {
+                Ok(Meta::NameValue(nv)) => uuid = Some(lit_str(&nv.lit, "uuid")),
+                _ => panic!("uuid attribute must be of the form #[uuid = \"...\"]"),
+            }
+        }
+        if !attr.path.is_ident("asset") {
+            continue;
+        }
+        let list = match attr.parse_meta() {
+            Ok(Meta::List(list)) => list,
+            _ => panic!("asset attribute must be of the form #[asset(uuid = \"...\", ...)]"),
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) => {
+                    let key = nv
+                        .path
+                        .get_ident()
+                        .map(ToString::to_string)
+                        .unwrap_or_default();
+                    let value = lit_str(&nv.lit, &key);
+                    match key.as_str() {
+                        "uuid" => uuid = Some(value),
+                        "name" => asset_name = value,
+                        "data" => data = Some(parse(&value, "data")),
+                        "processor" => processor = Some(parse(&value, "processor")),
+                        "assets" => assets = parse(&value, "assets"),
+                        _ => panic!("unknown asset attribute `{}`", key),
+                    }
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("from_data") => {
+                    from_data = true;
+                }
+                _ => panic!("unknown asset attribute"),
+            }
+        }
+    }
+
+    let uuid = uuid.unwrap_or_else(|| {
+        panic!(
+            r#"
+#[derive(Asset)] requested for {}, but the uuid is missing
+
+Example usage:
+#[derive(Asset)]
+#[asset(uuid = "a016abff-623d-48cf-a6e4-e76e069fe843", data = "VertexData")]
+pub struct Vertex {{
+    positions: Vec<[f32; 3]>,
+}}
+"#,
+            name
+        )
+    });
+    let uuid_bytes = parse_uuid(&uuid)
+        .unwrap_or_else(|| panic!("`{}` is not a valid uuid", uuid))
+        .iter()
+        .map(|byte| Literal::u8_suffixed(*byte))
+        .collect::<Vec<_>>();
+    let data_type = data.map_or_else(|| quote!(#name), |data| quote!(#data));
+    let processor = processor.map_or_else(
+        || quote!(amethyst_assets::AssetProcessorSystem<#name>),
+        |processor| quote!(#processor),
+    );
+    let processable = if from_data {
+        quote! {
+            impl amethyst_assets::ProcessableAsset for #name {
+                fn process(
+                    data: #data_type,
+                    _storage: &mut amethyst_assets::AssetStorage<Self>,
+                    _handle: &amethyst_assets::LoadHandle,
+                ) -> Result<
+                    amethyst_assets::ProcessingState<#data_type, Self>,
+                    amethyst_assets::amethyst_error::Error,
+                > {
+                    Ok(amethyst_assets::ProcessingState::Loaded(Self::from(data)))
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        const _: () = {
+            use #assets as amethyst_assets;
+
+            impl amethyst_assets::Asset for #name {
+                fn name() -> &'static str {
+                    #asset_name
+                }
+
+                type Data = #data_type;
+            }
+
+            impl amethyst_assets::TypeUuid for #name {
+                const UUID: [u8; 16] = [#(#uuid_bytes),*];
+            }
+
+            #processable
+
+            amethyst_assets::register_asset_type!(amethyst_assets; #data_type => #name; #processor);
+        };
+    }
+}
+
+fn lit_str(lit: &Lit, key: &str) -> String {
+    match lit {
+        Lit::Str(s) => s.value(),
+        _ => panic!("asset attribute `{}` must be a string", key),
+    }
+}
+
+fn parse<T: syn::parse::Parse>(value: &str, key: &str) -> T {
+    syn::parse_str(value)
+        .unwrap_or_else(|_| panic!("asset attribute `{}` is not valid: `{}`", key, value))
+}
+
+/// Parses a uuid in its hyphenated or simple form.
+fn parse_uuid(uuid: &str) -> Option<[u8; 16]> {
+    let hex: Vec<u8> = uuid.bytes().filter(|c| *c != b'-').collect();
+    if hex.len() != 32 {
+        return None;
+    }
+    let mut bytes = [0; 16];
+    for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
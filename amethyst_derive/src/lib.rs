@@ -23,6 +23,7 @@ use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use syn::{parse_macro_input, DeriveInput};
 
+mod asset;
 mod event_reader;
 mod widget_id;
 
@@ -34,6 +35,34 @@ pub fn event_reader_derive(input: TokenStream) -> TokenStream {
     gen.into()
 }
 
+/// `Asset`
+///
+/// Implements `Asset` and `TypeUuid` and registers the asset type, so its `AssetStorage` and
+/// processor are created by the `LoaderBundle`.
+///
+/// The `#[asset(...)]` attribute takes:
+///
+/// * `uuid`: Uuid of the asset type, which can be given with `#[uuid = "..."]` instead.
+/// * `name`: Name of the asset, the name of the type by default.
+/// * `data`: Type the asset is processed from, the asset itself by default.
+/// * `processor`: System processing the data, `AssetProcessorSystem<Self>` by default.
+/// * `from_data`: Implements `ProcessableAsset` with the `From<Data>` implementation of the asset.
+/// * `assets`: Path of the assets crate, `amethyst::assets` by default.
+///
+/// ```rust,ignore
+/// #[derive(Asset)]
+/// #[asset(uuid = "a016abff-623d-48cf-a6e4-e76e069fe843", data = "VertexData", from_data)]
+/// pub struct Vertex {
+///     positions: Vec<[f32; 3]>,
+/// }
+/// ```
+#[proc_macro_derive(Asset, attributes(asset, uuid))]
+pub fn asset_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let gen = asset::impl_asset(&ast);
+    gen.into()
+}
+
 /// This allows the use of an enum as an ID for the `Widgets` resource. One
 /// variant has to be marked as the default variant with `#[widget_id_default]`
 /// and will be used when a `Widget` is added to the resource without an
@@ -0,0 +1,35 @@
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::EventChannel,
+};
+use amethyst_error::Error;
+
+use crate::{
+    runner::DialogueEvent,
+    system::{DialogueLocale, DialogueSystem},
+    variables::DialogueVariables,
+};
+
+/// Bundle running the [`DialogueRunner`](crate::DialogueRunner)s.
+///
+/// This will add empty `DialogueVariables` unless they're already present, e.g. restored from a
+/// saved game, an empty `DialogueLocale`, an `EventChannel<DialogueEvent>` and the
+/// `DialogueSystem`. The `AssetStorage<Locale>` resource must be available, e.g. by adding the
+/// `LoaderBundle` first.
+#[derive(Default, Debug)]
+pub struct DialogueBundle;
+
+impl SystemBundle for DialogueBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_default::<DialogueVariables>();
+        resources.get_or_default::<DialogueLocale>();
+        resources.get_or_insert_with(EventChannel::<DialogueEvent>::new);
+        builder.add_system(DialogueSystem);
+        Ok(())
+    }
+}
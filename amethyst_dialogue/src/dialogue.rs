@@ -0,0 +1,181 @@
+//! The dialogue asset, a graph of nodes linked by choices.
+
+use std::collections::HashMap;
+
+use amethyst_assets::{
+    distill_importer,
+    distill_importer::{typetag, SerdeImportable},
+    register_asset_type, Asset, AssetProcessorSystem, Handle,
+};
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+use crate::variables::{DialogueValue, DialogueVariables};
+
+/// A handle to a dialogue asset.
+pub type DialogueHandle = Handle<Dialogue>;
+
+/// A dialogue, made of nodes showing a line and leading to other nodes.
+///
+/// The texts of the nodes and choices are keys of the [`DialogueLocale`](crate::DialogueLocale),
+/// which are formatted with the [`DialogueVariables`] as arguments. Dialogues are loaded from RON
+/// files:
+///
+/// ```ron
+/// {
+///     "0f5d1a7e-49a2-4f0c-9b6e-5cbd1c3bb8a4": (
+///         start: "greeting",
+///         nodes: {
+///             "greeting": (
+///                 speaker: Some("npc-guard"),
+///                 text: "guard-greeting",
+///                 choices: [
+///                     (
+///                         text: "choice-bribe",
+///                         condition: Some(Greater("gold", 10.0)),
+///                         changes: [Add("gold", -10.0)],
+///                         next: Some("bribed"),
+///                     ),
+///                     (text: "choice-leave"),
+///                 ],
+///             ),
+///             "bribed": (
+///                 speaker: Some("npc-guard"),
+///                 text: "guard-bribed",
+///                 changes: [Set("gate_open", Bool(true))],
+///             ),
+///         },
+///     ),
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, TypeUuid, SerdeImportable)]
+#[uuid = "89de8b86-e645-40f4-8b27-3ac809b67654"]
+pub struct Dialogue {
+    /// Id of the node the dialogue starts at.
+    pub start: String,
+    /// Nodes of the dialogue by id.
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+impl Asset for Dialogue {
+    fn name() -> &'static str {
+        "dialogue::Dialogue"
+    }
+    type Data = Self;
+}
+
+register_asset_type!(Dialogue => Dialogue; AssetProcessorSystem<Dialogue>);
+
+/// A line of a `Dialogue`, with the choices answering it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DialogueNode {
+    /// Locale key of the name of the speaker.
+    pub speaker: Option<String>,
+    /// Locale key of the line.
+    pub text: String,
+    /// Changes made to the variables when the node is entered.
+    pub changes: Vec<VariableChange>,
+    /// Choices answering the line. Without available choices, the dialogue continues with
+    /// `branches` and `next`.
+    pub choices: Vec<DialogueChoice>,
+    /// Nodes the dialogue continues at, the first one whose condition holds being taken.
+    pub branches: Vec<DialogueBranch>,
+    /// Node the dialogue continues at when no branch is taken, the dialogue ending without it.
+    pub next: Option<String>,
+}
+
+/// A choice answering a `DialogueNode`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DialogueChoice {
+    /// Locale key of the text of the choice.
+    pub text: String,
+    /// Condition for the choice to be available.
+    pub condition: Option<Condition>,
+    /// Changes made to the variables when the choice is picked.
+    pub changes: Vec<VariableChange>,
+    /// Node the dialogue continues at, the node of the choice continuing as without choices
+    /// when not set.
+    pub next: Option<String>,
+}
+
+/// A conditional link to another `DialogueNode`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DialogueBranch {
+    /// Condition for the branch to be taken.
+    pub condition: Condition,
+    /// Node the dialogue continues at.
+    pub next: String,
+}
+
+/// Condition on the `DialogueVariables`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    /// The variable is set to the value.
+    Equals(String, DialogueValue),
+    /// The variable is a number less than the value.
+    Less(String, f64),
+    /// The variable is a number greater than the value.
+    Greater(String, f64),
+    /// The variable is set.
+    IsSet(String),
+    /// The condition doesn't hold.
+    Not(Box<Condition>),
+    /// All the conditions hold.
+    All(Vec<Condition>),
+    /// Any of the conditions holds.
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    /// Whether the condition holds for `variables`.
+    #[must_use]
+    pub fn holds(&self, variables: &DialogueVariables) -> bool {
+        match self {
+            Condition::Equals(name, value) => variables.get(name) == Some(value),
+            Condition::Less(name, value) => variables.number(name).map_or(false, |n| n < *value),
+            Condition::Greater(name, value) => variables.number(name).map_or(false, |n| n > *value),
+            Condition::IsSet(name) => variables.get(name).is_some(),
+            Condition::Not(condition) => !condition.holds(variables),
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(variables)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.holds(variables)),
+        }
+    }
+}
+
+/// Change made to the `DialogueVariables`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VariableChange {
+    /// Sets the variable to the value.
+    Set(String, DialogueValue),
+    /// Adds the value to the variable, which is `0.0` when it isn't a number.
+    Add(String, f64),
+    /// Removes the variable.
+    Unset(String),
+}
+
+impl DialogueNode {
+    /// The choices whose condition holds, with their index in `choices`.
+    pub fn available_choices<'a>(
+        &'a self,
+        variables: &'a DialogueVariables,
+    ) -> impl Iterator<Item = (usize, &'a DialogueChoice)> + 'a {
+        self.choices.iter().enumerate().filter(move |(_, choice)| {
+            choice
+                .condition
+                .as_ref()
+                .map_or(true, |condition| condition.holds(variables))
+        })
+    }
+
+    /// The node the dialogue continues at without choice, `None` ending the dialogue.
+    #[must_use]
+    pub fn next_node(&self, variables: &DialogueVariables) -> Option<&str> {
+        self.branches
+            .iter()
+            .find(|branch| branch.condition.holds(variables))
+            .map(|branch| branch.next.as_str())
+            .or_else(|| self.next.as_deref())
+    }
+}
@@ -0,0 +1,52 @@
+//! Data-driven dialogues, presented by the UI of the game.
+//!
+//! A [`Dialogue`] is an asset made of nodes, each showing a line and leading to other nodes
+//! through the choices answering it. Conditions on the [`DialogueVariables`] make choices
+//! available and pick the branches taken, and the nodes and choices change the variables.
+//!
+//! Add the [`DialogueBundle`], load a dialogue and add a [`DialogueRunner`] to an entity. The
+//! [`DialogueSystem`] sends a [`DialogueEvent::Line`] for each node entered, for the UI to show
+//! it along with its choices, and the UI moves the dialogue on:
+//!
+//! ```ignore
+//! let dialogue = loader.load("dialogues/guard.dialogue.ron");
+//! let guard_talk = world.push((DialogueRunner::new(dialogue),));
+//!
+//! // Later, when the player clicks on a choice.
+//! runner.choose(option.index);
+//! ```
+//!
+//! The texts of the dialogues are keys of the locale of the [`DialogueLocale`] resource, whose
+//! messages are formatted with the variables, e.g. `guard-greeting = Halt, { $player_name }!`.
+//! The variables are the state of the story, to be saved and restored with the game.
+
+#![doc(
+    html_logo_url = "https://amethyst.rs/brand/logo-standard.svg",
+    html_root_url = "https://docs.amethyst.rs/stable"
+)]
+#![deny(
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    rust_2018_compatibility,
+    clippy::all
+)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::new_without_default, clippy::module_name_repetitions)]
+
+pub use self::{
+    bundle::DialogueBundle,
+    dialogue::{
+        Condition, Dialogue, DialogueBranch, DialogueChoice, DialogueHandle, DialogueNode,
+        VariableChange,
+    },
+    runner::{DialogueEvent, DialogueOption, DialogueRunner},
+    system::{DialogueLocale, DialogueSystem},
+    variables::{DialogueValue, DialogueVariables},
+};
+
+mod bundle;
+mod dialogue;
+mod runner;
+mod system;
+mod variables;
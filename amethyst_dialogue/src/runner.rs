@@ -0,0 +1,258 @@
+use amethyst_core::ecs::Entity;
+
+use crate::{
+    dialogue::{Dialogue, DialogueHandle},
+    variables::DialogueVariables,
+};
+
+/// Component running a [`Dialogue`], see the [crate documentation](crate).
+///
+/// The dialogue starts once it's loaded. Each node entered is sent as a [`DialogueEvent::Line`]
+/// for the UI to present, which then moves the dialogue on with [`DialogueRunner::proceed`] or
+/// [`DialogueRunner::choose`].
+#[derive(Debug, Clone)]
+pub struct DialogueRunner {
+    dialogue: DialogueHandle,
+    pub(crate) state: DialogueState,
+}
+
+/// Progress of a `DialogueRunner` through its dialogue.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DialogueState {
+    node: Option<String>,
+    started: bool,
+    input: Option<DialogueInput>,
+}
+
+/// Answer of the player to the current node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DialogueInput {
+    Proceed,
+    Choose(usize),
+}
+
+impl DialogueRunner {
+    /// Creates a runner of `dialogue`, starting once it's loaded.
+    #[must_use]
+    pub fn new(dialogue: DialogueHandle) -> Self {
+        Self {
+            dialogue,
+            state: DialogueState::default(),
+        }
+    }
+
+    /// The dialogue run.
+    #[must_use]
+    pub fn dialogue(&self) -> &DialogueHandle {
+        &self.dialogue
+    }
+
+    /// Id of the current node, `None` before the dialogue starts and after it ends.
+    #[must_use]
+    pub fn node(&self) -> Option<&str> {
+        self.state.node()
+    }
+
+    /// Whether the dialogue reached its end.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    /// Moves on from the current node when it has no available choice.
+    pub fn proceed(&mut self) {
+        self.state.input = Some(DialogueInput::Proceed);
+    }
+
+    /// Picks the choice of the current node at `index` in its `choices`.
+    pub fn choose(&mut self, index: usize) {
+        self.state.input = Some(DialogueInput::Choose(index));
+    }
+
+    /// Starts the dialogue again from its start node.
+    pub fn restart(&mut self) {
+        self.state = DialogueState::default();
+    }
+}
+
+impl DialogueState {
+    pub(crate) fn node(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.started && self.node.is_none()
+    }
+
+    /// Moves the dialogue on, returning whether the current node changed.
+    pub(crate) fn step(&mut self, dialogue: &Dialogue, variables: &mut DialogueVariables) -> bool {
+        let next = if self.started {
+            let input = match self.input.take() {
+                Some(input) => input,
+                None => return false,
+            };
+            let node = match self.node.as_ref().and_then(|id| dialogue.nodes.get(id)) {
+                Some(node) => node,
+                None => return false,
+            };
+            match input {
+                DialogueInput::Proceed if node.available_choices(variables).next().is_none() => {
+                    node.next_node(variables).map(str::to_string)
+                }
+                DialogueInput::Choose(index) => {
+                    match node.available_choices(variables).find(|(i, _)| *i == index) {
+                        Some((_, choice)) => {
+                            for change in &choice.changes {
+                                variables.apply(change);
+                            }
+                            choice
+                                .next
+                                .clone()
+                                .or_else(|| node.next_node(variables).map(str::to_string))
+                        }
+                        None => {
+                            log::warn!("The choice {} of the dialogue isn't available", index);
+                            return false;
+                        }
+                    }
+                }
+                DialogueInput::Proceed => {
+                    log::warn!("The dialogue can't proceed without a choice");
+                    return false;
+                }
+            }
+        } else {
+            self.started = true;
+            self.input = None;
+            Some(dialogue.start.clone())
+        };
+
+        self.node = next.and_then(|id| {
+            if let Some(node) = dialogue.nodes.get(&id) {
+                for change in &node.changes {
+                    variables.apply(change);
+                }
+                Some(id)
+            } else {
+                log::error!("The dialogue has no node {:?}, ending it", id);
+                None
+            }
+        });
+        true
+    }
+}
+
+/// Event sent by the `DialogueSystem` on the `EventChannel<DialogueEvent>` resource.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DialogueEvent {
+    /// A dialogue entered a node, to be presented to the player.
+    Line {
+        /// Entity of the `DialogueRunner`.
+        runner: Entity,
+        /// Id of the node.
+        node: String,
+        /// Name of the speaker, resolved through the `DialogueLocale`.
+        speaker: Option<String>,
+        /// Line of the node, resolved through the `DialogueLocale`.
+        text: String,
+        /// Available choices answering the line.
+        choices: Vec<DialogueOption>,
+    },
+    /// A dialogue reached its end.
+    Ended {
+        /// Entity of the `DialogueRunner`.
+        runner: Entity,
+    },
+}
+
+/// A choice presented to the player in a `DialogueEvent::Line`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialogueOption {
+    /// Index to pass to `DialogueRunner::choose` to pick the choice.
+    pub index: usize,
+    /// Text of the choice, resolved through the `DialogueLocale`.
+    pub text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::{Condition, DialogueChoice, DialogueNode, VariableChange},
+        variables::DialogueValue,
+    };
+
+    fn dialogue() -> Dialogue {
+        let mut dialogue = Dialogue {
+            start: "greeting".into(),
+            ..Dialogue::default()
+        };
+        dialogue.nodes.insert(
+            "greeting".into(),
+            DialogueNode {
+                text: "greeting".into(),
+                choices: vec![
+                    DialogueChoice {
+                        text: "bribe".into(),
+                        condition: Some(Condition::Greater("gold".into(), 10.0)),
+                        changes: vec![VariableChange::Add("gold".into(), -10.0)],
+                        next: Some("bribed".into()),
+                    },
+                    DialogueChoice {
+                        text: "leave".into(),
+                        ..DialogueChoice::default()
+                    },
+                ],
+                ..DialogueNode::default()
+            },
+        );
+        dialogue.nodes.insert(
+            "bribed".into(),
+            DialogueNode {
+                text: "bribed".into(),
+                changes: vec![VariableChange::Set(
+                    "gate_open".into(),
+                    DialogueValue::Bool(true),
+                )],
+                ..DialogueNode::default()
+            },
+        );
+        dialogue
+    }
+
+    #[test]
+    fn unavailable_choices_are_refused() {
+        let dialogue = dialogue();
+        let mut variables = DialogueVariables::new();
+        let mut state = DialogueState::default();
+
+        assert!(state.step(&dialogue, &mut variables));
+        assert_eq!(state.node(), Some("greeting"));
+        state.input = Some(DialogueInput::Choose(0));
+        assert!(!state.step(&dialogue, &mut variables));
+        state.input = Some(DialogueInput::Proceed);
+        assert!(!state.step(&dialogue, &mut variables));
+        state.input = Some(DialogueInput::Choose(1));
+        assert!(state.step(&dialogue, &mut variables));
+        assert!(state.is_finished());
+    }
+
+    #[test]
+    fn choices_change_variables() {
+        let dialogue = dialogue();
+        let mut variables = DialogueVariables::new();
+        variables.set("gold", DialogueValue::Number(15.0));
+        let mut state = DialogueState::default();
+
+        state.step(&dialogue, &mut variables);
+        state.input = Some(DialogueInput::Choose(0));
+        assert!(state.step(&dialogue, &mut variables));
+        assert_eq!(state.node(), Some("bribed"));
+        assert_eq!(variables.number("gold"), Some(5.0));
+        assert_eq!(variables.get("gate_open"), Some(&DialogueValue::Bool(true)));
+
+        state.input = Some(DialogueInput::Proceed);
+        assert!(state.step(&dialogue, &mut variables));
+        assert!(state.is_finished());
+    }
+}
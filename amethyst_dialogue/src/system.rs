@@ -0,0 +1,129 @@
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::EventChannel,
+};
+use amethyst_locale::Locale;
+use fluent::{FluentArgs, FluentValue};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    dialogue::Dialogue,
+    runner::{DialogueEvent, DialogueOption, DialogueRunner},
+    variables::{DialogueValue, DialogueVariables},
+};
+
+/// Resource selecting the locale the keys of the dialogues are resolved through.
+///
+/// When it's `None` or the locale isn't loaded yet, the keys are sent as the texts of the lines.
+#[derive(Debug, Default)]
+pub struct DialogueLocale(pub Option<Handle<Locale>>);
+
+/// Formats the message of `key`, with the variables as arguments.
+fn resolve(locale: Option<&Locale>, key: &str, variables: &DialogueVariables) -> String {
+    locale
+        .and_then(|locale| {
+            let message = locale.bundle.get_message(key)?;
+            let pattern = message.value?;
+            let mut args = FluentArgs::new();
+            for (name, value) in variables.iter() {
+                match value {
+                    DialogueValue::Bool(flag) => {
+                        args.add(name, FluentValue::from(flag.to_string()))
+                    }
+                    DialogueValue::Number(number) => args.add(name, FluentValue::from(*number)),
+                    DialogueValue::Text(text) => args.add(name, FluentValue::from(text.as_str())),
+                }
+            }
+            let mut errors = Vec::new();
+            Some(
+                locale
+                    .bundle
+                    .format_pattern(pattern, Some(&args), &mut errors)
+                    .into_owned(),
+            )
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Runs the [`DialogueRunner`]s, sending a [`DialogueEvent`] for each node entered and at the end
+/// of the dialogues.
+///
+/// Added by the [`DialogueBundle`](crate::DialogueBundle), which inserts the resources it needs.
+#[derive(Debug)]
+pub struct DialogueSystem;
+
+impl System for DialogueSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("DialogueSystem")
+                .read_resource::<AssetStorage<Dialogue>>()
+                .read_resource::<DialogueLocale>()
+                .read_resource::<AssetStorage<Locale>>()
+                .write_resource::<DialogueVariables>()
+                .write_resource::<EventChannel<DialogueEvent>>()
+                .with_query(<(Entity, &mut DialogueRunner)>::query())
+                .build(
+                    |_, world, (dialogues, locale, locales, variables, events), query| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("dialogue_system");
+
+                        let locale = locale.0.as_ref().and_then(|handle| locales.get(handle));
+                        for (entity, runner) in query.iter_mut(world) {
+                            let dialogue = match dialogues.get(runner.dialogue()) {
+                                Some(dialogue) => dialogue,
+                                None => continue,
+                            };
+                            if !runner.state.step(dialogue, variables) {
+                                continue;
+                            }
+
+                            let (id, node) = match runner
+                                .node()
+                                .and_then(|id| Some((id, dialogue.nodes.get(id)?)))
+                            {
+                                Some(node) => node,
+                                None => {
+                                    events.single_write(DialogueEvent::Ended { runner: *entity });
+                                    continue;
+                                }
+                            };
+                            let choices = node
+                                .available_choices(variables)
+                                .map(|(index, choice)| {
+                                    DialogueOption {
+                                        index,
+                                        text: resolve(locale, &choice.text, variables),
+                                    }
+                                })
+                                .collect();
+                            events.single_write(DialogueEvent::Line {
+                                runner: *entity,
+                                node: id.to_string(),
+                                speaker: node
+                                    .speaker
+                                    .as_ref()
+                                    .map(|speaker| resolve(locale, speaker, variables)),
+                                text: resolve(locale, &node.text, variables),
+                                choices,
+                            });
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unresolved_keys_are_shown_as_is() {
+        assert_eq!(
+            resolve(None, "guard-greeting", &DialogueVariables::new()),
+            "guard-greeting"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue::VariableChange;
+
+/// Value of a dialogue variable.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DialogueValue {
+    /// A flag.
+    Bool(bool),
+    /// A number, like a counter or an amount.
+    Number(f64),
+    /// A text, like the name of the player.
+    Text(String),
+}
+
+/// Resource holding the variables the dialogues read in their conditions and text, and change
+/// through their nodes and choices.
+///
+/// The variables are shared by all the dialogues, so they're the state of the story so far. They
+/// can be serialized with the rest of the saved state of the game, and inserted back when loading
+/// it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DialogueVariables {
+    values: HashMap<String, DialogueValue>,
+}
+
+impl DialogueVariables {
+    /// Creates an empty set of variables.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value of the variable, if it's set.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&DialogueValue> {
+        self.values.get(name)
+    }
+
+    /// Value of the variable, if it's set to a number.
+    #[must_use]
+    pub fn number(&self, name: &str) -> Option<f64> {
+        match self.values.get(name) {
+            Some(DialogueValue::Number(number)) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Sets the variable, returning its previous value.
+    pub fn set(&mut self, name: impl Into<String>, value: DialogueValue) -> Option<DialogueValue> {
+        self.values.insert(name.into(), value)
+    }
+
+    /// Removes the variable, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<DialogueValue> {
+        self.values.remove(name)
+    }
+
+    /// Iterates over the variables and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DialogueValue)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Applies a change from a dialogue.
+    pub fn apply(&mut self, change: &VariableChange) {
+        match change {
+            VariableChange::Set(name, value) => {
+                self.set(name.clone(), value.clone());
+            }
+            VariableChange::Add(name, value) => {
+                let number = self.number(name).unwrap_or(0.0) + value;
+                self.set(name.clone(), DialogueValue::Number(number));
+            }
+            VariableChange::Unset(name) => {
+                self.remove(name);
+            }
+        }
+    }
+}
@@ -75,6 +75,7 @@ pub fn load_material(
         &*material_name,
     );
     let (cavity_id, cavity_asset) = load_cavity(state, op, &*material_name);
+    let (lightmap_id, lightmap_asset) = load_lightmap(state, op, &*material_name);
     let alpha_cutoff = match material.alpha_mode() {
         AlphaMode::Blend => {
             state
@@ -94,6 +95,7 @@ pub fn load_material(
     assets_accumulator.push(normal_asset);
     assets_accumulator.push(occlusion_asset);
     assets_accumulator.push(cavity_asset);
+    assets_accumulator.push(lightmap_asset);
 
     let material = Material {
         alpha_cutoff,
@@ -104,6 +106,9 @@ pub fn load_material(
         ambient_occlusion: make_handle(occlusion_id),
         cavity: make_handle(cavity_id),
         uv_offset: amethyst_rendy::mtl::TextureOffset::default(),
+        uv_animation: amethyst_rendy::mtl::UvAnimation::default(),
+        lightmap: make_handle(lightmap_id),
+        lightmap_offset: amethyst_rendy::mtl::TextureOffset::default(),
     };
     let id = *state
         .material_uuids
@@ -140,8 +145,11 @@ fn load_texture_with_factor(
         }
         None => {
             Ok((
+                // glTF color factors are always linear, even for sRGB textures.
                 if srgb {
-                    load_from_srgba(Srgba::new(factor[0], factor[1], factor[2], factor[3]))
+                    load_from_srgba(Srgba::from_linear(LinSrgba::new(
+                        factor[0], factor[1], factor[2], factor[3],
+                    )))
                 } else {
                     load_from_linear_rgba(LinSrgba::new(factor[0], factor[1], factor[2], factor[3]))
                 },
@@ -360,6 +368,31 @@ fn load_cavity(
     )
 }
 
+fn load_lightmap(
+    state: &mut GltfImporterState,
+    op: &mut ImportOp,
+    material_name: &str,
+) -> (AssetUuid, ImportedAsset) {
+    let lightmap: TextureData = load_from_linear_rgba(LinSrgba::new(0.0, 0.0, 0.0, 1.0)).into();
+    let id = *state
+        .material_uuids
+        .as_mut()
+        .expect("Meshes hashmap didn't work")
+        .entry(format!("{}_lightmap", material_name))
+        .or_insert_with(|| op.new_asset_uuid());
+    (
+        id,
+        ImportedAsset {
+            id,
+            search_tags: vec![],
+            build_deps: vec![],
+            load_deps: vec![],
+            build_pipeline: None,
+            asset_data: Box::new(lightmap),
+        },
+    )
+}
+
 fn load_texture(
     texture: &gltf::Texture<'_>,
     buffers: &[Data],
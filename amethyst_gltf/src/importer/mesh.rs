@@ -3,6 +3,7 @@ use std::{iter::repeat, ops::Range};
 use amethyst_assets::error::Error;
 use amethyst_core::math::{zero, Vector3};
 use amethyst_rendy::{
+    lightmap::LightmapTexCoord,
     rendy::mesh::{Color, MeshBuilder, Normal, Position, Tangent, TexCoord},
     skinning::JointCombined,
 };
@@ -70,6 +71,22 @@ pub fn load_mesh(
             }
         });
 
+        let lightmap_tex_coords = try_compute_if(options.load_texcoords, || {
+            debug!("Loading lightmap texture coordinates");
+            reader
+                .read_tex_coords(1)
+                .map(gltf::mesh::util::ReadTexCoords::into_f32)
+                .map(|tex_coords| {
+                    if options.flip_v_coord {
+                        tex_coords
+                            .map(|[u, v]| LightmapTexCoord([u, 1. - v]))
+                            .collect::<Vec<_>>()
+                    } else {
+                        tex_coords.map(LightmapTexCoord).collect::<Vec<_>>()
+                    }
+                })
+        });
+
         let tangents = compute_if(options.load_tangents, || {
             debug!("Loading tangents");
             let tangents = reader.read_tangents();
@@ -123,6 +140,7 @@ pub fn load_mesh(
         normals.map(|v| builder.add_vertices(v));
         tangents.map(|v| builder.add_vertices(v));
         tex_coords.map(|v| builder.add_vertices(v));
+        lightmap_tex_coords.map(|v| builder.add_vertices(v));
         colors.map(|v| builder.add_vertices(v));
         joints.map(|v| builder.add_vertices(v));
 
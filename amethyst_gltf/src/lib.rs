@@ -67,7 +67,8 @@ pub struct GltfSceneOptions {
     /// Load vertex color data from the Gltf file
     pub load_colors: bool,
     #[derivative(Default(value = "true"))]
-    /// Load texture coordinates data from the Gltf file
+    /// Load texture coordinates data from the Gltf file, the second set being the lightmap
+    /// texture coordinates
     pub load_texcoords: bool,
     #[derivative(Default(value = "true"))]
     /// Load vertex tangent data from the Gltf file
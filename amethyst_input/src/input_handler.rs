@@ -17,6 +17,7 @@ use super::{
         ButtonReleased, CursorMoved, KeyPressed, KeyReleased, KeyTyped, MouseButtonPressed,
         MouseButtonReleased, MouseMoved, MouseWheelMoved,
     },
+    scroll_delta::{ScrollDelta, DEFAULT_SCROLL_PIXELS_PER_LINE},
     scroll_direction::ScrollDirection,
     Axis, Bindings, Button, ControllerAxis, ElementState, Iterator, MouseAxis,
 };
@@ -81,6 +82,9 @@ pub struct InputHandler {
     mouse_position: Option<(f32, f32)>,
     mouse_wheel_vertical: f32,
     mouse_wheel_horizontal: f32,
+    scroll: ScrollDelta,
+    scroll_pixels_per_line: Option<f32>,
+    actions_blocked: bool,
 }
 
 impl InputHandler {
@@ -138,9 +142,10 @@ impl InputHandler {
                                     c.contains(&Button::Key(key_code))
                                         || c.contains(&Button::ScanCode(scancode))
                                 }) {
-                                    if combination
-                                        .iter()
-                                        .all(|button| self.button_is_down(*button))
+                                    if !self.actions_blocked
+                                        && combination
+                                            .iter()
+                                            .all(|button| self.button_is_down(*button))
                                     {
                                         event_handler.single_write(ActionPressed(action.clone()));
                                     }
@@ -219,9 +224,10 @@ impl InputHandler {
                                     .iter()
                                     .filter(|c| c.contains(&Button::Mouse(mouse_button)))
                                 {
-                                    if combination
-                                        .iter()
-                                        .all(|button| self.button_is_down(*button))
+                                    if !self.actions_blocked
+                                        && combination
+                                            .iter()
+                                            .all(|button| self.button_is_down(*button))
                                     {
                                         event_handler.single_write(ActionPressed(action.clone()));
                                     }
@@ -303,6 +309,8 @@ impl InputHandler {
                         if delta_y != 0.0 {
                             self.mouse_wheel_vertical = delta_y.signum();
                         }
+                        self.scroll.lines.0 += delta_x;
+                        self.scroll.lines.1 += delta_y;
                         self.invoke_wheel_moved(delta_x, delta_y, event_handler);
                     }
                     DeviceEvent::MouseWheel {
@@ -314,6 +322,8 @@ impl InputHandler {
                         if y != 0.0 {
                             self.mouse_wheel_vertical = y.signum() as f32;
                         }
+                        self.scroll.pixels.0 += x as f32;
+                        self.scroll.pixels.1 += y as f32;
                         self.invoke_wheel_moved(x as f32, y as f32, event_handler);
                     }
                     _ => {}
@@ -373,9 +383,10 @@ impl InputHandler {
                                 .iter()
                                 .filter(|c| c.contains(&Button::Controller(controller_id, button)))
                             {
-                                if combination
-                                    .iter()
-                                    .all(|button| self.button_is_down(*button))
+                                if !self.actions_blocked
+                                    && combination
+                                        .iter()
+                                        .all(|button| self.button_is_down(*button))
                                 {
                                     event_handler.single_write(ActionPressed(action.clone()));
                                 }
@@ -455,6 +466,7 @@ impl InputHandler {
     pub fn send_frame_begin(&mut self) {
         self.mouse_wheel_vertical = 0.0;
         self.mouse_wheel_horizontal = 0.0;
+        self.scroll = ScrollDelta::default();
         self.mouse_last_position = self.mouse_position;
     }
 
@@ -495,6 +507,36 @@ impl InputHandler {
         }
     }
 
+    /// Returns the scroll of the mouse wheels and touchpads this frame, as reported in lines and
+    /// in pixels by the platform.
+    #[must_use]
+    pub fn raw_scroll_delta(&self) -> ScrollDelta {
+        self.scroll
+    }
+
+    /// Returns the scroll of the mouse wheels and touchpads this frame in lines, the pixels
+    /// reported by touchpads being converted with `scroll_pixels_per_line`.
+    ///
+    /// Unlike `mouse_wheel_value`, which is only the direction of the scroll, this is
+    /// proportional to the scrolled distance on every platform.
+    #[must_use]
+    pub fn scroll_delta(&self) -> (f32, f32) {
+        self.scroll.normalized(self.scroll_pixels_per_line())
+    }
+
+    /// Returns the number of pixels scrolled by a touchpad counted as one line.
+    #[must_use]
+    pub fn scroll_pixels_per_line(&self) -> f32 {
+        self.scroll_pixels_per_line
+            .unwrap_or(DEFAULT_SCROLL_PIXELS_PER_LINE)
+    }
+
+    /// Sets the number of pixels scrolled by a touchpad counted as one line, to tune the
+    /// touchpad scroll speed relative to mouse wheels.
+    pub fn set_scroll_pixels_per_line(&mut self, pixels_per_line: f32) {
+        self.scroll_pixels_per_line = Some(pixels_per_line);
+    }
+
     /// Returns an iterator over all pressed scan codes
     pub fn scan_codes_that_are_down(&self) -> impl Iterator<Item = u32> + '_ {
         self.pressed_keys.iter().map(|k| k.1)
@@ -647,26 +689,50 @@ impl InputHandler {
     }
 
     /// Returns the value of an axis by the id, if the id doesn't exist this returns None.
+    /// Axes are at zero while actions are blocked.
     #[must_use]
     pub fn axis_value(&self, id: &str) -> Option<f32> {
-        self.bindings.axes.get(id).map(|a| self.axis_value_impl(a))
+        self.bindings.axes.get(id).map(|a| {
+            if self.actions_blocked {
+                0.0
+            } else {
+                self.axis_value_impl(a)
+            }
+        })
     }
 
     /// Returns Some(true) if any of the actions bindings is down, and Some(false) if
     /// the actions bindings is not down. Returns None if the given action is not found.
     ///
     /// If a binding represents a combination of buttons, all of them need to be down.
+    /// Actions are never down while they are blocked.
     #[must_use]
     pub fn action_is_down(&self, action: &str) -> Option<bool> {
         self.bindings.actions.get(action).map(|combinations| {
-            combinations.iter().any(|combination| {
-                combination
-                    .iter()
-                    .all(|button| self.button_is_down(*button))
-            })
+            !self.actions_blocked
+                && combinations.iter().any(|combination| {
+                    combination
+                        .iter()
+                        .all(|button| self.button_is_down(*button))
+                })
         })
     }
 
+    /// Blocks the actions and axes, for instance while a modal ui captures the input.
+    ///
+    /// While they are blocked, actions are never down, axes stay at zero, and no
+    /// `ActionPressed` or `ActionWheelMoved` events are sent. `ActionReleased` events are still
+    /// sent, so actions held when they got blocked are released.
+    pub fn set_actions_blocked(&mut self, blocked: bool) {
+        self.actions_blocked = blocked;
+    }
+
+    /// Returns whether the actions and axes are blocked.
+    #[must_use]
+    pub fn actions_blocked(&self) -> bool {
+        self.actions_blocked
+    }
+
     /// Retrieve next free controller number to allocate new controller to
     fn alloc_controller_id(&self) -> u32 {
         let mut i = 0_u32;
@@ -725,7 +791,8 @@ impl InputHandler {
         for (action, combinations) in &self.bindings.actions {
             for combination in combinations {
                 if let Some(dir) = dir_x {
-                    if combination.contains(&Button::MouseWheel(dir))
+                    if !self.actions_blocked
+                        && combination.contains(&Button::MouseWheel(dir))
                         && combination
                             .iter()
                             .filter(|b| **b != Button::MouseWheel(dir))
@@ -735,7 +802,8 @@ impl InputHandler {
                     }
                 }
                 if let Some(dir) = dir_y {
-                    if combination.contains(&Button::MouseWheel(dir))
+                    if !self.actions_blocked
+                        && combination.contains(&Button::MouseWheel(dir))
                         && combination
                             .iter()
                             .filter(|b| **b != Button::MouseWheel(dir))
@@ -894,6 +962,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn blocked_actions_are_not_pressed() {
+        let mut handler = InputHandler::new();
+        let mut events = EventChannel::<InputEvent>::new();
+        let mut reader = events.register_reader();
+
+        const TEST_KEY_ACTION: Cow<'static, str> = Cow::Borrowed("test_key_action");
+
+        handler
+            .bindings
+            .insert_action_binding(
+                TEST_KEY_ACTION,
+                [Button::Key(VirtualKeyCode::Up)].iter().cloned(),
+            )
+            .unwrap();
+        handler.set_actions_blocked(true);
+        handler.send_event(&key_press(104, VirtualKeyCode::Up), &mut events);
+        assert_eq!(handler.action_is_down(&TEST_KEY_ACTION), Some(false));
+        assert!(handler.key_is_down(VirtualKeyCode::Up));
+        assert!(!events
+            .read(&mut reader)
+            .any(|event| *event == InputEvent::ActionPressed(TEST_KEY_ACTION)));
+
+        handler.set_actions_blocked(false);
+        assert_eq!(handler.action_is_down(&TEST_KEY_ACTION), Some(true));
+    }
+
     #[test]
     fn mouse_action_response() {
         // Register an action triggered by a mouse button
@@ -1271,6 +1366,25 @@ mod tests {
         assert!(!handler.button_is_down(Button::Mouse(MouseButton::Left)));
     }
 
+    #[test]
+    fn scroll_delta_is_normalized_to_lines() {
+        let mut handler = InputHandler::new();
+        let mut events = EventChannel::<InputEvent>::new();
+        handler.set_scroll_pixels_per_line(20.0);
+        handler.send_event(&mouse_wheel(0.0, 2.0), &mut events);
+        handler.send_event(&touchpad_scroll(10.0, -10.0), &mut events);
+        assert_eq!(
+            ScrollDelta {
+                lines: (0.0, 2.0),
+                pixels: (10.0, -10.0),
+            },
+            handler.raw_scroll_delta()
+        );
+        assert_eq!((0.5, 1.5), handler.scroll_delta());
+        handler.send_frame_begin();
+        assert!(handler.raw_scroll_delta().is_zero());
+    }
+
     #[test]
     fn basic_mouse_wheel_check() {
         use approx::assert_ulps_eq;
@@ -1378,6 +1492,15 @@ right: `{:?}`",
         }
     }
 
+    fn touchpad_scroll(x: f64, y: f64) -> Event<'static, ()> {
+        Event::DeviceEvent {
+            device_id: unsafe { DeviceId::dummy() },
+            event: DeviceEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(PhysicalPosition { x, y }),
+            },
+        }
+    }
+
     fn mouse_wheel(x: f32, y: f32) -> Event<'static, ()> {
         Event::DeviceEvent {
             device_id: unsafe { DeviceId::dummy() },
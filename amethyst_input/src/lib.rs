@@ -32,6 +32,7 @@ pub use self::{
     event::InputEvent,
     input_handler::{InputHandler, KeyboardModifiersState},
     mouse::MouseAxis,
+    scroll_delta::{ScrollDelta, ScrollMomentum, DEFAULT_SCROLL_PIXELS_PER_LINE},
     scroll_direction::ScrollDirection,
     system::InputSystem,
     util::{
@@ -48,6 +49,7 @@ mod controller;
 mod event;
 mod input_handler;
 mod mouse;
+mod scroll_delta;
 mod scroll_direction;
 mod system;
 mod util;
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of pixels scrolled by a touchpad counted as one line of a mouse wheel, by default.
+pub const DEFAULT_SCROLL_PIXELS_PER_LINE: f32 = 40.0;
+
+/// Scroll of the mouse wheels and touchpads during a frame, as reported by the platform.
+///
+/// Mouse wheels usually report lines, one per notch, while touchpads and some platforms report
+/// pixels. Positive x scrolls right and positive y scrolls up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScrollDelta {
+    /// Scroll reported in lines.
+    pub lines: (f32, f32),
+    /// Scroll reported in pixels.
+    pub pixels: (f32, f32),
+}
+
+impl ScrollDelta {
+    /// Returns the whole scroll in lines, counting `pixels_per_line` pixels as a line.
+    #[must_use]
+    pub fn normalized(&self, pixels_per_line: f32) -> (f32, f32) {
+        (
+            self.lines.0 + self.pixels.0 / pixels_per_line,
+            self.lines.1 + self.pixels.1 / pixels_per_line,
+        )
+    }
+
+    /// Returns whether nothing was scrolled.
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Smooths scroll deltas over a few frames and keeps scrolling after the input stops,
+/// decelerating, like touchpad scrolling on most platforms.
+///
+/// It's fed the scroll of every frame, usually `InputHandler::scroll_delta`, including frames
+/// without scroll, and returns the scroll to apply.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScrollMomentum {
+    /// Time in seconds the scroll takes to mostly catch up with the input, or zero to apply it
+    /// right away.
+    pub smoothing: f32,
+    /// Rate at which the scroll decelerates once the input stops, as the fraction of the speed
+    /// kept after a second, or zero to stop right away.
+    pub momentum: f32,
+    #[serde(skip)]
+    pending: (f32, f32),
+    #[serde(skip)]
+    velocity: (f32, f32),
+}
+
+impl Default for ScrollMomentum {
+    fn default() -> Self {
+        Self::new(0.08, 0.05)
+    }
+}
+
+impl ScrollMomentum {
+    /// Speed under which the scroll stops, in units per second.
+    const MIN_SPEED: f32 = 0.05;
+
+    /// Creates the smoothing of given time constant and momentum.
+    #[must_use]
+    pub fn new(smoothing: f32, momentum: f32) -> Self {
+        Self {
+            smoothing,
+            momentum,
+            pending: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+        }
+    }
+
+    /// Returns whether scroll is still to be applied without further input.
+    #[must_use]
+    pub fn is_moving(&self) -> bool {
+        self.pending != (0.0, 0.0) || self.velocity != (0.0, 0.0)
+    }
+
+    /// Stops the scroll, for instance when the view is moved by other means.
+    pub fn stop(&mut self) {
+        self.pending = (0.0, 0.0);
+        self.velocity = (0.0, 0.0);
+    }
+
+    /// Adds the scroll input of a frame lasting `delta_seconds`, and returns the scroll to apply
+    /// during that frame.
+    pub fn update(&mut self, input: (f32, f32), delta_seconds: f32) -> (f32, f32) {
+        if delta_seconds <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let scrolled = input != (0.0, 0.0);
+        if scrolled {
+            self.velocity = (0.0, 0.0);
+        }
+        self.pending.0 += input.0;
+        self.pending.1 += input.1;
+
+        let caught_up = if self.smoothing > 0.0 {
+            1.0 - (-delta_seconds / self.smoothing).exp()
+        } else {
+            1.0
+        };
+        let mut step = (self.pending.0 * caught_up, self.pending.1 * caught_up);
+        self.pending.0 -= step.0;
+        self.pending.1 -= step.1;
+        if self.pending.0.abs() + self.pending.1.abs() < Self::MIN_SPEED * delta_seconds {
+            step.0 += self.pending.0;
+            step.1 += self.pending.1;
+            self.pending = (0.0, 0.0);
+        }
+
+        if scrolled {
+            if self.momentum > 0.0 {
+                self.velocity = (step.0 / delta_seconds, step.1 / delta_seconds);
+            }
+        } else if self.velocity != (0.0, 0.0) {
+            step.0 += self.velocity.0 * delta_seconds;
+            step.1 += self.velocity.1 * delta_seconds;
+            let kept = self.momentum.powf(delta_seconds);
+            self.velocity = (self.velocity.0 * kept, self.velocity.1 * kept);
+            if self.velocity.0.abs() + self.velocity.1.abs() < Self::MIN_SPEED {
+                self.velocity = (0.0, 0.0);
+            }
+        }
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_ulps_eq;
+
+    use super::*;
+
+    #[test]
+    fn pixels_are_normalized_to_lines() {
+        let delta = ScrollDelta {
+            lines: (0.0, 1.0),
+            pixels: (20.0, -40.0),
+        };
+        assert_eq!((0.5, 0.0), delta.normalized(40.0));
+    }
+
+    #[test]
+    fn smoothing_applies_the_whole_input() {
+        let mut scroll = ScrollMomentum::new(0.1, 0.0);
+        let mut total = scroll.update((0.0, 3.0), 1.0 / 60.0).1;
+        assert!(total < 3.0);
+        for _ in 0..120 {
+            total += scroll.update((0.0, 0.0), 1.0 / 60.0).1;
+        }
+        assert!((total - 3.0).abs() < 1e-5);
+        assert!(!scroll.is_moving());
+    }
+
+    #[test]
+    fn momentum_keeps_scrolling_then_stops() {
+        let mut scroll = ScrollMomentum::new(0.0, 0.1);
+        assert_eq!((0.0, 1.0), scroll.update((0.0, 1.0), 0.1));
+        let glide = scroll.update((0.0, 0.0), 0.1).1;
+        assert_ulps_eq!(1.0, glide);
+        assert!(scroll.update((0.0, 0.0), 0.1).1 < glide);
+        for _ in 0..100 {
+            scroll.update((0.0, 0.0), 0.1);
+        }
+        assert!(!scroll.is_moving());
+    }
+}
@@ -19,12 +19,18 @@ use amethyst_assets::{
     register_asset_type, register_importer, Asset, AssetProcessorSystem, AssetStorage, Format,
     LoadHandle, ProcessableAsset, ProcessingState,
 };
-use amethyst_error::Error;
+use amethyst_error::{format_err, Error};
 pub use fluent::{concurrent::FluentBundle, FluentResource};
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 use unic_langid::langid;
 
+#[cfg(feature = "ui")]
+pub use crate::ui::{LocalizedText, LocalizedTextSystem};
+
+#[cfg(feature = "ui")]
+mod ui;
+
 /// Internal representation of a Locale
 #[derive(Clone, Debug, Serialize, Deserialize, TypeUuid)]
 #[uuid = "442ea0e0-48d8-4a3c-ab36-faba55f2c0db"]
@@ -32,6 +38,9 @@ pub struct LocaleData(pub Vec<u8>);
 register_asset_type!(LocaleData => Locale; AssetProcessorSystem<Locale>);
 
 /// A loaded locale.
+///
+/// It's reprocessed when its `.ftl` file changes while the asset daemon runs. A file failing to
+/// parse is reported as a failed load, and the previous version of the locale stays in use.
 #[allow(missing_debug_implementations)]
 #[derive(TypeUuid)]
 #[uuid = "bf7713bb-6e1f-4873-bf0b-9d7c2253f46a"]
@@ -55,13 +64,14 @@ impl ProcessableAsset for Locale {
     ) -> Result<amethyst_assets::ProcessingState<LocaleData, Locale>, Error> {
         let s = String::from_utf8(data.0)?;
 
-        let resource = FluentResource::try_new(s).expect("Failed to parse locale data");
+        let resource = FluentResource::try_new(s)
+            .map_err(|(_, errors)| format_err!("Failed to parse locale data: {:?}", errors))?;
         let lang_en = langid!("en");
         let mut bundle = FluentBundle::new(vec![lang_en]);
 
         bundle
             .add_resource(resource)
-            .expect("Failed to add resource");
+            .map_err(|errors| format_err!("Failed to add resource: {:?}", errors))?;
 
         Ok(ProcessingState::Loaded(Locale { bundle }))
     }
@@ -0,0 +1,129 @@
+//! Texts of the UI resolved from a locale.
+
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::ecs::{IntoQuery, ParallelRunnable, System, SystemBuilder};
+use amethyst_ui::UiText;
+use fluent::{FluentArgs, FluentValue};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::Locale;
+
+/// Component setting the `UiText` of its entity to a message of a `Locale`.
+///
+/// The text is resolved again whenever the locale is reloaded, so editing a `.ftl` file while
+/// the asset daemon runs updates the texts in-game. Until the locale is loaded, and when it has
+/// no such message, the key is shown instead.
+#[derive(Clone, Debug)]
+pub struct LocalizedText {
+    locale: Handle<Locale>,
+    key: String,
+    args: Vec<(String, String)>,
+    resolved_version: Option<u32>,
+}
+
+impl LocalizedText {
+    /// Creates a text showing the message `key` of `locale`.
+    pub fn new(locale: Handle<Locale>, key: impl Into<String>) -> Self {
+        Self {
+            locale,
+            key: key.into(),
+            args: Vec::new(),
+            resolved_version: None,
+        }
+    }
+
+    /// Adds an argument of the message.
+    #[must_use]
+    pub fn with_arg(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_arg(name, value);
+        self
+    }
+
+    /// Returns the locale of the message.
+    #[must_use]
+    pub fn locale(&self) -> &Handle<Locale> {
+        &self.locale
+    }
+
+    /// Returns the key of the message.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Shows the message `key` of `locale` instead, e.g. to switch the language.
+    pub fn set_message(&mut self, locale: Handle<Locale>, key: impl Into<String>) {
+        self.locale = locale;
+        self.key = key.into();
+        self.resolved_version = None;
+    }
+
+    /// Sets an argument of the message.
+    pub fn set_arg(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.args.iter_mut().find(|(arg, _)| *arg == name) {
+            Some((_, arg_value)) => *arg_value = value,
+            None => self.args.push((name, value)),
+        }
+        self.resolved_version = None;
+    }
+
+    /// Formats the message with the arguments, or returns the key if the locale has no such
+    /// message.
+    fn resolve(&self, locale: &Locale) -> String {
+        locale
+            .bundle
+            .get_message(&self.key)
+            .and_then(|message| message.value)
+            .map(|pattern| {
+                let mut args = FluentArgs::new();
+                for (name, value) in &self.args {
+                    args.add(name.as_str(), FluentValue::from(value.as_str()));
+                }
+                let mut errors = Vec::new();
+                locale
+                    .bundle
+                    .format_pattern(pattern, Some(&args), &mut errors)
+                    .into_owned()
+            })
+            .unwrap_or_else(|| self.key.clone())
+    }
+}
+
+/// System updating the `UiText`s of the `LocalizedText`s when their message changes or their
+/// locale is loaded or reloaded.
+#[derive(Debug)]
+pub struct LocalizedTextSystem;
+
+impl System for LocalizedTextSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("LocalizedTextSystem")
+                .read_resource::<AssetStorage<Locale>>()
+                .with_query(<(&mut LocalizedText, &mut UiText)>::query())
+                .build(|_, world, locales, query| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("localized_text_system");
+
+                    for (localized, text) in query.iter_mut(world) {
+                        match locales.get_asset_with_version(&localized.locale) {
+                            Some((locale, version)) => {
+                                if localized.resolved_version != Some(version) {
+                                    text.text = localized.resolve(locale);
+                                    localized.resolved_version = Some(version);
+                                }
+                            }
+                            None => {
+                                if localized.resolved_version.is_none()
+                                    && text.text != localized.key
+                                {
+                                    text.text = localized.key.clone();
+                                }
+                            }
+                        }
+                    }
+                }),
+        )
+    }
+}
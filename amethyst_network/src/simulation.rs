@@ -8,7 +8,7 @@ mod requirements;
 mod timing;
 mod transport;
 
-pub use events::NetworkSimulationEvent;
+pub use events::{MessageMetadata, NetworkSimulationEvent, ReceiveSequences};
 pub use message::Message;
 pub use requirements::{DeliveryRequirement, UrgencyRequirement};
 pub use timing::NetworkSimulationTime;
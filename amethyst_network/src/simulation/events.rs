@@ -1,18 +1,22 @@
-use std::{io, net::SocketAddr};
+use std::{collections::HashMap, io, net::SocketAddr, time::Instant};
 
 use bytes::Bytes;
 
-use crate::simulation::Message;
+use crate::simulation::{requirements::DeliveryRequirement, Message};
 
 /// Events which can be received from the network.
 #[derive(Debug)]
 pub enum NetworkSimulationEvent {
     // A message was received from a remote client
-    Message(SocketAddr, Bytes),
+    Message(SocketAddr, Bytes, MessageMetadata),
     // A new host has connected to us
     Connect(SocketAddr),
     // A host has disconnected from us
     Disconnect(SocketAddr),
+    // Nothing was received from a host within the idle timeout, so it's considered gone
+    Timeout(SocketAddr),
+    // The connection to a host was lost, e.g. its socket was closed
+    ConnectionLost(SocketAddr),
     // An error occurred while receiving a message.
     RecvError(io::Error),
     // An error occurred while sending a message.
@@ -20,3 +24,72 @@ pub enum NetworkSimulationEvent {
     // An error occurred while managing connections.
     ConnectionError(io::Error, Option<SocketAddr>),
 }
+
+/// Information about a received message, so systems such as interpolation or lag compensation can
+/// order the messages without adding it to every payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageMetadata {
+    /// Time at which the transport received the message.
+    pub received_at: Instant,
+    /// Delivery guarantee of the channel the message was received through.
+    pub delivery: DeliveryRequirement,
+    /// Index of the message among the messages received from its source, starting at 0.
+    pub sequence: u64,
+}
+
+/// Numbers the messages received from each source, for the `MessageMetadata` of the messages.
+///
+/// Transports own one and reset the numbering of a source when it disconnects.
+#[derive(Debug, Default)]
+pub struct ReceiveSequences {
+    next: HashMap<SocketAddr, u64>,
+}
+
+impl ReceiveSequences {
+    /// Returns the metadata of a message just received from `source`.
+    pub fn receive(
+        &mut self,
+        source: SocketAddr,
+        delivery: DeliveryRequirement,
+    ) -> MessageMetadata {
+        let next = self.next.entry(source).or_insert(0);
+        let sequence = *next;
+        *next += 1;
+        MessageMetadata {
+            received_at: Instant::now(),
+            delivery,
+            sequence,
+        }
+    }
+
+    /// Restarts the numbering of the messages received from `source`.
+    pub fn reset(&mut self, source: SocketAddr) {
+        self.next.remove(&source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_are_numbered_per_source() {
+        let mut sequences = ReceiveSequences::default();
+        let (a, b) = (
+            "127.0.0.1:3000".parse().unwrap(),
+            "127.0.0.1:3001".parse().unwrap(),
+        );
+        let unreliable = DeliveryRequirement::Unreliable;
+
+        assert_eq!(0, sequences.receive(a, unreliable).sequence);
+        assert_eq!(1, sequences.receive(a, unreliable).sequence);
+        assert_eq!(0, sequences.receive(b, unreliable).sequence);
+
+        let first = sequences.receive(a, unreliable);
+        let second = sequences.receive(a, unreliable);
+        assert!(first.received_at <= second.received_at);
+
+        sequences.reset(a);
+        assert_eq!(0, sequences.receive(a, unreliable).sequence);
+    }
+}
@@ -11,17 +11,22 @@ use amethyst_core::{
 use amethyst_error::Error;
 use bytes::Bytes;
 pub use laminar::{Config as LaminarConfig, ErrorKind, Socket as LaminarSocket};
-use laminar::{Packet, SocketEvent};
+use laminar::{DeliveryGuarantee, OrderingGuarantee, Packet, SocketEvent};
 use log::error;
 
 use crate::simulation::{
-    events::NetworkSimulationEvent,
+    events::{NetworkSimulationEvent, ReceiveSequences},
     requirements::DeliveryRequirement,
     timing::{NetworkSimulationTime, NetworkSimulationTimeSystem},
     transport::TransportResource,
 };
 
 /// Use this network bundle to add the laminar transport layer to your game.
+///
+/// The keep-alives and the idle timeout are the `heartbeat_interval` and
+/// `idle_connection_timeout` of the `LaminarConfig` the socket is bound with. A peer timing out
+/// sends a `NetworkSimulationEvent::Timeout`, and a dropped connection a
+/// `NetworkSimulationEvent::ConnectionLost`.
 pub struct LaminarNetworkBundle {
     socket: Option<LaminarSocket>,
 }
@@ -159,17 +164,29 @@ impl System for LaminarNetworkRecvSystem {
                 .write_resource::<LaminarSocketResource>()
                 .write_resource::<EventChannel<NetworkSimulationEvent>>()
                 .build(move |_commands, _world, (socket, event_channel), _| {
-                    if let Some(socket) = socket.get_mut() {
+                    let LaminarSocketResource {
+                        ref mut socket,
+                        ref mut sequences,
+                    } = **socket;
+                    if let Some(socket) = socket {
                         while let Some(event) = socket.recv() {
                             let event = match event {
                                 SocketEvent::Packet(packet) => {
+                                    let metadata =
+                                        sequences.receive(packet.addr(), delivery_of(&packet));
                                     NetworkSimulationEvent::Message(
                                         packet.addr(),
                                         Bytes::copy_from_slice(packet.payload()),
+                                        metadata,
                                     )
                                 }
-                                SocketEvent::Disconnect(addr) | SocketEvent::Timeout(addr) => {
-                                    NetworkSimulationEvent::Disconnect(addr)
+                                SocketEvent::Timeout(addr) => {
+                                    sequences.reset(addr);
+                                    NetworkSimulationEvent::Timeout(addr)
+                                }
+                                SocketEvent::Disconnect(addr) => {
+                                    sequences.reset(addr);
+                                    NetworkSimulationEvent::ConnectionLost(addr)
                                 }
                                 SocketEvent::Connect(addr) => NetworkSimulationEvent::Connect(addr),
                             };
@@ -181,17 +198,38 @@ impl System for LaminarNetworkRecvSystem {
     }
 }
 
+/// Returns the delivery requirement a packet was sent with.
+fn delivery_of(packet: &Packet) -> DeliveryRequirement {
+    match (packet.delivery_guarantee(), packet.order_guarantee()) {
+        (DeliveryGuarantee::Unreliable, OrderingGuarantee::Sequenced(stream_id)) => {
+            DeliveryRequirement::UnreliableSequenced(stream_id)
+        }
+        (DeliveryGuarantee::Unreliable, _) => DeliveryRequirement::Unreliable,
+        (DeliveryGuarantee::Reliable, OrderingGuarantee::None) => DeliveryRequirement::Reliable,
+        (DeliveryGuarantee::Reliable, OrderingGuarantee::Sequenced(stream_id)) => {
+            DeliveryRequirement::ReliableSequenced(stream_id)
+        }
+        (DeliveryGuarantee::Reliable, OrderingGuarantee::Ordered(stream_id)) => {
+            DeliveryRequirement::ReliableOrdered(stream_id)
+        }
+    }
+}
+
 /// Resource that owns the Laminar socket.
 #[derive(Default)]
 pub struct LaminarSocketResource {
     socket: Option<LaminarSocket>,
+    sequences: ReceiveSequences,
 }
 
 impl LaminarSocketResource {
     /// Creates a new instance of the `UdpSocketResource`.
     #[must_use]
     pub fn new(socket: Option<LaminarSocket>) -> Self {
-        Self { socket }
+        Self {
+            socket,
+            sequences: ReceiveSequences::default(),
+        }
     }
 
     /// Returns a reference to the socket if there is one configured.
@@ -17,7 +17,7 @@ use bytes::Bytes;
 use log::warn;
 
 use crate::simulation::{
-    events::NetworkSimulationEvent,
+    events::{NetworkSimulationEvent, ReceiveSequences},
     message::Message,
     requirements::DeliveryRequirement,
     timing::{NetworkSimulationTime, NetworkSimulationTimeSystem},
@@ -99,6 +99,7 @@ impl System for TcpStreamManagementSystem {
                                 s.set_nonblocking(true).expect("Setting non-blocking mode");
                                 s.set_nodelay(true).expect("Setting nodelay");
                                 net.streams.insert(message.destination, (true, s));
+                                net.sequences.reset(message.destination);
                             }
                         });
 
@@ -136,6 +137,7 @@ impl System for TcpConnectionListenerSystem {
                                         .expect("Setting nonblocking mode");
                                     stream.set_nodelay(true).expect("Setting nodelay");
                                     resource.streams.insert(addr, (true, stream));
+                                    resource.sequences.reset(addr);
                                     event_channel
                                         .single_write(NetworkSimulationEvent::Connect(addr));
                                 }
@@ -234,11 +236,16 @@ impl System for TcpNetworkRecvSystem {
                             match stream.read(&mut resource.recv_buffer) {
                                 Ok(recv_len) => {
                                     if recv_len > 0 {
+                                        let metadata = resource.sequences.receive(
+                                            peer_addr,
+                                            DeliveryRequirement::ReliableOrdered(None),
+                                        );
                                         let event = NetworkSimulationEvent::Message(
                                             peer_addr,
                                             Bytes::copy_from_slice(
                                                 &resource.recv_buffer[..recv_len],
                                             ),
+                                            metadata,
                                         );
                                         event_channel.single_write(event);
                                     } else {
@@ -272,6 +279,7 @@ pub struct TcpNetworkResource {
     listener: Option<TcpListener>,
     streams: HashMap<SocketAddr, (bool, TcpStream)>,
     recv_buffer: Vec<u8>,
+    sequences: ReceiveSequences,
 }
 
 impl TcpNetworkResource {
@@ -281,6 +289,7 @@ impl TcpNetworkResource {
             listener,
             streams: HashMap::new(),
             recv_buffer: vec![0; recv_buffer_size_bytes],
+            sequences: ReceiveSequences::default(),
         }
     }
 
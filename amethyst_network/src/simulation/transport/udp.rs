@@ -1,6 +1,11 @@
 //! Network systems implementation backed by the UDP network protocol.
 
-use std::{io, net::UdpSocket};
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
 
 use amethyst_core::{
     ecs::{
@@ -12,17 +17,42 @@ use amethyst_error::Error;
 use bytes::Bytes;
 
 use crate::simulation::{
-    events::NetworkSimulationEvent,
+    events::{NetworkSimulationEvent, ReceiveSequences},
     requirements::DeliveryRequirement,
     timing::{NetworkSimulationTime, NetworkSimulationTimeSystem},
     transport::TransportResource,
 };
 
 /// Use this network bundle to add the UDP transport layer to your game.
+///
+/// UDP has no connections, so peers silently disappearing are only noticed with an idle timeout,
+/// after which a `NetworkSimulationEvent::Timeout` is sent. Keep-alives, empty datagrams which
+/// aren't sent as messages, keep the peers from timing out while no message is exchanged.
 #[derive(new)]
 pub struct UdpNetworkBundle {
     socket: Option<UdpSocket>,
     recv_buffer_size_bytes: usize,
+    #[new(default)]
+    keep_alive: Option<Duration>,
+    #[new(default)]
+    idle_timeout: Option<Duration>,
+}
+
+impl UdpNetworkBundle {
+    /// Sends a keep-alive to the peers no message was sent to for `interval`.
+    #[must_use]
+    pub fn with_keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Forgets the peers nothing was received from for `timeout`, sending a
+    /// `NetworkSimulationEvent::Timeout`.
+    #[must_use]
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
 }
 
 impl SystemBundle for UdpNetworkBundle {
@@ -32,10 +62,10 @@ impl SystemBundle for UdpNetworkBundle {
         resources: &mut Resources,
         builder: &mut DispatcherBuilder,
     ) -> Result<(), Error> {
-        resources.insert(UdpSocketResource::new(
-            self.socket.take(),
-            self.recv_buffer_size_bytes,
-        ));
+        let mut socket = UdpSocketResource::new(self.socket.take(), self.recv_buffer_size_bytes);
+        socket.peers.keep_alive = self.keep_alive;
+        socket.peers.idle_timeout = self.idle_timeout;
+        resources.insert(socket);
 
         builder
             .add_system(NetworkSimulationTimeSystem)
@@ -59,7 +89,14 @@ impl System for UdpNetworkSendSystem {
                 .write_resource::<EventChannel<NetworkSimulationEvent>>()
                 .build(
                     move |_commands, _world, (transport, socket, sim_time, channel), _| {
-                        if let Some(socket) = socket.get_mut() {
+                        let UdpSocketResource {
+                            ref mut socket,
+                            ref mut peers,
+                            ref mut sequences,
+                            ..
+                        } = **socket;
+                        if let Some(socket) = socket {
+                            let now = Instant::now();
                             let messages = transport
                                 .drain_messages_to_send(|_| sim_time.should_send_message_now());
                             for message in messages {
@@ -72,6 +109,8 @@ impl System for UdpNetworkSendSystem {
                                             channel.single_write(
                                                 NetworkSimulationEvent::SendError(e, message),
                                             );
+                                        } else {
+                                            peers.sent(message.destination, now);
                                         }
                                     }
                                     delivery => {
@@ -82,6 +121,29 @@ impl System for UdpNetworkSendSystem {
                                     }
                                 }
                             }
+
+                            for address in peers.keep_alive_due(now) {
+                                if let Err(e) = socket.send_to(&[], address) {
+                                    match e.kind() {
+                                        io::ErrorKind::ConnectionRefused
+                                        | io::ErrorKind::ConnectionReset => {
+                                            peers.remove(address);
+                                            sequences.reset(address);
+                                            channel.single_write(
+                                                NetworkSimulationEvent::ConnectionLost(address),
+                                            );
+                                        }
+                                        _ => {
+                                            channel.single_write(
+                                                NetworkSimulationEvent::ConnectionError(
+                                                    e,
+                                                    Some(address),
+                                                ),
+                                            );
+                                        }
+                                    }
+                                }
+                            }
                         }
                     },
                 ),
@@ -102,14 +164,25 @@ impl System for UdpNetworkReceiveSystem {
                     let UdpSocketResource {
                         ref mut socket,
                         ref mut recv_buffer,
+                        ref mut sequences,
+                        ref mut peers,
                     } = **socket;
                     if let Some(socket) = socket {
+                        let now = Instant::now();
                         loop {
                             match socket.recv_from(recv_buffer) {
                                 Ok((recv_len, address)) => {
+                                    peers.received(address, now);
+                                    if recv_len == 0 {
+                                        // Keep-alive
+                                        continue;
+                                    }
+                                    let metadata = sequences
+                                        .receive(address, DeliveryRequirement::Unreliable);
                                     let event = NetworkSimulationEvent::Message(
                                         address,
                                         Bytes::copy_from_slice(&recv_buffer[..recv_len]),
+                                        metadata,
                                     );
                                     // TODO: Handle other types of events.
                                     event_channel.single_write(event);
@@ -123,6 +196,11 @@ impl System for UdpNetworkReceiveSystem {
                                 }
                             }
                         }
+
+                        for address in peers.timed_out(now) {
+                            sequences.reset(address);
+                            event_channel.single_write(NetworkSimulationEvent::Timeout(address));
+                        }
                     }
                 }),
         )
@@ -134,6 +212,8 @@ impl System for UdpNetworkReceiveSystem {
 pub struct UdpSocketResource {
     socket: Option<UdpSocket>,
     recv_buffer: Vec<u8>,
+    sequences: ReceiveSequences,
+    peers: UdpPeers,
 }
 
 impl UdpSocketResource {
@@ -141,6 +221,8 @@ impl UdpSocketResource {
         Self {
             socket,
             recv_buffer: vec![0; recv_buffer_size_bytes],
+            sequences: ReceiveSequences::default(),
+            peers: UdpPeers::default(),
         }
     }
 }
@@ -166,4 +248,129 @@ impl UdpSocketResource {
     pub fn drop_socket(&mut self) {
         self.socket = None;
     }
+
+    /// Sets the interval after which a keep-alive is sent to the peers no message was sent to,
+    /// or disables the keep-alives.
+    pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
+        self.peers.keep_alive = interval;
+    }
+
+    /// Sets the time after which the peers nothing was received from are forgotten, or disables
+    /// the timeouts.
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.peers.idle_timeout = timeout;
+    }
+
+    /// Returns the addresses of the peers tracked for the keep-alives and timeouts.
+    #[must_use]
+    pub fn peers(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.peers.peers.keys().copied()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct UdpPeer {
+    last_received: Instant,
+    last_sent: Instant,
+}
+
+/// Peers of the socket, tracked while keep-alives or timeouts are enabled.
+#[derive(Debug, Default)]
+struct UdpPeers {
+    keep_alive: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    peers: HashMap<SocketAddr, UdpPeer>,
+}
+
+impl UdpPeers {
+    fn is_tracking(&self) -> bool {
+        self.keep_alive.is_some() || self.idle_timeout.is_some()
+    }
+
+    fn peer(&mut self, address: SocketAddr, now: Instant) -> &mut UdpPeer {
+        self.peers.entry(address).or_insert(UdpPeer {
+            last_received: now,
+            last_sent: now,
+        })
+    }
+
+    fn received(&mut self, address: SocketAddr, now: Instant) {
+        if self.is_tracking() {
+            self.peer(address, now).last_received = now;
+        }
+    }
+
+    fn sent(&mut self, address: SocketAddr, now: Instant) {
+        if self.is_tracking() {
+            self.peer(address, now).last_sent = now;
+        }
+    }
+
+    fn remove(&mut self, address: SocketAddr) {
+        self.peers.remove(&address);
+    }
+
+    /// Returns the peers a keep-alive is due to, considering it sent.
+    fn keep_alive_due(&mut self, now: Instant) -> Vec<SocketAddr> {
+        let interval = match self.keep_alive {
+            Some(interval) => interval,
+            None => return Vec::new(),
+        };
+        self.peers
+            .iter_mut()
+            .filter(|(_, peer)| now.saturating_duration_since(peer.last_sent) >= interval)
+            .map(|(address, peer)| {
+                peer.last_sent = now;
+                *address
+            })
+            .collect()
+    }
+
+    /// Removes and returns the peers which timed out.
+    fn timed_out(&mut self, now: Instant) -> Vec<SocketAddr> {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return Vec::new(),
+        };
+        let timed_out: Vec<SocketAddr> = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| now.saturating_duration_since(peer.last_received) >= timeout)
+            .map(|(address, _)| *address)
+            .collect();
+        for address in &timed_out {
+            self.peers.remove(address);
+        }
+        timed_out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silent_peers_time_out() {
+        let mut peers = UdpPeers {
+            keep_alive: Some(Duration::from_secs(1)),
+            idle_timeout: Some(Duration::from_secs(5)),
+            ..UdpPeers::default()
+        };
+        let (a, b) = (
+            "127.0.0.1:3000".parse().unwrap(),
+            "127.0.0.1:3001".parse().unwrap(),
+        );
+        let start = Instant::now();
+        peers.sent(a, start);
+        peers.received(b, start);
+
+        let later = start + Duration::from_secs(2);
+        peers.received(a, later);
+        assert_eq!(2, peers.keep_alive_due(later).len());
+        assert!(peers.keep_alive_due(later).is_empty());
+
+        let timeout = start + Duration::from_secs(5);
+        assert_eq!(vec![b], peers.timed_out(timeout));
+        assert_eq!(vec![a], peers.timed_out(later + Duration::from_secs(5)));
+    }
 }
@@ -0,0 +1,18 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(location = 0) in vec2 tex_coord;
+layout(location = 0) out vec4 out_color;
+
+vec3 linear_to_srgb(vec3 linear) {
+    vec3 low = linear * 12.92;
+    vec3 high = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, vec3(lessThanEqual(linear, vec3(0.0031308))));
+}
+
+void main() {
+    // The target stores values as they are written, so encode them for display here.
+    vec4 color = texture(source, tex_coord);
+    out_color = vec4(linear_to_srgb(clamp(color.rgb, 0.0, 1.0)), color.a);
+}
@@ -0,0 +1,23 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+// Matrices applied to the linear colors of each quarter of the screen.
+layout(std140, set = 1, binding = 0) uniform ColorBlindArgs {
+    uniform mat4 top_left;
+    uniform mat4 top_right;
+    uniform mat4 bottom_left;
+    uniform mat4 bottom_right;
+};
+
+layout(location = 0) in vec2 tex_coord;
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    bool right = tex_coord.x >= 0.5;
+    mat4 matrix = tex_coord.y < 0.5
+        ? (right ? top_right : top_left)
+        : (right ? bottom_right : bottom_left);
+    out_color = vec4(clamp((matrix * vec4(color.rgb, 0.0)).rgb, 0.0, 1.0), color.a);
+}
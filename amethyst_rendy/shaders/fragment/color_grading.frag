@@ -0,0 +1,39 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(std140, set = 1, binding = 0) uniform ColorGradingArgs {
+    uniform float intensity;
+};
+
+// Strip of `size` slices of `size * size` texels, blue increasing across the slices.
+layout(set = 2, binding = 0) uniform sampler2D lut;
+
+layout(location = 0) in vec2 tex_coord;
+layout(location = 0) out vec4 out_color;
+
+vec3 linear_to_srgb(vec3 linear) {
+    vec3 low = linear * 12.92;
+    vec3 high = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, vec3(lessThanEqual(linear, vec3(0.0031308))));
+}
+
+vec3 grade(vec3 srgb) {
+    float size = float(textureSize(lut, 0).y);
+    vec3 scaled = clamp(srgb, 0.0, 1.0) * (size - 1.0);
+    float slice = floor(scaled.b);
+    float next_slice = min(slice + 1.0, size - 1.0);
+
+    // Sample texel centers so that neighbouring slices don't bleed into each other.
+    vec2 uv = vec2((scaled.r + 0.5) / (size * size), (scaled.g + 0.5) / size);
+    vec3 lower = texture(lut, uv + vec2(slice / size, 0.0)).rgb;
+    vec3 upper = texture(lut, uv + vec2(next_slice / size, 0.0)).rgb;
+    return mix(lower, upper, scaled.b - slice);
+}
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    // Lookup tables are authored on display colors, the source and lut are decoded from sRGB.
+    vec3 graded = grade(linear_to_srgb(color.rgb));
+    out_color = vec4(mix(color.rgb, graded, intensity), color.a);
+}
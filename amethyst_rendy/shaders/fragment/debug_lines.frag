@@ -1,11 +1,29 @@
 #version 450
 
+layout(std140, set = 1, binding = 0) uniform DebugLinesArgs {
+    uniform vec2 pixel_size;
+    uniform float line_width;
+    uniform float anti_aliasing;
+};
+
 layout(location = 0) in VertexData {
     vec4 color;
+    noperspective float across;
+    noperspective float along;
+    flat float half_width;
+    flat vec2 dashes;
 } vertex;
 
 layout(location = 0) out vec4 out_color;
 
 void main() {
-    out_color = vertex.color;
+    if (vertex.dashes.x > 0.0 && mod(vertex.along, vertex.dashes.x + vertex.dashes.y) > vertex.dashes.x) {
+        discard;
+    }
+
+    float coverage = 1.0;
+    if (anti_aliasing > 0.0) {
+        coverage = clamp(vertex.half_width + 0.5 - abs(vertex.across), 0.0, 1.0);
+    }
+    out_color = vec4(vertex.color.rgb, vertex.color.a * coverage);
 }
@@ -0,0 +1,26 @@
+#version 450
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+layout(set = 2, binding = 0) uniform sampler2D scene_depth;
+
+layout(location = 0) in VertexData {
+    flat mat4 clip_to_decal;
+    flat vec4 color;
+} vertex;
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    // Reconstruct the position of the scene surface behind this pixel in the unit box of the
+    // decal, and project the texture along its Z axis.
+    vec2 screen_uv = gl_FragCoord.xy / vec2(textureSize(scene_depth, 0));
+    float depth = texture(scene_depth, screen_uv).r;
+    vec4 position = vertex.clip_to_decal * vec4(screen_uv * 2.0 - 1.0, depth, 1.0);
+    vec3 local = position.xyz / position.w;
+    if (any(greaterThan(abs(local), vec3(0.5)))) {
+        discard;
+    }
+
+    vec2 tex_uv = vec2(local.x + 0.5, 0.5 - local.y);
+    out_color = texture(albedo, tex_uv) * vertex.color;
+}
@@ -0,0 +1,57 @@
+// Image based lighting shader definition.
+// Set 3.
+// Keep in sync with amethyst_rendy/src/submodules/environment_map.rs
+
+#define MAX_REFLECTION_PROBES 4
+
+struct ReflectionProbe {
+    vec3 center;
+    float radius;
+    float blend_distance;
+    float intensity;
+};
+
+layout(set = 3, binding = 0) uniform samplerCube irradiance_map;
+layout(set = 3, binding = 1) uniform samplerCube specular_map;
+layout(std140, set = 3, binding = 2) uniform EnvironmentMapArgs {
+    float environment_intensity;
+    int reflection_probe_count;
+    ReflectionProbe reflection_probes[MAX_REFLECTION_PROBES];
+};
+layout(set = 3, binding = 3) uniform samplerCube reflection_probe_map_0;
+layout(set = 3, binding = 4) uniform samplerCube reflection_probe_map_1;
+layout(set = 3, binding = 5) uniform samplerCube reflection_probe_map_2;
+layout(set = 3, binding = 6) uniform samplerCube reflection_probe_map_3;
+
+// Samples a prefiltered specular cubemap, rougher surfaces sampling lower mip levels.
+vec3 sample_specular(samplerCube map, vec3 direction, float roughness) {
+    float lod = roughness * float(textureQueryLevels(map) - 1);
+    return textureLod(map, direction, lod).rgb;
+}
+
+vec3 sample_reflection_probe(int i, vec3 direction, float roughness) {
+    switch (i) {
+        case 0: return sample_specular(reflection_probe_map_0, direction, roughness);
+        case 1: return sample_specular(reflection_probe_map_1, direction, roughness);
+        case 2: return sample_specular(reflection_probe_map_2, direction, roughness);
+        default: return sample_specular(reflection_probe_map_3, direction, roughness);
+    }
+}
+
+// Specular light reflected towards `direction` at `position`. The reflection probes covering
+// the position are blended in order, the environment map filling the remaining weight.
+vec3 environment_specular(vec3 position, vec3 direction, float roughness) {
+    vec3 specular = vec3(0.0);
+    float weight = 0.0;
+    for (int i = 0; i < reflection_probe_count && weight < 1.0; i++) {
+        ReflectionProbe probe = reflection_probes[i];
+        float probe_distance = length(position - probe.center);
+        float probe_weight = clamp((probe.radius - probe_distance) / max(probe.blend_distance, 0.0001), 0.0, 1.0);
+        probe_weight = min(probe_weight, 1.0 - weight);
+        if (probe_weight > 0.0) {
+            specular += probe_weight * probe.intensity * sample_reflection_probe(i, direction, roughness);
+            weight += probe_weight;
+        }
+    }
+    return specular + (1.0 - weight) * environment_intensity * sample_specular(specular_map, direction, roughness);
+}
@@ -0,0 +1,30 @@
+// Shading of `flat.frag`, shared with its order-independent transparency variant.
+
+#include "math.frag"
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+    float time;
+};
+
+layout(std140, set = 1, binding = 0) uniform Material {
+    UvOffset uv_offset;
+    float alpha_cutoff;
+    UvAnimation uv_animation;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedo;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+vec4 shade() {
+    vec4 albedo = texture(albedo, tex_coords(animate_tex_coords(vertex.tex_coord, uv_animation, time), uv_offset));
+    if(albedo.w < alpha_cutoff) discard;
+    return albedo * vertex.color;
+}
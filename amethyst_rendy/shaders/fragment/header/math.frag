@@ -15,6 +15,24 @@ float tex_coord(float coord, vec2 offset) {
 vec2 tex_coords(vec2 coord, UvOffset offset) {
     return vec2(tex_coord(coord.x, offset.u_offset), tex_coord(coord.y, offset.v_offset));
 }
+
+struct UvAnimation {
+    vec2 scroll;
+    ivec2 grid;
+    int frames;
+    float frames_per_second;
+};
+
+// Scrolls the coordinates, then maps them to the current cell of the flipbook.
+vec2 animate_tex_coords(vec2 coord, UvAnimation animation, float time) {
+    coord += animation.scroll * time;
+    if (animation.frames > 1) {
+        int frame = int(time * animation.frames_per_second) % animation.frames;
+        vec2 cell = vec2(frame % animation.grid.x, frame / animation.grid.x);
+        coord = (fract(coord) + cell) / vec2(animation.grid);
+    }
+    return coord;
+}
  
 vec3 schlick_fresnel(float HdotV, vec3 fresnel_base) {
     return fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
@@ -0,0 +1,16 @@
+// Weighted blended order-independent transparency, by McGuire and Bavoil.
+//
+// The premultiplied colors are summed into the accumulation target, weighted to favour the
+// fragments closest to the camera, and the coverage of the fragments is multiplied into the
+// revealage target. Both are resolved over the opaque scene by `oit_composite.frag`.
+
+layout(location = 0) out vec4 out_accumulation;
+layout(location = 1) out float out_revealage;
+
+void write_order_independent(vec4 color) {
+    // Reversed depth, 1.0 on the near plane.
+    float depth = gl_FragCoord.z;
+    float weight = color.a * clamp(3e3 * depth * depth * depth, 1e-2, 3e3);
+    out_accumulation = color * weight;
+    out_revealage = color.a;
+}
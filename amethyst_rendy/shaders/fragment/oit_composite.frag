@@ -0,0 +1,18 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform sampler2D accumulation;
+layout(set = 0, binding = 1) uniform sampler2D revealage;
+
+layout(location = 0) in vec2 tex_coord;
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+    float revealed = texelFetch(revealage, texel, 0).r;
+    if (revealed >= 1.0) discard;
+
+    vec4 accumulated = texelFetch(accumulation, texel, 0);
+    vec3 average = accumulated.rgb / clamp(accumulated.a, 1e-4, 5e4);
+    // Blended with the opaque scene as `average * (1 - revealed) + scene * revealed`.
+    out_color = vec4(average, revealed);
+}
@@ -0,0 +1,9 @@
+#version 450
+
+#include "header/pbr_shading.frag"
+
+#include "header/oit.frag"
+
+void main() {
+    write_order_independent(shade());
+}
@@ -0,0 +1,23 @@
+#version 450
+
+layout(early_fragment_tests) in;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec2 tex_coord;
+} vertex;
+
+layout(location = 0) out vec4 out_color;
+
+layout(std140, set = 1, binding = 0) uniform _ {
+    vec3 nadir_color;
+    vec3 zenith_color;
+    float intensity;
+};
+
+layout(set = 2, binding = 0) uniform samplerCube sky;
+
+void main() {
+    vec3 direction = normalize(vertex.position.xyz);
+    out_color = vec4(texture(sky, direction).rgb * intensity, 1.0f);
+}
@@ -0,0 +1,108 @@
+#version 450
+
+// Keep in sync with amethyst_rendy/src/submodules/light2d.rs
+#define MAX_LIGHTS_2D 32
+#define MAX_OCCLUDERS_2D 128
+
+struct Light2D {
+    vec3 position;
+    float radius;
+    vec3 color;
+    int shadows;
+};
+
+struct Occluder2D {
+    vec4 inverse_axes;
+    vec2 center;
+};
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+layout(set = 2, binding = 0) uniform sampler2D normal_map;
+
+layout(std140, set = 3, binding = 0) uniform Environment2D {
+    vec3 ambient_color;
+    int light_count;
+    int occluder_count;
+};
+
+layout(std140, set = 3, binding = 1) uniform Lights2D {
+    Light2D lights[MAX_LIGHTS_2D];
+};
+
+layout(std140, set = 3, binding = 2) uniform Occluders2D {
+    Occluder2D occluders[MAX_OCCLUDERS_2D];
+};
+
+layout(location = 0) in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    vec2 position;
+    vec2 tangent;
+    vec2 bitangent;
+} vertex;
+layout(location = 0) out vec4 out_color;
+
+// Whether the segment from a fragment to a light enters the quad of a shadow casting sprite.
+// Fragments inside a quad aren't shadowed by it.
+bool occluded(vec2 from, vec2 to) {
+    for (int i = 0; i < occluder_count; i++) {
+        Occluder2D occluder = occluders[i];
+        vec2 a = from - occluder.center;
+        vec2 b = to - occluder.center;
+        a = vec2(dot(occluder.inverse_axes.xy, a), dot(occluder.inverse_axes.zw, a));
+        b = vec2(dot(occluder.inverse_axes.xy, b), dot(occluder.inverse_axes.zw, b));
+
+        vec2 dir = b - a;
+        dir = vec2(
+            abs(dir.x) < 1e-6 ? 1e-6 : dir.x,
+            abs(dir.y) < 1e-6 ? 1e-6 : dir.y
+        );
+        vec2 t0 = (vec2(-0.5) - a) / dir;
+        vec2 t1 = (vec2(0.5) - a) / dir;
+        vec2 t_min = min(t0, t1);
+        vec2 t_max = max(t0, t1);
+        float enter = max(t_min.x, t_min.y);
+        float exit = min(t_max.x, t_max.y);
+        if (enter > 0.0 && enter < 1.0 && enter <= exit) {
+            return true;
+        }
+    }
+    return false;
+}
+
+void main() {
+    vec4 color = texture(albedo, vertex.tex_uv) * vertex.color;
+    if (color.a == 0.0) {
+        discard;
+    }
+
+    vec3 tangent_normal = texture(normal_map, vertex.tex_uv).rgb * 2.0 - 1.0;
+    vec3 normal = normalize(vec3(
+        tangent_normal.x * vertex.tangent + tangent_normal.y * vertex.bitangent,
+        tangent_normal.z
+    ));
+
+    vec3 lighting = ambient_color;
+    for (int i = 0; i < light_count; i++) {
+        Light2D light = lights[i];
+        vec3 to_light;
+        float attenuation = 1.0;
+        if (light.radius > 0.0) {
+            vec2 delta = light.position.xy - vertex.position;
+            float falloff = clamp(1.0 - length(delta) / light.radius, 0.0, 1.0);
+            attenuation = falloff * falloff;
+            if (attenuation <= 0.0) {
+                continue;
+            }
+            if (light.shadows != 0 && occluded(vertex.position, light.position.xy)) {
+                continue;
+            }
+            to_light = normalize(vec3(delta, light.position.z));
+        } else {
+            to_light = light.position;
+        }
+        lighting += light.color * attenuation * max(dot(normal, to_light), 0.0);
+    }
+
+    out_color = vec4(color.rgb * lighting, color.a);
+}
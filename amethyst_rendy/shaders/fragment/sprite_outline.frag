@@ -0,0 +1,46 @@
+#version 450
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+
+layout(location = 0) in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    flat vec4 tex_bounds;
+    flat vec2 tex_step;
+} vertex;
+layout(location = 0) out vec4 out_color;
+
+const vec2 directions[8] = vec2[](
+    vec2(1.0, 0.0),
+    vec2(-1.0, 0.0),
+    vec2(0.0, 1.0),
+    vec2(0.0, -1.0),
+    vec2(0.7071, 0.7071),
+    vec2(-0.7071, 0.7071),
+    vec2(0.7071, -0.7071),
+    vec2(-0.7071, -0.7071)
+);
+
+// Alpha of the sprite, transparent outside of its region of the sheet.
+float alpha_at(vec2 uv) {
+    if (any(lessThan(uv, vertex.tex_bounds.xy)) || any(greaterThan(uv, vertex.tex_bounds.zw))) {
+        return 0.0;
+    }
+    return texture(albedo, uv).a;
+}
+
+void main() {
+    if (alpha_at(vertex.tex_uv) >= 0.5) {
+        discard;
+    }
+
+    float edge = 0.0;
+    for (int i = 0; i < 8; i++) {
+        edge = max(edge, alpha_at(vertex.tex_uv + directions[i] * vertex.tex_step));
+        edge = max(edge, alpha_at(vertex.tex_uv + directions[i] * vertex.tex_step * 0.5));
+    }
+    if (edge < 0.5) {
+        discard;
+    }
+    out_color = vertex.color;
+}
@@ -0,0 +1,44 @@
+#version 450
+
+const uint OPERATOR_LINEAR = 0;
+const uint OPERATOR_REINHARD = 1;
+const uint OPERATOR_ACES = 2;
+
+layout(set = 0, binding = 0) uniform sampler2D hdr;
+
+layout(std140, set = 1, binding = 0) uniform TonemapArgs {
+    uniform float exposure;
+    uniform uint operator;
+};
+
+layout(location = 0) in vec2 tex_coord;
+layout(location = 0) out vec4 out_color;
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+vec3 reinhard(vec3 x) {
+    return x / (1.0 + x);
+}
+
+void main() {
+    vec4 color = texture(hdr, tex_coord);
+    vec3 exposed = color.rgb * exposure;
+
+    vec3 mapped;
+    if (operator == OPERATOR_ACES) {
+        mapped = aces(exposed);
+    } else if (operator == OPERATOR_REINHARD) {
+        mapped = reinhard(exposed);
+    } else {
+        mapped = clamp(exposed, 0.0, 1.0);
+    }
+    out_color = vec4(mapped, color.a);
+}
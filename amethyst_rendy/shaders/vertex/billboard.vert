@@ -0,0 +1,54 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+    uniform float time;
+};
+
+layout(std140, set = 2, binding = 0) uniform WindArgs {
+    vec2 wind_direction;
+    float wind_strength;
+    float wind_frequency;
+    float wind_wavelength;
+};
+
+// Billboard placement.
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec2 size;
+layout(location = 2) in vec4 color;
+layout(location = 3) in float sway;
+
+layout(location = 0) out VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+const vec2 corners[4] = vec2[](
+    vec2(0.5, 0.0), // Right bottom
+    vec2(-0.5, 0.0), // Left bottom
+    vec2(0.5, 1.0), // Right top
+    vec2(-0.5, 1.0) // Left top
+);
+
+const float TAU = 6.28318530718;
+
+void main() {
+    vec2 corner = corners[gl_VertexIndex];
+
+    // Rotate around the world Y axis to face the camera.
+    vec3 right = vec3(view[0][0], 0.0, view[2][0]);
+    right = dot(right, right) > 0.0 ? normalize(right) : vec3(1.0, 0.0, 0.0);
+
+    vec3 world = position + right * corner.x * size.x + vec3(0.0, corner.y * size.y, 0.0);
+
+    // Gusts travel along the wind direction, bending the top of the billboard.
+    float wave = dot(position.xz, wind_direction) / max(wind_wavelength, 0.001);
+    float gust = 0.5 + 0.5 * sin(TAU * (time * wind_frequency - wave));
+    world.xz += wind_direction * (wind_strength * sway * gust * corner.y * corner.y);
+
+    vertex.tex_uv = vec2(corner.x + 0.5, 1.0 - corner.y);
+    vertex.color = color;
+    gl_Position = proj_view * vec4(world, 1.0);
+}
@@ -7,21 +7,38 @@ layout(std140, set = 0, binding = 0) uniform ViewArgs {
 };
 
 layout(std140, set = 1, binding = 0) uniform DebugLinesArgs {
-    uniform vec2 screen_space_thickness;
+    uniform vec2 pixel_size;
+    uniform float line_width;
+    uniform float anti_aliasing;
 };
 
 layout(location = 0) in vec3 position_a;
 layout(location = 1) in vec4 color_a;
 layout(location = 2) in vec3 position_b;
 layout(location = 3) in vec4 color_b;
+// width, dash length, gap length, drawn over the geometry
+layout(location = 4) in vec4 style;
 
 layout(location = 0) out VertexData {
     vec4 color;
+    // Distance in pixels from the center of the line
+    noperspective float across;
+    // Distance in pixels from the start of the line
+    noperspective float along;
+    flat float half_width;
+    flat vec2 dashes;
 } vertex;
 
 void main() {
     float factor = float(gl_VertexIndex >> 1);
     vertex.color = mix(color_a, color_b, factor);
+    vertex.half_width = (style.x > 0.0 ? style.x : line_width) * 0.5;
+    vertex.dashes = style.yz;
+
+    // The quad is widened to fit the smoothed edges.
+    float extent = vertex.half_width + anti_aliasing;
+    float side = mod(gl_VertexIndex, 2) == 0 ? 1.0 : -1.0;
+    vertex.across = side * extent;
 
     vec4 projected_a = proj_view * vec4(position_a, 1.0);
     vec4 projected_b = proj_view * vec4(position_b, 1.0);
@@ -34,19 +51,19 @@ void main() {
         float coef = -proj_current.w / clip_space_dir.z;
         vec3 intersect_pos = proj_current.xyw + (clip_space_dir * coef);
         gl_Position = vec4(intersect_pos.x, intersect_pos.y, 0, intersect_pos.z);
+        vertex.along = 0.0;
     } else {
-        vec2 screen_a = projected_a.xy / projected_a.w;
-        vec2 screen_b = projected_b.xy / projected_b.w;
+        vec2 screen_a = projected_a.xy / projected_a.w / pixel_size;
+        vec2 screen_b = projected_b.xy / projected_b.w / pixel_size;
         vec2 dir = normalize(screen_b - screen_a);
+        vec2 normal = side * vec2(-dir.y, dir.x);
+
+        gl_Position = proj_current + vec4(normal * extent * pixel_size * proj_current.w, 0.0, 0.0);
+        vertex.along = factor * length(screen_b - screen_a);
+    }
 
-        vec2 normal;
-        if (mod(gl_VertexIndex, 2) == 0) {
-            normal = vec2(-dir.y, dir.x);
-        } else {
-            normal = vec2(dir.y, -dir.x);
-        }
-        
-        normal *= proj_current.w * screen_space_thickness;
-        gl_Position = proj_current + vec4(normal, 0.0, 0.0);
+    if (style.w > 0.5) {
+        // Depth of the near plane, in front of all the geometry.
+        gl_Position.z = gl_Position.w;
     }
 }
@@ -0,0 +1,45 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+// Decal box transform.
+layout(location = 0) in mat4 model;
+layout(location = 4) in mat4 inverse_model;
+layout(location = 8) in vec4 color;
+
+layout(location = 0) out VertexData {
+    // Transforms clip space positions into the unit box of the decal.
+    flat mat4 clip_to_decal;
+    flat vec4 color;
+} vertex;
+
+const vec3 corners[8] = vec3[](
+    vec3(-0.5, -0.5, -0.5),
+    vec3(0.5, -0.5, -0.5),
+    vec3(-0.5, 0.5, -0.5),
+    vec3(0.5, 0.5, -0.5),
+    vec3(-0.5, -0.5, 0.5),
+    vec3(0.5, -0.5, 0.5),
+    vec3(-0.5, 0.5, 0.5),
+    vec3(0.5, 0.5, 0.5)
+);
+
+// Triangles of the box faces, counter-clockwise seen from outside.
+const int indices[36] = int[](
+    0, 2, 1, 1, 2, 3, // back
+    4, 5, 6, 5, 7, 6, // front
+    0, 4, 2, 2, 4, 6, // left
+    1, 3, 5, 3, 7, 5, // right
+    0, 1, 4, 1, 5, 4, // bottom
+    2, 6, 3, 3, 6, 7  // top
+);
+
+void main() {
+    vertex.clip_to_decal = inverse_model * inverse(proj_view);
+    vertex.color = color;
+    gl_Position = proj_view * model * vec4(corners[indices[gl_VertexIndex]], 1.0);
+}
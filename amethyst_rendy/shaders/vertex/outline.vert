@@ -0,0 +1,24 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in mat4 model; // instance rate
+layout(location = 6) in vec4 color; // instance rate
+layout(location = 7) in float width; // instance rate
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    // Grow the mesh along its normals, the back faces drawn around it form the outline.
+    vec3 world_normal = normalize(transpose(inverse(mat3(model))) * normal);
+    vec4 world_position = model * vec4(position, 1.0);
+    world_position.xyz += world_normal * width;
+    out_color = color;
+    gl_Position = proj_view * world_position;
+}
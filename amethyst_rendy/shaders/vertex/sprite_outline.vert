@@ -0,0 +1,52 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+// Quad transform.
+layout(location = 0) in vec2 dir_x;
+layout(location = 1) in vec2 dir_y;
+layout(location = 2) in vec3 pos;
+layout(location = 3) in vec2 u_offset;
+layout(location = 4) in vec2 v_offset;
+layout(location = 5) in vec2 grow;
+layout(location = 6) in vec4 color;
+
+layout(location = 0) out VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    flat vec4 tex_bounds;
+    flat vec2 tex_step;
+} vertex;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+// coords = 0.0 to 1.0 texture coordinates, extrapolated outside of that range
+vec2 texture_coords(vec2 coords, vec2 u, vec2 v) {
+    return vec2(mix(u.x, u.y, coords.x+0.5), mix(v.x, v.y, coords.y+0.5));
+}
+
+void main() {
+    // The quad is grown by the outline width on every side.
+    vec2 coords = positions[gl_VertexIndex] * (1.0 + 2.0 * grow);
+
+    vertex.tex_uv = texture_coords(coords, u_offset, v_offset);
+    vertex.color = color;
+    vertex.tex_bounds = vec4(
+        min(u_offset.x, u_offset.y),
+        min(v_offset.x, v_offset.y),
+        max(u_offset.x, u_offset.y),
+        max(v_offset.x, v_offset.y)
+    );
+    vertex.tex_step = abs(vec2(u_offset.y - u_offset.x, v_offset.y - v_offset.x)) * grow;
+    vec2 final_pos = pos.xy + coords.x * dir_x + coords.y * dir_y;
+    gl_Position = proj_view * vec4(final_pos, pos.z, 1.0);
+}
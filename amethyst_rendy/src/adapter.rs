@@ -0,0 +1,324 @@
+//! Graphics adapters the game can render with, and the capabilities of the one it does.
+//!
+//! When the backend is initialized, the adapter is picked by [`AdapterPreference`] if one matches
+//! it, or else the discrete GPU, integrated GPU, virtual GPU and CPU adapters are preferred in
+//! this order. The adapters found are published as the [`Adapters`] resource, and the limits of
+//! the picked one as the [`GraphicsCapabilities`] resource, which plugins and games can consult
+//! to pick their settings.
+
+use std::sync::Mutex;
+
+use amethyst_core::ecs::Resources;
+use amethyst_error::{format_err, Error};
+use rendy::{
+    factory::{BasicHeapsConfigure, Config, DevicesConfigure, OneGraphicsQueue},
+    hal::{
+        self,
+        adapter::{Adapter, DeviceType, PhysicalDevice},
+        memory::Properties,
+        queue::{QueueFamily, QueueType},
+    },
+    init::Rendy,
+};
+
+use crate::{bundle::RenderPlugin, types::Backend};
+
+/// Resource holding the name of the adapter to render with, matched case-insensitively against
+/// part of the adapter names.
+///
+/// It is inserted by `AnyRenderingBundle` from `BackendConfig::adapter`, or else from the
+/// [`RenderPlugin::adapter_preference`] of the plugins before the backend is initialized, e.g. the
+/// `DisplayConfig::adapter` of `RenderToWindow`. A preference inserted by the game beforehand is
+/// kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdapterPreference(pub String);
+
+/// Description of a queue family of an adapter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueueFamilyInfo {
+    /// Kind of commands the queues support
+    pub queue_type: QueueType,
+    /// Number of queues of the family
+    pub max_queues: usize,
+}
+
+/// Description of a graphics adapter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdapterInfo {
+    /// Name of the adapter
+    pub name: String,
+    /// PCI vendor id
+    pub vendor: usize,
+    /// PCI device id
+    pub device: usize,
+    /// Kind of adapter
+    pub device_type: DeviceType,
+    /// Bytes of memory local to the device
+    pub memory: u64,
+    /// Queue families of the adapter
+    pub queue_families: Vec<QueueFamilyInfo>,
+}
+
+impl AdapterInfo {
+    fn new<B: hal::Backend>(adapter: &Adapter<B>) -> Self {
+        let properties = adapter.physical_device.memory_properties();
+        let mut local_heaps: Vec<usize> = properties
+            .memory_types
+            .iter()
+            .filter(|ty| ty.properties.contains(Properties::DEVICE_LOCAL))
+            .map(|ty| ty.heap_index)
+            .collect();
+        local_heaps.sort_unstable();
+        local_heaps.dedup();
+
+        Self {
+            name: adapter.info.name.clone(),
+            vendor: adapter.info.vendor,
+            device: adapter.info.device,
+            device_type: adapter.info.device_type.clone(),
+            memory: local_heaps
+                .into_iter()
+                .map(|heap| properties.memory_heaps[heap])
+                .sum(),
+            queue_families: adapter
+                .queue_families
+                .iter()
+                .map(|family| {
+                    QueueFamilyInfo {
+                        queue_type: family.queue_type(),
+                        max_queues: family.max_queues(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Resource listing the adapters found when the backend was initialized.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Adapters {
+    /// The adapters, in the order reported by the backend
+    pub adapters: Vec<AdapterInfo>,
+    /// Index of the adapter the game renders with
+    pub selected: usize,
+}
+
+impl Adapters {
+    /// The adapter the game renders with.
+    #[must_use]
+    pub fn selected(&self) -> Option<&AdapterInfo> {
+        self.adapters.get(self.selected)
+    }
+}
+
+/// Resource holding the limits of the adapter the game renders with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphicsCapabilities {
+    /// Maximum width and height of 2D textures
+    pub max_texture_size: u32,
+    /// Maximum width and height of cubemap faces
+    pub max_cubemap_size: u32,
+    /// Sample counts supported for multisampling render targets with depth, ascending
+    pub msaa_samples: Vec<u8>,
+    /// Maximum anisotropic filtering level, `1.0` when it's not supported
+    pub max_anisotropy: f32,
+}
+
+impl GraphicsCapabilities {
+    fn new<B: Backend>(physical: &B::PhysicalDevice) -> Self {
+        let limits = physical.limits();
+        let supported =
+            limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts;
+        let anisotropy = physical
+            .features()
+            .contains(hal::Features::SAMPLER_ANISOTROPY);
+
+        Self {
+            max_texture_size: limits.max_image_2d_size,
+            max_cubemap_size: limits.max_image_cube_size,
+            msaa_samples: (0..7)
+                .map(|bit| 1_u8 << bit)
+                .filter(|&samples| samples == 1 || supported & samples != 0)
+                .collect(),
+            max_anisotropy: if anisotropy {
+                limits.max_sampler_anisotropy
+            } else {
+                1.0
+            },
+        }
+    }
+
+    /// Whether multisampling with `samples` samples per pixel is supported.
+    #[must_use]
+    pub fn supports_msaa(&self, samples: u8) -> bool {
+        self.msaa_samples.contains(&samples)
+    }
+}
+
+/// Picks the adapter to render with, recording the adapters it was offered.
+#[derive(Debug)]
+struct PickAdapter {
+    preference: Option<String>,
+    found: Mutex<Adapters>,
+}
+
+unsafe impl DevicesConfigure for PickAdapter {
+    fn pick<B: hal::Backend>(&self, adapters: &[Adapter<B>]) -> usize {
+        let infos: Vec<AdapterInfo> = adapters.iter().map(AdapterInfo::new).collect();
+
+        let preferred = self.preference.as_ref().and_then(|preference| {
+            let preference = preference.to_lowercase();
+            let found = infos
+                .iter()
+                .position(|info| info.name.to_lowercase().contains(&preference));
+            if found.is_none() {
+                log::warn!("No graphics adapter matches {:?}", preference);
+            }
+            found
+        });
+        let selected = preferred.unwrap_or_else(|| {
+            (0..infos.len())
+                .min_by_key(|&index| device_type_rank(&infos[index].device_type))
+                .unwrap_or(0)
+        });
+
+        if let Some(info) = infos.get(selected) {
+            log::info!("Rendering with graphics adapter {:?}", info.name);
+        }
+        *self.found.lock().unwrap() = Adapters {
+            adapters: infos,
+            selected,
+        };
+        selected
+    }
+}
+
+fn device_type_rank(device_type: &DeviceType) -> u8 {
+    match device_type {
+        DeviceType::DiscreteGpu => 0,
+        DeviceType::IntegratedGpu => 1,
+        DeviceType::VirtualGpu => 2,
+        DeviceType::Cpu => 3,
+        DeviceType::Other => 4,
+    }
+}
+
+/// Inserts the [`AdapterPreference`] of the first of `plugins` having one, unless the resource is
+/// already present.
+pub(crate) fn prefer_adapter<B: Backend>(
+    resources: &mut Resources,
+    plugins: &[Box<dyn RenderPlugin<B>>],
+) {
+    if resources.contains::<AdapterPreference>() {
+        return;
+    }
+    if let Some(adapter) = plugins.iter().find_map(|plugin| plugin.adapter_preference()) {
+        resources.insert(AdapterPreference(adapter));
+    }
+}
+
+/// Initializes backend `B` with the adapter picked by the [`AdapterPreference`] resource, and
+/// inserts the [`Adapters`] and [`GraphicsCapabilities`] resources.
+pub(crate) fn init_rendy<B: Backend>(resources: &mut Resources) -> Result<Rendy<B>, Error> {
+    let config = Config {
+        devices: PickAdapter {
+            preference: resources
+                .get::<AdapterPreference>()
+                .map(|preference| preference.0.clone()),
+            found: Mutex::new(Adapters::default()),
+        },
+        heaps: BasicHeapsConfigure,
+        queues: OneGraphicsQueue,
+    };
+    let rendy = Rendy::<B>::init(&config)
+        .map_err(|e| format_err!("Unable to initialize the {} backend: {:?}", B::KIND, e))?;
+
+    resources.insert(config.devices.found.into_inner().unwrap());
+    resources.insert(GraphicsCapabilities::new::<B>(rendy.factory.physical()));
+    Ok(rendy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DefaultBackend;
+
+    #[derive(Debug)]
+    struct Prefers(Option<&'static str>);
+
+    impl RenderPlugin<DefaultBackend> for Prefers {
+        fn adapter_preference(&self) -> Option<String> {
+            self.0.map(String::from)
+        }
+
+        fn on_plan(
+            &mut self,
+            _plan: &mut crate::bundle::RenderPlan<DefaultBackend>,
+            _factory: &mut rendy::factory::Factory<DefaultBackend>,
+            _world: &amethyst_core::ecs::World,
+            _resources: &Resources,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn plugins(preferences: &[Option<&'static str>]) -> Vec<Box<dyn RenderPlugin<DefaultBackend>>> {
+        preferences
+            .iter()
+            .map(|preference| Box::new(Prefers(*preference)) as Box<dyn RenderPlugin<_>>)
+            .collect()
+    }
+
+    #[test]
+    fn plugin_preference_is_inserted_before_init() {
+        let mut resources = Resources::default();
+        prefer_adapter(&mut resources, &plugins(&[None, Some("radeon"), Some("intel")]));
+        assert_eq!(
+            resources.get::<AdapterPreference>().as_deref(),
+            Some(&AdapterPreference("radeon".into()))
+        );
+
+        let mut resources = Resources::default();
+        prefer_adapter(&mut resources, &plugins(&[None]));
+        assert!(!resources.contains::<AdapterPreference>());
+    }
+
+    #[test]
+    fn inserted_preference_is_kept() {
+        let mut resources = Resources::default();
+        resources.insert(AdapterPreference("nvidia".into()));
+        prefer_adapter(&mut resources, &plugins(&[Some("radeon")]));
+        assert_eq!(
+            resources.get::<AdapterPreference>().as_deref(),
+            Some(&AdapterPreference("nvidia".into()))
+        );
+    }
+
+    #[test]
+    fn discrete_gpus_come_first() {
+        let mut types = vec![
+            DeviceType::Cpu,
+            DeviceType::IntegratedGpu,
+            DeviceType::Other,
+            DeviceType::DiscreteGpu,
+        ];
+        types.sort_by_key(device_type_rank);
+
+        assert_eq!(types[0], DeviceType::DiscreteGpu);
+        assert_eq!(types[1], DeviceType::IntegratedGpu);
+        assert_eq!(types[3], DeviceType::Other);
+    }
+
+    #[test]
+    fn msaa_support() {
+        let capabilities = GraphicsCapabilities {
+            max_texture_size: 4096,
+            max_cubemap_size: 4096,
+            msaa_samples: vec![1, 2, 4],
+            max_anisotropy: 16.0,
+        };
+
+        assert!(capabilities.supports_msaa(4));
+        assert!(!capabilities.supports_msaa(8));
+    }
+}
@@ -0,0 +1,294 @@
+//! Selection of the rendering backend at runtime.
+//!
+//! [`RenderingBundle`] renders with the backend it is given at compile time, usually
+//! `DefaultBackend`. [`AnyRenderingBundle`] instead tries the backends compiled into the game in
+//! order of preference when it is loaded, and renders with the first one that initializes and
+//! whose plugins load, e.g. falling back to DirectX 12 on a Windows machine without Vulkan drivers,
+//! or to OpenGL on a Linux machine without them:
+//!
+//! ```ignore
+//! struct Plugins;
+//!
+//! impl RenderPluginSet for Plugins {
+//!     fn plugins<B: Backend>(&mut self) -> Vec<Box<dyn RenderPlugin<B>>> {
+//!         vec![
+//!             Box::new(RenderToWindow::from_config(display_config())),
+//!             Box::new(RenderFlat2D::default()),
+//!         ]
+//!     }
+//! }
+//!
+//! game_data.add_bundle(AnyRenderingBundle::new(Plugins).with_config(BackendConfig::load(path)?));
+//! ```
+//!
+//! Either bundle publishes the backend it renders with as the [`RenderBackend`] resource.
+
+use std::fmt;
+
+use amethyst_core::ecs::{DispatcherBuilder, Resources, SystemBundle, World};
+use amethyst_error::{format_err, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    adapter::{init_rendy, prefer_adapter, AdapterPreference},
+    bundle::{RenderPlugin, RenderingBundle},
+    types::Backend,
+};
+
+/// Kind of a rendering backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BackendKind {
+    /// Vulkan, on Linux and Windows
+    Vulkan,
+    /// DirectX 12, on Windows
+    Dx12,
+    /// Metal, on macOS
+    Metal,
+    /// OpenGL on Linux and Windows, WebGL 2 in the browser
+    Gl,
+    /// Backend rendering nothing, with the `empty` feature
+    Empty,
+}
+
+impl BackendKind {
+    /// The backends tried by [`AnyRenderingBundle`] by default, in order.
+    pub const FALLBACK_CHAIN: [BackendKind; 4] = [
+        BackendKind::Vulkan,
+        BackendKind::Dx12,
+        BackendKind::Metal,
+        BackendKind::Gl,
+    ];
+
+    /// Whether the backend is compiled into this build.
+    #[must_use]
+    pub fn is_compiled(self) -> bool {
+        match self {
+            BackendKind::Vulkan => {
+                cfg!(all(
+                    not(target_os = "macos"),
+                    not(target_arch = "wasm32"),
+                    not(feature = "empty")
+                ))
+            }
+            BackendKind::Dx12 => cfg!(all(target_os = "windows", not(feature = "empty"))),
+            BackendKind::Metal => cfg!(target_os = "macos"),
+            BackendKind::Gl => {
+                cfg!(all(
+                    any(target_arch = "wasm32", target_os = "linux", target_os = "windows"),
+                    not(feature = "empty")
+                ))
+            }
+            BackendKind::Empty => cfg!(feature = "empty"),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BackendKind::Vulkan => "Vulkan",
+            BackendKind::Dx12 => "DirectX 12",
+            BackendKind::Metal => "Metal",
+            BackendKind::Gl => "GL",
+            BackendKind::Empty => "empty",
+        })
+    }
+}
+
+/// Resource holding the backend the game renders with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderBackend {
+    /// Kind of the backend
+    pub kind: BackendKind,
+}
+
+/// Backend preference of [`AnyRenderingBundle`], loadable from a configuration file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    /// Backends to try in order. Backends not compiled into the build are skipped.
+    pub backends: Vec<BackendKind>,
+    /// Name of the graphics adapter to render with, see
+    /// [`AdapterPreference`](crate::adapter::AdapterPreference).
+    pub adapter: Option<String>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            backends: BackendKind::FALLBACK_CHAIN.to_vec(),
+            adapter: None,
+        }
+    }
+}
+
+/// Render plugins of an [`AnyRenderingBundle`], created for the backend it picks.
+pub trait RenderPluginSet: 'static {
+    /// Creates the plugins rendering with backend `B`.
+    fn plugins<B: Backend>(&mut self) -> Vec<Box<dyn RenderPlugin<B>>>;
+}
+
+/// A [`RenderingBundle`] picking its backend at runtime, see the
+/// [module documentation](self).
+///
+/// Loading the bundle fails when none of the backends could be initialized.
+pub struct AnyRenderingBundle<P: RenderPluginSet> {
+    plugins: P,
+    config: BackendConfig,
+    loaded: Option<Box<dyn SystemBundle>>,
+    timings: bool,
+}
+
+impl<P: RenderPluginSet> AnyRenderingBundle<P> {
+    /// Create `AnyRenderingBundle` trying the backends of [`BackendKind::FALLBACK_CHAIN`].
+    #[must_use]
+    pub fn new(plugins: P) -> Self {
+        Self {
+            plugins,
+            config: BackendConfig::default(),
+            loaded: None,
+            timings: false,
+        }
+    }
+
+    /// Try the backends of `config` instead.
+    #[must_use]
+    pub fn with_config(mut self, config: BackendConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Measure the time every render group takes per frame, see
+    /// [`RenderingBundle::with_render_timings`].
+    #[must_use]
+    pub fn with_render_timings(mut self) -> Self {
+        self.timings = true;
+        self
+    }
+
+    /// Loads a [`RenderingBundle`] for backend `B` if it initializes and loads.
+    ///
+    /// The bundle is loaded into a builder of its own, discarded with the resources of the
+    /// backend when loading fails, so that the next backend starts from a clean slate.
+    fn try_load<B: Backend>(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> bool {
+        let plugins = self.plugins.plugins::<B>();
+        prefer_adapter(resources, &plugins);
+        let rendy = match init_rendy::<B>(resources) {
+            Ok(rendy) => rendy,
+            Err(e) => {
+                log::warn!("{}", e);
+                return false;
+            }
+        };
+
+        let mut bundle = RenderingBundle::from_rendy(rendy, plugins);
+        if self.timings {
+            bundle = bundle.with_render_timings();
+        }
+        let mut bundle_builder = DispatcherBuilder::default();
+        if let Err(e) = bundle.load(world, resources, &mut bundle_builder) {
+            log::warn!("Failed to load the {} backend: {}", B::KIND, e);
+            RenderingBundle::<B>::discard(resources);
+            return false;
+        }
+        log::info!("Rendering with the {} backend", B::KIND);
+        builder.append(&mut bundle_builder);
+        self.loaded = Some(Box::new(bundle));
+        true
+    }
+}
+
+impl<P: RenderPluginSet> SystemBundle for AnyRenderingBundle<P> {
+    fn load(
+        &mut self,
+        world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        if let Some(adapter) = &self.config.adapter {
+            resources.insert(AdapterPreference(adapter.clone()));
+        }
+
+        for kind in self.config.backends.clone() {
+            let loaded = match kind {
+                #[cfg(all(
+                    not(target_os = "macos"),
+                    not(target_arch = "wasm32"),
+                    not(feature = "empty")
+                ))]
+                BackendKind::Vulkan => {
+                    self.try_load::<rendy::vulkan::Backend>(world, resources, builder)
+                }
+                #[cfg(all(target_os = "windows", not(feature = "empty")))]
+                BackendKind::Dx12 => {
+                    self.try_load::<rendy::dx12::Backend>(world, resources, builder)
+                }
+                #[cfg(target_os = "macos")]
+                BackendKind::Metal => {
+                    self.try_load::<rendy::metal::Backend>(world, resources, builder)
+                }
+                #[cfg(all(
+                    any(target_arch = "wasm32", target_os = "linux", target_os = "windows"),
+                    not(feature = "empty")
+                ))]
+                BackendKind::Gl => {
+                    self.try_load::<rendy::gl::Backend>(world, resources, builder)
+                }
+                #[cfg(feature = "empty")]
+                BackendKind::Empty => {
+                    self.try_load::<rendy::empty::Backend>(world, resources, builder)
+                }
+                #[allow(unreachable_patterns)]
+                _ => {
+                    log::debug!("Skipping the {} backend, not compiled into the build", kind);
+                    false
+                }
+            };
+            if loaded {
+                return Ok(());
+            }
+        }
+
+        Err(format_err!(
+            "None of the rendering backends {:?} could be initialized and loaded",
+            self.config.backends
+        ))
+    }
+
+    fn unload(&mut self, world: &mut World, resources: &mut Resources) -> Result<(), Error> {
+        match self.loaded.as_mut() {
+            Some(bundle) => bundle.unload(world, resources),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<P: RenderPluginSet> fmt::Debug for AnyRenderingBundle<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyRenderingBundle")
+            .field("config", &self.config)
+            .field("timings", &self.timings)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_backend_is_compiled() {
+        assert!(<crate::types::DefaultBackend as Backend>::KIND.is_compiled());
+    }
+
+    #[test]
+    fn config_defaults_to_fallback_chain() {
+        let config: BackendConfig = ron::de::from_str("()").unwrap();
+        assert_eq!(config.backends, BackendKind::FALLBACK_CHAIN.to_vec());
+    }
+}
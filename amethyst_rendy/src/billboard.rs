@@ -0,0 +1,195 @@
+//! Camera facing billboards in large numbers, e.g. grass or distant trees.
+//!
+//! A [`BillboardField`] holds the placements of billboards sharing a texture, in world space.
+//! The [`RenderBillboards`] plugin draws them in a single instanced draw call per texture.
+//! Billboards rotate around the world Y axis to face the camera, and their base stays in place
+//! while their top bends with the [`Wind`] resource, scaled by the sway of each placement.
+//!
+//! ```ignore
+//! let mut grass = BillboardField::new(grass_texture);
+//! for x in 0..100 {
+//!     for z in 0..100 {
+//!         grass.push(BillboardPlacement::new([x as f32, 0.0, z as f32], [0.5, 0.8]));
+//!     }
+//! }
+//! world.push((grass,));
+//! ```
+
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, World},
+    math::Vector2,
+};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::DrawBillboardsDesc,
+    plugins,
+    types::{Backend, Texture},
+    Factory,
+};
+
+/// Placement of a billboard in a [`BillboardField`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BillboardPlacement {
+    /// World position of the bottom center of the billboard
+    pub position: [f32; 3],
+    /// Width and height of the billboard
+    pub size: [f32; 2],
+    /// Linear RGBA color the texture is multiplied with
+    pub color: [f32; 4],
+    /// How much the top of the billboard bends with the wind, `0.0` for rigid billboards
+    pub sway: f32,
+}
+
+impl Default for BillboardPlacement {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            size: [1.0; 2],
+            color: [1.0; 4],
+            sway: 1.0,
+        }
+    }
+}
+
+impl BillboardPlacement {
+    /// Creates a white billboard of given size, swaying with the wind.
+    #[must_use]
+    pub fn new(position: [f32; 3], size: [f32; 2]) -> Self {
+        Self {
+            position,
+            size,
+            ..Self::default()
+        }
+    }
+
+    /// Multiply the texture with given linear RGBA color.
+    #[must_use]
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Bend the top of the billboard by `sway` times the wind strength.
+    #[must_use]
+    pub fn with_sway(mut self, sway: f32) -> Self {
+        self.sway = sway;
+        self
+    }
+}
+
+/// Component holding the placements of billboards drawn with the same texture, see the
+/// [module documentation](self).
+///
+/// Fragments of the texture with an alpha below one half are discarded, so billboards are
+/// drawn with the opaque geometry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BillboardField {
+    /// Texture of the billboards
+    pub texture: Handle<Texture>,
+    /// Placements of the billboards
+    pub placements: Vec<BillboardPlacement>,
+}
+
+impl BillboardField {
+    /// Creates a field without billboards.
+    #[must_use]
+    pub fn new(texture: Handle<Texture>) -> Self {
+        Self {
+            texture,
+            placements: Vec::new(),
+        }
+    }
+
+    /// Creates a field of given placements.
+    #[must_use]
+    pub fn with_placements(mut self, placements: Vec<BillboardPlacement>) -> Self {
+        self.placements = placements;
+        self
+    }
+
+    /// Adds a billboard to the field.
+    pub fn push(&mut self, placement: BillboardPlacement) {
+        self.placements.push(placement);
+    }
+}
+
+/// Wind bending swaying [`BillboardField`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Wind {
+    /// Direction the wind blows to, in the XZ plane
+    pub direction: Vector2<f32>,
+    /// Distance the top of a billboard of sway `1.0` bends by at the peak of a gust
+    pub strength: f32,
+    /// Gusts per second
+    pub frequency: f32,
+    /// Length of the gust waves travelling along the wind direction
+    pub wavelength: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: Vector2::new(1.0, 0.0),
+            strength: 0.1,
+            frequency: 0.5,
+            wavelength: 10.0,
+        }
+    }
+}
+
+/// A [`RenderPlugin`] drawing [`BillboardField`]s, see the [module documentation](self).
+#[derive(Default, Debug)]
+pub struct RenderBillboards {
+    target: Option<Target>,
+}
+
+impl RenderBillboards {
+    /// Set target to which billboards will be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderBillboards {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        _builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        if !resources.contains::<Wind>() {
+            resources.insert(Wind::default());
+        }
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        resources: &Resources,
+    ) -> Result<(), Error> {
+        let target = plugins::scene_target(self.target, resources);
+        plan.extend_target(target, move |ctx| {
+            ctx.add(
+                RenderOrder::Opaque,
+                DrawBillboardsDesc::new()
+                    .with_target(target)
+                    .with_samples(ctx.samples())
+                    .builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -5,26 +5,35 @@ use std::collections::HashMap;
 use amethyst_assets::{register_asset_type, AssetProcessorSystem, AssetStorage};
 use amethyst_core::ecs::{DispatcherBuilder, Resources, SystemBundle, World};
 use amethyst_error::{format_err, Error};
+use derivative::Derivative;
 use rendy::init::Rendy;
 
 use crate::{
+    adapter::{init_rendy, prefer_adapter},
+    backend::RenderBackend,
     bundle,
     camera::ActiveCamera,
+    compute::ComputeNodeDesc,
+    graph_info::{
+        group_name, is_write, RenderGraphInfo, RenderGroupInfo, RenderImageInfo, RenderNodeInfo,
+        RenderTimings, TimedGroupBuilder,
+    },
     mtl::{Material, MaterialDefaults},
     rendy::{
         command::QueueId,
         factory::Factory,
         graph::{
             render::{RenderGroupBuilder, RenderPassNodeBuilder, SubpassBuilder},
-            GraphBuilder, ImageId, NodeId,
+            BufferId, GraphBuilder, ImageId, NodeBuilder, NodeId,
         },
         hal,
         wsi::Surface,
     },
     system::{
-        create_default_mat, make_graph_aux_data, render, GraphAuxData, GraphCreator, RenderState,
+        create_default_mat, make_graph_aux_data, release_suspended, render, GraphAuxData,
+        GraphCreator, MeshProcessorSystem, RenderState, TextureProcessorSystem,
     },
-    types::{Backend, DefaultBackend, Mesh, Texture},
+    types::{Backend, Mesh, Texture},
 };
 
 /// A bundle of systems used for rendering using `Rendy` render graph.
@@ -36,9 +45,20 @@ use crate::{
 /// If you need much more control, or you need to deal directly with the render pipeline,
 /// it's possible to define a `RenderGraphCreator` as show by the
 /// `renderable_custom` example.
-#[derive(Debug)]
+///
+/// The bundle initializes the backend `B` when it is loaded, and publishes it as the
+/// [`RenderBackend`] resource. To pick the backend at runtime instead, see
+/// [`AnyRenderingBundle`](crate::backend::AnyRenderingBundle).
+///
+/// The render graph built by the bundle is described by the [`RenderGraphInfo`] resource, which
+/// can be dumped to debug it, see the [`graph_info`](crate::graph_info) module.
+#[derive(Derivative)]
+#[derivative(Debug)]
 pub struct RenderingBundle<B: Backend> {
     plugins: Vec<Box<dyn RenderPlugin<B>>>,
+    #[derivative(Debug = "ignore")]
+    rendy: Option<Rendy<B>>,
+    timings: bool,
 }
 
 impl<B: Backend> RenderingBundle<B> {
@@ -48,9 +68,37 @@ impl<B: Backend> RenderingBundle<B> {
     pub fn new() -> Self {
         Self {
             plugins: Vec::new(),
+            rendy: None,
+            timings: false,
         }
     }
 
+    /// Create `RenderingBundle` rendering with an already initialized backend.
+    pub(crate) fn from_rendy(rendy: Rendy<B>, plugins: Vec<Box<dyn RenderPlugin<B>>>) -> Self {
+        Self {
+            plugins,
+            rendy: Some(rendy),
+            timings: false,
+        }
+    }
+
+    /// Removes the resources a bundle for backend `B` inserted before failing to load.
+    pub(crate) fn discard(resources: &mut Resources) {
+        resources.remove::<RenderState<B, PluggableRenderGraphCreator<B>>>();
+        resources.remove::<MaterialDefaults>();
+        resources.remove::<QueueId>();
+        resources.remove::<RenderBackend>();
+        resources.remove::<Factory<B>>();
+    }
+
+    /// Measure the time every render group takes per frame, published as the [`RenderTimings`]
+    /// resource. See the [`graph_info`](crate::graph_info) module for what is measured.
+    #[must_use]
+    pub fn with_render_timings(mut self) -> Self {
+        self.timings = true;
+        self
+    }
+
     /// Register a [`RenderPlugin`].
     ///
     /// If you want the non-consuming version of this method, see [`add_plugin`].
@@ -75,13 +123,23 @@ impl<B: Backend> SystemBundle for RenderingBundle<B> {
         builder: &mut DispatcherBuilder,
     ) -> Result<(), Error> {
         resources.insert(ActiveCamera::default());
+        resources.insert(RenderGraphInfo::default());
+        if self.timings {
+            resources.insert(RenderTimings::default());
+        }
 
         for plugin in &mut self.plugins {
             plugin.on_build(world, resources, builder)?;
         }
 
-        let config: rendy::factory::Config = Default::default();
-        let r: Rendy<DefaultBackend> = rendy::init::Rendy::init(&config).unwrap();
+        let r = match self.rendy.take() {
+            Some(r) => r,
+            None => {
+                prefer_adapter(resources, &self.plugins);
+                init_rendy::<B>(resources)?
+            }
+        };
+        resources.insert(RenderBackend { kind: B::KIND });
 
         let queue_id = QueueId {
             family: r.families.family_by_index(0).id(),
@@ -99,10 +157,15 @@ impl<B: Backend> SystemBundle for RenderingBundle<B> {
             families: r.families,
             graph_creator: PluggableRenderGraphCreator {
                 plugins: self.plugins.drain(..).collect(),
+                timings: self.timings,
             },
         });
 
-        builder.add_thread_local_fn(render::<B, PluggableRenderGraphCreator<B>>);
+        builder
+            .add_system(MeshProcessorSystem::<B>::default())
+            .add_system(TextureProcessorSystem::<B>::default())
+            .add_platform_thread_local_fn(release_suspended::<B, PluggableRenderGraphCreator<B>>)
+            .add_thread_local_fn(render::<B, PluggableRenderGraphCreator<B>>);
 
         Ok(())
     }
@@ -137,6 +200,7 @@ impl<B: Backend> SystemBundle for RenderingBundle<B> {
 
 struct PluggableRenderGraphCreator<B: Backend> {
     plugins: Vec<Box<dyn RenderPlugin<B>>>,
+    timings: bool,
 }
 
 impl<B: Backend> GraphCreator<B> for PluggableRenderGraphCreator<B> {
@@ -159,12 +223,22 @@ impl<B: Backend> GraphCreator<B> for PluggableRenderGraphCreator<B> {
         }
 
         let mut plan = RenderPlan::new();
+        plan.timings = self.timings;
         for plugin in &mut self.plugins {
             plugin
                 .on_plan(&mut plan, factory, world, resources)
                 .unwrap();
         }
-        plan.build(factory).unwrap()
+        let (builder, mut info) = plan.build(factory).unwrap();
+
+        if let Some(mut timings) = resources.get_mut::<RenderTimings>() {
+            timings.reset(&info);
+        }
+        if let Some(mut graph_info) = resources.get_mut::<RenderGraphInfo>() {
+            info.builds = graph_info.builds + 1;
+            *graph_info = info;
+        }
+        builder
     }
 }
 
@@ -184,6 +258,13 @@ pub trait RenderPlugin<B: Backend>: std::fmt::Debug {
         Ok(())
     }
 
+    /// Name of the graphics adapter this plugin prefers to render with, inserted as the
+    /// [`AdapterPreference`](crate::adapter::AdapterPreference) before the backend is initialized
+    /// unless one is already present.
+    fn adapter_preference(&self) -> Option<String> {
+        None
+    }
+
     /// Hook for providing triggers to rebuild the render graph.
     fn should_rebuild(&mut self, _world: &World, _resources: &Resources) -> bool {
         false
@@ -200,10 +281,14 @@ pub trait RenderPlugin<B: Backend>: std::fmt::Debug {
 }
 
 /// Builder of a rendering plan for specified target.
-#[derive(Debug)]
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
 pub struct RenderPlan<B: Backend> {
     targets: HashMap<Target, TargetPlan<B>>,
     roots: Vec<Target>,
+    #[derivative(Debug = "ignore")]
+    graph_extensions: Vec<Box<dyn FnOnce(&mut GraphPlanContext<'_, B>) -> Result<(), Error>>>,
+    timings: bool,
 }
 
 impl<B: Backend> RenderPlan<B> {
@@ -211,6 +296,8 @@ impl<B: Backend> RenderPlan<B> {
         Self {
             targets: std::collections::HashMap::default(),
             roots: vec![],
+            graph_extensions: vec![],
+            timings: false,
         }
     }
 
@@ -253,7 +340,20 @@ impl<B: Backend> RenderPlan<B> {
         target_plan.add_extension(Box::new(closure));
     }
 
-    fn build(self, factory: &Factory<B>) -> Result<GraphBuilder<B, GraphAuxData>, Error> {
+    /// Extend the render graph with nodes that are not part of any render target, e.g.
+    /// resolve, present or compute nodes. The closure is evaluated after all root targets,
+    /// and can depend on images of any target.
+    pub fn extend_graph(
+        &mut self,
+        closure: impl FnOnce(&mut GraphPlanContext<'_, B>) -> Result<(), Error> + 'static,
+    ) {
+        self.graph_extensions.push(Box::new(closure));
+    }
+
+    fn build(
+        self,
+        factory: &Factory<B>,
+    ) -> Result<(GraphBuilder<B, GraphAuxData>, RenderGraphInfo), Error> {
         let mut ctx = PlanContext {
             target_metadata: self
                 .targets
@@ -264,13 +364,24 @@ impl<B: Backend> RenderPlan<B> {
             passes: std::collections::HashMap::default(),
             outputs: std::collections::HashMap::default(),
             graph_builder: GraphBuilder::new(),
+            info: RenderGraphInfo::default(),
+            info_nodes: vec![],
+            info_images: vec![],
+            timings: self.timings,
         };
 
         for target in self.roots {
             ctx.evaluate_target(target)?;
         }
 
-        Ok(ctx.graph_builder)
+        for extension in self.graph_extensions {
+            extension(&mut GraphPlanContext {
+                plan_context: &mut ctx,
+                factory,
+            })?;
+        }
+
+        Ok((ctx.graph_builder, ctx.info))
     }
 }
 
@@ -294,12 +405,39 @@ impl EvaluationState {
 }
 
 /// Metadata for a planned render target.
-/// Defines effective size and layer count that target's renderpass will operate on.
+/// Defines effective size, layer and sample count that target's renderpass will operate on.
 #[derive(Debug, Clone, Copy)]
 pub struct TargetMetadata {
     width: u32,
     height: u32,
     layers: u16,
+    samples: u8,
+}
+
+impl TargetMetadata {
+    /// Width of the target framebuffer in pixels.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the target framebuffer in pixels.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of layers of the target framebuffer.
+    #[must_use]
+    pub fn layers(&self) -> u16 {
+        self.layers
+    }
+
+    /// Number of samples per pixel of the target framebuffer, `1` when not multisampled.
+    #[must_use]
+    pub fn samples(&self) -> u8 {
+        self.samples
+    }
 }
 
 #[derive(Debug)]
@@ -309,6 +447,10 @@ struct PlanContext<B: Backend> {
     passes: HashMap<Target, EvaluationState>,
     outputs: HashMap<TargetImage, ImageId>,
     graph_builder: GraphBuilder<B, GraphAuxData>,
+    info: RenderGraphInfo,
+    info_nodes: Vec<NodeId>,
+    info_images: Vec<(ImageId, usize)>,
+    timings: bool,
 }
 
 impl<B: Backend> PlanContext<B> {
@@ -408,6 +550,102 @@ impl<B: Backend> PlanContext<B> {
         self.graph_builder
             .create_image(options.kind, options.levels, options.format, options.clear)
     }
+
+    /// Describe an image in the [`RenderGraphInfo`], returning its index there.
+    fn record_image(&mut self, image: Option<ImageId>, info: RenderImageInfo) -> usize {
+        let index = self.info.images.len();
+        if let Some(image) = image {
+            self.info_images.push((image, index));
+        }
+        self.info.images.push(info);
+        index
+    }
+
+    /// Describe a node in the [`RenderGraphInfo`]. Images and nodes unknown to the plan are
+    /// left out of its reads and dependencies.
+    fn record_node(
+        &mut self,
+        node: NodeId,
+        mut info: RenderNodeInfo,
+        reads: &[ImageId],
+        deps: &[NodeId],
+    ) {
+        info.reads.extend(reads.iter().filter_map(|read| self.image_index(*read)));
+        info.dependencies.extend(
+            deps.iter()
+                .filter_map(|dep| self.info_nodes.iter().position(|node| node == dep)),
+        );
+        self.info_nodes.push(node);
+        self.info.nodes.push(info);
+    }
+
+    /// Index of an image in the [`RenderGraphInfo`], if it is known to the plan.
+    fn image_index(&self, image: ImageId) -> Option<usize> {
+        self.info_images
+            .iter()
+            .find(|(known, _)| *known == image)
+            .map(|(_, index)| *index)
+    }
+}
+
+/// Access to the underlying rendy's `GraphBuilder`, for adding nodes that are not render groups
+/// of a render target, e.g. resolve, present or custom nodes. The nodes and images added
+/// through it are described in the [`RenderGraphInfo`], named by the type of their builder.
+#[derive(Debug)]
+pub struct PlanGraph<'a, B: Backend> {
+    plan_context: &'a mut PlanContext<B>,
+    prefix: String,
+}
+
+impl<'a, B: Backend> PlanGraph<'a, B> {
+    /// Create an image used by the nodes of the graph.
+    pub fn create_image(
+        &mut self,
+        kind: hal::image::Kind,
+        levels: hal::image::Level,
+        format: hal::format::Format,
+        clear: Option<hal::command::ClearValue>,
+    ) -> ImageId {
+        let options = ImageOptions {
+            kind,
+            levels,
+            format,
+            clear,
+        };
+        let image = self.plan_context.create_image(&options);
+        let name = format!("{} image {}", self.prefix, self.plan_context.info.images.len());
+        self.plan_context.record_image(Some(image), RenderImageInfo::new(name, &options));
+        image
+    }
+
+    /// Create a buffer of given size in bytes used by the nodes of the graph.
+    pub fn create_buffer(&mut self, size: u64) -> BufferId {
+        self.plan_context.graph_builder.create_buffer(size)
+    }
+
+    /// Add a node to the graph. Images the node accesses with write access are described as
+    /// written by it, the others as read.
+    pub fn add_node<N: NodeBuilder<B, GraphAuxData> + 'static>(&mut self, builder: N) -> NodeId {
+        let mut info = RenderNodeInfo::graph(format!(
+            "{} {}",
+            self.prefix,
+            group_name(std::any::type_name::<N>())
+        ));
+        let (writes, reads): (Vec<_>, Vec<_>) = builder
+            .images()
+            .into_iter()
+            .partition(|(_, access)| is_write(access.access));
+        let reads = reads.into_iter().map(|(image, _)| image).collect::<Vec<_>>();
+        info.writes = writes
+            .into_iter()
+            .filter_map(|(image, _)| self.plan_context.image_index(image))
+            .collect();
+        let deps = builder.dependencies();
+
+        let node = self.plan_context.graph_builder.add_node(builder);
+        self.plan_context.record_node(node, info, &reads, &deps);
+        node
+    }
 }
 
 /// A planning context focused on specific render target.
@@ -417,13 +655,15 @@ pub struct TargetPlanContext<'a, B: Backend> {
     key: Target,
     colors: usize,
     depth: bool,
-    actions: Vec<(i32, RenderableAction<B>)>,
+    actions: Vec<(i32, String, RenderableAction<B>)>,
     deps: Vec<NodeId>,
+    reads: Vec<ImageId>,
 }
 
 impl<'a, B: Backend> TargetPlanContext<'a, B> {
     /// Add new action to render target in defined order.
-    pub fn add(&mut self, order: impl Into<i32>, action: impl IntoAction<B>) -> Result<(), Error> {
+    pub fn add<A: IntoAction<B>>(&mut self, order: impl Into<i32>, action: A) -> Result<(), Error> {
+        let name = group_name(std::any::type_name::<A>());
         let action = action.into();
 
         if self.colors != action.colors() {
@@ -443,7 +683,7 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
             ));
         }
 
-        self.actions.push((order.into(), action));
+        self.actions.push((order.into(), name, action));
         Ok(())
     }
 
@@ -459,6 +699,17 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
         self.depth
     }
 
+    /// Get number of samples per pixel of current render target.
+    ///
+    /// Render groups added to a multisampled target must build their pipelines with matching
+    /// multisampling, see [`util::multisampling`](crate::util::multisampling).
+    #[must_use]
+    pub fn samples(&self) -> u8 {
+        self.plan_context
+            .target_metadata(self.key)
+            .map_or(1, |metadata| metadata.samples())
+    }
+
     /// Retrieve an image produced by other render target.
     ///
     /// # Errors
@@ -471,6 +722,7 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
                 .get_pass_node_raw(image.target())
                 .expect("Image without target node");
             self.add_dep(node);
+            self.add_read(i);
             i
         })
     }
@@ -487,11 +739,18 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
                     .get_pass_node_raw(image.target())
                     .expect("Image without target node");
                 self.add_dep(node);
+                self.add_read(i);
                 i
             })
         })
     }
 
+    fn add_read(&mut self, image: ImageId) {
+        if !self.reads.contains(&image) {
+            self.reads.push(image);
+        }
+    }
+
     /// Add explicit dependency on another node.
     ///
     /// This is done automatically when you use `get_image`.
@@ -501,12 +760,32 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
         }
     }
 
-    /// Access underlying rendy's `GraphBuilder` directly.
+    /// Access underlying rendy's `GraphBuilder` through a [`PlanGraph`].
     /// This is useful for adding custom rendering nodes
     /// that are not just standard graphics render passes,
-    /// e.g. for compute dispatch.
-    pub fn graph(&mut self) -> &mut GraphBuilder<B, GraphAuxData> {
-        self.plan_context.graph()
+    /// e.g. for resolving images the target reads.
+    pub fn graph(&mut self) -> PlanGraph<'_, B> {
+        PlanGraph {
+            prefix: format!("{:?}", self.key),
+            plan_context: &mut *self.plan_context,
+        }
+    }
+
+    /// Add a compute node running before the render target is drawn, e.g. to update buffers
+    /// it draws. The node depends on the nodes of the target dependencies.
+    pub fn add_compute(&mut self, desc: ComputeNodeDesc) -> NodeId {
+        let node = self
+            .plan_context
+            .graph()
+            .add_node(desc.into_builder(&self.deps));
+        self.plan_context.record_node(
+            node,
+            RenderNodeInfo::compute(format!("{:?}", self.key)),
+            &self.reads,
+            &self.deps,
+        );
+        self.add_dep(node);
+        node
     }
 
     /// Retrieve render target metadata, e.g. size.
@@ -521,6 +800,62 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
     }
 }
 
+/// A planning context for nodes added with [`RenderPlan::extend_graph`].
+#[derive(Debug)]
+pub struct GraphPlanContext<'a, B: Backend> {
+    plan_context: &'a mut PlanContext<B>,
+    factory: &'a Factory<B>,
+}
+
+impl<'a, B: Backend> GraphPlanContext<'a, B> {
+    /// Retrieve an image produced by a render target, together with the node producing it.
+    ///
+    /// # Errors
+    /// Results in an error if such image doesn't exist.
+    pub fn get_image(&mut self, image: TargetImage) -> Result<(ImageId, NodeId), Error> {
+        let id = self.plan_context.get_image(image)?;
+        let node = self
+            .plan_context
+            .get_pass_node_raw(image.target())
+            .expect("Image without target node");
+        Ok((id, node))
+    }
+
+    /// Access computed `NodeId` of render target.
+    pub fn get_node(&mut self, target: Target) -> Result<NodeId, Error> {
+        self.plan_context.get_node(target)
+    }
+
+    /// Retrieve render target metadata, e.g. size.
+    #[must_use]
+    pub fn target_metadata(&self, target: Target) -> Option<TargetMetadata> {
+        self.plan_context.target_metadata(target)
+    }
+
+    /// Access underlying rendy's `GraphBuilder` through a [`PlanGraph`].
+    pub fn graph(&mut self) -> PlanGraph<'_, B> {
+        PlanGraph {
+            plan_context: &mut *self.plan_context,
+            prefix: "Graph".into(),
+        }
+    }
+
+    /// Add a compute node, e.g. a post-process effect running on an image retrieved with
+    /// [`get_image`](Self::get_image), after the node producing it.
+    pub fn add_compute(&mut self, desc: ComputeNodeDesc) -> NodeId {
+        let node = self.plan_context.graph().add_node(desc.into_builder(&[]));
+        self.plan_context
+            .record_node(node, RenderNodeInfo::compute("Graph".into()), &[], &[]);
+        node
+    }
+
+    /// Access the factory the graph is built with.
+    #[must_use]
+    pub fn factory(&self) -> &Factory<B> {
+        self.factory
+    }
+}
+
 /// An identifier for output image of specific render target.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum TargetImage {
@@ -599,6 +934,7 @@ impl<B: Backend> TargetPlan<B> {
                 let mut framebuffer_width = u32::MAX;
                 let mut framebuffer_height = u32::MAX;
                 let mut framebuffer_layers = u16::MAX;
+                let mut framebuffer_samples = u8::MAX;
 
                 for color in colors {
                     match color {
@@ -615,12 +951,15 @@ impl<B: Backend> TargetPlan<B> {
                                 framebuffer_height = min(framebuffer_height, 1);
                             }
                             framebuffer_layers = min(framebuffer_layers, 1);
+                            framebuffer_samples = min(framebuffer_samples, 1);
                         }
                         OutputColor::Image(options) => {
                             let extent = options.kind.extent();
                             framebuffer_width = min(framebuffer_width, extent.width);
                             framebuffer_height = min(framebuffer_height, extent.height);
                             framebuffer_layers = min(framebuffer_layers, options.kind.num_layers());
+                            framebuffer_samples =
+                                min(framebuffer_samples, options.kind.num_samples());
                         }
                     };
                 }
@@ -629,11 +968,13 @@ impl<B: Backend> TargetPlan<B> {
                     framebuffer_width = min(framebuffer_width, extent.width);
                     framebuffer_height = min(framebuffer_height, extent.height);
                     framebuffer_layers = min(framebuffer_layers, options.kind.num_layers());
+                    framebuffer_samples = min(framebuffer_samples, options.kind.num_samples());
                 }
                 TargetMetadata {
                     width: framebuffer_width,
                     height: framebuffer_height,
                     layers: framebuffer_layers,
+                    samples: framebuffer_samples,
                 }
             })
     }
@@ -678,6 +1019,7 @@ impl<B: Backend> TargetPlan<B> {
             colors: outputs.colors.len(),
             depth: outputs.depth.is_some(),
             deps: vec![],
+            reads: vec![],
         };
 
         for extension in self.extensions {
@@ -685,19 +1027,35 @@ impl<B: Backend> TargetPlan<B> {
         }
 
         let TargetPlanContext {
-            mut actions, deps, ..
+            mut actions,
+            deps,
+            mut reads,
+            ..
         } = target_ctx;
 
         let mut subpass = SubpassBuilder::new();
         let mut pass = RenderPassNodeBuilder::new();
+        let mut info = RenderNodeInfo::render_pass(format!("{:?}", self.key));
+        let first_slot = ctx.info.group_count();
 
         actions.sort_by_key(|a| a.0);
-        for action in actions.drain(..).map(|a| a.1) {
+        for (i, (order, name, action)) in actions.drain(..).enumerate() {
             match action {
                 RenderableAction::RenderGroup(group) => {
-                    subpass.add_dyn_group(group);
+                    for (image, _) in group.images() {
+                        if !reads.contains(&image) {
+                            reads.push(image);
+                        }
+                    }
+                    if ctx.timings {
+                        subpass
+                            .add_dyn_group(Box::new(TimedGroupBuilder::new(group, first_slot + i)));
+                    } else {
+                        subpass.add_dyn_group(group);
+                    }
                 }
             }
+            info.groups.push(RenderGroupInfo { name, order });
         }
 
         for (i, color) in outputs.colors.drain(..).enumerate() {
@@ -705,11 +1063,28 @@ impl<B: Backend> TargetPlan<B> {
                 OutputColor::Surface(surface, clear) => {
                     subpass.add_color_surface();
                     pass.add_surface(surface, suggested_extent, clear);
+                    let image = ctx.record_image(
+                        None,
+                        RenderImageInfo {
+                            name: format!("{:?} surface", self.key),
+                            width: suggested_extent.width,
+                            height: suggested_extent.height,
+                            layers: 1,
+                            samples: 1,
+                            format: None,
+                        },
+                    );
+                    info.writes.push(image);
                 }
                 OutputColor::Image(opts) => {
                     let node = ctx.create_image(&opts);
                     ctx.register_output(TargetImage::Color(self.key, i), node)?;
                     subpass.add_color(node);
+                    let image = ctx.record_image(
+                        Some(node),
+                        RenderImageInfo::new(format!("{:?} color {}", self.key, i), &opts),
+                    );
+                    info.writes.push(image);
                 }
             }
         }
@@ -718,14 +1093,21 @@ impl<B: Backend> TargetPlan<B> {
             let node = ctx.create_image(&opts);
             ctx.register_output(TargetImage::Depth(self.key), node)?;
             subpass.set_depth_stencil(node);
+            let image = ctx.record_image(
+                Some(node),
+                RenderImageInfo::new(format!("{:?} depth", self.key), &opts),
+            );
+            info.writes.push(image);
         }
 
-        for node in deps {
-            subpass.add_dependency(node);
+        for node in &deps {
+            subpass.add_dependency(*node);
         }
 
         pass.add_subpass(subpass);
         ctx.submit_pass(self.key, pass);
+        let node = ctx.get_pass_node_raw(self.key).expect("Just submitted");
+        ctx.record_node(node, info, &reads, &deps);
         Ok(())
     }
 }
@@ -817,6 +1199,21 @@ pub enum Target {
     /// Render target for shadow mapping.
     /// Builtin plugins use cascaded shadow maps.
     ShadowMap,
+    /// Floating point render target the scene is drawn into
+    /// before being tonemapped into `Main` by `RenderTonemap`.
+    Hdr,
+    /// Render target the scene is drawn or tonemapped into
+    /// before being color graded into `Main` by `RenderColorGrading`.
+    ColorGrading,
+    /// Render target the scene is drawn, tonemapped or color graded into
+    /// before being filtered into `Main` by `RenderColorBlindFilter`.
+    ColorBlindFilter,
+    /// Render target of the scene below window resolution, upscaled into
+    /// `Main` by `RenderToWindow` when rendering at a render scale.
+    Scaled,
+    /// Render target presented to the secondary window with given id by
+    /// `RenderToSecondaryWindows`, see `SecondaryWindows::open`.
+    Window(u32),
     /// Custom render target identifier.
     Custom(&'static str),
 }
@@ -924,7 +1321,7 @@ mod tests {
         )
         .unwrap();
 
-        let planned_graph = plan.build(&factory).unwrap();
+        let (planned_graph, _) = plan.build(&factory).unwrap();
 
         let mut manual_graph = GraphBuilder::<DefaultBackend, World>::new();
         let color = manual_graph.create_image(kind, 1, Format::Rgb8Unorm, None);
@@ -1005,7 +1402,7 @@ mod tests {
             Ok(())
         });
 
-        let planned_graph = plan.build(&factory).unwrap();
+        let (planned_graph, _) = plan.build(&factory).unwrap();
 
         let mut manual_graph = GraphBuilder::<DefaultBackend, World>::new();
         let depth = manual_graph.create_image(
@@ -9,14 +9,17 @@ use amethyst_assets::{
     Asset,
 };
 use amethyst_core::{
-    ecs::Entity,
+    ecs::{component, Entity, IntoQuery, World},
     geometry::Ray,
     math::{Matrix4, Point2, Point3, Vector2},
     transform::Transform,
 };
+use rendy::hal::pso::Rect;
 use serde::{de, de::SeqAccess, ser::SerializeSeq};
 use type_uuid::TypeUuid;
 
+use crate::{bundle::Target, render_target::RenderTarget};
+
 /// Camera struct.
 ///
 /// Contains a projection matrix to convert from world/eye-space
@@ -289,6 +292,151 @@ pub struct ActiveCamera {
     pub entity: Option<Entity>,
 }
 
+/// Region of the window a camera renders into, used for split screen.
+///
+/// When at least one camera has a `Viewport`, the window is no longer rendered from the
+/// [`ActiveCamera`]: every camera with a `Viewport` renders into its own region instead. The
+/// coordinates are normalized, `(0.0, 0.0)` being the top left corner of the window and
+/// `(1.0, 1.0)` the bottom right one.
+///
+/// The projection of the camera should match the aspect ratio of the region, not the one of the
+/// window.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Viewport {
+    /// Left edge of the region
+    pub x: f32,
+    /// Top edge of the region
+    pub y: f32,
+    /// Width of the region
+    pub width: f32,
+    /// Height of the region
+    pub height: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
+}
+
+impl Viewport {
+    /// Creates a viewport covering given region of the window.
+    #[must_use]
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates the viewport of a cell of the window divided into a grid, counting cells row by row
+    /// from the top left one.
+    ///
+    /// `Viewport::grid(2, 1, 0)` is the left half of the window, `Viewport::grid(2, 2, 3)` its
+    /// bottom right quarter.
+    #[must_use]
+    pub fn grid(columns: u32, rows: u32, index: u32) -> Self {
+        let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+        Self::new(
+            (index % columns) as f32 * width,
+            (index / columns) as f32 * height,
+            width,
+            height,
+        )
+    }
+
+    /// Width divided by height of the region on a surface of given size.
+    #[must_use]
+    pub fn aspect_ratio(&self, surface_width: f32, surface_height: f32) -> f32 {
+        (self.width * surface_width) / (self.height * surface_height)
+    }
+
+    /// Pixel rectangle of the region on a surface of given size.
+    #[must_use]
+    pub fn pixel_rect(&self, surface_width: u32, surface_height: u32) -> Rect {
+        let left = (self.x * surface_width as f32).round();
+        let top = (self.y * surface_height as f32).round();
+        let right = ((self.x + self.width) * surface_width as f32).round();
+        let bottom = ((self.y + self.height) * surface_height as f32).round();
+        Rect {
+            x: left as i16,
+            y: top as i16,
+            w: (right - left) as i16,
+            h: (bottom - top) as i16,
+        }
+    }
+
+    /// Cameras rendering into a region of the window, ordered by the position of their region
+    /// from top to bottom and left to right.
+    ///
+    /// Render passes drawing a split screen use one instance per viewport, identified by its index
+    /// in this list.
+    #[must_use]
+    pub fn cameras(world: &World) -> Vec<(Entity, Viewport)> {
+        let mut cameras: Vec<_> = <(Entity, &Camera, &Viewport)>::query()
+            .filter(!component::<RenderTarget>())
+            .iter(world)
+            .map(|(entity, _, viewport)| (*entity, *viewport))
+            .collect();
+        cameras.sort_by(|(_, a), (_, b)| a.screen_order(b));
+        cameras
+    }
+
+    /// Order of the regions from top to bottom and left to right, the order of
+    /// [`cameras`](Self::cameras).
+    pub(crate) fn screen_order(&self, other: &Self) -> std::cmp::Ordering {
+        (self.y, self.x)
+            .partial_cmp(&(other.y, other.x))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+
+    /// Viewports of the split screen cameras rendering into `target`, in the order of
+    /// [`cameras`](Self::cameras). Only the window has them.
+    #[must_use]
+    pub fn of_target(world: &World, target: Target) -> Vec<Viewport> {
+        if target == Target::Main {
+            Self::cameras(world)
+                .into_iter()
+                .map(|(_, viewport)| viewport)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Viewport index of each render group instance drawing the given viewports, a single one
+    /// drawing the whole target without split screen.
+    #[must_use]
+    pub fn instances(viewports: &[Viewport]) -> Vec<Option<usize>> {
+        if viewports.is_empty() {
+            vec![None]
+        } else {
+            (0..viewports.len()).map(Some).collect()
+        }
+    }
+
+    /// Pixel rectangle drawn by the render pass instance of given viewport index, the whole
+    /// surface when there is no index or no such viewport.
+    #[must_use]
+    pub fn pixel_rect_of(
+        world: &World,
+        index: Option<usize>,
+        surface_width: u32,
+        surface_height: u32,
+    ) -> Rect {
+        index
+            .and_then(|index| {
+                Self::cameras(world)
+                    .get(index)
+                    .map(|(_, viewport)| *viewport)
+            })
+            .unwrap_or_default()
+            .pixel_rect(surface_width, surface_height)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     //! Tests for amethysts camera implementation.
@@ -664,4 +812,40 @@ mod tests {
         assert_ulps_eq!(ray.origin, expected_ray.origin);
         assert_ulps_eq!(ray.direction, expected_ray.direction);
     }
+
+    #[test]
+    fn viewport_grid_cells() {
+        assert_eq!(Viewport::grid(2, 1, 1), Viewport::new(0.5, 0.0, 0.5, 1.0));
+        assert_eq!(Viewport::grid(2, 2, 2), Viewport::new(0.0, 0.5, 0.5, 0.5));
+        assert_ulps_eq!(
+            Viewport::grid(2, 1, 0).aspect_ratio(1600.0, 900.0),
+            800.0 / 900.0
+        );
+    }
+
+    #[test]
+    fn viewport_pixel_rect_covers_surface() {
+        let left = Viewport::grid(3, 1, 0).pixel_rect(1000, 600);
+        let middle = Viewport::grid(3, 1, 1).pixel_rect(1000, 600);
+        let right = Viewport::grid(3, 1, 2).pixel_rect(1000, 600);
+
+        assert_eq!((left.x, left.w, left.h), (0, 333, 600));
+        assert_eq!(middle.x, left.x + left.w);
+        assert_eq!(right.x, middle.x + middle.w);
+        assert_eq!(right.x + right.w, 1000);
+    }
+
+    #[test]
+    fn viewport_cameras_are_ordered_by_region() {
+        let mut world = World::default();
+        let bottom = world.push((Camera::standard_2d(1.0, 1.0), Viewport::grid(1, 2, 1)));
+        let top = world.push((Camera::standard_2d(1.0, 1.0), Viewport::grid(1, 2, 0)));
+        world.push((Camera::standard_2d(1.0, 1.0),));
+
+        let cameras: Vec<_> = Viewport::cameras(&world)
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(cameras, vec![top, bottom]);
+    }
 }
@@ -0,0 +1,588 @@
+//! Render graph nodes dispatching compute shaders.
+//!
+//! A [`ComputeNodeDesc`] runs a compute shader over buffers and images of the render graph,
+//! which it binds in the order they're added, to the set 0 of the shader. The barriers between
+//! the node and the other nodes using its buffers and images are managed by the graph.
+//!
+//! Plugins add compute nodes while planning, with
+//! [`TargetPlanContext::add_compute`](crate::bundle::TargetPlanContext::add_compute) when a
+//! render target uses the results, e.g. to draw particles simulated on the GPU, or with
+//! [`GraphPlanContext::add_compute`](crate::bundle::GraphPlanContext::add_compute) when the node
+//! uses the images of render targets, e.g. for a post-process effect.
+//!
+//! ```ignore
+//! plan.extend_target(Target::Main, move |ctx| {
+//!     let particles = ctx.graph().create_buffer(PARTICLES * 32);
+//!     ctx.add_compute(
+//!         ComputeNodeDesc::new(simulate_shader, ComputeDispatch::Groups([PARTICLES / 64, 1, 1]))
+//!             .with_storage_buffer(particles)
+//!             .with_params(|aux| SimulateArgs::from(&*aux.resources.get::<Time>().unwrap())),
+//!     );
+//!     ctx.add(RenderOrder::Transparent, DrawParticlesDesc.builder().with_buffer(particles))
+//! });
+//! ```
+
+use std::iter::once;
+
+use derivative::Derivative;
+use glsl_layout::Uniform;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, Compute, ExecutableState, Family, Fence, MultiShot,
+        PendingState, PrimaryLevel, Queue, QueueType, SimultaneousUse, Submission, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, BufferId, DescBuilder,
+        GraphContext, ImageAccess, ImageId, Node, NodeBuffer, NodeBuildError, NodeDesc, NodeId,
+        NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    memory::Dynamic,
+    resource::{
+        Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView,
+        ImageViewInfo, Sampler, SubRange,
+    },
+    shader::{Shader, SpirvShader},
+};
+
+use crate::{system::GraphAuxData, types::Backend, util};
+
+/// Resource of the render graph bound to a compute shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBinding {
+    /// Buffer read and written by the shader, a `buffer` block in GLSL
+    StorageBuffer(BufferId),
+    /// Image read and written by the shader, an `image2D` in GLSL
+    StorageImage(ImageId),
+    /// Image sampled by the shader with linear filtering, a `sampler2D` in GLSL
+    SampledImage(ImageId),
+}
+
+/// Number of work groups dispatched by a [`ComputeNodeDesc`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeDispatch {
+    /// Fixed number of work groups along each axis.
+    Groups([u32; 3]),
+    /// Enough work groups of the given local size to cover every pixel of an image.
+    Image {
+        /// Image covered, which must be bound to the node
+        image: ImageId,
+        /// Local size of the shader along the X and Y axes
+        local_size: [u32; 2],
+    },
+}
+
+impl ComputeDispatch {
+    fn groups(self, ctx: &GraphContext<impl Backend>) -> [u32; 3] {
+        match self {
+            ComputeDispatch::Groups(groups) => groups,
+            ComputeDispatch::Image { image, local_size } => {
+                let extent = ctx
+                    .get_image(image)
+                    .expect("Compute dispatch image does not exist")
+                    .kind()
+                    .extent();
+                [
+                    (extent.width + local_size[0] - 1) / local_size[0],
+                    (extent.height + local_size[1] - 1) / local_size[1],
+                    1,
+                ]
+            }
+        }
+    }
+}
+
+type ParamsWriter = Box<dyn Fn(&GraphAuxData) -> Vec<u8> + Send + Sync>;
+
+/// Describes a node dispatching a compute shader, see the [module documentation](self).
+///
+/// The parameters set with [`with_params`](Self::with_params) are bound as a uniform buffer to
+/// the binding 0 of the set 1 of the shader.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ComputeNodeDesc {
+    shader: SpirvShader,
+    dispatch: ComputeDispatch,
+    bindings: Vec<ComputeBinding>,
+    dependencies: Vec<NodeId>,
+    #[derivative(Debug = "ignore")]
+    params: Option<(u64, ParamsWriter)>,
+}
+
+impl ComputeNodeDesc {
+    /// Creates a node dispatching `shader`, a compute shader with `main` as entry point.
+    #[must_use]
+    pub fn new(shader: SpirvShader, dispatch: ComputeDispatch) -> Self {
+        Self {
+            shader,
+            dispatch,
+            bindings: Vec::new(),
+            dependencies: Vec::new(),
+            params: None,
+        }
+    }
+
+    /// Binds a resource to the next binding of the set 0 of the shader.
+    #[must_use]
+    pub fn with_binding(mut self, binding: ComputeBinding) -> Self {
+        self.bindings.push(binding);
+        self
+    }
+
+    /// Binds a buffer read and written by the shader to its next binding.
+    #[must_use]
+    pub fn with_storage_buffer(self, buffer: BufferId) -> Self {
+        self.with_binding(ComputeBinding::StorageBuffer(buffer))
+    }
+
+    /// Binds an image read and written by the shader to its next binding.
+    #[must_use]
+    pub fn with_storage_image(self, image: ImageId) -> Self {
+        self.with_binding(ComputeBinding::StorageImage(image))
+    }
+
+    /// Binds an image sampled by the shader to its next binding.
+    #[must_use]
+    pub fn with_sampled_image(self, image: ImageId) -> Self {
+        self.with_binding(ComputeBinding::SampledImage(image))
+    }
+
+    /// Runs the node after `node`, in addition to the nodes it depends on through the planning
+    /// context.
+    #[must_use]
+    pub fn with_dependency(mut self, node: NodeId) -> Self {
+        if !self.dependencies.contains(&node) {
+            self.dependencies.push(node);
+        }
+        self
+    }
+
+    /// Passes parameters computed each frame from the world and resources to the shader.
+    #[must_use]
+    pub fn with_params<T, F>(mut self, params: F) -> Self
+    where
+        T: Uniform,
+        T::Std140: Sized,
+        F: Fn(&GraphAuxData) -> T + Send + Sync + 'static,
+    {
+        self.params = Some((
+            std::mem::size_of::<T::Std140>() as u64,
+            Box::new(move |aux| util::slice_as_bytes(&[params(aux).std140()]).to_vec()),
+        ));
+        self
+    }
+
+    /// The resources bound to the shader, in binding order.
+    #[must_use]
+    pub fn bindings(&self) -> &[ComputeBinding] {
+        &self.bindings
+    }
+
+    /// Builder of the node, with its buffers, images and dependencies.
+    pub(crate) fn into_builder<B: Backend>(
+        self,
+        extra_dependencies: &[NodeId],
+    ) -> DescBuilder<B, GraphAuxData, Self> {
+        let bindings = self.bindings.clone();
+        let dependencies = self.dependencies.clone();
+        let mut builder = NodeDesc::<B, GraphAuxData>::builder(self);
+        for binding in bindings {
+            builder = match binding {
+                ComputeBinding::StorageBuffer(buffer) => builder.with_buffer(buffer),
+                ComputeBinding::StorageImage(image) | ComputeBinding::SampledImage(image) => {
+                    builder.with_image(image)
+                }
+            };
+        }
+        for node in dependencies
+            .into_iter()
+            .chain(extra_dependencies.iter().copied())
+        {
+            builder = builder.with_dependency(node);
+        }
+        builder
+    }
+}
+
+/// Uniform buffers of the parameters of a [`ComputeNode`], one per frame in flight.
+struct NodeParams<B: Backend> {
+    layout: Handle<DescriptorSetLayout<B>>,
+    per_frame: Vec<(Escape<Buffer<B>>, Escape<DescriptorSet<B>>)>,
+    writer: ParamsWriter,
+}
+
+impl<B: Backend> NodeDesc<B, GraphAuxData> for ComputeNodeDesc {
+    type Node = ComputeNode<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        self.bindings
+            .iter()
+            .filter_map(|binding| {
+                match binding {
+                    ComputeBinding::StorageBuffer(_) => {
+                        Some(BufferAccess {
+                            access: hal::buffer::Access::SHADER_READ
+                                | hal::buffer::Access::SHADER_WRITE,
+                            usage: hal::buffer::Usage::STORAGE,
+                            stages: pso::PipelineStage::COMPUTE_SHADER,
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        self.bindings
+            .iter()
+            .filter_map(|binding| {
+                match binding {
+                    ComputeBinding::StorageImage(_) => {
+                        Some(ImageAccess {
+                            access: hal::image::Access::SHADER_READ
+                                | hal::image::Access::SHADER_WRITE,
+                            usage: hal::image::Usage::STORAGE,
+                            layout: hal::image::Layout::General,
+                            stages: pso::PipelineStage::COMPUTE_SHADER,
+                        })
+                    }
+                    ComputeBinding::SampledImage(_) => {
+                        Some(ImageAccess {
+                            access: hal::image::Access::SHADER_READ,
+                            usage: hal::image::Usage::SAMPLED,
+                            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                            stages: pso::PipelineStage::COMPUTE_SHADER,
+                        })
+                    }
+                    ComputeBinding::StorageBuffer(_) => None,
+                }
+            })
+            .collect()
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &GraphAuxData,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, NodeBuildError> {
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(self.bindings.iter().map(
+                |binding| {
+                    let ty = match binding {
+                        ComputeBinding::StorageBuffer(_) => {
+                            pso::DescriptorType::Buffer {
+                                ty: pso::BufferDescriptorType::Storage { read_only: false },
+                                format: pso::BufferDescriptorFormat::Structured {
+                                    dynamic_offset: false,
+                                },
+                            }
+                        }
+                        ComputeBinding::StorageImage(_) => {
+                            pso::DescriptorType::Image {
+                                ty: pso::ImageDescriptorType::Storage { read_only: false },
+                            }
+                        }
+                        ComputeBinding::SampledImage(_) => {
+                            pso::DescriptorType::Image {
+                                ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                            }
+                        }
+                    };
+                    (1, ty, pso::ShaderStageFlags::COMPUTE)
+                },
+            )))
+            .map_err(NodeBuildError::OutOfMemory)?
+            .into();
+        let set = factory
+            .create_descriptor_set(layout.clone())
+            .map_err(NodeBuildError::OutOfMemory)?;
+
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Linear,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| NodeBuildError::Pipeline(pso::CreationError::Other))?;
+        let mut views = Vec::with_capacity(images.len());
+        for node_image in &images {
+            let image = ctx
+                .get_image(node_image.id)
+                .expect("Compute image does not exist")
+                .clone();
+            let format = image.format();
+            views.push(
+                factory
+                    .create_image_view(
+                        image,
+                        ImageViewInfo {
+                            view_kind: hal::image::ViewKind::D2,
+                            format,
+                            swizzle: hal::format::Swizzle::NO,
+                            range: hal::image::SubresourceRange {
+                                aspects: hal::format::Aspects::COLOR,
+                                levels: 0..1,
+                                layers: 0..1,
+                            },
+                        },
+                    )
+                    .map_err(|_| NodeBuildError::Pipeline(pso::CreationError::Other))?,
+            );
+        }
+
+        let (mut buffer_index, mut image_index) = (0, 0);
+        let mut descriptors = Vec::with_capacity(self.bindings.len());
+        for binding in &self.bindings {
+            descriptors.push(match binding {
+                ComputeBinding::StorageBuffer(_) => {
+                    let buffer = ctx
+                        .get_buffer(buffers[buffer_index].id)
+                        .expect("Compute buffer does not exist");
+                    buffer_index += 1;
+                    pso::Descriptor::Buffer(buffer.raw(), SubRange::WHOLE)
+                }
+                ComputeBinding::StorageImage(_) => {
+                    image_index += 1;
+                    pso::Descriptor::Image(
+                        views[image_index - 1].raw(),
+                        hal::image::Layout::General,
+                    )
+                }
+                ComputeBinding::SampledImage(_) => {
+                    image_index += 1;
+                    pso::Descriptor::CombinedImageSampler(
+                        views[image_index - 1].raw(),
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                        sampler.raw(),
+                    )
+                }
+            });
+        }
+        unsafe {
+            factory.write_descriptor_sets(descriptors.into_iter().enumerate().map(
+                |(binding, descriptor)| util::desc_write(set.raw(), binding as u32, descriptor),
+            ));
+        }
+
+        // Each frame in flight has its own parameters, the others may still be read.
+        let params = match self.params {
+            Some((size, writer)) => {
+                let layout: Handle<DescriptorSetLayout<B>> = factory
+                    .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                        1,
+                        pso::DescriptorType::Buffer {
+                            ty: pso::BufferDescriptorType::Uniform,
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: false,
+                            },
+                        },
+                        pso::ShaderStageFlags::COMPUTE,
+                    ))))
+                    .map_err(NodeBuildError::OutOfMemory)?
+                    .into();
+                let mut per_frame = Vec::new();
+                for _ in 0..ctx.frames_in_flight() {
+                    let buffer = factory
+                        .create_buffer(
+                            BufferInfo {
+                                size,
+                                usage: hal::buffer::Usage::UNIFORM,
+                            },
+                            Dynamic,
+                        )
+                        .map_err(|_| NodeBuildError::OutOfMemory(hal::device::OutOfMemory::Host))?;
+                    let set = factory
+                        .create_descriptor_set(layout.clone())
+                        .map_err(NodeBuildError::OutOfMemory)?;
+                    unsafe {
+                        factory.write_descriptor_sets(once(util::desc_write(
+                            set.raw(),
+                            0,
+                            pso::Descriptor::Buffer(buffer.raw(), SubRange::WHOLE),
+                        )));
+                    }
+                    per_frame.push((buffer, set));
+                }
+                Some(NodeParams {
+                    layout,
+                    per_frame,
+                    writer,
+                })
+            }
+            None => None,
+        };
+
+        let pipeline_layout = unsafe {
+            let mut layouts = vec![layout.raw()];
+            if let Some(params) = &params {
+                layouts.push(params.layout.raw());
+            }
+            factory
+                .device()
+                .create_pipeline_layout(layouts, None as Option<(_, _)>)
+        }
+        .map_err(NodeBuildError::OutOfMemory)?;
+
+        let pipeline = unsafe {
+            let module = self
+                .shader
+                .module(factory)
+                .map_err(|_| NodeBuildError::Pipeline(pso::CreationError::Other))?;
+            let pipeline = factory.device().create_compute_pipeline(
+                &pso::ComputePipelineDesc::new(
+                    pso::EntryPoint {
+                        entry: "main",
+                        module: &module,
+                        specialization: pso::Specialization::default(),
+                    },
+                    &pipeline_layout,
+                ),
+                None,
+            );
+            factory.destroy_shader_module(module);
+            pipeline
+        };
+        let pipeline = match pipeline {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                unsafe {
+                    factory.device().destroy_pipeline_layout(pipeline_layout);
+                }
+                return Err(NodeBuildError::Pipeline(e));
+            }
+        };
+
+        let mut pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?;
+        let groups = self.dispatch.groups(ctx);
+        let param_sets = params.as_ref().map_or(1, |params| params.per_frame.len());
+        let mut submits = Vec::with_capacity(param_sets);
+        let mut command_buffers = Vec::with_capacity(param_sets);
+        for (index, command_buffer) in pool.allocate_buffers(param_sets).into_iter().enumerate() {
+            let mut command_buffer = command_buffer.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = command_buffer.encoder();
+            unsafe {
+                gfx_acquire_barriers(ctx, buffers.iter(), images.iter(), &mut encoder);
+                encoder.bind_compute_pipeline(&pipeline);
+                encoder.bind_compute_descriptor_sets(
+                    &pipeline_layout,
+                    0,
+                    once(set.raw()),
+                    std::iter::empty(),
+                );
+                if let Some(params) = &params {
+                    encoder.bind_compute_descriptor_sets(
+                        &pipeline_layout,
+                        1,
+                        once(params.per_frame[index].1.raw()),
+                        std::iter::empty(),
+                    );
+                }
+                encoder.dispatch(groups[0], groups[1], groups[2]);
+                gfx_release_barriers(ctx, buffers.iter(), images.iter(), &mut encoder);
+            }
+            let (submit, command_buffer) = command_buffer.finish().submit();
+            submits.push(submit);
+            command_buffers.push(command_buffer);
+        }
+
+        Ok(ComputeNode {
+            pool,
+            submits,
+            command_buffers,
+            pipeline,
+            pipeline_layout,
+            set,
+            views,
+            sampler,
+            params,
+        })
+    }
+}
+
+/// Node dispatching a compute shader, see [`ComputeNodeDesc`].
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ComputeNode<B: Backend> {
+    pool: CommandPool<B, QueueType>,
+    submits: Vec<Submit<B, SimultaneousUse>>,
+    command_buffers: Vec<
+        CommandBuffer<
+            B,
+            QueueType,
+            PendingState<ExecutableState<MultiShot<SimultaneousUse>>>,
+            PrimaryLevel,
+        >,
+    >,
+    pipeline: B::ComputePipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    views: Vec<Escape<ImageView<B>>>,
+    sampler: Handle<Sampler<B>>,
+    #[derivative(Debug = "ignore")]
+    params: Option<NodeParams<B>>,
+}
+
+impl<B: Backend> Node<B, GraphAuxData> for ComputeNode<B> {
+    type Capability = Compute;
+
+    fn run<'a>(
+        &mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        queue: &mut Queue<B>,
+        aux: &GraphAuxData,
+        frames: &Frames<B>,
+        waits: &[(&'a B::Semaphore, pso::PipelineStage)],
+        signals: &[&'a B::Semaphore],
+        fence: Option<&mut Fence<B>>,
+    ) {
+        let index = match &mut self.params {
+            Some(params) => {
+                let index = frames.next().index() as usize % params.per_frame.len();
+                let bytes = (params.writer)(aux);
+                if let Err(e) = unsafe {
+                    factory.upload_visible_buffer(&mut params.per_frame[index].0, 0, &bytes)
+                } {
+                    log::error!("Unable to write compute parameters: {}", e);
+                }
+                index
+            }
+            None => 0,
+        };
+
+        unsafe {
+            queue.submit(
+                once(
+                    Submission::new()
+                        .submits(once(&self.submits[index]))
+                        .wait(waits.iter().cloned())
+                        .signal(signals.iter().cloned()),
+                ),
+                fence,
+            );
+        }
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        drop(self.submits);
+        self.pool.free_buffers(
+            self.command_buffers
+                .into_iter()
+                .map(|command_buffer| command_buffer.mark_complete()),
+        );
+        factory.destroy_command_pool(self.pool);
+        factory.device().destroy_compute_pipeline(self.pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.pipeline_layout);
+    }
+}
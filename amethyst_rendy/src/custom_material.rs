@@ -0,0 +1,468 @@
+//! Materials drawn with user supplied shaders.
+//!
+//! A [`CustomMaterial`] holds a vertex and a fragment shader together with a description of the
+//! uniform block they read. Entities with a `Handle<CustomMaterial>`, a `Handle<Mesh>` and a
+//! `Transform` are drawn by the [`RenderCustomMaterials`](crate::plugins::RenderCustomMaterials)
+//! plugin.
+//!
+//! Materials are imported from `.material` files written in RON:
+//!
+//! ```ron
+//! (
+//!     vertex: Glsl(r#"
+//!         #version 450
+//!         ...
+//!     "#),
+//!     fragment: Glsl(r#"..."#),
+//!     uniforms: [
+//!         (name: "color", value: Vec4((1.0, 0.5, 0.0, 1.0))),
+//!         (name: "speed", value: Float(2.0)),
+//!     ],
+//!     params: [
+//!         (name: "dissolve", value: Float(0.0)),
+//!     ],
+//! )
+//! ```
+//!
+//! GLSL sources are compiled when the material is loaded and require the `shader-compiler`
+//! feature, precompiled shaders can be given as `Spirv` bytes instead. Modifying a material
+//! file while the asset daemon is running reloads it, and the pipeline drawing it is rebuilt.
+//! When the new shaders fail to compile, the error is logged and the previous version is kept.
+//!
+//! # Shader interface
+//!
+//! Vertex shaders receive the positions, normals and texture coordinates of meshes, followed by
+//! the per instance model matrix and tint:
+//!
+//! ```glsl,ignore
+//! layout(location = 0) in vec3 position;
+//! layout(location = 1) in vec3 normal;
+//! layout(location = 2) in vec2 tex_coord;
+//! layout(location = 3) in mat4 model;
+//! layout(location = 7) in vec4 tint;
+//! layout(location = 8) in vec4 params[4];
+//! ```
+//!
+//! Descriptor set 0 holds the camera and lights as in the other 3D passes (see
+//! [`EnvironmentSub`](crate::submodules::EnvironmentSub)), and set 1 the material uniforms,
+//! laid out with `std140` rules in the order they are described:
+//!
+//! ```glsl,ignore
+//! layout(std140, set = 1, binding = 0) uniform CustomMaterialArgs {
+//!     vec4 color;
+//!     float speed;
+//! };
+//! ```
+//!
+//! # Per-entity parameters
+//!
+//! Up to [`MAX_MATERIAL_PARAMS`] parameters can be declared in `params`. They are uploaded with
+//! the instance data of every entity, so gameplay code can drive effects like a dissolve amount
+//! or a damage flash without a material per entity. Entities override the declared defaults
+//! with a [`MaterialParams`] component:
+//!
+//! ```ignore
+//! world.push((
+//!     material,
+//!     mesh,
+//!     Transform::default(),
+//!     MaterialParams::default().with("dissolve", UniformValue::Float(0.5)),
+//! ));
+//! ```
+//!
+//! The n-th declared parameter is read from `params[n]`, starting with its first component.
+//! Unused components are zero.
+
+use amethyst_assets::{
+    register_asset_type, Asset, AssetProcessorSystem, AssetStorage, Format, LoadHandle,
+    ProcessableAsset, ProcessingState,
+};
+use amethyst_error::{format_err, Error, ResultExt};
+use rendy::{hal::pso::ShaderStageFlags, shader::SpirvShader};
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+/// Number of per-entity parameters a [`CustomMaterial`] can declare.
+pub const MAX_MATERIAL_PARAMS: usize = 4;
+
+/// Source of a custom material shader.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ShaderSource {
+    /// Compiled SPIR-V bytes.
+    Spirv(Vec<u8>),
+    /// GLSL source, compiled when the material is loaded. Requires the `shader-compiler` feature.
+    Glsl(String),
+}
+
+impl ShaderSource {
+    fn compile(&self, stage: ShaderStageFlags) -> Result<SpirvShader, Error> {
+        match self {
+            ShaderSource::Spirv(bytes) => {
+                SpirvShader::from_bytes(bytes, stage, "main")
+                    .with_context(|_| format_err!("Invalid SPIR-V for {:?} stage", stage))
+            }
+            ShaderSource::Glsl(source) => compile_glsl(source, stage),
+        }
+    }
+}
+
+#[cfg(feature = "shader-compiler")]
+fn compile_glsl(source: &str, stage: ShaderStageFlags) -> Result<SpirvShader, Error> {
+    use rendy::shader::{Shader, ShaderKind, SourceLanguage, SourceShaderInfo};
+
+    let kind = if stage == ShaderStageFlags::VERTEX {
+        ShaderKind::Vertex
+    } else {
+        ShaderKind::Fragment
+    };
+    let info = SourceShaderInfo::new(
+        source,
+        "custom_material",
+        kind,
+        SourceLanguage::GLSL,
+        "main",
+    );
+    let spirv = info
+        .spirv()
+        .map_err(|e| format_err!("Failed to compile {:?} shader: {:?}", stage, e))?;
+    Ok(SpirvShader::new(spirv.into_owned(), stage, "main"))
+}
+
+#[cfg(not(feature = "shader-compiler"))]
+fn compile_glsl(_source: &str, stage: ShaderStageFlags) -> Result<SpirvShader, Error> {
+    Err(format_err!(
+        "Compiling GLSL for {:?} stage requires the `shader-compiler` feature",
+        stage
+    ))
+}
+
+/// Value of a custom material uniform, which also determines its type in the uniform block.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UniformValue {
+    /// `float`
+    Float(f32),
+    /// `vec2`
+    Vec2([f32; 2]),
+    /// `vec3`
+    Vec3([f32; 3]),
+    /// `vec4`
+    Vec4([f32; 4]),
+}
+
+impl UniformValue {
+    fn components(&self) -> &[f32] {
+        match self {
+            UniformValue::Float(v) => std::slice::from_ref(v),
+            UniformValue::Vec2(v) => v,
+            UniformValue::Vec3(v) => v,
+            UniformValue::Vec4(v) => v,
+        }
+    }
+
+    /// Alignment of the value in a `std140` block, in bytes.
+    fn alignment(&self) -> usize {
+        match self {
+            UniformValue::Float(_) => 4,
+            UniformValue::Vec2(_) => 8,
+            UniformValue::Vec3(_) | UniformValue::Vec4(_) => 16,
+        }
+    }
+}
+
+/// Named member of the uniform block of a custom material, with its default value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniformDesc {
+    /// Name of the uniform, used to look it up.
+    pub name: String,
+    /// Value of the uniform.
+    pub value: UniformValue,
+}
+
+/// Shaders and uniforms of a [`CustomMaterial`] as stored in material files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "3b7a5d4e-8f0c-4b5e-a1d2-6c9e7f3a2b18"]
+pub struct CustomMaterialData {
+    /// Vertex shader.
+    pub vertex: ShaderSource,
+    /// Fragment shader.
+    pub fragment: ShaderSource,
+    /// Members of the material uniform block, in declaration order.
+    #[serde(default)]
+    pub uniforms: Vec<UniformDesc>,
+    /// Per-entity parameters with their defaults, at most [`MAX_MATERIAL_PARAMS`].
+    #[serde(default)]
+    pub params: Vec<UniformDesc>,
+}
+
+/// Material drawn with compiled user shaders, see the [module documentation](self).
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "9d1c6a2f-5e47-4c3b-8b0a-7f2e1d6c5a94"]
+pub struct CustomMaterial {
+    vertex: SpirvShader,
+    fragment: SpirvShader,
+    uniforms: Vec<UniformDesc>,
+    params: Vec<UniformDesc>,
+}
+
+impl CustomMaterial {
+    /// Creates a material from its description, compiling GLSL shaders.
+    ///
+    /// # Errors
+    /// Results in an error if a shader can't be compiled or isn't valid SPIR-V, or if more than
+    /// [`MAX_MATERIAL_PARAMS`] parameters are declared.
+    pub fn new(data: &CustomMaterialData) -> Result<Self, Error> {
+        if data.params.len() > MAX_MATERIAL_PARAMS {
+            return Err(format_err!(
+                "Custom material declares {} parameters, at most {} are supported",
+                data.params.len(),
+                MAX_MATERIAL_PARAMS
+            ));
+        }
+        Ok(Self {
+            vertex: data.vertex.compile(ShaderStageFlags::VERTEX)?,
+            fragment: data.fragment.compile(ShaderStageFlags::FRAGMENT)?,
+            uniforms: data.uniforms.clone(),
+            params: data.params.clone(),
+        })
+    }
+
+    /// Compiled vertex shader.
+    #[must_use]
+    pub fn vertex(&self) -> &SpirvShader {
+        &self.vertex
+    }
+
+    /// Compiled fragment shader.
+    #[must_use]
+    pub fn fragment(&self) -> &SpirvShader {
+        &self.fragment
+    }
+
+    /// Members of the material uniform block.
+    #[must_use]
+    pub fn uniforms(&self) -> &[UniformDesc] {
+        &self.uniforms
+    }
+
+    /// Value of the uniform with given name.
+    #[must_use]
+    pub fn uniform(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|uniform| uniform.name == name)
+            .map(|uniform| uniform.value)
+    }
+
+    /// Per-entity parameters with their defaults.
+    #[must_use]
+    pub fn params(&self) -> &[UniformDesc] {
+        &self.params
+    }
+
+    /// Per-entity parameters of an instance, the defaults overridden by its [`MaterialParams`].
+    ///
+    /// Values of names the material doesn't declare are ignored, as are values of another type
+    /// than the declared one.
+    #[must_use]
+    pub fn param_data(
+        &self,
+        overrides: Option<&MaterialParams>,
+    ) -> [[f32; 4]; MAX_MATERIAL_PARAMS] {
+        let mut data = [[0.0; 4]; MAX_MATERIAL_PARAMS];
+        for (slot, param) in data.iter_mut().zip(&self.params) {
+            let value = overrides
+                .and_then(|overrides| overrides.get(&param.name))
+                .filter(|value| {
+                    std::mem::discriminant(value) == std::mem::discriminant(&param.value)
+                })
+                .unwrap_or(param.value);
+            let components = value.components();
+            slot[..components.len()].copy_from_slice(components);
+        }
+        data
+    }
+
+    /// Contents of the uniform block laid out with `std140` rules.
+    ///
+    /// The block is padded to a multiple of 16 bytes and is never empty.
+    #[must_use]
+    pub fn uniform_data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for uniform in &self.uniforms {
+            let alignment = uniform.value.alignment();
+            data.resize((data.len() + alignment - 1) / alignment * alignment, 0);
+            for component in uniform.value.components() {
+                data.extend_from_slice(&component.to_ne_bytes());
+            }
+        }
+        data.resize(((data.len() + 15) / 16 * 16).max(16), 0);
+        data
+    }
+}
+
+/// Per-entity values of the parameters declared by a [`CustomMaterial`], see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MaterialParams {
+    values: Vec<UniformDesc>,
+}
+
+impl MaterialParams {
+    /// Sets the value of the parameter with given name.
+    #[must_use]
+    pub fn with(mut self, name: &str, value: UniformValue) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Sets the value of the parameter with given name.
+    pub fn set(&mut self, name: &str, value: UniformValue) {
+        match self.values.iter_mut().find(|param| param.name == name) {
+            Some(param) => param.value = value,
+            None => {
+                self.values.push(UniformDesc {
+                    name: name.into(),
+                    value,
+                })
+            }
+        }
+    }
+
+    /// Value of the parameter with given name, if set.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<UniformValue> {
+        self.values
+            .iter()
+            .find(|param| param.name == name)
+            .map(|param| param.value)
+    }
+
+    /// Resets the parameter with given name to the default of the material.
+    pub fn remove(&mut self, name: &str) {
+        self.values.retain(|param| param.name != name);
+    }
+}
+
+impl Asset for CustomMaterial {
+    fn name() -> &'static str {
+        "renderer::CustomMaterial"
+    }
+    type Data = CustomMaterialData;
+}
+
+impl ProcessableAsset for CustomMaterial {
+    fn process(
+        data: CustomMaterialData,
+        _storage: &mut AssetStorage<CustomMaterial>,
+        _handle: &LoadHandle,
+    ) -> Result<ProcessingState<CustomMaterialData, CustomMaterial>, Error> {
+        CustomMaterial::new(&data).map(ProcessingState::Loaded)
+    }
+}
+
+register_asset_type!(CustomMaterialData => CustomMaterial; AssetProcessorSystem<CustomMaterial>);
+
+/// Format for loading [`CustomMaterial`]s from RON `.material` files.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, TypeUuid)]
+#[uuid = "c4e8b2a7-1d3f-4a6c-9e5b-0f7d2c8a6b31"]
+pub struct CustomMaterialFormat;
+
+amethyst_assets::register_importer!(".material", CustomMaterialFormat);
+impl Format<CustomMaterialData> for CustomMaterialFormat {
+    fn name(&self) -> &'static str {
+        "CUSTOM_MATERIAL"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<CustomMaterialData, Error> {
+        ron::de::from_bytes(&bytes).with_context(|_| format_err!("Failed to parse custom material"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn material(uniforms: Vec<UniformDesc>) -> CustomMaterial {
+        let shader = SpirvShader::new(vec![], ShaderStageFlags::VERTEX, "main");
+        CustomMaterial {
+            vertex: shader.clone(),
+            fragment: shader,
+            uniforms,
+            params: vec![],
+        }
+    }
+
+    fn uniform(name: &str, value: UniformValue) -> UniformDesc {
+        UniformDesc {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[test]
+    fn uniform_data_follows_std140() {
+        let material = material(vec![
+            uniform("a", UniformValue::Float(1.0)),
+            uniform("b", UniformValue::Vec3([2.0, 3.0, 4.0])),
+            uniform("c", UniformValue::Vec2([5.0, 6.0])),
+            uniform("d", UniformValue::Float(7.0)),
+        ]);
+        let floats: Vec<f32> = material
+            .uniform_data()
+            .chunks(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+
+        assert_eq!(
+            floats,
+            vec![1.0, 0.0, 0.0, 0.0, 2.0, 3.0, 4.0, 0.0, 5.0, 6.0, 7.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn empty_uniform_block_is_not_empty() {
+        assert_eq!(material(vec![]).uniform_data().len(), 16);
+    }
+
+    #[test]
+    fn param_data_overrides_defaults() {
+        let mut material = material(vec![]);
+        material.params = vec![
+            uniform("dissolve", UniformValue::Float(0.0)),
+            uniform("flash", UniformValue::Vec3([1.0, 1.0, 1.0])),
+        ];
+        let params = MaterialParams::default()
+            .with("dissolve", UniformValue::Float(0.5))
+            .with("flash", UniformValue::Float(2.0))
+            .with("unknown", UniformValue::Float(3.0));
+
+        assert_eq!(
+            material.param_data(Some(&params)),
+            [
+                [0.5, 0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0, 0.0],
+                [0.0; 4],
+                [0.0; 4]
+            ]
+        );
+        assert_eq!(material.param_data(None)[0], [0.0; 4]);
+    }
+
+    #[test]
+    fn parses_material_file() {
+        let data = CustomMaterialFormat
+            .import_simple(
+                br#"(
+                    vertex: Spirv([]),
+                    fragment: Glsl("void main() {}"),
+                    uniforms: [(name: "speed", value: Float(2.0))],
+                )"#
+                .to_vec(),
+            )
+            .unwrap();
+
+        assert_eq!(data.vertex, ShaderSource::Spirv(vec![]));
+        assert_eq!(data.uniforms[0].value, UniformValue::Float(2.0));
+    }
+}
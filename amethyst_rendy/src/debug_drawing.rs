@@ -1,27 +1,63 @@
 //! Debug Drawing library
-use amethyst_core::math::{Point2, Point3, UnitQuaternion, Vector2, Vector3};
+use amethyst_core::{
+    collision::{Collider, WorldShape},
+    ecs::{systems::ParallelRunnable, IntoQuery, System, SystemBuilder},
+    math::{Matrix4, Point2, Point3, Point4, UnitQuaternion, Vector2, Vector3},
+    transform::Transform,
+};
 use palette::Srgba;
-use rendy::mesh::{AsVertex, Color, PosColor, VertexFormat};
+use rendy::{
+    hal::format::Format,
+    mesh::{AsAttribute, AsVertex, Color, PosColor, VertexFormat},
+};
 
-use crate::{debug_drawing, pod::IntoPod};
+use crate::{camera::Camera, debug_drawing, pod::IntoPod};
 
-/// Debug lines are stored as a pair of position and color.
+/// Style of a debug line, packed as its width, dash length, gap length and whether it's drawn
+/// over the geometry.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[repr(C)]
+struct LineStyle([f32; 4]);
+
+impl AsAttribute for LineStyle {
+    const NAME: &'static str = "style";
+    const FORMAT: Format = Format::Rgba32Sfloat;
+}
+
+impl From<DebugLineStyle> for LineStyle {
+    fn from(style: DebugLineStyle) -> Self {
+        let (dash, gap) = style.dashes.unwrap_or((0.0, 0.0));
+        LineStyle([
+            style.width.unwrap_or(0.0),
+            dash,
+            gap,
+            if style.depth_test { 0.0 } else { 1.0 },
+        ])
+    }
+}
+
+/// Debug lines are stored as a pair of position and color, and their style.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct DebugLine {
     start: PosColor,
     end: PosColor,
+    style: LineStyle,
 }
 
 impl AsVertex for DebugLine {
     fn vertex() -> VertexFormat {
-        VertexFormat::new((PosColor::vertex(), PosColor::vertex()))
+        VertexFormat::new((PosColor::vertex(), PosColor::vertex(), LineStyle::vertex()))
     }
 }
 
 impl DebugLine {
-    fn new(start: PosColor, end: PosColor) -> Self {
-        Self { start, end }
+    fn new(start: PosColor, end: PosColor, style: DebugLineStyle) -> Self {
+        Self {
+            start,
+            end,
+            style: style.into(),
+        }
     }
 }
 
@@ -30,11 +66,69 @@ impl DebugLine {
 pub struct DebugLinesParams {
     /// Width of lines in screen space pixels, default is 1.0 pixel
     pub line_width: f32,
+    /// Whether the edges of the lines are smoothed, default is `true`
+    pub anti_aliasing: bool,
 }
 
 impl Default for DebugLinesParams {
     fn default() -> Self {
-        DebugLinesParams { line_width: 1.0 }
+        DebugLinesParams {
+            line_width: 1.0,
+            anti_aliasing: true,
+        }
+    }
+}
+
+/// Style of debug lines, see [`DebugLinesComponent::add_styled`] and
+/// [`DebugLines::draw_styled`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct DebugLineStyle {
+    /// Width of the lines in screen space pixels, `None` for the width of the
+    /// [`DebugLinesParams`]
+    pub width: Option<f32>,
+    /// Lengths in screen space pixels of the dashes and of the gaps between them, `None` for
+    /// solid lines
+    pub dashes: Option<(f32, f32)>,
+    /// Whether the lines are hidden by the geometry in front of them, default is `true`
+    pub depth_test: bool,
+}
+
+impl Default for DebugLineStyle {
+    fn default() -> Self {
+        DebugLineStyle {
+            width: None,
+            dashes: None,
+            depth_test: true,
+        }
+    }
+}
+
+impl DebugLineStyle {
+    /// Creates the default style, solid lines of the width of the [`DebugLinesParams`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw lines `width` pixels wide.
+    #[must_use]
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Draw dashes `dash` pixels long, separated by `gap` pixels.
+    #[must_use]
+    pub fn with_dashes(mut self, dash: f32, gap: f32) -> Self {
+        self.dashes = Some((dash, gap));
+        self
+    }
+
+    /// Draw lines over the geometry in front of them when `depth_test` is `false`.
+    #[must_use]
+    pub fn with_depth_test(mut self, depth_test: bool) -> Self {
+        self.depth_test = depth_test;
+        self
     }
 }
 
@@ -44,6 +138,8 @@ impl Default for DebugLinesParams {
 pub struct DebugLinesComponent {
     /// Lines to be rendered
     lines: Vec<DebugLine>,
+    /// Style of the lines added
+    style: DebugLineStyle,
 }
 
 impl DebugLinesComponent {
@@ -58,9 +154,17 @@ impl DebugLinesComponent {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             lines: Vec::with_capacity(capacity),
+            style: DebugLineStyle::default(),
         }
     }
 
+    /// Adds the lines added by `add` with the given style, e.g. to draw a dashed box.
+    pub fn add_styled(&mut self, style: DebugLineStyle, add: impl FnOnce(&mut Self)) {
+        let previous = std::mem::replace(&mut self.style, style);
+        add(self);
+        self.style = previous;
+    }
+
     /// Adds a line to be rendered by giving a position and a direction.
     pub fn add_direction(&mut self, position: Point3<f32>, direction: Vector3<f32>, color: Srgba) {
         self.add_line(position, position + direction, color);
@@ -88,6 +192,7 @@ impl DebugLinesComponent {
                 position: end.to_homogeneous().xyz().into(),
                 color: Color(end_color.into_pod()),
             },
+            self.style,
         );
         self.lines.push(vertex);
     }
@@ -360,6 +465,68 @@ impl DebugLinesComponent {
         }
     }
 
+    /// Adds multiple lines that form an axis aligned box to be rendered by giving its center and
+    /// half of its size along each axis.
+    pub fn add_aabb(&mut self, center: Point3<f32>, half_extents: Vector3<f32>, color: Srgba) {
+        self.add_box(center - half_extents, center + half_extents, color);
+    }
+
+    /// Adds multiple lines that form a circle to be rendered by giving a center, the normal of its
+    /// plane, a radius and an amount of points.
+    pub fn add_oriented_circle(
+        &mut self,
+        center: Point3<f32>,
+        normal: Vector3<f32>,
+        radius: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        let rotation =
+            UnitQuaternion::rotation_between(&Vector3::z(), &normal).unwrap_or_else(|| {
+                UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::PI)
+            });
+        self.add_rotated_circle(center, radius, points, rotation, color);
+    }
+
+    /// Adds three lines of the given length along the X, Y and Z axes of `transform`, in red,
+    /// green and blue.
+    pub fn add_axes(&mut self, transform: &Matrix4<f32>, length: f32) {
+        let origin = transform.transform_point(&Point3::origin());
+        let axes = [
+            (Vector3::x(), Srgba::new(1.0, 0.0, 0.0, 1.0)),
+            (Vector3::y(), Srgba::new(0.0, 1.0, 0.0, 1.0)),
+            (Vector3::z(), Srgba::new(0.0, 0.0, 1.0, 1.0)),
+        ];
+        for (axis, color) in &axes {
+            let end = transform.transform_point(&Point3::from(axis * length));
+            self.add_line(origin, end, *color);
+        }
+    }
+
+    /// Adds multiple lines that form the frustum of a `camera` with the global matrix
+    /// `camera_transform`, up to `far` units in front of it.
+    ///
+    /// Perspective cameras having no far plane, the frustum is cut at `far`.
+    pub fn add_frustum(
+        &mut self,
+        camera: &Camera,
+        camera_transform: &Matrix4<f32>,
+        far: f32,
+        color: Srgba,
+    ) {
+        let corners = frustum_corners(camera, far);
+        let corners: Vec<Point3<f32>> = corners
+            .iter()
+            .map(|corner| camera_transform.transform_point(corner))
+            .collect();
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.add_line(corners[i], corners[next], color);
+            self.add_line(corners[i + 4], corners[next + 4], color);
+            self.add_line(corners[i], corners[i + 4], color);
+        }
+    }
+
     /// Clears lines buffer.
     ///
     /// As lines are persistent, it's necessary to use this function for updating or deleting lines.
@@ -513,7 +680,142 @@ impl DebugLines {
             .add_rotated_cylinder(center, radius, height, points, rotation, color);
     }
 
+    /// Submits the lines submitted by `draw` with the given style, e.g. to draw a dashed box.
+    pub fn draw_styled(&mut self, style: DebugLineStyle, draw: impl FnOnce(&mut Self)) {
+        let previous = std::mem::replace(&mut self.inner.style, style);
+        draw(self);
+        self.inner.style = previous;
+    }
+
+    /// Submits multiple lines that form an axis aligned box to be rendered by giving its center
+    /// and half of its size along each axis.
+    pub fn draw_aabb(&mut self, center: Point3<f32>, half_extents: Vector3<f32>, color: Srgba) {
+        self.inner.add_aabb(center, half_extents, color);
+    }
+
+    /// Submits multiple lines that form a circle to be rendered by giving a center, the normal of
+    /// its plane, a radius and an amount of points.
+    pub fn draw_oriented_circle(
+        &mut self,
+        center: Point3<f32>,
+        normal: Vector3<f32>,
+        radius: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        self.inner
+            .add_oriented_circle(center, normal, radius, points, color);
+    }
+
+    /// Submits three lines of the given length along the X, Y and Z axes of `transform`, in red,
+    /// green and blue.
+    pub fn draw_axes(&mut self, transform: &Matrix4<f32>, length: f32) {
+        self.inner.add_axes(transform, length);
+    }
+
+    /// Submits multiple lines that form the frustum of a `camera` with the global matrix
+    /// `camera_transform`, up to `far` units in front of it.
+    ///
+    /// Perspective cameras having no far plane, the frustum is cut at `far`.
+    pub fn draw_frustum(
+        &mut self,
+        camera: &Camera,
+        camera_transform: &Matrix4<f32>,
+        far: f32,
+        color: Srgba,
+    ) {
+        self.inner.add_frustum(camera, camera_transform, far, color);
+    }
+
+    pub(crate) fn lines(&self) -> &[DebugLine] {
+        &self.inner.lines
+    }
+
     pub(crate) fn drain(&mut self) -> impl Iterator<Item = DebugLine> + '_ {
         self.inner.lines.drain(..)
     }
 }
+
+/// Corners of the frustum of `camera` in view space, the four on the near plane then the four
+/// `far` units in front of the camera, each in order around the frustum.
+fn frustum_corners(camera: &Camera, far: f32) -> [Point3<f32>; 8] {
+    let mut corners = [Point3::origin(); 8];
+    let ndc = [(-1.0, 1.0), (1.0, 1.0), (1.0, -1.0), (-1.0, -1.0)];
+    for (i, (x, y)) in ndc.iter().enumerate() {
+        // Depth goes from 1 on the near plane to 0 on the far one, at infinity for perspective
+        // cameras, so a second point of the ray of the corner is unprojected to reach `far`.
+        let unproject = |depth: f32| {
+            Point3::from_homogeneous((camera.inverse * Point4::new(*x, *y, depth, 1.0)).coords)
+                .unwrap_or_else(Point3::origin)
+        };
+        let near = unproject(1.0);
+        let direction = unproject(0.5) - near;
+        let t = if direction.z.abs() > f32::EPSILON {
+            (-far - near.z) / direction.z
+        } else {
+            0.0
+        };
+        corners[i] = near;
+        corners[i + 4] = near + direction * t;
+    }
+    corners
+}
+
+/// System drawing the outline of every [`Collider`] into the [`DebugLines`] resource.
+///
+/// The `DebugLines` resource must be inserted, and drawn by adding the `RenderDebugLines` plugin.
+#[derive(Debug)]
+pub struct DebugCollidersSystem {
+    color: Srgba,
+}
+
+impl DebugCollidersSystem {
+    /// Creates the system drawing colliders with given color.
+    #[must_use]
+    pub fn new(color: Srgba) -> Self {
+        Self { color }
+    }
+}
+
+impl Default for DebugCollidersSystem {
+    fn default() -> Self {
+        Self::new(Srgba::new(0.0, 1.0, 0.0, 1.0))
+    }
+}
+
+impl System for DebugCollidersSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        let color = self.color;
+        Box::new(
+            SystemBuilder::new("DebugCollidersSystem")
+                .with_query(<(&Transform, &Collider)>::query())
+                .write_resource::<DebugLines>()
+                .build(move |_commands, world, debug_lines, query| {
+                    for (transform, collider) in query.iter(world) {
+                        let z = transform.global_matrix()[(2, 3)];
+                        match collider.to_world(transform.global_matrix()) {
+                            WorldShape::Aabb {
+                                center,
+                                half_extents,
+                            } => {
+                                debug_lines.draw_rectangle(
+                                    Point2::from(center - half_extents),
+                                    Point2::from(center + half_extents),
+                                    z,
+                                    color,
+                                )
+                            }
+                            WorldShape::Circle { center, radius } => {
+                                debug_lines.draw_circle(
+                                    Point3::new(center.x, center.y, z),
+                                    radius,
+                                    32,
+                                    color,
+                                )
+                            }
+                        }
+                    }
+                }),
+        )
+    }
+}
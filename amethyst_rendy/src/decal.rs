@@ -0,0 +1,191 @@
+//! Decals projecting a texture onto the geometry of the scene, e.g. bullet holes or blood splats.
+//!
+//! A [`Decal`] is projected along the local Z axis of its entity, onto every surface inside the
+//! box of size 1 centered on the entity, scaled by its `Transform`. The [`RenderDecals`] plugin
+//! reconstructs these surfaces from the depth image of the scene, so the scene must be drawn into
+//! an offscreen target without multisampling, e.g. [`Target::Hdr`] with `RenderTonemap`:
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToWindow::from_config(display_config))
+//!     .with_plugin(RenderTonemap::default())
+//!     .with_plugin(RenderPbr3D::default())
+//!     .with_plugin(RenderDecals::default())
+//! ```
+
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Entity, IntoQuery, Resources, SystemBuilder, World},
+    Time,
+};
+use amethyst_error::{format_err, Error};
+use rendy::graph::render::RenderGroupDesc;
+
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage},
+    pass::DrawDecalDesc,
+    types::{Backend, Texture},
+    Factory,
+};
+
+/// Component projecting a texture onto the scene, see the [module documentation](self).
+///
+/// Decals with a lifetime fade out during the last `fade` seconds of it, and their entity is
+/// deleted when it ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decal {
+    /// Texture projected onto the scene
+    pub texture: Handle<Texture>,
+    /// Linear RGBA color the texture is multiplied with
+    pub color: [f32; 4],
+    /// Seconds the decal lives for, forever when `None`
+    pub lifetime: Option<f32>,
+    /// Seconds the decal fades out for at the end of its lifetime
+    pub fade: f32,
+    age: f32,
+}
+
+impl Decal {
+    /// Creates a decal projecting `texture` forever.
+    #[must_use]
+    pub fn new(texture: Handle<Texture>) -> Self {
+        Self {
+            texture,
+            color: [1.0; 4],
+            lifetime: None,
+            fade: 0.0,
+            age: 0.0,
+        }
+    }
+
+    /// Multiply the texture with given linear RGBA color.
+    #[must_use]
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Delete the decal after `lifetime` seconds, fading it out during the last `fade` ones.
+    #[must_use]
+    pub fn with_lifetime(mut self, lifetime: f32, fade: f32) -> Self {
+        self.lifetime = Some(lifetime);
+        self.fade = fade;
+        self
+    }
+
+    /// Seconds since the decal was created.
+    #[must_use]
+    pub fn age(&self) -> f32 {
+        self.age
+    }
+
+    /// Whether the lifetime of the decal has ended.
+    #[must_use]
+    pub fn expired(&self) -> bool {
+        self.lifetime.map_or(false, |lifetime| self.age >= lifetime)
+    }
+
+    /// Factor the alpha of the decal is multiplied with, from `1.0` to `0.0` while fading out.
+    #[must_use]
+    pub fn opacity(&self) -> f32 {
+        match self.lifetime {
+            None => 1.0,
+            Some(lifetime) => {
+                let remaining = (lifetime - self.age).max(0.0);
+                if remaining >= self.fade {
+                    1.0
+                } else {
+                    remaining / self.fade
+                }
+            }
+        }
+    }
+}
+
+/// A [`RenderPlugin`] drawing [`Decal`]s, see the [module documentation](self).
+#[derive(Debug)]
+pub struct RenderDecals {
+    target: Target,
+    depth_target: Target,
+}
+
+impl Default for RenderDecals {
+    fn default() -> Self {
+        Self {
+            target: Target::Main,
+            depth_target: Target::Hdr,
+        }
+    }
+}
+
+impl RenderDecals {
+    /// Set target the decals are drawn into, after its tonemapping.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set target the scene is drawn into, whose depth image and camera are used to project
+    /// the decals. It must differ from the target decals are drawn into.
+    #[must_use]
+    pub fn with_depth_target(mut self, target: Target) -> Self {
+        self.depth_target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderDecals {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        _resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        builder.add_system(|| {
+            SystemBuilder::new("DecalLifetimeSystem")
+                .read_resource::<Time>()
+                .with_query(<(Entity, &mut Decal)>::query())
+                .build(|commands, world, time, query| {
+                    let delta = time.delta_time().as_secs_f32();
+                    for (entity, decal) in query.iter_mut(world) {
+                        decal.age += delta;
+                        if decal.expired() {
+                            commands.remove(*entity);
+                        }
+                    }
+                })
+        });
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        if self.target == self.depth_target {
+            return Err(format_err!(
+                "Decals can't be drawn into target {:?} they read the depth of.",
+                self.target
+            ));
+        }
+
+        let depth_target = self.depth_target;
+        plan.extend_target(self.target, move |ctx| {
+            let depth = ctx.get_image(TargetImage::Depth(depth_target))?;
+            ctx.add(
+                RenderOrder::DisplayPostEffects,
+                DrawDecalDesc::new()
+                    .with_target(depth_target)
+                    .with_samples(ctx.samples())
+                    .builder()
+                    .with_image(depth),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! Environment maps used for image based lighting of physically based materials.
+//!
+//! An [`EnvironmentMap`] pairs a diffuse irradiance cubemap with a prefiltered specular cubemap.
+//! `RenderPbr3D` uses the map attached to the rendered camera entity, or the `EnvironmentMap`
+//! resource when the camera has none, to light materials with their surroundings. Nearby
+//! [`ReflectionProbe`](crate::reflection_probe::ReflectionProbe)s replace its specular map
+//! within their radius.
+//!
+//! Both cubemaps can be created from a single Radiance HDR cubemap:
+//!
+//! ```ignore
+//! let bytes = std::fs::read(app_root.join("assets/texture/environment.hdr"))?;
+//! let environment = EnvironmentMap::from_hdr_cubemap(&resources, &bytes)?.with_intensity(0.8);
+//! resources.insert(environment);
+//! ```
+
+use amethyst_assets::{DefaultLoader, Handle, Loader, ProcessingQueue};
+use amethyst_core::ecs::Resources;
+use amethyst_error::Error;
+
+use crate::{
+    formats::cubemap::Cubemap,
+    types::{Texture, TextureData},
+};
+
+/// Image based lighting environment, usable both as a resource and as a camera component.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentMap {
+    /// Cube texture holding the diffuse irradiance of the environment.
+    pub irradiance: Handle<Texture>,
+    /// Cube texture with a mip chain, sampled at increasing mip levels for rougher surfaces.
+    pub specular: Handle<Texture>,
+    /// Multiplier applied to the light coming from the environment.
+    pub intensity: f32,
+}
+
+impl EnvironmentMap {
+    /// Creates an environment map from already loaded cube textures.
+    #[must_use]
+    pub fn new(irradiance: Handle<Texture>, specular: Handle<Texture>) -> Self {
+        Self {
+            irradiance,
+            specular,
+            intensity: 1.0,
+        }
+    }
+
+    /// Sets the multiplier applied to the light coming from the environment.
+    #[must_use]
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Loads an environment map from a cubemap, computing its irradiance on the CPU.
+    #[must_use]
+    pub fn from_cubemap(resources: &Resources, cubemap: Cubemap) -> Self {
+        let loader = resources.get::<DefaultLoader>().unwrap();
+        let queue = resources.get::<ProcessingQueue<TextureData>>().unwrap();
+
+        let irradiance = cubemap.irradiance().into_texture_data(false);
+        let irradiance = loader.load_from_data(irradiance, (), &queue);
+        let specular = loader.load_from_data(cubemap.into_texture_data(true), (), &queue);
+
+        Self::new(irradiance, specular)
+    }
+
+    /// Loads an environment map from a Radiance HDR cubemap with the six faces stacked
+    /// vertically, see [`HdrCubemapFormat`](crate::formats::cubemap::HdrCubemapFormat).
+    ///
+    /// # Errors
+    /// Results in an error if the cubemap can't be decoded.
+    pub fn from_hdr_cubemap(resources: &Resources, bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_cubemap(resources, Cubemap::from_hdr(bytes)?))
+    }
+}
@@ -0,0 +1,419 @@
+//! HDR cubemap format and prefiltering used for image based lighting and skyboxes.
+use std::io::{BufReader, Cursor};
+
+use amethyst_assets::Format;
+use amethyst_error::{format_err, Error};
+use image::codecs::hdr::HdrDecoder;
+use rendy::{
+    hal::{
+        self,
+        format::Format as HalFormat,
+        image::{Filter, Kind, ViewKind},
+    },
+    texture::{MipLevels, TextureBuilder},
+};
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+use crate::types::TextureData;
+
+/// Face size of irradiance maps computed by [`Cubemap::irradiance`].
+pub const IRRADIANCE_SIZE: usize = 16;
+
+/// Linear HDR cubemap with six square faces, in `+X, -X, +Y, -Y, +Z, -Z` order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cubemap {
+    size: usize,
+    texels: Vec<[f32; 3]>,
+}
+
+impl Cubemap {
+    /// Creates a cubemap from texels of all faces, each face stored row by row.
+    ///
+    /// # Errors
+    /// Results in an error if the number of texels doesn't match the size.
+    pub fn new(size: usize, texels: Vec<[f32; 3]>) -> Result<Self, Error> {
+        if size == 0 || texels.len() != size * size * 6 {
+            return Err(format_err!(
+                "Cubemap of size {} expects {} texels, got {}",
+                size,
+                size * size * 6,
+                texels.len()
+            ));
+        }
+        Ok(Self { size, texels })
+    }
+
+    /// Decodes a Radiance HDR (`.hdr`) image, either with the six faces stacked vertically or
+    /// as an equirectangular panorama twice as wide as high.
+    ///
+    /// # Errors
+    /// Results in an error if the image can't be decoded or has neither layout.
+    pub fn from_hdr(bytes: &[u8]) -> Result<Self, Error> {
+        let decoder = HdrDecoder::new(BufReader::new(Cursor::new(bytes)))
+            .map_err(|e| format_err!("Failed to decode HDR cubemap: {}", e))?;
+        let metadata = decoder.metadata();
+        let (width, height) = (metadata.width as usize, metadata.height as usize);
+        if height != width * 6 && width != height * 2 {
+            return Err(format_err!(
+                "HDR cubemap must have its six faces stacked vertically or be an \
+                 equirectangular panorama, got {}x{} image",
+                width,
+                height
+            ));
+        }
+        let texels: Vec<_> = decoder
+            .read_image_hdr()
+            .map_err(|e| format_err!("Failed to decode HDR cubemap: {}", e))?
+            .into_iter()
+            .map(|pixel| pixel.0)
+            .collect();
+        if height == width * 6 {
+            Self::new(width, texels)
+        } else {
+            Self::from_equirectangular(width, height, &texels)
+        }
+    }
+
+    /// Resamples an equirectangular panorama, stored row by row from the zenith down, into a
+    /// cubemap with faces a quarter of the panorama wide.
+    ///
+    /// The center of the panorama faces `-Z`, and `+X` is a quarter of its width to the right.
+    ///
+    /// # Errors
+    /// Results in an error if the panorama isn't twice as wide as high or the number of texels
+    /// doesn't match its size.
+    pub fn from_equirectangular(
+        width: usize,
+        height: usize,
+        texels: &[[f32; 3]],
+    ) -> Result<Self, Error> {
+        if height == 0 || width != height * 2 || texels.len() != width * height {
+            return Err(format_err!(
+                "Equirectangular panorama must be twice as wide as high, got {}x{} image with {} \
+                 texels",
+                width,
+                height,
+                texels.len()
+            ));
+        }
+
+        // Bilinear filtering, wrapping around horizontally.
+        let texel = |x: isize, y: isize| {
+            let x = x.rem_euclid(width as isize) as usize;
+            let y = y.max(0).min(height as isize - 1) as usize;
+            texels[y * width + x]
+        };
+        let sample = |u: f32, v: f32| {
+            let x = u * width as f32 - 0.5;
+            let y = v * height as f32 - 0.5;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as isize, y0 as isize);
+            let mut color = [0.0; 3];
+            for (dx, dy, weight) in &[
+                (0, 0, (1.0 - fx) * (1.0 - fy)),
+                (1, 0, fx * (1.0 - fy)),
+                (0, 1, (1.0 - fx) * fy),
+                (1, 1, fx * fy),
+            ] {
+                for (c, t) in color.iter_mut().zip(&texel(x0 + dx, y0 + dy)) {
+                    *c += t * weight;
+                }
+            }
+            color
+        };
+
+        let size = (width / 4).max(1);
+        let mut faces = Vec::with_capacity(size * size * 6);
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let ([dx, dy, dz], _) = texel_direction(face, x, y, size);
+                    let u = 0.5 + dx.atan2(-dz) / (2.0 * std::f32::consts::PI);
+                    let v = dy.max(-1.0).min(1.0).acos() / std::f32::consts::PI;
+                    faces.push(sample(u, v));
+                }
+            }
+        }
+        Self::new(size, faces)
+    }
+
+    /// Decodes six square sRGB images of the same size, e.g. PNG or JPEG files, as the faces
+    /// of a cubemap in `+X, -X, +Y, -Y, +Z, -Z` order.
+    ///
+    /// # Errors
+    /// Results in an error if an image can't be decoded or the faces aren't square images of
+    /// the same size.
+    pub fn from_faces(faces: [&[u8]; 6]) -> Result<Self, Error> {
+        let mut size = None;
+        let mut texels = Vec::new();
+        for (i, bytes) in faces.iter().enumerate() {
+            let image = image::load_from_memory(bytes)
+                .map_err(|e| format_err!("Failed to decode cubemap face {}: {}", i, e))?
+                .into_rgb8();
+            let (width, height) = image.dimensions();
+            if width != height || size.map_or(false, |size| size != width) {
+                return Err(format_err!(
+                    "Cubemap faces must be square images of the same size, face {} is {}x{}",
+                    i,
+                    width,
+                    height
+                ));
+            }
+            size = Some(width);
+            texels.extend(image.pixels().map(|pixel| {
+                let [r, g, b] = pixel.0;
+                [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b)]
+            }));
+        }
+        Self::new(size.unwrap_or(0) as usize, texels)
+    }
+
+    /// Size of a face in texels.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Color of a texel of given face.
+    #[must_use]
+    pub fn texel(&self, face: usize, x: usize, y: usize) -> [f32; 3] {
+        self.texels[(face * self.size + y) * self.size + x]
+    }
+
+    /// Halves the face size by averaging texels until it's not above `size`.
+    #[must_use]
+    pub fn downsample(&self, size: usize) -> Self {
+        let mut cubemap = self.clone();
+        while cubemap.size > size.max(1) && cubemap.size % 2 == 0 {
+            let half = cubemap.size / 2;
+            let mut texels = Vec::with_capacity(half * half * 6);
+            for face in 0..6 {
+                for y in 0..half {
+                    for x in 0..half {
+                        let mut sum = [0.0; 3];
+                        for (dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                            let texel = cubemap.texel(face, x * 2 + dx, y * 2 + dy);
+                            for (s, t) in sum.iter_mut().zip(&texel) {
+                                *s += t * 0.25;
+                            }
+                        }
+                        texels.push(sum);
+                    }
+                }
+            }
+            cubemap = Self { size: half, texels };
+        }
+        cubemap
+    }
+
+    /// Convolves the cubemap with a cosine lobe, giving the diffuse irradiance for every
+    /// normal direction divided by pi.
+    ///
+    /// A uniform environment results in an irradiance map of the same color.
+    #[must_use]
+    pub fn irradiance(&self) -> Self {
+        let source = self.downsample(IRRADIANCE_SIZE);
+        let samples: Vec<_> = (0..6)
+            .flat_map(|face| {
+                let source = &source;
+                (0..source.size).flat_map(move |y| {
+                    (0..source.size).map(move |x| {
+                        let (direction, solid_angle) = texel_direction(face, x, y, source.size);
+                        (direction, solid_angle, source.texel(face, x, y))
+                    })
+                })
+            })
+            .collect();
+
+        let mut texels = Vec::with_capacity(IRRADIANCE_SIZE * IRRADIANCE_SIZE * 6);
+        for face in 0..6 {
+            for y in 0..IRRADIANCE_SIZE {
+                for x in 0..IRRADIANCE_SIZE {
+                    let (normal, _) = texel_direction(face, x, y, IRRADIANCE_SIZE);
+                    let mut sum = [0.0; 3];
+                    for (direction, solid_angle, color) in &samples {
+                        let weight = dot(normal, *direction).max(0.0) * solid_angle;
+                        for (s, c) in sum.iter_mut().zip(color) {
+                            *s += c * weight;
+                        }
+                    }
+                    texels.push([
+                        sum[0] / std::f32::consts::PI,
+                        sum[1] / std::f32::consts::PI,
+                        sum[2] / std::f32::consts::PI,
+                    ]);
+                }
+            }
+        }
+
+        Self {
+            size: IRRADIANCE_SIZE,
+            texels,
+        }
+    }
+
+    /// Converts the cubemap into `Rgba16Sfloat` cube texture data.
+    ///
+    /// With `mips`, a full mip chain is generated, used by the PBR pass as a prefiltered
+    /// specular map where rougher surfaces sample lower mip levels.
+    #[must_use]
+    pub fn into_texture_data(self, mips: bool) -> TextureData {
+        let size = self.size as u32;
+        let data: Vec<u8> = self
+            .texels
+            .iter()
+            .flat_map(|[r, g, b]| vec![*r, *g, *b, 1.0])
+            .flat_map(|c| half::f16::from_f32(c).to_bits().to_le_bytes().to_vec())
+            .collect();
+
+        let builder = TextureBuilder::new()
+            .with_kind(Kind::D2(size, size, 6, 1))
+            .with_view_kind(ViewKind::Cube)
+            .with_data_width(size)
+            .with_data_height(size)
+            .with_sampler_info(hal::image::SamplerDesc::new(
+                Filter::Linear,
+                hal::image::WrapMode::Clamp,
+            ))
+            .with_raw_data(data, HalFormat::Rgba16Sfloat);
+
+        if mips {
+            builder.with_mip_levels(MipLevels::GenerateAuto).into()
+        } else {
+            builder.into()
+        }
+    }
+}
+
+/// Direction through the center of a cubemap texel and the solid angle it covers.
+fn texel_direction(face: usize, x: usize, y: usize, size: usize) -> ([f32; 3], f32) {
+    let u = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+    let v = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+    let direction = match face {
+        0 => [1.0, -v, -u],
+        1 => [-1.0, -v, u],
+        2 => [u, 1.0, v],
+        3 => [u, -1.0, -v],
+        4 => [u, -v, 1.0],
+        _ => [-u, -v, -1.0],
+    };
+    let length_squared = 1.0 + u * u + v * v;
+    let length = length_squared.sqrt();
+    let texel_area = (2.0 / size as f32).powi(2);
+    (
+        [
+            direction[0] / length,
+            direction[1] / length,
+            direction[2] / length,
+        ],
+        texel_area / (length_squared * length),
+    )
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = f32::from(value) / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Radiance HDR cubemap format, with the six faces stacked vertically in
+/// `+X, -X, +Y, -Y, +Z, -Z` order, or an equirectangular panorama.
+///
+/// Imports a cube texture with a generated mip chain, suitable as the specular map of an
+/// [`EnvironmentMap`](crate::environment_map::EnvironmentMap) or as a
+/// [`Skybox`](crate::resources::Skybox). With `irradiance` set, the
+/// cubemap is convolved into a diffuse irradiance map instead.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TypeUuid)]
+#[uuid = "5bb5b8b8-4a6b-4a8f-9a39-6b1f4b0c2d7e"]
+pub struct HdrCubemapFormat {
+    /// Import the diffuse irradiance of the cubemap.
+    pub irradiance: bool,
+}
+
+amethyst_assets::register_importer!(".hdr", HdrCubemapFormat);
+impl Format<TextureData> for HdrCubemapFormat {
+    fn name(&self) -> &'static str {
+        "HDR_CUBEMAP"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        let cubemap = Cubemap::from_hdr(&bytes)?;
+        if self.irradiance {
+            Ok(cubemap.irradiance().into_texture_data(false))
+        } else {
+            Ok(cubemap.into_texture_data(true))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solid_angles_cover_sphere() {
+        let size = 8;
+        let total: f32 = (0..6)
+            .flat_map(|face| (0..size).flat_map(move |y| (0..size).map(move |x| (face, x, y))))
+            .map(|(face, x, y)| texel_direction(face, x, y, size).1)
+            .sum();
+        assert!((total - 4.0 * std::f32::consts::PI).abs() < 0.1);
+    }
+
+    #[test]
+    fn uniform_environment_irradiance() {
+        let cubemap = Cubemap::new(8, vec![[0.5, 1.0, 2.0]; 8 * 8 * 6]).unwrap();
+        let irradiance = cubemap.irradiance();
+
+        assert_eq!(irradiance.size(), IRRADIANCE_SIZE);
+        for face in 0..6 {
+            let [r, g, b] = irradiance.texel(face, 3, 7);
+            assert!((r - 0.5).abs() < 0.01, "{}", r);
+            assert!((g - 1.0).abs() < 0.01, "{}", g);
+            assert!((b - 2.0).abs() < 0.02, "{}", b);
+        }
+    }
+
+    #[test]
+    fn equirectangular_panorama_resampling() {
+        // Upper half red, lower half blue.
+        let (width, height) = (16, 8);
+        let texels: Vec<_> = (0..width * height)
+            .map(|i| {
+                if i / width < height / 2 {
+                    [1.0, 0.0, 0.0]
+                } else {
+                    [0.0, 0.0, 1.0]
+                }
+            })
+            .collect();
+        let cubemap = Cubemap::from_equirectangular(width, height, &texels).unwrap();
+
+        let close = |a: [f32; 3], b: [f32; 3]| a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-4);
+        assert_eq!(cubemap.size(), 4);
+        assert!(close(cubemap.texel(2, 1, 2), [1.0, 0.0, 0.0]));
+        assert!(close(cubemap.texel(3, 2, 1), [0.0, 0.0, 1.0]));
+        assert!(close(cubemap.texel(5, 1, 0), [1.0, 0.0, 0.0]));
+        assert!(close(cubemap.texel(5, 1, 3), [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn rejects_non_panorama_images() {
+        assert!(Cubemap::from_equirectangular(8, 8, &[[0.0; 3]; 64]).is_err());
+        assert!(Cubemap::from_equirectangular(16, 8, &[[0.0; 3]; 64]).is_err());
+    }
+
+    #[test]
+    fn rejects_mismatched_texels() {
+        assert!(Cubemap::new(2, vec![[0.0; 3]; 4]).is_err());
+    }
+}
@@ -1,3 +1,4 @@
 //! Pre-defined graphical formats and data provided by `amethyst_rendy`
+pub mod cubemap;
 pub mod mesh;
 pub mod texture;
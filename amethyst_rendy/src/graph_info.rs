@@ -0,0 +1,531 @@
+//! Introspection of the render graph built by [`RenderingBundle`], to debug why frames are slow.
+//!
+//! The bundle keeps a [`RenderGraphInfo`] resource describing the graph it built last: the
+//! render pass of every evaluated target with its render groups in drawing order, the nodes
+//! added directly to the graph through `graph()` of the planning contexts, e.g. presenting and
+//! resolve nodes, the images they read and write, and the dependencies between them. It can be
+//! dumped at runtime as a Graphviz DOT graph or as JSON:
+//!
+//! ```ignore
+//! let info = resources.get::<RenderGraphInfo>().unwrap();
+//! std::fs::write("render_graph.dot", info.to_dot())?;
+//! std::fs::write("render_graph.json", info.to_json()?)?;
+//! ```
+//!
+//! Nodes are described in the order the plugins added them, which may differ from the order
+//! rendy schedules them in.
+//!
+//! With [`RenderingBundle::with_render_timings`], the bundle also keeps a [`RenderTimings`]
+//! resource with the CPU time every render group took in the last frame to be prepared and to
+//! record its draw commands. Only render groups added through the plan are timed, nodes added
+//! directly through `graph()` are not. [`RenderTimings::frame`] includes waiting for the GPU to
+//! finish the previous frames in flight, so a frame time much larger than the sum of the group
+//! timings hints at the GPU being the bottleneck.
+//!
+//! The time the GPU spends executing the commands of a group is not measured. Timestamp queries
+//! must be reset outside of render passes before being written again, while render groups only
+//! record commands inside the render pass of their target, so timing them on the GPU requires
+//! support from the render pass nodes of rendy.
+//!
+//! [`RenderingBundle`]: crate::RenderingBundle
+//! [`RenderingBundle::with_render_timings`]: crate::RenderingBundle::with_render_timings
+
+use std::{fmt::Write, time::Duration};
+
+use amethyst_core::Stopwatch;
+use amethyst_error::{format_err, Error};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupBuilder},
+        BufferAccess, BufferId, GraphContext, ImageAccess, ImageId, NodeBuffer, NodeId, NodeImage,
+    },
+    hal,
+};
+use serde::Serialize;
+
+use crate::{bundle::ImageOptions, system::GraphAuxData, types::Backend};
+
+/// Description of the render graph built last, see the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RenderGraphInfo {
+    /// Nodes of the graph, in the order they were added
+    pub nodes: Vec<RenderNodeInfo>,
+    /// Images created for the outputs of the render targets and by the nodes of the graph
+    pub images: Vec<RenderImageInfo>,
+    /// Number of times the graph was built, incremented every time it is rebuilt
+    pub builds: u64,
+}
+
+/// Kind of a node of the render graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum RenderNodeKind {
+    /// Render pass drawing a render target
+    RenderPass,
+    /// Compute shader dispatch
+    Compute,
+    /// Node added directly to the rendy graph, e.g. resolving or presenting an image
+    Graph,
+}
+
+/// Node of the render graph.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RenderNodeInfo {
+    /// Name of the node, the render target for render passes and the target or `Graph`
+    /// followed by the type of the node builder for nodes added directly to the graph
+    pub name: String,
+    /// Kind of the node
+    pub kind: RenderNodeKind,
+    /// Render groups drawn by a render pass, in drawing order
+    pub groups: Vec<RenderGroupInfo>,
+    /// Indices of the images read by the node
+    pub reads: Vec<usize>,
+    /// Indices of the images written by the node
+    pub writes: Vec<usize>,
+    /// Indices of the nodes that must run before this node
+    pub dependencies: Vec<usize>,
+}
+
+/// Render group drawn by a render pass.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RenderGroupInfo {
+    /// Type name of the render group description
+    pub name: String,
+    /// Order of the render group within the pass, see
+    /// [`RenderOrder`](crate::bundle::RenderOrder)
+    pub order: i32,
+}
+
+/// Image of the render graph.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RenderImageInfo {
+    /// Name of the image, the render target output it is created for
+    pub name: String,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Number of layers
+    pub layers: u16,
+    /// Number of samples per pixel
+    pub samples: u8,
+    /// Pixel format, `None` for window surfaces whose format is picked by the swapchain
+    pub format: Option<String>,
+}
+
+impl RenderNodeInfo {
+    pub(crate) fn render_pass(name: String) -> Self {
+        Self::new(name, RenderNodeKind::RenderPass)
+    }
+
+    pub(crate) fn compute(name: String) -> Self {
+        Self::new(name, RenderNodeKind::Compute)
+    }
+
+    pub(crate) fn graph(name: String) -> Self {
+        Self::new(name, RenderNodeKind::Graph)
+    }
+
+    fn new(name: String, kind: RenderNodeKind) -> Self {
+        Self {
+            name,
+            kind,
+            groups: vec![],
+            reads: vec![],
+            writes: vec![],
+            dependencies: vec![],
+        }
+    }
+}
+
+impl RenderImageInfo {
+    pub(crate) fn new(name: String, options: &ImageOptions) -> Self {
+        let extent = options.kind.extent();
+        Self {
+            name,
+            width: extent.width,
+            height: extent.height,
+            layers: options.kind.num_layers(),
+            samples: options.kind.num_samples(),
+            format: Some(format!("{:?}", options.format)),
+        }
+    }
+}
+
+impl RenderGraphInfo {
+    /// Total number of render groups drawn by the render passes.
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.nodes.iter().map(|node| node.groups.len()).sum()
+    }
+
+    /// Graph in the Graphviz DOT format, with nodes as boxes, images as ellipses and explicit
+    /// dependencies as dashed edges.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph render_graph {\n    rankdir=LR;\n");
+        for (i, image) in self.images.iter().enumerate() {
+            let mut label = format!("{}\n{}x{}", image.name, image.width, image.height);
+            if image.layers > 1 {
+                write!(label, "x{}", image.layers).unwrap();
+            }
+            if let Some(format) = &image.format {
+                write!(label, " {}", format).unwrap();
+            }
+            if image.samples > 1 {
+                write!(label, " {}x MSAA", image.samples).unwrap();
+            }
+            writeln!(
+                dot,
+                "    image{} [shape=ellipse, label=\"{}\"];",
+                i,
+                escape(&label)
+            )
+            .unwrap();
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut label = node.name.clone();
+            if node.kind == RenderNodeKind::Compute {
+                label.push_str(" (compute)");
+            }
+            for group in &node.groups {
+                write!(label, "\n{} {}", group.order, group.name).unwrap();
+            }
+            writeln!(
+                dot,
+                "    node{} [shape=box, label=\"{}\"];",
+                i,
+                escape(&label)
+            )
+            .unwrap();
+            for image in &node.reads {
+                writeln!(dot, "    image{} -> node{};", image, i).unwrap();
+            }
+            for image in &node.writes {
+                writeln!(dot, "    node{} -> image{};", i, image).unwrap();
+            }
+            for dependency in &node.dependencies {
+                writeln!(dot, "    node{} -> node{} [style=dashed];", dependency, i).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Graph as pretty printed JSON.
+    ///
+    /// # Errors
+    /// Results in an error if the serialization fails.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format_err!("Failed to serialize render graph: {}", e))
+    }
+}
+
+/// Whether a node accessing an image with given access writes it.
+pub(crate) fn is_write(access: hal::image::Access) -> bool {
+    use hal::image::Access;
+    access.intersects(
+        Access::SHADER_WRITE
+            | Access::COLOR_ATTACHMENT_WRITE
+            | Access::DEPTH_STENCIL_ATTACHMENT_WRITE
+            | Access::TRANSFER_WRITE
+            | Access::HOST_WRITE
+            | Access::MEMORY_WRITE,
+    )
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Name of a render group from the type name of its builder, without module paths, e.g.
+/// `DrawFlat2DDesc` for a `DescBuilder<Vulkan, InternalGraphAuxData, DrawFlat2DDesc>`.
+pub(crate) fn group_name(type_name: &str) -> String {
+    let mut name = String::with_capacity(type_name.len());
+    let mut segment = String::new();
+    for c in type_name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            name.push_str(segment.rsplit("::").next().unwrap_or(""));
+            segment.clear();
+            name.push(c);
+        }
+    }
+    name.push_str(segment.rsplit("::").next().unwrap_or(""));
+
+    // Render groups are mostly added with `RenderGroupDesc::builder`, name them by the desc.
+    match name
+        .strip_prefix("DescBuilder<")
+        .and_then(|args| args.strip_suffix('>'))
+    {
+        Some(args) => {
+            let mut depth = 0;
+            let mut last = 0;
+            for (i, c) in args.char_indices() {
+                match c {
+                    '<' | '(' | '[' => depth += 1,
+                    '>' | ')' | ']' => depth -= 1,
+                    ',' if depth == 0 => last = i + 1,
+                    _ => {}
+                }
+            }
+            args[last..].trim().to_string()
+        }
+        None => name,
+    }
+}
+
+/// CPU time render groups took in the last frame to be prepared and recorded, see the
+/// [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RenderTimings {
+    /// Timings of the render groups, in the order of [`RenderGraphInfo::nodes`] and their groups
+    pub groups: Vec<RenderGroupTiming>,
+    /// Time spent running the whole render graph, including waiting for the GPU
+    pub frame: Duration,
+}
+
+/// CPU time a render group took in the last frame.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RenderGroupTiming {
+    /// Name of the node drawing the group
+    pub node: String,
+    /// Name of the render group
+    pub group: String,
+    /// Time spent preparing the group for drawing, e.g. uploading its buffers
+    pub prepare: Duration,
+    /// Time spent recording the draw commands of the group the last time they were recorded,
+    /// as groups reuse their commands when nothing changed
+    pub draw: Duration,
+}
+
+impl RenderTimings {
+    /// Empty timings for the render groups of a newly built graph.
+    pub(crate) fn reset(&mut self, info: &RenderGraphInfo) {
+        self.groups = info
+            .nodes
+            .iter()
+            .flat_map(|node| {
+                node.groups.iter().map(move |group| {
+                    RenderGroupTiming {
+                        node: node.name.clone(),
+                        group: group.name.clone(),
+                        prepare: Duration::default(),
+                        draw: Duration::default(),
+                    }
+                })
+            })
+            .collect();
+        self.frame = Duration::default();
+    }
+
+    /// Render groups by the time they took, slowest first.
+    #[must_use]
+    pub fn slowest(&self) -> Vec<&RenderGroupTiming> {
+        let mut groups = self.groups.iter().collect::<Vec<_>>();
+        groups.sort_by(|a, b| (b.prepare + b.draw).cmp(&(a.prepare + a.draw)));
+        groups
+    }
+}
+
+/// Render group builder measuring the time taken by the group it builds.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub(crate) struct TimedGroupBuilder<B: Backend> {
+    inner: Box<dyn RenderGroupBuilder<B, GraphAuxData>>,
+    slot: usize,
+}
+
+impl<B: Backend> TimedGroupBuilder<B> {
+    /// Measure a render group, recording its timings at `slot` of [`RenderTimings::groups`].
+    pub(crate) fn new(inner: Box<dyn RenderGroupBuilder<B, GraphAuxData>>, slot: usize) -> Self {
+        Self { inner, slot }
+    }
+}
+
+impl<B: Backend> RenderGroupBuilder<B, GraphAuxData> for TimedGroupBuilder<B> {
+    fn colors(&self) -> usize {
+        self.inner.colors()
+    }
+
+    fn depth(&self) -> bool {
+        self.inner.depth()
+    }
+
+    fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
+        self.inner.buffers()
+    }
+
+    fn images(&self) -> Vec<(ImageId, ImageAccess)> {
+        self.inner.images()
+    }
+
+    fn dependencies(&self) -> Vec<NodeId> {
+        self.inner.dependencies()
+    }
+
+    fn build(
+        self: Box<Self>,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, hal::pso::CreationError> {
+        let inner = self.inner.build(
+            ctx,
+            factory,
+            queue,
+            aux,
+            framebuffer_width,
+            framebuffer_height,
+            subpass,
+            buffers,
+            images,
+        )?;
+        Ok(Box::new(TimedGroup {
+            inner,
+            slot: self.slot,
+        }))
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct TimedGroup<B: Backend> {
+    inner: Box<dyn RenderGroup<B, GraphAuxData>>,
+    slot: usize,
+}
+
+impl<B: Backend> TimedGroup<B> {
+    fn record(&self, aux: &GraphAuxData, record: impl FnOnce(&mut RenderGroupTiming)) {
+        if let Some(mut timings) = aux.resources.get_mut::<RenderTimings>() {
+            if let Some(timing) = timings.groups.get_mut(self.slot) {
+                record(timing);
+            }
+        }
+    }
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for TimedGroup<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        queue: QueueId,
+        index: usize,
+        subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start();
+        let result = self.inner.prepare(factory, queue, index, subpass, aux);
+        stopwatch.stop();
+        self.record(aux, |timing| timing.prepare = stopwatch.elapsed());
+        result
+    }
+
+    fn draw_inline(
+        &mut self,
+        encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start();
+        self.inner.draw_inline(encoder, index, subpass, aux);
+        stopwatch.stop();
+        self.record(aux, |timing| timing.draw = stopwatch.elapsed());
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &GraphAuxData) {
+        self.inner.dispose(factory, aux);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_names_are_shortened() {
+        assert_eq!(
+            group_name("rendy_graph::node::render::group::DescBuilder<rendy_vulkan::Backend, amethyst_rendy::system::InternalGraphAuxData, amethyst_rendy::pass::flat2d::DrawFlat2DDesc>"),
+            "DrawFlat2DDesc"
+        );
+        assert_eq!(
+            group_name("rendy_graph::node::render::group::DescBuilder<rendy_vulkan::Backend, amethyst_rendy::system::InternalGraphAuxData, amethyst_rendy::pass::base_3d::DrawBase3DDesc<rendy_vulkan::Backend, amethyst_rendy::pass::pbr::PbrPassDef>>"),
+            "DrawBase3DDesc<Backend, PbrPassDef>"
+        );
+        assert_eq!(group_name("my_game::render::MyGroup"), "MyGroup");
+    }
+
+    #[test]
+    fn write_accesses_are_detected() {
+        use hal::image::Access;
+        assert!(is_write(Access::TRANSFER_WRITE));
+        assert!(is_write(Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE));
+        assert!(!is_write(Access::TRANSFER_READ | Access::SHADER_READ));
+    }
+
+    #[test]
+    fn dot_contains_nodes_images_and_edges() {
+        let info = RenderGraphInfo {
+            nodes: vec![
+                RenderNodeInfo {
+                    name: "Hdr".into(),
+                    kind: RenderNodeKind::RenderPass,
+                    groups: vec![RenderGroupInfo {
+                        name: "DrawSkyboxDesc".into(),
+                        order: 210,
+                    }],
+                    reads: vec![],
+                    writes: vec![0],
+                    dependencies: vec![],
+                },
+                RenderNodeInfo {
+                    name: "Main".into(),
+                    kind: RenderNodeKind::RenderPass,
+                    groups: vec![RenderGroupInfo {
+                        name: "DrawTonemapDesc".into(),
+                        order: 400,
+                    }],
+                    reads: vec![0],
+                    writes: vec![],
+                    dependencies: vec![0],
+                },
+            ],
+            images: vec![RenderImageInfo {
+                name: "Hdr color 0".into(),
+                width: 1280,
+                height: 720,
+                layers: 1,
+                samples: 1,
+                format: Some("Rgba16Sfloat".into()),
+            }],
+            builds: 1,
+        };
+
+        let dot = info.to_dot();
+        assert!(dot.starts_with("digraph render_graph {"));
+        assert!(
+            dot.contains("image0 [shape=ellipse, label=\"Hdr color 0\\n1280x720 Rgba16Sfloat\"];")
+        );
+        assert!(dot.contains("node1 [shape=box, label=\"Main\\n400 DrawTonemapDesc\"];"));
+        assert!(dot.contains("node0 -> image0;"));
+        assert!(dot.contains("image0 -> node1;"));
+        assert!(dot.contains("node0 -> node1 [style=dashed];"));
+        assert_eq!(info.group_count(), 2);
+    }
+}
@@ -11,17 +11,32 @@
 //!
 //! * [`DrawFlat2DDesc`](crate::pass::flat2d::DrawFlat2DDesc)
 //! * [`DrawFlat2DTransparentDesc`](crate::pass::flat2d::DrawFlat2DTransparentDesc)
+//! * [`DrawFlat2DLitDesc`](crate::pass::flat2d_lit::DrawFlat2DLitDesc)
+//! * [`DrawFlat2DLitTransparentDesc`](crate::pass::flat2d_lit::DrawFlat2DLitTransparentDesc)
+//! * [`DrawBillboardsDesc`](crate::pass::billboard::DrawBillboardsDesc)
 //! * [`DrawPbrDesc`](crate::pass::pbr::DrawPbrDesc)
+//! * [`DrawPbrLightmapDesc`](crate::pass::pbr::DrawPbrLightmapDesc)
 //! * [`DrawFlatDesc`](crate::pass::flat::DrawFlatDesc)
 //! * [`DrawShadedDesc`](crate::pass::shaded::DrawShadedDesc)
 //! * [`DrawSkyboxDesc`](crate::pass::skybox::DrawSkyboxDesc)
 //! * [`DrawDebugLinesDesc`](crate::pass::debug_lines::DrawDebugLinesDesc)
+//! * [`DrawTonemapDesc`](crate::pass::tonemap::DrawTonemapDesc)
+//! * [`DrawColorGradingDesc`](crate::pass::color_grading::DrawColorGradingDesc)
+//! * [`DrawColorBlindFilterDesc`](crate::pass::color_blind::DrawColorBlindFilterDesc)
+//! * [`DrawBlitDesc`](crate::pass::blit::DrawBlitDesc)
+//! * [`DrawCustomMaterialDesc`](crate::pass::custom_material::DrawCustomMaterialDesc)
+//! * [`DrawSimpleDesc`](crate::pass::simple::DrawSimpleDesc)
+//! * [`DrawOutlineDesc`](crate::pass::outline::DrawOutlineDesc)
+//! * [`DrawOitCompositeDesc`](crate::pass::oit::DrawOitCompositeDesc)
+//! * [`ComputeNodeDesc`](crate::compute::ComputeNodeDesc)
 //!
 //! ## Systems
 //!
 //! * [`RenderingSystem`](crate::system::RenderingSystem)
 //! * [`VisibilitySortingSystem`](crate::visibility::VisibilitySortingSystem)
 //! * [`SpriteVisibilitySortingSystem`](crate::sprite_visibility::SpriteVisibilitySortingSystem)
+//! * [`DebugCollidersSystem`](crate::debug_drawing::DebugCollidersSystem)
+//! * [`RenderScaleSystem`](crate::render_scale::RenderScaleSystem)
 //!
 //! ## Components
 //!
@@ -29,11 +44,22 @@
 //! * [`SpriteVisibility`](sprite_visibility::SpriteVisibility)
 //! * [`Visibility`](visibility::Visibility)
 //! * [`BoundingSphere`](visibility::BoundingSphere)
+//! * [`Occluder`](occlusion::Occluder)
 //! * [`DebugLinesComponent`](debug_drawing::DebugLinesComponent)
 //! * [`Light`](light::Light)
+//! * [`Light2D`](light2d::Light2D)
+//! * [`SpriteNormalMap`](light2d::SpriteNormalMap)
+//! * [`ShadowCaster2D`](light2d::ShadowCaster2D)
+//! * [`Lightmapped`](lightmap::Lightmapped)
+//! * [`EnvironmentMap`](environment_map::EnvironmentMap)
+//! * [`ReflectionProbe`](reflection_probe::ReflectionProbe)
 //! * [`Tint`](resources::Tint)
 //! * [`JointTransforms`](skinning::JointTransforms)
 //! * [`SpriteRender`](sprite::SpriteRender)
+//! * [`RenderTarget`](render_target::RenderTarget)
+//! * [`Decal`](decal::Decal)
+//! * [`BillboardField`](billboard::BillboardField)
+//! * [`StaticBatchSources`](static_batch::StaticBatchSources)
 
 #![doc(
     html_logo_url = "https://amethyst.rs/brand/logo-standard.svg",
@@ -61,22 +87,40 @@ pub use rendy;
 
 pub mod pass;
 
+pub mod adapter;
+pub mod backend;
 pub mod batch;
+pub mod billboard;
 pub mod bundle;
 pub mod camera;
+pub mod compute;
+pub mod custom_material;
 pub mod debug_drawing;
+pub mod decal;
+pub mod environment_map;
 pub mod error;
 pub mod formats;
+pub mod graph_info;
 pub mod light;
+pub mod light2d;
+pub mod lightmap;
 pub mod mtl;
+pub mod occlusion;
+pub mod outline;
 pub mod pipeline;
 pub mod plugins;
+pub mod reflection_probe;
+pub mod render_scale;
+pub mod render_target;
+pub mod resolve;
 pub mod resources;
 pub mod serde_shim;
 pub mod shape;
+pub mod simple_pass;
 pub mod skinning;
 pub mod sprite;
 pub mod sprite_visibility;
+pub mod static_batch;
 pub mod submodules;
 pub mod system;
 pub mod transparent;
@@ -105,13 +149,25 @@ pub use rendy::{
 
 #[doc(inline)]
 pub use crate::{
+    adapter::{Adapters, GraphicsCapabilities},
+    backend::{AnyRenderingBundle, BackendConfig, BackendKind, RenderBackend, RenderPluginSet},
+    billboard::{BillboardField, RenderBillboards},
     bundle::{RenderPlugin, RenderingBundle},
-    camera::{ActiveCamera, Camera},
+    camera::{ActiveCamera, Camera, Viewport},
+    custom_material::{CustomMaterial, MaterialParams},
+    decal::{Decal, RenderDecals},
+    environment_map::EnvironmentMap,
     formats::texture::ImageFormat,
+    graph_info::{RenderGraphInfo, RenderTimings},
     mtl::{Material, MaterialDefaults},
+    occlusion::{Occluder, OcclusionCulling},
+    outline::{Outlined, RenderOutline},
     plugins::*,
+    reflection_probe::{ReflectionProbe, RenderReflectionProbes},
+    render_target::{RenderTarget, RenderToTexture, TargetTexture},
+    simple_pass::{RenderSimple, SimplePassDef},
     sprite::{Sprite, SpriteRender, SpriteSheet},
-    system::{GraphCreator, MeshProcessorSystem, TextureProcessorSystem},
+    system::{DeferredProcessorSystem, GraphCreator, MeshProcessorSystem, TextureProcessorSystem},
     transparent::Transparent,
     types::{Backend, Mesh, Texture},
     util::{simple_shader_set, ChangeDetection},
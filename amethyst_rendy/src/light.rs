@@ -217,9 +217,10 @@ impl From<SunLight> for Light {
 /// Import a gltf light into a Light
 impl From<khr_lights_punctual::Light<'_>> for Light {
     fn from(light: khr_lights_punctual::Light<'_>) -> Self {
+        // glTF light colors are linear, so encode them to match the sRGB `color` fields.
         let color = {
             let parts = light.color();
-            palette::Srgb::new(parts[0], parts[1], parts[2])
+            palette::Srgb::from_linear(palette::LinSrgb::new(parts[0], parts[1], parts[2]))
         };
         let intensity = light.intensity();
         let range = light.range();
@@ -0,0 +1,146 @@
+//! Lights for 2D scenes, lighting sprites drawn by
+//! [`RenderFlat2DLit`](crate::plugins::RenderFlat2DLit).
+//!
+//! Sprites are lit by the [`Light2D`]s of the scene and the [`AmbientColor`] resource, ideally
+//! wrapped in `Tracked`. A sprite with a [`SpriteNormalMap`] is shaded by its normals, other
+//! sprites face the camera. Sprites marked as [`ShadowCaster2D`] block the light of point lights
+//! casting shadows, the quad of the sprite being the occluder.
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToWindow::from_config(display_config))
+//!     .with_plugin(RenderFlat2DLit::default())
+//! ```
+//!
+//! [`AmbientColor`]: crate::resources::AmbientColor
+
+use amethyst_assets::Handle;
+use amethyst_core::math::Vector2;
+use palette::Srgb;
+use serde::{Deserialize, Serialize};
+
+use crate::types::Texture;
+
+/// A light source lighting sprites, positioned in the XY plane by its `Transform`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Light2D {
+    /// A light radiating from its position.
+    Point(PointLight2D),
+    /// A light coming from far away, e.g. the sun.
+    Directional(DirectionalLight2D),
+}
+
+impl From<PointLight2D> for Light2D {
+    fn from(light: PointLight2D) -> Self {
+        Light2D::Point(light)
+    }
+}
+
+impl From<DirectionalLight2D> for Light2D {
+    fn from(light: DirectionalLight2D) -> Self {
+        Light2D::Directional(light)
+    }
+}
+
+/// A 2D point light, fading out until its radius.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PointLight2D {
+    /// Color of the light in SRGB format.
+    #[serde(with = "crate::serde_shim::srgb")]
+    pub color: Srgb,
+    /// Brightness of the light source.
+    pub intensity: f32,
+    /// Distance at which the light no longer lights sprites.
+    pub radius: f32,
+    /// Height of the light above the sprites, lighting normal mapped sprites at a lower angle
+    /// when closer to them.
+    pub height: f32,
+    /// Whether sprites marked as [`ShadowCaster2D`] block the light.
+    pub shadows: bool,
+}
+
+impl Default for PointLight2D {
+    fn default() -> Self {
+        PointLight2D {
+            color: Srgb::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            radius: 100.0,
+            height: 10.0,
+            shadows: false,
+        }
+    }
+}
+
+/// A 2D directional light, lighting every sprite from the same direction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirectionalLight2D {
+    /// Color of the light in SRGB format.
+    #[serde(with = "crate::serde_shim::srgb")]
+    pub color: Srgb,
+    /// Brightness of the light source.
+    pub intensity: f32,
+    /// Direction the light is pointing to in the XY plane.
+    pub direction: Vector2<f32>,
+    /// Angle of the light above the XY plane in radians, from grazing at `0` to facing the
+    /// sprites at `PI / 2`.
+    pub elevation: f32,
+}
+
+impl Default for DirectionalLight2D {
+    fn default() -> Self {
+        DirectionalLight2D {
+            color: Srgb::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            direction: Vector2::new(1.0, -1.0),
+            elevation: std::f32::consts::FRAC_PI_4,
+        }
+    }
+}
+
+impl DirectionalLight2D {
+    /// Unit vector pointing towards the light, the Z axis facing the camera.
+    #[must_use]
+    pub fn to_light(&self) -> [f32; 3] {
+        let direction = if self.direction.norm_squared() > 0.0 {
+            self.direction.normalize()
+        } else {
+            Vector2::zeros()
+        };
+        let (sin, cos) = self.elevation.sin_cos();
+        [-direction.x * cos, -direction.y * cos, sin]
+    }
+}
+
+/// Normal map of the sprite of the entity, laid out like the texture of its sprite sheet.
+///
+/// Normals are stored in the usual tangent space, red pointing right and green up in the image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteNormalMap(pub Handle<Texture>);
+
+/// Marks a sprite as blocking the light of point lights casting shadows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShadowCaster2D;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directional_light_points_away_from_direction() {
+        let light = DirectionalLight2D {
+            direction: Vector2::new(2.0, 0.0),
+            elevation: 0.0,
+            ..Default::default()
+        };
+        let [x, y, z] = light.to_light();
+        assert!((x + 1.0).abs() < 1e-6 && y.abs() < 1e-6 && z.abs() < 1e-6);
+
+        let overhead = DirectionalLight2D {
+            elevation: std::f32::consts::FRAC_PI_2,
+            ..Default::default()
+        };
+        assert!((overhead.to_light()[2] - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,294 @@
+//! Lightmaps baked by external tools.
+//!
+//! Lightmapped meshes are lit by a texture of their lighting baked offline, sampled with a
+//! second set of texture coordinates, the [`LightmapTexCoord`]s. The glTF importer loads the
+//! second texture coordinates set of its meshes as lightmap texture coordinates.
+//!
+//! The round trip through an external baker goes as follows:
+//!
+//! 1. The static geometry of the scene is exported with a [`LightmapBakeScene`], as a Wavefront
+//!    OBJ file with an object per [`Named`] entity, textured with its lightmap texture
+//!    coordinates. The lights are set up in the baker.
+//! 2. The baker renders the lightmaps of the objects, e.g. into an atlas.
+//! 3. A [`LightmapManifest`] lists the lightmap of each entity, by name, and its part of the
+//!    texture. Applying it gives the entities a copy of their material with their lightmap,
+//!    and makes them [`Lightmapped`].
+//!
+//! The lightmapped meshes are drawn by [`RenderLightmaps`](crate::plugins::RenderLightmaps),
+//! added next to [`RenderPbr3D`](crate::plugins::RenderPbr3D). Their baked lighting replaces the
+//! ambient color and the diffuse lighting of the environment map.
+
+use std::{collections::HashMap, fmt::Write, fs, path::Path};
+
+use amethyst_assets::{AssetStorage, DefaultLoader, Handle, Loader, ProcessingQueue};
+use amethyst_core::{
+    ecs::{component, Entity, IntoQuery, Resources, World},
+    math::Matrix4,
+    Named,
+};
+use amethyst_error::{format_err, Error, ResultExt};
+use rendy::{hal::format::Format, mesh::AsAttribute};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mtl::{Material, TextureOffset},
+    static_batch::StaticMesh,
+    types::Texture,
+};
+
+/// Type for the lightmap texture coordinates attribute of vertex
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct LightmapTexCoord(pub [f32; 2]);
+
+impl From<[f32; 2]> for LightmapTexCoord {
+    fn from(from: [f32; 2]) -> Self {
+        Self(from)
+    }
+}
+
+impl AsAttribute for LightmapTexCoord {
+    const NAME: &'static str = "lightmap_tex_coord";
+    const FORMAT: Format = Format::Rg32Sfloat;
+}
+
+/// Component drawing its mesh lit by the lightmap of its material, see the
+/// [module documentation](self).
+///
+/// The mesh must have [`LightmapTexCoord`]s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lightmapped;
+
+/// Static geometry of a scene exported for a lightmap baker, see the
+/// [module documentation](self).
+///
+/// The geometry is written in world space, each object textured with its lightmap texture
+/// coordinates, V pointing up as usual for OBJ files.
+#[derive(Debug, Clone, Default)]
+pub struct LightmapBakeScene {
+    obj: String,
+    vertices: usize,
+}
+
+impl LightmapBakeScene {
+    /// Creates an empty scene.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the `mesh` of the entity named `name`, at its global `transform`.
+    pub fn add(
+        &mut self,
+        name: &str,
+        mesh: &StaticMesh,
+        transform: &Matrix4<f32>,
+    ) -> Result<(), Error> {
+        if mesh.lightmap_tex_coords.len() != mesh.vertex_count() {
+            return Err(format_err!(
+                "The mesh of {} has no lightmap texture coordinates",
+                name
+            ));
+        }
+        let mut world = StaticMesh::default();
+        world.append(mesh, transform);
+
+        // Writing to a `String` never fails.
+        let obj = &mut self.obj;
+        let _ = writeln!(obj, "o {}", name);
+        for p in &world.positions {
+            let _ = writeln!(obj, "v {} {} {}", p.0[0], p.0[1], p.0[2]);
+        }
+        for n in &world.normals {
+            let _ = writeln!(obj, "vn {} {} {}", n.0[0], n.0[1], n.0[2]);
+        }
+        for t in &world.lightmap_tex_coords {
+            let _ = writeln!(obj, "vt {} {}", t.0[0], 1.0 - t.0[1]);
+        }
+        let has_normals = world.normals.len() == world.positions.len();
+        for triangle in world.indices.chunks_exact(3) {
+            let _ = write!(obj, "f");
+            for index in triangle {
+                // OBJ indices start at 1, and count the vertices of the previous objects.
+                let index = self.vertices + *index as usize + 1;
+                if has_normals {
+                    let _ = write!(obj, " {0}/{0}/{0}", index);
+                } else {
+                    let _ = write!(obj, " {0}/{0}", index);
+                }
+            }
+            let _ = writeln!(obj);
+        }
+        self.vertices += world.positions.len();
+        Ok(())
+    }
+
+    /// The scene as a Wavefront OBJ file.
+    #[must_use]
+    pub fn to_obj(&self) -> &str {
+        &self.obj
+    }
+
+    /// Writes the scene to the Wavefront OBJ file at `path`.
+    pub fn write_obj(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, &self.obj).with_context(|_| format_err!("Unable to write the OBJ file"))
+    }
+}
+
+/// Lightmap of an entity baked by an external tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightmapEntry {
+    /// Asset path of the lightmap texture
+    pub texture: String,
+    /// Part of the texture holding the lightmap of the entity, e.g. in an atlas
+    #[serde(default)]
+    pub offset: TextureOffset,
+}
+
+/// Lightmaps baked for a scene, by name of their entity, see the
+/// [module documentation](self).
+///
+/// ```ron
+/// (
+///     lightmaps: {
+///         "floor": (texture: "lightmaps/atlas_0.hdr", offset: (u: (0.0, 0.5), v: (0.0, 0.5))),
+///         "wall": (texture: "lightmaps/atlas_0.hdr", offset: (u: (0.5, 1.0), v: (0.0, 0.5))),
+///     },
+/// )
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LightmapManifest {
+    /// Lightmaps by name of their entity
+    pub lightmaps: HashMap<String, LightmapEntry>,
+}
+
+impl LightmapManifest {
+    /// Reads the manifest from the RON file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).with_context(|_| {
+            format_err!("Unable to read the lightmap manifest {}", path.display())
+        })?;
+        ron::de::from_str(&content).with_context(|_| {
+            format_err!("Unable to parse the lightmap manifest {}", path.display())
+        })
+    }
+
+    /// Gives the [`Named`] entities listed a copy of their material with their lightmap, and
+    /// makes them [`Lightmapped`].
+    ///
+    /// Entities whose material isn't loaded yet are skipped, so this is called again until
+    /// every entity is lightmapped. Returns the number of entities lightmapped.
+    pub fn apply(&self, world: &mut World, resources: &Resources) -> usize {
+        let loader = resources.get::<DefaultLoader>().unwrap();
+        let materials = resources.get::<AssetStorage<Material>>().unwrap();
+        let material_queue = resources.get::<ProcessingQueue<Material>>().unwrap();
+
+        let mut textures = HashMap::<&str, Handle<Texture>>::new();
+        let mut lightmapped = Vec::new();
+        let mut query =
+            <(Entity, &Named, &Handle<Material>)>::query().filter(!component::<Lightmapped>());
+        for (entity, name, material) in query.iter(world) {
+            let entry = match self.lightmaps.get(name.0.as_ref()) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let material = match materials.get(material) {
+                Some(material) => material,
+                None => continue,
+            };
+            let lightmap = textures
+                .entry(&entry.texture)
+                .or_insert_with(|| loader.load(&entry.texture))
+                .clone();
+            let material = Material {
+                lightmap,
+                lightmap_offset: entry.offset.clone(),
+                ..material.clone()
+            };
+            lightmapped.push((
+                *entity,
+                loader.load_from_data(material, (), &material_queue),
+            ));
+        }
+
+        let count = lightmapped.len();
+        for (entity, material) in lightmapped {
+            if let Some(mut entry) = world.entry(entity) {
+                entry.add_component(material);
+                entry.add_component(Lightmapped);
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rendy::mesh::{Normal, Position, Tangent, TexCoord};
+
+    use super::*;
+
+    fn quad() -> StaticMesh {
+        StaticMesh {
+            positions: vec![
+                Position([0.0, 0.0, 0.0]),
+                Position([1.0, 0.0, 0.0]),
+                Position([1.0, 1.0, 0.0]),
+                Position([0.0, 1.0, 0.0]),
+            ],
+            normals: vec![Normal([0.0, 0.0, 1.0]); 4],
+            tangents: vec![Tangent([1.0, 0.0, 0.0, 1.0]); 4],
+            tex_coords: vec![TexCoord([0.0, 0.0]); 4],
+            lightmap_tex_coords: vec![
+                LightmapTexCoord([0.0, 1.0]),
+                LightmapTexCoord([1.0, 1.0]),
+                LightmapTexCoord([1.0, 0.0]),
+                LightmapTexCoord([0.0, 0.0]),
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        }
+    }
+
+    #[test]
+    fn exports_objects_in_world_space() {
+        let mut scene = LightmapBakeScene::new();
+        scene.add("floor", &quad(), &Matrix4::identity()).unwrap();
+        scene
+            .add(
+                "wall",
+                &quad(),
+                &Matrix4::new_translation(&[0.0, 0.0, 2.0].into()),
+            )
+            .unwrap();
+        let obj = scene.to_obj();
+
+        assert!(obj.starts_with("o floor\nv 0 0 0\n"));
+        assert!(obj.contains("vt 0 0\n"));
+        assert!(obj.contains("f 1/1/1 2/2/2 3/3/3\n"));
+        assert!(obj.contains("o wall\nv 0 0 2\n"));
+        assert!(obj.contains("f 5/5/5 6/6/6 7/7/7\n"));
+    }
+
+    #[test]
+    fn meshes_without_lightmap_tex_coords_are_rejected() {
+        let mesh = StaticMesh {
+            lightmap_tex_coords: Vec::new(),
+            ..quad()
+        };
+
+        assert!(LightmapBakeScene::new()
+            .add("floor", &mesh, &Matrix4::identity())
+            .is_err());
+    }
+
+    #[test]
+    fn parses_manifest() {
+        let manifest: LightmapManifest =
+            ron::de::from_str(r#"(lightmaps: {"floor": (texture: "lightmaps/floor.png")})"#)
+                .unwrap();
+
+        assert_eq!(manifest.lightmaps["floor"].texture, "lightmaps/floor.png");
+        assert_eq!(manifest.lightmaps["floor"].offset, TextureOffset::default());
+    }
+}
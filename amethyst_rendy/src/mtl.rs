@@ -32,6 +32,72 @@ impl Default for TextureOffset {
     }
 }
 
+/// Animation of the texture coordinates of a material over time, evaluated on the GPU, e.g. for
+/// flowing water or animated signs.
+///
+/// The texture coordinates are scrolled first. Then, for a flipbook of more than one frame, the
+/// texture is divided into a grid of frames, ordered row by row from the top left, and the
+/// current one is shown. Both apply within the `uv_offset` of the material.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UvAnimation {
+    /// Texture coordinates scrolled per second along U and V
+    pub scroll: (f32, f32),
+    /// Columns and rows of the flipbook grid
+    pub grid: (u32, u32),
+    /// Number of frames of the flipbook, at most the number of cells of its grid
+    pub frames: u32,
+    /// Flipbook frames shown per second
+    pub frames_per_second: f32,
+}
+
+impl Default for UvAnimation {
+    fn default() -> Self {
+        UvAnimation {
+            scroll: (0., 0.),
+            grid: (1, 1),
+            frames: 1,
+            frames_per_second: 0.,
+        }
+    }
+}
+
+impl UvAnimation {
+    /// Scroll the texture coordinates by `u` and `v` per second.
+    #[must_use]
+    pub fn scrolling(u: f32, v: f32) -> Self {
+        UvAnimation {
+            scroll: (u, v),
+            ..Default::default()
+        }
+    }
+
+    /// Play all cells of a flipbook grid of `columns` by `rows` frames, at `frames_per_second`.
+    #[must_use]
+    pub fn flipbook(columns: u32, rows: u32, frames_per_second: f32) -> Self {
+        UvAnimation {
+            grid: (columns, rows),
+            frames: columns * rows,
+            frames_per_second,
+            ..Default::default()
+        }
+    }
+
+    /// Play only the first `frames` cells of the flipbook grid.
+    #[must_use]
+    pub fn with_frames(mut self, frames: u32) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Also scroll the texture coordinates by `u` and `v` per second.
+    #[must_use]
+    pub fn with_scroll(mut self, u: f32, v: f32) -> Self {
+        self.scroll = (u, v);
+        self
+    }
+}
+
 /// A physically based Material with metallic workflow, fully utilized in PBR render pass.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
 #[uuid = "e238c036-42e9-4d0e-9aa9-c6511c906820"]
@@ -52,6 +118,13 @@ pub struct Material {
     pub cavity: Handle<Texture>,
     /// Texture offset
     pub uv_offset: TextureOffset,
+    /// Texture coordinates animation
+    pub uv_animation: UvAnimation,
+    /// Baked lighting map, sampled with the lightmap texture coordinates of the
+    /// [`Lightmapped`](crate::lightmap::Lightmapped) meshes.
+    pub lightmap: Handle<Texture>,
+    /// Part of the lightmap used by the material, e.g. in a lightmap atlas
+    pub lightmap_offset: TextureOffset,
 }
 
 impl Asset for Material {
@@ -131,6 +204,17 @@ pub type FullTextureSet = (
     TexCavity,
 );
 
+/// Type alias for a tuple collection of a complete PBR texture set with a lightmap.
+pub type LightmapTextureSet = (
+    TexAlbedo,
+    TexEmission,
+    TexNormal,
+    TexMetallicRoughness,
+    TexAmbientOcclusion,
+    TexCavity,
+    TexLightmap,
+);
+
 macro_rules! impl_texture {
     ($name:ident, $prop:ident) => {
         #[doc = "Macro Generated Texture Type"]
@@ -152,6 +236,7 @@ impl_texture!(TexNormal, normal);
 impl_texture!(TexMetallicRoughness, metallic_roughness);
 impl_texture!(TexAmbientOcclusion, ambient_occlusion);
 impl_texture!(TexCavity, cavity);
+impl_texture!(TexLightmap, lightmap);
 
 macro_rules! recursive_iter {
     (@value $first:expr, $($rest:expr),*) => { $first.chain(recursive_iter!(@value $($rest),*)) };
@@ -184,3 +269,4 @@ impl_texture_set_tuple!(A, B, C);
 impl_texture_set_tuple!(A, B, C, D);
 impl_texture_set_tuple!(A, B, C, D, E);
 impl_texture_set_tuple!(A, B, C, D, E, F);
+impl_texture_set_tuple!(A, B, C, D, E, F, G);
@@ -0,0 +1,402 @@
+//! Occlusion culling of 3D entities hidden behind [`Occluder`]s.
+//!
+//! Frustum culling still draws everything in front of the camera, e.g. every room of a building
+//! seen from outside. When occlusion culling is enabled with
+//! [`RenderBase3D::with_occlusion_culling`](crate::plugins::RenderBase3D::with_occlusion_culling)
+//! or [`VisibilitySortingSystem::with_occlusion_culling`], the occluders in front of each camera
+//! are rasterized on the CPU into a small depth buffer, and the entities whose bounding sphere is
+//! behind the occluders on every pixel it covers are not drawn.
+//!
+//! The depth buffer is conservative: an occluder only covers the pixels it covers entirely, at
+//! the depth of its farthest point on them, so culling never hides an entity that is visible.
+//!
+//! [`VisibilitySortingSystem::with_occlusion_culling`]: crate::visibility::VisibilitySortingSystem::with_occlusion_culling
+
+use amethyst_core::math::{Matrix4, Point2, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Component making its entity hide the entities behind it, see the
+/// [module documentation](self).
+///
+/// The occluder is the box of size `2 * half_extents` centered on the entity, scaled by its
+/// `Transform`. It must lie inside the geometry drawn for the entity, e.g. walls, floors and
+/// large props, otherwise the entities seen past this geometry would be culled. Entities with an
+/// `Occluder` are never occlusion culled themselves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Occluder {
+    /// Half of the size of the box along each local axis.
+    pub half_extents: Vector3<f32>,
+}
+
+impl Occluder {
+    /// Create a new `Occluder` box with the supplied half extents.
+    #[must_use]
+    pub fn new(half_extents: Vector3<f32>) -> Self {
+        Self { half_extents }
+    }
+}
+
+/// Settings of occlusion culling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcclusionCulling {
+    /// Width in pixels of the depth buffer occluders are rasterized into.
+    pub width: usize,
+    /// Height in pixels of the depth buffer occluders are rasterized into.
+    pub height: usize,
+}
+
+impl Default for OcclusionCulling {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 128,
+        }
+    }
+}
+
+/// Corners of the quads bounding a box, indexing its corners with one bit per positive axis.
+const FACES: [[usize; 4]; 6] = [
+    [0, 2, 6, 4],
+    [1, 3, 7, 5],
+    [0, 1, 5, 4],
+    [2, 3, 7, 6],
+    [0, 1, 3, 2],
+    [4, 5, 7, 6],
+];
+
+/// Depth buffer the occluders in front of a camera are rasterized into.
+///
+/// Depth goes from 1 on the near plane to 0 on the far plane, like the depth of the camera
+/// projection, and pixels not covered by any occluder have depth 0.
+#[derive(Debug, Default)]
+pub(crate) struct OcclusionBuffer {
+    width: usize,
+    height: usize,
+    depth: Vec<f32>,
+    projection: Matrix4<f32>,
+    view: Matrix4<f32>,
+    view_projection: Matrix4<f32>,
+    empty: bool,
+}
+
+impl OcclusionBuffer {
+    /// Clears the buffer for a camera with the supplied projection and view matrices.
+    pub(crate) fn reset(
+        &mut self,
+        settings: &OcclusionCulling,
+        projection: Matrix4<f32>,
+        view: Matrix4<f32>,
+    ) {
+        self.width = settings.width;
+        self.height = settings.height;
+        self.depth.clear();
+        self.depth.resize(self.width * self.height, 0.0);
+        self.projection = projection;
+        self.view = view;
+        self.view_projection = projection * view;
+        self.empty = true;
+    }
+
+    /// Projects `point` to pixel coordinates and depth, or `None` when it's behind the camera.
+    fn project(&self, matrix: &Matrix4<f32>, point: &Point3<f32>) -> Option<Point3<f32>> {
+        let clip = matrix * point.to_homogeneous();
+        if clip.w <= f32::EPSILON || clip.z > clip.w {
+            return None;
+        }
+        Some(Point3::new(
+            (clip.x / clip.w * 0.5 + 0.5) * self.width as f32,
+            (clip.y / clip.w * 0.5 + 0.5) * self.height as f32,
+            clip.z / clip.w,
+        ))
+    }
+
+    /// Rasterizes `occluder` placed by the global matrix `model`.
+    ///
+    /// Occluders crossing the near plane are skipped.
+    pub(crate) fn rasterize(&mut self, model: &Matrix4<f32>, occluder: &Occluder) {
+        let matrix = self.view_projection * model;
+        let mut corners = [Point3::origin(); 8];
+        for (index, corner) in corners.iter_mut().enumerate() {
+            let local = Point3::new(
+                sign(index & 1) * occluder.half_extents.x,
+                sign(index & 2) * occluder.half_extents.y,
+                sign(index & 4) * occluder.half_extents.z,
+            );
+            match self.project(&matrix, &local) {
+                Some(projected) => *corner = projected,
+                None => return,
+            }
+        }
+
+        // A point inside the projected box, to orient the normals of its faces outwards.
+        let inside = corners
+            .iter()
+            .fold(Vector3::zeros(), |sum, corner| sum + corner.coords)
+            / 8.0;
+        let faces: Vec<Face> = FACES
+            .iter()
+            .filter_map(|face| {
+                let [a, b, c, d] = *face;
+                Face::front([corners[a], corners[b], corners[c], corners[d]], &inside)
+            })
+            .collect();
+        let hull = convex_hull(&corners);
+        if faces.is_empty() || hull.len() < 3 {
+            return;
+        }
+
+        let (min, max) = hull.iter().fold(
+            (
+                Point2::new(f32::MAX, f32::MAX),
+                Point2::new(f32::MIN, f32::MIN),
+            ),
+            |(min, max), p| (min.inf(p), max.sup(p)),
+        );
+        let (x0, x1) = pixel_range(min.x, max.x, self.width);
+        let (y0, y1) = pixel_range(min.y, max.y, self.height);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let center = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if !covers_pixel(&hull, &center) {
+                    continue;
+                }
+                // The farthest depth of the faces overlapping the pixel is behind the occluder
+                // surface seen through it.
+                let depth = faces
+                    .iter()
+                    .filter(|face| face.overlaps_pixel(&center))
+                    .map(|face| face.farthest_depth(&center))
+                    .fold(f32::INFINITY, f32::min);
+                if depth.is_finite() {
+                    let pixel = &mut self.depth[y * self.width + x];
+                    *pixel = pixel.max(depth);
+                    self.empty = false;
+                }
+            }
+        }
+    }
+
+    /// Whether the sphere of `radius` around `center`, in world space, is hidden by the
+    /// occluders.
+    pub(crate) fn is_occluded(&self, center: &Point3<f32>, radius: f32) -> bool {
+        if self.empty {
+            return false;
+        }
+
+        // The bounding box of the sphere in view space covers the sphere on the screen, and
+        // its depth is the depth of the nearest point of the sphere.
+        let center = self.view.transform_point(center);
+        let mut min = Point2::new(f32::MAX, f32::MAX);
+        let mut max = Point2::new(f32::MIN, f32::MIN);
+        let mut nearest = f32::MIN;
+        for index in 0..8 {
+            let corner = center
+                + Vector3::new(
+                    sign(index & 1) * radius,
+                    sign(index & 2) * radius,
+                    sign(index & 4) * radius,
+                );
+            match self.project(&self.projection, &corner) {
+                Some(projected) => {
+                    min = min.inf(&projected.xy());
+                    max = max.sup(&projected.xy());
+                    nearest = nearest.max(projected.z);
+                }
+                None => return false,
+            }
+        }
+
+        let (x0, x1) = pixel_range(min.x, max.x, self.width);
+        let (y0, y1) = pixel_range(min.y, max.y, self.height);
+        if x0 >= x1 || y0 >= y1 {
+            return false;
+        }
+        (y0..y1).all(|y| {
+            self.depth[y * self.width + x0..y * self.width + x1]
+                .iter()
+                .all(|&depth| depth > nearest)
+        })
+    }
+}
+
+/// A face of an occluder facing the camera, in pixel coordinates.
+#[derive(Debug)]
+struct Face {
+    corners: [Point2<f32>; 4],
+    winding: f32,
+    origin: Point3<f32>,
+    depth_dx: f32,
+    depth_dy: f32,
+}
+
+impl Face {
+    /// Creates the face with the supplied corners if it faces the camera.
+    fn front(corners: [Point3<f32>; 4], inside: &Vector3<f32>) -> Option<Self> {
+        let mut normal = (corners[1] - corners[0]).cross(&(corners[3] - corners[0]));
+        if normal.dot(&(corners[0].coords - inside)) < 0.0 {
+            normal = -normal;
+        }
+        // Depth grows towards the camera.
+        if normal.z <= f32::EPSILON {
+            return None;
+        }
+
+        let corners2d = [
+            corners[0].xy(),
+            corners[1].xy(),
+            corners[2].xy(),
+            corners[3].xy(),
+        ];
+        let area = (0..4)
+            .map(|i| {
+                edge(
+                    &corners2d[i],
+                    &corners2d[(i + 1) % 4],
+                    &corners2d[(i + 2) % 4],
+                )
+            })
+            .sum::<f32>();
+        Some(Self {
+            corners: corners2d,
+            winding: area.signum(),
+            origin: corners[0],
+            depth_dx: -normal.x / normal.z,
+            depth_dy: -normal.y / normal.z,
+        })
+    }
+
+    /// Whether the face overlaps the pixel with the supplied center, possibly reporting faces
+    /// passing close to it.
+    fn overlaps_pixel(&self, center: &Point2<f32>) -> bool {
+        (0..4).all(|i| {
+            let (a, b) = (&self.corners[i], &self.corners[(i + 1) % 4]);
+            self.winding * edge(a, b, center) >= -half_pixel(a, b)
+        })
+    }
+
+    /// Depth of the farthest point of the face's plane on the pixel with the supplied center.
+    fn farthest_depth(&self, center: &Point2<f32>) -> f32 {
+        self.origin.z
+            + self.depth_dx * (center.x - self.origin.x)
+            + self.depth_dy * (center.y - self.origin.y)
+            - 0.5 * (self.depth_dx.abs() + self.depth_dy.abs())
+    }
+}
+
+fn sign(bit: usize) -> f32 {
+    if bit == 0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`, positive when `p` is left of `a` to `b`.
+fn edge(a: &Point2<f32>, b: &Point2<f32>, p: &Point2<f32>) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Largest change of [`edge`] between the center and the corners of a pixel.
+fn half_pixel(a: &Point2<f32>, b: &Point2<f32>) -> f32 {
+    0.5 * ((b.x - a.x).abs() + (b.y - a.y).abs())
+}
+
+/// Whether the counter-clockwise convex polygon `hull` covers the whole pixel with the supplied
+/// center.
+fn covers_pixel(hull: &[Point2<f32>], center: &Point2<f32>) -> bool {
+    (0..hull.len()).all(|i| {
+        let (a, b) = (&hull[i], &hull[(i + 1) % hull.len()]);
+        edge(a, b, center) >= half_pixel(a, b)
+    })
+}
+
+/// Range of the pixels overlapping `min..max`, clamped to `0..size`.
+fn pixel_range(min: f32, max: f32, size: usize) -> (usize, usize) {
+    let clamp = |value: f32| value.max(0.0).min(size as f32) as usize;
+    (clamp(min.floor()), clamp(max.ceil()))
+}
+
+/// Counter-clockwise convex hull of the projection of `points` on the screen.
+fn convex_hull(points: &[Point3<f32>]) -> Vec<Point2<f32>> {
+    let mut points: Vec<Point2<f32>> = points.iter().map(|point| point.xy()).collect();
+    points.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    let mut hull: Vec<Point2<f32>> = Vec::with_capacity(points.len() + 1);
+    for pass in 0..2 {
+        let start = hull.len();
+        for point in &points {
+            while hull.len() >= start + 2
+                && edge(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        hull.pop();
+        if pass == 0 {
+            points.reverse();
+        }
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::math::Translation3;
+
+    use super::*;
+
+    fn buffer() -> OcclusionBuffer {
+        let mut buffer = OcclusionBuffer::default();
+        let projection = crate::camera::Camera::perspective(1.0, 1.0, 0.1).matrix;
+        buffer.reset(
+            &OcclusionCulling::default(),
+            projection,
+            Matrix4::identity(),
+        );
+        // A wall of 4 by 4 at 10 in front of the camera, which looks down -Z.
+        buffer.rasterize(
+            &Translation3::new(0.0, 0.0, -10.0).to_homogeneous(),
+            &Occluder::new(Vector3::new(2.0, 2.0, 0.5)),
+        );
+        buffer
+    }
+
+    #[test]
+    fn spheres_behind_occluders_are_occluded() {
+        let buffer = buffer();
+        assert!(buffer.is_occluded(&Point3::new(0.0, 0.0, -20.0), 1.0));
+        assert!(buffer.is_occluded(&Point3::new(2.0, -1.0, -50.0), 5.0));
+    }
+
+    #[test]
+    fn spheres_not_behind_occluders_are_visible() {
+        let buffer = buffer();
+        // In front of the wall
+        assert!(!buffer.is_occluded(&Point3::new(0.0, 0.0, -5.0), 1.0));
+        // Overlapping the wall
+        assert!(!buffer.is_occluded(&Point3::new(0.0, 0.0, -11.0), 2.0));
+        // Seen past the edge of the wall
+        assert!(!buffer.is_occluded(&Point3::new(6.0, 0.0, -20.0), 1.0));
+    }
+
+    #[test]
+    fn hull_is_counter_clockwise() {
+        let points = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.5, 0.5, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        let hull = convex_hull(&points);
+
+        assert_eq!(hull.len(), 4);
+        assert!(covers_pixel(&hull, &Point2::new(0.5, 0.5)));
+    }
+}
@@ -0,0 +1,114 @@
+//! Colored outlines around selected entities, e.g. for selection feedback in strategy games and
+//! editors.
+//!
+//! Entities with an [`Outlined`] component are outlined by the [`RenderOutline`] plugin:
+//!
+//! * meshes by drawing their back faces grown along the normals behind them, so only the part
+//!   sticking out around the silhouette is visible. Meshes need positions and normals.
+//! * sprites by detecting the edges of their opaque texels, drawing the outline over the
+//!   transparent ones around them.
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToWindow::from_config(display_config))
+//!     .with_plugin(RenderFlat2D::default())
+//!     .with_plugin(RenderOutline::default())
+//! ```
+//!
+//! Outlines are hidden behind what's in front of the entity, as the depth of the scene drawn
+//! into the target before them is tested.
+
+use amethyst_core::ecs::{Resources, World};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::DrawOutlineDesc,
+    types::Backend,
+    Factory,
+};
+
+/// Component outlining an entity, see the [module documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outlined {
+    /// Linear RGBA color of the outline
+    pub color: [f32; 4],
+    /// Width of the outline in world units
+    pub width: f32,
+}
+
+impl Default for Outlined {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 0.8, 0.0, 1.0],
+            width: 0.05,
+        }
+    }
+}
+
+impl Outlined {
+    /// Outline with given linear RGBA color and width in world units.
+    #[must_use]
+    pub fn new(color: [f32; 4], width: f32) -> Self {
+        Self { color, width }
+    }
+}
+
+/// A [`RenderPlugin`] drawing the outlines of [`Outlined`] entities, see the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct RenderOutline {
+    target: Target,
+    order: i32,
+}
+
+impl Default for RenderOutline {
+    fn default() -> Self {
+        Self {
+            target: Target::default(),
+            order: RenderOrder::AfterTransparent.into(),
+        }
+    }
+}
+
+impl RenderOutline {
+    /// Set target to which the outlines will be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the outlines at given point of the frame, [`RenderOrder::AfterTransparent`] by
+    /// default.
+    #[must_use]
+    pub fn with_order(mut self, order: impl Into<i32>) -> Self {
+        self.order = order.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderOutline {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        let target = self.target;
+        let order = self.order;
+        plan.extend_target(self.target, move |ctx| {
+            ctx.add(
+                order,
+                DrawOutlineDesc::new()
+                    .with_target(target)
+                    .with_samples(ctx.samples())
+                    .builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
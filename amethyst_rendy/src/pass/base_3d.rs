@@ -19,13 +19,19 @@ use smallvec::SmallVec;
 use crate::{
     batch,
     batch::{GroupIterator, OrderedTwoLevelBatch, TwoLevelBatch},
-    mtl::{FullTextureSet, Material, StaticTextureSet},
+    bundle::Target,
+    camera::Viewport,
+    lightmap::Lightmapped,
+    mtl::{Material, StaticTextureSet},
     pass,
     pipeline::{PipelineDescBuilder, PipelinesBuilder},
     pod::{SkinnedVertexArgs, VertexArgs},
     resources::Tint,
     skinning::JointTransforms,
-    submodules::{DynamicVertexBuffer, EnvironmentSub, MaterialId, MaterialSub, SkinningSub},
+    submodules::{
+        DynamicVertexBuffer, EnvironmentMapSub, EnvironmentSub, MaterialId, MaterialSub,
+        SkinningSub,
+    },
     system::GraphAuxData,
     types::{Backend, Mesh},
     util,
@@ -49,6 +55,14 @@ pub trait Base3DPassDef: 'static + std::fmt::Debug + Send + Sync {
     /// The human-readable name of this pass
     const NAME: &'static str;
 
+    /// Whether the fragment shader samples the environment map of the rendered camera,
+    /// bound as descriptor set 3. See [`EnvironmentMapSub`].
+    const IMAGE_BASED_LIGHTING: bool = false;
+
+    /// Whether the pass draws the [`Lightmapped`] meshes, instead of the other ones.
+    /// Skinned meshes are never lightmapped.
+    const LIGHTMAPPED: bool = false;
+
     /// The [`mtl::StaticTextureSet`] type implementation for this pass
     type TextureSet: for<'a> StaticTextureSet<'a>;
 
@@ -61,6 +75,13 @@ pub trait Base3DPassDef: 'static + std::fmt::Debug + Send + Sync {
     /// Returns the fragment `SpirvShader` which will be used for this pass
     fn fragment_shader() -> &'static SpirvShader;
 
+    /// Returns the fragment `SpirvShader` writing the outputs of weighted blended
+    /// order-independent transparency, see [`DrawOitCompositeDesc`](super::DrawOitCompositeDesc).
+    /// Transparent meshes of passes without one can only be drawn in order.
+    fn fragment_order_independent_shader() -> Option<&'static SpirvShader> {
+        None
+    }
+
     /// Returns the `VertexFormat` of this pass
     fn base_format() -> Vec<VertexFormat>;
 
@@ -73,6 +94,10 @@ pub trait Base3DPassDef: 'static + std::fmt::Debug + Send + Sync {
 #[derivative(Debug(bound = ""), Default(bound = ""))]
 pub struct DrawBase3DDesc<B: Backend, T: Base3DPassDef> {
     skinning: bool,
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+    depth_only: bool,
     marker: PhantomData<(B, T)>,
 }
 
@@ -88,6 +113,10 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DDesc<B, T> {
     pub fn skinned() -> Self {
         Self {
             skinning: true,
+            target: Target::default(),
+            viewport: None,
+            samples: 1,
+            depth_only: false,
             marker: PhantomData,
         }
     }
@@ -98,6 +127,37 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DDesc<B, T> {
         self.skinning = skinned;
         self
     }
+
+    /// Draw meshes as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw meshes as seen by the camera of the viewport with given index, limited to its region
+    /// of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Only write the depth of the meshes, into a target with the accumulation and revealage
+    /// color outputs of order-independent transparency, which are left untouched. See
+    /// [`DrawBase3DTransparentDesc::with_order_independent`].
+    #[must_use]
+    pub fn with_depth_only(mut self, depth_only: bool) -> Self {
+        self.depth_only = depth_only;
+        self
+    }
 }
 
 impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData> for DrawBase3DDesc<B, T> {
@@ -105,8 +165,8 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData> for DrawBase
         self,
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
-        _queue: QueueId,
-        _aux: &GraphAuxData,
+        queue: QueueId,
+        aux: &GraphAuxData,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -118,12 +178,24 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData> for DrawBase
         let env = EnvironmentSub::new(
             factory,
             [
-                hal::pso::ShaderStageFlags::VERTEX,
+                // The fragment shaders animate texture coordinates with the time of the view.
+                hal::pso::ShaderStageFlags::VERTEX | hal::pso::ShaderStageFlags::FRAGMENT,
                 hal::pso::ShaderStageFlags::FRAGMENT,
             ],
-        )?;
+        )?
+        .with_target(self.target)
+        .with_viewport(self.viewport);
         let materials = MaterialSub::new(factory)?;
         let skinning = SkinningSub::new(factory)?;
+        let env_map = if T::IMAGE_BASED_LIGHTING {
+            Some(
+                EnvironmentMapSub::new(factory, queue)?
+                    .with_target(self.target)
+                    .with_viewport(self.viewport),
+            )
+        } else {
+            None
+        };
 
         let mut vertex_format_base = T::base_format();
         let mut vertex_format_skinned = T::skinned_format();
@@ -131,17 +203,29 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData> for DrawBase
         let (mut pipelines, pipeline_layout) = build_pipelines::<B, T>(
             factory,
             subpass,
-            framebuffer_width,
-            framebuffer_height,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
             &vertex_format_base,
             &vertex_format_skinned,
             self.skinning,
-            false,
+            if self.depth_only {
+                Blending::DepthOnly
+            } else {
+                Blending::Opaque
+            },
             vec![
                 env.raw_layout(),
                 materials.raw_layout(),
                 skinning.raw_layout(),
-            ],
+            ]
+            .into_iter()
+            .chain(env_map.as_ref().map(EnvironmentMapSub::raw_layout))
+            .collect(),
         )?;
 
         vertex_format_base.sort();
@@ -156,6 +240,7 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData> for DrawBase
             vertex_format_base,
             vertex_format_skinned,
             env,
+            env_map,
             materials,
             skinning,
             models: DynamicVertexBuffer::new(),
@@ -178,6 +263,7 @@ pub struct DrawBase3D<B: Backend, T: Base3DPassDef> {
     vertex_format_base: Vec<VertexFormat>,
     vertex_format_skinned: Vec<VertexFormat>,
     env: EnvironmentSub<B>,
+    env_map: Option<EnvironmentMapSub<B>>,
     materials: MaterialSub<B, T::TextureSet>,
     skinning: SkinningSub<B>,
     models: DynamicVertexBuffer<B, VertexArgs>,
@@ -203,6 +289,9 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3D<B
 
         // Prepare environment
         self.env.process(factory, index, world, resources);
+        if let Some(env_map) = self.env_map.as_mut() {
+            env_map.process(factory, index, world, resources);
+        }
         self.materials.maintain();
 
         self.static_batches.clear_inner();
@@ -215,14 +304,20 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3D<B
 
         {
             profile_scope_impl!("prepare");
-            let mut query =
-                <(&Handle<Material>, &Handle<Mesh>, &Transform, Option<&Tint>)>::query();
+            let mut query = <(
+                &Handle<Material>,
+                &Handle<Mesh>,
+                &Transform,
+                Option<&Tint>,
+                Option<&Lightmapped>,
+            )>::query();
 
             visibility
                 .visible_unordered
                 .iter()
                 .filter_map(|entity| Some((entity, query.get(*world, *entity).ok()?)))
-                .map(|(entity, (mat, mesh, tform, tint))| {
+                .filter(|(_, (_, _, _, _, lightmapped))| lightmapped.is_some() == T::LIGHTMAPPED)
+                .map(|(entity, (mat, mesh, tform, tint, _))| {
                     // log::debug!("(entity, (mat, mesh, tform, tint))");
                     if let Some(tint) = tint {
                         (
@@ -332,6 +427,9 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3D<B
 
         encoder.bind_graphics_pipeline(&self.pipeline_basic);
         self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        if let Some(env_map) = self.env_map.as_ref() {
+            env_map.bind(index, &self.pipeline_layout, 3, &mut encoder);
+        }
 
         if self.models.bind(index, models_loc, 0, &mut encoder) {
             let mut instances_drawn = 0;
@@ -419,6 +517,10 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3D<B
 #[derivative(Debug(bound = ""), Default(bound = ""))]
 pub struct DrawBase3DTransparentDesc<B: Backend, T: Base3DPassDef> {
     skinning: bool,
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+    order_independent: bool,
     marker: PhantomData<(B, T)>,
 }
 
@@ -428,6 +530,10 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DTransparentDesc<B, T> {
     pub fn new() -> Self {
         Self {
             skinning: false,
+            target: Target::default(),
+            viewport: None,
+            samples: 1,
+            order_independent: false,
             marker: PhantomData,
         }
     }
@@ -437,6 +543,10 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DTransparentDesc<B, T> {
     pub fn skinned() -> Self {
         Self {
             skinning: true,
+            target: Target::default(),
+            viewport: None,
+            samples: 1,
+            order_independent: false,
             marker: PhantomData,
         }
     }
@@ -447,6 +557,41 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DTransparentDesc<B, T> {
         self.skinning = skinned;
         self
     }
+
+    /// Draw meshes as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw meshes as seen by the camera of the viewport with given index, limited to its region
+    /// of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Accumulate the meshes into the accumulation and revealage color outputs of weighted
+    /// blended order-independent transparency, instead of blending them over the target in
+    /// order. The outputs are composited over the scene by
+    /// [`DrawOitCompositeDesc`](super::DrawOitCompositeDesc).
+    ///
+    /// The pass definition must have a
+    /// [`fragment_order_independent_shader`](Base3DPassDef::fragment_order_independent_shader).
+    #[must_use]
+    pub fn with_order_independent(mut self, order_independent: bool) -> Self {
+        self.order_independent = order_independent;
+        self
+    }
 }
 
 impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData>
@@ -456,8 +601,8 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData>
         self,
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
-        _queue: QueueId,
-        _aux: &GraphAuxData,
+        queue: QueueId,
+        aux: &GraphAuxData,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -467,13 +612,24 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData>
         let env = EnvironmentSub::new(
             factory,
             [
-                hal::pso::ShaderStageFlags::VERTEX,
+                // The fragment shaders animate texture coordinates with the time of the view.
+                hal::pso::ShaderStageFlags::VERTEX | hal::pso::ShaderStageFlags::FRAGMENT,
                 hal::pso::ShaderStageFlags::FRAGMENT,
             ],
-        )?;
+        )?
+        .with_target(self.target);
 
         let materials = MaterialSub::new(factory)?;
         let skinning = SkinningSub::new(factory)?;
+        let env_map = if T::IMAGE_BASED_LIGHTING {
+            Some(
+                EnvironmentMapSub::new(factory, queue)?
+                    .with_target(self.target)
+                    .with_viewport(self.viewport),
+            )
+        } else {
+            None
+        };
 
         let mut vertex_format_base = T::base_format();
         let mut vertex_format_skinned = T::skinned_format();
@@ -481,17 +637,29 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData>
         let (mut pipelines, pipeline_layout) = build_pipelines::<B, T>(
             factory,
             subpass,
-            framebuffer_width,
-            framebuffer_height,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
             &vertex_format_base,
             &vertex_format_skinned,
             self.skinning,
-            true,
+            if self.order_independent {
+                Blending::OrderIndependent
+            } else {
+                Blending::Transparent
+            },
             vec![
                 env.raw_layout(),
                 materials.raw_layout(),
                 skinning.raw_layout(),
-            ],
+            ]
+            .into_iter()
+            .chain(env_map.as_ref().map(EnvironmentMapSub::raw_layout))
+            .collect(),
         )?;
 
         vertex_format_base.sort();
@@ -506,10 +674,12 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, GraphAuxData>
             vertex_format_base,
             vertex_format_skinned,
             env,
+            env_map,
             materials,
             skinning,
             models: DynamicVertexBuffer::new(),
             skinned_models: DynamicVertexBuffer::new(),
+            viewport: self.viewport,
             change: util::ChangeDetection::default(),
             marker: PhantomData,
         }))
@@ -528,10 +698,12 @@ pub struct DrawBase3DTransparent<B: Backend, T: Base3DPassDef> {
     vertex_format_base: Vec<VertexFormat>,
     vertex_format_skinned: Vec<VertexFormat>,
     env: EnvironmentSub<B>,
-    materials: MaterialSub<B, FullTextureSet>,
+    env_map: Option<EnvironmentMapSub<B>>,
+    materials: MaterialSub<B, T::TextureSet>,
     skinning: SkinningSub<B>,
     models: DynamicVertexBuffer<B, VertexArgs>,
     skinned_models: DynamicVertexBuffer<B, SkinnedVertexArgs>,
+    viewport: Option<usize>,
     change: util::ChangeDetection,
     marker: PhantomData<T>,
 }
@@ -554,6 +726,9 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3DTr
 
         // Prepare environment
         self.env.process(factory, index, world, resources);
+        if let Some(env_map) = self.env_map.as_mut() {
+            env_map.process(factory, index, world, resources);
+        }
         self.materials.maintain();
 
         self.static_batches.swap_clear();
@@ -568,14 +743,20 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3DTr
         {
             profile_scope_impl!("prepare");
 
-            let mut query =
-                <(&Handle<Material>, &Handle<Mesh>, &Transform, Option<&Tint>)>::query();
+            let mut query = <(
+                &Handle<Material>,
+                &Handle<Mesh>,
+                &Transform,
+                Option<&Tint>,
+                Option<&Lightmapped>,
+            )>::query();
 
             visibility
-                .visible_ordered
+                .ordered(self.viewport)
                 .iter()
                 .filter_map(|entity| Some((entity, query.get(*world, *entity).ok()?)))
-                .map(|(entity, (mat, mesh, tform, tint))| {
+                .filter(|(_, (_, _, _, _, lightmapped))| lightmapped.is_some() == T::LIGHTMAPPED)
+                .map(|(entity, (mat, mesh, tform, tint, _))| {
                     if let Some(tint) = tint {
                         (
                             (mat, mesh.load_handle()),
@@ -690,6 +871,9 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3DTr
 
         encoder.bind_graphics_pipeline(&self.pipeline_basic);
         self.env.bind(index, layout, 0, encoder);
+        if let Some(env_map) = self.env_map.as_ref() {
+            env_map.bind(index, layout, 3, encoder);
+        }
 
         if self.models.bind(index, models_loc, 0, encoder) {
             for (&mat, batches) in self.static_batches.iter() {
@@ -772,17 +956,43 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, GraphAuxData> for DrawBase3DTr
     }
 }
 
+/// How the pipelines of a pass write the fragments of the meshes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Blending {
+    /// Write the colors and depth.
+    Opaque,
+    /// Only write the depth, keeping the two color outputs of order-independent transparency.
+    DepthOnly,
+    /// Blend the premultiplied colors over the target, without writing depth.
+    Transparent,
+    /// Accumulate the colors and revealage of order-independent transparency, without writing
+    /// depth.
+    OrderIndependent,
+}
+
 fn build_pipelines<B: Backend, T: Base3DPassDef>(
     factory: &Factory<B>,
     subpass: hal::pass::Subpass<'_, B>,
-    framebuffer_width: u32,
-    framebuffer_height: u32,
+    viewport: pso::Rect,
+    samples: u8,
     vertex_format_base: &[VertexFormat],
     vertex_format_skinned: &[VertexFormat],
     skinning: bool,
-    transparent: bool,
+    blending: Blending,
     layouts: Vec<&B::DescriptorSetLayout>,
 ) -> Result<(Vec<B::GraphicsPipeline>, B::PipelineLayout), pso::CreationError> {
+    let fragment_shader = if blending == Blending::OrderIndependent {
+        T::fragment_order_independent_shader().ok_or_else(|| {
+            log::error!(
+                "Pass {} has no shader for order-independent transparency.",
+                T::NAME
+            );
+            pso::CreationError::Other
+        })?
+    } else {
+        T::fragment_shader()
+    };
+
     let pipeline_layout = unsafe {
         factory
             .device()
@@ -799,7 +1009,7 @@ fn build_pipelines<B: Backend, T: Base3DPassDef>(
         .collect::<Vec<_>>();
 
     let shader_vertex_basic = unsafe { T::vertex_shader().module(factory).unwrap() };
-    let shader_fragment = unsafe { T::fragment_shader().module(factory).unwrap() };
+    let shader_fragment = unsafe { fragment_shader.module(factory).unwrap() };
     let pipe_desc = PipelineDescBuilder::new()
         .with_vertex_desc(&vertex_desc)
         .with_shaders(util::simple_shader_set(
@@ -808,20 +1018,62 @@ fn build_pipelines<B: Backend, T: Base3DPassDef>(
         ))
         .with_layout(&pipeline_layout)
         .with_subpass(subpass)
-        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_viewport_rect(viewport)
+        .with_multisampling(util::multisampling(samples))
         .with_face_culling(pso::Face::BACK)
         .with_depth_test(pso::DepthTest {
             fun: pso::Comparison::Greater,
-            write: !transparent,
+            write: matches!(blending, Blending::Opaque | Blending::DepthOnly),
         })
-        .with_blend_targets(vec![pso::ColorBlendDesc {
-            mask: pso::ColorMask::ALL,
-            blend: if transparent {
-                Some(pso::BlendState::PREMULTIPLIED_ALPHA)
-            } else {
-                None
-            },
-        }]);
+        .with_blend_targets(match blending {
+            Blending::Opaque => {
+                vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]
+            }
+            Blending::DepthOnly => {
+                vec![
+                    pso::ColorBlendDesc {
+                        mask: pso::ColorMask::empty(),
+                        blend: None,
+                    },
+                    pso::ColorBlendDesc {
+                        mask: pso::ColorMask::empty(),
+                        blend: None,
+                    },
+                ]
+            }
+            Blending::Transparent => {
+                vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::PREMULTIPLIED_ALPHA),
+                }]
+            }
+            Blending::OrderIndependent => {
+                vec![
+                    // Sum of the weighted colors.
+                    pso::ColorBlendDesc {
+                        mask: pso::ColorMask::ALL,
+                        blend: Some(pso::BlendState::ADD),
+                    },
+                    // Product of the transmittances `1 - alpha`.
+                    pso::ColorBlendDesc {
+                        mask: pso::ColorMask::RED,
+                        blend: Some(pso::BlendState {
+                            color: pso::BlendOp::Add {
+                                src: pso::Factor::Zero,
+                                dst: pso::Factor::OneMinusSrcColor,
+                            },
+                            alpha: pso::BlendOp::Add {
+                                src: pso::Factor::Zero,
+                                dst: pso::Factor::OneMinusSrcAlpha,
+                            },
+                        }),
+                    },
+                ]
+            }
+        });
 
     let pipelines = if skinning {
         let shader_vertex_skinned = unsafe { T::vertex_skinned_shader().module(factory).unwrap() };
@@ -0,0 +1,288 @@
+use amethyst_core::ecs::IntoQuery;
+use derivative::Derivative;
+use glsl_layout::{float, vec2, Uniform};
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    batch::OneLevelBatch,
+    billboard::{BillboardField, Wind},
+    bundle::Target,
+    pass,
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pod::{BillboardArgs, IntoPod},
+    submodules::{DynamicUniform, DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+
+#[derive(Clone, Copy, Debug, Uniform)]
+struct WindArgs {
+    direction: vec2,
+    strength: float,
+    frequency: float,
+    wavelength: float,
+}
+
+impl From<&Wind> for WindArgs {
+    fn from(wind: &Wind) -> Self {
+        let direction = if wind.direction.norm_squared() > 0.0 {
+            wind.direction.normalize()
+        } else {
+            wind.direction
+        };
+        WindArgs {
+            direction: direction.into_pod(),
+            strength: wind.strength,
+            frequency: wind.frequency,
+            wavelength: wind.wavelength,
+        }
+    }
+}
+
+/// Draw billboard fields facing the camera.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawBillboardsDesc {
+    target: Target,
+    samples: u8,
+}
+
+impl DrawBillboardsDesc {
+    /// Create instance of `DrawBillboards` render group
+    #[must_use]
+    pub fn new() -> Self {
+        pass::billboard::DrawBillboardsDesc::default()
+    }
+
+    /// Draw billboards as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawBillboardsDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = FlatEnvironmentSub::new(factory)?.with_target(self.target);
+        let textures = TextureSub::new(factory)?;
+        let wind = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_billboard_pipeline(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![env.raw_layout(), textures.raw_layout(), wind.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawBillboards::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            wind,
+            vertex,
+            billboards: OneLevelBatch::default(),
+        }))
+    }
+}
+
+/// Draws billboard fields facing the camera, in one instanced draw call per texture.
+#[derive(Debug)]
+pub struct DrawBillboards<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    wind: DynamicUniform<B, WindArgs>,
+    vertex: DynamicVertexBuffer<B, BillboardArgs>,
+    billboards: OneLevelBatch<TextureId, BillboardArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawBillboards<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+
+        self.env.process(factory, index, world, resources);
+        let wind = resources.get::<Wind>().map_or_else(
+            || WindArgs::from(&Wind::default()),
+            |wind| WindArgs::from(&*wind),
+        );
+        self.wind.write(factory, index, wind.std140());
+
+        self.billboards.clear_inner();
+        for field in <&BillboardField>::query().iter(*world) {
+            if field.placements.is_empty() {
+                continue;
+            }
+            if let Some((tex_id, _)) = self.textures.insert(
+                factory,
+                resources,
+                &field.texture,
+                hal::image::Layout::ShaderReadOnlyOptimal,
+            ) {
+                self.billboards
+                    .insert(tex_id, field.placements.iter().map(BillboardArgs::from));
+            }
+        }
+
+        self.textures.maintain(factory, resources);
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            self.billboards.prune();
+            self.vertex.write(
+                factory,
+                index,
+                self.billboards.count() as u64,
+                self.billboards.data(),
+            );
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.billboards.count() == 0 {
+            return;
+        }
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.wind.bind(index, layout, 2, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        for (&tex, range) in self.billboards.iter() {
+            if self.textures.loaded(tex) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_billboard_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::BILLBOARD_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::BILLBOARD_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(BillboardArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }])
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                }),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,267 @@
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo, Sampler,
+    },
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+
+/// Describes drawing a fullscreen triangle copying an image into the target, stretched to its
+/// size with linear filtering.
+///
+/// The source image must be the only image attached to the render group builder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawBlitDesc {
+    samples: u8,
+    encode_srgb: bool,
+}
+
+impl Default for DrawBlitDesc {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            encode_srgb: false,
+        }
+    }
+}
+
+impl DrawBlitDesc {
+    /// Create instance of `DrawBlitDesc` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Encode the copied colors to sRGB in the shader, for targets with a non-sRGB format that
+    /// would otherwise store the linear values as they are.
+    #[must_use]
+    pub fn with_encode_srgb(mut self, encode_srgb: bool) -> Self {
+        self.encode_srgb = encode_srgb;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawBlitDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::FRAGMENT_SHADER,
+        }]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .expect("Blit source image does not exist")
+            .clone();
+        let format = image.format();
+        let view = factory
+            .create_image_view(
+                image,
+                ImageViewInfo {
+                    view_kind: hal::image::ViewKind::D2,
+                    format,
+                    swizzle: hal::format::Swizzle::NO,
+                    range: hal::image::SubresourceRange {
+                        aspects: hal::format::Aspects::COLOR,
+                        levels: 0..1,
+                        layers: 0..1,
+                    },
+                },
+            )
+            .map_err(|_| pso::CreationError::Other)?;
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Linear,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| pso::CreationError::Other)?;
+
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                },
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                ),
+            )));
+        }
+
+        let (pipeline, pipeline_layout) = build_blit_pipeline(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            self.encode_srgb,
+            vec![layout.raw()],
+        )?;
+
+        Ok(Box::new(DrawBlit::<B> {
+            pipeline,
+            pipeline_layout,
+            set,
+            view,
+            sampler,
+        }))
+    }
+}
+
+/// Draws an image into the target, stretched to its size.
+#[derive(Debug)]
+pub struct DrawBlit<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    view: Escape<ImageView<B>>,
+    sampler: Handle<Sampler<B>>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawBlit<B> {
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) -> PrepareResult {
+        PrepareResult::DrawReuse
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline_layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_blit_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    encode_srgb: bool,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::FULLSCREEN_VERTEX.module(factory).unwrap() };
+    let shader_fragment = if encode_srgb {
+        unsafe { super::BLIT_SRGB_FRAGMENT.module(factory).unwrap() }
+    } else {
+        unsafe { super::BLIT_FRAGMENT.module(factory).unwrap() }
+    };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
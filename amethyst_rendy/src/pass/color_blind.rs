@@ -0,0 +1,306 @@
+use glsl_layout::{mat4, Uniform};
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, format::Format, pso},
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo, Sampler,
+    },
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    resources::ColorBlindFilter,
+    submodules::DynamicUniform,
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+
+/// Format of the color image consumed by [`DrawColorBlindFilterDesc`].
+pub const COLOR_BLIND_FILTER_FORMAT: Format = Format::Rgba8Srgb;
+
+#[derive(Clone, Copy, Debug, PartialEq, Uniform)]
+struct ColorBlindArgs {
+    top_left: mat4,
+    top_right: mat4,
+    bottom_left: mat4,
+    bottom_right: mat4,
+}
+
+/// Column major matrix of the shader from a row major 3x3 matrix.
+fn to_mat4(matrix: [[f32; 3]; 3]) -> mat4 {
+    let mut columns = [[0.0; 4]; 4];
+    for (row, values) in matrix.iter().enumerate() {
+        for (column, value) in values.iter().enumerate() {
+            columns[column][row] = *value;
+        }
+    }
+    columns[3][3] = 1.0;
+    columns.into()
+}
+
+/// Describes drawing a fullscreen triangle filtering the colors of an image into the target
+/// with the [`ColorBlindFilter`] resource.
+///
+/// The source image must be the only image attached to the render group builder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawColorBlindFilterDesc {
+    samples: u8,
+}
+
+impl Default for DrawColorBlindFilterDesc {
+    fn default() -> Self {
+        Self { samples: 1 }
+    }
+}
+
+impl DrawColorBlindFilterDesc {
+    /// Create instance of `DrawColorBlindFilterDesc` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawColorBlindFilterDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::FRAGMENT_SHADER,
+        }]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .expect("Color blind filter source image does not exist")
+            .clone();
+        let format = image.format();
+        let view = factory
+            .create_image_view(
+                image,
+                ImageViewInfo {
+                    view_kind: hal::image::ViewKind::D2,
+                    format,
+                    swizzle: hal::format::Swizzle::NO,
+                    range: hal::image::SubresourceRange {
+                        aspects: hal::format::Aspects::COLOR,
+                        levels: 0..1,
+                        layers: 0..1,
+                    },
+                },
+            )
+            .map_err(|_| pso::CreationError::Other)?;
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Nearest,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| pso::CreationError::Other)?;
+
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                },
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                ),
+            )));
+        }
+
+        let args = DynamicUniform::new(factory, pso::ShaderStageFlags::FRAGMENT)?;
+
+        let (pipeline, pipeline_layout) = build_color_blind_filter_pipeline(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![layout.raw(), args.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawColorBlindFilter::<B> {
+            pipeline,
+            pipeline_layout,
+            set,
+            view,
+            sampler,
+            args,
+        }))
+    }
+}
+
+/// Draws an image into the target, filtering its colors with the [`ColorBlindFilter`] resource.
+#[derive(Debug)]
+pub struct DrawColorBlindFilter<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    view: Escape<ImageView<B>>,
+    sampler: Handle<Sampler<B>>,
+    args: DynamicUniform<B, ColorBlindArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawColorBlindFilter<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let [top_left, top_right, bottom_left, bottom_right] = aux
+            .resources
+            .get::<ColorBlindFilter>()
+            .map(|filter| filter.quarter_matrices())
+            .unwrap_or_else(|| ColorBlindFilter::default().quarter_matrices());
+
+        let changed = self.args.write(
+            factory,
+            index,
+            ColorBlindArgs {
+                top_left: to_mat4(top_left),
+                top_right: to_mat4(top_right),
+                bottom_left: to_mat4(bottom_left),
+                bottom_right: to_mat4(bottom_right),
+            }
+            .std140(),
+        );
+
+        if changed {
+            PrepareResult::DrawRecord
+        } else {
+            PrepareResult::DrawReuse
+        }
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.args
+            .bind(index, &self.pipeline_layout, 1, &mut encoder);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline_layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_color_blind_filter_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::FULLSCREEN_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::COLOR_BLIND_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
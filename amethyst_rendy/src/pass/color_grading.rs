@@ -0,0 +1,332 @@
+use glsl_layout::{float, Uniform};
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, format::Format, pso},
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo, Sampler,
+    },
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    resources::ColorGrading,
+    submodules::{DynamicUniform, TextureId, TextureSub},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+
+/// Format of the color image consumed by [`DrawColorGradingDesc`].
+pub const COLOR_GRADING_FORMAT: Format = Format::Rgba8Srgb;
+
+#[derive(Clone, Copy, Debug, PartialEq, Uniform)]
+struct ColorGradingArgs {
+    intensity: float,
+}
+
+/// Describes drawing a fullscreen triangle mapping the colors of an image into the target using
+/// the lookup table of the [`ColorGrading`] resource.
+///
+/// The source image must be the only image attached to the render group builder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawColorGradingDesc {
+    samples: u8,
+}
+
+impl Default for DrawColorGradingDesc {
+    fn default() -> Self {
+        Self { samples: 1 }
+    }
+}
+
+impl DrawColorGradingDesc {
+    /// Create instance of `DrawColorGradingDesc` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawColorGradingDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::FRAGMENT_SHADER,
+        }]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .expect("Color grading source image does not exist")
+            .clone();
+        let format = image.format();
+        let view = factory
+            .create_image_view(
+                image,
+                ImageViewInfo {
+                    view_kind: hal::image::ViewKind::D2,
+                    format,
+                    swizzle: hal::format::Swizzle::NO,
+                    range: hal::image::SubresourceRange {
+                        aspects: hal::format::Aspects::COLOR,
+                        levels: 0..1,
+                        layers: 0..1,
+                    },
+                },
+            )
+            .map_err(|_| pso::CreationError::Other)?;
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Linear,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| pso::CreationError::Other)?;
+
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                },
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                ),
+            )));
+        }
+
+        let args = DynamicUniform::new(factory, pso::ShaderStageFlags::FRAGMENT)?;
+        let textures = TextureSub::new(factory)?;
+
+        let (grading_pipeline, blit_pipeline, pipeline_layout) = build_color_grading_pipelines(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![layout.raw(), args.raw_layout(), textures.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawColorGrading::<B> {
+            grading_pipeline,
+            blit_pipeline,
+            pipeline_layout,
+            set,
+            view,
+            sampler,
+            args,
+            textures,
+            lut: None,
+        }))
+    }
+}
+
+/// Draws an image into the target, grading its colors with the [`ColorGrading`] resource.
+#[derive(Debug)]
+pub struct DrawColorGrading<B: Backend> {
+    grading_pipeline: B::GraphicsPipeline,
+    blit_pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    view: Escape<ImageView<B>>,
+    sampler: Handle<Sampler<B>>,
+    args: DynamicUniform<B, ColorGradingArgs>,
+    textures: TextureSub<B>,
+    lut: Option<TextureId>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawColorGrading<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        self.textures.maintain(factory, aux.resources);
+
+        let grading = aux.resources.get::<ColorGrading>();
+        let (intensity, lut) = match grading.as_deref() {
+            Some(ColorGrading {
+                lut: Some(lut),
+                intensity,
+            }) => {
+                // Colors are left unchanged while the lookup table loads.
+                let id = self
+                    .textures
+                    .insert(
+                        factory,
+                        aux.resources,
+                        lut,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )
+                    .map(|(id, _)| id);
+                (*intensity, id)
+            }
+            _ => (0.0, None),
+        };
+
+        let mut changed = self.args.write(
+            factory,
+            index,
+            ColorGradingArgs {
+                intensity: intensity.max(0.0).min(1.0),
+            }
+            .std140(),
+        );
+        if self.lut != lut {
+            self.lut = lut;
+            changed = true;
+        }
+
+        if changed {
+            PrepareResult::DrawRecord
+        } else {
+            PrepareResult::DrawReuse
+        }
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        match self.lut {
+            Some(lut) if self.textures.loaded(lut) => {
+                encoder.bind_graphics_pipeline(&self.grading_pipeline);
+                self.args
+                    .bind(index, &self.pipeline_layout, 1, &mut encoder);
+                self.textures
+                    .bind(&self.pipeline_layout, 2, lut, &mut encoder);
+            }
+            _ => encoder.bind_graphics_pipeline(&self.blit_pipeline),
+        }
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline_layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.grading_pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.blit_pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_color_grading_pipelines<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::FULLSCREEN_VERTEX.module(factory).unwrap() };
+    let shader_grading = unsafe { super::COLOR_GRADING_FRAGMENT.module(factory).unwrap() };
+    let shader_blit = unsafe { super::BLIT_FRAGMENT.module(factory).unwrap() };
+
+    let pipe_desc = PipelineDescBuilder::new()
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_multisampling(util::multisampling(samples))
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: None,
+        }]);
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipe_desc.clone().with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_grading),
+        )))
+        .with_pipeline(
+            pipe_desc.with_shaders(util::simple_shader_set(&shader_vertex, Some(&shader_blit))),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_grading);
+        factory.destroy_shader_module(shader_blit);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipes.remove(0), pipeline_layout)),
+    }
+}
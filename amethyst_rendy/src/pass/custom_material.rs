@@ -0,0 +1,458 @@
+use amethyst_assets::{AssetHandle, AssetStorage, Handle, LoadHandle};
+use amethyst_core::{ecs::IntoQuery, transform::Transform};
+use derivative::Derivative;
+use fnv::FnvHashMap;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    memory::Write as _,
+    mesh::{AsVertex, Normal, Position, TexCoord, VertexFormat},
+    resource::{
+        Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle,
+    },
+    shader::Shader,
+};
+use smallvec::SmallVec;
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    batch::{GroupIterator, TwoLevelBatch},
+    bundle::Target,
+    camera::Viewport,
+    custom_material::{CustomMaterial, MaterialParams},
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pod::{CustomMaterialArgs, VertexArgs},
+    resources::Tint,
+    submodules::{DynamicVertexBuffer, EnvironmentSub},
+    system::GraphAuxData,
+    types::{Backend, Mesh},
+    util,
+    visibility::Visibility,
+};
+
+/// Describes drawing meshes with [`CustomMaterial`]s.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawCustomMaterialDesc {
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+}
+
+impl DrawCustomMaterialDesc {
+    /// Create instance of `DrawCustomMaterialDesc` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Draw meshes as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw meshes as seen by the camera of the viewport with given index, limited to its region
+    /// of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawCustomMaterialDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = EnvironmentSub::new(
+            factory,
+            [
+                pso::ShaderStageFlags::VERTEX,
+                pso::ShaderStageFlags::FRAGMENT,
+            ],
+        )?
+        .with_target(self.target)
+        .with_viewport(self.viewport);
+
+        let material_layout: RendyHandle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::Buffer {
+                    ty: pso::BufferDescriptorType::Uniform,
+                    format: pso::BufferDescriptorFormat::Structured {
+                        dynamic_offset: false,
+                    },
+                },
+                pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+
+        let pipeline_layout = unsafe {
+            factory.device().create_pipeline_layout(
+                vec![env.raw_layout(), material_layout.raw()],
+                None as Option<(_, _)>,
+            )
+        }?;
+
+        Ok(Box::new(DrawCustomMaterial::<B> {
+            pipeline_layout,
+            material_layout,
+            materials: FnvHashMap::default(),
+            batches: TwoLevelBatch::default(),
+            vertex_format: vec![Position::vertex(), Normal::vertex(), TexCoord::vertex()],
+            env,
+            models: DynamicVertexBuffer::new(),
+            viewport: Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            samples: self.samples,
+        }))
+    }
+}
+
+/// Draws meshes with [`CustomMaterial`]s, rebuilding the pipeline of a material when it's
+/// reloaded.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawCustomMaterial<B: Backend> {
+    pipeline_layout: B::PipelineLayout,
+    material_layout: RendyHandle<DescriptorSetLayout<B>>,
+    materials: FnvHashMap<LoadHandle, MaterialPipeline<B>>,
+    batches: TwoLevelBatch<LoadHandle, LoadHandle, SmallVec<[CustomMaterialArgs; 4]>>,
+    vertex_format: Vec<VertexFormat>,
+    env: EnvironmentSub<B>,
+    models: DynamicVertexBuffer<B, CustomMaterialArgs>,
+    viewport: pso::Rect,
+    samples: u8,
+}
+
+/// Pipeline and uniforms of a loaded material version.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct MaterialPipeline<B: Backend> {
+    version: u32,
+    pipeline: Option<B::GraphicsPipeline>,
+    buffer: Option<Escape<Buffer<B>>>,
+    set: Escape<DescriptorSet<B>>,
+}
+
+impl<B: Backend> DrawCustomMaterial<B> {
+    /// Builds the pipeline of a material if it's new or its version changed since last frame.
+    ///
+    /// A version that fails to build keeps drawing with the previous pipeline.
+    fn maintain_material(
+        &mut self,
+        factory: &Factory<B>,
+        subpass: hal::pass::Subpass<'_, B>,
+        handle: &Handle<CustomMaterial>,
+        storage: &AssetStorage<CustomMaterial>,
+    ) -> Option<LoadHandle> {
+        let (material, version) = storage.get_asset_with_version(handle)?;
+        let load_handle = handle.load_handle();
+
+        if !self.materials.contains_key(&load_handle) {
+            let set = factory
+                .create_descriptor_set(self.material_layout.clone())
+                .ok()?;
+            self.materials.insert(
+                load_handle,
+                MaterialPipeline {
+                    version,
+                    pipeline: None,
+                    buffer: None,
+                    set,
+                },
+            );
+        } else if self.materials[&load_handle].version == version {
+            return Some(load_handle);
+        }
+
+        let pipeline = match build_custom_material_pipeline(
+            factory,
+            subpass,
+            self.viewport,
+            self.samples,
+            &self.vertex_format,
+            &self.pipeline_layout,
+            material,
+        ) {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                log::error!("Failed to build custom material pipeline: {:?}", e);
+                self.materials.get_mut(&load_handle).unwrap().version = version;
+                return Some(load_handle);
+            }
+        };
+
+        let entry = self.materials.get_mut(&load_handle).unwrap();
+        if entry.pipeline.is_some() {
+            // Frames in flight may still use the previous version of the material.
+            factory.device().wait_idle().unwrap();
+        }
+        if let Some(previous) = entry.pipeline.replace(pipeline) {
+            unsafe {
+                factory.device().destroy_graphics_pipeline(previous);
+            }
+        }
+        entry.version = version;
+
+        let data = material.uniform_data();
+        let size = data.len() as u64;
+        let mut buffer = factory
+            .create_buffer(
+                BufferInfo {
+                    size,
+                    usage: hal::buffer::Usage::UNIFORM,
+                },
+                rendy::memory::Dynamic,
+            )
+            .unwrap();
+        unsafe {
+            let mut mapped = buffer.map(factory.device(), 0..size).unwrap();
+            let mut writer = mapped.write::<u8>(factory.device(), 0..size).unwrap();
+            writer.slice().copy_from_slice(&data);
+        }
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                entry.set.raw(),
+                0,
+                pso::Descriptor::Buffer(buffer.raw(), rendy::resource::SubRange::WHOLE),
+            )));
+        }
+        entry.buffer = Some(buffer);
+
+        Some(load_handle)
+    }
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawCustomMaterial<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+
+        let visibility = resources.get::<Visibility>().unwrap();
+        let mesh_storage = resources.get::<AssetStorage<Mesh>>().unwrap();
+        let material_storage = resources.get::<AssetStorage<CustomMaterial>>().unwrap();
+
+        self.env.process(factory, index, world, resources);
+        self.batches.clear_inner();
+
+        let mut grouped = Vec::new();
+        let mut query = <(
+            &Handle<CustomMaterial>,
+            &Handle<Mesh>,
+            &Transform,
+            Option<&Tint>,
+            Option<&MaterialParams>,
+        )>::query();
+        visibility
+            .visible_unordered
+            .iter()
+            .filter_map(|entity| query.get(*world, *entity).ok())
+            .filter_map(|(material, mesh, tform, tint, params)| {
+                let params = material_storage.get(material)?.param_data(params);
+                Some((
+                    (material, mesh.load_handle()),
+                    CustomMaterialArgs::new(VertexArgs::from_object_data(tform, tint), params),
+                ))
+            })
+            .for_each_group(|(material, mesh_id), data| {
+                if mesh_storage.contains(mesh_id) {
+                    grouped.push((
+                        material.clone(),
+                        mesh_id,
+                        data.drain(..).collect::<Vec<_>>(),
+                    ));
+                }
+            });
+
+        for (material, mesh_id, data) in grouped {
+            if let Some(material_id) =
+                self.maintain_material(factory, subpass, &material, &material_storage)
+            {
+                if self.materials[&material_id].pipeline.is_some() {
+                    self.batches.insert(material_id, mesh_id, data);
+                }
+            }
+        }
+
+        self.batches.prune();
+        self.models.write(
+            factory,
+            index,
+            self.batches.count() as u64,
+            self.batches.data(),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let mesh_storage = aux.resources.get::<AssetStorage<Mesh>>().unwrap();
+        let models_loc = self.vertex_format.len() as u32;
+
+        if !self.models.bind(index, models_loc, 0, &mut encoder) {
+            return;
+        }
+
+        let mut instances_drawn = 0;
+        for (material_id, batches) in self.batches.iter() {
+            let material = &self.materials[material_id];
+            let pipeline = material
+                .pipeline
+                .as_ref()
+                .expect("Batched custom material has no pipeline");
+
+            encoder.bind_graphics_pipeline(pipeline);
+            self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+            unsafe {
+                encoder.bind_graphics_descriptor_sets(
+                    &self.pipeline_layout,
+                    1,
+                    Some(material.set.raw()),
+                    std::iter::empty(),
+                );
+            }
+
+            for (mesh_id, batch_data) in batches {
+                if let Some(mesh) = B::unwrap_mesh({
+                    mesh_storage
+                        .get_for_load_handle(*mesh_id)
+                        .expect("Could not get mesh.")
+                }) {
+                    mesh.bind_and_draw(
+                        0,
+                        &self.vertex_format,
+                        instances_drawn..instances_drawn + batch_data.len() as u32,
+                        &mut encoder,
+                    )
+                    .unwrap();
+                }
+                instances_drawn += batch_data.len() as u32;
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            for (_, material) in self.materials {
+                if let Some(pipeline) = material.pipeline {
+                    factory.device().destroy_graphics_pipeline(pipeline);
+                }
+            }
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_custom_material_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    viewport: pso::Rect,
+    samples: u8,
+    vertex_format: &[VertexFormat],
+    pipeline_layout: &B::PipelineLayout,
+    material: &CustomMaterial,
+) -> Result<B::GraphicsPipeline, pso::CreationError> {
+    let vertex_desc = vertex_format
+        .iter()
+        .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
+        .chain(Some((
+            CustomMaterialArgs::vertex(),
+            pso::VertexInputRate::Instance(1),
+        )))
+        .collect::<Vec<_>>();
+
+    let shader_vertex =
+        unsafe { material.vertex().module(factory) }.map_err(|_| pso::CreationError::Other)?;
+    let shader_fragment = match unsafe { material.fragment().module(factory) } {
+        Ok(module) => module,
+        Err(_) => {
+            unsafe {
+                factory.destroy_shader_module(shader_vertex);
+            }
+            return Err(pso::CreationError::Other);
+        }
+    };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&vertex_desc)
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(pipeline_layout)
+                .with_subpass(subpass)
+                .with_viewport_rect(viewport)
+                .with_multisampling(util::multisampling(samples))
+                .with_face_culling(pso::Face::BACK)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                })
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    pipes.map(|mut pipes| pipes.remove(0))
+}
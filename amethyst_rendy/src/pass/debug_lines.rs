@@ -1,6 +1,6 @@
 use amethyst_core::ecs::{IntoQuery, Read};
 use derivative::Derivative;
-use glsl_layout::{vec2, Uniform};
+use glsl_layout::{float, vec2, Uniform};
 use rendy::{
     command::{QueueId, RenderPassEncoder},
     factory::Factory,
@@ -16,6 +16,8 @@ use rendy::{
 use thread_profiler::profile_scope;
 
 use crate::{
+    bundle::Target,
+    camera::Viewport,
     debug_drawing::{DebugLine, DebugLines, DebugLinesComponent, DebugLinesParams},
     pass,
     pipeline::{PipelineDescBuilder, PipelinesBuilder},
@@ -28,13 +30,22 @@ use crate::{
 
 #[derive(Debug, Clone, Copy, Uniform)]
 struct DebugLinesArgs {
-    screen_space_thickness: vec2,
+    /// Size of a pixel in normalized device coordinates
+    pixel_size: vec2,
+    line_width: float,
+    /// Width in pixels of the smoothed edges of the lines
+    anti_aliasing: float,
 }
 
 /// Draw opaque sprites without lighting.
 #[derive(Clone, Debug, PartialEq, Derivative)]
 #[derivative(Default(bound = ""))]
-pub struct DrawDebugLinesDesc;
+pub struct DrawDebugLinesDesc {
+    target: Target,
+    viewport: Option<usize>,
+    keep_lines: bool,
+    samples: u8,
+}
 
 impl DrawDebugLinesDesc {
     /// Create instance of `DrawDebugLines` render group
@@ -42,6 +53,36 @@ impl DrawDebugLinesDesc {
     pub fn new() -> Self {
         pass::debug_lines::DrawDebugLinesDesc::default()
     }
+
+    /// Draw the lines as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the lines as seen by the camera of the viewport with given index, limited to its
+    /// region of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Leave the lines of the [`DebugLines`] resource to a render group prepared after this one,
+    /// e.g. drawing them into another viewport. The last one clears them.
+    #[must_use]
+    pub fn keep_lines(mut self) -> Self {
+        self.keep_lines = true;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawDebugLinesDesc {
@@ -50,7 +91,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawDebugLinesDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _aux: &GraphAuxData,
+        aux: &GraphAuxData,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -61,14 +102,23 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawDebugLinesDesc {
         profile_scope!("build");
 
         let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
-        let args = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let args = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
         let vertex = DynamicVertexBuffer::new();
+        let viewport = Viewport::pixel_rect_of(
+            aux.world,
+            self.viewport,
+            framebuffer_width,
+            framebuffer_height,
+        );
 
         let (pipeline, pipeline_layout) = build_lines_pipeline(
             factory,
             subpass,
-            framebuffer_width,
-            framebuffer_height,
+            viewport,
+            self.samples,
             vec![env.raw_layout(), args.raw_layout()],
         )?;
 
@@ -78,8 +128,11 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawDebugLinesDesc {
             env,
             args,
             vertex,
-            framebuffer_width: framebuffer_width as f32,
-            framebuffer_height: framebuffer_height as f32,
+            target: self.target,
+            viewport: self.viewport,
+            keep_lines: self.keep_lines,
+            viewport_width: f32::from(viewport.w),
+            viewport_height: f32::from(viewport.h),
             lines: Vec::new(),
             change: util::ChangeDetection::default(),
         }))
@@ -94,8 +147,11 @@ pub struct DrawDebugLines<B: Backend> {
     env: DynamicUniform<B, ViewArgs>,
     args: DynamicUniform<B, DebugLinesArgs>,
     vertex: DynamicVertexBuffer<B, DebugLine>,
-    framebuffer_width: f32,
-    framebuffer_height: f32,
+    target: Target,
+    viewport: Option<usize>,
+    keep_lines: bool,
+    viewport_width: f32,
+    viewport_height: f32,
     lines: Vec<DebugLine>,
     change: util::ChangeDetection,
 }
@@ -120,25 +176,27 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawDebugLines<B> {
             self.lines.extend_from_slice(lines_component.lines());
         }
 
-        if let Some(mut lines_res) = resources.get_mut::<DebugLines>() {
+        if self.keep_lines {
+            if let Some(lines_res) = resources.get::<DebugLines>() {
+                self.lines.extend_from_slice(lines_res.lines());
+            }
+        } else if let Some(mut lines_res) = resources.get_mut::<DebugLines>() {
             self.lines.extend(lines_res.drain());
         };
 
-        let cam = CameraGatherer::gather(world, resources);
-        let line_width = resources
+        let cam = CameraGatherer::gather_for_viewport(world, resources, self.target, self.viewport);
+        let params = resources
             .get::<DebugLinesParams>()
-            .map_or(DebugLinesParams::default().line_width, |p| p.line_width);
+            .map_or_else(DebugLinesParams::default, |p| *p);
 
         self.env.write(factory, index, cam.projview);
         self.args.write(
             factory,
             index,
             DebugLinesArgs {
-                screen_space_thickness: [
-                    (line_width * 2.0) / self.framebuffer_width,
-                    (line_width * 2.0) / self.framebuffer_height,
-                ]
-                .into(),
+                pixel_size: [2.0 / self.viewport_width, 2.0 / self.viewport_height].into(),
+                line_width: params.line_width,
+                anti_aliasing: if params.anti_aliasing { 1.0 } else { 0.0 },
             }
             .std140(),
         );
@@ -191,8 +249,8 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawDebugLines<B> {
 fn build_lines_pipeline<B: Backend>(
     factory: &Factory<B>,
     subpass: hal::pass::Subpass<'_, B>,
-    framebuffer_width: u32,
-    framebuffer_height: u32,
+    viewport: pso::Rect,
+    samples: u8,
     layouts: Vec<&B::DescriptorSetLayout>,
 ) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
     let pipeline_layout = unsafe {
@@ -215,7 +273,8 @@ fn build_lines_pipeline<B: Backend>(
                 ))
                 .with_layout(&pipeline_layout)
                 .with_subpass(subpass)
-                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_viewport_rect(viewport)
+                .with_multisampling(util::multisampling(samples))
                 .with_blend_targets(vec![pso::ColorBlendDesc {
                     mask: pso::ColorMask::ALL,
                     blend: Some(pso::BlendState::ALPHA),
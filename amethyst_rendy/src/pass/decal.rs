@@ -0,0 +1,346 @@
+use amethyst_core::{
+    ecs::{component, IntoQuery},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo, Sampler,
+    },
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    bundle::Target,
+    decal::Decal,
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pod::DecalArgs,
+    submodules::{DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+
+/// Number of vertices of the box a decal is drawn with.
+const BOX_VERTICES: u32 = 36;
+
+/// Describes drawing [`Decal`]s projected onto the scene using its depth image.
+///
+/// The depth image of the scene must be the only image attached to the render group builder, and
+/// must not be multisampled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawDecalDesc {
+    target: Target,
+    samples: u8,
+}
+
+impl Default for DrawDecalDesc {
+    fn default() -> Self {
+        Self {
+            target: Target::default(),
+            samples: 1,
+        }
+    }
+}
+
+impl DrawDecalDesc {
+    /// Create instance of `DrawDecalDesc` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Project decals as seen by the camera of given render target, which should be the target
+    /// the depth image comes from.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawDecalDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::FRAGMENT_SHADER,
+        }]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .expect("Decal depth image does not exist")
+            .clone();
+        let format = image.format();
+        let view = factory
+            .create_image_view(
+                image,
+                ImageViewInfo {
+                    view_kind: hal::image::ViewKind::D2,
+                    format,
+                    swizzle: hal::format::Swizzle::NO,
+                    range: hal::image::SubresourceRange {
+                        aspects: hal::format::Aspects::DEPTH,
+                        levels: 0..1,
+                        layers: 0..1,
+                    },
+                },
+            )
+            .map_err(|_| pso::CreationError::Other)?;
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Nearest,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| pso::CreationError::Other)?;
+
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                },
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                ),
+            )));
+        }
+
+        let env = FlatEnvironmentSub::new(factory)?.with_target(self.target);
+        let textures = TextureSub::new(factory)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_decal_pipeline(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![env.raw_layout(), textures.raw_layout(), layout.raw()],
+        )?;
+
+        Ok(Box::new(DrawDecal::<B> {
+            pipeline,
+            pipeline_layout,
+            set,
+            view,
+            sampler,
+            env,
+            textures,
+            vertex,
+            decals: OneLevelBatch::default(),
+        }))
+    }
+}
+
+/// Draws [`Decal`]s projected onto the scene using its depth image.
+#[derive(Debug)]
+pub struct DrawDecal<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    view: Escape<ImageView<B>>,
+    sampler: Handle<Sampler<B>>,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, DecalArgs>,
+    decals: OneLevelBatch<TextureId, DecalArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawDecal<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+
+        self.env.process(factory, index, world, resources);
+
+        let decals_ref = &mut self.decals;
+        let textures_ref = &mut self.textures;
+
+        decals_ref.clear_inner();
+
+        <(&Decal, &Transform)>::query()
+            .filter(!component::<Hidden>() & !component::<HiddenPropagate>())
+            .iter(*world)
+            .filter_map(|(decal, transform)| {
+                let opacity = decal.opacity();
+                if opacity <= 0.0 {
+                    return None;
+                }
+                let [r, g, b, a] = decal.color;
+                let args = DecalArgs::from_object_data(transform, [r, g, b, a * opacity]);
+
+                let (tex_id, _) = textures_ref.insert(
+                    factory,
+                    resources,
+                    &decal.texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                )?;
+                Some((tex_id, args))
+            })
+            .for_each_group(|tex_id, batch_data| {
+                decals_ref.insert(tex_id, batch_data.drain(..));
+            });
+
+        self.textures.maintain(factory, resources);
+
+        self.decals.prune();
+        self.vertex.write(
+            factory,
+            index,
+            self.decals.count() as u64,
+            self.decals.data(),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                layout,
+                2,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+        }
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        for (&tex, range) in self.decals.iter() {
+            if self.textures.loaded(tex) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                unsafe {
+                    encoder.draw(0..BOX_VERTICES, range);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_decal_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::DECAL_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::DECAL_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(DecalArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleList))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
+                // Drawing the back faces keeps decals visible with the camera inside their box.
+                .with_face_culling(pso::Face::FRONT)
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::ALPHA),
+                }]),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -23,6 +23,9 @@ impl Base3DPassDef for FlatPassDef {
     fn fragment_shader() -> &'static SpirvShader {
         &super::FLAT_FRAGMENT
     }
+    fn fragment_order_independent_shader() -> Option<&'static SpirvShader> {
+        Some(&super::FLAT_OIT_FRAGMENT)
+    }
     fn base_format() -> Vec<VertexFormat> {
         vec![Position::vertex(), TexCoord::vertex()]
     }
@@ -15,17 +15,20 @@ use rendy::{
     mesh::AsVertex,
     shader::Shader,
 };
+use smallvec::{smallvec, SmallVec};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
 use crate::{
     batch,
     batch::{GroupIterator, OneLevelBatch, OrderedOneLevelBatch},
+    bundle::Target,
+    camera::Viewport,
     pass,
     pipeline::{PipelineDescBuilder, PipelinesBuilder},
     pod::SpriteArgs,
     resources::Tint,
-    sprite::{SpriteRender, SpriteSheet, Sprites},
+    sprite::{NineSlice, Sprite, SpriteRender, SpriteSheet, Sprites},
     sprite_visibility::SpriteVisibility,
     submodules::{DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
     system::GraphAuxData,
@@ -36,7 +39,11 @@ use crate::{
 /// Draw opaque sprites without lighting.
 #[derive(Clone, Debug, PartialEq, Derivative)]
 #[derivative(Default(bound = ""))]
-pub struct DrawFlat2DDesc;
+pub struct DrawFlat2DDesc {
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+}
 
 impl DrawFlat2DDesc {
     /// Create instance of `DrawFlat2D` render group
@@ -44,6 +51,28 @@ impl DrawFlat2DDesc {
     pub fn new() -> Self {
         pass::flat2d::DrawFlat2DDesc::default()
     }
+
+    /// Draw opaque sprites as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw opaque sprites as seen by the camera of the viewport with given index, limited to
+    /// its region of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DDesc {
@@ -52,7 +81,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _aux: &GraphAuxData,
+        aux: &GraphAuxData,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -62,15 +91,22 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DDesc {
         #[cfg(feature = "profiler")]
         profile_scope!("build");
 
-        let env = FlatEnvironmentSub::new(factory)?;
+        let env = FlatEnvironmentSub::new(factory)?
+            .with_target(self.target)
+            .with_viewport(self.viewport);
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
         let (pipeline, pipeline_layout) = build_sprite_pipeline(
             factory,
             subpass,
-            framebuffer_width,
-            framebuffer_height,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
             false,
             vec![env.raw_layout(), textures.raw_layout()],
         )?;
@@ -130,19 +166,20 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2D<B> {
             #[cfg(feature = "profiler")]
             profile_scope!("gather_visibility");
 
-            let mut query = <(&SpriteRender, &Transform, Option<&Tint>)>::query();
+            let mut query =
+                <(&SpriteRender, &Transform, Option<&Tint>, Option<&NineSlice>)>::query();
 
             visibility
                 .visible_unordered
                 .iter()
                 .filter_map(|entity| Some((entity, query.get(*world, *entity).ok()?)))
-                .filter_map(|(entity, (sprite_render, global, tint))| {
+                .filter_map(|(entity, (sprite_render, global, tint, nine_slice))| {
                     let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
                     let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
 
                     let sprite = &sprites[sprite_render.sprite_number];
 
-                    let batch_data = SpriteArgs::from_data(sprite, global, tint);
+                    let batch_data = sprite_args(sprite, nine_slice, global, tint);
 
                     let (tex_id, _) = textures_ref.insert(
                         factory,
@@ -152,6 +189,9 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2D<B> {
                     )?;
                     Some((tex_id, batch_data))
                 })
+                .flat_map(|(tex_id, batch_data)| {
+                    batch_data.into_iter().map(move |args| (tex_id, args))
+                })
                 .for_each_group(|tex_id, batch_data| {
                     sprites_ref.insert(tex_id, batch_data.drain(..));
                 });
@@ -211,7 +251,11 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2D<B> {
 /// Describes drawing transparent sprites without lighting.
 #[derive(Clone, Debug, PartialEq, Derivative)]
 #[derivative(Default(bound = ""))]
-pub struct DrawFlat2DTransparentDesc;
+pub struct DrawFlat2DTransparentDesc {
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+}
 
 impl DrawFlat2DTransparentDesc {
     /// Create instance of `DrawFlat2D` render group
@@ -219,6 +263,28 @@ impl DrawFlat2DTransparentDesc {
     pub fn new() -> Self {
         pass::flat2d::DrawFlat2DTransparentDesc::default()
     }
+
+    /// Draw transparent sprites as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw transparent sprites as seen by the camera of the viewport with given index, limited to
+    /// its region of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DTransparentDesc {
@@ -227,7 +293,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DTransparentDesc
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _aux: &GraphAuxData,
+        aux: &GraphAuxData,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -237,15 +303,22 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DTransparentDesc
         #[cfg(feature = "profiler")]
         profile_scope!("build_trans");
 
-        let env = FlatEnvironmentSub::new(factory)?;
+        let env = FlatEnvironmentSub::new(factory)?
+            .with_target(self.target)
+            .with_viewport(self.viewport);
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
         let (pipeline, pipeline_layout) = build_sprite_pipeline(
             factory,
             subpass,
-            framebuffer_width,
-            framebuffer_height,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
             true,
             vec![env.raw_layout(), textures.raw_layout()],
         )?;
@@ -256,6 +329,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DTransparentDesc
             env,
             textures,
             vertex,
+            viewport: self.viewport,
             sprites: batch::OrderedOneLevelBatch::default(),
             change: util::ChangeDetection::default(),
         }))
@@ -270,6 +344,7 @@ pub struct DrawFlat2DTransparent<B: Backend> {
     env: FlatEnvironmentSub<B>,
     textures: TextureSub<B>,
     vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    viewport: Option<usize>,
     sprites: OrderedOneLevelBatch<TextureId, SpriteArgs>,
     change: util::ChangeDetection,
 }
@@ -308,19 +383,20 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2DTransparent<B> {
             #[cfg(feature = "profiler")]
             profile_scope!("gather_visibility");
 
-            let mut query = <(&SpriteRender, &Transform, Option<&Tint>)>::query();
+            let mut query =
+                <(&SpriteRender, &Transform, Option<&Tint>, Option<&NineSlice>)>::query();
 
             visibility
-                .visible_ordered
+                .ordered(self.viewport)
                 .iter()
                 .filter_map(|entity| Some((entity, query.get(*world, *entity).ok()?)))
-                .filter_map(|(entity, (sprite_render, global, tint))| {
+                .filter_map(|(entity, (sprite_render, global, tint, nine_slice))| {
                     let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
                     let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
 
                     let sprite = &sprites[sprite_render.sprite_number];
 
-                    let batch_data = SpriteArgs::from_data(sprite, global, tint);
+                    let batch_data = sprite_args(sprite, nine_slice, global, tint);
 
                     let (tex_id, this_changed) = textures_ref.insert(
                         factory,
@@ -332,6 +408,9 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2DTransparent<B> {
                     changed = changed || this_changed;
                     Some((tex_id, batch_data))
                 })
+                .flat_map(|(tex_id, batch_data)| {
+                    batch_data.into_iter().map(move |args| (tex_id, args))
+                })
                 .for_each_group(|tex_id, batch_data| {
                     sprites_ref.insert(tex_id, batch_data.drain(..));
                 });
@@ -392,8 +471,8 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2DTransparent<B> {
 fn build_sprite_pipeline<B: Backend>(
     factory: &Factory<B>,
     subpass: hal::pass::Subpass<'_, B>,
-    framebuffer_width: u32,
-    framebuffer_height: u32,
+    viewport: pso::Rect,
+    samples: u8,
     transparent: bool,
     layouts: Vec<&B::DescriptorSetLayout>,
 ) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
@@ -417,7 +496,8 @@ fn build_sprite_pipeline<B: Backend>(
                 ))
                 .with_layout(&pipeline_layout)
                 .with_subpass(subpass)
-                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_viewport_rect(viewport)
+                .with_multisampling(util::multisampling(samples))
                 .with_blend_targets(vec![pso::ColorBlendDesc {
                     mask: pso::ColorMask::ALL,
                     blend: if transparent {
@@ -448,3 +528,22 @@ fn build_sprite_pipeline<B: Backend>(
         Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
     }
 }
+
+/// Vertex data of a sprite, split into nine slices when the entity has a `NineSlice`.
+pub(super) fn sprite_args(
+    sprite: &Sprite,
+    nine_slice: Option<&NineSlice>,
+    transform: &Transform,
+    tint: Option<&Tint>,
+) -> SmallVec<[SpriteArgs; 9]> {
+    match nine_slice {
+        Some(nine_slice) => {
+            nine_slice
+                .slice(sprite)
+                .iter()
+                .map(|slice| SpriteArgs::from_data(slice, transform, tint))
+                .collect()
+        }
+        None => smallvec![SpriteArgs::from_data(sprite, transform, tint)],
+    }
+}
@@ -0,0 +1,581 @@
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{systems::ResourceSet, IntoQuery, Read, Resources},
+    transform::Transform,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use super::flat2d::sprite_args;
+use crate::{
+    batch::{GroupIterator, OneLevelBatch, OrderedOneLevelBatch},
+    bundle::Target,
+    camera::Viewport,
+    light2d::SpriteNormalMap,
+    mtl::MaterialDefaults,
+    pass,
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pod::SpriteArgs,
+    resources::Tint,
+    sprite::{NineSlice, SpriteRender, SpriteSheet, Sprites},
+    sprite_visibility::SpriteVisibility,
+    submodules::{DynamicVertexBuffer, FlatEnvironmentSub, Light2DSub, TextureId, TextureSub},
+    system::GraphAuxData,
+    types::{Backend, Texture},
+    util,
+};
+
+/// Albedo and normal map textures of a batch of sprites.
+type LitTextures = (TextureId, TextureId);
+
+/// Draw opaque sprites lit by 2D lights.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawFlat2DLitDesc {
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+}
+
+impl DrawFlat2DLitDesc {
+    /// Create instance of `DrawFlat2DLit` render group
+    #[must_use]
+    pub fn new() -> Self {
+        pass::flat2d_lit::DrawFlat2DLitDesc::default()
+    }
+
+    /// Draw opaque sprites as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw opaque sprites as seen by the camera of the viewport with given index, limited to
+    /// its region of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DLitDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = FlatEnvironmentSub::new(factory)?
+            .with_target(self.target)
+            .with_viewport(self.viewport);
+        let textures = TextureSub::new(factory)?;
+        let lights = Light2DSub::new(factory)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_lit_sprite_pipeline(
+            factory,
+            subpass,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
+            false,
+            vec![
+                env.raw_layout(),
+                textures.raw_layout(),
+                textures.raw_layout(),
+                lights.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawFlat2DLit::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            lights,
+            vertex,
+            sprites: OneLevelBatch::default(),
+        }))
+    }
+}
+
+/// Draws opaque 2D sprites lit by 2D lights.
+#[derive(Debug)]
+pub struct DrawFlat2DLit<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    lights: Light2DSub<B>,
+    vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    sprites: OneLevelBatch<LitTextures, SpriteArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2DLit<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare opaque");
+
+        let GraphAuxData { world, resources } = aux;
+
+        let (sprite_sheet_storage, sprites_storage, visibility, defaults) =
+            <(
+                Read<AssetStorage<SpriteSheet>>,
+                Read<AssetStorage<Sprites>>,
+                Read<SpriteVisibility>,
+                Read<MaterialDefaults>,
+            )>::fetch(resources);
+
+        self.env.process(factory, index, world, resources);
+        self.lights.process(factory, index, world, resources);
+
+        let sprites_ref = &mut self.sprites;
+        let textures_ref = &mut self.textures;
+
+        sprites_ref.clear_inner();
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("gather_visibility");
+
+            let mut query = <(
+                &SpriteRender,
+                &Transform,
+                Option<&Tint>,
+                Option<&NineSlice>,
+                Option<&SpriteNormalMap>,
+            )>::query();
+
+            visibility
+                .visible_unordered
+                .iter()
+                .filter_map(|entity| query.get(*world, *entity).ok())
+                .filter_map(|(sprite_render, global, tint, nine_slice, normal_map)| {
+                    let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
+                    let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
+                    let sprite = &sprites[sprite_render.sprite_number];
+
+                    let batch_data = sprite_args(sprite, nine_slice, global, tint);
+                    let normal = normal_map.map_or(&defaults.0.normal, |map| &map.0);
+                    let (textures, _) = insert_textures(
+                        textures_ref,
+                        factory,
+                        resources,
+                        &sprite_sheet.texture,
+                        normal,
+                    )?;
+                    Some((textures, batch_data))
+                })
+                .flat_map(|(textures, batch_data)| {
+                    batch_data.into_iter().map(move |args| (textures, args))
+                })
+                .for_each_group(|textures, batch_data| {
+                    sprites_ref.insert(textures, batch_data.drain(..));
+                });
+        }
+
+        self.textures.maintain(factory, resources);
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            sprites_ref.prune();
+            self.vertex.write(
+                factory,
+                index,
+                self.sprites.count() as u64,
+                self.sprites.data(),
+            );
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw opaque");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.lights.bind(index, layout, 3, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        for (&(albedo, normal), range) in self.sprites.iter() {
+            if self.textures.loaded(albedo) && self.textures.loaded(normal) {
+                self.textures.bind(layout, 1, albedo, &mut encoder);
+                self.textures.bind(layout, 2, normal, &mut encoder);
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Describes drawing transparent sprites lit by 2D lights.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawFlat2DLitTransparentDesc {
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+}
+
+impl DrawFlat2DLitTransparentDesc {
+    /// Create instance of `DrawFlat2DLitTransparent` render group
+    #[must_use]
+    pub fn new() -> Self {
+        pass::flat2d_lit::DrawFlat2DLitTransparentDesc::default()
+    }
+
+    /// Draw transparent sprites as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw transparent sprites as seen by the camera of the viewport with given index, limited
+    /// to its region of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawFlat2DLitTransparentDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build_trans");
+
+        let env = FlatEnvironmentSub::new(factory)?
+            .with_target(self.target)
+            .with_viewport(self.viewport);
+        let textures = TextureSub::new(factory)?;
+        let lights = Light2DSub::new(factory)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_lit_sprite_pipeline(
+            factory,
+            subpass,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
+            true,
+            vec![
+                env.raw_layout(),
+                textures.raw_layout(),
+                textures.raw_layout(),
+                lights.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawFlat2DLitTransparent::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            lights,
+            vertex,
+            viewport: self.viewport,
+            sprites: OrderedOneLevelBatch::default(),
+            change: util::ChangeDetection::default(),
+        }))
+    }
+}
+
+/// Draws transparent sprites lit by 2D lights.
+#[derive(Debug)]
+pub struct DrawFlat2DLitTransparent<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    lights: Light2DSub<B>,
+    vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    viewport: Option<usize>,
+    sprites: OrderedOneLevelBatch<LitTextures, SpriteArgs>,
+    change: util::ChangeDetection,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawFlat2DLitTransparent<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare transparent");
+
+        let GraphAuxData { world, resources } = aux;
+
+        let (sprite_sheet_storage, sprites_storage, visibility, defaults) =
+            <(
+                Read<AssetStorage<SpriteSheet>>,
+                Read<AssetStorage<Sprites>>,
+                Read<SpriteVisibility>,
+                Read<MaterialDefaults>,
+            )>::fetch(resources);
+
+        self.env.process(factory, index, world, resources);
+        let mut changed = self.lights.process(factory, index, world, resources);
+
+        let sprites_ref = &mut self.sprites;
+        let textures_ref = &mut self.textures;
+
+        sprites_ref.swap_clear();
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("gather_visibility");
+
+            let mut query = <(
+                &SpriteRender,
+                &Transform,
+                Option<&Tint>,
+                Option<&NineSlice>,
+                Option<&SpriteNormalMap>,
+            )>::query();
+
+            visibility
+                .ordered(self.viewport)
+                .iter()
+                .filter_map(|entity| query.get(*world, *entity).ok())
+                .filter_map(|(sprite_render, global, tint, nine_slice, normal_map)| {
+                    let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
+                    let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
+                    let sprite = &sprites[sprite_render.sprite_number];
+
+                    let batch_data = sprite_args(sprite, nine_slice, global, tint);
+                    let normal = normal_map.map_or(&defaults.0.normal, |map| &map.0);
+                    let (textures, this_changed) = insert_textures(
+                        textures_ref,
+                        factory,
+                        resources,
+                        &sprite_sheet.texture,
+                        normal,
+                    )?;
+
+                    changed = changed || this_changed;
+                    Some((textures, batch_data))
+                })
+                .flat_map(|(textures, batch_data)| {
+                    batch_data.into_iter().map(move |args| (textures, args))
+                })
+                .for_each_group(|textures, batch_data| {
+                    sprites_ref.insert(textures, batch_data.drain(..));
+                });
+        }
+
+        self.textures.maintain(factory, resources);
+        changed = changed || sprites_ref.changed();
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            self.vertex.write(
+                factory,
+                index,
+                self.sprites.count() as u64,
+                Some(self.sprites.data()),
+            );
+        }
+
+        self.change.prepare_result(index, changed)
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw transparent");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.lights.bind(index, layout, 3, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        for (&(albedo, normal), range) in self.sprites.iter() {
+            if self.textures.loaded(albedo) && self.textures.loaded(normal) {
+                self.textures.bind(layout, 1, albedo, &mut encoder);
+                self.textures.bind(layout, 2, normal, &mut encoder);
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Inserts the albedo and normal map textures of a sprite, returning whether either one was
+/// newly loaded.
+fn insert_textures<B: Backend>(
+    textures: &mut TextureSub<B>,
+    factory: &Factory<B>,
+    resources: &Resources,
+    albedo: &Handle<Texture>,
+    normal: &Handle<Texture>,
+) -> Option<(LitTextures, bool)> {
+    let layout = hal::image::Layout::ShaderReadOnlyOptimal;
+    let (albedo, albedo_changed) = textures.insert(factory, resources, albedo, layout)?;
+    let (normal, normal_changed) = textures.insert(factory, resources, normal, layout)?;
+    Some(((albedo, normal), albedo_changed || normal_changed))
+}
+
+fn build_lit_sprite_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    viewport: pso::Rect,
+    samples: u8,
+    transparent: bool,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::SPRITE_LIT_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::SPRITE_LIT_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(SpriteArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_viewport_rect(viewport)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: if transparent {
+                        Some(pso::BlendState::PREMULTIPLIED_ALPHA)
+                    } else {
+                        None
+                    },
+                }])
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: !transparent,
+                }),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -1,16 +1,31 @@
 //! Passes and shaders implemented by amethyst
 
 mod base_3d;
+mod billboard;
+mod blit;
+mod color_blind;
+mod color_grading;
+mod custom_material;
 mod debug_lines;
+mod decal;
 mod flat;
 mod flat2d;
+mod flat2d_lit;
+mod oit;
+mod outline;
 mod pbr;
 mod shaded;
+mod simple;
 mod skybox;
+mod tonemap;
 
 use rendy::{hal::pso::ShaderStageFlags, shader::SpirvShader};
 
-pub use self::{base_3d::*, debug_lines::*, flat::*, flat2d::*, pbr::*, shaded::*, skybox::*};
+pub use self::{
+    base_3d::*, billboard::*, blit::*, color_blind::*, color_grading::*, custom_material::*,
+    debug_lines::*, decal::*, flat::*, flat2d::*, flat2d_lit::*, oit::*, outline::*, pbr::*,
+    shaded::*, simple::*, skybox::*, tonemap::*,
+};
 
 lazy_static::lazy_static! {
     static ref POS_TEX_VERTEX: SpirvShader = SpirvShader::from_bytes(
@@ -49,24 +64,54 @@ lazy_static::lazy_static! {
         "main",
     ).unwrap();
 
+    static ref POS_NORM_TANG_TEX_LIGHTMAP_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/pos_norm_tang_tex_lightmap.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
     static ref FLAT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
         include_bytes!("../../compiled/fragment/flat.frag.spv"),
         ShaderStageFlags::FRAGMENT,
         "main",
     ).unwrap();
 
+    static ref FLAT_OIT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/flat_oit.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
     static ref SHADED_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
         include_bytes!("../../compiled/fragment/shaded.frag.spv"),
         ShaderStageFlags::FRAGMENT,
         "main",
     ).unwrap();
 
+    static ref SHADED_OIT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/shaded_oit.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
     static ref PBR_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
         include_bytes!("../../compiled/fragment/pbr.frag.spv"),
         ShaderStageFlags::FRAGMENT,
         "main",
     ).unwrap();
 
+    static ref PBR_OIT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/pbr_oit.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref PBR_LIGHTMAP_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/pbr_lightmap.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
     static ref SPRITE_VERTEX: SpirvShader = SpirvShader::from_bytes(
         include_bytes!("../../compiled/vertex/sprite.vert.spv"),
         ShaderStageFlags::VERTEX,
@@ -79,6 +124,30 @@ lazy_static::lazy_static! {
         "main",
     ).unwrap();
 
+    static ref SPRITE_LIT_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/sprite_lit.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref SPRITE_LIT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/sprite_lit.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref BILLBOARD_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/billboard.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref BILLBOARD_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/billboard.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
     static ref SKYBOX_VERTEX: SpirvShader = SpirvShader::from_bytes(
         include_bytes!("../../compiled/vertex/skybox.vert.spv"),
         ShaderStageFlags::VERTEX,
@@ -91,6 +160,54 @@ lazy_static::lazy_static! {
         "main",
     ).unwrap();
 
+    static ref SKYBOX_CUBEMAP_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/skybox_cubemap.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref FULLSCREEN_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/fullscreen.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref TONEMAP_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/tonemap.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref COLOR_BLIND_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/color_blind.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref COLOR_GRADING_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/color_grading.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref BLIT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/blit.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref BLIT_SRGB_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/blit_srgb.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref OIT_COMPOSITE_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/oit_composite.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
     static ref DEBUG_LINES_VERTEX: SpirvShader = SpirvShader::from_bytes(
         include_bytes!("../../compiled/vertex/debug_lines.vert.spv"),
         ShaderStageFlags::VERTEX,
@@ -102,4 +219,40 @@ lazy_static::lazy_static! {
         ShaderStageFlags::FRAGMENT,
         "main",
     ).unwrap();
+
+    static ref DECAL_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/decal.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref DECAL_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/decal.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref OUTLINE_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/outline.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref OUTLINE_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/outline.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+
+    static ref SPRITE_OUTLINE_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/vertex/sprite_outline.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref SPRITE_OUTLINE_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../../compiled/fragment/sprite_outline.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
 }
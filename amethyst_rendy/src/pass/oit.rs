@@ -0,0 +1,281 @@
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo, Sampler,
+    },
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+
+/// Format of the accumulation image of weighted blended order-independent transparency.
+pub const OIT_ACCUMULATION_FORMAT: hal::format::Format = hal::format::Format::Rgba16Sfloat;
+
+/// Format of the revealage image of weighted blended order-independent transparency.
+pub const OIT_REVEALAGE_FORMAT: hal::format::Format = hal::format::Format::R16Sfloat;
+
+/// Describes drawing a fullscreen triangle compositing the transparent meshes accumulated with
+/// weighted blended order-independent transparency over the target.
+///
+/// The transparent meshes are drawn with
+/// [`DrawBase3DTransparentDesc::with_order_independent`](super::DrawBase3DTransparentDesc::with_order_independent)
+/// into an accumulation image of [`OIT_ACCUMULATION_FORMAT`] cleared to zero and a revealage
+/// image of [`OIT_REVEALAGE_FORMAT`] cleared to one, which must be the two images attached to
+/// the render group builder, in that order. Both must be of the target size, with one sample
+/// per pixel.
+///
+/// The colors of overlapping meshes are averaged, weighted by their opacity and distance to the
+/// camera, so they don't need to be sorted but the result is only approximate for opaque layers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawOitCompositeDesc {
+    samples: u8,
+}
+
+impl Default for DrawOitCompositeDesc {
+    fn default() -> Self {
+        Self { samples: 1 }
+    }
+}
+
+impl DrawOitCompositeDesc {
+    /// Create instance of `DrawOitCompositeDesc` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawOitCompositeDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        let access = ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::FRAGMENT_SHADER,
+        };
+        vec![access, access]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let mut views = Vec::with_capacity(2);
+        for node_image in images.iter().take(2) {
+            let image = ctx
+                .get_image(node_image.id)
+                .expect("Order-independent transparency image does not exist")
+                .clone();
+            let format = image.format();
+            views.push(
+                factory
+                    .create_image_view(
+                        image,
+                        ImageViewInfo {
+                            view_kind: hal::image::ViewKind::D2,
+                            format,
+                            swizzle: hal::format::Swizzle::NO,
+                            range: hal::image::SubresourceRange {
+                                aspects: hal::format::Aspects::COLOR,
+                                levels: 0..1,
+                                layers: 0..1,
+                            },
+                        },
+                    )
+                    .map_err(|_| pso::CreationError::Other)?,
+            );
+        }
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Nearest,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| pso::CreationError::Other)?;
+
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                2,
+                pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                },
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets((0..).zip(&views).map(|(binding, view)| {
+                util::desc_write(
+                    set.raw(),
+                    binding,
+                    pso::Descriptor::CombinedImageSampler(
+                        view.raw(),
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                        sampler.raw(),
+                    ),
+                )
+            }));
+        }
+
+        let (pipeline, pipeline_layout) = build_oit_composite_pipeline(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![layout.raw()],
+        )?;
+
+        Ok(Box::new(DrawOitComposite::<B> {
+            pipeline,
+            pipeline_layout,
+            set,
+            views,
+            sampler,
+        }))
+    }
+}
+
+/// Composites order-independent transparency over the target.
+#[derive(Debug)]
+pub struct DrawOitComposite<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    views: Vec<Escape<ImageView<B>>>,
+    sampler: Handle<Sampler<B>>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawOitComposite<B> {
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) -> PrepareResult {
+        PrepareResult::DrawReuse
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline_layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_oit_composite_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::FULLSCREEN_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::OIT_COMPOSITE_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    // The shader outputs the revealage as alpha.
+                    blend: Some(pso::BlendState {
+                        color: pso::BlendOp::Add {
+                            src: pso::Factor::OneMinusSrcAlpha,
+                            dst: pso::Factor::SrcAlpha,
+                        },
+                        alpha: pso::BlendOp::Add {
+                            src: pso::Factor::Zero,
+                            dst: pso::Factor::One,
+                        },
+                    }),
+                }]),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
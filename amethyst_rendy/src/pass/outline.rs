@@ -0,0 +1,386 @@
+use amethyst_assets::{AssetHandle, AssetStorage, Handle, LoadHandle};
+use amethyst_core::{
+    ecs::{systems::ResourceSet, IntoQuery, Read},
+    transform::Transform,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Normal, Position, VertexFormat},
+    shader::Shader,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    bundle::Target,
+    outline::Outlined,
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pod::{OutlineArgs, SpriteOutlineArgs},
+    sprite::{SpriteRender, SpriteSheet, Sprites},
+    sprite_visibility::SpriteVisibility,
+    submodules::{DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
+    system::GraphAuxData,
+    types::{Backend, Mesh},
+    util,
+    visibility::Visibility,
+};
+
+/// Describes drawing the outlines of [`Outlined`] meshes and sprites.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawOutlineDesc {
+    target: Target,
+    samples: u8,
+}
+
+impl DrawOutlineDesc {
+    /// Create instance of `DrawOutline` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Draw outlines as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawOutlineDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = FlatEnvironmentSub::new(factory)?.with_target(self.target);
+        let textures = TextureSub::new(factory)?;
+        let vertex_format = vec![Position::vertex(), Normal::vertex()];
+
+        let (mesh_pipeline, sprite_pipeline, pipeline_layout) = build_outline_pipelines(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            &vertex_format,
+            vec![env.raw_layout(), textures.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawOutline::<B> {
+            mesh_pipeline,
+            sprite_pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            vertex_format,
+            mesh_vertex: DynamicVertexBuffer::new(),
+            sprite_vertex: DynamicVertexBuffer::new(),
+            meshes: OneLevelBatch::default(),
+            sprites: OneLevelBatch::default(),
+        }))
+    }
+}
+
+/// Draws the outlines of [`Outlined`] meshes and sprites.
+#[derive(Debug)]
+pub struct DrawOutline<B: Backend> {
+    mesh_pipeline: B::GraphicsPipeline,
+    sprite_pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    vertex_format: Vec<VertexFormat>,
+    mesh_vertex: DynamicVertexBuffer<B, OutlineArgs>,
+    sprite_vertex: DynamicVertexBuffer<B, SpriteOutlineArgs>,
+    meshes: OneLevelBatch<LoadHandle, OutlineArgs>,
+    sprites: OneLevelBatch<TextureId, SpriteOutlineArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawOutline<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+
+        let (mesh_storage, sprite_sheet_storage, sprites_storage, visibility, sprite_visibility) =
+            <(
+                Read<AssetStorage<Mesh>>,
+                Read<AssetStorage<SpriteSheet>>,
+                Read<AssetStorage<Sprites>>,
+                Read<Visibility>,
+                Read<SpriteVisibility>,
+            )>::fetch(resources);
+
+        self.env.process(factory, index, world, resources);
+
+        let meshes_ref = &mut self.meshes;
+        let sprites_ref = &mut self.sprites;
+        let textures_ref = &mut self.textures;
+
+        meshes_ref.clear_inner();
+        sprites_ref.clear_inner();
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("gather_meshes");
+
+            let mut query = <(&Outlined, &Handle<Mesh>, &Transform)>::query();
+            visibility
+                .visible_unordered
+                .iter()
+                .chain(&visibility.visible_ordered)
+                .filter_map(|entity| query.get(*world, *entity).ok())
+                .filter(|(_, mesh, _)| mesh_storage.contains(mesh.load_handle()))
+                .map(|(outlined, mesh, transform)| {
+                    (
+                        mesh.load_handle(),
+                        OutlineArgs::from_object_data(transform, outlined),
+                    )
+                })
+                .for_each_group(|mesh_id, data| {
+                    meshes_ref.insert(mesh_id, data.drain(..));
+                });
+        }
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("gather_sprites");
+
+            let mut query = <(&Outlined, &SpriteRender, &Transform)>::query();
+            sprite_visibility
+                .visible_unordered
+                .iter()
+                .chain(&sprite_visibility.visible_ordered)
+                .filter_map(|entity| query.get(*world, *entity).ok())
+                .filter_map(|(outlined, sprite_render, transform)| {
+                    let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
+                    let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
+                    let sprite = sprites.get(sprite_render.sprite_number)?;
+
+                    let (tex_id, _) = textures_ref.insert(
+                        factory,
+                        resources,
+                        &sprite_sheet.texture,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )?;
+                    Some((
+                        tex_id,
+                        SpriteOutlineArgs::from_data(sprite, transform, outlined),
+                    ))
+                })
+                .for_each_group(|tex_id, data| {
+                    sprites_ref.insert(tex_id, data.drain(..));
+                });
+        }
+
+        self.textures.maintain(factory, resources);
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            self.meshes.prune();
+            self.sprites.prune();
+            self.mesh_vertex.write(
+                factory,
+                index,
+                self.meshes.count() as u64,
+                self.meshes.data(),
+            );
+            self.sprite_vertex.write(
+                factory,
+                index,
+                self.sprites.count() as u64,
+                self.sprites.data(),
+            );
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let layout = &self.pipeline_layout;
+
+        if self.meshes.count() > 0 {
+            let mesh_storage = aux.resources.get::<AssetStorage<Mesh>>().unwrap();
+
+            encoder.bind_graphics_pipeline(&self.mesh_pipeline);
+            self.env.bind(index, layout, 0, &mut encoder);
+            self.mesh_vertex
+                .bind(index, self.vertex_format.len() as u32, 0, &mut encoder);
+            for (mesh_id, range) in self.meshes.iter() {
+                if let Some(mesh) = mesh_storage
+                    .get_for_load_handle(*mesh_id)
+                    .and_then(B::unwrap_mesh)
+                {
+                    if let Err(error) =
+                        mesh.bind_and_draw(0, &self.vertex_format, range, &mut encoder)
+                    {
+                        log::warn!(
+                            "Trying to outline a mesh that lacks {:?} vertex attributes.",
+                            error.not_found.attributes,
+                        );
+                    }
+                }
+            }
+        }
+
+        if self.sprites.count() > 0 {
+            encoder.bind_graphics_pipeline(&self.sprite_pipeline);
+            self.env.bind(index, layout, 0, &mut encoder);
+            self.sprite_vertex.bind(index, 0, 0, &mut encoder);
+            for (&tex, range) in self.sprites.iter() {
+                if self.textures.loaded(tex) {
+                    self.textures.bind(layout, 1, tex, &mut encoder);
+                    unsafe {
+                        encoder.draw(0..4, range);
+                    }
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.mesh_pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.sprite_pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_outline_pipelines<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    vertex_format: &[VertexFormat],
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_mesh_vertex = unsafe { super::OUTLINE_VERTEX.module(factory).unwrap() };
+    let shader_mesh_fragment = unsafe { super::OUTLINE_FRAGMENT.module(factory).unwrap() };
+    let shader_sprite_vertex = unsafe { super::SPRITE_OUTLINE_VERTEX.module(factory).unwrap() };
+    let shader_sprite_fragment = unsafe { super::SPRITE_OUTLINE_FRAGMENT.module(factory).unwrap() };
+
+    let mesh_vertex_desc = vertex_format
+        .iter()
+        .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
+        .chain(Some((
+            OutlineArgs::vertex(),
+            pso::VertexInputRate::Instance(1),
+        )))
+        .collect::<Vec<_>>();
+
+    let pipe_desc = PipelineDescBuilder::new()
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_multisampling(util::multisampling(samples))
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: Some(pso::BlendState::ALPHA),
+        }])
+        .with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::Greater,
+            write: false,
+        });
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            pipe_desc
+                .clone()
+                .with_vertex_desc(&mesh_vertex_desc)
+                .with_shaders(util::simple_shader_set(
+                    &shader_mesh_vertex,
+                    Some(&shader_mesh_fragment),
+                ))
+                .with_face_culling(pso::Face::FRONT),
+        )
+        .with_pipeline(
+            pipe_desc
+                .with_vertex_desc(&[(
+                    SpriteOutlineArgs::vertex(),
+                    pso::VertexInputRate::Instance(1),
+                )])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_sprite_vertex,
+                    Some(&shader_sprite_fragment),
+                )),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_mesh_vertex);
+        factory.destroy_shader_module(shader_mesh_fragment);
+        factory.destroy_shader_module(shader_sprite_vertex);
+        factory.destroy_shader_module(shader_sprite_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipes.remove(0), pipeline_layout)),
+    }
+}
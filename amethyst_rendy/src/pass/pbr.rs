@@ -6,13 +6,18 @@ use rendy::{
 use super::base_3d::{
     Base3DPassDef, DrawBase3D, DrawBase3DDesc, DrawBase3DTransparent, DrawBase3DTransparentDesc,
 };
-use crate::{mtl::FullTextureSet, skinning::JointCombined};
+use crate::{
+    lightmap::LightmapTexCoord,
+    mtl::{FullTextureSet, LightmapTextureSet},
+    skinning::JointCombined,
+};
 
 /// Implementation of `Base3DPassDef` for Physically-based (PBR) rendering pass.
 #[derive(Debug)]
 pub struct PbrPassDef;
 impl Base3DPassDef for PbrPassDef {
     const NAME: &'static str = "Pbr";
+    const IMAGE_BASED_LIGHTING: bool = true;
     type TextureSet = FullTextureSet;
     fn vertex_shader() -> &'static SpirvShader {
         &super::POS_NORM_TANG_TEX_VERTEX
@@ -23,6 +28,9 @@ impl Base3DPassDef for PbrPassDef {
     fn fragment_shader() -> &'static SpirvShader {
         &super::PBR_FRAGMENT
     }
+    fn fragment_order_independent_shader() -> Option<&'static SpirvShader> {
+        Some(&super::PBR_OIT_FRAGMENT)
+    }
     fn base_format() -> Vec<VertexFormat> {
         vec![
             Position::vertex(),
@@ -50,3 +58,45 @@ pub type DrawPbr<B> = DrawBase3D<B, PbrPassDef>;
 pub type DrawPbrTransparentDesc<B> = DrawBase3DTransparentDesc<B, PbrPassDef>;
 /// Draws a Physically-based (PBR) 3d Pass with lighting and transparency
 pub type DrawPbrTransparent<B> = DrawBase3DTransparent<B, PbrPassDef>;
+
+/// Implementation of `Base3DPassDef` for Physically-based (PBR) rendering pass of the
+/// [`Lightmapped`](crate::lightmap::Lightmapped) meshes, lit by their material's lightmap.
+#[derive(Debug)]
+pub struct PbrLightmapPassDef;
+impl Base3DPassDef for PbrLightmapPassDef {
+    const NAME: &'static str = "PbrLightmap";
+    const IMAGE_BASED_LIGHTING: bool = true;
+    const LIGHTMAPPED: bool = true;
+    type TextureSet = LightmapTextureSet;
+    fn vertex_shader() -> &'static SpirvShader {
+        &super::POS_NORM_TANG_TEX_LIGHTMAP_VERTEX
+    }
+    fn vertex_skinned_shader() -> &'static SpirvShader {
+        // Skinned meshes are never lightmapped.
+        &super::POS_NORM_TANG_TEX_SKIN_VERTEX
+    }
+    fn fragment_shader() -> &'static SpirvShader {
+        &super::PBR_LIGHTMAP_FRAGMENT
+    }
+    fn base_format() -> Vec<VertexFormat> {
+        vec![
+            Position::vertex(),
+            Normal::vertex(),
+            Tangent::vertex(),
+            TexCoord::vertex(),
+            LightmapTexCoord::vertex(),
+        ]
+    }
+    fn skinned_format() -> Vec<VertexFormat> {
+        PbrPassDef::skinned_format()
+    }
+}
+
+/// Describes a Physically-based (PBR) 3d Pass of lightmapped meshes
+pub type DrawPbrLightmapDesc<B> = DrawBase3DDesc<B, PbrLightmapPassDef>;
+/// Draws a Physically-based (PBR) 3d Pass of lightmapped meshes
+pub type DrawPbrLightmap<B> = DrawBase3D<B, PbrLightmapPassDef>;
+/// Describes a Physically-based (PBR) 3d Pass of lightmapped meshes with transparency
+pub type DrawPbrLightmapTransparentDesc<B> = DrawBase3DTransparentDesc<B, PbrLightmapPassDef>;
+/// Draws a Physically-based (PBR) 3d Pass of lightmapped meshes with transparency
+pub type DrawPbrLightmapTransparent<B> = DrawBase3DTransparent<B, PbrLightmapPassDef>;
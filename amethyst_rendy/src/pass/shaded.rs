@@ -26,6 +26,9 @@ impl Base3DPassDef for ShadedPassDef {
     fn fragment_shader() -> &'static SpirvShader {
         &super::SHADED_FRAGMENT
     }
+    fn fragment_order_independent_shader() -> Option<&'static SpirvShader> {
+        Some(&super::SHADED_OIT_FRAGMENT)
+    }
     fn base_format() -> Vec<VertexFormat> {
         vec![Position::vertex(), Normal::vertex(), TexCoord::vertex()]
     }
@@ -0,0 +1,249 @@
+use std::marker::PhantomData;
+
+use amethyst_core::ecs::IntoQuery;
+use derivative::Derivative;
+use glsl_layout::Uniform;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    bundle::Target,
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    simple_pass::SimplePassDef,
+    submodules::{DynamicUniform, DynamicVertexBuffer, FlatEnvironmentSub},
+    system::GraphAuxData,
+    types::Backend,
+    util::{self, ChangeDetection},
+};
+
+/// Draw the triangles generated by a [`SimplePassDef`].
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""), Default(bound = ""))]
+pub struct DrawSimpleDesc<P: SimplePassDef> {
+    target: Target,
+    samples: u8,
+    marker: PhantomData<P>,
+}
+
+impl<P: SimplePassDef> DrawSimpleDesc<P> {
+    /// Create instance of `DrawSimple` render group
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Draw the triangles as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend, P: SimplePassDef> RenderGroupDesc<B, GraphAuxData> for DrawSimpleDesc<P> {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let uniform = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let env = FlatEnvironmentSub::new(factory)?.with_target(self.target);
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_simple_pipeline::<B, P>(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![uniform.raw_layout(), env.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawSimple::<B, P> {
+            pipeline,
+            pipeline_layout,
+            uniform,
+            env,
+            vertex,
+            vertices: Vec::new(),
+            vertex_count: 0,
+            change: Default::default(),
+        }))
+    }
+}
+
+/// Draws the triangles generated by a [`SimplePassDef`] in one draw call.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawSimple<B: Backend, P: SimplePassDef> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    uniform: DynamicUniform<B, P::Uniform>,
+    env: FlatEnvironmentSub<B>,
+    vertex: DynamicVertexBuffer<B, P::Vertex>,
+    vertices: Vec<P::Vertex>,
+    vertex_count: usize,
+    change: ChangeDetection,
+}
+
+impl<B: Backend, P: SimplePassDef> RenderGroup<B, GraphAuxData> for DrawSimple<B, P> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+
+        self.env.process(factory, index, world, resources);
+        let mut changed = self
+            .uniform
+            .write(factory, index, P::uniform(world, resources).std140());
+
+        self.vertices.clear();
+        for component in <&P::Component>::query().iter(*world) {
+            P::vertices(component, &mut self.vertices);
+        }
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            changed |= self.vertex_count != self.vertices.len();
+            self.vertex_count = self.vertices.len();
+            changed |= self.vertex.write(
+                factory,
+                index,
+                self.vertex_count as u64,
+                Some(&self.vertices),
+            );
+        }
+
+        self.change.prepare_result(index, changed)
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.uniform
+            .bind(index, &self.pipeline_layout, 0, &mut encoder);
+        self.env.bind(index, &self.pipeline_layout, 1, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        unsafe {
+            encoder.draw(0..self.vertex_count as u32, 0..1);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_simple_pipeline<B: Backend, P: SimplePassDef>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { P::vertex_shader().module(factory).unwrap() };
+    let shader_fragment = unsafe { P::fragment_shader().module(factory).unwrap() };
+
+    let mut pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(P::Vertex::vertex(), pso::VertexInputRate::Vertex)])
+        .with_input_assembler(pso::InputAssemblerDesc::new(P::PRIMITIVE))
+        .with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_fragment),
+        ))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_multisampling(util::multisampling(samples))
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: P::BLEND,
+        }]);
+    if let Some(depth_test) = P::DEPTH_TEST {
+        pipe_desc = pipe_desc.with_depth_test(depth_test);
+    }
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipe_desc)
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -1,5 +1,5 @@
 use derivative::Derivative;
-use glsl_layout::{vec3, Uniform};
+use glsl_layout::{float, vec3, Uniform};
 use rendy::{
     command::{QueueId, RenderPassEncoder},
     factory::{Factory, UploadError},
@@ -15,12 +15,15 @@ use rendy::{
 use thread_profiler::profile_scope;
 
 use crate::{
+    bundle::Target,
+    camera::Viewport,
     palette::Srgb,
     pass,
     pipeline::{PipelineDescBuilder, PipelinesBuilder},
     pod::IntoPod,
+    resources::Skybox,
     shape::Shape,
-    submodules::{DynamicUniform, FlatEnvironmentSub},
+    submodules::{DynamicUniform, FlatEnvironmentSub, TextureId, TextureSub},
     system::GraphAuxData,
     types::Backend,
     util,
@@ -45,13 +48,15 @@ impl Default for SkyboxSettings {
 pub(crate) struct SkyboxUniform {
     nadir_color: vec3,
     zenith_color: vec3,
+    intensity: float,
 }
 
 impl SkyboxSettings {
-    pub(crate) fn uniform(&self) -> <SkyboxUniform as Uniform>::Std140 {
+    pub(crate) fn uniform(&self, intensity: f32) -> <SkyboxUniform as Uniform>::Std140 {
         SkyboxUniform {
             nadir_color: self.nadir_color.into_pod(),
             zenith_color: self.zenith_color.into_pod(),
+            intensity,
         }
         .std140()
     }
@@ -62,6 +67,9 @@ impl SkyboxSettings {
 #[derivative(Default(bound = ""))]
 pub struct DrawSkyboxDesc {
     default_settings: SkyboxSettings,
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
 }
 
 impl DrawSkyboxDesc {
@@ -79,8 +87,33 @@ impl DrawSkyboxDesc {
                 nadir_color,
                 zenith_color,
             },
+            target: Target::default(),
+            viewport: None,
+            samples: 1,
         }
     }
+
+    /// Draw the skybox around the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the skybox around the camera of the viewport with given index, limited to its region
+    /// of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawSkyboxDesc {
@@ -89,7 +122,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawSkyboxDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         queue: QueueId,
-        _aux: &GraphAuxData,
+        aux: &GraphAuxData,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -99,8 +132,11 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawSkyboxDesc {
         #[cfg(feature = "profiler")]
         profile_scope!("build");
 
-        let env = FlatEnvironmentSub::new(factory)?;
+        let env = FlatEnvironmentSub::new(factory)?
+            .with_target(self.target)
+            .with_viewport(self.viewport);
         let colors = DynamicUniform::new(factory, pso::ShaderStageFlags::FRAGMENT)?;
+        let textures = TextureSub::new(factory)?;
         let mesh = Shape::Sphere(16, 16)
             .generate::<Vec<PosTex>>(None)
             .build(queue, factory)
@@ -111,32 +147,43 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawSkyboxDesc {
                 }
             })?;
 
-        let (pipeline, pipeline_layout) = build_skybox_pipeline(
+        let (gradient_pipeline, cubemap_pipeline, pipeline_layout) = build_skybox_pipelines(
             factory,
             subpass,
-            framebuffer_width,
-            framebuffer_height,
-            vec![env.raw_layout(), colors.raw_layout()],
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
+            vec![env.raw_layout(), colors.raw_layout(), textures.raw_layout()],
         )?;
 
         Ok(Box::new(DrawSkybox::<B> {
-            pipeline,
+            gradient_pipeline,
+            cubemap_pipeline,
             pipeline_layout,
             env,
             colors,
+            textures,
+            cubemap: None,
             mesh,
             default_settings: self.default_settings,
         }))
     }
 }
 
-/// Draw a skybox around the camera view
+/// Draw a skybox around the camera view, as set by the [`Skybox`] resource.
 #[derive(Debug)]
 pub struct DrawSkybox<B: Backend> {
-    pipeline: B::GraphicsPipeline,
+    gradient_pipeline: B::GraphicsPipeline,
+    cubemap_pipeline: B::GraphicsPipeline,
     pipeline_layout: B::PipelineLayout,
     env: FlatEnvironmentSub<B>,
     colors: DynamicUniform<B, SkyboxUniform>,
+    textures: TextureSub<B>,
+    cubemap: Option<TextureId>,
     mesh: Mesh<B>,
     default_settings: SkyboxSettings,
 }
@@ -153,13 +200,41 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawSkybox<B> {
         #[cfg(feature = "profiler")]
         profile_scope!("prepare");
 
-        let settings = aux
-            .resources
-            .get::<SkyboxSettings>()
-            .map_or_else(|| self.default_settings.uniform(), |s| s.uniform());
-
         self.env.process(factory, index, aux.world, aux.resources);
-        let changed = self.colors.write(factory, index, settings);
+        self.textures.maintain(factory, aux.resources);
+
+        let skybox = aux.resources.get::<Skybox>();
+        let (settings, cubemap) = match skybox.as_deref() {
+            Some(Skybox::Gradient {
+                nadir_color,
+                zenith_color,
+            }) => {
+                let settings = SkyboxSettings {
+                    nadir_color: *nadir_color,
+                    zenith_color: *zenith_color,
+                };
+                (settings.uniform(1.0), None)
+            }
+            Some(Skybox::Cubemap { texture, intensity }) => {
+                // The default colors are drawn while the texture loads.
+                match self.textures.insert(
+                    factory,
+                    aux.resources,
+                    texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                ) {
+                    Some((id, _)) => (self.default_settings.uniform(*intensity), Some(id)),
+                    None => (self.default_settings.uniform(1.0), None),
+                }
+            }
+            None => (self.default_settings.uniform(1.0), None),
+        };
+
+        let mut changed = self.colors.write(factory, index, settings);
+        if self.cubemap != cubemap {
+            self.cubemap = cubemap;
+            changed = true;
+        }
 
         if changed {
             PrepareResult::DrawRecord
@@ -177,7 +252,14 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawSkybox<B> {
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw");
-        encoder.bind_graphics_pipeline(&self.pipeline);
+        match self.cubemap {
+            Some(cubemap) if self.textures.loaded(cubemap) => {
+                encoder.bind_graphics_pipeline(&self.cubemap_pipeline);
+                self.textures
+                    .bind(&self.pipeline_layout, 2, cubemap, &mut encoder);
+            }
+            _ => encoder.bind_graphics_pipeline(&self.gradient_pipeline),
+        }
         self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
         self.colors
             .bind(index, &self.pipeline_layout, 1, &mut encoder);
@@ -191,7 +273,12 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawSkybox<B> {
 
     fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
         unsafe {
-            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.gradient_pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.cubemap_pipeline);
             factory
                 .device()
                 .destroy_pipeline_layout(self.pipeline_layout);
@@ -199,13 +286,13 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawSkybox<B> {
     }
 }
 
-fn build_skybox_pipeline<B: Backend>(
+fn build_skybox_pipelines<B: Backend>(
     factory: &Factory<B>,
     subpass: hal::pass::Subpass<'_, B>,
-    framebuffer_width: u32,
-    framebuffer_height: u32,
+    viewport: pso::Rect,
+    samples: u8,
     layouts: Vec<&B::DescriptorSetLayout>,
-) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+) -> Result<(B::GraphicsPipeline, B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
     let pipeline_layout = unsafe {
         factory
             .device()
@@ -213,33 +300,39 @@ fn build_skybox_pipeline<B: Backend>(
     }?;
 
     let shader_vertex = unsafe { super::SKYBOX_VERTEX.module(factory).unwrap() };
-    let shader_fragment = unsafe { super::SKYBOX_FRAGMENT.module(factory).unwrap() };
+    let shader_gradient = unsafe { super::SKYBOX_FRAGMENT.module(factory).unwrap() };
+    let shader_cubemap = unsafe { super::SKYBOX_CUBEMAP_FRAGMENT.module(factory).unwrap() };
+
+    let pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(PosTex::vertex(), pso::VertexInputRate::Vertex)])
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_viewport_rect(viewport)
+        .with_multisampling(util::multisampling(samples))
+        .with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::GreaterEqual,
+            write: false,
+        })
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: None,
+        }]);
 
     let pipes = PipelinesBuilder::new()
-        .with_pipeline(
-            PipelineDescBuilder::new()
-                .with_vertex_desc(&[(PosTex::vertex(), pso::VertexInputRate::Vertex)])
-                .with_shaders(util::simple_shader_set(
-                    &shader_vertex,
-                    Some(&shader_fragment),
-                ))
-                .with_layout(&pipeline_layout)
-                .with_subpass(subpass)
-                .with_framebuffer_size(framebuffer_width, framebuffer_height)
-                .with_depth_test(pso::DepthTest {
-                    fun: pso::Comparison::GreaterEqual,
-                    write: false,
-                })
-                .with_blend_targets(vec![pso::ColorBlendDesc {
-                    mask: pso::ColorMask::ALL,
-                    blend: None,
-                }]),
-        )
+        .with_pipeline(pipe_desc.clone().with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_gradient),
+        )))
+        .with_pipeline(pipe_desc.with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_cubemap),
+        )))
         .build(factory, None);
 
     unsafe {
         factory.destroy_shader_module(shader_vertex);
-        factory.destroy_shader_module(shader_fragment);
+        factory.destroy_shader_module(shader_gradient);
+        factory.destroy_shader_module(shader_cubemap);
     }
 
     match pipes {
@@ -249,6 +342,6 @@ fn build_skybox_pipeline<B: Backend>(
             }
             Err(e)
         }
-        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+        Ok(mut pipes) => Ok((pipes.remove(0), pipes.remove(0), pipeline_layout)),
     }
 }
@@ -209,12 +209,22 @@ impl<'a, B: Backend> PipelineDescBuilder<'a, B> {
     }
     /// Set to use the provided framebuffer size.
     pub fn set_framebuffer_size(&mut self, fb_w: u32, fb_h: u32) {
-        let rect = Rect {
+        self.set_viewport_rect(Rect {
             x: 0,
             y: 0,
             w: fb_w as i16,
             h: fb_h as i16,
-        };
+        });
+    }
+
+    /// Build with viewport and scissor limited to the provided region of the framebuffer.
+    #[must_use]
+    pub fn with_viewport_rect(mut self, rect: Rect) -> Self {
+        self.set_viewport_rect(rect);
+        self
+    }
+    /// Set to use viewport and scissor limited to the provided region of the framebuffer.
+    pub fn set_viewport_rect(&mut self, rect: Rect) {
         let old_baked_states = self.baked_states.clone();
         self.set_baked_states(BakedStates {
             viewport: Some(Viewport {
@@ -1,44 +1,87 @@
 //! Set of predefined implementations of `RenderPlugin` for use with `RenderingBundle`.
 
+use amethyst_assets::ProcessingQueue;
 use amethyst_core::ecs::{DispatcherBuilder, Resources, World};
-use amethyst_error::Error;
+use amethyst_error::{format_err, Error};
 use palette::Srgb;
-use rendy::graph::render::RenderGroupDesc;
+use rendy::{
+    graph::render::{RenderGroupDesc, RenderPassNodeBuilder, SubpassBuilder},
+    hal::command::{ClearColor, ClearDepthStencil, ClearValue},
+};
 #[cfg(feature = "window")]
-pub use window::RenderToWindow;
+pub use window::{
+    RenderColorBlindFilter, RenderColorGrading, RenderToSecondaryWindows, RenderToWindow,
+    RenderTonemap,
+};
 
 use crate::{
-    bundle,
     bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    camera::Viewport,
+    occlusion::OcclusionCulling,
     pass::{
-        Base3DPassDef, DrawBase3DDesc, DrawBase3DTransparentDesc, DrawDebugLinesDesc,
-        DrawFlat2DDesc, DrawFlat2DTransparentDesc, DrawSkyboxDesc,
+        Base3DPassDef, DrawBase3DDesc, DrawBase3DTransparentDesc, DrawCustomMaterialDesc,
+        DrawDebugLinesDesc, DrawFlat2DDesc, DrawFlat2DLitDesc, DrawFlat2DLitTransparentDesc,
+        DrawFlat2DTransparentDesc, DrawOitCompositeDesc, DrawSkyboxDesc, PbrLightmapPassDef,
+        OIT_ACCUMULATION_FORMAT, OIT_REVEALAGE_FORMAT,
     },
+    sprite::{SpriteAtlasCache, SpriteAtlasSystem, SpriteSheet, Sprites},
     sprite_visibility::{SpriteVisibility, SpriteVisibilitySortingSystem},
+    types::TextureData,
     visibility::{Visibility, VisibilitySortingSystem},
-    Backend, Factory,
+    Backend, Factory, Format, Kind,
 };
 
 #[cfg(feature = "window")]
 mod window {
     use std::path::Path;
 
+    use amethyst_assets::Handle;
     use amethyst_config::{Config, ConfigError};
-    use amethyst_window::{DisplayConfig, ScreenDimensions, Window, WindowBundle};
-    use rendy::hal::command::{ClearColor, ClearDepthStencil, ClearValue};
+    use amethyst_window::{
+        DisplayConfig, ScreenDimensions, SecondaryWindows, Window, WindowBundle, WindowId,
+    };
+    use rendy::{
+        graph::{
+            present::PresentNode,
+            render::{RenderGroupBuilder, RenderGroupDesc},
+            NodeDesc,
+        },
+        hal::{
+            command::{ClearColor, ClearDepthStencil, ClearValue},
+            format::ChannelType,
+            window::Extent2D,
+        },
+    };
 
     use super::{
         Backend, DispatcherBuilder, Error, Factory, RenderPlan, RenderPlugin, Resources, Target,
         World,
     };
     use crate::{
-        bundle::{ImageOptions, OutputColor},
-        plugins, Format, Kind,
+        bundle::{ImageOptions, OutputColor, RenderOrder, TargetImage},
+        pass::{
+            DrawBlitDesc, DrawColorBlindFilterDesc, DrawColorGradingDesc, DrawTonemapDesc,
+            COLOR_BLIND_FILTER_FORMAT, COLOR_GRADING_FORMAT, HDR_FORMAT,
+        },
+        plugins,
+        render_scale::{AutoRenderScale, RenderScale, RenderScaleSystem},
+        resolve::ResolveImageDesc,
+        resources::{ColorBlindFilter, ColorBlindMode, ColorGrading, Tonemap, TonemapOperator},
+        types::Texture,
+        Format, Kind,
     };
 
     /// A [`RenderPlugin`] for opening a window and displaying a render target to it.
     ///
     /// When you provide [`DisplayConfig`], it opens a window for you using [`WindowBundle`].
+    ///
+    /// Colors are presented as sRGB. Render passes output linear colors, so when the surface
+    /// format isn't sRGB-encoded the target is rendered into an intermediate image and encoded
+    /// while copying it to the window.
+    ///
+    /// HDR10 and scRGB swapchains can't be requested: the graphics backend doesn't expose
+    /// surface color spaces. For a scene-referred pipeline, render the scene into
+    /// [`Target::Hdr`] with [`RenderTonemap`] mapping it to the presented target.
     #[derive(Default, Debug)]
     pub struct RenderToWindow {
         target: Target,
@@ -46,6 +89,10 @@ mod window {
         dimensions: Option<ScreenDimensions>,
         dirty: bool,
         clear: Option<ClearColor>,
+        samples: u8,
+        adapter: Option<String>,
+        render_scale: Option<RenderScale>,
+        planned_scale: Option<f32>,
     }
 
     impl RenderToWindow {
@@ -58,11 +105,33 @@ mod window {
         #[must_use]
         pub fn from_config(display_config: DisplayConfig) -> Self {
             Self {
+                samples: display_config.multisampling,
+                adapter: display_config.adapter.clone(),
                 config: Some(display_config),
                 ..plugins::window::RenderToWindow::default()
             }
         }
 
+        /// Render with multisample anti-aliasing using given number of samples per pixel.
+        ///
+        /// Overrides `DisplayConfig::multisampling`. The sample count is lowered to the
+        /// highest count supported by the device, with `1` disabling multisampling.
+        #[must_use]
+        pub fn with_multisampling(mut self, samples: u8) -> Self {
+            self.samples = samples;
+            self
+        }
+
+        /// Render with the graphics adapter whose name contains `name`, ignoring case.
+        ///
+        /// Overrides `DisplayConfig::adapter`. The adapters found and the capabilities of the
+        /// one picked are published as the `Adapters` and `GraphicsCapabilities` resources.
+        #[must_use]
+        pub fn with_adapter(mut self, name: impl Into<String>) -> Self {
+            self.adapter = Some(name.into());
+            self
+        }
+
         /// Select render target which will be presented to window.
         #[must_use]
         pub fn with_target(mut self, target: Target) -> Self {
@@ -70,6 +139,30 @@ mod window {
             self
         }
 
+        /// Render the scene into [`Target::Scaled`] at given fraction of the window resolution,
+        /// upscaled into the presented target before other plugins draw into it.
+        ///
+        /// The scale can be changed at runtime through the [`RenderScale`] resource, see the
+        /// [`render_scale`](crate::render_scale) module.
+        #[must_use]
+        pub fn with_render_scale(mut self, scale: f32) -> Self {
+            self.render_scale
+                .get_or_insert_with(RenderScale::default)
+                .scale = scale;
+            self
+        }
+
+        /// Render the scene into [`Target::Scaled`], adjusting its resolution to keep the frame
+        /// time under the target of `auto`, starting at its highest scale.
+        #[must_use]
+        pub fn with_auto_render_scale(mut self, auto: AutoRenderScale) -> Self {
+            self.render_scale = Some(RenderScale {
+                scale: auto.max_scale,
+                auto: Some(auto),
+            });
+            self
+        }
+
         /// Clear window with specified color every frame.
         /// This function takes linear RGBA. You can convert rgba to linear rgba like so:
         ///
@@ -104,6 +197,10 @@ mod window {
     }
 
     impl<B: Backend> RenderPlugin<B> for RenderToWindow {
+        fn adapter_preference(&self) -> Option<String> {
+            self.adapter.clone()
+        }
+
         fn on_build(
             &mut self,
             world: &mut World,
@@ -113,6 +210,10 @@ mod window {
             if let Some(config) = self.config.take() {
                 builder.add_bundle(WindowBundle::from_config(config));
             }
+            if let Some(render_scale) = self.render_scale {
+                resources.insert(render_scale);
+                builder.add_system(RenderScaleSystem);
+            }
 
             Ok(())
         }
@@ -125,6 +226,14 @@ mod window {
                 self.dimensions = new_dimensions.map(|d| (*d).clone());
                 return false;
             }
+            if let Some(planned) = self.planned_scale {
+                let scale = resources
+                    .get::<RenderScale>()
+                    .map_or(planned, |render_scale| render_scale.clamped());
+                if (scale - planned).abs() > std::f32::EPSILON {
+                    self.dirty = true;
+                }
+            }
             self.dirty
         }
 
@@ -142,7 +251,18 @@ mod window {
             let window: &Window = &window;
             let surface = factory.create_surface(window)?;
             let dimensions = self.dimensions.as_ref().unwrap();
-            let window_kind = Kind::D2(dimensions.width() as u32, dimensions.height() as u32, 1, 1);
+            let (width, height) = (dimensions.width() as u32, dimensions.height() as u32);
+            let samples = supported_samples(factory, self.samples);
+            let window_kind = Kind::D2(width, height, 1, samples);
+            let format = factory.get_surface_format(&surface);
+            // Shaders write linear colors, which only display correctly on sRGB targets. Other
+            // surfaces receive the scene through a blit encoding it to sRGB instead.
+            let encode_srgb = format.base_format().1 != ChannelType::Srgb;
+            let color_format = if encode_srgb {
+                Format::Rgba8Srgb
+            } else {
+                format
+            };
 
             let depth_options = ImageOptions {
                 kind: window_kind,
@@ -157,20 +277,692 @@ mod window {
             };
 
             plan.add_root(Target::Main);
+
+            if let Some(render_scale) = self.render_scale {
+                let render_scale = resources
+                    .get::<RenderScale>()
+                    .map_or(render_scale, |render_scale| *render_scale);
+                self.planned_scale = Some(render_scale.clamped());
+                self.plan_scaled(
+                    plan,
+                    render_scale.apply(width, height),
+                    color_format,
+                    samples,
+                )?;
+            }
+
+            if samples == 1 && !encode_srgb {
+                plan.define_pass(
+                    self.target,
+                    crate::bundle::TargetPlanOutputs {
+                        colors: vec![OutputColor::Surface(
+                            surface,
+                            self.clear.map(|color| ClearValue { color }),
+                        )],
+                        depth: Some(depth_options),
+                    },
+                )?;
+                return Ok(());
+            }
+
+            // Render into images, then resolve the samples and present or encode the result.
             plan.define_pass(
                 self.target,
                 crate::bundle::TargetPlanOutputs {
-                    colors: vec![OutputColor::Surface(
-                        surface,
-                        self.clear.map(|color| ClearValue { color }),
-                    )],
+                    colors: vec![OutputColor::Image(ImageOptions {
+                        kind: window_kind,
+                        levels: 1,
+                        format: color_format,
+                        clear: Some(ClearValue {
+                            color: self.clear.unwrap_or(ClearColor {
+                                float32: [0.0, 0.0, 0.0, 1.0],
+                            }),
+                        }),
+                    })],
                     depth: Some(depth_options),
                 },
             )?;
 
+            let target = self.target;
+            plan.extend_graph(move |ctx| {
+                let (mut color, mut node) = ctx.get_image(TargetImage::Color(target, 0))?;
+                if samples > 1 {
+                    let resolved = ctx.graph().create_image(
+                        Kind::D2(width, height, 1, 1),
+                        1,
+                        color_format,
+                        None,
+                    );
+                    node = ctx.graph().add_node(
+                        ResolveImageDesc
+                            .builder()
+                            .with_image(color)
+                            .with_image(resolved)
+                            .with_dependency(node),
+                    );
+                    color = resolved;
+                }
+                if encode_srgb {
+                    ctx.graph().add_node(
+                        DrawBlitDesc::new()
+                            .with_encode_srgb(true)
+                            .builder()
+                            .with_image(color)
+                            .into_subpass()
+                            .with_color_surface()
+                            .with_dependency(node)
+                            .into_pass()
+                            .with_surface(surface, Extent2D { width, height }, None),
+                    );
+                } else {
+                    let present =
+                        PresentNode::builder(ctx.factory(), surface, color).with_dependency(node);
+                    ctx.graph().add_node(present);
+                }
+                Ok(())
+            });
+
+            Ok(())
+        }
+    }
+
+    impl RenderToWindow {
+        /// Defines the [`Target::Scaled`] scene target of given size, upscaled into the target
+        /// presented to the window.
+        fn plan_scaled<B: Backend>(
+            &self,
+            plan: &mut RenderPlan<B>,
+            (width, height): (u32, u32),
+            format: Format,
+            samples: u8,
+        ) -> Result<(), Error> {
+            let scaled_kind = Kind::D2(width, height, 1, samples);
+            plan.define_pass(
+                Target::Scaled,
+                crate::bundle::TargetPlanOutputs {
+                    colors: vec![OutputColor::Image(ImageOptions {
+                        kind: scaled_kind,
+                        levels: 1,
+                        format,
+                        clear: Some(ClearValue {
+                            color: self.clear.unwrap_or(ClearColor {
+                                float32: [0.0, 0.0, 0.0, 1.0],
+                            }),
+                        }),
+                    })],
+                    depth: Some(ImageOptions {
+                        kind: scaled_kind,
+                        levels: 1,
+                        format: Format::D32Sfloat,
+                        clear: Some(ClearValue {
+                            depth_stencil: ClearDepthStencil {
+                                depth: 0.0,
+                                stencil: 0,
+                            },
+                        }),
+                    }),
+                },
+            )?;
+
+            plan.extend_target(self.target, move |ctx| {
+                let mut scaled = ctx.get_image(TargetImage::Color(Target::Scaled, 0))?;
+                if samples > 1 {
+                    let scaled_node = ctx.get_node(Target::Scaled)?;
+                    let resolved =
+                        ctx.graph()
+                            .create_image(Kind::D2(width, height, 1, 1), 1, format, None);
+                    let resolve = ctx.graph().add_node(
+                        ResolveImageDesc
+                            .builder()
+                            .with_image(scaled)
+                            .with_image(resolved)
+                            .with_dependency(scaled_node),
+                    );
+                    ctx.add_dep(resolve);
+                    scaled = resolved;
+                }
+
+                let group = DrawBlitDesc::new()
+                    .with_samples(ctx.samples())
+                    .builder()
+                    .with_image(scaled);
+                ctx.add(RenderOrder::BeforeOpaque, group)?;
+                Ok(())
+            });
+            Ok(())
+        }
+    }
+
+    /// A [`RenderPlugin`] presenting render targets to the [`SecondaryWindows`].
+    ///
+    /// The window with the id `id` returned by [`SecondaryWindows::open`] displays
+    /// `Target::Window(id)`, drawn by plugins configured with that target, e.g.
+    /// `RenderFlat2D::default().with_target(Target::Window(id))`, from the camera bound to it
+    /// with [`RenderTarget::bind`](crate::render_target::RenderTarget::bind). Opening, closing
+    /// or resizing a window rebuilds the render graph. Secondary windows are rendered without
+    /// multisampling.
+    ///
+    /// Closed windows are dropped once the rebuilt graph no longer presents to them.
+    #[derive(Default, Debug)]
+    pub struct RenderToSecondaryWindows {
+        windows: Vec<(u32, WindowId, (u32, u32))>,
+        clear: Option<ClearColor>,
+    }
+
+    impl RenderToSecondaryWindows {
+        /// Clear the windows with specified linear color every frame.
+        #[must_use]
+        pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+            self.clear = Some(clear.into());
+            self
+        }
+
+        fn window_sizes(resources: &Resources) -> Vec<(u32, WindowId, (u32, u32))> {
+            resources
+                .get::<SecondaryWindows>()
+                .map(|windows| {
+                    windows
+                        .iter()
+                        .map(|(id, window)| {
+                            let size = window.inner_size();
+                            (id, window.id(), (size.width, size.height))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    impl<B: Backend> RenderPlugin<B> for RenderToSecondaryWindows {
+        fn on_build(
+            &mut self,
+            _world: &mut World,
+            resources: &mut Resources,
+            _builder: &mut DispatcherBuilder,
+        ) -> Result<(), Error> {
+            resources
+                .get_or_default::<SecondaryWindows>()
+                .keep_closed_until_released();
+            Ok(())
+        }
+
+        fn should_rebuild(&mut self, _world: &World, resources: &Resources) -> bool {
+            self.windows != Self::window_sizes(resources)
+        }
+
+        fn on_plan(
+            &mut self,
+            plan: &mut RenderPlan<B>,
+            factory: &mut Factory<B>,
+            _world: &World,
+            resources: &Resources,
+        ) -> Result<(), Error> {
+            self.windows = Self::window_sizes(resources);
+            let mut windows = match resources.get_mut::<SecondaryWindows>() {
+                Some(windows) => windows,
+                None => return Ok(()),
+            };
+            // The previous graph, disposed before planning, destroyed the surfaces of the closed
+            // windows.
+            windows.release_closed();
+
+            for (id, window) in windows.iter() {
+                let size = window.inner_size();
+                // Minimized windows have no surface to present to.
+                if size.width == 0 || size.height == 0 {
+                    continue;
+                }
+                let surface = factory.create_surface(window)?;
+                let target = Target::Window(id);
+
+                plan.add_root(target);
+                plan.define_pass(
+                    target,
+                    crate::bundle::TargetPlanOutputs {
+                        colors: vec![OutputColor::Surface(
+                            surface,
+                            self.clear.map(|color| ClearValue { color }),
+                        )],
+                        depth: Some(ImageOptions {
+                            kind: Kind::D2(size.width, size.height, 1, 1),
+                            levels: 1,
+                            format: Format::D32Sfloat,
+                            clear: Some(ClearValue {
+                                depth_stencil: ClearDepthStencil {
+                                    depth: 0.0,
+                                    stencil: 0,
+                                },
+                            }),
+                        }),
+                    },
+                )?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// A [`RenderPlugin`] rendering the scene in high dynamic range and tonemapping it
+    /// into another render target before presenting.
+    ///
+    /// Defines a floating point [`Target::Hdr`] render target of window size. The 3d plugins
+    /// render into it unless they are given another target with `with_target`, while 2d and UI
+    /// plugins keep drawing into `Main` over the tonemapped image. The colors are mapped
+    /// into `Main` (or the target given to [`RenderTonemap::with_target`]) using the [`Tonemap`]
+    /// resource, which can be modified at runtime to change the operator or exposure.
+    ///
+    /// The operator and exposure of this plugin are only used when no `Tonemap` resource was
+    /// inserted beforehand, e.g. restored from the settings of the player.
+    #[derive(Default, Debug)]
+    pub struct RenderTonemap {
+        target: Target,
+        tonemap: Tonemap,
+        dimensions: Option<ScreenDimensions>,
+        dirty: bool,
+        clear: Option<ClearColor>,
+        samples: u8,
+    }
+
+    impl RenderTonemap {
+        /// Tonemap using given operator.
+        #[must_use]
+        pub fn with_operator(mut self, operator: TonemapOperator) -> Self {
+            self.tonemap.operator = operator;
+            self
+        }
+
+        /// Multiply scene colors by given exposure before tonemapping.
+        #[must_use]
+        pub fn with_exposure(mut self, exposure: f32) -> Self {
+            self.tonemap.exposure = exposure;
+            self
+        }
+
+        /// Select render target the tonemapped image is drawn into.
+        #[must_use]
+        pub fn with_target(mut self, target: Target) -> Self {
+            self.target = target;
+            self
+        }
+
+        /// Clear the HDR target with specified linear color every frame.
+        #[must_use]
+        pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+            self.clear = Some(clear.into());
+            self
+        }
+
+        /// Render the HDR target with multisample anti-aliasing using given number of samples
+        /// per pixel. It is resolved before tonemapping.
+        #[must_use]
+        pub fn with_multisampling(mut self, samples: u8) -> Self {
+            self.samples = samples;
+            self
+        }
+    }
+
+    impl<B: Backend> RenderPlugin<B> for RenderTonemap {
+        fn on_build(
+            &mut self,
+            world: &mut World,
+            resources: &mut Resources,
+            builder: &mut DispatcherBuilder,
+        ) -> Result<(), Error> {
+            let tonemap = self.tonemap;
+            resources.get_or_insert_with(|| tonemap);
+            resources.insert(plugins::HdrScene);
+            Ok(())
+        }
+
+        #[allow(clippy::map_clone)]
+        fn should_rebuild(&mut self, world: &World, resources: &Resources) -> bool {
+            let new_dimensions = resources.get::<ScreenDimensions>();
+            if self.dimensions.as_ref() != new_dimensions.as_deref() {
+                self.dirty = true;
+                self.dimensions = new_dimensions.map(|d| (*d).clone());
+                return false;
+            }
+            self.dirty
+        }
+
+        fn on_plan(
+            &mut self,
+            plan: &mut RenderPlan<B>,
+            factory: &mut Factory<B>,
+            world: &World,
+            resources: &Resources,
+        ) -> Result<(), Error> {
+            self.dirty = false;
+
+            let dimensions = self.dimensions.as_ref().unwrap();
+            let (width, height) = (dimensions.width() as u32, dimensions.height() as u32);
+            let samples = supported_samples(factory, self.samples);
+            let hdr_kind = Kind::D2(width, height, 1, samples);
+
+            plan.define_pass(
+                Target::Hdr,
+                crate::bundle::TargetPlanOutputs {
+                    colors: vec![OutputColor::Image(ImageOptions {
+                        kind: hdr_kind,
+                        levels: 1,
+                        format: HDR_FORMAT,
+                        clear: Some(ClearValue {
+                            color: self.clear.unwrap_or(ClearColor {
+                                float32: [0.0, 0.0, 0.0, 1.0],
+                            }),
+                        }),
+                    })],
+                    depth: Some(ImageOptions {
+                        kind: hdr_kind,
+                        levels: 1,
+                        format: Format::D32Sfloat,
+                        clear: Some(ClearValue {
+                            depth_stencil: ClearDepthStencil {
+                                depth: 0.0,
+                                stencil: 0,
+                            },
+                        }),
+                    }),
+                },
+            )?;
+
+            plan.extend_target(self.target, move |ctx| {
+                let mut hdr = ctx.get_image(TargetImage::Color(Target::Hdr, 0))?;
+                if samples > 1 {
+                    let hdr_node = ctx.get_node(Target::Hdr)?;
+                    let resolved = ctx.graph().create_image(
+                        Kind::D2(width, height, 1, 1),
+                        1,
+                        HDR_FORMAT,
+                        None,
+                    );
+                    let resolve = ctx.graph().add_node(
+                        ResolveImageDesc
+                            .builder()
+                            .with_image(hdr)
+                            .with_image(resolved)
+                            .with_dependency(hdr_node),
+                    );
+                    ctx.add_dep(resolve);
+                    hdr = resolved;
+                }
+
+                let group = DrawTonemapDesc::new()
+                    .with_samples(ctx.samples())
+                    .builder()
+                    .with_image(hdr);
+                ctx.add(RenderOrder::ToneMap, group)?;
+                Ok(())
+            });
+
+            Ok(())
+        }
+    }
+
+    /// A [`RenderPlugin`] grading the colors of the scene with a lookup table before presenting.
+    ///
+    /// Defines a [`Target::ColorGrading`] render target of window size. Plugins drawing the scene
+    /// should be configured to render into it, e.g.
+    /// `RenderTonemap::default().with_target(Target::ColorGrading)` or
+    /// `RenderFlat2D::default().with_target(Target::ColorGrading)`. The colors are graded into
+    /// `Main` (or the target given to [`RenderColorGrading::with_target`]) using the
+    /// [`ColorGrading`] resource, whose lookup table can be swapped at runtime.
+    #[derive(Default, Debug)]
+    pub struct RenderColorGrading {
+        target: Target,
+        grading: ColorGrading,
+        dimensions: Option<ScreenDimensions>,
+        dirty: bool,
+        clear: Option<ClearColor>,
+    }
+
+    impl RenderColorGrading {
+        /// Grade colors with given lookup table, see [`ColorGrading::lut`].
+        #[must_use]
+        pub fn with_lut(mut self, lut: Handle<Texture>) -> Self {
+            self.grading.lut = Some(lut);
+            self
+        }
+
+        /// Blend between the original and graded colors, see [`ColorGrading::intensity`].
+        #[must_use]
+        pub fn with_intensity(mut self, intensity: f32) -> Self {
+            self.grading.intensity = intensity;
+            self
+        }
+
+        /// Select render target the graded image is drawn into.
+        #[must_use]
+        pub fn with_target(mut self, target: Target) -> Self {
+            self.target = target;
+            self
+        }
+
+        /// Clear the color grading target with specified linear color every frame.
+        #[must_use]
+        pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+            self.clear = Some(clear.into());
+            self
+        }
+    }
+
+    impl<B: Backend> RenderPlugin<B> for RenderColorGrading {
+        fn on_build(
+            &mut self,
+            _world: &mut World,
+            resources: &mut Resources,
+            _builder: &mut DispatcherBuilder,
+        ) -> Result<(), Error> {
+            resources.insert(self.grading.clone());
+            Ok(())
+        }
+
+        #[allow(clippy::map_clone)]
+        fn should_rebuild(&mut self, _world: &World, resources: &Resources) -> bool {
+            let new_dimensions = resources.get::<ScreenDimensions>();
+            if self.dimensions.as_ref() != new_dimensions.as_deref() {
+                self.dirty = true;
+                self.dimensions = new_dimensions.map(|d| (*d).clone());
+                return false;
+            }
+            self.dirty
+        }
+
+        fn on_plan(
+            &mut self,
+            plan: &mut RenderPlan<B>,
+            _factory: &mut Factory<B>,
+            _world: &World,
+            _resources: &Resources,
+        ) -> Result<(), Error> {
+            self.dirty = false;
+
+            let dimensions = self.dimensions.as_ref().unwrap();
+            let kind = Kind::D2(dimensions.width() as u32, dimensions.height() as u32, 1, 1);
+
+            plan.define_pass(
+                Target::ColorGrading,
+                crate::bundle::TargetPlanOutputs {
+                    colors: vec![OutputColor::Image(ImageOptions {
+                        kind,
+                        levels: 1,
+                        format: COLOR_GRADING_FORMAT,
+                        clear: Some(ClearValue {
+                            color: self.clear.unwrap_or(ClearColor {
+                                float32: [0.0, 0.0, 0.0, 1.0],
+                            }),
+                        }),
+                    })],
+                    depth: Some(ImageOptions {
+                        kind,
+                        levels: 1,
+                        format: Format::D32Sfloat,
+                        clear: Some(ClearValue {
+                            depth_stencil: ClearDepthStencil {
+                                depth: 0.0,
+                                stencil: 0,
+                            },
+                        }),
+                    }),
+                },
+            )?;
+
+            plan.extend_target(self.target, move |ctx| {
+                let source = ctx.get_image(TargetImage::Color(Target::ColorGrading, 0))?;
+                let group = DrawColorGradingDesc::new()
+                    .with_samples(ctx.samples())
+                    .builder()
+                    .with_image(source);
+                ctx.add(RenderOrder::DisplayPostEffects, group)?;
+                Ok(())
+            });
+
             Ok(())
         }
     }
+
+    /// A [`RenderPlugin`] filtering the colors of the scene for color-blind players before
+    /// presenting.
+    ///
+    /// Defines a [`Target::ColorBlindFilter`] render target of window size. Plugins drawing the
+    /// scene should be configured to render into it, e.g.
+    /// `RenderColorGrading::default().with_target(Target::ColorBlindFilter)` or
+    /// `RenderFlat2D::default().with_target(Target::ColorBlindFilter)`. The colors are filtered
+    /// into `Main` (or the target given to [`RenderColorBlindFilter::with_target`]) using the
+    /// [`ColorBlindFilter`] resource, whose mode can be toggled at runtime.
+    #[derive(Default, Debug)]
+    pub struct RenderColorBlindFilter {
+        target: Target,
+        filter: ColorBlindFilter,
+        dimensions: Option<ScreenDimensions>,
+        dirty: bool,
+        clear: Option<ClearColor>,
+    }
+
+    impl RenderColorBlindFilter {
+        /// Filter colors with given mode, see [`ColorBlindFilter::mode`].
+        #[must_use]
+        pub fn with_mode(mut self, mode: ColorBlindMode) -> Self {
+            self.filter.mode = mode;
+            self
+        }
+
+        /// Blend between the original and filtered colors, see
+        /// [`ColorBlindFilter::intensity`].
+        #[must_use]
+        pub fn with_intensity(mut self, intensity: f32) -> Self {
+            self.filter.intensity = intensity;
+            self
+        }
+
+        /// Select render target the filtered image is drawn into.
+        #[must_use]
+        pub fn with_target(mut self, target: Target) -> Self {
+            self.target = target;
+            self
+        }
+
+        /// Clear the color blind filter target with specified linear color every frame.
+        #[must_use]
+        pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+            self.clear = Some(clear.into());
+            self
+        }
+    }
+
+    impl<B: Backend> RenderPlugin<B> for RenderColorBlindFilter {
+        fn on_build(
+            &mut self,
+            _world: &mut World,
+            resources: &mut Resources,
+            _builder: &mut DispatcherBuilder,
+        ) -> Result<(), Error> {
+            resources.insert(self.filter.clone());
+            Ok(())
+        }
+
+        #[allow(clippy::map_clone)]
+        fn should_rebuild(&mut self, _world: &World, resources: &Resources) -> bool {
+            let new_dimensions = resources.get::<ScreenDimensions>();
+            if self.dimensions.as_ref() != new_dimensions.as_deref() {
+                self.dirty = true;
+                self.dimensions = new_dimensions.map(|d| (*d).clone());
+                return false;
+            }
+            self.dirty
+        }
+
+        fn on_plan(
+            &mut self,
+            plan: &mut RenderPlan<B>,
+            _factory: &mut Factory<B>,
+            _world: &World,
+            _resources: &Resources,
+        ) -> Result<(), Error> {
+            self.dirty = false;
+
+            let dimensions = self.dimensions.as_ref().unwrap();
+            let kind = Kind::D2(dimensions.width() as u32, dimensions.height() as u32, 1, 1);
+
+            plan.define_pass(
+                Target::ColorBlindFilter,
+                crate::bundle::TargetPlanOutputs {
+                    colors: vec![OutputColor::Image(ImageOptions {
+                        kind,
+                        levels: 1,
+                        format: COLOR_BLIND_FILTER_FORMAT,
+                        clear: Some(ClearValue {
+                            color: self.clear.unwrap_or(ClearColor {
+                                float32: [0.0, 0.0, 0.0, 1.0],
+                            }),
+                        }),
+                    })],
+                    depth: Some(ImageOptions {
+                        kind,
+                        levels: 1,
+                        format: Format::D32Sfloat,
+                        clear: Some(ClearValue {
+                            depth_stencil: ClearDepthStencil {
+                                depth: 0.0,
+                                stencil: 0,
+                            },
+                        }),
+                    }),
+                },
+            )?;
+
+            plan.extend_target(self.target, move |ctx| {
+                let source = ctx.get_image(TargetImage::Color(Target::ColorBlindFilter, 0))?;
+                let group = DrawColorBlindFilterDesc::new()
+                    .with_samples(ctx.samples())
+                    .builder()
+                    .with_image(source);
+                ctx.add(RenderOrder::DisplayPostEffects, group)?;
+                Ok(())
+            });
+
+            Ok(())
+        }
+    }
+
+    /// Highest sample count not above the requested one that the device supports.
+    fn supported_samples<B: Backend>(factory: &Factory<B>, requested: u8) -> u8 {
+        let limits = factory.physical().limits();
+        let supported =
+            limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts;
+        let mut samples = requested.max(1).next_power_of_two().min(64);
+        while samples > 1 && supported & samples == 0 {
+            samples /= 2;
+        }
+        if samples != requested.max(1) {
+            log::warn!(
+                "Multisampling with {} samples is not supported, using {} samples instead.",
+                requested,
+                samples
+            );
+        }
+        samples
+    }
 }
 
 /// A `RenderPlugin` for forward rendering of 3d objects using flat shading.
@@ -182,11 +974,16 @@ pub type RenderPbr3D = RenderBase3D<crate::pass::PbrPassDef>;
 
 /// A `RenderPlugin` for forward rendering of 3d objects.
 /// Generic over 3d pass rendering method.
+///
+/// Meshes are drawn once per split screen camera when rendering to the window, see [`Viewport`].
 #[derive(derivative::Derivative)]
 #[derivative(Default(bound = ""), Debug(bound = ""))]
 pub struct RenderBase3D<D: Base3DPassDef> {
-    target: Target,
+    target: Option<Target>,
     skinning: bool,
+    occlusion: Option<OcclusionCulling>,
+    order_independent: bool,
+    viewports: Vec<Viewport>,
     marker: std::marker::PhantomData<D>,
 }
 
@@ -194,7 +991,7 @@ impl<D: Base3DPassDef> RenderBase3D<D> {
     /// Set target to which 3d meshes will be rendered.
     #[must_use]
     pub fn with_target(mut self, target: Target) -> Self {
-        self.target = target;
+        self.target = Some(target);
         self
     }
 
@@ -206,6 +1003,26 @@ impl<D: Base3DPassDef> RenderBase3D<D> {
         self.skinning = true;
         self
     }
+
+    /// Skip drawing the meshes hidden behind [`Occluder`](crate::occlusion::Occluder)s.
+    #[must_use]
+    pub fn with_occlusion_culling(mut self, settings: OcclusionCulling) -> Self {
+        self.occlusion = Some(settings);
+        self
+    }
+
+    /// Draw the transparent meshes with weighted blended order-independent transparency, so
+    /// overlapping transparent meshes don't need to be sorted, see [`DrawOitCompositeDesc`].
+    ///
+    /// The transparent meshes are accumulated in a separate pass of the target size, occluded by
+    /// the opaque meshes of this plugin drawn again into its depth buffer, and composited over
+    /// the target after the opaque meshes. Meshes drawn by other plugins, e.g. lightmapped
+    /// meshes, don't occlude them.
+    #[must_use]
+    pub fn with_order_independent_transparency(mut self) -> Self {
+        self.order_independent = true;
+        self
+    }
 }
 
 impl<B: Backend, D: Base3DPassDef> RenderPlugin<B> for RenderBase3D<D> {
@@ -215,32 +1032,196 @@ impl<B: Backend, D: Base3DPassDef> RenderPlugin<B> for RenderBase3D<D> {
         resources: &mut Resources,
         builder: &mut DispatcherBuilder,
     ) -> Result<(), Error> {
-        resources.insert(Visibility::default());
-        builder.add_system(VisibilitySortingSystem::default());
+        add_visibility_sorting(resources, builder, self.occlusion);
         Ok(())
     }
 
+    fn should_rebuild(&mut self, world: &World, resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, scene_target(self.target, resources))
+    }
+
     fn on_plan(
         &mut self,
         plan: &mut RenderPlan<B>,
         _factory: &mut Factory<B>,
-        _world: &World,
-        _resources: &Resources,
+        world: &World,
+        resources: &Resources,
     ) -> Result<(), Error> {
+        if self.order_independent && D::fragment_order_independent_shader().is_none() {
+            return Err(format_err!(
+                "Pass {} doesn't support order-independent transparency.",
+                D::NAME
+            ));
+        }
+
+        let target = scene_target(self.target, resources);
+        self.viewports = Viewport::of_target(world, target);
+        let indices = Viewport::instances(&self.viewports);
         let skinning = self.skinning;
-        plan.extend_target(self.target, move |ctx| {
-            ctx.add(
-                RenderOrder::Opaque,
-                DrawBase3DDesc::<B, D>::new()
-                    .with_skinning(skinning)
-                    .builder(),
-            )?;
-            ctx.add(
-                RenderOrder::Transparent,
-                DrawBase3DTransparentDesc::<B, D>::new()
-                    .with_skinning(skinning)
-                    .builder(),
-            )?;
+        let order_independent = self.order_independent;
+        plan.extend_target(target, move |ctx| {
+            for viewport in &indices {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawBase3DDesc::<B, D>::new()
+                        .with_skinning(skinning)
+                        .with_target(target)
+                        .with_viewport(*viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+                if !order_independent {
+                    ctx.add(
+                        RenderOrder::Transparent,
+                        DrawBase3DTransparentDesc::<B, D>::new()
+                            .with_skinning(skinning)
+                            .with_target(target)
+                            .with_viewport(*viewport)
+                            .with_samples(ctx.samples())
+                            .builder(),
+                    )?;
+                }
+            }
+
+            if order_independent {
+                let metadata = ctx.target_metadata(target).ok_or_else(|| {
+                    format_err!(
+                        "Target {:?} has no size to accumulate transparency.",
+                        target
+                    )
+                })?;
+                let kind = Kind::D2(metadata.width(), metadata.height(), 1, 1);
+                let accumulation = ctx.graph().create_image(
+                    kind,
+                    1,
+                    OIT_ACCUMULATION_FORMAT,
+                    Some(ClearValue {
+                        color: ClearColor {
+                            float32: [0.0, 0.0, 0.0, 0.0],
+                        },
+                    }),
+                );
+                let revealage = ctx.graph().create_image(
+                    kind,
+                    1,
+                    OIT_REVEALAGE_FORMAT,
+                    Some(ClearValue {
+                        color: ClearColor {
+                            float32: [1.0, 1.0, 1.0, 1.0],
+                        },
+                    }),
+                );
+                let depth = ctx.graph().create_image(
+                    kind,
+                    1,
+                    Format::D32Sfloat,
+                    Some(ClearValue {
+                        depth_stencil: ClearDepthStencil {
+                            depth: 0.0,
+                            stencil: 0,
+                        },
+                    }),
+                );
+
+                let mut subpass = SubpassBuilder::new()
+                    .with_color(accumulation)
+                    .with_color(revealage)
+                    .with_depth_stencil(depth);
+                for viewport in &indices {
+                    subpass = subpass
+                        .with_group(
+                            DrawBase3DDesc::<B, D>::new()
+                                .with_skinning(skinning)
+                                .with_target(target)
+                                .with_viewport(*viewport)
+                                .with_samples(1)
+                                .with_depth_only(true)
+                                .builder(),
+                        )
+                        .with_group(
+                            DrawBase3DTransparentDesc::<B, D>::new()
+                                .with_skinning(skinning)
+                                .with_target(target)
+                                .with_viewport(*viewport)
+                                .with_samples(1)
+                                .with_order_independent(true)
+                                .builder(),
+                        );
+                }
+                let node = ctx
+                    .graph()
+                    .add_node(RenderPassNodeBuilder::new().with_subpass(subpass));
+                ctx.add_dep(node);
+
+                ctx.add(
+                    RenderOrder::Transparent,
+                    DrawOitCompositeDesc::new()
+                        .with_samples(ctx.samples())
+                        .builder()
+                        .with_image(accumulation)
+                        .with_image(revealage),
+                )?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+/// A [`RenderPlugin`] drawing the [`Lightmapped`](crate::lightmap::Lightmapped) meshes with
+/// physically-based shading, lit by their lightmap. It's added next to [`RenderPbr3D`], which
+/// draws the other meshes.
+///
+/// Meshes are drawn once per split screen camera when rendering to the window, see [`Viewport`].
+#[derive(Default, Debug)]
+pub struct RenderLightmaps {
+    target: Option<Target>,
+    viewports: Vec<Viewport>,
+}
+
+impl RenderLightmaps {
+    /// Set target to which lightmapped meshes will be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderLightmaps {
+    fn should_rebuild(&mut self, world: &World, resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, scene_target(self.target, resources))
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+        resources: &Resources,
+    ) -> Result<(), Error> {
+        let target = scene_target(self.target, resources);
+        self.viewports = Viewport::of_target(world, target);
+        let indices = Viewport::instances(&self.viewports);
+        plan.extend_target(target, move |ctx| {
+            for viewport in indices {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawBase3DDesc::<B, PbrLightmapPassDef>::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+                ctx.add(
+                    RenderOrder::Transparent,
+                    DrawBase3DTransparentDesc::<B, PbrLightmapPassDef>::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+            }
             Ok(())
         });
         Ok(())
@@ -249,9 +1230,12 @@ impl<B: Backend, D: Base3DPassDef> RenderPlugin<B> for RenderBase3D<D> {
 
 /// A [`RenderPlugin`] for drawing 2d objects with flat shading.
 /// Required to display sprites defined with [`SpriteRender`] component.
+///
+/// Sprites are drawn once per split screen camera when rendering to the window, see [`Viewport`].
 #[derive(Default, Debug)]
 pub struct RenderFlat2D {
     target: Target,
+    viewports: Vec<Viewport>,
 }
 
 impl RenderFlat2D {
@@ -270,59 +1254,228 @@ impl<B: Backend> RenderPlugin<B> for RenderFlat2D {
         resources: &mut Resources,
         builder: &mut DispatcherBuilder,
     ) -> Result<(), Error> {
-        resources.insert(SpriteVisibility::default());
-        builder.add_system(SpriteVisibilitySortingSystem);
+        add_sprite_visibility_sorting(resources, builder);
+        add_sprite_atlas_cache(resources, builder);
         Ok(())
     }
 
+    fn should_rebuild(&mut self, world: &World, _resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, self.target)
+    }
+
     fn on_plan(
         &mut self,
         plan: &mut RenderPlan<B>,
         _factory: &mut Factory<B>,
-        _world: &World,
+        world: &World,
         _resources: &Resources,
     ) -> Result<(), Error> {
-        plan.extend_target(self.target, |ctx| {
-            ctx.add(RenderOrder::Opaque, DrawFlat2DDesc::new().builder())?;
-            ctx.add(
-                RenderOrder::Transparent,
-                DrawFlat2DTransparentDesc::new().builder(),
-            )?;
+        self.viewports = Viewport::of_target(world, self.target);
+        let indices = Viewport::instances(&self.viewports);
+        let target = self.target;
+        plan.extend_target(self.target, move |ctx| {
+            for viewport in indices {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawFlat2DDesc::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+                ctx.add(
+                    RenderOrder::Transparent,
+                    DrawFlat2DTransparentDesc::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+            }
             Ok(())
         });
         Ok(())
     }
 }
 
+/// A [`RenderPlugin`] for drawing sprites lit by 2D lights, instead of [`RenderFlat2D`].
+///
+/// Sprites can be normal mapped and cast shadows, see the [`light2d`](crate::light2d) module.
+/// Without an [`AmbientColor`](crate::resources::AmbientColor) resource, sprites out of reach of
+/// the lights are black.
+#[derive(Default, Debug)]
+pub struct RenderFlat2DLit {
+    target: Target,
+    viewports: Vec<Viewport>,
+}
+
+impl RenderFlat2DLit {
+    /// Set target to which lit 2d sprites will be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderFlat2DLit {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        add_sprite_visibility_sorting(resources, builder);
+        add_sprite_atlas_cache(resources, builder);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World, _resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, self.target)
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        self.viewports = Viewport::of_target(world, self.target);
+        let indices = Viewport::instances(&self.viewports);
+        let target = self.target;
+        plan.extend_target(self.target, move |ctx| {
+            for viewport in indices {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawFlat2DLitDesc::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+                ctx.add(
+                    RenderOrder::Transparent,
+                    DrawFlat2DLitTransparentDesc::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+/// Adds the `VisibilitySortingSystem` unless another plugin already did, as several plugins may
+/// draw the same entities. The `Visibility` resource marks it as added.
+fn add_visibility_sorting(
+    resources: &mut Resources,
+    builder: &mut DispatcherBuilder,
+    occlusion: Option<OcclusionCulling>,
+) {
+    if resources.contains::<Visibility>() {
+        if occlusion.is_some() {
+            log::warn!(
+                "Occlusion culling ignored, visibility sorting was already added by another \
+                 plugin. Add the plugin culling occluded meshes first."
+            );
+        }
+        return;
+    }
+
+    resources.insert(Visibility::default());
+    let mut visibility = VisibilitySortingSystem::default();
+    if let Some(settings) = occlusion {
+        visibility = visibility.with_occlusion_culling(settings);
+    }
+    builder.add_system(visibility);
+}
+
+/// Adds the `SpriteVisibilitySortingSystem` unless another plugin already did.
+fn add_sprite_visibility_sorting(resources: &mut Resources, builder: &mut DispatcherBuilder) {
+    if !resources.contains::<SpriteVisibility>() {
+        resources.insert(SpriteVisibility::default());
+        builder.add_system(SpriteVisibilitySortingSystem);
+    }
+}
+
+/// Resource marking that [`RenderTonemap`] defines the [`Target::Hdr`] render target.
+#[derive(Debug, Default)]
+pub(crate) struct HdrScene;
+
+/// Resolves the target a 3d plugin renders into: the one it was configured with, otherwise
+/// [`Target::Hdr`] when the scene is tonemapped and `Main` when it isn't.
+pub(crate) fn scene_target(target: Option<Target>, resources: &Resources) -> Target {
+    target.unwrap_or_else(|| {
+        if resources.contains::<HdrScene>() {
+            Target::Hdr
+        } else {
+            Target::Main
+        }
+    })
+}
+
+/// Adds the `SpriteAtlasSystem` repacking the `SpriteAtlasCache` unless another plugin already did.
+fn add_sprite_atlas_cache(resources: &mut Resources, builder: &mut DispatcherBuilder) {
+    if !resources.contains::<SpriteAtlasCache>() {
+        resources.insert(SpriteAtlasCache::default());
+        resources.get_or_default::<ProcessingQueue<TextureData>>();
+        resources.get_or_default::<ProcessingQueue<Sprites>>();
+        resources.get_or_default::<ProcessingQueue<SpriteSheet>>();
+        builder.add_system(SpriteAtlasSystem);
+    }
+}
+
 /// A [`RenderPlugin`] for drawing debug lines.
 /// Use with [`debug_drawing::DebugLines`] resource or [`debug_drawing::DebugLinesComponent`].
+///
+/// Lines are drawn once per split screen camera when rendering to the window, see [`Viewport`].
 #[derive(Default, Debug)]
 pub struct RenderDebugLines {
-    target: Target,
+    target: Option<Target>,
+    viewports: Vec<Viewport>,
 }
 
 impl RenderDebugLines {
     /// Set target to which debug lines will be rendered.
     #[must_use]
     pub fn with_target(mut self, target: Target) -> Self {
-        self.target = target;
+        self.target = Some(target);
         self
     }
 }
 
 impl<B: Backend> RenderPlugin<B> for RenderDebugLines {
+    fn should_rebuild(&mut self, world: &World, resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, scene_target(self.target, resources))
+    }
+
     fn on_plan(
         &mut self,
         plan: &mut RenderPlan<B>,
         _factory: &mut Factory<B>,
-        _world: &World,
-        _resources: &Resources,
+        world: &World,
+        resources: &Resources,
     ) -> Result<(), Error> {
-        plan.extend_target(self.target, |ctx| {
-            ctx.add(
-                RenderOrder::BeforeTransparent,
-                DrawDebugLinesDesc::new().builder(),
-            )?;
+        let target = scene_target(self.target, resources);
+        self.viewports = Viewport::of_target(world, target);
+        let indices = Viewport::instances(&self.viewports);
+        plan.extend_target(target, move |ctx| {
+            let last = indices.len() - 1;
+            for (i, viewport) in indices.into_iter().enumerate() {
+                let mut group = DrawDebugLinesDesc::new()
+                    .with_target(target)
+                    .with_viewport(viewport)
+                    .with_samples(ctx.samples());
+                // only the last instance clears the `DebugLines` resource
+                if i != last {
+                    group = group.keep_lines();
+                }
+                ctx.add(RenderOrder::BeforeTransparent, group.builder())?;
+            }
             Ok(())
         });
         Ok(())
@@ -330,10 +1483,16 @@ impl<B: Backend> RenderPlugin<B> for RenderDebugLines {
 }
 
 /// `RenderPlugin` for rendering skyboxes.
+///
+/// The sky is a gradient between the plugin colors, unless a [`Skybox`](crate::resources::Skybox)
+/// resource selects other colors or a cube texture.
+///
+/// The sky is drawn once per split screen camera when rendering to the window, see [`Viewport`].
 #[derive(Default, Debug)]
 pub struct RenderSkybox {
-    target: Target,
+    target: Option<Target>,
     colors: Option<(Srgb, Srgb)>,
+    viewports: Vec<Viewport>,
 }
 
 impl RenderSkybox {
@@ -341,38 +1500,130 @@ impl RenderSkybox {
     #[must_use]
     pub fn with_colors(nadir_color: Srgb, zenith_color: Srgb) -> Self {
         Self {
-            target: bundle::Target::default(),
+            target: None,
             colors: Some((nadir_color, zenith_color)),
+            viewports: Vec::new(),
         }
     }
 
     /// Set target to which skybox will be rendered.
     #[must_use]
     pub fn with_target(mut self, target: Target) -> Self {
-        self.target = target;
+        self.target = Some(target);
         self
     }
 }
 
 impl<B: Backend> RenderPlugin<B> for RenderSkybox {
+    fn should_rebuild(&mut self, world: &World, resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, scene_target(self.target, resources))
+    }
+
     fn on_plan(
         &mut self,
         plan: &mut RenderPlan<B>,
         _factory: &mut Factory<B>,
-        _world: &World,
-        _resources: &Resources,
+        world: &World,
+        resources: &Resources,
     ) -> Result<(), Error> {
+        let target = scene_target(self.target, resources);
+        self.viewports = Viewport::of_target(world, target);
+        let indices = Viewport::instances(&self.viewports);
         let colors = self.colors;
-        plan.extend_target(self.target, move |ctx| {
-            let group = if let Some((nadir, zenith)) = colors {
-                DrawSkyboxDesc::with_colors(nadir, zenith).builder()
-            } else {
-                DrawSkyboxDesc::new().builder()
-            };
+        plan.extend_target(target, move |ctx| {
+            for viewport in indices {
+                let group = if let Some((nadir, zenith)) = colors {
+                    DrawSkyboxDesc::with_colors(nadir, zenith)
+                } else {
+                    DrawSkyboxDesc::new()
+                }
+                .with_target(target)
+                .with_viewport(viewport)
+                .with_samples(ctx.samples())
+                .builder();
 
-            ctx.add(RenderOrder::AfterOpaque, group)?;
+                ctx.add(RenderOrder::AfterOpaque, group)?;
+            }
             Ok(())
         });
         Ok(())
     }
 }
+
+/// A [`RenderPlugin`] for drawing meshes with [`CustomMaterial`](crate::custom_material::CustomMaterial)s.
+///
+/// Materials are rebuilt when their asset is reloaded, which allows iterating on shaders while
+/// the application is running.
+///
+/// Meshes are drawn once per split screen camera when rendering to the window, see [`Viewport`].
+#[derive(Default, Debug)]
+pub struct RenderCustomMaterials {
+    target: Option<Target>,
+    viewports: Vec<Viewport>,
+}
+
+impl RenderCustomMaterials {
+    /// Set target to which custom materials will be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderCustomMaterials {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        add_visibility_sorting(resources, builder, None);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World, resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, scene_target(self.target, resources))
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+        resources: &Resources,
+    ) -> Result<(), Error> {
+        let target = scene_target(self.target, resources);
+        self.viewports = Viewport::of_target(world, target);
+        let indices = Viewport::instances(&self.viewports);
+        plan.extend_target(target, move |ctx| {
+            for viewport in indices {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawCustomMaterialDesc::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_target_follows_tonemapping() {
+        let mut resources = Resources::default();
+        assert_eq!(scene_target(None, &resources), Target::Main);
+
+        resources.insert(HdrScene);
+        assert_eq!(scene_target(None, &resources), Target::Hdr);
+        assert_eq!(scene_target(Some(Target::Main), &resources), Target::Main);
+    }
+}
@@ -1,16 +1,16 @@
 //! GPU POD data types.
 
 use amethyst_core::{
-    math::{convert, Matrix4, Vector4},
+    math::{convert, Matrix4, Vector2, Vector4},
     transform::Transform,
 };
-use glsl_layout::{float, int, mat4, vec2, vec3, vec4, Uniform};
+use glsl_layout::{float, int, ivec2, mat4, vec2, vec3, vec4, Uniform};
 use rendy::{
     hal::format::Format,
     mesh::{AsAttribute, AsVertex, Model, VertexFormat},
 };
 
-use crate::{mtl, resources::Tint as TintComponent, Sprite};
+use crate::{mtl, outline::Outlined, resources::Tint as TintComponent, Sprite};
 
 /// `TextureOffset`
 /// ```glsl
@@ -39,12 +39,53 @@ impl TextureOffset {
     }
 }
 
+/// `UvAnimation`
+/// ```glsl
+/// struct UvAnimation {
+///    vec2 scroll;
+///    ivec2 grid;
+///    int frames;
+///    float frames_per_second;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Uniform)]
+#[repr(C, align(16))]
+pub struct UvAnimation {
+    /// Texture coordinates scrolled per second
+    pub scroll: vec2,
+    /// Columns and rows of the flipbook
+    pub grid: ivec2,
+    /// Number of frames of the flipbook
+    pub frames: int,
+    /// Flipbook frames per second
+    pub frames_per_second: float,
+}
+
+impl UvAnimation {
+    /// Helper function from proper type to Pod type.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn from_animation(animation: &crate::mtl::UvAnimation) -> Self {
+        UvAnimation {
+            scroll: [animation.scroll.0, animation.scroll.1].into(),
+            grid: [
+                animation.grid.0.max(1) as i32,
+                animation.grid.1.max(1) as i32,
+            ]
+            .into(),
+            frames: animation.frames as i32,
+            frames_per_second: animation.frames_per_second,
+        }
+    }
+}
+
 /// `ViewArgs`
 /// ```glsl
 /// uniform ViewArgs {
 ///    uniform mat4 proj;
 ///    uniform mat4 view;
 ///    uniform mat4 proj_view;
+///    uniform float time;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, Uniform)]
@@ -56,6 +97,8 @@ pub struct ViewArgs {
     pub view: mat4,
     /// Premultiplied Proj-View matrix
     pub proj_view: mat4,
+    /// Seconds since the start of the application
+    pub time: float,
 }
 
 /// Tint
@@ -112,6 +155,100 @@ impl AsVertex for VertexArgs {
     }
 }
 
+/// Instance-rate arguments of meshes drawn with a custom material, with the per-entity
+/// material parameters.
+/// ```glsl
+///  mat4 model;
+///  vec4 tint;
+///  vec4 params[4];
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, align(16))]
+pub struct CustomMaterialArgs {
+    /// Instance-rate model matrix
+    pub model: mat4,
+    /// Instance-rate model `Tint`
+    pub tint: vec4,
+    /// Instance-rate material parameters, one per element
+    pub params: [vec4; 4],
+}
+
+impl CustomMaterialArgs {
+    /// Appends material parameters to the arguments of an instance.
+    #[must_use]
+    pub fn new(args: VertexArgs, params: [[f32; 4]; 4]) -> Self {
+        CustomMaterialArgs {
+            model: args.model,
+            tint: args.tint,
+            params: [
+                params[0].into(),
+                params[1].into(),
+                params[2].into(),
+                params[3].into(),
+            ],
+        }
+    }
+}
+
+impl AsVertex for CustomMaterialArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            Tint::vertex(),
+            (Format::Rgba32Sfloat, "params"),
+            (Format::Rgba32Sfloat, "params"),
+            (Format::Rgba32Sfloat, "params"),
+            (Format::Rgba32Sfloat, "params"),
+        ))
+    }
+}
+
+/// Instance-rate decal arguments
+/// ```glsl
+///  mat4 model;
+///  mat4 inverse_model;
+///  vec4 color;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, align(16))]
+pub struct DecalArgs {
+    /// Transform of the box the decal is projected in
+    pub model: mat4,
+    /// Inverse of the model matrix
+    pub inverse_model: mat4,
+    /// Linear color multiplied with the texture, including the fading
+    pub color: vec4,
+}
+
+impl DecalArgs {
+    /// Populates `DecalArgs` from the `Transform` of a decal and its color.
+    #[must_use]
+    pub fn from_object_data(transform: &Transform, color: [f32; 4]) -> Self {
+        let model = convert::<_, Matrix4<f32>>(*transform.global_matrix());
+        let inverse_model = model.try_inverse().unwrap_or_else(Matrix4::identity);
+        let model: [[f32; 4]; 4] = model.into();
+        let inverse_model: [[f32; 4]; 4] = inverse_model.into();
+        DecalArgs {
+            model: model.into(),
+            inverse_model: inverse_model.into(),
+            color: color.into(),
+        }
+    }
+}
+
+impl AsVertex for DecalArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::Rgba32Sfloat, "inverse_model"),
+            (Format::Rgba32Sfloat, "inverse_model"),
+            (Format::Rgba32Sfloat, "inverse_model"),
+            (Format::Rgba32Sfloat, "inverse_model"),
+            (Format::Rgba32Sfloat, "color"),
+        ))
+    }
+}
+
 /// Instance-rate joints offset
 /// ```glsl
 ///  uint joints_offset;
@@ -263,11 +400,90 @@ pub struct Environment {
     pub spot_light_count: int,
 }
 
+/// 2D environment uniform
+/// ```glsl
+/// uniform Environment2D {
+///    vec3 ambient_color;
+///    int light_count;
+///    int occluder_count;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Uniform)]
+pub struct Environment2D {
+    /// Ambient color for the entire image
+    pub ambient_color: vec3,
+    /// Number of 2D lights
+    pub light_count: int,
+    /// Number of shadow casting sprites
+    pub occluder_count: int,
+}
+
+/// 2D light struct
+/// ```glsl
+/// struct Light2D {
+///    vec3 position;
+///    float radius;
+///    vec3 color;
+///    int shadows;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Uniform)]
+#[repr(C, align(16))]
+pub struct Light2D {
+    /// World position and height of a point light, or unit vector towards a directional light
+    pub position: vec3,
+    /// Radius of a point light, `0` for directional lights
+    pub radius: float,
+    /// Linear light color multiplied by its intensity
+    pub color: vec3,
+    /// Whether shadow casting sprites block the light
+    pub shadows: int,
+}
+
+/// Shadow casting sprite struct
+/// ```glsl
+/// struct Occluder2D {
+///    vec4 inverse_axes;
+///    vec2 center;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Uniform)]
+#[repr(C, align(16))]
+pub struct Occluder2D {
+    /// Rows of the matrix transforming world offsets into the unit quad of the sprite
+    pub inverse_axes: vec4,
+    /// World position of the center of the sprite
+    pub center: vec2,
+}
+
+impl Occluder2D {
+    /// Occluder covering the quad of a sprite drawn with given transform, `None` when the quad
+    /// is degenerate.
+    #[must_use]
+    pub fn from_data(sprite: &Sprite, transform: &Transform) -> Option<Self> {
+        let transform = convert::<_, Matrix4<f32>>(*transform.global_matrix());
+        let dir_x = transform.column(0) * sprite.width;
+        let dir_y = transform.column(1) * -sprite.height;
+        let pos = transform * Vector4::new(-sprite.offsets[0], -sprite.offsets[1], 0.0, 1.0);
+
+        let det = dir_x.x * dir_y.y - dir_y.x * dir_x.y;
+        if det.abs() <= std::f32::EPSILON {
+            return None;
+        }
+        Some(Occluder2D {
+            inverse_axes: [dir_y.y / det, -dir_y.x / det, -dir_x.y / det, dir_x.x / det].into(),
+            center: pos.xy().into_pod(),
+        })
+    }
+}
+
 /// Material Uniform
 /// ```glsl
 /// uniform Material {
 ///    UvOffset uv_offset;
 ///    float alpha_cutoff;
+///    UvAnimation uv_animation;
+///    UvOffset lightmap_offset;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, Uniform)]
@@ -277,6 +493,10 @@ pub struct Material {
     pub uv_offset: TextureOffset,
     /// Material alpha cutoff
     pub alpha_cutoff: float,
+    /// UV animation of material
+    pub uv_animation: UvAnimation,
+    /// UV offset of the lightmap of material
+    pub lightmap_offset: TextureOffset,
 }
 
 impl Material {
@@ -286,6 +506,8 @@ impl Material {
         Material {
             uv_offset: TextureOffset::from_offset(&mat.uv_offset),
             alpha_cutoff: mat.alpha_cutoff,
+            uv_animation: UvAnimation::from_animation(&mat.uv_animation),
+            lightmap_offset: TextureOffset::from_offset(&mat.lightmap_offset),
         }
     }
 }
@@ -368,6 +590,160 @@ impl SpriteArgs {
     }
 }
 
+/// Billboard instance data
+/// ```glsl
+/// vec3 position;
+/// vec2 size;
+/// vec4 color;
+/// float sway;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C)]
+pub struct BillboardArgs {
+    /// World position of the bottom center of the billboard
+    pub position: [f32; 3],
+    /// Width and height of the billboard
+    pub size: [f32; 2],
+    /// Linear RGBA color the texture is multiplied with
+    pub color: [f32; 4],
+    /// How much the billboard bends with the wind
+    pub sway: f32,
+}
+
+impl AsVertex for BillboardArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rgb32Sfloat, "position"),
+            (Format::Rg32Sfloat, "size"),
+            (Format::Rgba32Sfloat, "color"),
+            (Format::R32Sfloat, "sway"),
+        ))
+    }
+}
+
+impl From<&crate::billboard::BillboardPlacement> for BillboardArgs {
+    fn from(placement: &crate::billboard::BillboardPlacement) -> Self {
+        BillboardArgs {
+            position: placement.position,
+            size: placement.size,
+            color: placement.color,
+            sway: placement.sway,
+        }
+    }
+}
+
+/// Instance-rate mesh outline arguments
+/// ```glsl
+/// mat4 model;
+/// vec4 color;
+/// float width;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C)]
+pub struct OutlineArgs {
+    /// Instance-rate model matrix
+    pub model: [[f32; 4]; 4],
+    /// Linear RGBA color of the outline
+    pub color: [f32; 4],
+    /// Distance the mesh is grown by along its normals, in world units
+    pub width: f32,
+}
+
+impl OutlineArgs {
+    /// Populates `OutlineArgs` from the `Transform` and `Outlined` components of a mesh.
+    #[must_use]
+    pub fn from_object_data(transform: &Transform, outlined: &Outlined) -> Self {
+        OutlineArgs {
+            model: convert::<_, Matrix4<f32>>(*transform.global_matrix()).into(),
+            color: outlined.color,
+            width: outlined.width,
+        }
+    }
+}
+
+impl AsVertex for OutlineArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::Rgba32Sfloat, "color"),
+            (Format::R32Sfloat, "width"),
+        ))
+    }
+}
+
+/// Instance-rate sprite outline arguments
+/// ```glsl
+/// vec2 dir_x;
+/// vec2 dir_y;
+/// vec3 pos;
+/// vec2 u_offset;
+/// vec2 v_offset;
+/// vec2 grow;
+/// vec4 color;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C)]
+pub struct SpriteOutlineArgs {
+    /// Rotation of the sprite, X-axis
+    pub dir_x: [f32; 2],
+    /// Rotation of the sprite, Y-axis
+    pub dir_y: [f32; 2],
+    /// Position and depth of the sprite
+    pub pos: [f32; 3],
+    /// Horizontal texture coordinates of the sprite in the spritesheet
+    pub u_offset: [f32; 2],
+    /// Vertical texture coordinates of the sprite in the spritesheet
+    pub v_offset: [f32; 2],
+    /// Outline width relative to the width and height of the sprite
+    pub grow: [f32; 2],
+    /// Linear RGBA color of the outline
+    pub color: [f32; 4],
+}
+
+impl SpriteOutlineArgs {
+    /// Populates `SpriteOutlineArgs` from a sprite and the `Transform` and `Outlined`
+    /// components of its entity.
+    #[must_use]
+    pub fn from_data(sprite: &Sprite, transform: &Transform, outlined: &Outlined) -> Self {
+        let transform = convert::<_, Matrix4<f32>>(*transform.global_matrix());
+        let dir_x = (transform.column(0) * sprite.width).xy();
+        let dir_y = (transform.column(1) * -sprite.height).xy();
+        let pos = transform * Vector4::new(-sprite.offsets[0], -sprite.offsets[1], 0.0, 1.0);
+        let grow = |dir: Vector2<f32>| {
+            let length = dir.norm();
+            if length > 0.0 {
+                outlined.width / length
+            } else {
+                0.0
+            }
+        };
+
+        SpriteOutlineArgs {
+            dir_x: dir_x.into(),
+            dir_y: dir_y.into(),
+            pos: [pos.x, pos.y, pos.z],
+            u_offset: [sprite.tex_coords.left, sprite.tex_coords.right],
+            v_offset: [sprite.tex_coords.top, sprite.tex_coords.bottom],
+            grow: [grow(dir_x), grow(dir_y)],
+            color: outlined.color,
+        }
+    }
+}
+
+impl AsVertex for SpriteOutlineArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rg32Sfloat, "dir_x"),
+            (Format::Rg32Sfloat, "dir_y"),
+            (Format::Rgb32Sfloat, "pos"),
+            (Format::Rg32Sfloat, "u_offset"),
+            (Format::Rg32Sfloat, "v_offset"),
+            (Format::Rg32Sfloat, "grow"),
+            (Format::Rgba32Sfloat, "color"),
+        ))
+    }
+}
+
 /// Trait for auto conversion into standard GLSL POD types.
 pub trait IntoPod<T> {
     /// Converts `Self` to the supplied `T` GLSL type.
@@ -388,30 +764,32 @@ impl IntoPod<vec3> for amethyst_core::math::Vector3<f32> {
     }
 }
 
+// Colors stored as `Srgb`/`Srgba` are authored in sRGB space, while every shader works on linear
+// values. Decode them here so lights, skyboxes and debug lines match sRGB textures and `Tint`.
 impl IntoPod<vec3> for palette::Srgb {
     fn into_pod(self) -> vec3 {
-        let (r, g, b) = self.into_components();
+        let (r, g, b) = self.into_linear().into_components();
         [r, g, b].into()
     }
 }
 
 impl IntoPod<[f32; 3]> for palette::Srgb {
     fn into_pod(self) -> [f32; 3] {
-        let (r, g, b) = self.into_components();
+        let (r, g, b) = self.into_linear().into_components();
         [r, g, b]
     }
 }
 
 impl IntoPod<vec4> for palette::Srgba {
     fn into_pod(self) -> vec4 {
-        let (r, g, b, a) = self.into_components();
+        let (r, g, b, a) = self.into_linear().into_components();
         [r, g, b, a].into()
     }
 }
 
 impl IntoPod<[f32; 4]> for palette::Srgba {
     fn into_pod(self) -> [f32; 4] {
-        let (r, g, b, a) = self.into_components();
+        let (r, g, b, a) = self.into_linear().into_components();
         [r, g, b, a]
     }
 }
@@ -0,0 +1,821 @@
+//! Reflection probes giving physically based materials local reflections.
+//!
+//! A [`ReflectionProbe`] holds a prefiltered specular cubemap of the surroundings of its entity.
+//! Materials rendered by `RenderPbr3D` within the radius of a probe reflect its cubemap instead
+//! of the specular map of the [`EnvironmentMap`](crate::environment_map::EnvironmentMap), the
+//! probes fading out into the environment over their blend distance. The nearest
+//! [`MAX_REFLECTION_PROBES`] probes to the camera are used.
+//!
+//! The cubemap of a probe is either baked, loaded from a cubemap with
+//! [`ReflectionProbe::from_cubemap`], or captured on the GPU by the [`RenderReflectionProbes`]
+//! plugin, when the probe is created with [`ReflectionProbe::new`] and whenever
+//! [`ReflectionProbe::capture`] is called. Captures render the scene from the probe into
+//! [`RenderReflectionProbes::target`], one face per frame, drawn by plugins configured with that
+//! target:
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderReflectionProbes::default())
+//!     .with_plugin(RenderPbr3D::default())
+//!     .with_plugin(RenderPbr3D::default().with_target(REFLECTION_PROBE_TARGET))
+//!     .with_plugin(RenderSkybox::default().with_target(REFLECTION_PROBE_TARGET))
+//! ```
+
+use std::{cmp::Ordering, iter::once};
+
+use amethyst_assets::{
+    AssetHandle, AssetStorage, DefaultLoader, Handle, LoadHandle, Loader, ProcessingQueue,
+};
+use amethyst_core::{
+    ecs::{
+        DispatcherBuilder, Entity, EntityStore, IntoQuery, ParallelRunnable, Resources, System,
+        SystemBuilder, World,
+    },
+    math::Vector3,
+    transform::Transform,
+};
+use amethyst_error::Error;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, Fence, Graphics, MultiShot,
+        PendingState, PrimaryLevel, Queue, QueueType, SimultaneousUse, Submission, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, GraphContext, ImageAccess, Node,
+        NodeBuffer, NodeBuildError, NodeDesc, NodeImage,
+    },
+    hal::{
+        self,
+        command::{ClearColor, ClearDepthStencil, ClearValue},
+        format::Format,
+        image::{Filter, Kind, ViewKind},
+    },
+    texture::{MipLevels, TextureBuilder},
+};
+
+use crate::{
+    bundle::{
+        ImageOptions, OutputColor, RenderPlan, RenderPlugin, Target, TargetImage, TargetPlanOutputs,
+    },
+    camera::Camera,
+    formats::cubemap::Cubemap,
+    pass::HDR_FORMAT,
+    render_target::RenderTarget,
+    system::GraphAuxData,
+    types::{Backend, Texture, TextureData},
+};
+
+/// Maximum number of reflection probes blended by the PBR pass.
+pub const MAX_REFLECTION_PROBES: usize = 4;
+
+/// Render target reflection probes are captured into by default.
+pub const REFLECTION_PROBE_TARGET: Target = Target::Custom("reflection_probes");
+
+/// Component reflecting a cubemap of its surroundings on the materials around its entity, see
+/// the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectionProbe {
+    /// Radius of the sphere around the entity whose materials reflect the probe.
+    pub radius: f32,
+    /// Distance inside the radius over which the probe fades into the environment.
+    pub blend_distance: f32,
+    /// Multiplier applied to the reflected light.
+    pub intensity: f32,
+    specular: Option<Handle<Texture>>,
+    capture_requested: bool,
+}
+
+impl ReflectionProbe {
+    /// Creates a probe of given radius, captured by [`RenderReflectionProbes`].
+    ///
+    /// The probe fades out over the outer quarter of its radius.
+    #[must_use]
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            blend_distance: radius * 0.25,
+            intensity: 1.0,
+            specular: None,
+            capture_requested: true,
+        }
+    }
+
+    /// Creates a probe of given radius reflecting a baked cubemap.
+    #[must_use]
+    pub fn from_cubemap(resources: &Resources, cubemap: Cubemap, radius: f32) -> Self {
+        let loader = resources.get::<DefaultLoader>().unwrap();
+        let queue = resources.get::<ProcessingQueue<TextureData>>().unwrap();
+
+        Self {
+            specular: Some(loader.load_from_data(cubemap.into_texture_data(true), (), &queue)),
+            capture_requested: false,
+            ..Self::new(radius)
+        }
+    }
+
+    /// Creates a probe of given radius reflecting a baked Radiance HDR cubemap with the six
+    /// faces stacked vertically, see [`HdrCubemapFormat`](crate::formats::cubemap::HdrCubemapFormat).
+    ///
+    /// # Errors
+    /// Results in an error if the cubemap can't be decoded.
+    pub fn from_hdr_cubemap(
+        resources: &Resources,
+        bytes: &[u8],
+        radius: f32,
+    ) -> Result<Self, Error> {
+        Ok(Self::from_cubemap(
+            resources,
+            Cubemap::from_hdr(bytes)?,
+            radius,
+        ))
+    }
+
+    /// Sets the distance inside the radius over which the probe fades into the environment.
+    #[must_use]
+    pub fn with_blend_distance(mut self, blend_distance: f32) -> Self {
+        self.blend_distance = blend_distance;
+        self
+    }
+
+    /// Sets the multiplier applied to the reflected light.
+    #[must_use]
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Captures the surroundings of the probe again, e.g. after the scene changed.
+    ///
+    /// The previous cubemap is reflected until the capture completes.
+    pub fn capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// Whether the probe waits for [`RenderReflectionProbes`] to capture it.
+    #[must_use]
+    pub fn is_capture_requested(&self) -> bool {
+        self.capture_requested
+    }
+
+    /// The prefiltered specular cubemap of the probe, once baked or captured.
+    #[must_use]
+    pub fn specular(&self) -> Option<&Handle<Texture>> {
+        self.specular.as_ref()
+    }
+
+    /// Weight of the probe at `distance` from its center, before blending with other probes.
+    #[must_use]
+    pub fn weight(&self, distance: f32) -> f32 {
+        ((self.radius - distance) / self.blend_distance.max(1e-4))
+            .max(0.0)
+            .min(1.0)
+    }
+}
+
+/// The probes influencing the view from `camera`, at most [`MAX_REFLECTION_PROBES`] of the
+/// nearest ones, smallest first so the most local probes take
+/// precedence when blending.
+pub(crate) fn nearest_probes<'a>(
+    camera: &Vector3<f32>,
+    probes: impl IntoIterator<Item = (Vector3<f32>, &'a ReflectionProbe)>,
+) -> Vec<(Vector3<f32>, &'a ReflectionProbe)> {
+    let mut probes: Vec<_> = probes
+        .into_iter()
+        .filter(|(_, probe)| probe.radius > 0.0)
+        .collect();
+    let distance = |(center, probe): &(Vector3<f32>, &ReflectionProbe)| {
+        ((center - camera).norm() - probe.radius).max(0.0)
+    };
+    probes.sort_by(|a, b| {
+        distance(a)
+            .partial_cmp(&distance(b))
+            .unwrap_or(Ordering::Equal)
+    });
+    probes.truncate(MAX_REFLECTION_PROBES);
+    probes.sort_by(|(_, a), (_, b)| a.radius.partial_cmp(&b.radius).unwrap_or(Ordering::Equal));
+    probes
+}
+
+/// Camera orientation capturing each face of a cubemap, as the direction looked at and the up
+/// direction, in `+X, -X, +Y, -Y, +Z, -Z` order.
+///
+/// The captured images are mirrored horizontally into the faces, as cubemaps look at their
+/// faces from the inside.
+const FACE_ORIENTATIONS: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+
+/// Progress of the capture of a [`ReflectionProbe`], shared by the
+/// [`ReflectionProbeCaptureSystem`] and the render graph.
+#[derive(Debug, Default)]
+pub struct ReflectionProbeCapture {
+    camera: Option<Entity>,
+    probe: Option<(Entity, Handle<Texture>)>,
+    /// Face rendered this frame, once the camera is in place
+    face: Option<usize>,
+    /// Face captured next
+    next_face: usize,
+    /// Last face copied into the cubemap
+    copied: Option<usize>,
+}
+
+impl ReflectionProbeCapture {
+    /// The probe being captured.
+    #[must_use]
+    pub fn probe(&self) -> Option<Entity> {
+        self.probe.as_ref().map(|(entity, _)| *entity)
+    }
+}
+
+/// Captures the [`ReflectionProbe`]s waiting for it, one at a time and one face per frame,
+/// placing a capture camera at the probe.
+#[derive(Debug)]
+pub struct ReflectionProbeCaptureSystem {
+    target: Target,
+    resolution: u32,
+}
+
+impl System for ReflectionProbeCaptureSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        let Self { target, resolution } = self;
+        Box::new(
+            SystemBuilder::new("ReflectionProbeCaptureSystem")
+                .write_resource::<ReflectionProbeCapture>()
+                .read_resource::<DefaultLoader>()
+                .read_resource::<ProcessingQueue<TextureData>>()
+                .read_resource::<AssetStorage<Texture>>()
+                .with_query(<(Entity, &mut ReflectionProbe, &Transform)>::query())
+                .with_query(<&mut Transform>::query())
+                .build(
+                    move |commands,
+                          world,
+                          (capture, loader, texture_queue, textures),
+                          (probes, _)| {
+                        // The capture advances once the face rendered last frame is copied.
+                        if let Some(face) = capture.face.take() {
+                            if capture.copied == Some(face) {
+                                capture.next_face = face + 1;
+                            }
+                        }
+                        if capture.next_face == FACE_ORIENTATIONS.len() {
+                            if let Some((entity, texture)) = capture.probe.take() {
+                                if let Some((_, probe, _)) = probes
+                                    .iter_mut(world)
+                                    .find(|(probe_entity, _, _)| **probe_entity == entity)
+                                {
+                                    probe.specular = Some(texture);
+                                }
+                            }
+                        }
+
+                        if capture.probe.is_none() {
+                            let requested = probes
+                                .iter_mut(world)
+                                .find(|(_, probe, _)| probe.capture_requested);
+                            if let Some((entity, probe, _)) = requested {
+                                probe.capture_requested = false;
+                                let texture = loader.load_from_data(
+                                    capture_texture_data(resolution),
+                                    (),
+                                    texture_queue,
+                                );
+                                capture.probe = Some((*entity, texture));
+                                capture.next_face = 0;
+                                capture.copied = None;
+                            }
+                        }
+
+                        // The capture waits until its cubemap is created.
+                        let entity = match &capture.probe {
+                            Some((entity, texture)) if textures.get(texture).is_some() => *entity,
+                            _ => return,
+                        };
+                        let position = match probes
+                            .iter_mut(world)
+                            .find(|(probe_entity, _, _)| **probe_entity == entity)
+                        {
+                            Some((_, _, transform)) => transform.global_matrix().column(3).xyz(),
+                            None => {
+                                capture.probe = None;
+                                return;
+                            }
+                        };
+
+                        let (forward, up) = FACE_ORIENTATIONS[capture.next_face];
+                        let positioned = capture.camera.map_or(false, |camera| {
+                            world
+                                .entry_mut(camera)
+                                .ok()
+                                .and_then(|mut entry| {
+                                    let transform = entry.get_component_mut::<Transform>().ok()?;
+                                    transform.set_translation(position);
+                                    transform
+                                        .face_towards(position + Vector3::from(forward), up.into());
+                                    transform.copy_local_to_global();
+                                    Some(())
+                                })
+                                .is_some()
+                        });
+                        if positioned {
+                            capture.face = Some(capture.next_face);
+                        } else {
+                            capture.camera = Some(commands.push((
+                                Camera::perspective(1.0, std::f32::consts::FRAC_PI_2, 0.1),
+                                Transform::default(),
+                                RenderTarget::bind(target),
+                            )));
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Blank `Rgba16Sfloat` cube texture data with a full mip chain, captured into.
+fn capture_texture_data(resolution: u32) -> TextureData {
+    TextureBuilder::new()
+        .with_kind(Kind::D2(resolution, resolution, 6, 1))
+        .with_view_kind(ViewKind::Cube)
+        .with_data_width(resolution)
+        .with_data_height(resolution)
+        .with_sampler_info(hal::image::SamplerDesc::new(
+            Filter::Linear,
+            hal::image::WrapMode::Clamp,
+        ))
+        .with_raw_data(
+            vec![0_u8; (resolution * resolution * 6 * 8) as usize],
+            Format::Rgba16Sfloat,
+        )
+        .with_mip_levels(MipLevels::GenerateAuto)
+        .into()
+}
+
+/// A [`RenderPlugin`] capturing the [`ReflectionProbe`]s waiting for it, see the
+/// [module documentation](self).
+///
+/// The capture target is only part of the render graph while a probe is captured.
+#[derive(Debug)]
+pub struct RenderReflectionProbes {
+    target: Target,
+    resolution: u32,
+    capturing: bool,
+}
+
+impl Default for RenderReflectionProbes {
+    fn default() -> Self {
+        Self {
+            target: REFLECTION_PROBE_TARGET,
+            resolution: 128,
+            capturing: false,
+        }
+    }
+}
+
+impl RenderReflectionProbes {
+    /// Capture the probes into given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set the size of the faces of the captured cubemaps in pixels.
+    #[must_use]
+    pub fn with_resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Render target the probes are captured into.
+    #[must_use]
+    pub fn target(&self) -> Target {
+        self.target
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderReflectionProbes {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_default::<ReflectionProbeCapture>();
+        resources.get_or_default::<ProcessingQueue<TextureData>>();
+        builder.add_system(ReflectionProbeCaptureSystem {
+            target: self.target,
+            resolution: self.resolution,
+        });
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, _world: &World, resources: &Resources) -> bool {
+        let capturing = resources
+            .get::<ReflectionProbeCapture>()
+            .map_or(false, |capture| capture.probe.is_some());
+        capturing != self.capturing
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        resources: &Resources,
+    ) -> Result<(), Error> {
+        self.capturing = resources
+            .get::<ReflectionProbeCapture>()
+            .map_or(false, |capture| capture.probe.is_some());
+        if !self.capturing {
+            return Ok(());
+        }
+
+        let kind = Kind::D2(self.resolution, self.resolution, 1, 1);
+        plan.define_pass(
+            self.target,
+            TargetPlanOutputs {
+                colors: vec![OutputColor::Image(ImageOptions {
+                    kind,
+                    levels: 1,
+                    format: HDR_FORMAT,
+                    clear: Some(ClearValue {
+                        color: ClearColor {
+                            float32: [0.0, 0.0, 0.0, 1.0],
+                        },
+                    }),
+                })],
+                depth: Some(ImageOptions {
+                    kind,
+                    levels: 1,
+                    format: Format::D32Sfloat,
+                    clear: Some(ClearValue {
+                        depth_stencil: ClearDepthStencil {
+                            depth: 0.0,
+                            stencil: 0,
+                        },
+                    }),
+                }),
+            },
+        )?;
+
+        // Like render targets, the copy is scheduled from the main target.
+        let target = self.target;
+        plan.extend_target(Target::Main, move |ctx| {
+            let image = ctx.get_image(TargetImage::Color(target, 0))?;
+            let source = ctx.get_node(target)?;
+            let copy = ctx.graph().add_node(
+                CopyToProbeDesc
+                    .builder()
+                    .with_image(image)
+                    .with_dependency(source),
+            );
+            ctx.add_dep(copy);
+            Ok(())
+        });
+
+        Ok(())
+    }
+}
+
+/// Render graph node copying its input image into the face of the probe cubemap captured
+/// this frame, generating the mip chain once the last face is copied.
+#[derive(Debug)]
+struct CopyToProbeDesc;
+
+impl<B: Backend> NodeDesc<B, GraphAuxData> for CopyToProbeDesc {
+    type Node = CopyToProbe<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        Vec::new()
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::TRANSFER_READ,
+            usage: hal::image::Usage::TRANSFER_SRC,
+            layout: hal::image::Layout::TransferSrcOptimal,
+            stages: hal::pso::PipelineStage::TRANSFER,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &GraphAuxData,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, NodeBuildError> {
+        assert!(buffers.is_empty());
+        assert_eq!(images.len(), 1);
+
+        let pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?;
+
+        Ok(CopyToProbe {
+            image: images.into_iter().next().unwrap(),
+            pool,
+            recorded: None,
+            pending: Vec::new(),
+        })
+    }
+}
+
+type PendingBuffer<B> = CommandBuffer<
+    B,
+    QueueType,
+    PendingState<ExecutableState<MultiShot<SimultaneousUse>>>,
+    PrimaryLevel,
+>;
+
+#[derive(Debug)]
+struct CopyToProbe<B: Backend> {
+    image: NodeImage,
+    pool: CommandPool<B, QueueType>,
+    /// Copies into each face of the cubemap with given load handle
+    recorded: Option<(LoadHandle, Vec<Submit<B, SimultaneousUse>>)>,
+    pending: Vec<PendingBuffer<B>>,
+}
+
+impl<B: Backend> CopyToProbe<B> {
+    /// Records the copy into a face of the cubemap, followed by the mip chain generation for
+    /// the last face.
+    fn record(
+        &mut self,
+        ctx: &GraphContext<B>,
+        texture: &rendy::texture::Texture<B>,
+        face: u16,
+    ) -> Submit<B, SimultaneousUse> {
+        let source = ctx
+            .get_image(self.image.id)
+            .expect("Reflection probe capture image does not exist");
+        let extent = source.kind().extent();
+        let target = texture.image();
+        let size = target.kind().extent().width as i32;
+        let levels = target.levels();
+
+        let mut buffer = self
+            .pool
+            .allocate_buffers(1)
+            .pop()
+            .unwrap()
+            .begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = buffer.encoder();
+
+        let range = |levels: std::ops::Range<u8>, layers: std::ops::Range<u16>| {
+            hal::image::SubresourceRange {
+                aspects: hal::format::Aspects::COLOR,
+                levels,
+                layers,
+            }
+        };
+        let layers = |level: u8, layers: std::ops::Range<u16>| {
+            hal::image::SubresourceLayers {
+                aspects: hal::format::Aspects::COLOR,
+                level,
+                layers,
+            }
+        };
+        let shader_read = (
+            hal::image::Access::SHADER_READ,
+            hal::image::Layout::ShaderReadOnlyOptimal,
+        );
+        let transfer_write = (
+            hal::image::Access::TRANSFER_WRITE,
+            hal::image::Layout::TransferDstOptimal,
+        );
+        let transfer_read = (
+            hal::image::Access::TRANSFER_READ,
+            hal::image::Layout::TransferSrcOptimal,
+        );
+        let barrier = |states: std::ops::Range<_>, range| {
+            hal::memory::Barrier::Image {
+                states,
+                target: target.raw(),
+                families: None,
+                range,
+            }
+        };
+
+        unsafe {
+            gfx_acquire_barriers(ctx, None, Some(&self.image), &mut encoder);
+
+            encoder.pipeline_barrier(
+                hal::pso::PipelineStage::FRAGMENT_SHADER..hal::pso::PipelineStage::TRANSFER,
+                hal::memory::Dependencies::empty(),
+                once(barrier(
+                    shader_read..transfer_write,
+                    range(0..1, face..face + 1),
+                )),
+            );
+
+            encoder.blit_image(
+                source.raw(),
+                hal::image::Layout::TransferSrcOptimal,
+                target.raw(),
+                hal::image::Layout::TransferDstOptimal,
+                Filter::Linear,
+                once(hal::command::ImageBlit {
+                    src_subresource: layers(0, 0..1),
+                    src_bounds: hal::image::Offset::ZERO..hal::image::Offset {
+                        x: extent.width as i32,
+                        y: extent.height as i32,
+                        z: 1,
+                    },
+                    dst_subresource: layers(0, face..face + 1),
+                    dst_bounds: hal::image::Offset {
+                        x: size,
+                        y: 0,
+                        z: 0,
+                    }..hal::image::Offset {
+                        x: 0,
+                        y: size,
+                        z: 1,
+                    },
+                }),
+            );
+
+            if usize::from(face) + 1 < FACE_ORIENTATIONS.len() {
+                encoder.pipeline_barrier(
+                    hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::FRAGMENT_SHADER,
+                    hal::memory::Dependencies::empty(),
+                    once(barrier(
+                        transfer_write..shader_read,
+                        range(0..1, face..face + 1),
+                    )),
+                );
+            } else {
+                // Each level of the mip chain is downsampled from the previous one.
+                encoder.pipeline_barrier(
+                    hal::pso::PipelineStage::FRAGMENT_SHADER | hal::pso::PipelineStage::TRANSFER
+                        ..hal::pso::PipelineStage::TRANSFER,
+                    hal::memory::Dependencies::empty(),
+                    vec![
+                        barrier(shader_read..transfer_read, range(0..1, 0..face)),
+                        barrier(transfer_write..transfer_read, range(0..1, face..face + 1)),
+                    ],
+                );
+                for level in 1..levels {
+                    let source_size = (size >> (level - 1)).max(1);
+                    let level_size = (size >> level).max(1);
+                    encoder.pipeline_barrier(
+                        hal::pso::PipelineStage::FRAGMENT_SHADER..hal::pso::PipelineStage::TRANSFER,
+                        hal::memory::Dependencies::empty(),
+                        once(barrier(
+                            shader_read..transfer_write,
+                            range(level..level + 1, 0..face + 1),
+                        )),
+                    );
+                    encoder.blit_image(
+                        target.raw(),
+                        hal::image::Layout::TransferSrcOptimal,
+                        target.raw(),
+                        hal::image::Layout::TransferDstOptimal,
+                        Filter::Linear,
+                        once(hal::command::ImageBlit {
+                            src_subresource: layers(level - 1, 0..face + 1),
+                            src_bounds: hal::image::Offset::ZERO..hal::image::Offset {
+                                x: source_size,
+                                y: source_size,
+                                z: 1,
+                            },
+                            dst_subresource: layers(level, 0..face + 1),
+                            dst_bounds: hal::image::Offset::ZERO..hal::image::Offset {
+                                x: level_size,
+                                y: level_size,
+                                z: 1,
+                            },
+                        }),
+                    );
+                    encoder.pipeline_barrier(
+                        hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::TRANSFER,
+                        hal::memory::Dependencies::empty(),
+                        once(barrier(
+                            transfer_write..transfer_read,
+                            range(level..level + 1, 0..face + 1),
+                        )),
+                    );
+                }
+                encoder.pipeline_barrier(
+                    hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::FRAGMENT_SHADER,
+                    hal::memory::Dependencies::empty(),
+                    once(barrier(
+                        transfer_read..shader_read,
+                        range(0..levels, 0..face + 1),
+                    )),
+                );
+            }
+
+            gfx_release_barriers(ctx, None, Some(&self.image), &mut encoder);
+        }
+
+        let (submit, buffer) = buffer.finish().submit();
+        self.pending.push(buffer);
+        submit
+    }
+}
+
+impl<B: Backend> Node<B, GraphAuxData> for CopyToProbe<B> {
+    type Capability = Graphics;
+
+    fn run<'a>(
+        &mut self,
+        ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        queue: &mut Queue<B>,
+        aux: &GraphAuxData,
+        _frames: &Frames<B>,
+        waits: &[(&'a B::Semaphore, hal::pso::PipelineStage)],
+        signals: &[&'a B::Semaphore],
+        fence: Option<&mut Fence<B>>,
+    ) {
+        let mut capture = aux.resources.get_mut::<ReflectionProbeCapture>().unwrap();
+        let storage = aux.resources.get::<AssetStorage<Texture>>().unwrap();
+        let texture = capture.probe.as_ref().and_then(|(_, handle)| {
+            storage
+                .get(handle)
+                .and_then(B::unwrap_texture)
+                .map(|texture| (handle.load_handle(), texture))
+        });
+
+        let mut submit = None;
+        if let (Some(face), Some((handle, texture))) = (capture.face, texture) {
+            if capture.copied != Some(face) {
+                if self.recorded.as_ref().map(|(recorded, _)| *recorded) != Some(handle) {
+                    let submits = (0..FACE_ORIENTATIONS.len() as u16)
+                        .map(|face| self.record(ctx, texture, face))
+                        .collect();
+                    self.recorded = Some((handle, submits));
+                }
+                submit = self.recorded.as_ref().map(|(_, submits)| &submits[face]);
+                capture.copied = Some(face);
+            }
+        }
+
+        let submission = Submission::new()
+            .submits(submit)
+            .wait(waits.iter().cloned())
+            .signal(signals.iter().cloned());
+
+        unsafe {
+            queue.submit(once(submission), fence);
+        }
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        self.recorded.take();
+        let buffers = self.pending.drain(..).map(|buffer| buffer.mark_complete());
+        self.pool.free_buffers(buffers);
+        factory.destroy_command_pool(self.pool);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weight_fades_over_blend_distance() {
+        let probe = ReflectionProbe::new(4.0).with_blend_distance(2.0);
+
+        assert_eq!(probe.weight(0.0), 1.0);
+        assert_eq!(probe.weight(2.0), 1.0);
+        assert!((probe.weight(3.0) - 0.5).abs() < 1e-6);
+        assert_eq!(probe.weight(5.0), 0.0);
+    }
+
+    #[test]
+    fn nearest_probes_are_sorted_by_radius() {
+        let (small, large, empty) = (
+            ReflectionProbe::new(1.0),
+            ReflectionProbe::new(10.0),
+            ReflectionProbe::new(0.0),
+        );
+        let mut probes = vec![
+            (Vector3::new(100.0, 0.0, 0.0), &small),
+            (Vector3::new(5.0, 0.0, 0.0), &large),
+            (Vector3::new(2.0, 0.0, 0.0), &small),
+            (Vector3::new(0.0, 0.0, 0.0), &empty),
+        ];
+        for x in 0..MAX_REFLECTION_PROBES {
+            probes.push((Vector3::new(50.0 + x as f32, 0.0, 0.0), &small));
+        }
+
+        let nearest = nearest_probes(&Vector3::zeros(), probes);
+
+        assert_eq!(nearest.len(), MAX_REFLECTION_PROBES);
+        assert_eq!(nearest[0].0.x, 2.0);
+        assert!(nearest[1..3].iter().all(|(center, _)| center.x >= 50.0));
+        assert_eq!(nearest[3].0.x, 5.0);
+    }
+}
@@ -0,0 +1,251 @@
+//! Rendering the scene below window resolution, to keep the frame rate up on weak GPUs.
+//!
+//! `RenderToWindow::with_render_scale` defines a [`Target::Scaled`] render target, a fraction of
+//! the window size given by the [`RenderScale`] resource. Plugins drawing the scene should be
+//! configured to render into it, e.g. `RenderPbr3D::default().with_target(Target::Scaled)`. It is
+//! upscaled into the window before other plugins draw into `Main`, so the UI stays sharp.
+//!
+//! The scale can be changed at runtime, rebuilding the render graph, or adjusted automatically
+//! by the [`RenderScaleSystem`] to keep the frame time under a target with [`AutoRenderScale`].
+//!
+//! The graph is rebuilt rather than drawing into a part of a full size image, because the
+//! pipelines of the render passes bake the viewport of their framebuffer. Frames during which the
+//! graph was rebuilt, for any reason, are left out of the frame time average.
+//!
+//! [`Target::Scaled`]: crate::bundle::Target::Scaled
+
+use amethyst_core::{
+    ecs::{ParallelRunnable, System, SystemBuilder},
+    Time,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::graph_info::RenderGraphInfo;
+
+/// Lowest supported render scale.
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+
+/// Number of frames averaged before the render scale is adjusted automatically.
+const AUTO_SCALE_FRAMES: u32 = 30;
+
+/// Resolution of the scene relative to the window, see the [module documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderScale {
+    /// Fraction of the window resolution the scene is rendered at, between
+    /// [`MIN_RENDER_SCALE`] and `1.0`.
+    pub scale: f32,
+    /// Adjusts the scale to the frame time when set.
+    pub auto: Option<AutoRenderScale>,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            auto: None,
+        }
+    }
+}
+
+impl RenderScale {
+    /// Render the scene at given fraction of the window resolution.
+    #[must_use]
+    pub fn new(scale: f32) -> Self {
+        Self { scale, auto: None }
+    }
+
+    /// Scale clamped to the supported range.
+    #[must_use]
+    pub fn clamped(&self) -> f32 {
+        if self.scale.is_nan() {
+            return 1.0;
+        }
+        self.scale.max(MIN_RENDER_SCALE).min(1.0)
+    }
+
+    /// Size of the scene image for a window of given size, at least one pixel.
+    #[must_use]
+    pub fn apply(&self, width: u32, height: u32) -> (u32, u32) {
+        let scale = self.clamped();
+        let scaled = |size: u32| ((size as f32 * scale).round() as u32).max(1);
+        (scaled(width), scaled(height))
+    }
+}
+
+/// Automatic adjustment of the [`RenderScale`] to the frame time.
+///
+/// The scale is lowered by `step` while the average frame time exceeds the target by more than
+/// `tolerance`, and raised again when frames take well under the target, by less than 80% of it.
+/// The tolerance keeps frames synchronized to the display, which take about the target frame
+/// time, from lowering the scale. Every change rebuilds the render graph, so the step should not
+/// be too small.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoRenderScale {
+    /// Frame time to stay under, in seconds
+    pub target_frame_time: f32,
+    /// Lowest scale the adjustment goes down to
+    pub min_scale: f32,
+    /// Highest scale the adjustment goes up to
+    pub max_scale: f32,
+    /// Change of the scale per adjustment
+    pub step: f32,
+    /// Fraction of the target the average frame time may exceed it by before the scale is
+    /// lowered
+    pub tolerance: f32,
+}
+
+impl Default for AutoRenderScale {
+    fn default() -> Self {
+        Self {
+            target_frame_time: 1.0 / 60.0,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            step: 0.1,
+            tolerance: 0.1,
+        }
+    }
+}
+
+impl AutoRenderScale {
+    /// Keep the frame time under given target, in seconds.
+    #[must_use]
+    pub fn new(target_frame_time: f32) -> Self {
+        Self {
+            target_frame_time,
+            ..Self::default()
+        }
+    }
+
+    /// Scale to render at next, given the current one and the average frame time in seconds.
+    #[must_use]
+    pub fn adjust(&self, scale: f32, frame_time: f32) -> f32 {
+        let adjusted = if frame_time > self.target_frame_time * (1.0 + self.tolerance) {
+            scale - self.step
+        } else if frame_time < self.target_frame_time * 0.8 {
+            scale + self.step
+        } else {
+            scale
+        };
+        adjusted
+            .max(self.min_scale.max(MIN_RENDER_SCALE))
+            .min(self.max_scale.min(1.0))
+    }
+}
+
+/// Adjusts the [`RenderScale`] resource to the average frame time when it has an
+/// [`AutoRenderScale`], leaving out the frames during which the render graph was rebuilt.
+///
+/// Added by `RenderToWindow` when rendering at a render scale.
+#[derive(Debug)]
+pub struct RenderScaleSystem;
+
+impl System for RenderScaleSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        let mut frames = 0;
+        let mut elapsed = 0.0;
+        let mut builds = 0;
+
+        Box::new(
+            SystemBuilder::new("RenderScaleSystem")
+                .read_resource::<Time>()
+                .read_resource::<RenderGraphInfo>()
+                .write_resource::<RenderScale>()
+                .build(move |_commands, _world, (time, graph_info, render_scale), _query| {
+                    let auto = match render_scale.auto {
+                        Some(auto) => auto,
+                        None => {
+                            frames = 0;
+                            elapsed = 0.0;
+                            return;
+                        }
+                    };
+
+                    // the last frame rebuilt the graph, its time doesn't reflect the scale
+                    if graph_info.builds != builds {
+                        builds = graph_info.builds;
+                        frames = 0;
+                        elapsed = 0.0;
+                        return;
+                    }
+
+                    frames += 1;
+                    elapsed += time.delta_real_time().as_secs_f32();
+                    if frames < AUTO_SCALE_FRAMES {
+                        return;
+                    }
+
+                    let scale = auto.adjust(render_scale.clamped(), elapsed / frames as f32);
+                    if (scale - render_scale.scale).abs() > std::f32::EPSILON {
+                        render_scale.scale = scale;
+                    }
+                    frames = 0;
+                    elapsed = 0.0;
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use amethyst_core::ecs::{DispatcherBuilder, Resources, World};
+
+    use super::*;
+
+    #[test]
+    fn scaled_size_is_clamped() {
+        assert_eq!(RenderScale::new(0.5).apply(1280, 720), (640, 360));
+        assert_eq!(RenderScale::new(2.0).apply(1280, 720), (1280, 720));
+        assert_eq!(RenderScale::new(0.0).apply(1280, 720), (320, 180));
+        assert_eq!(RenderScale::new(0.5).apply(1, 1), (1, 1));
+    }
+
+    #[test]
+    fn auto_scale_follows_frame_time() {
+        let auto = AutoRenderScale::new(0.016);
+        assert!((auto.adjust(1.0, 0.020) - 0.9).abs() < 1e-6);
+        assert!((auto.adjust(0.5, 0.030) - 0.5).abs() < 1e-6);
+        assert!((auto.adjust(0.7, 0.010) - 0.8).abs() < 1e-6);
+        assert!((auto.adjust(0.7, 0.014) - 0.7).abs() < 1e-6);
+        assert!((auto.adjust(1.0, 0.005) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn auto_scale_tolerates_vsync() {
+        let auto = AutoRenderScale::default();
+        assert!((auto.adjust(1.0, 1.0 / 59.5) - 1.0).abs() < 1e-6);
+        assert!((auto.adjust(1.0, 1.0 / 50.0) - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rebuild_frames_are_not_averaged() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let mut time = Time::default();
+        time.set_delta_time(Duration::from_millis(100));
+        resources.insert(time);
+        resources.insert(RenderGraphInfo::default());
+        resources.insert(RenderScale {
+            scale: 1.0,
+            auto: Some(AutoRenderScale::default()),
+        });
+        let mut builder = DispatcherBuilder::default();
+        builder.add_system(RenderScaleSystem);
+        let mut dispatcher = builder.build(&mut world, &mut resources).unwrap();
+
+        for _ in 1..AUTO_SCALE_FRAMES {
+            dispatcher.execute(&mut world, &mut resources);
+        }
+        resources.get_mut::<RenderGraphInfo>().unwrap().builds += 1;
+        dispatcher.execute(&mut world, &mut resources);
+        assert!((resources.get::<RenderScale>().unwrap().scale - 1.0).abs() < 1e-6);
+
+        for _ in 0..AUTO_SCALE_FRAMES {
+            dispatcher.execute(&mut world, &mut resources);
+        }
+        assert!((resources.get::<RenderScale>().unwrap().scale - 0.9).abs() < 1e-6);
+    }
+}
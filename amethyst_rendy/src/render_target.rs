@@ -0,0 +1,510 @@
+//! Render targets driven by camera entities.
+//!
+//! Attaching a [`RenderTarget`] with a texture to an entity with a [`Camera`] makes the
+//! [`RenderToTexture`] plugin render that camera's view into an offscreen image every frame, and
+//! copy the result into a regular [`Texture`] asset. The texture can then be used like any other
+//! texture, e.g. as the albedo of a [`Material`](crate::Material) for security monitors, mirrors
+//! or portals.
+//!
+//! Other render plugins draw into the offscreen target when configured with its [`Target`]:
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToTexture::default())
+//!     .with_plugin(RenderPbr3D::default())
+//!     .with_plugin(RenderPbr3D::default().with_target(Target::Custom("mirror")))
+//! ```
+
+use std::iter::once;
+
+use amethyst_assets::{AssetStorage, DefaultLoader, Handle, Loader, ProcessingQueue};
+use amethyst_core::ecs::{DispatcherBuilder, Entity, IntoQuery, Resources, World};
+use amethyst_error::Error;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, Fence, Graphics, MultiShot,
+        PendingState, PrimaryLevel, Queue, QueueType, SimultaneousUse, Submission, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, GraphContext, ImageAccess, Node,
+        NodeBuffer, NodeBuildError, NodeDesc, NodeImage,
+    },
+    hal::{
+        self,
+        command::{ClearColor, ClearDepthStencil, ClearValue},
+        format::Format,
+        image::{Extent, Filter, Kind, ViewKind},
+    },
+    texture::TextureBuilder,
+};
+
+use crate::{
+    bundle::{
+        ImageOptions, OutputColor, RenderPlan, RenderPlugin, Target, TargetImage, TargetPlanOutputs,
+    },
+    camera::Camera,
+    system::GraphAuxData,
+    types::{Backend, Texture, TextureData},
+};
+
+/// Binds the [`Camera`] on the same entity to the render target it renders into, instead of the
+/// main window.
+///
+/// Render plugins configured with `target` draw into it, using this entity as their camera. The
+/// target is either defined by another plugin, e.g. `Target::Window` presented to a secondary
+/// window by `RenderToSecondaryWindows`, see [`RenderTarget::bind`], or rendered offscreen by
+/// [`RenderToTexture`] and copied into a texture, see [`RenderTarget::new`].
+///
+/// Cameras bound to a target are never used as the fallback when there is no
+/// [`ActiveCamera`](crate::ActiveCamera). Changing `target` or the size of the texture rebuilds
+/// the render graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderTarget {
+    /// Render target identifier, usually `Target::Custom` or `Target::Window`.
+    pub target: Target,
+    /// Texture the target is rendered into offscreen, if it isn't defined by another plugin.
+    pub texture: Option<TargetTexture>,
+}
+
+/// Offscreen image of a [`RenderTarget`], copied into a texture every frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetTexture {
+    /// Width of the offscreen image in pixels.
+    pub width: u32,
+    /// Height of the offscreen image in pixels.
+    pub height: u32,
+    /// Texture the rendered image is copied to every frame.
+    pub texture: Handle<Texture>,
+    /// Linear RGBA color the target is cleared with every frame.
+    pub clear: [f32; 4],
+}
+
+impl RenderTarget {
+    /// Creates a render target of given size rendering into `texture`.
+    ///
+    /// The texture should have the same size as the target, see [`RenderTarget::create_texture`].
+    /// Otherwise only the area the texture and the target have in common is copied, unscaled.
+    #[must_use]
+    pub fn new(target: Target, width: u32, height: u32, texture: Handle<Texture>) -> Self {
+        Self {
+            target,
+            texture: Some(TargetTexture {
+                width,
+                height,
+                texture,
+                clear: [0.0, 0.0, 0.0, 1.0],
+            }),
+        }
+    }
+
+    /// Binds the camera to `target`, defined by another plugin.
+    #[must_use]
+    pub fn bind(target: Target) -> Self {
+        Self {
+            target,
+            texture: None,
+        }
+    }
+
+    /// Set the color the offscreen target is cleared with every frame.
+    #[must_use]
+    pub fn with_clear(mut self, clear: [f32; 4]) -> Self {
+        if let Some(texture) = &mut self.texture {
+            texture.clear = clear;
+        }
+        self
+    }
+
+    /// Loads a blank texture suitable as the destination of a render target of given size.
+    #[must_use]
+    pub fn create_texture(resources: &Resources, width: u32, height: u32) -> Handle<Texture> {
+        let loader = resources.get::<DefaultLoader>().unwrap();
+        let queue = resources.get::<ProcessingQueue<TextureData>>().unwrap();
+
+        let builder = TextureBuilder::new()
+            .with_kind(Kind::D2(width, height, 1, 1))
+            .with_view_kind(ViewKind::D2)
+            .with_data_width(width)
+            .with_data_height(height)
+            .with_raw_data(vec![0_u8; width as usize * height as usize * 4], Format::Rgba8Srgb);
+
+        loader.load_from_data(builder.into(), (), &queue)
+    }
+}
+
+/// A [`RenderPlugin`] that renders every [`RenderTarget`] with a texture into it.
+///
+/// The render graph is rebuilt automatically when targets are added, removed or resized.
+#[derive(Default, Debug)]
+pub struct RenderToTexture {
+    targets: Vec<(Entity, Target, TargetTexture)>,
+}
+
+impl RenderToTexture {
+    fn gather_targets(world: &World) -> Vec<(Entity, Target, TargetTexture)> {
+        let mut targets: Vec<_> = <(Entity, &RenderTarget, &Camera)>::query()
+            .iter(world)
+            .filter_map(|(entity, target, _)| {
+                Some((*entity, target.target, target.texture.clone()?))
+            })
+            .collect();
+        targets.sort_by_key(|(entity, _, _)| *entity);
+        targets
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderToTexture {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        _builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_default::<ProcessingQueue<TextureData>>();
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World, _resources: &Resources) -> bool {
+        let targets = Self::gather_targets(world);
+        if targets == self.targets {
+            false
+        } else {
+            self.targets = targets;
+            true
+        }
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        self.targets = Self::gather_targets(world);
+
+        for (_, target, render_texture) in &self.targets {
+            let kind = Kind::D2(render_texture.width, render_texture.height, 1, 1);
+
+            plan.define_pass(
+                *target,
+                TargetPlanOutputs {
+                    colors: vec![OutputColor::Image(ImageOptions {
+                        kind,
+                        levels: 1,
+                        format: Format::Rgba8Srgb,
+                        clear: Some(ClearValue {
+                            color: ClearColor {
+                                float32: render_texture.clear,
+                            },
+                        }),
+                    })],
+                    depth: Some(ImageOptions {
+                        kind,
+                        levels: 1,
+                        format: Format::D32Sfloat,
+                        clear: Some(ClearValue {
+                            depth_stencil: ClearDepthStencil {
+                                depth: 0.0,
+                                stencil: 0,
+                            },
+                        }),
+                    }),
+                },
+            )?;
+
+            // The copy is scheduled from the main target, so offscreen targets are only
+            // evaluated when something is actually presented.
+            let target = *target;
+            let texture = render_texture.texture.clone();
+            plan.extend_target(Target::Main, move |ctx| {
+                let image = ctx.get_image(TargetImage::Color(target, 0))?;
+                let source = ctx.get_node(target)?;
+                let copy = ctx.graph().add_node(
+                    CopyToTextureDesc { texture }
+                        .builder()
+                        .with_image(image)
+                        .with_dependency(source),
+                );
+                ctx.add_dep(copy);
+                Ok(())
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Render graph node copying its input image into a `Texture` asset.
+#[derive(Debug)]
+struct CopyToTextureDesc {
+    texture: Handle<Texture>,
+}
+
+impl<B: Backend> NodeDesc<B, GraphAuxData> for CopyToTextureDesc {
+    type Node = CopyToTexture<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        Vec::new()
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::TRANSFER_READ,
+            usage: hal::image::Usage::TRANSFER_SRC,
+            layout: hal::image::Layout::TransferSrcOptimal,
+            stages: hal::pso::PipelineStage::TRANSFER,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &GraphAuxData,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, NodeBuildError> {
+        assert!(buffers.is_empty());
+        assert_eq!(images.len(), 1);
+
+        let pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?;
+
+        Ok(CopyToTexture {
+            texture: self.texture,
+            image: images.into_iter().next().unwrap(),
+            pool,
+            submit: None,
+            buffer: None,
+            stale: Vec::new(),
+            version: None,
+        })
+    }
+}
+
+type CopyBuffer<B> = CommandBuffer<
+    B,
+    QueueType,
+    PendingState<ExecutableState<MultiShot<SimultaneousUse>>>,
+    PrimaryLevel,
+>;
+
+#[derive(Debug)]
+struct CopyToTexture<B: Backend> {
+    texture: Handle<Texture>,
+    image: NodeImage,
+    pool: CommandPool<B, QueueType>,
+    submit: Option<Submit<B, SimultaneousUse>>,
+    buffer: Option<CopyBuffer<B>>,
+    /// Buffers replaced by a new recording, with the index of the first frame not using them.
+    stale: Vec<(u64, CopyBuffer<B>)>,
+    /// Version of the texture asset the submit was recorded for.
+    version: Option<u32>,
+}
+
+/// Size of the region copied from a render target of `source` size into a texture of
+/// `destination` size.
+///
+/// The image is never scaled: when the sizes differ only the overlapping top-left area is copied.
+fn copy_extent(source: Extent, destination: Extent) -> Extent {
+    Extent {
+        width: source.width.min(destination.width),
+        height: source.height.min(destination.height),
+        depth: 1,
+    }
+}
+
+impl<B: Backend> CopyToTexture<B> {
+    /// Records the copy into the loaded destination texture, from the frame `frame` on.
+    fn record(&mut self, ctx: &GraphContext<B>, texture: &rendy::texture::Texture<B>, frame: u64) {
+        let source = ctx
+            .get_image(self.image.id)
+            .expect("Render target image does not exist");
+        let target = texture.image();
+        let extent = copy_extent(source.kind().extent(), target.kind().extent());
+        let bounds = hal::image::Offset::ZERO..hal::image::Offset {
+            x: extent.width as i32,
+            y: extent.height as i32,
+            z: 1,
+        };
+
+        let mut buffer = self
+            .pool
+            .allocate_buffers(1)
+            .pop()
+            .unwrap()
+            .begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = buffer.encoder();
+
+        let subresource = hal::image::SubresourceRange {
+            aspects: hal::format::Aspects::COLOR,
+            levels: 0..1,
+            layers: 0..1,
+        };
+
+        unsafe {
+            gfx_acquire_barriers(ctx, None, Some(&self.image), &mut encoder);
+
+            encoder.pipeline_barrier(
+                hal::pso::PipelineStage::FRAGMENT_SHADER..hal::pso::PipelineStage::TRANSFER,
+                hal::memory::Dependencies::empty(),
+                once(hal::memory::Barrier::Image {
+                    states: (
+                        hal::image::Access::SHADER_READ,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )
+                        ..(
+                            hal::image::Access::TRANSFER_WRITE,
+                            hal::image::Layout::TransferDstOptimal,
+                        ),
+                    target: target.raw(),
+                    families: None,
+                    range: subresource.clone(),
+                }),
+            );
+
+            encoder.blit_image(
+                source.raw(),
+                hal::image::Layout::TransferSrcOptimal,
+                target.raw(),
+                hal::image::Layout::TransferDstOptimal,
+                Filter::Nearest,
+                once(hal::command::ImageBlit {
+                    src_subresource: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0..1,
+                    },
+                    src_bounds: bounds.clone(),
+                    dst_subresource: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0..1,
+                    },
+                    dst_bounds: bounds,
+                }),
+            );
+
+            encoder.pipeline_barrier(
+                hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::FRAGMENT_SHADER,
+                hal::memory::Dependencies::empty(),
+                once(hal::memory::Barrier::Image {
+                    states: (
+                        hal::image::Access::TRANSFER_WRITE,
+                        hal::image::Layout::TransferDstOptimal,
+                    )
+                        ..(
+                            hal::image::Access::SHADER_READ,
+                            hal::image::Layout::ShaderReadOnlyOptimal,
+                        ),
+                    target: target.raw(),
+                    families: None,
+                    range: subresource,
+                }),
+            );
+
+            gfx_release_barriers(ctx, None, Some(&self.image), &mut encoder);
+        }
+
+        let (submit, buffer) = buffer.finish().submit();
+        self.submit = Some(submit);
+        if let Some(stale) = self.buffer.replace(buffer) {
+            self.stale.push((frame, stale));
+        }
+    }
+
+    /// Frees the replaced buffers once the frames that may still use them are complete.
+    fn free_stale(&mut self, frames: &Frames<B>) {
+        let complete = frames.complete_upper_bound();
+        let (free, stale) = self
+            .stale
+            .drain(..)
+            .partition::<Vec<_>, _>(|(frame, _)| *frame <= complete);
+        self.stale = stale;
+        if !free.is_empty() {
+            unsafe {
+                self.pool
+                    .free_buffers(free.into_iter().map(|(_, buffer)| buffer.mark_complete()));
+            }
+        }
+    }
+}
+
+impl<B: Backend> Node<B, GraphAuxData> for CopyToTexture<B> {
+    type Capability = Graphics;
+
+    fn run<'a>(
+        &mut self,
+        ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        queue: &mut Queue<B>,
+        aux: &GraphAuxData,
+        frames: &Frames<B>,
+        waits: &[(&'a B::Semaphore, hal::pso::PipelineStage)],
+        signals: &[&'a B::Semaphore],
+        fence: Option<&mut Fence<B>>,
+    ) {
+        // Recorded again when the texture is reloaded, as the old image may be gone.
+        let storage = aux.resources.get::<AssetStorage<Texture>>().unwrap();
+        if let Some((texture, version)) = storage.get_asset_with_version(&self.texture) {
+            if self.version != Some(version) {
+                if let Some(texture) = B::unwrap_texture(texture) {
+                    self.record(ctx, texture, frames.next().index());
+                    self.version = Some(version);
+                }
+            }
+        }
+        self.free_stale(frames);
+
+        let submission = Submission::new()
+            .submits(self.submit.as_ref())
+            .wait(waits.iter().cloned())
+            .signal(signals.iter().cloned());
+
+        unsafe {
+            queue.submit(once(submission), fence);
+        }
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        self.submit.take();
+        let buffers = self
+            .stale
+            .drain(..)
+            .map(|(_, buffer)| buffer)
+            .chain(self.buffer.take())
+            .map(|buffer| buffer.mark_complete());
+        self.pool.free_buffers(buffers);
+        factory.destroy_command_pool(self.pool);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extent(width: u32, height: u32) -> Extent {
+        Extent {
+            width,
+            height,
+            depth: 1,
+        }
+    }
+
+    #[test]
+    fn copies_whole_target_into_texture_of_same_size() {
+        assert_eq!(copy_extent(extent(256, 128), extent(256, 128)), extent(256, 128));
+    }
+
+    #[test]
+    fn copies_overlap_when_sizes_differ() {
+        assert_eq!(copy_extent(extent(256, 128), extent(64, 512)), extent(64, 128));
+        assert_eq!(copy_extent(extent(32, 32), extent(64, 64)), extent(32, 32));
+    }
+}
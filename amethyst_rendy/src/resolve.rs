@@ -0,0 +1,162 @@
+//! Render graph node resolving a multisampled image into a single-sampled one.
+
+use std::iter::once;
+
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, Fence, Graphics, MultiShot,
+        PendingState, PrimaryLevel, Queue, QueueType, SimultaneousUse, Submission, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, GraphContext, ImageAccess, Node, NodeBuffer,
+        NodeBuildError, NodeDesc, NodeImage,
+    },
+    hal,
+};
+
+use crate::{system::GraphAuxData, types::Backend};
+
+/// Describes a node resolving its first image into its second image.
+///
+/// Both images must have the same size and format, and the first one must be multisampled.
+#[derive(Debug, Default)]
+pub struct ResolveImageDesc;
+
+impl<B: Backend> NodeDesc<B, GraphAuxData> for ResolveImageDesc {
+    type Node = ResolveImage<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![
+            ImageAccess {
+                access: hal::image::Access::TRANSFER_READ,
+                usage: hal::image::Usage::TRANSFER_SRC,
+                layout: hal::image::Layout::TransferSrcOptimal,
+                stages: hal::pso::PipelineStage::TRANSFER,
+            },
+            ImageAccess {
+                access: hal::image::Access::TRANSFER_WRITE,
+                usage: hal::image::Usage::TRANSFER_DST,
+                layout: hal::image::Layout::TransferDstOptimal,
+                stages: hal::pso::PipelineStage::TRANSFER,
+            },
+        ]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &GraphAuxData,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, NodeBuildError> {
+        assert!(buffers.is_empty());
+        assert_eq!(images.len(), 2);
+
+        let mut pool = factory
+            .create_command_pool(family)
+            .map_err(NodeBuildError::OutOfMemory)?;
+
+        let source = &images[0];
+        let destination = &images[1];
+        let extent = ctx
+            .get_image(source.id)
+            .expect("Resolve source image does not exist")
+            .kind()
+            .extent();
+
+        let mut buffer = pool
+            .allocate_buffers(1)
+            .pop()
+            .unwrap()
+            .begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = buffer.encoder();
+
+        unsafe {
+            gfx_acquire_barriers(ctx, None, images.iter(), &mut encoder);
+
+            encoder.resolve_image(
+                ctx.get_image(source.id).unwrap().raw(),
+                source.layout,
+                ctx.get_image(destination.id).unwrap().raw(),
+                destination.layout,
+                once(hal::command::ImageResolve {
+                    src_subresource: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0..1,
+                    },
+                    src_offset: hal::image::Offset::ZERO,
+                    dst_subresource: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0..1,
+                    },
+                    dst_offset: hal::image::Offset::ZERO,
+                    extent,
+                }),
+            );
+
+            gfx_release_barriers(ctx, None, images.iter(), &mut encoder);
+        }
+
+        let (submit, buffer) = buffer.finish().submit();
+
+        Ok(ResolveImage {
+            pool,
+            submit,
+            buffer,
+        })
+    }
+}
+
+/// Node resolving a multisampled image, see [`ResolveImageDesc`].
+#[derive(Debug)]
+pub struct ResolveImage<B: Backend> {
+    pool: CommandPool<B, QueueType>,
+    submit: Submit<B, SimultaneousUse>,
+    buffer: CommandBuffer<
+        B,
+        QueueType,
+        PendingState<ExecutableState<MultiShot<SimultaneousUse>>>,
+        PrimaryLevel,
+    >,
+}
+
+impl<B: Backend> Node<B, GraphAuxData> for ResolveImage<B> {
+    type Capability = Graphics;
+
+    fn run<'a>(
+        &mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        queue: &mut Queue<B>,
+        _aux: &GraphAuxData,
+        _frames: &Frames<B>,
+        waits: &[(&'a B::Semaphore, hal::pso::PipelineStage)],
+        signals: &[&'a B::Semaphore],
+        fence: Option<&mut Fence<B>>,
+    ) {
+        unsafe {
+            queue.submit(
+                once(
+                    Submission::new()
+                        .submits(once(&self.submit))
+                        .wait(waits.iter().cloned())
+                        .signal(signals.iter().cloned()),
+                ),
+                fence,
+            );
+        }
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        drop(self.submit);
+        self.pool.free_buffers(once(self.buffer.mark_complete()));
+        factory.destroy_command_pool(self.pool);
+    }
+}
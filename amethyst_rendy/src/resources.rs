@@ -1,6 +1,20 @@
 //! `amethyst` rendering ecs resources
 
-/// The ambient color of a scene
+use amethyst_assets::Handle;
+
+use crate::types::Texture;
+
+/// The ambient color of a scene, in sRGB space. It is decoded to linear before reaching the shaders.
+///
+/// It's best inserted as a `Tracked<AmbientColor>` resource, so that the passes only convert it
+/// again when it changes. A plain `AmbientColor` resource is converted every frame.
+///
+/// ```
+/// # use amethyst_core::{ecs::Resources, Tracked};
+/// # use amethyst_rendy::{palette::Srgba, resources::AmbientColor};
+/// # let mut resources = Resources::default();
+/// resources.insert(Tracked::new(AmbientColor(Srgba::new(0.1, 0.1, 0.1, 1.0))));
+/// ```
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct AmbientColor(#[serde(with = "crate::serde_shim::srgba")] pub palette::Srgba);
 
@@ -20,3 +34,253 @@ impl From<Tint> for [f32; 4] {
         [r, g, b, a]
     }
 }
+
+/// Curve used to map HDR scene colors into the displayable range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TonemapOperator {
+    /// Clamp exposed colors to `[0, 1]`.
+    Linear,
+    /// Reinhard operator, `c / (1 + c)`.
+    Reinhard,
+    /// Filmic curve approximating the ACES reference rendering transform.
+    Aces,
+}
+
+impl Default for TonemapOperator {
+    fn default() -> Self {
+        TonemapOperator::Aces
+    }
+}
+
+/// Tonemapping settings used by the `RenderTonemap` plugin.
+///
+/// Can be modified at runtime to change exposure or operator without rebuilding the graph.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Tonemap {
+    /// Operator applied to exposed scene colors.
+    pub operator: TonemapOperator,
+    /// Linear multiplier applied to scene colors before tonemapping.
+    pub exposure: f32,
+}
+
+impl Default for Tonemap {
+    fn default() -> Self {
+        Self {
+            operator: TonemapOperator::default(),
+            exposure: 1.0,
+        }
+    }
+}
+
+/// Color grading applied by the `RenderColorGrading` plugin.
+///
+/// Replace the lookup table at runtime to change the mood of the scene, e.g. between day and
+/// night, or raise the intensity to fade in a damage effect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGrading {
+    /// Lookup table mapping display colors to graded colors, colors are left unchanged without
+    /// it or while it loads.
+    ///
+    /// The table is a strip of `N` square slices of `N * N` texels, e.g. a 256x16 image. Red
+    /// increases to the right and green downwards within a slice, blue increases from slice to
+    /// slice. It should be loaded in an sRGB format like other color textures.
+    pub lut: Option<Handle<Texture>>,
+    /// Blend between the original colors at `0.0` and the graded colors at `1.0`.
+    pub intensity: f32,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            lut: None,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl ColorGrading {
+    /// Grade colors fully with given lookup table.
+    #[must_use]
+    pub fn new(lut: Handle<Texture>) -> Self {
+        Self {
+            lut: Some(lut),
+            ..Self::default()
+        }
+    }
+}
+
+/// Color vision deficiency targeted by a [`ColorBlindFilter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ColorBlindness {
+    /// Missing red cones.
+    Protanopia,
+    /// Missing green cones, the most common deficiency.
+    Deuteranopia,
+    /// Missing blue cones.
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// Matrix simulating the deficiency on linear RGB colors, from Machado et al. 2009.
+    fn simulation(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorBlindness::Protanopia => {
+                [
+                    [0.152_286, 1.052_583, -0.204_868],
+                    [0.114_503, 0.786_281, 0.099_216],
+                    [-0.003_882, -0.048_116, 1.051_998],
+                ]
+            }
+            ColorBlindness::Deuteranopia => {
+                [
+                    [0.367_322, 0.860_646, -0.227_968],
+                    [0.280_085, 0.672_501, 0.047_413],
+                    [-0.011_820, 0.042_940, 0.968_881],
+                ]
+            }
+            ColorBlindness::Tritanopia => {
+                [
+                    [1.255_528, -0.076_749, -0.178_779],
+                    [-0.078_411, 0.930_809, 0.147_602],
+                    [0.004_733, 0.691_367, 0.303_900],
+                ]
+            }
+        }
+    }
+
+    /// Matrix shifting the colors lost to the deficiency towards those still perceived.
+    fn daltonization(self) -> [[f32; 3]; 3] {
+        // The error between the original and simulated colors is moved to the green and blue
+        // channels, then added back to the original colors.
+        const SHIFT: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+        let simulation = self.simulation();
+        let mut matrix = IDENTITY_3;
+        for (row, shift) in matrix.iter_mut().zip(&SHIFT) {
+            for (column, value) in row.iter_mut().enumerate() {
+                *value += (0..3)
+                    .map(|k| shift[k] * (IDENTITY_3[k][column] - simulation[k][column]))
+                    .sum::<f32>();
+            }
+        }
+        matrix
+    }
+}
+
+const IDENTITY_3: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// What the `RenderColorBlindFilter` plugin does to the colors of the scene.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ColorBlindMode {
+    /// Colors are left unchanged.
+    Off,
+    /// Colors are adjusted so players with the deficiency can tell them apart more easily.
+    Daltonize(ColorBlindness),
+    /// Colors are shown as seen with the deficiency, to test the game is playable with it.
+    Simulate(ColorBlindness),
+    /// Debug view of the screen split in four, the original colors at the top left and the
+    /// simulated protanopia, deuteranopia and tritanopia at the top right, bottom left and
+    /// bottom right, to check the palette at a glance.
+    PaletteCheck,
+}
+
+impl Default for ColorBlindMode {
+    fn default() -> Self {
+        ColorBlindMode::Off
+    }
+}
+
+/// Color-blind assist filter applied by the `RenderColorBlindFilter` plugin.
+///
+/// Change the mode at runtime, e.g. from an accessibility menu.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ColorBlindFilter {
+    /// Filter applied to the colors.
+    pub mode: ColorBlindMode,
+    /// Blend between the original colors at `0.0` and the filtered colors at `1.0`.
+    pub intensity: f32,
+}
+
+impl Default for ColorBlindFilter {
+    fn default() -> Self {
+        Self {
+            mode: ColorBlindMode::Off,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl ColorBlindFilter {
+    /// Filter colors fully with given mode.
+    #[must_use]
+    pub fn new(mode: ColorBlindMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Matrices applied to linear RGB colors in the top left, top right, bottom left and bottom
+    /// right quarters of the screen.
+    pub(crate) fn quarter_matrices(&self) -> [[[f32; 3]; 3]; 4] {
+        let intensity = self.intensity.max(0.0).min(1.0);
+        let blend = |matrix: [[f32; 3]; 3]| {
+            let mut blended = IDENTITY_3;
+            for (blended, matrix) in blended.iter_mut().zip(&matrix) {
+                for (blended, value) in blended.iter_mut().zip(matrix) {
+                    *blended += (value - *blended) * intensity;
+                }
+            }
+            blended
+        };
+        match self.mode {
+            ColorBlindMode::Off => [IDENTITY_3; 4],
+            ColorBlindMode::Daltonize(deficiency) => [blend(deficiency.daltonization()); 4],
+            ColorBlindMode::Simulate(deficiency) => [blend(deficiency.simulation()); 4],
+            ColorBlindMode::PaletteCheck => {
+                [
+                    IDENTITY_3,
+                    blend(ColorBlindness::Protanopia.simulation()),
+                    blend(ColorBlindness::Deuteranopia.simulation()),
+                    blend(ColorBlindness::Tritanopia.simulation()),
+                ]
+            }
+        }
+    }
+}
+
+/// Sky drawn around the camera by the `RenderSkybox` plugin.
+///
+/// Replaces the colors the plugin was created with, and can be changed at runtime, e.g. when
+/// switching levels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Skybox {
+    /// Vertical gradient from the nadir to the zenith.
+    Gradient {
+        /// Color straight down
+        nadir_color: palette::Srgb,
+        /// Color straight up
+        zenith_color: palette::Srgb,
+    },
+    /// Cube texture, e.g. a Radiance HDR image imported by
+    /// [`HdrCubemapFormat`](crate::formats::cubemap::HdrCubemapFormat) or built from six faces
+    /// with [`Cubemap::from_faces`](crate::formats::cubemap::Cubemap::from_faces).
+    ///
+    /// The plugin colors are drawn until the texture is loaded.
+    Cubemap {
+        /// Linear cube texture
+        texture: Handle<Texture>,
+        /// Linear multiplier applied to the texture colors
+        intensity: f32,
+    },
+}
+
+impl Skybox {
+    /// Sky of given cube texture at full intensity.
+    #[must_use]
+    pub fn cubemap(texture: Handle<Texture>) -> Self {
+        Skybox::Cubemap {
+            texture,
+            intensity: 1.0,
+        }
+    }
+}
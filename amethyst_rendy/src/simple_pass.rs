@@ -0,0 +1,158 @@
+//! Simple passes drawing triangles generated from a component, with custom shaders.
+//!
+//! Implementing [`SimplePassDef`] describes the vertex format, shaders and uniform of a pass,
+//! and how the vertices are generated from the component of the drawn entities. The
+//! [`RenderSimple`] plugin then takes care of the render group and pipeline, drawing the
+//! vertices of every entity with the component in one draw call.
+//!
+//! The shaders bind the uniform of the pass at descriptor set 0 and the camera at set 1:
+//!
+//! ```glsl
+//! layout(std140, set = 0, binding = 0) uniform TriangleArgs {
+//!     uniform float scale;
+//! };
+//!
+//! layout(std140, set = 1, binding = 0) uniform ViewArgs {
+//!     uniform mat4 proj;
+//!     uniform mat4 view;
+//!     uniform mat4 proj_view;
+//!     uniform float time;
+//! };
+//! ```
+//!
+//! ```ignore
+//! #[derive(Debug)]
+//! struct TrianglePass;
+//!
+//! impl SimplePassDef for TrianglePass {
+//!     type Component = Triangle;
+//!     type Vertex = TriangleVertex;
+//!     type Uniform = TriangleArgs;
+//!
+//!     fn vertex_shader() -> &'static SpirvShader {
+//!         &TRIANGLE_VERTEX
+//!     }
+//!
+//!     fn fragment_shader() -> &'static SpirvShader {
+//!         &TRIANGLE_FRAGMENT
+//!     }
+//!
+//!     fn uniform(_world: &World, resources: &Resources) -> TriangleArgs {
+//!         *resources.get::<TriangleArgs>().unwrap()
+//!     }
+//!
+//!     fn vertices(triangle: &Triangle, vertices: &mut Vec<TriangleVertex>) {
+//!         vertices.extend(triangle.vertices());
+//!     }
+//! }
+//!
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToWindow::from_config(display_config))
+//!     .with_plugin(RenderSimple::<TrianglePass>::default())
+//! ```
+
+use amethyst_core::ecs::{storage::Component, Resources, World};
+use amethyst_error::Error;
+use derivative::Derivative;
+use glsl_layout::Uniform;
+use rendy::{graph::render::RenderGroupDesc, hal::pso, mesh::AsVertex, shader::SpirvShader};
+
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::DrawSimpleDesc,
+    types::Backend,
+    Factory,
+};
+
+/// Define drawing the triangles generated from a component with specified shaders.
+pub trait SimplePassDef: 'static + std::fmt::Debug + Send + Sync {
+    /// Topology of the generated vertices.
+    const PRIMITIVE: pso::Primitive = pso::Primitive::TriangleList;
+
+    /// How the drawn colors are blended into the target, alpha blending by default.
+    const BLEND: Option<pso::BlendState> = Some(pso::BlendState::ALPHA);
+
+    /// Depth test of the drawn triangles, none by default. The depth of the camera is reversed,
+    /// closer fragments having greater depths.
+    const DEPTH_TEST: Option<pso::DepthTest> = None;
+
+    /// Component of the drawn entities
+    type Component: Component;
+
+    /// Vertex type generated from the component
+    type Vertex: AsVertex + Copy + std::fmt::Debug + Send + Sync + 'static;
+
+    /// Uniform shared by every draw of a frame, bound as descriptor set 0
+    type Uniform: Uniform + std::fmt::Debug + Send + Sync + 'static;
+
+    /// Returns the vertex `SpirvShader` which will be used for this pass
+    fn vertex_shader() -> &'static SpirvShader;
+
+    /// Returns the fragment `SpirvShader` which will be used for this pass
+    fn fragment_shader() -> &'static SpirvShader;
+
+    /// Returns the uniform of the frame about to be drawn.
+    fn uniform(world: &World, resources: &Resources) -> Self::Uniform;
+
+    /// Appends the vertices of an entity to the drawn ones.
+    fn vertices(component: &Self::Component, vertices: &mut Vec<Self::Vertex>);
+}
+
+/// A [`RenderPlugin`] drawing a [`SimplePassDef`], see the [module documentation](self).
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct RenderSimple<P: SimplePassDef> {
+    target: Target,
+    order: i32,
+    marker: std::marker::PhantomData<P>,
+}
+
+impl<P: SimplePassDef> Default for RenderSimple<P> {
+    fn default() -> Self {
+        Self {
+            target: Target::default(),
+            order: RenderOrder::Transparent.into(),
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<P: SimplePassDef> RenderSimple<P> {
+    /// Set target to which the pass will be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the pass at given point of the frame, [`RenderOrder::Transparent`] by default.
+    #[must_use]
+    pub fn with_order(mut self, order: impl Into<i32>) -> Self {
+        self.order = order.into();
+        self
+    }
+}
+
+impl<B: Backend, P: SimplePassDef> RenderPlugin<B> for RenderSimple<P> {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        let target = self.target;
+        let order = self.order;
+        plan.extend_target(self.target, move |ctx| {
+            ctx.add(
+                order,
+                DrawSimpleDesc::<P>::new()
+                    .with_target(target)
+                    .with_samples(ctx.samples())
+                    .builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
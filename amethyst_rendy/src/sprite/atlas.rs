@@ -0,0 +1,645 @@
+//! Packing of many small sprite sheet images into shared atlas textures.
+//!
+//! `DrawFlat2D` starts a new batch every time the texture of consecutive sprites changes. Games
+//! built from many small images, one per character or prop, end up with almost one draw call per
+//! sprite. The [`TextureAtlasBuilder`] merges the images into a single texture at load time, and
+//! rewrites the sprite positions of every sheet so that they point into the shared texture.
+//! Sprite sheets loaded from the resulting [`TextureAtlas`] share one `Handle<Texture>`, letting
+//! the renderer draw all of them in a single batch.
+//!
+//! ```ignore
+//! let mut builder = TextureAtlasBuilder::new().with_padding(2);
+//! let hero = builder.add(hero_pixels, 64, 32, &hero_sprites)?;
+//! let slime = builder.add(slime_pixels, 16, 16, &slime_sprites)?;
+//! let (texture, sheets) = builder.build()?.load(resources);
+//! let hero_sheet = sheets[hero].clone();
+//! ```
+//!
+//! Images only known at runtime, e.g. downloaded avatars or generated sprites, can be added to
+//! the [`SpriteAtlasCache`] resource instead. The 2D render plugins repack it at the start of
+//! the next frame:
+//!
+//! ```ignore
+//! resources
+//!     .get_mut::<SpriteAtlasCache>()
+//!     .unwrap()
+//!     .insert("avatar", avatar_pixels, 32, 32, &avatar_sprites)?;
+//! // Next frame
+//! let avatar_sheet = resources.get::<SpriteAtlasCache>().unwrap().get("avatar");
+//! ```
+
+use std::collections::HashMap;
+
+use amethyst_assets::{DefaultLoader, Handle, Loader, ProcessingQueue};
+use amethyst_core::ecs::{ParallelRunnable, Resources, System, SystemBuilder};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    hal::{
+        format::Format,
+        image::{Filter, Kind, SamplerDesc, ViewKind, WrapMode},
+    },
+    texture::TextureBuilder,
+};
+
+use super::{SpriteGrid, SpriteList, SpritePosition, SpriteSheet, Sprites};
+use crate::types::{Texture, TextureData};
+
+/// Default maximum width and height of an atlas, supported by virtually all GPUs.
+pub const DEFAULT_ATLAS_SIZE: u32 = 4096;
+
+/// Collects sprite sheet images to be packed into a [`TextureAtlas`].
+///
+/// Images are given as tightly packed RGBA8 pixels, rows ordered from top to bottom.
+#[derive(Debug, Clone)]
+pub struct TextureAtlasBuilder {
+    max_size: u32,
+    padding: u32,
+    images: Vec<AtlasImage>,
+}
+
+#[derive(Debug, Clone)]
+struct AtlasImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    sprites: Vec<SpritePosition>,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_ATLAS_SIZE,
+            padding: 1,
+            images: Vec::new(),
+        }
+    }
+}
+
+impl TextureAtlasBuilder {
+    /// Creates a builder for atlases of at most [`DEFAULT_ATLAS_SIZE`] pixels wide and high, with
+    /// one pixel of padding between images.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum width and height of the atlas.
+    #[must_use]
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the number of transparent pixels kept between images, preventing texture filtering
+    /// from bleeding neighbouring images into the edges of a sprite.
+    #[must_use]
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Adds the image of a sprite sheet, returning the index of its sheet in the built atlas.
+    ///
+    /// `sprites` describes the sprites on the image, the texture size it holds is ignored in
+    /// favor of `width` and `height`.
+    pub fn add(
+        &mut self,
+        pixels: Vec<u8>,
+        width: u32,
+        height: u32,
+        sprites: &Sprites,
+    ) -> Result<usize, Error> {
+        let expected = u64::from(width) * u64::from(height) * 4;
+        if pixels.len() as u64 != expected {
+            return Err(format_err!(
+                "Expected {} bytes of RGBA8 pixels for a {}x{} image, got {}",
+                expected,
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+        let sprites = match sprites {
+            Sprites::List(list) => list.sprites.clone(),
+            Sprites::Grid(grid) => grid_positions(grid),
+        };
+        self.images.push(AtlasImage {
+            width,
+            height,
+            pixels,
+            sprites,
+        });
+        Ok(self.images.len() - 1)
+    }
+
+    /// Places the added images, returning the atlas size and the top left corner of every image.
+    fn layout(&self) -> Result<(u32, u32, Vec<(u32, u32)>), Error> {
+        let sizes: Vec<_> = self
+            .images
+            .iter()
+            .map(|image| (image.width, image.height))
+            .collect();
+        pack(&sizes, self.max_size, self.padding)
+    }
+
+    /// Packs the added images into the smallest power of two texture they fit in.
+    ///
+    /// Fails if the images don't fit into the maximum size.
+    pub fn build(&self) -> Result<TextureAtlas, Error> {
+        let (width, height, placements) = self.layout()?;
+        let byte_count = usize::try_from(u64::from(width) * u64::from(height) * 4)
+            .map_err(|_| format_err!("A {}x{} atlas doesn't fit into memory", width, height))?;
+
+        let mut pixels = vec![0_u8; byte_count];
+        let mut sheets = Vec::with_capacity(self.images.len());
+        for (image, (x, y)) in self.images.iter().zip(placements) {
+            // The image is within the atlas, whose byte count fits into `usize`.
+            let row_bytes = image.width as usize * 4;
+            for (row, source) in image.pixels.chunks_exact(row_bytes).enumerate() {
+                let start = ((y as usize + row) * width as usize + x as usize) * 4;
+                pixels[start..start + row_bytes].copy_from_slice(source);
+            }
+            sheets.push(Sprites::List(SpriteList {
+                texture_width: width,
+                texture_height: height,
+                sprites: image
+                    .sprites
+                    .iter()
+                    .map(|sprite| {
+                        SpritePosition {
+                            x: sprite.x + x,
+                            y: sprite.y + y,
+                            ..sprite.clone()
+                        }
+                    })
+                    .collect(),
+            }));
+        }
+
+        Ok(TextureAtlas {
+            width,
+            height,
+            pixels,
+            sheets,
+        })
+    }
+}
+
+/// Texture holding the images of many sprite sheets, with their sprites rebased onto it.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    sheets: Vec<Sprites>,
+}
+
+impl TextureAtlas {
+    /// Width of the atlas texture in pixels.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the atlas texture in pixels.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA8 pixels of the atlas texture.
+    #[must_use]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Sprites of the sheet with given index, pointing into the atlas texture.
+    #[must_use]
+    pub fn sprites(&self, index: usize) -> Option<&Sprites> {
+        self.sheets.get(index)
+    }
+
+    /// Texture data of the atlas, in sRGB with nearest filtering.
+    #[must_use]
+    pub fn texture_data(&self) -> TextureData {
+        texture_data(self.width, self.height, self.pixels.clone())
+    }
+
+    /// Loads the atlas texture and a sprite sheet per added image, all sharing the texture.
+    ///
+    /// The sheets are ordered like the images were added to the builder.
+    #[must_use]
+    pub fn load(&self, resources: &Resources) -> (Handle<Texture>, Vec<Handle<SpriteSheet>>) {
+        self.clone().load_with(
+            &resources.get::<DefaultLoader>().unwrap(),
+            &resources.get::<ProcessingQueue<TextureData>>().unwrap(),
+            &resources.get::<ProcessingQueue<Sprites>>().unwrap(),
+            &resources.get::<ProcessingQueue<SpriteSheet>>().unwrap(),
+        )
+    }
+
+    fn load_with(
+        self,
+        loader: &DefaultLoader,
+        texture_queue: &ProcessingQueue<TextureData>,
+        sprites_queue: &ProcessingQueue<Sprites>,
+        sheet_queue: &ProcessingQueue<SpriteSheet>,
+    ) -> (Handle<Texture>, Vec<Handle<SpriteSheet>>) {
+        let data = texture_data(self.width, self.height, self.pixels);
+        let texture: Handle<Texture> = loader.load_from_data(data, (), texture_queue);
+        let sheets = self
+            .sheets
+            .into_iter()
+            .map(|sprites| {
+                let sprites = loader.load_from_data(sprites, (), sprites_queue);
+                loader.load_from_data(
+                    SpriteSheet {
+                        texture: texture.clone(),
+                        sprites,
+                    },
+                    (),
+                    sheet_queue,
+                )
+            })
+            .collect();
+        (texture, sheets)
+    }
+}
+
+fn texture_data(width: u32, height: u32, pixels: Vec<u8>) -> TextureData {
+    TextureBuilder::new()
+        .with_kind(Kind::D2(width, height, 1, 1))
+        .with_view_kind(ViewKind::D2)
+        .with_data_width(width)
+        .with_data_height(height)
+        .with_sampler_info(SamplerDesc::new(Filter::Nearest, WrapMode::Clamp))
+        .with_raw_data(pixels, Format::Rgba8Srgb)
+        .into()
+}
+
+/// Resource packing sprite sheet images added at runtime into a shared atlas.
+///
+/// Added images are packed together with all the previous ones by the [`SpriteAtlasSystem`],
+/// which the 2D render plugins add. Every repack loads a new texture and new sprite sheets,
+/// dropping the cache's handles to the previous ones: the previous atlas is released once the
+/// handles returned before by [`get`](SpriteAtlasCache::get) are dropped too.
+#[derive(Debug, Default)]
+pub struct SpriteAtlasCache {
+    builder: TextureAtlasBuilder,
+    indices: HashMap<String, usize>,
+    texture: Option<Handle<Texture>>,
+    sheets: HashMap<String, Handle<SpriteSheet>>,
+    dirty: bool,
+}
+
+impl SpriteAtlasCache {
+    /// Creates a cache packing images with given builder settings.
+    #[must_use]
+    pub fn new(builder: TextureAtlasBuilder) -> Self {
+        Self {
+            builder,
+            ..Self::default()
+        }
+    }
+
+    /// Adds the image of a sprite sheet under given name, see [`TextureAtlasBuilder::add`].
+    ///
+    /// Its sheet is available from [`get`](SpriteAtlasCache::get) once the atlas was repacked.
+    /// Fails, leaving the cache unchanged, if the image doesn't fit into the atlas with the images
+    /// added before.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        pixels: Vec<u8>,
+        width: u32,
+        height: u32,
+        sprites: &Sprites,
+    ) -> Result<(), Error> {
+        let name = name.into();
+        if self.indices.contains_key(&name) {
+            return Err(format_err!("Sprite atlas already contains {:?}", name));
+        }
+        let index = self.builder.add(pixels, width, height, sprites)?;
+        if let Err(err) = self.builder.layout() {
+            self.builder.images.pop();
+            return Err(err);
+        }
+        self.indices.insert(name, index);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Returns the sprite sheet of the image added under `name`, once packed.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Handle<SpriteSheet>> {
+        self.sheets.get(name).cloned()
+    }
+
+    /// Returns the texture of the current atlas, once packed.
+    #[must_use]
+    pub fn texture(&self) -> Option<Handle<Texture>> {
+        self.texture.clone()
+    }
+
+    /// Checks if images were added since the last repack.
+    #[must_use]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn repack(
+        &mut self,
+        loader: &DefaultLoader,
+        texture_queue: &ProcessingQueue<TextureData>,
+        sprites_queue: &ProcessingQueue<Sprites>,
+        sheet_queue: &ProcessingQueue<SpriteSheet>,
+    ) -> Result<(), Error> {
+        self.dirty = false;
+        let atlas = self.builder.build()?;
+        let (texture, sheets) = atlas.load_with(loader, texture_queue, sprites_queue, sheet_queue);
+        self.texture = Some(texture);
+        self.sheets = self
+            .indices
+            .iter()
+            .map(|(name, index)| (name.clone(), sheets[*index].clone()))
+            .collect();
+        Ok(())
+    }
+}
+
+/// Repacks the [`SpriteAtlasCache`] when images were added to it.
+#[derive(Debug, Default)]
+pub struct SpriteAtlasSystem;
+
+impl System for SpriteAtlasSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("SpriteAtlasSystem")
+                .write_resource::<SpriteAtlasCache>()
+                .read_resource::<DefaultLoader>()
+                .read_resource::<ProcessingQueue<TextureData>>()
+                .read_resource::<ProcessingQueue<Sprites>>()
+                .read_resource::<ProcessingQueue<SpriteSheet>>()
+                .build(
+                    |_, _, (cache, loader, texture_queue, sprites_queue, sheet_queue), _| {
+                        if cache.is_dirty() {
+                            if let Err(err) =
+                                cache.repack(loader, texture_queue, sprites_queue, sheet_queue)
+                            {
+                                log::error!("Failed to pack sprite atlas: {}", err);
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Pixel positions of the sprites of a grid.
+fn grid_positions(grid: &SpriteGrid) -> Vec<SpritePosition> {
+    let (cell_width, cell_height) = grid.cell_size();
+    let (left, top) = grid.position();
+    (0..grid.sprite_count())
+        .map(|cell| {
+            SpritePosition {
+                x: (cell % grid.columns) * cell_width + left,
+                y: (cell / grid.columns) * cell_height + top,
+                width: cell_width,
+                height: cell_height,
+                offsets: None,
+                flip_horizontal: false,
+                flip_vertical: false,
+            }
+        })
+        .collect()
+}
+
+/// Places rectangles of given sizes on shelves, growing the atlas in powers of two until they fit.
+///
+/// Returns the atlas size and the top left corner of every rectangle.
+fn pack(
+    sizes: &[(u32, u32)],
+    max_size: u32,
+    padding: u32,
+) -> Result<(u32, u32, Vec<(u32, u32)>), Error> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse((sizes[index].1, sizes[index].0)));
+
+    let too_large = || {
+        format_err!(
+            "{} images don't fit into a {}x{} atlas",
+            sizes.len(),
+            max_size,
+            max_size
+        )
+    };
+
+    let area: u64 = sizes
+        .iter()
+        .map(|&(w, h)| {
+            (u64::from(w) + u64::from(padding)).saturating_mul(u64::from(h) + u64::from(padding))
+        })
+        .fold(0, u64::saturating_add);
+    let widest = sizes.iter().map(|&(w, _)| w).max().unwrap_or(1);
+    let mut width = widest
+        .max(1)
+        .checked_next_power_of_two()
+        .ok_or_else(too_large)?;
+    while u64::from(width) * u64::from(width) < area {
+        width = width.checked_mul(2).ok_or_else(too_large)?;
+    }
+
+    while width <= max_size {
+        if let Some((height, placements)) = pack_shelves(sizes, &order, width, padding) {
+            if let Some(height) = height.max(1).checked_next_power_of_two() {
+                if height <= max_size {
+                    return Ok((width, height, placements));
+                }
+            }
+        }
+        width = match width.checked_mul(2) {
+            Some(width) => width,
+            None => break,
+        };
+    }
+    Err(too_large())
+}
+
+/// Fills shelves of given width from top to bottom, returning the used height.
+///
+/// Returns `None` if a rectangle is wider than the shelves or the height overflows.
+fn pack_shelves(
+    sizes: &[(u32, u32)],
+    order: &[usize],
+    width: u32,
+    padding: u32,
+) -> Option<(u32, Vec<(u32, u32)>)> {
+    let mut placements = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0_u32, 0_u32, 0_u32);
+    for &index in order {
+        let (w, h) = sizes[index];
+        if w > width {
+            return None;
+        }
+        if u64::from(x) + u64::from(w) > u64::from(width) {
+            x = 0;
+            y = y.checked_add(shelf_height)?.checked_add(padding)?;
+            shelf_height = 0;
+        }
+        placements[index] = (x, y);
+        x = x.saturating_add(w).saturating_add(padding);
+        shelf_height = shelf_height.max(h);
+    }
+    Some((y.checked_add(shelf_height)?, placements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn packed_rectangles_fit_without_overlap() {
+        let sizes = [(30, 10), (12, 40), (64, 8), (5, 5), (20, 20), (33, 17)];
+        let (width, height, placements) = pack(&sizes, 256, 1).unwrap();
+
+        assert!(width.is_power_of_two() && height.is_power_of_two());
+        let rects: Vec<_> = placements
+            .iter()
+            .zip(&sizes)
+            .map(|(&(x, y), &(w, h))| (x, y, w, h))
+            .collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.0 + a.2 <= width && a.1 + a.3 <= height);
+            for b in &rects[i + 1..] {
+                assert!(!overlaps(*a, *b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn too_large_images_are_rejected() {
+        assert!(pack(&[(300, 10)], 256, 0).is_err());
+        assert!(pack(&[(200, 200), (200, 200)], 256, 0).is_err());
+    }
+
+    #[test]
+    fn sheets_are_rebased_onto_the_atlas() {
+        let mut builder = TextureAtlasBuilder::new().with_padding(0);
+        let red = builder
+            .add(
+                [255, 0, 0, 255].repeat(4 * 2),
+                4,
+                2,
+                &Sprites::Grid(SpriteGrid {
+                    texture_width: 4,
+                    texture_height: 2,
+                    columns: 2,
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+        let blue = builder
+            .add(
+                [0, 0, 255, 255].repeat(2 * 2),
+                2,
+                2,
+                &Sprites::List(SpriteList {
+                    texture_width: 2,
+                    texture_height: 2,
+                    sprites: vec![SpritePosition {
+                        x: 0,
+                        y: 0,
+                        width: 2,
+                        height: 2,
+                        offsets: None,
+                        flip_horizontal: false,
+                        flip_vertical: false,
+                    }],
+                }),
+            )
+            .unwrap();
+        let atlas = builder.build().unwrap();
+
+        let pixel = |x: u32, y: u32| {
+            let start = ((y * atlas.width() + x) * 4) as usize;
+            &atlas.pixels()[start..start + 4]
+        };
+        for index in &[red, blue] {
+            let sprites = match atlas.sprites(*index).unwrap() {
+                Sprites::List(list) => list,
+                Sprites::Grid(_) => panic!("atlas sheets are lists"),
+            };
+            assert_eq!(sprites.texture_width, atlas.width());
+            let color = if *index == red {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            };
+            for sprite in &sprites.sprites {
+                assert_eq!(pixel(sprite.x, sprite.y), &color);
+                assert_eq!(
+                    pixel(sprite.x + sprite.width - 1, sprite.y + sprite.height - 1),
+                    &color
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pixel_count_is_checked() {
+        let sprites = Sprites::Grid(SpriteGrid {
+            columns: 1,
+            ..Default::default()
+        });
+        assert!(TextureAtlasBuilder::new()
+            .add(vec![0; 3], 1, 1, &sprites)
+            .is_err());
+    }
+    #[test]
+    fn huge_sizes_are_rejected_without_overflow() {
+        let sprites = Sprites::Grid(SpriteGrid {
+            columns: 1,
+            ..Default::default()
+        });
+        assert!(TextureAtlasBuilder::new()
+            .add(vec![0; 4], u32::MAX, u32::MAX, &sprites)
+            .is_err());
+
+        assert!(pack(&[(u32::MAX, 1)], u32::MAX, 1).is_err());
+        assert!(pack(&[(1 << 31, 1 << 31), (1 << 31, 1 << 31)], u32::MAX, 0).is_err());
+    }
+
+    #[test]
+    fn cache_rejects_duplicate_names() {
+        let sprites = Sprites::Grid(SpriteGrid {
+            columns: 1,
+            ..Default::default()
+        });
+        let mut cache = SpriteAtlasCache::default();
+        assert!(!cache.is_dirty());
+
+        cache.insert("dot", vec![0; 4], 1, 1, &sprites).unwrap();
+        assert!(cache.is_dirty());
+        assert!(cache.insert("dot", vec![0; 4], 1, 1, &sprites).is_err());
+        assert!(cache.get("dot").is_none());
+    }
+
+    #[test]
+    fn cache_rejects_images_overflowing_the_atlas() {
+        let sprites = Sprites::Grid(SpriteGrid {
+            columns: 1,
+            ..Default::default()
+        });
+        let mut cache =
+            SpriteAtlasCache::new(TextureAtlasBuilder::new().with_max_size(4).with_padding(0));
+
+        cache.insert("full", vec![0; 4 * 4 * 4], 4, 4, &sprites).unwrap();
+        assert!(cache.insert("dot", vec![0; 4], 1, 1, &sprites).is_err());
+        assert!(cache.builder.build().is_ok());
+        assert!(cache.insert("dot", vec![0; 4], 1, 1, &sprites).is_err());
+    }
+}
@@ -3,8 +3,15 @@ use amethyst_assets::{register_asset_type, Asset, AssetProcessorSystem, Handle};
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 
+pub use self::{
+    atlas::{SpriteAtlasCache, SpriteAtlasSystem, TextureAtlas, TextureAtlasBuilder},
+    nine_slice::{NineSlice, SpriteBorders},
+};
 use crate::types::Texture;
 
+pub mod atlas;
+pub mod nine_slice;
+
 /// Metadata for a sprite sheet texture.
 ///
 /// Contains a handle to the texture and the sprite coordinates on the texture.
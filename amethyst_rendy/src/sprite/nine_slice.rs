@@ -0,0 +1,222 @@
+//! Nine-slice rendering of sprites, for panels and buttons that scale without stretching corners.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Sprite, TextureCoordinates};
+
+/// Borders of a sprite in pixels, which keep their size when the sprite is rendered with nine
+/// slices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpriteBorders {
+    /// Width of the left border
+    pub left: f32,
+    /// Width of the right border
+    pub right: f32,
+    /// Height of the bottom border
+    pub bottom: f32,
+    /// Height of the top border
+    pub top: f32,
+}
+
+impl SpriteBorders {
+    /// Creates borders of given sizes.
+    #[must_use]
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        Self {
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// Creates borders of the same size on all sides.
+    #[must_use]
+    pub fn uniform(size: f32) -> Self {
+        Self::new(size, size, size, size)
+    }
+
+    /// Splits `sprite` into the nine slices covering `width` x `height` pixels, ordered row by row
+    /// from the bottom left slice.
+    ///
+    /// The corners keep their size, the edges stretch along their side and the center stretches
+    /// in both directions. When the size is smaller than the borders, the borders shrink to fit.
+    /// The offsets of `sprite` are scaled with its size, and the offsets of the slices place them
+    /// around that point.
+    #[must_use]
+    pub fn slice(&self, sprite: &Sprite, width: f32, height: f32) -> [Sprite; 9] {
+        let (xs, us) = split(
+            width,
+            sprite.width,
+            self.left,
+            self.right,
+            sprite.tex_coords.left,
+            sprite.tex_coords.right,
+        );
+        let (ys, vs) = split(
+            height,
+            sprite.height,
+            self.bottom,
+            self.top,
+            sprite.tex_coords.bottom,
+            sprite.tex_coords.top,
+        );
+        let offsets = [
+            scale(sprite.offsets[0], width, sprite.width),
+            scale(sprite.offsets[1], height, sprite.height),
+        ];
+
+        let slice = |index: usize| {
+            let (column, row) = (index % 3, index / 3);
+            Sprite {
+                width: xs[column + 1] - xs[column],
+                height: ys[row + 1] - ys[row],
+                offsets: [
+                    offsets[0] - (xs[column] + xs[column + 1]) / 2.0,
+                    offsets[1] - (ys[row] + ys[row + 1]) / 2.0,
+                ],
+                tex_coords: TextureCoordinates {
+                    left: us[column],
+                    right: us[column + 1],
+                    bottom: vs[row],
+                    top: vs[row + 1],
+                },
+            }
+        };
+        [
+            slice(0),
+            slice(1),
+            slice(2),
+            slice(3),
+            slice(4),
+            slice(5),
+            slice(6),
+            slice(7),
+            slice(8),
+        ]
+    }
+}
+
+/// Component rendering the `SpriteRender` of its entity with nine slices, stretched to `width` x
+/// `height` pixels while its borders keep their size.
+///
+/// Like sprite pixels, the size is scaled by the `Transform` of the entity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NineSlice {
+    /// Borders of the sprite that aren't stretched
+    pub borders: SpriteBorders,
+    /// Width the sprite is stretched to
+    pub width: f32,
+    /// Height the sprite is stretched to
+    pub height: f32,
+}
+
+impl NineSlice {
+    /// Creates a `NineSlice` stretching the sprite to given size.
+    #[must_use]
+    pub fn new(borders: SpriteBorders, width: f32, height: f32) -> Self {
+        Self {
+            borders,
+            width,
+            height,
+        }
+    }
+
+    /// Splits `sprite` into its nine slices, see [`SpriteBorders::slice`].
+    #[must_use]
+    pub fn slice(&self, sprite: &Sprite) -> [Sprite; 9] {
+        self.borders.slice(sprite, self.width, self.height)
+    }
+}
+
+/// Edges of the slices along one axis, centered on zero, with their texture coordinates.
+fn split(
+    size: f32,
+    sprite_size: f32,
+    start: f32,
+    end: f32,
+    tex_start: f32,
+    tex_end: f32,
+) -> ([f32; 4], [f32; 4]) {
+    let shrink = if start + end > size && start + end > 0.0 {
+        size / (start + end)
+    } else {
+        1.0
+    };
+    let (start, end) = (start * shrink, end * shrink);
+    let half = size / 2.0;
+    let positions = [-half, start - half, half - end, half];
+
+    let tex = |pixels: f32| {
+        let fraction = if sprite_size > 0.0 {
+            pixels / sprite_size
+        } else {
+            0.0
+        };
+        tex_start + (tex_end - tex_start) * fraction
+    };
+    let tex_coords = [tex_start, tex(start), tex(sprite_size - end), tex_end];
+    (positions, tex_coords)
+}
+
+fn scale(offset: f32, size: f32, sprite_size: f32) -> f32 {
+    if sprite_size > 0.0 {
+        offset * size / sprite_size
+    } else {
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprite() -> Sprite {
+        Sprite {
+            width: 30.0,
+            height: 30.0,
+            offsets: [0.0, 0.0],
+            tex_coords: TextureCoordinates {
+                left: 0.0,
+                right: 0.3,
+                bottom: 1.0,
+                top: 0.7,
+            },
+        }
+    }
+
+    #[test]
+    fn corners_keep_their_size() {
+        let slices = SpriteBorders::uniform(10.0).slice(&sprite(), 100.0, 50.0);
+
+        for corner in &[0, 2, 6, 8] {
+            assert_eq!(slices[*corner].width, 10.0);
+            assert_eq!(slices[*corner].height, 10.0);
+        }
+        assert_eq!(slices[4].width, 80.0);
+        assert_eq!(slices[4].height, 30.0);
+        assert_eq!(slices[0].offsets, [45.0, 20.0]);
+        assert_eq!(slices[8].offsets, [-45.0, -20.0]);
+        assert_eq!(slices[4].offsets, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn texture_coordinates_follow_borders() {
+        let slices = SpriteBorders::new(15.0, 3.0, 6.0, 0.0).slice(&sprite(), 60.0, 60.0);
+
+        assert_eq!(slices[0].tex_coords.left, 0.0);
+        assert!((slices[0].tex_coords.right - 0.15).abs() < 1e-6);
+        assert!((slices[2].tex_coords.left - 0.27).abs() < 1e-6);
+        assert!((slices[0].tex_coords.top - 0.94).abs() < 1e-6);
+        assert_eq!(slices[6].height, 0.0);
+    }
+
+    #[test]
+    fn borders_shrink_to_fit() {
+        let slices = SpriteBorders::uniform(10.0).slice(&sprite(), 10.0, 40.0);
+
+        assert_eq!(slices[0].width, 5.0);
+        assert_eq!(slices[1].width, 0.0);
+        assert_eq!(slices[2].width, 5.0);
+    }
+}
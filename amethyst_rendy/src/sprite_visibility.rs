@@ -1,18 +1,21 @@
 //! Transparency, visibility sorting and camera centroid culling for 2D Sprites.
 use std::cmp::Ordering;
 
+use amethyst_assets::{AssetHandle, AssetStorage, LoadHandle};
 use amethyst_core::{
     ecs::{component, Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
-    math::{Point3, Vector3},
+    math::{Point3, Vector2, Vector3},
     transform::Transform,
     Hidden, HiddenPropagate,
 };
+use fnv::FnvHashMap;
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
 use crate::{
-    camera::{ActiveCamera, Camera},
-    sprite::SpriteRender,
+    camera::{ActiveCamera, Camera, Viewport},
+    render_target::RenderTarget,
+    sprite::{Sprite, SpriteRender, SpriteSheet, Sprites},
     transparent::Transparent,
 };
 
@@ -24,6 +27,78 @@ pub struct SpriteVisibility {
     pub visible_unordered: Vec<Entity>,
     /// Visible entities that need to be drawn in the given order
     pub visible_ordered: Vec<Entity>,
+    /// Visible entities that need to be drawn in the given order by each split screen camera,
+    /// indexed like [`Viewport::cameras`]
+    pub viewport_ordered: Vec<Vec<Entity>>,
+}
+
+impl SpriteVisibility {
+    /// Visible entities that need to be drawn in the given order by the render pass instance of
+    /// given viewport index, see [`Viewport::cameras`]. Without an index or a list for it, these
+    /// are all of [`visible_ordered`](Self::visible_ordered).
+    #[must_use]
+    pub fn ordered(&self, viewport: Option<usize>) -> &[Entity] {
+        viewport
+            .and_then(|index| self.viewport_ordered.get(index))
+            .unwrap_or(&self.visible_ordered)
+    }
+}
+
+/// Rectangle in the XY plane of the world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+}
+
+impl Rect {
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Bounds of the world seen by an orthographic camera, `None` for perspective cameras whose
+    /// view is not bounded in the XY plane.
+    fn of_camera(camera: &Camera, transform: &Transform) -> Option<Rect> {
+        let projection = &camera.matrix;
+        if (projection[(3, 3)] - 1.0).abs() > f32::EPSILON
+            || projection[(3, 2)].abs() > f32::EPSILON
+        {
+            return None;
+        }
+
+        let clip_to_world = transform.global_matrix() * camera.inverse;
+        let mut min = Vector2::repeat(f32::INFINITY);
+        let mut max = Vector2::repeat(f32::NEG_INFINITY);
+        for &x in &[-1.0, 1.0] {
+            for &y in &[-1.0, 1.0] {
+                for &z in &[0.0, 1.0] {
+                    let corner = clip_to_world.transform_point(&Point3::new(x, y, z)).xy();
+                    min = min.inf(&corner.coords);
+                    max = max.sup(&corner.coords);
+                }
+            }
+        }
+        Some(Rect { min, max })
+    }
+
+    /// Bounds of the quad of a sprite, as drawn by the sprite passes.
+    fn of_sprite(sprite: &Sprite, transform: &Transform) -> Rect {
+        let matrix = transform.global_matrix();
+        let dir_x = matrix.column(0).xy() * sprite.width;
+        let dir_y = matrix.column(1).xy() * sprite.height;
+        let center = matrix
+            .transform_point(&Point3::new(-sprite.offsets[0], -sprite.offsets[1], 0.0))
+            .xy()
+            .coords;
+        let extent = (dir_x.abs() + dir_y.abs()) * 0.5;
+        Rect {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +115,14 @@ struct Internals {
 /// The sprite render pass should draw all sprites without semi-transparent pixels, then draw the
 /// sprites with semi-transparent pixels from far to near.
 ///
+/// With split screen cameras, see [`Viewport`], or cameras bound to other targets, see
+/// [`RenderTarget`], sprites in front of any of them are visible and sorted by their distance to
+/// the nearest one. Each split screen camera also gets its own back to front order in
+/// [`SpriteVisibility::viewport_ordered`].
+///
+/// Sprites outside the view of every orthographic camera are culled, using the size of their
+/// sprite. Tile maps can be culled with `DrawTiles2DBoundsCameraCulling` from `amethyst_tiles`.
+///
 /// Note that this should run after `Transform` has been updated for the current frame, and
 /// before rendering occurs.
 #[derive(Debug)]
@@ -48,13 +131,29 @@ pub struct SpriteVisibilitySortingSystem;
 impl System for SpriteVisibilitySortingSystem {
     fn build(self) -> Box<dyn ParallelRunnable> {
         let mut transparent_centroids: Vec<Internals> = Vec::default();
+        let mut viewport_transparent: Vec<Vec<(Entity, f32)>> = Vec::default();
+        let mut cameras: Vec<(Point3<f32>, Vector3<f32>, Option<Rect>)> = Vec::default();
+        // sprites of each sheet by the handle of its `Sprites`, with the version they were built
+        // from and the last frame they were used in
+        let mut sheet_sprites: FnvHashMap<LoadHandle, (u32, u64, Vec<Sprite>)> =
+            FnvHashMap::default();
+        let mut frame = 0_u64;
 
         Box::new(
             SystemBuilder::<()>::new("SpriteVisibilitySortingSystem")
                 .read_resource::<ActiveCamera>()
+                .read_resource::<AssetStorage<SpriteSheet>>()
+                .read_resource::<AssetStorage<Sprites>>()
                 .write_resource::<SpriteVisibility>()
                 .with_query(<(&Camera, &Transform)>::query())
                 .with_query(<(Entity, &Camera, &Transform)>::query())
+                .with_query(
+                    <(&Camera, &Transform, &Viewport)>::query()
+                        .filter(!component::<RenderTarget>()),
+                )
+                .with_query(
+                    <(&Camera, &Transform)>::query().filter(component::<RenderTarget>()),
+                )
                 .with_query(
                     <(Entity, &Transform, &SpriteRender, &Transparent)>::query()
                         .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
@@ -67,10 +166,12 @@ impl System for SpriteVisibilitySortingSystem {
                 .build(
                     move |commands,
                           world,
-                          (active_camera, visibility),
+                          (active_camera, sprite_sheet_storage, sprites_storage, visibility),
                           (
                         camera_query1,
                         camera_query2,
+                        viewport_query,
+                        bound_query,
                         transparent_query,
                         non_transparent_query,
                     )| {
@@ -80,37 +181,124 @@ impl System for SpriteVisibilitySortingSystem {
                         transparent_centroids.clear();
                         visibility.visible_ordered.clear();
                         visibility.visible_unordered.clear();
+                        for ordered in &mut visibility.viewport_ordered {
+                            ordered.clear();
+                        }
 
                         let origin = Point3::origin();
+                        let camera_view = |camera: &Camera, transform: &Transform| {
+                            (
+                                transform.global_matrix().transform_point(&origin),
+                                transform.global_matrix().column(2).xyz(),
+                                Rect::of_camera(camera, transform),
+                            )
+                        };
+
+                        // split screen cameras first, in the order of `Viewport::cameras`
+                        let mut viewport_cameras: Vec<_> = viewport_query.iter(world).collect();
+                        viewport_cameras.sort_by(|(_, _, a), (_, _, b)| a.screen_order(b));
+                        cameras.clear();
+                        cameras.extend(
+                            viewport_cameras
+                                .into_iter()
+                                .map(|(camera, transform, _)| camera_view(camera, transform)),
+                        );
+                        let viewport_count = cameras.len();
+                        let split_screen = viewport_count != 0;
+                        cameras.extend(
+                            bound_query
+                                .iter(world)
+                                .map(|(camera, transform)| camera_view(camera, transform)),
+                        );
+                        if !split_screen {
+                            let camera = active_camera.entity.map_or_else(
+                                || camera_query1.iter(world).next(),
+                                |e| {
+                                    camera_query2
+                                        .iter(world)
+                                        .find(|(camera_entity, _, _)| **camera_entity == e)
+                                        .map(|(_entity, camera, camera_transform)| {
+                                            (camera, camera_transform)
+                                        })
+                                },
+                            );
+                            cameras.extend(
+                                camera.map(|(camera, transform)| camera_view(camera, transform)),
+                            );
+                        }
+                        if cameras.is_empty() {
+                            return;
+                        }
+                        let cameras = &cameras;
 
-                        let (camera, camera_transform) = match active_camera.entity.map_or_else(
-                            || camera_query1.iter(world).next(),
-                            |e| {
-                                camera_query2
-                                    .iter(world)
-                                    .find(|(camera_entity, _, _)| **camera_entity == e)
-                                    .map(|(_entity, camera, camera_transform)| {
-                                        (camera, camera_transform)
-                                    })
-                            },
-                        ) {
-                            Some(r) => r,
-                            None => return,
+                        frame += 1;
+                        let mut sprite_rect =
+                            |sprite_render: &SpriteRender, transform: &Transform| {
+                                let sheet =
+                                    sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
+                                let (sprites, version) =
+                                    sprites_storage.get_asset_with_version(&sheet.sprites)?;
+                                let entry = sheet_sprites
+                                    .entry(sheet.sprites.load_handle())
+                                    .or_insert_with(|| (version, frame, sprites.build_sprites()));
+                                if entry.0 != version {
+                                    *entry = (version, frame, sprites.build_sprites());
+                                }
+                                entry.1 = frame;
+                                let sprite = entry.2.get(sprite_render.sprite_number)?;
+                                Some(Rect::of_sprite(sprite, transform))
+                            };
+
+                        // filter entities behind or beside all cameras, sprites still loading
+                        // are kept
+                        let visible_to = |camera: &(Point3<f32>, Vector3<f32>, Option<Rect>),
+                                          c: &Point3<f32>,
+                                          rect: Option<Rect>| {
+                            let (camera_centroid, camera_backward, camera_rect) = camera;
+                            (c - camera_centroid).dot(camera_backward) < 0.0
+                                && match (camera_rect, rect) {
+                                    (Some(camera_rect), Some(rect)) => camera_rect.overlaps(&rect),
+                                    _ => true,
+                                }
+                        };
+                        let visible = |c: &Point3<f32>, rect: Option<Rect>| {
+                            cameras.iter().any(|camera| visible_to(camera, c, rect))
                         };
 
-                        let camera_backward = camera_transform.global_matrix().column(2).xyz();
-                        let camera_centroid =
-                            camera_transform.global_matrix().transform_point(&origin);
+                        viewport_transparent.resize_with(viewport_count, Vec::new);
+                        for transparent in &mut viewport_transparent {
+                            transparent.clear();
+                        }
 
                         transparent_centroids.extend(
                             transparent_query
                                 .iter(world)
-                                .map(|(e, t, _, _)| {
-                                    (*e, t.global_matrix().transform_point(&origin))
+                                .map(|(e, t, sprite_render, _)| {
+                                    (
+                                        *e,
+                                        t.global_matrix().transform_point(&origin),
+                                        sprite_rect(sprite_render, t),
+                                    )
                                 })
-                                // filter entities behind the camera
-                                .filter(|(_, c)| (c - camera_centroid).dot(&camera_backward) < 0.0)
-                                .map(|(entity, centroid)| {
+                                .filter(|(_, c, rect)| visible(c, *rect))
+                                .map(|(entity, centroid, rect)| {
+                                    for (transparent, camera) in
+                                        viewport_transparent.iter_mut().zip(cameras)
+                                    {
+                                        if visible_to(camera, &centroid, rect) {
+                                            let distance = (centroid.z - camera.0.z).abs();
+                                            transparent.push((entity, distance));
+                                        }
+                                    }
+                                    let (camera_centroid, _, _) = cameras
+                                        .iter()
+                                        .min_by(|(a, _), (b, _)| {
+                                            (centroid.z - a.z)
+                                                .abs()
+                                                .partial_cmp(&(centroid.z - b.z).abs())
+                                                .unwrap_or(Ordering::Equal)
+                                        })
+                                        .unwrap();
                                     Internals {
                                         entity,
                                         centroid,
@@ -130,16 +318,82 @@ impl System for SpriteVisibilitySortingSystem {
                             .visible_ordered
                             .extend(transparent_centroids.iter().map(|c| c.entity));
 
+                        visibility.viewport_ordered.resize_with(viewport_count, Vec::new);
+                        for (ordered, transparent) in visibility
+                            .viewport_ordered
+                            .iter_mut()
+                            .zip(&mut viewport_transparent)
+                        {
+                            transparent.sort_by(|(_, a), (_, b)| {
+                                b.partial_cmp(a).unwrap_or(Ordering::Equal)
+                            });
+                            ordered.clear();
+                            ordered.extend(transparent.iter().map(|(entity, _)| *entity));
+                        }
+
                         visibility.visible_unordered.extend(
                             non_transparent_query
                                 .iter(world)
-                                .map(|(e, t, _)| (e, t.global_matrix().transform_point(&origin)))
-                                // filter entities behind the camera
-                                .filter(|(_, c)| (c - camera_centroid).dot(&camera_backward) < 0.0)
-                                .map(|(entity, _)| entity),
+                                .filter(|(_, t, sprite_render)| {
+                                    visible(
+                                        &t.global_matrix().transform_point(&origin),
+                                        sprite_rect(sprite_render, t),
+                                    )
+                                })
+                                .map(|(entity, _, _)| entity),
                         );
+
+                        // forget the sheets no sprite uses anymore
+                        sheet_sprites.retain(|_, (_, used, _)| *used == frame);
                     },
                 ),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::math::Vector3;
+
+    use super::*;
+    use crate::sprite::TextureCoordinates;
+
+    fn sprite(width: f32, height: f32) -> Sprite {
+        Sprite {
+            width,
+            height,
+            offsets: [0.0; 2],
+            tex_coords: TextureCoordinates {
+                left: 0.0,
+                right: 1.0,
+                bottom: 0.0,
+                top: 1.0,
+            },
+        }
+    }
+
+    fn transform(x: f32, y: f32, z: f32) -> Transform {
+        let mut transform = Transform::from(Vector3::new(x, y, z));
+        transform.copy_local_to_global();
+        transform
+    }
+
+    #[test]
+    fn sprites_beside_orthographic_camera_are_culled() {
+        let camera = Camera::standard_2d(100.0, 50.0);
+        let view = Rect::of_camera(&camera, &transform(0.0, 0.0, 10.0)).unwrap();
+        assert!((view.min - Vector2::new(-50.0, -25.0)).norm() < 1e-3);
+        assert!((view.max - Vector2::new(50.0, 25.0)).norm() < 1e-3);
+
+        let inside = Rect::of_sprite(&sprite(10.0, 10.0), &transform(52.0, 0.0, 0.0));
+        assert!(view.overlaps(&inside));
+        let outside = Rect::of_sprite(&sprite(10.0, 10.0), &transform(56.0, 0.0, 0.0));
+        assert!(!view.overlaps(&outside));
+    }
+
+    #[test]
+    fn perspective_cameras_are_not_bounded() {
+        let camera = Camera::perspective(1.0, std::f32::consts::FRAC_PI_3, 0.1);
+        assert_eq!(Rect::of_camera(&camera, &transform(0.0, 0.0, 10.0)), None);
+    }
+}
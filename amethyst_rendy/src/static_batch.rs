@@ -0,0 +1,386 @@
+//! Static batching of level geometry.
+//!
+//! Every entity with a mesh is a draw call of its own, which quickly adds up for levels built
+//! out of many small static pieces. A [`StaticBatcher`] merges the meshes sharing a material
+//! into combined [`StaticBatch`]es, baking the transforms of the source entities into the
+//! vertices, so the whole level draws in a few calls.
+//!
+//! The batches are built from CPU-side [`StaticMesh`]es, either offline while building the level
+//! or at runtime when it's loaded. Each batch is then drawn by an entity of its own:
+//!
+//! * its mesh is uploaded from [`StaticMesh::to_mesh_builder`],
+//! * it has the material of the batch and an identity `Transform`, the transforms being baked,
+//! * its [`BoundingSphere`] is [`StaticBatch::bounding_sphere`] for culling,
+//! * its [`StaticBatchSources`] map the triangles of the batch back to the source entities,
+//!   e.g. to find the entity picked by a ray hitting the batch.
+//!
+//! The source entities keep their transforms and other components, but must not be drawn
+//! anymore, e.g. by removing their `Handle<Mesh>`.
+
+use std::{collections::HashMap, hash::Hash, ops::Range};
+
+use amethyst_core::{
+    ecs::Entity,
+    math::{Matrix3, Matrix4, Point3, Vector3, Vector4, U3},
+};
+use rendy::mesh::{MeshBuilder, Normal, Position, Tangent, TexCoord};
+
+use crate::{lightmap::LightmapTexCoord, shape::Shape, visibility::BoundingSphere};
+
+/// Default maximum number of vertices of a batch.
+const DEFAULT_MAX_VERTICES: usize = 1 << 20;
+
+/// CPU-side mesh, with the vertex attributes of the 3D passes and 32-bit indices.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StaticMesh {
+    /// Vertex positions
+    pub positions: Vec<Position>,
+    /// Vertex normals
+    pub normals: Vec<Normal>,
+    /// Vertex tangents, the fourth component being the sign of the bitangent
+    pub tangents: Vec<Tangent>,
+    /// Vertex texture coordinates
+    pub tex_coords: Vec<TexCoord>,
+    /// Vertex lightmap texture coordinates, empty when the mesh has none
+    pub lightmap_tex_coords: Vec<LightmapTexCoord>,
+    /// Triangle list indices
+    pub indices: Vec<u32>,
+}
+
+impl StaticMesh {
+    /// Generate the mesh of a `Shape`, scaled by the given amounts along the x, y, z axes.
+    #[must_use]
+    pub fn from_shape(shape: &Shape, scale: Option<(f32, f32, f32)>) -> Self {
+        let (positions, normals, tangents, tex_coords) =
+            shape.generate_vertices::<(Vec<Position>, Vec<Normal>, Vec<Tangent>, Vec<TexCoord>)>(
+                scale,
+            );
+        #[allow(clippy::cast_possible_truncation)]
+        let indices = (0..positions.len() as u32).collect();
+        Self {
+            positions,
+            normals,
+            tangents,
+            tex_coords,
+            lightmap_tex_coords: Vec::new(),
+            indices,
+        }
+    }
+
+    /// Number of vertices of the mesh.
+    #[must_use]
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Build the `MeshBuilder` uploading the mesh.
+    #[must_use]
+    pub fn to_mesh_builder(&self) -> MeshBuilder<'static> {
+        let mut builder = MeshBuilder::new()
+            .with_vertices(self.positions.clone())
+            .with_vertices(self.normals.clone())
+            .with_vertices(self.tangents.clone())
+            .with_vertices(self.tex_coords.clone())
+            .with_indices(self.indices.clone());
+        if !self.lightmap_tex_coords.is_empty() {
+            builder.add_vertices(self.lightmap_tex_coords.clone());
+        }
+        builder
+    }
+
+    /// Appends `mesh` transformed by `transform`.
+    pub(crate) fn append(&mut self, mesh: &StaticMesh, transform: &Matrix4<f32>) {
+        let linear: Matrix3<f32> = transform.fixed_slice::<U3, U3>(0, 0).clone_owned();
+        let normal_matrix = linear
+            .try_inverse()
+            .map_or_else(Matrix3::identity, |inverse| inverse.transpose());
+        // A mirroring transform turns the triangles inside out and flips the bitangents.
+        let mirrored = linear.determinant() < 0.0;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let base = self.positions.len() as u32;
+        self.positions.extend(mesh.positions.iter().map(|p| {
+            let p = transform.transform_point(&Point3::from(p.0));
+            Position([p.x, p.y, p.z])
+        }));
+        self.normals.extend(mesh.normals.iter().map(|n| {
+            let n = normalize(normal_matrix * Vector3::from(n.0));
+            Normal([n.x, n.y, n.z])
+        }));
+        self.tangents.extend(mesh.tangents.iter().map(|t| {
+            let t = Vector4::from(t.0);
+            let xyz = normalize(linear * t.xyz());
+            let w = if mirrored { -t.w } else { t.w };
+            Tangent([xyz.x, xyz.y, xyz.z, w])
+        }));
+        self.tex_coords.extend_from_slice(&mesh.tex_coords);
+        // Meshes without lightmap texture coordinates get zeroes when merged with others.
+        if !mesh.lightmap_tex_coords.is_empty() || !self.lightmap_tex_coords.is_empty() {
+            let zero = LightmapTexCoord([0.0, 0.0]);
+            self.lightmap_tex_coords.resize(base as usize, zero);
+            self.lightmap_tex_coords
+                .extend_from_slice(&mesh.lightmap_tex_coords);
+            self.lightmap_tex_coords.resize(self.positions.len(), zero);
+        }
+        if mirrored {
+            for triangle in mesh.indices.chunks_exact(3) {
+                self.indices.extend_from_slice(&[
+                    triangle[0] + base,
+                    triangle[2] + base,
+                    triangle[1] + base,
+                ]);
+            }
+        } else {
+            self.indices.extend(mesh.indices.iter().map(|i| i + base));
+        }
+    }
+}
+
+fn normalize(v: Vector3<f32>) -> Vector3<f32> {
+    v.try_normalize(f32::EPSILON).unwrap_or(v)
+}
+
+/// Entity merged into a [`StaticBatch`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchSource {
+    /// The source entity
+    pub entity: Entity,
+    /// Range of the indices of the batch drawing the entity
+    pub indices: Range<u32>,
+    /// Bounding sphere of the entity, in world space
+    pub bounds: BoundingSphere,
+}
+
+/// Component mapping the triangles of a [`StaticBatch`] to the entities they come from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StaticBatchSources {
+    /// The source entities, in the order of their indices
+    pub sources: Vec<BatchSource>,
+}
+
+impl StaticBatchSources {
+    /// The source drawing the `triangle`-th triangle of the batch.
+    #[must_use]
+    pub fn source_of_triangle(&self, triangle: u32) -> Option<&BatchSource> {
+        let index = triangle.checked_mul(3)?;
+        let position = self
+            .sources
+            .binary_search_by(|source| {
+                if source.indices.end <= index {
+                    std::cmp::Ordering::Less
+                } else if source.indices.start > index {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .ok()?;
+        Some(&self.sources[position])
+    }
+
+    /// The source entity drawing the `triangle`-th triangle of the batch.
+    #[must_use]
+    pub fn entity_of_triangle(&self, triangle: u32) -> Option<Entity> {
+        self.source_of_triangle(triangle)
+            .map(|source| source.entity)
+    }
+}
+
+/// Meshes sharing a material merged into one, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct StaticBatch<M> {
+    /// Material of the merged meshes
+    pub material: M,
+    /// The merged mesh, in world space
+    pub mesh: StaticMesh,
+    /// The entities merged into the batch
+    pub sources: StaticBatchSources,
+}
+
+impl<M> StaticBatch<M> {
+    /// Bounding sphere of the whole batch, in world space.
+    #[must_use]
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        bounding_sphere(&self.mesh.positions)
+    }
+}
+
+/// Builds [`StaticBatch`]es out of the meshes of static entities, see the
+/// [module documentation](self).
+///
+/// `M` identifies the material of the meshes, usually a `Handle<Material>`.
+#[derive(Debug, Clone)]
+pub struct StaticBatcher<M> {
+    max_vertices: usize,
+    batches: Vec<StaticBatch<M>>,
+    /// Index of the batch being filled for each material
+    open: HashMap<M, usize>,
+}
+
+impl<M: Clone + Eq + Hash> Default for StaticBatcher<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Clone + Eq + Hash> StaticBatcher<M> {
+    /// Creates an empty batcher.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_vertices: DEFAULT_MAX_VERTICES,
+            batches: Vec::new(),
+            open: HashMap::new(),
+        }
+    }
+
+    /// Starts another batch for a material when it would exceed `max_vertices` vertices, to
+    /// keep the batches small enough to be culled. Defaults to 2^20.
+    ///
+    /// Meshes larger than `max_vertices` still get a batch of their own.
+    #[must_use]
+    pub fn with_max_vertices(mut self, max_vertices: usize) -> Self {
+        self.max_vertices = max_vertices;
+        self
+    }
+
+    /// Merges the `mesh` of `entity` drawn with `material`, at its global `transform`.
+    pub fn add(
+        &mut self,
+        entity: Entity,
+        material: M,
+        mesh: &StaticMesh,
+        transform: &Matrix4<f32>,
+    ) {
+        let max_vertices = self.max_vertices;
+        let batches = &mut self.batches;
+        let index = match self.open.get(&material) {
+            Some(&index)
+                if batches[index].mesh.vertex_count() + mesh.vertex_count() <= max_vertices =>
+            {
+                index
+            }
+            _ => {
+                batches.push(StaticBatch {
+                    material: material.clone(),
+                    mesh: StaticMesh::default(),
+                    sources: StaticBatchSources::default(),
+                });
+                self.open.insert(material, batches.len() - 1);
+                batches.len() - 1
+            }
+        };
+
+        let batch = &mut batches[index];
+        #[allow(clippy::cast_possible_truncation)]
+        let (first_vertex, first_index) =
+            (batch.mesh.positions.len(), batch.mesh.indices.len() as u32);
+        batch.mesh.append(mesh, transform);
+        #[allow(clippy::cast_possible_truncation)]
+        let last_index = batch.mesh.indices.len() as u32;
+        batch.sources.sources.push(BatchSource {
+            entity,
+            indices: first_index..last_index,
+            bounds: bounding_sphere(&batch.mesh.positions[first_vertex..]),
+        });
+    }
+
+    /// The batches built, in the order their materials were first added.
+    #[must_use]
+    pub fn build(self) -> Vec<StaticBatch<M>> {
+        self.batches
+    }
+}
+
+/// Sphere centered on the bounding box of `positions`, containing them.
+fn bounding_sphere(positions: &[Position]) -> BoundingSphere {
+    if positions.is_empty() {
+        return BoundingSphere::origin(0.0);
+    }
+    let (min, max) = positions.iter().fold(
+        (Vector3::repeat(f32::MAX), Vector3::repeat(f32::MIN)),
+        |(min, max), p| {
+            let p = Vector3::from(p.0);
+            (min.inf(&p), max.sup(&p))
+        },
+    );
+    let center = Point3::from((min + max) / 2.0);
+    let radius = positions
+        .iter()
+        .map(|p| (Point3::from(p.0) - center).norm())
+        .fold(0.0, f32::max);
+    BoundingSphere::new(center, radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{ecs::World, math::Translation3};
+
+    use super::*;
+
+    fn triangle() -> StaticMesh {
+        StaticMesh {
+            positions: vec![
+                Position([0.0, 0.0, 0.0]),
+                Position([1.0, 0.0, 0.0]),
+                Position([0.0, 1.0, 0.0]),
+            ],
+            normals: vec![Normal([0.0, 0.0, 1.0]); 3],
+            tangents: vec![Tangent([1.0, 0.0, 0.0, 1.0]); 3],
+            tex_coords: vec![TexCoord([0.0, 0.0]); 3],
+            lightmap_tex_coords: Vec::new(),
+            indices: vec![0, 1, 2],
+        }
+    }
+
+    #[test]
+    fn merges_meshes_sharing_a_material() {
+        let mut world = World::default();
+        let (a, b, c) = (world.push(()), world.push(()), world.push(()));
+        let mut batcher = StaticBatcher::new();
+        batcher.add(a, "stone", &triangle(), &Matrix4::identity());
+        batcher.add(b, "wood", &triangle(), &Matrix4::identity());
+        batcher.add(
+            c,
+            "stone",
+            &triangle(),
+            &Translation3::new(2.0, 0.0, 0.0).to_homogeneous(),
+        );
+        let batches = batcher.build();
+
+        assert_eq!(batches.len(), 2);
+        let stone = &batches[0];
+        assert_eq!(stone.material, "stone");
+        assert_eq!(stone.mesh.vertex_count(), 6);
+        assert_eq!(stone.mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(stone.mesh.positions[4], Position([3.0, 0.0, 0.0]));
+        assert_eq!(stone.sources.entity_of_triangle(0), Some(a));
+        assert_eq!(stone.sources.entity_of_triangle(1), Some(c));
+        assert_eq!(stone.sources.entity_of_triangle(2), None);
+        assert_eq!(batches[1].sources.entity_of_triangle(0), Some(b));
+    }
+
+    #[test]
+    fn mirroring_keeps_the_triangles_facing_out() {
+        let mut world = World::default();
+        let mut batcher = StaticBatcher::new();
+        let mirror = Matrix4::new_nonuniform_scaling(&Vector3::new(-1.0, 1.0, 1.0));
+        batcher.add(world.push(()), (), &triangle(), &mirror);
+        let batch = &batcher.build()[0];
+
+        assert_eq!(batch.mesh.indices, vec![0, 2, 1]);
+        assert_eq!(batch.mesh.tangents[0], Tangent([-1.0, 0.0, 0.0, -1.0]));
+        assert_eq!(batch.mesh.normals[0], Normal([0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn splits_batches_exceeding_max_vertices() {
+        let mut world = World::default();
+        let mut batcher = StaticBatcher::new().with_max_vertices(4);
+        for _ in 0..3 {
+            batcher.add(world.push(()), (), &triangle(), &Matrix4::identity());
+        }
+
+        assert_eq!(batcher.build().len(), 3);
+    }
+}
@@ -5,12 +5,13 @@ use amethyst_core::{
     math::{convert, Vector3},
     transform::Transform,
 };
-use glsl_layout::Uniform;
+use glsl_layout::{vec3, Uniform};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 use util::{usize_range, write_into_slice};
 
 use crate::{
+    bundle::Target,
     light::Light,
     pod::{self, IntoPod},
     rendy::{
@@ -41,6 +42,9 @@ const MAX_SPOT_LIGHTS: usize = 128;
 pub struct EnvironmentSub<B: Backend> {
     layout: RendyHandle<DescriptorSetLayout<B>>,
     per_image: Vec<PerImageEnvironmentSub<B>>,
+    target: Target,
+    viewport: Option<usize>,
+    ambient: AmbientGatherer,
 }
 
 /// Submodule for loading and binding descriptor sets for a 3D, lit environment.
@@ -89,9 +93,27 @@ impl<B: Backend> EnvironmentSub<B> {
         Ok(Self {
             layout,
             per_image: Vec::new(),
+            target: Target::Main,
+            viewport: None,
+            ambient: AmbientGatherer::default(),
         })
     }
 
+    /// Use the camera of given render target instead of the active camera.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Use the camera rendering into the viewport with given index, see
+    /// [`Viewport::cameras`](crate::camera::Viewport::cameras).
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
     /// Returns the raw `DescriptorSetLayout` for this environment
     #[must_use]
     pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
@@ -116,7 +138,15 @@ impl<B: Backend> EnvironmentSub<B> {
             }
             &mut self.per_image[index]
         };
-        this_image.process(factory, world, resources)
+        let ambient_color = self.ambient.gather(resources);
+        this_image.process(
+            factory,
+            world,
+            resources,
+            self.target,
+            self.viewport,
+            ambient_color,
+        )
     }
 
     /// Binds this environment set for all images.
@@ -157,7 +187,15 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
         }
     }
 
-    fn process(&mut self, factory: &Factory<B>, world: &World, resources: &Resources) -> bool {
+    fn process(
+        &mut self,
+        factory: &Factory<B>,
+        world: &World,
+        resources: &Resources,
+        target: Target,
+        viewport: Option<usize>,
+        ambient_color: vec3,
+    ) -> bool {
         let align = factory
             .physical()
             .limits()
@@ -211,14 +249,14 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
             let CameraGatherer {
                 camera_position,
                 projview,
-            } = CameraGatherer::gather(world, resources);
+            } = CameraGatherer::gather_for_viewport(world, resources, target, viewport);
 
             let mut mapped = buffer.map(factory, whole_range.clone()).unwrap();
             let mut writer = unsafe { mapped.write::<u8>(factory, whole_range).unwrap() };
             let dst_slice = unsafe { writer.slice() };
 
             let mut env = pod::Environment {
-                ambient_color: AmbientGatherer::gather(resources),
+                ambient_color,
                 camera_position,
                 point_light_count: 0,
                 directional_light_count: 0,
@@ -0,0 +1,372 @@
+//! Image based lighting submodule, binding the `EnvironmentMap` and the `ReflectionProbe`s of
+//! the rendered camera.
+use amethyst_assets::{AssetHandle, AssetStorage, LoadHandle};
+use amethyst_core::{
+    ecs::{EntityStore, IntoQuery, Resources, World},
+    math::Vector3,
+    transform::Transform,
+};
+use glsl_layout::{float, int, vec3, Uniform};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    bundle::Target,
+    environment_map::EnvironmentMap,
+    pod::IntoPod,
+    reflection_probe::{nearest_probes, ReflectionProbe, MAX_REFLECTION_PROBES},
+    rendy::{
+        command::{QueueId, RenderPassEncoder},
+        factory::{Factory, ImageState},
+        hal::{
+            self,
+            format::Format,
+            image::{Kind, ViewKind},
+            pso::{CreationError, Descriptor},
+        },
+        memory::Write as _,
+        resource::{Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle},
+        texture::{Texture as RendyTexture, TextureBuilder},
+    },
+    submodules::gather::CameraGatherer,
+    types::{Backend, Texture},
+    util,
+};
+
+/// Reflection probe arguments.
+/// ```glsl,ignore
+/// struct ReflectionProbe {
+///    vec3 center;
+///    float radius;
+///    float blend_distance;
+///    float intensity;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Default, Uniform)]
+#[repr(C, align(16))]
+pub(crate) struct ReflectionProbeArgs {
+    center: vec3,
+    radius: float,
+    blend_distance: float,
+    intensity: float,
+}
+
+/// Image based lighting arguments.
+/// ```glsl,ignore
+/// uniform EnvironmentMapArgs {
+///    float intensity;
+///    int reflection_probe_count;
+///    ReflectionProbe reflection_probes[MAX_REFLECTION_PROBES];
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Uniform)]
+#[repr(C, align(16))]
+pub(crate) struct EnvironmentMapArgs {
+    intensity: float,
+    reflection_probe_count: int,
+    reflection_probes: [ReflectionProbeArgs; MAX_REFLECTION_PROBES],
+}
+
+/// Textures bound to a set, by load handle and version.
+type BoundTextures = (
+    Option<[(LoadHandle, u32); 2]>,
+    [Option<(LoadHandle, u32)>; MAX_REFLECTION_PROBES],
+);
+
+/// Submodule binding irradiance and specular cubemaps of an [`EnvironmentMap`], and the specular
+/// cubemaps of the [`ReflectionProbe`]s nearest to the camera.
+///
+/// When no environment map is available or its textures aren't loaded yet, black cubemaps are
+/// bound with zero intensity.
+#[derive(Debug)]
+pub struct EnvironmentMapSub<B: Backend> {
+    layout: Handle<DescriptorSetLayout<B>>,
+    fallback: RendyTexture<B>,
+    per_image: Vec<PerImageEnvironmentMapSub<B>>,
+    target: Target,
+    viewport: Option<usize>,
+}
+
+#[derive(Debug)]
+struct PerImageEnvironmentMapSub<B: Backend> {
+    buffer: Escape<Buffer<B>>,
+    set: Escape<DescriptorSet<B>>,
+    bound: Option<BoundTextures>,
+}
+
+impl<B: Backend> EnvironmentMapSub<B> {
+    /// Create a new `EnvironmentMapSub`, uploading the fallback cubemap on given queue.
+    pub fn new(factory: &mut Factory<B>, queue: QueueId) -> Result<Self, CreationError> {
+        use rendy::hal::pso::{
+            BufferDescriptorFormat, BufferDescriptorType, DescriptorType, ImageDescriptorType,
+            ShaderStageFlags,
+        };
+
+        let layout = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(vec![
+                (
+                    2,
+                    DescriptorType::Image {
+                        ty: ImageDescriptorType::Sampled { with_sampler: true },
+                    },
+                    ShaderStageFlags::FRAGMENT,
+                ),
+                (
+                    1,
+                    DescriptorType::Buffer {
+                        ty: BufferDescriptorType::Uniform,
+                        format: BufferDescriptorFormat::Structured {
+                            dynamic_offset: false,
+                        },
+                    },
+                    ShaderStageFlags::FRAGMENT,
+                ),
+                (
+                    MAX_REFLECTION_PROBES as u32,
+                    DescriptorType::Image {
+                        ty: ImageDescriptorType::Sampled { with_sampler: true },
+                    },
+                    ShaderStageFlags::FRAGMENT,
+                ),
+            ]))?
+            .into();
+
+        let fallback = TextureBuilder::new()
+            .with_kind(Kind::D2(1, 1, 6, 1))
+            .with_view_kind(ViewKind::Cube)
+            .with_data_width(1)
+            .with_data_height(1)
+            .with_raw_data(vec![0_u8; 4 * 6], Format::Rgba8Unorm)
+            .build(
+                ImageState {
+                    queue,
+                    stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
+                    access: hal::image::Access::SHADER_READ,
+                    layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                },
+                factory,
+            )
+            .map_err(|_| CreationError::Other)?;
+
+        Ok(Self {
+            layout,
+            fallback,
+            per_image: Vec::new(),
+            target: Target::Main,
+            viewport: None,
+        })
+    }
+
+    /// Use the environment map of the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Use the environment map of the camera of the viewport with given index, see
+    /// [`Viewport::cameras`](crate::camera::Viewport::cameras).
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Returns the raw `DescriptorSetLayout` for this environment map
+    #[must_use]
+    pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
+        self.layout.raw()
+    }
+
+    /// Writes the environment map of the rendered camera into the set of given image.
+    ///
+    /// Returns `true` if the bound cubemaps changed.
+    pub fn process(
+        &mut self,
+        factory: &Factory<B>,
+        index: usize,
+        world: &World,
+        resources: &Resources,
+    ) -> bool {
+        #[cfg(feature = "profiler")]
+        profile_scope!("process");
+
+        while self.per_image.len() <= index {
+            self.per_image
+                .push(PerImageEnvironmentMapSub::new(factory, &self.layout));
+        }
+
+        let camera = CameraGatherer::gather_camera_entity_for_viewport(
+            world,
+            resources,
+            self.target,
+            self.viewport,
+        );
+        let map = camera
+            .and_then(|entity| world.entry_ref(entity).ok())
+            .and_then(|entry| entry.get_component::<EnvironmentMap>().ok().cloned())
+            .or_else(|| resources.get::<EnvironmentMap>().map(|map| (*map).clone()));
+
+        let storage = resources.get::<AssetStorage<Texture>>().unwrap();
+        let loaded = map.as_ref().and_then(|map| {
+            let (irradiance, irradiance_version) =
+                storage.get_asset_with_version(&map.irradiance)?;
+            let (specular, specular_version) = storage.get_asset_with_version(&map.specular)?;
+            Some((
+                [
+                    (map.irradiance.load_handle(), irradiance_version),
+                    (map.specular.load_handle(), specular_version),
+                ],
+                B::unwrap_texture(irradiance)?,
+                B::unwrap_texture(specular)?,
+                map.intensity,
+            ))
+        });
+
+        let camera_position = camera
+            .and_then(|entity| world.entry_ref(entity).ok())
+            .and_then(|entry| {
+                entry
+                    .get_component::<Transform>()
+                    .ok()
+                    .map(|transform| transform.global_matrix().column(3).xyz())
+            })
+            .unwrap_or_else(Vector3::zeros);
+        let probes = nearest_probes(
+            &camera_position,
+            <(&ReflectionProbe, &Transform)>::query()
+                .iter(world)
+                .filter(|(probe, _)| {
+                    probe
+                        .specular()
+                        .and_then(|specular| storage.get(specular))
+                        .and_then(B::unwrap_texture)
+                        .is_some()
+                })
+                .map(|(probe, transform)| (transform.global_matrix().column(3).xyz(), probe)),
+        );
+        let mut probe_args = [ReflectionProbeArgs::default(); MAX_REFLECTION_PROBES];
+        let mut probe_maps = [None; MAX_REFLECTION_PROBES];
+        for (i, (center, probe)) in probes.iter().enumerate() {
+            let specular = probe.specular().unwrap();
+            let (texture, version) = storage.get_asset_with_version(specular).unwrap();
+            probe_args[i] = ReflectionProbeArgs {
+                center: (*center).into_pod(),
+                radius: probe.radius,
+                blend_distance: probe.blend_distance,
+                intensity: probe.intensity,
+            };
+            probe_maps[i] = Some((
+                (specular.load_handle(), version),
+                B::unwrap_texture(texture).unwrap(),
+            ));
+        }
+
+        let this_image = &mut self.per_image[index];
+        let (key, irradiance, specular, intensity) = match loaded {
+            Some((key, irradiance, specular, intensity)) => {
+                (Some(key), irradiance, specular, intensity)
+            }
+            None => (None, &self.fallback, &self.fallback, 0.0),
+        };
+        this_image.write_args(
+            factory,
+            EnvironmentMapArgs {
+                intensity,
+                reflection_probe_count: probes.len() as i32,
+                reflection_probes: probe_args,
+            },
+        );
+
+        let mut probe_keys = [None; MAX_REFLECTION_PROBES];
+        for (probe_key, map) in probe_keys.iter_mut().zip(&probe_maps) {
+            *probe_key = map.map(|(key, _)| key);
+        }
+        let key = (key, probe_keys);
+        if this_image.bound == Some(key) {
+            return false;
+        }
+        this_image.bound = Some(key);
+
+        let layout = hal::image::Layout::ShaderReadOnlyOptimal;
+        let cubemap = |binding, texture: &RendyTexture<B>| {
+            util::desc_write(
+                this_image.set.raw(),
+                binding,
+                Descriptor::CombinedImageSampler(
+                    texture.view().raw(),
+                    layout,
+                    texture.sampler().raw(),
+                ),
+            )
+        };
+        let fallback = &self.fallback;
+        let writes: Vec<_> = vec![cubemap(0, irradiance), cubemap(1, specular)]
+            .into_iter()
+            .chain(probe_maps.iter().enumerate().map(|(i, map)| {
+                cubemap(3 + i as u32, map.map_or(fallback, |(_, texture)| texture))
+            }))
+            .collect();
+        unsafe {
+            factory.write_descriptor_sets(writes);
+        }
+        true
+    }
+
+    /// Binds the environment map set of given image.
+    #[inline]
+    pub fn bind(
+        &self,
+        index: usize,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id,
+                Some(self.per_image[index].set.raw()),
+                std::iter::empty(),
+            );
+        }
+    }
+}
+
+impl<B: Backend> PerImageEnvironmentMapSub<B> {
+    fn new(factory: &Factory<B>, layout: &Handle<DescriptorSetLayout<B>>) -> Self {
+        let buffer = factory
+            .create_buffer(
+                BufferInfo {
+                    size: std::mem::size_of::<<EnvironmentMapArgs as Uniform>::Std140>() as u64,
+                    usage: hal::buffer::Usage::UNIFORM,
+                },
+                rendy::memory::Dynamic,
+            )
+            .unwrap();
+
+        let set = factory.create_descriptor_set(layout.clone()).unwrap();
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                2,
+                Descriptor::Buffer(buffer.raw(), rendy::resource::SubRange::WHOLE),
+            )));
+        }
+
+        Self {
+            buffer,
+            set,
+            bound: None,
+        }
+    }
+
+    fn write_args(&mut self, factory: &Factory<B>, args: EnvironmentMapArgs) {
+        let args = args.std140();
+        let size = std::mem::size_of_val(&args) as u64;
+        let mut mapped = self.buffer.map(factory.device(), 0..size).unwrap();
+        let mut writer = unsafe { mapped.write::<u8>(factory.device(), 0..size).unwrap() };
+        let slice = unsafe { writer.slice() };
+        slice.copy_from_slice(util::slice_as_bytes(&[args]));
+    }
+}
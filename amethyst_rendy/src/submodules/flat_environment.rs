@@ -5,6 +5,7 @@ use amethyst_core::ecs::{Resources, World};
 use thread_profiler::profile_scope;
 
 use crate::{
+    bundle::Target,
     pod::ViewArgs,
     rendy::{command::RenderPassEncoder, factory::Factory},
     submodules::{gather::CameraGatherer, uniform::DynamicUniform},
@@ -17,6 +18,8 @@ use crate::{
 #[derive(Debug)]
 pub struct FlatEnvironmentSub<B: Backend> {
     uniform: DynamicUniform<B, ViewArgs>,
+    target: Target,
+    viewport: Option<usize>,
 }
 
 impl<B: Backend> FlatEnvironmentSub<B> {
@@ -24,9 +27,26 @@ impl<B: Backend> FlatEnvironmentSub<B> {
     pub fn new(factory: &Factory<B>) -> Result<Self, rendy::hal::pso::CreationError> {
         Ok(Self {
             uniform: DynamicUniform::new(factory, rendy::hal::pso::ShaderStageFlags::VERTEX)?,
+            target: Target::Main,
+            viewport: None,
         })
     }
 
+    /// Use the camera of given render target instead of the active camera.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Use the camera rendering into the viewport with given index, see
+    /// [`Viewport::cameras`](crate::camera::Viewport::cameras).
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
     /// Returns the raw `DescriptorSetLayout` for this environment
     #[must_use]
     pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
@@ -43,7 +63,9 @@ impl<B: Backend> FlatEnvironmentSub<B> {
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("process");
-        let projview = CameraGatherer::gather(world, resources).projview;
+        let projview =
+            CameraGatherer::gather_for_viewport(world, resources, self.target, self.viewport)
+                .projview;
         self.uniform.write(factory, index, projview);
     }
 
@@ -1,16 +1,19 @@
 //! Helper gatherer structures for collecting information about the world.
 use amethyst_core::{
-    ecs::{Entity, EntityStore, IntoQuery, Read, Resources, World},
+    ecs::{component, Entity, EntityStore, IntoQuery, Read, Resources, World},
     math::{convert, Matrix4, Vector3},
     transform::Transform,
+    Time, Tracked, VersionTracker,
 };
 use glsl_layout::{vec3, Uniform};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
 use crate::{
-    camera::{ActiveCamera, Camera},
+    bundle::Target,
+    camera::{ActiveCamera, Camera, Viewport},
     pod::{self, IntoPod},
+    render_target::RenderTarget,
     resources::AmbientColor,
 };
 
@@ -27,9 +30,30 @@ impl CameraGatherer {
     /// Collect just the entity which has the current `ActiveCamera`
     #[must_use]
     pub fn gather_camera_entity(world: &World, resources: &Resources) -> Option<Entity> {
+        Self::gather_camera_entity_for(world, resources, Target::Main)
+    }
+
+    /// Collect the camera entity rendering into `target`.
+    ///
+    /// Targets bound to a camera by a matching [`RenderTarget`] use that camera, all other
+    /// targets use the current `ActiveCamera`, or the first camera not bound to another target.
+    #[must_use]
+    pub fn gather_camera_entity_for(
+        world: &World,
+        resources: &Resources,
+        target: Target,
+    ) -> Option<Entity> {
         #[cfg(feature = "profiler")]
         profile_scope!("gather_camera (1st)");
 
+        let bound = <(Entity, Read<Camera>, Read<RenderTarget>)>::query()
+            .iter(world)
+            .find(|(_, _, render_target)| render_target.target == target)
+            .map(|(e, _, _)| *e);
+        if bound.is_some() {
+            return bound;
+        }
+
         // Get camera entity from `ActiveCamera` resource
         let active_camera = resources.get::<ActiveCamera>().and_then(|r| r.entity);
 
@@ -49,6 +73,7 @@ impl CameraGatherer {
             None => {
                 // Fetch first available camera
                 <(Entity, Read<Camera>)>::query()
+                    .filter(!component::<RenderTarget>())
                     .iter(world)
                     .next()
                     .map(|(e, _)| *e)
@@ -56,6 +81,21 @@ impl CameraGatherer {
         }
     }
 
+    /// Collect the camera entity rendering into the viewport with given index, see
+    /// [`Viewport::cameras`]. Without an index, the camera rendering into `target` is used.
+    #[must_use]
+    pub fn gather_camera_entity_for_viewport(
+        world: &World,
+        resources: &Resources,
+        target: Target,
+        viewport: Option<usize>,
+    ) -> Option<Entity> {
+        match viewport {
+            Some(index) => Viewport::cameras(world).get(index).map(|(e, _)| *e),
+            None => Self::gather_camera_entity_for(world, resources, target),
+        }
+    }
+
     /// Collect `ActiveCamera` and `Camera` instances from the provided resource storage and selects
     /// the appropriate camera to use for projection, and returns the camera position and extracted
     /// projection matrix.
@@ -63,13 +103,32 @@ impl CameraGatherer {
     /// The matrix returned is the camera's `Projection` matrix and the camera `Transform::global_view_matrix`
     #[must_use]
     pub fn gather(world: &World, resources: &Resources) -> Self {
+        Self::gather_for(world, resources, Target::Main)
+    }
+
+    /// Same as [`CameraGatherer::gather`], but for the camera rendering into `target`.
+    #[must_use]
+    pub fn gather_for(world: &World, resources: &Resources, target: Target) -> Self {
+        Self::gather_for_viewport(world, resources, target, None)
+    }
+
+    /// Same as [`CameraGatherer::gather`], but for the camera rendering into the viewport with
+    /// given index, see [`CameraGatherer::gather_camera_entity_for_viewport`].
+    #[must_use]
+    pub fn gather_for_viewport(
+        world: &World,
+        resources: &Resources,
+        target: Target,
+        viewport: Option<usize>,
+    ) -> Self {
         #[cfg(feature = "profiler")]
         profile_scope!("gather_cameras");
 
         let defcam = Camera::standard_2d(1.0, 1.0);
         let identity = Transform::default();
 
-        let camera_entity = Self::gather_camera_entity(world, resources);
+        let camera_entity =
+            Self::gather_camera_entity_for_viewport(world, resources, target, viewport);
 
         let camera =
             camera_entity.and_then(|e| world.entry_ref(e).unwrap().into_component::<Camera>().ok());
@@ -98,6 +157,9 @@ impl CameraGatherer {
             proj: proj.into(),
             view: view.into(),
             proj_view: proj_view.into(),
+            time: resources
+                .get::<Time>()
+                .map_or(0.0, |time| time.absolute_time().as_secs_f32()),
         }
         .std140();
 
@@ -108,18 +170,34 @@ impl CameraGatherer {
     }
 }
 
-/// If an `AmbientColor` exists in the resources, return it - otherwise return pure white.
-#[derive(Debug)]
-pub struct AmbientGatherer;
+/// Helper `AmbientGatherer` for fetching the linear ambient color from the
+/// `Tracked<AmbientColor>` resource, or a plain `AmbientColor` resource, black when there is none.
+///
+/// A tracked color is only converted again when the resource changed, a plain one every frame.
+#[derive(Debug, Default)]
+pub struct AmbientGatherer {
+    tracker: VersionTracker,
+    color: [f32; 3],
+}
+
 impl AmbientGatherer {
-    /// If an `AmbientColor` exists in the resources, return it - otherwise return pure white.
-    #[must_use]
-    pub fn gather(resources: &Resources) -> vec3 {
-        resources
-            .get::<AmbientColor>()
-            .map_or([0.0, 0.0, 0.0].into(), |c| {
-                let (r, g, b, _) = c.0.into_components();
-                [r, g, b].into()
-            })
+    /// Returns the linear ambient color of the scene.
+    pub fn gather(&mut self, resources: &Resources) -> vec3 {
+        if let Some(ambient) = resources.get::<Tracked<AmbientColor>>() {
+            if self.tracker.update(&ambient) {
+                self.color = linear(&ambient);
+            }
+        } else {
+            self.tracker.reset();
+            self.color = resources
+                .get::<AmbientColor>()
+                .map_or([0.0; 3], |ambient| linear(&ambient));
+        }
+        self.color.into()
     }
 }
+
+fn linear(ambient: &AmbientColor) -> [f32; 3] {
+    let (r, g, b, _) = ambient.0.into_linear().into_components();
+    [r, g, b]
+}
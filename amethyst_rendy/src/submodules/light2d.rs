@@ -0,0 +1,264 @@
+//! 2D lighting submodule, binding the lights and shadow casting sprites of the scene.
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    ecs::{component, IntoQuery, Read, Resources, World},
+    transform::Transform,
+};
+use glsl_layout::{vec3, Uniform};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use util::{usize_range, write_into_slice};
+
+use crate::{
+    light2d::{Light2D, ShadowCaster2D},
+    pod,
+    rendy::{
+        command::RenderPassEncoder,
+        factory::Factory,
+        hal::{
+            self,
+            adapter::PhysicalDevice,
+            pso::{CreationError, Descriptor},
+        },
+        memory::Write as _,
+        resource::{Buffer, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle},
+    },
+    sprite::{SpriteRender, SpriteSheet, Sprites},
+    submodules::gather::AmbientGatherer,
+    types::Backend,
+    util::{self, TapCountIter},
+};
+
+/// Maximum number of [`Light2D`]s lighting sprites, further lights are ignored.
+pub const MAX_LIGHTS_2D: usize = 32;
+/// Maximum number of [`ShadowCaster2D`] sprites, further sprites cast no shadows.
+pub const MAX_OCCLUDERS_2D: usize = 128;
+
+/// Submodule for loading and binding descriptor sets of the 2D lights and shadow casting sprites
+/// of the scene, with per-image submissions.
+#[derive(Debug)]
+pub struct Light2DSub<B: Backend> {
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    per_image: Vec<PerImageLight2DSub<B>>,
+    ambient: AmbientGatherer,
+}
+
+#[derive(Debug)]
+struct PerImageLight2DSub<B: Backend> {
+    buffer: Option<Escape<Buffer<B>>>,
+    set: Escape<DescriptorSet<B>>,
+}
+
+impl<B: Backend> Light2DSub<B> {
+    /// Create and allocate a new `Light2DSub` with the provided rendy `Factory`.
+    pub fn new(factory: &Factory<B>) -> Result<Self, CreationError> {
+        use rendy::hal::pso::{
+            BufferDescriptorFormat, BufferDescriptorType, DescriptorType, ShaderStageFlags,
+        };
+
+        let layout = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(vec![(
+                3,
+                DescriptorType::Buffer {
+                    ty: BufferDescriptorType::Uniform,
+                    format: BufferDescriptorFormat::Structured {
+                        dynamic_offset: false,
+                    },
+                },
+                ShaderStageFlags::FRAGMENT,
+            )]))?
+            .into();
+
+        Ok(Self {
+            layout,
+            per_image: Vec::new(),
+            ambient: AmbientGatherer::default(),
+        })
+    }
+
+    /// Returns the raw `DescriptorSetLayout` for the 2D lights
+    #[must_use]
+    pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
+        self.layout.raw()
+    }
+
+    /// Writes the lights and shadow casting sprites of the scene into the set of given image.
+    ///
+    /// Returns `true` if the buffer was reallocated.
+    pub fn process(
+        &mut self,
+        factory: &Factory<B>,
+        index: usize,
+        world: &World,
+        resources: &Resources,
+    ) -> bool {
+        #[cfg(feature = "profiler")]
+        profile_scope!("process");
+
+        while self.per_image.len() <= index {
+            self.per_image
+                .push(PerImageLight2DSub::new(factory, &self.layout));
+        }
+        let ambient_color = self.ambient.gather(resources);
+        self.per_image[index].process(factory, world, resources, ambient_color)
+    }
+
+    /// Binds the 2D lights set of given image.
+    #[inline]
+    pub fn bind(
+        &self,
+        index: usize,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id,
+                Some(self.per_image[index].set.raw()),
+                std::iter::empty(),
+            );
+        }
+    }
+}
+
+impl<B: Backend> PerImageLight2DSub<B> {
+    fn new(factory: &Factory<B>, layout: &RendyHandle<DescriptorSetLayout<B>>) -> Self {
+        Self {
+            buffer: None,
+            set: factory.create_descriptor_set(layout.clone()).unwrap(),
+        }
+    }
+
+    fn process(
+        &mut self,
+        factory: &Factory<B>,
+        world: &World,
+        resources: &Resources,
+        ambient_color: vec3,
+    ) -> bool {
+        let align = factory
+            .physical()
+            .limits()
+            .min_uniform_buffer_offset_alignment;
+
+        let env_range = 0..util::align_size::<pod::Environment2D>(align, 1);
+        let light_range = util::next_range(
+            &env_range,
+            util::align_size::<pod::Light2D>(align, MAX_LIGHTS_2D),
+        );
+        let occluder_range = util::next_range(
+            &light_range,
+            util::align_size::<pod::Occluder2D>(align, MAX_OCCLUDERS_2D),
+        );
+        let whole_range = 0..occluder_range.end;
+
+        let new_buffer = util::ensure_buffer(
+            factory,
+            &mut self.buffer,
+            hal::buffer::Usage::UNIFORM,
+            rendy::memory::Dynamic,
+            whole_range.end,
+        )
+        .unwrap();
+        let buffer = match self.buffer.as_mut() {
+            Some(buffer) => buffer,
+            None => return new_buffer,
+        };
+        if new_buffer {
+            use util::{desc_write, sub_range};
+            let raw = buffer.raw();
+            let set = self.set.raw();
+            unsafe {
+                factory.write_descriptor_sets(vec![
+                    desc_write(
+                        set,
+                        0,
+                        Descriptor::Buffer(raw, sub_range(env_range.clone())),
+                    ),
+                    desc_write(
+                        set,
+                        1,
+                        Descriptor::Buffer(raw, sub_range(light_range.clone())),
+                    ),
+                    desc_write(
+                        set,
+                        2,
+                        Descriptor::Buffer(raw, sub_range(occluder_range.clone())),
+                    ),
+                ]);
+            }
+        }
+
+        let mut env = pod::Environment2D {
+            ambient_color,
+            light_count: 0,
+            occluder_count: 0,
+        }
+        .std140();
+
+        let mut light_query = <(Read<Light2D>, Read<Transform>)>::query();
+        let lights = light_query
+            .iter(world)
+            .map(|(light, transform)| {
+                let position = transform.global_matrix().column(3).xy();
+                match &*light {
+                    Light2D::Point(light) => {
+                        pod::Light2D {
+                            position: [position.x, position.y, light.height].into(),
+                            radius: light.radius.max(std::f32::EPSILON),
+                            color: light_color(light.color, light.intensity),
+                            shadows: i32::from(light.shadows),
+                        }
+                    }
+                    Light2D::Directional(light) => {
+                        pod::Light2D {
+                            position: light.to_light().into(),
+                            radius: 0.0,
+                            color: light_color(light.color, light.intensity),
+                            shadows: 0,
+                        }
+                    }
+                }
+                .std140()
+            })
+            .take(MAX_LIGHTS_2D);
+
+        let sprite_sheet_storage = resources.get::<AssetStorage<SpriteSheet>>().unwrap();
+        let sprites_storage = resources.get::<AssetStorage<Sprites>>().unwrap();
+        let mut occluder_query =
+            <(&SpriteRender, &Transform)>::query().filter(component::<ShadowCaster2D>());
+        let occluders = occluder_query
+            .iter(world)
+            .filter_map(|(sprite_render, transform)| {
+                let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
+                let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
+                let sprite = sprites.get(sprite_render.sprite_number)?;
+                pod::Occluder2D::from_data(sprite, transform)
+            })
+            .map(|occluder| occluder.std140())
+            .take(MAX_OCCLUDERS_2D);
+
+        let mut mapped = buffer.map(factory, whole_range.clone()).unwrap();
+        let mut writer = unsafe { mapped.write::<u8>(factory, whole_range).unwrap() };
+        let dst_slice = unsafe { writer.slice() };
+        write_into_slice(
+            &mut dst_slice[usize_range(light_range)],
+            lights.tap_count(&mut env.light_count),
+        );
+        write_into_slice(
+            &mut dst_slice[usize_range(occluder_range)],
+            occluders.tap_count(&mut env.occluder_count),
+        );
+        write_into_slice(&mut dst_slice[usize_range(env_range)], Some(env));
+
+        new_buffer
+    }
+}
+
+/// Linear light color multiplied by its intensity.
+fn light_color(color: palette::Srgb, intensity: f32) -> vec3 {
+    let (r, g, b) = color.into_linear().into_components();
+    [r * intensity, g * intensity, b * intensity].into()
+}
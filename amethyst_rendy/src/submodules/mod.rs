@@ -1,6 +1,8 @@
 //! Various helpers and implementations for sub functions of render passes.
 mod environment;
+mod environment_map;
 mod flat_environment;
+mod light2d;
 mod material;
 mod skinning;
 mod texture;
@@ -10,7 +12,9 @@ mod vertex;
 pub mod gather;
 
 pub use environment::*;
+pub use environment_map::*;
 pub use flat_environment::*;
+pub use light2d::*;
 pub use material::*;
 pub use skinning::*;
 pub use texture::*;
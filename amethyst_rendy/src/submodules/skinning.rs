@@ -1,4 +1,6 @@
 //! 3D Skinned per-image buffer handling.
+use std::ops::Range;
+
 use amethyst_core::ecs::Entity;
 use fnv::FnvHashMap;
 use rendy::resource::SubRange;
@@ -19,18 +21,36 @@ use crate::{
 };
 
 /// Provides per-image abstraction for submitting skinned mesh skeletal information.
+///
+/// The joint palettes of all skins share a single storage buffer per image, indexed with the
+/// offset returned by `insert`. Palettes keep their offset across frames, so only the range of
+/// palettes which changed since an image was last committed is uploaded to it.
 #[derive(Debug)]
 pub struct SkinningSub<B: Backend> {
     layout: RendyHandle<DescriptorSetLayout<B>>,
-    skin_offset_map: FnvHashMap<Entity, u32>,
+    palettes: FnvHashMap<Entity, Palette>,
     staging: Vec<[[f32; 4]; 4]>,
+    frame: u64,
+    /// Frame from which on palettes were moved, requiring a full upload.
+    relayout_frame: u64,
     per_image: Vec<PerImageSkinningSub<B>>,
 }
 
+#[derive(Debug)]
+struct Palette {
+    offset: u32,
+    len: u32,
+    /// Last frame the matrices of the palette changed.
+    modified: u64,
+    /// Was the palette inserted this frame.
+    used: bool,
+}
+
 #[derive(Debug)]
 struct PerImageSkinningSub<B: Backend> {
     buffer: Option<Escape<Buffer<B>>>,
     set: Escape<DescriptorSet<B>>,
+    committed: Option<u64>,
 }
 
 impl<B: Backend> SkinningSub<B> {
@@ -55,8 +75,10 @@ impl<B: Backend> SkinningSub<B> {
 
         Ok(Self {
             layout,
-            skin_offset_map: std::collections::HashMap::default(),
+            palettes: FnvHashMap::default(),
             staging: Vec::new(),
+            frame: 0,
+            relayout_frame: 0,
             per_image: Vec::new(),
         })
     }
@@ -67,8 +89,12 @@ impl<B: Backend> SkinningSub<B> {
         self.layout.raw()
     }
 
-    /// Allocates and writes the skinning information to GPU memory
+    /// Writes the palettes changed since the last commit of this image to GPU memory,
+    /// allocating if needed.
     pub fn commit(&mut self, factory: &Factory<B>, index: usize) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("commit");
+
         let this_image = {
             while self.per_image.len() <= index {
                 self.per_image
@@ -76,9 +102,56 @@ impl<B: Backend> SkinningSub<B> {
             }
             &mut self.per_image[index]
         };
-        this_image.commit(factory, util::slice_as_bytes(&self.staging));
-        self.staging.clear();
-        self.skin_offset_map.clear();
+
+        let dirty = match this_image.committed {
+            Some(committed) if committed >= self.relayout_frame => {
+                self.palettes
+                    .values()
+                    .filter(|palette| palette.modified > committed)
+                    .map(|palette| palette.offset as usize..(palette.offset + palette.len) as usize)
+                    .fold(None, |dirty: Option<Range<usize>>, range| {
+                        Some(dirty.map_or(range.clone(), |dirty| {
+                            dirty.start.min(range.start)..dirty.end.max(range.end)
+                        }))
+                    })
+            }
+            _ => Some(0..self.staging.len()),
+        };
+        this_image.commit(factory, &self.staging, dirty, self.frame);
+
+        self.end_frame();
+    }
+
+    /// Releases the palettes of skins which weren't inserted this frame and packs the staging
+    /// data once most of it is unused.
+    fn end_frame(&mut self) {
+        let used_len = self
+            .palettes
+            .values()
+            .filter(|palette| palette.used)
+            .map(|palette| palette.len as usize)
+            .sum::<usize>();
+        self.frame += 1;
+
+        if used_len * 2 < self.staging.len() {
+            self.palettes.retain(|_, palette| palette.used);
+            let mut palettes = self.palettes.values_mut().collect::<Vec<_>>();
+            palettes.sort_by_key(|palette| palette.offset);
+
+            let mut packed = Vec::with_capacity(used_len);
+            for palette in palettes {
+                let start = palette.offset as usize;
+                let offset = packed.len() as u32;
+                packed.extend_from_slice(&self.staging[start..start + palette.len as usize]);
+                palette.offset = offset;
+            }
+            self.staging = packed;
+            self.relayout_frame = self.frame;
+        }
+
+        for palette in self.palettes.values_mut() {
+            palette.used = false;
+        }
     }
 
     /// Insert a new `JointTransforms` instance for submission. Returns an index.
@@ -86,17 +159,43 @@ impl<B: Backend> SkinningSub<B> {
         #[cfg(feature = "profiler")]
         profile_scope!("insert");
 
+        let frame = self.frame;
         let staging = &mut self.staging;
-        *self.skin_offset_map.entry(joints.skin).or_insert_with(|| {
-            let len = staging.len();
-            staging.extend(
-                joints
-                    .matrices
-                    .iter()
-                    .map(|m| -> [[f32; 4]; 4] { (*m).into() }),
-            );
-            len as u32
-        })
+        let matrices = joints
+            .matrices
+            .iter()
+            .map(|m| -> [[f32; 4]; 4] { (*m).into() });
+
+        match self.palettes.get_mut(&joints.skin) {
+            Some(palette) if palette.len as usize == joints.matrices.len() => {
+                if !palette.used {
+                    palette.used = true;
+                    let start = palette.offset as usize;
+                    let end = start + palette.len as usize;
+                    for (staged, matrix) in staging[start..end].iter_mut().zip(matrices) {
+                        if *staged != matrix {
+                            *staged = matrix;
+                            palette.modified = frame;
+                        }
+                    }
+                }
+                palette.offset
+            }
+            _ => {
+                let offset = staging.len() as u32;
+                staging.extend(matrices);
+                self.palettes.insert(
+                    joints.skin,
+                    Palette {
+                        offset,
+                        len: joints.matrices.len() as u32,
+                        modified: frame,
+                        used: true,
+                    },
+                );
+                offset
+            }
+        }
     }
 
     /// Bind the skinned skeletal information.
@@ -117,14 +216,24 @@ impl<B: Backend> PerImageSkinningSub<B> {
         Self {
             buffer: None,
             set: factory.create_descriptor_set(layout.clone()).unwrap(),
+            committed: None,
         }
     }
 
-    fn commit(&mut self, factory: &Factory<B>, data: &[u8]) {
-        if data.is_empty() {
+    /// Writes the `dirty` range of the staged matrices, or all of them if the buffer was
+    /// reallocated.
+    fn commit(
+        &mut self,
+        factory: &Factory<B>,
+        staging: &[[[f32; 4]; 4]],
+        dirty: Option<Range<usize>>,
+        frame: u64,
+    ) {
+        if staging.is_empty() {
             return;
         }
 
+        let data = util::slice_as_bytes(staging);
         let allocated = util::ensure_buffer(
             factory,
             &mut self.buffer,
@@ -133,6 +242,15 @@ impl<B: Backend> PerImageSkinningSub<B> {
             data.len() as u64,
         )
         .unwrap();
+        self.committed = Some(frame);
+
+        let dirty = if allocated {
+            0..staging.len()
+        } else if let Some(dirty) = dirty.filter(|dirty| !dirty.is_empty()) {
+            dirty
+        } else {
+            return;
+        };
 
         if let Some(buffer) = self.buffer.as_mut() {
             if allocated {
@@ -145,14 +263,13 @@ impl<B: Backend> PerImageSkinningSub<B> {
                 }
             }
 
-            let mut mapped = buffer.map(factory.device(), 0..data.len() as u64).unwrap();
-            let mut writer = unsafe {
-                mapped
-                    .write(factory.device(), 0..data.len() as u64)
-                    .unwrap()
-            };
+            let matrix_size = std::mem::size_of::<[[f32; 4]; 4]>();
+            let bytes = dirty.start * matrix_size..dirty.end * matrix_size;
+            let range = bytes.start as u64..bytes.end as u64;
+            let mut mapped = buffer.map(factory.device(), range.clone()).unwrap();
+            let mut writer = unsafe { mapped.write(factory.device(), range).unwrap() };
             let dst_slice = unsafe { writer.slice() };
-            dst_slice.copy_from_slice(data);
+            dst_slice.copy_from_slice(&data[bytes]);
         }
     }
 
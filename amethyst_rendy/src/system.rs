@@ -1,7 +1,10 @@
 //! Renderer system
 
 use amethyst_assets::{AssetStorage, DefaultLoader, Loader, ProcessingQueue, ProcessingState};
-use amethyst_core::ecs::{ParallelRunnable, Resources, System, SystemBuilder, World};
+use amethyst_core::{
+    ecs::{ParallelRunnable, Resources, System, SystemBuilder, World},
+    Lifecycle, Stopwatch,
+};
 use derivative::Derivative;
 use palette::{LinSrgba, Srgba};
 use rendy::{
@@ -14,6 +17,7 @@ use rendy::{
 use thread_profiler::profile_scope;
 
 use crate::{
+    graph_info::RenderTimings,
     mtl::Material,
     types::{Backend, Mesh, MeshData, Texture, TextureData},
 };
@@ -109,6 +113,9 @@ where
     B: Backend,
     G: GraphCreator<B>,
 {
+    let mut stopwatch = Stopwatch::new();
+    stopwatch.start();
+
     let mut factory = resources.get_mut::<Factory<B>>().unwrap();
     factory.maintain(&mut state.families);
     let aux = make_graph_aux_data(world, resources);
@@ -117,6 +124,11 @@ where
         .as_mut()
         .unwrap()
         .run(&mut factory, &mut state.families, &aux);
+
+    stopwatch.stop();
+    if let Some(mut timings) = resources.get_mut::<RenderTimings>() {
+        timings.frame = stopwatch.elapsed();
+    }
 }
 
 /// Main render function to be executed as thread local system.
@@ -134,6 +146,49 @@ where
     run_graph(&mut state, world, resources);
 }
 
+/// Releases the render graph while the application is suspended, executed in the platform stage
+/// of the dispatcher.
+///
+/// Android destroys the window surface of a suspended application, so the graph and the swapchain
+/// presenting to it are disposed. [`render`] builds them again on resume.
+pub fn release_suspended<B, G>(world: &mut World, resources: &mut Resources)
+where
+    B: Backend,
+    G: 'static + GraphCreator<B>,
+{
+    let suspended = resources
+        .get::<Lifecycle>()
+        .map_or(false, |lifecycle| lifecycle.is_suspended());
+    if !suspended {
+        return;
+    }
+
+    let mut state = resources.get_mut::<RenderState<B, G>>().unwrap();
+    if let Some(graph) = state.graph.take() {
+        log::debug!("Dispose graph of suspended application");
+        let mut factory = resources.get_mut::<Factory<B>>().unwrap();
+        let aux = make_graph_aux_data(world, resources);
+        graph.dispose(&mut *factory, &aux);
+    }
+}
+
+/// Processing system registered for the `Mesh` and `Texture` asset types, which doesn't process
+/// anything.
+///
+/// The processing needs the `Factory` of the backend the game renders with, which is only known
+/// once [`RenderingBundle`] picked it, so the bundle adds [`MeshProcessorSystem`] and
+/// [`TextureProcessorSystem`] for that backend itself.
+///
+/// [`RenderingBundle`]: crate::RenderingBundle
+#[derive(Debug, Default)]
+pub struct DeferredProcessorSystem;
+
+impl System for DeferredProcessorSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(SystemBuilder::new("DeferredProcessorSystem").build(|_, _, _, _| {}))
+    }
+}
+
 /// Asset processing system for `Mesh` asset type.
 #[derive(Debug, Derivative)]
 #[derivative(Default(bound = ""))]
@@ -235,7 +290,7 @@ impl<B: Backend> System for TextureProcessorSystem<B> {
 }
 
 pub(crate) fn create_default_mat<B: Backend>(resources: &Resources) -> Material {
-    use crate::mtl::TextureOffset;
+    use crate::mtl::{TextureOffset, UvAnimation};
 
     let loader = resources.get::<DefaultLoader>().unwrap();
     let albedo = load_from_srgba(Srgba::new(0.5, 0.5, 0.5, 1.0));
@@ -244,6 +299,7 @@ pub(crate) fn create_default_mat<B: Backend>(resources: &Resources) -> Material
     let metallic_roughness = load_from_linear_rgba(LinSrgba::new(0.0, 0.5, 0.0, 0.0));
     let ambient_occlusion = load_from_linear_rgba(LinSrgba::new(1.0, 1.0, 1.0, 1.0));
     let cavity = load_from_linear_rgba(LinSrgba::new(1.0, 1.0, 1.0, 1.0));
+    let lightmap = load_from_linear_rgba(LinSrgba::new(0.0, 0.0, 0.0, 1.0));
 
     let tex_storage = resources.get::<ProcessingQueue<TextureData>>().unwrap();
 
@@ -253,6 +309,7 @@ pub(crate) fn create_default_mat<B: Backend>(resources: &Resources) -> Material
     let metallic_roughness = loader.load_from_data(metallic_roughness.into(), (), &tex_storage);
     let ambient_occlusion = loader.load_from_data(ambient_occlusion.into(), (), &tex_storage);
     let cavity = loader.load_from_data(cavity.into(), (), &tex_storage);
+    let lightmap = loader.load_from_data(lightmap.into(), (), &tex_storage);
 
     Material {
         alpha_cutoff: 0.01,
@@ -263,5 +320,8 @@ pub(crate) fn create_default_mat<B: Backend>(resources: &Resources) -> Material
         ambient_occlusion,
         cavity,
         uv_offset: TextureOffset::default(),
+        uv_animation: UvAnimation::default(),
+        lightmap,
+        lightmap_offset: TextureOffset::default(),
     }
 }
@@ -12,10 +12,12 @@ use amethyst_assets::{
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 
-use crate::system::{MeshProcessorSystem, TextureProcessorSystem};
+use crate::{backend::BackendKind, system::DeferredProcessorSystem};
 
 /// Extension of the rendy Backend trait.
 pub trait Backend: rendy::hal::Backend {
+    /// Kind of the backend
+    const KIND: BackendKind;
     /// Unwrap a Backend to a rendy `Mesh`
     fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>>;
     /// Unwrap a Backend to a rendy `Texture`
@@ -36,6 +38,7 @@ pub type DefaultBackend = rendy::metal::Backend;
 #[cfg(any(
     all(
         not(target_os = "macos"),
+        not(target_arch = "wasm32"),
         not(any(feature = "empty", feature = "metal"))
     ),
     all(feature = "vulkan", not(any(feature = "metal", feature = "empty")))
@@ -43,6 +46,10 @@ pub type DefaultBackend = rendy::metal::Backend;
 #[doc = "Default backend"]
 pub type DefaultBackend = rendy::vulkan::Backend;
 
+#[cfg(all(target_arch = "wasm32", not(feature = "empty")))]
+#[doc = "Default backend"]
+pub type DefaultBackend = rendy::gl::Backend;
+
 #[cfg(feature = "empty")]
 #[doc = "Default backend"]
 pub type DefaultBackend = rendy::empty::Backend;
@@ -54,9 +61,22 @@ pub enum Mesh {
     #[cfg(target_os = "macos")]
     #[doc = "Mesh Variant"]
     Metal(rendy::mesh::Mesh<rendy::metal::Backend>),
-    #[cfg(all(not(target_os = "macos"), not(feature = "empty")))]
+    #[cfg(all(
+        not(target_os = "macos"),
+        not(target_arch = "wasm32"),
+        not(feature = "empty")
+    ))]
     #[doc = "Mesh Variant"]
     Vulkan(rendy::mesh::Mesh<rendy::vulkan::Backend>),
+    #[cfg(all(target_os = "windows", not(feature = "empty")))]
+    #[doc = "Mesh Variant"]
+    Dx12(rendy::mesh::Mesh<rendy::dx12::Backend>),
+    #[cfg(all(
+        any(target_arch = "wasm32", target_os = "linux", target_os = "windows"),
+        not(feature = "empty")
+    ))]
+    #[doc = "Mesh Variant"]
+    Gl(rendy::mesh::Mesh<rendy::gl::Backend>),
     #[cfg(feature = "empty")]
     #[doc = "Mesh Variant"]
     Empty(rendy::mesh::Mesh<rendy::empty::Backend>),
@@ -119,9 +139,22 @@ pub enum Texture {
     #[cfg(target_os = "macos")]
     #[doc = "Texture Variant"]
     Metal(rendy::texture::Texture<rendy::metal::Backend>),
-    #[cfg(all(not(target_os = "macos"), not(feature = "empty")))]
+    #[cfg(all(
+        not(target_os = "macos"),
+        not(target_arch = "wasm32"),
+        not(feature = "empty")
+    ))]
     #[doc = "Texture Variant"]
     Vulkan(rendy::texture::Texture<rendy::vulkan::Backend>),
+    #[cfg(all(target_os = "windows", not(feature = "empty")))]
+    #[doc = "Texture Variant"]
+    Dx12(rendy::texture::Texture<rendy::dx12::Backend>),
+    #[cfg(all(
+        any(target_arch = "wasm32", target_os = "linux", target_os = "windows"),
+        not(feature = "empty")
+    ))]
+    #[doc = "Texture Variant"]
+    Gl(rendy::texture::Texture<rendy::gl::Backend>),
     #[cfg(feature = "empty")]
     #[doc = "Texture Variant"]
     Empty(rendy::texture::Texture<rendy::empty::Backend>),
@@ -129,6 +162,8 @@ pub enum Texture {
 
 #[cfg(target_os = "macos")]
 impl Backend for rendy::metal::Backend {
+    const KIND: BackendKind = BackendKind::Metal;
+
     #[inline]
     #[allow(irrefutable_let_patterns)]
     fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>> {
@@ -157,8 +192,14 @@ impl Backend for rendy::metal::Backend {
     }
 }
 
-#[cfg(all(not(target_os = "macos"), not(feature = "empty")))]
+#[cfg(all(
+    not(target_os = "macos"),
+    not(target_arch = "wasm32"),
+    not(feature = "empty")
+))]
 impl Backend for rendy::vulkan::Backend {
+    const KIND: BackendKind = BackendKind::Vulkan;
+
     #[inline]
     #[allow(irrefutable_let_patterns)]
     fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>> {
@@ -187,8 +228,77 @@ impl Backend for rendy::vulkan::Backend {
     }
 }
 
+#[cfg(all(target_os = "windows", not(feature = "empty")))]
+impl Backend for rendy::dx12::Backend {
+    const KIND: BackendKind = BackendKind::Dx12;
+
+    #[inline]
+    #[allow(irrefutable_let_patterns)]
+    fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>> {
+        if let Mesh::Dx12(inner) = mesh {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+    #[inline]
+    #[allow(irrefutable_let_patterns)]
+    fn unwrap_texture(texture: &Texture) -> Option<&rendy::texture::Texture<Self>> {
+        if let Texture::Dx12(inner) = texture {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+    #[inline]
+    fn wrap_mesh(mesh: rendy::mesh::Mesh<Self>) -> Mesh {
+        Mesh::Dx12(mesh)
+    }
+    #[inline]
+    fn wrap_texture(texture: rendy::texture::Texture<Self>) -> Texture {
+        Texture::Dx12(texture)
+    }
+}
+
+#[cfg(all(
+    any(target_arch = "wasm32", target_os = "linux", target_os = "windows"),
+    not(feature = "empty")
+))]
+impl Backend for rendy::gl::Backend {
+    const KIND: BackendKind = BackendKind::Gl;
+
+    #[inline]
+    #[allow(irrefutable_let_patterns)]
+    fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>> {
+        if let Mesh::Gl(inner) = mesh {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+    #[inline]
+    #[allow(irrefutable_let_patterns)]
+    fn unwrap_texture(texture: &Texture) -> Option<&rendy::texture::Texture<Self>> {
+        if let Texture::Gl(inner) = texture {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+    #[inline]
+    fn wrap_mesh(mesh: rendy::mesh::Mesh<Self>) -> Mesh {
+        Mesh::Gl(mesh)
+    }
+    #[inline]
+    fn wrap_texture(texture: rendy::texture::Texture<Self>) -> Texture {
+        Texture::Gl(texture)
+    }
+}
+
 #[cfg(feature = "empty")]
 impl Backend for rendy::empty::Backend {
+    const KIND: BackendKind = BackendKind::Empty;
+
     #[inline]
     #[allow(irrefutable_let_patterns)]
     fn unwrap_mesh(mesh: &Mesh) -> Option<&rendy::mesh::Mesh<Self>> {
@@ -217,8 +327,9 @@ impl Backend for rendy::empty::Backend {
     }
 }
 
-amethyst_assets::register_asset_type!(MeshData => Mesh; MeshProcessorSystem<DefaultBackend>);
-amethyst_assets::register_asset_type!(TextureData => Texture; TextureProcessorSystem<DefaultBackend>);
+// Meshes and textures are processed by the systems `RenderingBundle` adds for its backend.
+amethyst_assets::register_asset_type!(MeshData => Mesh; DeferredProcessorSystem);
+amethyst_assets::register_asset_type!(TextureData => Texture; DeferredProcessorSystem);
 
 impl Asset for Mesh {
     fn name() -> &'static str {
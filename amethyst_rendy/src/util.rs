@@ -221,6 +221,23 @@ pub fn set_layout_bindings(
         .collect()
 }
 
+/// Helper function to create the `Multisampling` state of a pipeline rendering into a target
+/// with given number of samples per pixel. Returns `None` when the target is not multisampled.
+#[must_use]
+pub fn multisampling(samples: u8) -> Option<pso::Multisampling> {
+    if samples > 1 {
+        Some(pso::Multisampling {
+            rasterization_samples: samples,
+            sample_shading: None,
+            sample_mask: !0,
+            alpha_coverage: false,
+            alpha_to_one: false,
+        })
+    } else {
+        None
+    }
+}
+
 /// Helper forward lookup struct using `FnvHashMap`
 #[derive(Debug, Default)]
 pub struct LookupBuilder<I: Hash + Eq> {
@@ -13,7 +13,9 @@ use serde::{Deserialize, Serialize};
 use thread_profiler::profile_scope;
 
 use crate::{
-    camera::{ActiveCamera, Camera},
+    camera::{ActiveCamera, Camera, Viewport},
+    occlusion::{Occluder, OcclusionBuffer, OcclusionCulling},
+    render_target::RenderTarget,
     transparent::Transparent,
 };
 
@@ -25,6 +27,21 @@ pub struct Visibility {
     pub visible_unordered: IndexSet<Entity>,
     /// Visible entities that need to be drawn in the given order
     pub visible_ordered: Vec<Entity>,
+    /// Visible entities that need to be drawn in the given order by each split screen camera,
+    /// indexed like [`Viewport::cameras`]
+    pub viewport_ordered: Vec<Vec<Entity>>,
+}
+
+impl Visibility {
+    /// Visible entities that need to be drawn in the given order by the render pass instance of
+    /// given viewport index, see [`Viewport::cameras`]. Without an index or a list for it, these
+    /// are all of [`visible_ordered`](Self::visible_ordered).
+    #[must_use]
+    pub fn ordered(&self, viewport: Option<usize>) -> &[Entity] {
+        viewport
+            .and_then(|index| self.viewport_ordered.get(index))
+            .unwrap_or(&self.visible_ordered)
+    }
 }
 
 /// Defines a object's bounding sphere used by frustum culling.
@@ -73,12 +90,42 @@ struct Internals {
 /// Determine what entities are visible to the camera, and which are not. Will also sort transparent
 /// entities back to front based on distance from camera.
 ///
+/// With split screen cameras, see [`Viewport`], or cameras bound to other targets, see
+/// [`RenderTarget`], entities visible to any of them are visible and sorted by their distance to
+/// the nearest camera seeing them. Each split screen camera also gets its own back to front order
+/// in [`Visibility::viewport_ordered`].
+///
+/// Entities hidden behind [`Occluder`]s can be culled as well, see
+/// [`with_occlusion_culling`](Self::with_occlusion_culling).
+///
 /// Note that this should run after `Transform` has been updated for the current frame, and
 /// before rendering occurs.
 #[derive(Default, Debug)]
 pub struct VisibilitySortingSystem {
     centroids: Vec<Internals>,
     transparent: Vec<Internals>,
+    viewport_transparent: Vec<Vec<(Entity, f32)>>,
+    views: Vec<View>,
+    occlusion: Option<OcclusionCulling>,
+    occlusion_buffers: Vec<OcclusionBuffer>,
+}
+
+#[derive(Debug)]
+struct View {
+    position: Point3<f32>,
+    frustum: Frustum,
+    projection: Matrix4<f32>,
+    view: Matrix4<f32>,
+}
+
+impl VisibilitySortingSystem {
+    /// Also cull the entities hidden behind [`Occluder`]s, see the
+    /// [`occlusion`](crate::occlusion) module.
+    #[must_use]
+    pub fn with_occlusion_culling(mut self, settings: OcclusionCulling) -> Self {
+        self.occlusion = Some(settings);
+        self
+    }
 }
 
 impl System for VisibilitySortingSystem {
@@ -89,83 +136,155 @@ impl System for VisibilitySortingSystem {
                 .write_resource::<Visibility>()
                 .with_query(<(&Camera, &Transform)>::query())
                 .with_query(<(Entity, &Camera, &Transform)>::query())
+                .with_query(
+                    <(&Camera, &Transform, &Viewport)>::query()
+                        .filter(!component::<RenderTarget>()),
+                )
+                .with_query(
+                    <(&Camera, &Transform)>::query().filter(component::<RenderTarget>()),
+                )
                 .with_query(
                     <(
                         Entity,
                         &Transform,
                         Option<&Transparent>,
                         Option<&BoundingSphere>,
+                        Option<&Occluder>,
                     )>::query()
                     .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
                 )
+                .with_query(
+                    <(&Transform, &Occluder)>::query()
+                        .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
+                )
                 .build(
                     move |commands,
                           world,
                           (active_camera, visibility),
-                          (camera_query1, camera_query2, entity_query)| {
+                          (
+                        camera_query1,
+                        camera_query2,
+                        viewport_query,
+                        bound_query,
+                        entity_query,
+                        occluder_query,
+                    )| {
                         #[cfg(feature = "profiler")]
                         profile_scope!("visibility_sorting_system");
 
                         visibility.visible_unordered.clear();
                         visibility.visible_ordered.clear();
+                        for ordered in &mut visibility.viewport_ordered {
+                            ordered.clear();
+                        }
                         self.transparent.clear();
                         self.centroids.clear();
+                        self.views.clear();
 
                         let origin = Point3::origin();
-
-                        let (camera, camera_transform) = match active_camera.entity.map_or_else(
-                            || camera_query1.iter(world).next(),
-                            |e| {
-                                camera_query2
-                                    .iter(world)
-                                    .find(|(camera_entity, _, _)| **camera_entity == e)
-                                    .map(|(_entity, camera, camera_transform)| {
-                                        (camera, camera_transform)
-                                    })
-                            },
-                        ) {
-                            Some(r) => r,
-                            None => return,
+                        let view = |camera: &Camera, camera_transform: &Transform| {
+                            let projection = convert::<_, Matrix4<f32>>(camera.matrix);
+                            let view = camera_transform.global_matrix().try_inverse().unwrap();
+                            View {
+                                position: camera_transform.global_matrix().transform_point(&origin),
+                                frustum: Frustum::new(projection * view),
+                                projection,
+                                view,
+                            }
                         };
 
-                        let camera_centroid =
-                            camera_transform.global_matrix().transform_point(&origin);
-                        let frustum = Frustum::new(
-                            convert::<_, Matrix4<f32>>(camera.matrix)
-                                * camera_transform.global_matrix().try_inverse().unwrap(),
+                        // split screen views first, in the order of `Viewport::cameras`
+                        let mut viewport_views: Vec<_> = viewport_query.iter(world).collect();
+                        viewport_views.sort_by(|(_, _, a), (_, _, b)| a.screen_order(b));
+                        self.views.extend(
+                            viewport_views
+                                .into_iter()
+                                .map(|(camera, transform, _)| view(camera, transform)),
                         );
-
-                        self.centroids.extend(
-                            entity_query
+                        let viewport_count = self.views.len();
+                        let split_screen = viewport_count != 0;
+                        self.views.extend(
+                            bound_query
                                 .iter(world)
-                                .map(|(entity, transform, transparent, sphere)| {
-                                    let pos = sphere.map_or(origin, |s| s.center);
-                                    let matrix = transform.global_matrix();
-                                    (
-                                        *entity,
-                                        transparent.is_some(),
-                                        matrix.transform_point(&pos),
-                                        sphere.map_or(1.0, |s| s.radius)
-                                            * matrix[(0, 0)]
-                                                .max(matrix[(1, 1)])
-                                                .max(matrix[(2, 2)]),
-                                    )
-                                })
-                                .filter(|(_, _, centroid, radius)| {
-                                    frustum.check_sphere(centroid, *radius)
-                                })
-                                .map(|(entity, transparent, centroid, _)| {
-                                    Internals {
-                                        entity,
-                                        transparent,
-                                        centroid,
-                                        camera_distance: distance_squared(
-                                            &centroid,
-                                            &camera_centroid,
-                                        ),
-                                    }
-                                }),
+                                .map(|(camera, transform)| view(camera, transform)),
                         );
+                        if !split_screen {
+                            let camera = active_camera.entity.map_or_else(
+                                || camera_query1.iter(world).next(),
+                                |e| {
+                                    camera_query2
+                                        .iter(world)
+                                        .find(|(camera_entity, _, _)| **camera_entity == e)
+                                        .map(|(_entity, camera, camera_transform)| {
+                                            (camera, camera_transform)
+                                        })
+                                },
+                            );
+                            self.views
+                                .extend(camera.map(|(camera, transform)| view(camera, transform)));
+                        }
+                        if self.views.is_empty() {
+                            return;
+                        }
+                        let views = &self.views;
+
+                        self.occlusion_buffers.clear();
+                        if let Some(settings) = &self.occlusion {
+                            #[cfg(feature = "profiler")]
+                            profile_scope!("occlusion_rasterization");
+
+                            self.occlusion_buffers
+                                .resize_with(views.len(), Default::default);
+                            for (buffer, view) in self.occlusion_buffers.iter_mut().zip(views) {
+                                buffer.reset(settings, view.projection, view.view);
+                                for (transform, occluder) in occluder_query.iter(world) {
+                                    buffer.rasterize(transform.global_matrix(), occluder);
+                                }
+                            }
+                        }
+                        let occlusion_buffers = &self.occlusion_buffers;
+
+                        self.viewport_transparent.resize_with(viewport_count, Vec::new);
+                        for transparent in &mut self.viewport_transparent {
+                            transparent.clear();
+                        }
+
+                        for (entity, transform, transparent, sphere, occluder) in
+                            entity_query.iter(world)
+                        {
+                            let pos = sphere.map_or(origin, |s| s.center);
+                            let matrix = transform.global_matrix();
+                            let centroid = matrix.transform_point(&pos);
+                            let radius = sphere.map_or(1.0, |s| s.radius)
+                                * matrix[(0, 0)].max(matrix[(1, 1)]).max(matrix[(2, 2)]);
+
+                            let mut camera_distance: Option<f32> = None;
+                            for (index, view) in views.iter().enumerate() {
+                                let visible = view.frustum.check_sphere(&centroid, radius)
+                                    && (occluder.is_some()
+                                        || occlusion_buffers.get(index).map_or(true, |buffer| {
+                                            !buffer.is_occluded(&centroid, radius)
+                                        }));
+                                if !visible {
+                                    continue;
+                                }
+                                let distance = distance_squared(&centroid, &view.position);
+                                if transparent.is_some() && index < viewport_count {
+                                    self.viewport_transparent[index].push((*entity, distance));
+                                }
+                                camera_distance =
+                                    Some(camera_distance.map_or(distance, |d| d.min(distance)));
+                            }
+
+                            if let Some(camera_distance) = camera_distance {
+                                self.centroids.push(Internals {
+                                    entity: *entity,
+                                    transparent: transparent.is_some(),
+                                    centroid,
+                                    camera_distance,
+                                });
+                            }
+                        }
 
                         self.transparent
                             .extend(self.centroids.iter().filter(|c| c.transparent).cloned());
@@ -186,6 +305,19 @@ impl System for VisibilitySortingSystem {
                         visibility
                             .visible_ordered
                             .extend(self.transparent.iter().map(|c| c.entity));
+
+                        visibility.viewport_ordered.resize_with(viewport_count, Vec::new);
+                        for (ordered, transparent) in visibility
+                            .viewport_ordered
+                            .iter_mut()
+                            .zip(&mut self.viewport_transparent)
+                        {
+                            transparent.sort_by(|(_, a), (_, b)| {
+                                b.partial_cmp(a).unwrap_or(Ordering::Equal)
+                            });
+                            ordered.clear();
+                            ordered.extend(transparent.iter().map(|(entity, _)| *entity));
+                        }
                     },
                 ),
         )
@@ -234,3 +366,88 @@ impl Frustum {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use amethyst_assets::{Handle, LoadHandle};
+    use amethyst_core::{
+        dispatcher::DispatcherBuilder,
+        ecs::{Resources, World},
+        math::Vector3,
+    };
+
+    use super::*;
+    use crate::bundle::Target;
+
+    fn transform(x: f32, y: f32, z: f32) -> Transform {
+        let mut transform = Transform::from(Vector3::new(x, y, z));
+        transform.copy_local_to_global();
+        transform
+    }
+
+    #[test]
+    fn split_screen_cameras_sort_transparent_entities_separately() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(ActiveCamera::default());
+        resources.insert(Visibility::default());
+
+        let camera = || Camera::perspective(1.0, 2.5, 0.1);
+        // the right half is pushed first, viewports are indexed from left to right
+        world.push((
+            camera(),
+            transform(10.0, 0.0, 10.0),
+            Viewport::new(0.5, 0.0, 0.5, 1.0),
+        ));
+        world.push((
+            camera(),
+            transform(-10.0, 0.0, 10.0),
+            Viewport::new(0.0, 0.0, 0.5, 1.0),
+        ));
+        let left = world.push((transform(-10.0, 0.0, 0.0), Transparent));
+        let middle = world.push((transform(0.0, 0.0, -4.0), Transparent));
+
+        let mut builder = DispatcherBuilder::default();
+        builder.add_system(VisibilitySortingSystem::default());
+        let mut dispatcher = builder.build(&mut world, &mut resources).unwrap();
+        dispatcher.execute(&mut world, &mut resources);
+
+        let visibility = resources.get::<Visibility>().unwrap();
+        assert_eq!(visibility.ordered(Some(0)), &[middle, left]);
+        assert_eq!(visibility.ordered(Some(1)), &[left, middle]);
+        assert_eq!(visibility.ordered(None).len(), 2);
+    }
+
+    #[test]
+    fn render_target_cameras_see_entities() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Visibility::default());
+
+        let (ref_sender, _ref_receiver) = crossbeam_channel::unbounded();
+        let camera = || Camera::perspective(1.0, 1.0, 0.1);
+        let main = world.push((camera(), transform(0.0, 0.0, 10.0)));
+        resources.insert(ActiveCamera { entity: Some(main) });
+        world.push((
+            camera(),
+            transform(100.0, 0.0, 10.0),
+            RenderTarget::new(
+                Target::Custom("mirror"),
+                64,
+                64,
+                Handle::new(ref_sender, LoadHandle(1)),
+            ),
+        ));
+        let seen_by_main = world.push((transform(0.0, 0.0, 0.0),));
+        let seen_by_target = world.push((transform(100.0, 0.0, 0.0),));
+
+        let mut builder = DispatcherBuilder::default();
+        builder.add_system(VisibilitySortingSystem::default());
+        let mut dispatcher = builder.build(&mut world, &mut resources).unwrap();
+        dispatcher.execute(&mut world, &mut resources);
+
+        let visibility = resources.get::<Visibility>().unwrap();
+        assert!(visibility.visible_unordered.contains(&seen_by_main));
+        assert!(visibility.visible_unordered.contains(&seen_by_target));
+    }
+}
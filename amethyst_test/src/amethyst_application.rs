@@ -1,23 +1,40 @@
-use std::{any::Any, marker::PhantomData, panic, path::PathBuf, sync::Mutex};
+use std::{
+    any::{type_name, Any},
+    fmt::Debug,
+    marker::PhantomData,
+    panic,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use amethyst::{
     self,
-    core::{transform::TransformBundle, EventReader, RunNowDesc, SystemBundle},
+    core::{
+        shrev::EventChannel, transform::TransformBundle, EventReader, RunNowDesc, SystemBundle,
+    },
     ecs::*,
     error::Error,
     input::{BindingTypes, InputBundle},
     prelude::*,
     shred::Resource,
-    ui::UiBundle,
+    ui::{UiBundle, UiTransform},
     utils::application_root_dir,
     window::ScreenDimensions,
+    winit::{
+        dpi::PhysicalPosition,
+        event::{
+            DeviceId, ElementState, Event, KeyboardInput, ModifiersState, MouseButton,
+            VirtualKeyCode, WindowEvent,
+        },
+        window::WindowId,
+    },
     StateEventReader,
 };
 use derivative::Derivative;
 use lazy_static::lazy_static;
 
 use crate::{
-    CustomDispatcherStateBuilder, FunctionState, GameUpdate, SequencerState,
+    CustomDispatcherStateBuilder, EffectReturn, FunctionState, GameUpdate, SequencerState,
     SystemDescInjectionBundle, SystemInjectionBundle, ThreadLocalInjectionBundle,
 };
 
@@ -580,6 +597,213 @@ where
     {
         self.with_fn(assertion_fn)
     }
+
+    /// Asserts that the resource of type `Res` equals `expected`.
+    ///
+    /// # Parameters
+    ///
+    /// * `expected`: Expected value of the resource.
+    pub fn assert_resource_eq<Res>(self, expected: Res) -> Self
+    where
+        Res: Debug + PartialEq + Resource,
+    {
+        self.with_assertion(move |world| {
+            assert_eq!(expected, *world.read_resource::<Res>());
+        })
+    }
+
+    /// Asserts that the entity in the `EffectReturn<Entity>` resource has a component of type `C`
+    /// satisfying `predicate`.
+    ///
+    /// # Parameters
+    ///
+    /// * `predicate`: Function returning whether the component has the expected value.
+    pub fn assert_component<C, F>(self, predicate: F) -> Self
+    where
+        C: Component + Debug,
+        F: FnOnce(&C) -> bool + Send + Sync + 'static,
+    {
+        self.with_assertion(move |world| {
+            let entity = world.read_resource::<EffectReturn<Entity>>().0;
+            let storage = world.read_storage::<C>();
+            let component = storage.get(entity).unwrap_or_else(|| {
+                panic!(
+                    "Expected entity {:?} to have a `{}` component.",
+                    entity,
+                    type_name::<C>()
+                )
+            });
+            assert!(
+                predicate(component),
+                "`{}` component of entity {:?} does not match: {:?}",
+                type_name::<C>(),
+                entity,
+                component
+            );
+        })
+    }
+
+    /// Asserts that an event of type `Evt` matching `matcher` was sent since the application
+    /// started, or since the previous `expect_event` for that type.
+    ///
+    /// The event channel is created when it doesn't exist yet, before the bundles are added, so
+    /// bundles must not replace it.
+    ///
+    /// # Parameters
+    ///
+    /// * `matcher`: Function returning whether an event is the expected one.
+    pub fn expect_event<Evt, F>(self, matcher: F) -> Self
+    where
+        Evt: Debug + Send + Sync + 'static,
+        F: Fn(&Evt) -> bool + Send + Sync + 'static,
+    {
+        let reader_id = Arc::new(Mutex::new(None));
+        let setup_reader_id = Arc::clone(&reader_id);
+        self.with_setup(move |world| {
+            let reader_id = world
+                .entry::<EventChannel<Evt>>()
+                .or_insert_with(EventChannel::new)
+                .register_reader();
+            *setup_reader_id.lock().unwrap() = Some(reader_id);
+        })
+        .with_assertion(move |world| {
+            let mut reader_id = reader_id
+                .lock()
+                .unwrap()
+                .take()
+                .expect("Expected the event reader to be registered during setup.");
+            let channel = world.read_resource::<EventChannel<Evt>>();
+            let events = channel.read(&mut reader_id).collect::<Vec<_>>();
+            assert!(
+                events.iter().any(|event| matcher(event)),
+                "Expected a matching `{}` event, got: {:?}",
+                type_name::<Evt>(),
+                events
+            );
+        })
+    }
+
+    /// Clicks the widget whose `UiTransform` has the id `id`, over two frames.
+    ///
+    /// The mouse is moved to the center of the widget and the left button pressed, then released
+    /// on the next frame, so the `InputHandler` and the UI systems handle it like a real click.
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: Id of the widget's `UiTransform`.
+    pub fn click_widget<S>(self, id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let id = id.into();
+        self.with_effect(move |world| {
+            let (x, y) = widget_position(world, &id);
+            send_window_events(
+                world,
+                vec![cursor_moved(x, y), mouse_input(ElementState::Pressed)],
+            );
+        })
+        .with_effect(|world| send_window_events(world, vec![mouse_input(ElementState::Released)]))
+    }
+
+    /// Types `text` into the focused text field, one character event per `char`.
+    ///
+    /// # Parameters
+    ///
+    /// * `text`: Text to type.
+    pub fn type_text<S>(self, text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let text = text.into();
+        self.with_effect(move |world| {
+            send_window_events(world, text.chars().map(WindowEvent::ReceivedCharacter).collect());
+        })
+    }
+
+    /// Moves the focus to the next `Selectable` widget by pressing `Tab`.
+    pub fn focus_next(self) -> Self {
+        self.with_effect(|world| send_window_events(world, vec![tab_input()]))
+    }
+
+    /// Moves the focus to the previous `Selectable` widget by pressing `Shift + Tab`, over two
+    /// frames.
+    pub fn focus_previous(self) -> Self {
+        self.with_effect(|world| {
+            send_window_events(
+                world,
+                vec![
+                    WindowEvent::ModifiersChanged(ModifiersState::SHIFT),
+                    tab_input(),
+                ],
+            );
+        })
+        .with_effect(|world| {
+            send_window_events(
+                world,
+                vec![WindowEvent::ModifiersChanged(ModifiersState::empty())],
+            );
+        })
+    }
+}
+
+/// Returns the window position of the center of the widget with the id `id`.
+fn widget_position(world: &World, id: &str) -> (f64, f64) {
+    let transforms = world.read_storage::<UiTransform>();
+    let transform = (&transforms)
+        .join()
+        .find(|transform| transform.id == id)
+        .unwrap_or_else(|| panic!("Expected a widget with the id `{}`.", id));
+    let screen_height = world.read_resource::<ScreenDimensions>().height();
+    (
+        f64::from(transform.pixel_x),
+        f64::from(screen_height - transform.pixel_y),
+    )
+}
+
+/// Writes the `events` to the window event channel, read by the `InputSystem` and UI systems.
+fn send_window_events(world: &mut World, events: Vec<WindowEvent<'static>>) {
+    world
+        .write_resource::<EventChannel<Event<'static, ()>>>()
+        .iter_write(events.into_iter().map(|event| {
+            Event::WindowEvent {
+                window_id: unsafe { WindowId::dummy() },
+                event,
+            }
+        }));
+}
+
+#[allow(deprecated)] // The modifiers field is still mandatory.
+fn cursor_moved(x: f64, y: f64) -> WindowEvent<'static> {
+    WindowEvent::CursorMoved {
+        device_id: unsafe { DeviceId::dummy() },
+        position: PhysicalPosition::new(x, y),
+        modifiers: ModifiersState::default(),
+    }
+}
+
+#[allow(deprecated)] // The modifiers field is still mandatory.
+fn mouse_input(state: ElementState) -> WindowEvent<'static> {
+    WindowEvent::MouseInput {
+        device_id: unsafe { DeviceId::dummy() },
+        state,
+        button: MouseButton::Left,
+        modifiers: ModifiersState::default(),
+    }
+}
+
+#[allow(deprecated)] // The modifiers field is still mandatory.
+fn tab_input() -> WindowEvent<'static> {
+    WindowEvent::KeyboardInput {
+        device_id: unsafe { DeviceId::dummy() },
+        input: KeyboardInput {
+            scancode: 15,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(VirtualKeyCode::Tab),
+            modifiers: ModifiersState::default(),
+        },
+        is_synthetic: false,
+    }
 }
 
 #[cfg(test)]
@@ -588,12 +812,13 @@ mod test {
 
     use amethyst::{
         assets::{Asset, AssetStorage, DefaultLoader, Handle, Loader, ProcessingState, Processor},
-        core::{bundle::SystemBundle, SystemDesc},
+        core::{bundle::SystemBundle, shrev::EventChannel, SystemDesc},
         derive::SystemDesc,
         ecs::*,
         error::Error,
+        input::StringBindings,
         prelude::*,
-        ui::FontAsset,
+        ui::{Anchor, FontAsset, Interactable, UiEvent, UiEventType, UiTransform},
         window::ScreenDimensions,
     };
 
@@ -815,6 +1040,71 @@ mod test {
             .run()
     }
 
+    #[test]
+    fn assert_resource_eq_succeeds_when_resource_is_equal() -> Result<(), Error> {
+        AmethystApplication::blank()
+            .with_resource(ResourceValue(3))
+            .assert_resource_eq(ResourceValue(3))
+            .run()
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: `(left == right)`")]
+    fn assert_resource_eq_panics_when_resource_differs() {
+        AmethystApplication::blank()
+            .with_resource(ResourceValue(3))
+            .assert_resource_eq(ResourceValue(4))
+            .run()
+            .unwrap();
+    }
+
+    #[test]
+    fn assert_component_checks_effect_return_entity() -> Result<(), Error> {
+        AmethystApplication::blank()
+            .with_system(SystemEffect, "system_effect", &[])
+            .with_effect(|world| {
+                let entity = world.push((ComponentZero(0),));
+                world.insert(EffectReturn(entity));
+            })
+            .assert_component::<ComponentZero, _>(|component| component.0 == 1)
+            .run()
+    }
+
+    #[test]
+    fn expect_event_finds_sent_event() -> Result<(), Error> {
+        AmethystApplication::blank()
+            .with_effect(|world| {
+                world
+                    .write_resource::<EventChannel<ResourceValue>>()
+                    .iter_write(vec![ResourceValue(1), ResourceValue(2)]);
+            })
+            .expect_event(|event: &ResourceValue| event.0 == 2)
+            .run()
+    }
+
+    #[test]
+    fn click_widget_sends_click_event() -> Result<(), Error> {
+        AmethystApplication::ui_base::<StringBindings>()
+            .with_effect(|world| {
+                world.push((
+                    UiTransform::new(
+                        "button".to_string(),
+                        Anchor::Middle,
+                        Anchor::Middle,
+                        0.,
+                        0.,
+                        0.,
+                        100.,
+                        50.,
+                    ),
+                    Interactable,
+                ));
+            })
+            .click_widget("button")
+            .expect_event(|event: &UiEvent| event.event_type == UiEventType::Click)
+            .run()
+    }
+
     #[test]
     fn with_system_invoked_twice_should_not_panic() {
         AmethystApplication::blank()
@@ -1008,6 +1298,8 @@ mod test {
     struct ApplicationResourceNonDefault;
     #[derive(Debug)]
     struct LoadResource;
+    #[derive(Debug, PartialEq)]
+    struct ResourceValue(i32);
 
     // === States === //
     struct LoadingState<'a, 'b, S, E>
@@ -1187,5 +1479,6 @@ mod test {
     }
 
     // === Components === //
+    #[derive(Debug)]
     struct ComponentZero(pub i32);
 }
@@ -213,10 +213,20 @@ pub struct DrawTiles2DDesc<
     E: CoordinateEncoder,
     Z: DrawTiles2DBounds = DrawTiles2DBoundsDefault,
 > {
+    samples: u8,
     #[derivative(Debug = "ignore")]
     _marker: PhantomData<(T, E, Z)>,
 }
 
+impl<T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> DrawTiles2DDesc<T, E, Z> {
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
 impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds>
     RenderGroupDesc<B, GraphAuxData> for DrawTiles2DDesc<T, E, Z>
 {
@@ -245,6 +255,7 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds>
             subpass,
             framebuffer_width,
             framebuffer_height,
+            self.samples,
             vec![env.raw_layout(), textures.raw_layout()],
         )?;
 
@@ -488,6 +499,7 @@ fn build_tiles_pipeline<B: Backend>(
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
+    samples: u8,
     layouts: Vec<&B::DescriptorSetLayout>,
 ) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
     let pipeline_layout = unsafe {
@@ -512,6 +524,7 @@ fn build_tiles_pipeline<B: Backend>(
                 .with_layout(&pipeline_layout)
                 .with_subpass(subpass)
                 .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
                 .with_blend_targets(vec![pso::ColorBlendDesc {
                     mask: pso::ColorMask::ALL,
                     blend: Some(pso::BlendState::PREMULTIPLIED_ALPHA),
@@ -585,7 +598,9 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderPlug
         plan.extend_target(self.target, |ctx| {
             ctx.add(
                 RenderOrder::BeforeTransparent,
-                DrawTiles2DDesc::<T, E, Z>::default().builder(),
+                DrawTiles2DDesc::<T, E, Z>::default()
+                    .with_samples(ctx.samples())
+                    .builder(),
             )?;
             Ok(())
         });
@@ -0,0 +1,55 @@
+#[cfg(feature = "audio")]
+use amethyst_audio::output::OutputWrapper;
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::EventChannel,
+};
+use amethyst_error::Error;
+
+#[cfg(feature = "animation")]
+use crate::system::TimelineAnimationSystem;
+#[cfg(feature = "audio")]
+use crate::system::TimelineAudioSystem;
+use crate::{event::TimelineEvent, system::TimelineSystem};
+
+/// Bundle playing the [`TimelinePlayer`](crate::TimelinePlayer)s.
+///
+/// This will add an `EventChannel<TimelineEvent>` and the `TimelineSystem`. With the `audio`
+/// feature, it also adds the `TimelineAudioSystem`, playing the sounds on the output of the
+/// `AudioBundle`, and with the `animation` feature the `TimelineAnimationSystem`, which needs the
+/// `AnimationBundle::<String, Transform>` to play the animations.
+///
+/// Add it before the `TransformBundle`, so the moved entities are rendered in the same frame.
+#[derive(Default, Debug)]
+pub struct TimelineBundle;
+
+impl SystemBundle for TimelineBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.get_or_insert_with(EventChannel::<TimelineEvent>::new);
+        builder.add_system(TimelineSystem);
+
+        #[cfg(feature = "audio")]
+        {
+            resources.get_or_default::<OutputWrapper>();
+            let reader = resources
+                .get_mut::<EventChannel<TimelineEvent>>()
+                .unwrap()
+                .register_reader();
+            builder.add_system(TimelineAudioSystem::new(reader));
+        }
+        #[cfg(feature = "animation")]
+        {
+            let reader = resources
+                .get_mut::<EventChannel<TimelineEvent>>()
+                .unwrap()
+                .register_reader();
+            builder.add_system(TimelineAnimationSystem::new(reader));
+        }
+        Ok(())
+    }
+}
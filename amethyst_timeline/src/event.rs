@@ -0,0 +1,29 @@
+use amethyst_core::ecs::Entity;
+
+use crate::timeline::CueAction;
+
+/// Event sent by the `TimelineSystem` on the `EventChannel<TimelineEvent>` resource.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimelineEvent {
+    /// The playback of a player reached a cue.
+    Cue {
+        /// Entity of the `TimelinePlayer`.
+        player: Entity,
+        /// Entity bound to the track of the cue, if any.
+        target: Option<Entity>,
+        /// Action of the cue.
+        action: CueAction,
+    },
+    /// The playback of a player was moved by `TimelinePlayer::seek`.
+    Seek {
+        /// Entity of the `TimelinePlayer`.
+        player: Entity,
+        /// Time the playback was moved to.
+        time: f32,
+    },
+    /// A player reached the end of its timeline, or looped back to its start.
+    Finished {
+        /// Entity of the `TimelinePlayer`.
+        player: Entity,
+    },
+}
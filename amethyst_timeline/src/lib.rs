@@ -0,0 +1,57 @@
+//! Timelines sequencing cutscenes from the other subsystems of the engine.
+//!
+//! A [`Timeline`] is an asset made of tracks at absolute times: `Transform` keys moving an entity
+//! like the camera, and cues showing or hiding an entity, playing a sound, starting an animation,
+//! or sending an event to the game. Add the [`TimelineBundle`], load a timeline and add a
+//! [`TimelinePlayer`] binding the tracks to entities:
+//!
+//! ```ignore
+//! let timeline = loader.load("cutscenes/intro.timeline.ron");
+//! world.push((TimelinePlayer::new(timeline)
+//!     .with_binding("camera", camera)
+//!     .with_binding("hero", hero)
+//!     .with_sound("hello", loader.load("audio/hello.ogg")),));
+//! ```
+//!
+//! The player can be paused, resumed and moved to any time with [`TimelinePlayer::seek`]. Each
+//! cue reached is sent as a [`TimelineEvent`], so game systems can react to the `Event` cues and
+//! to the end of the cutscene.
+//!
+//! With the `audio` feature, the `Sound` cues are played on the output of the `AudioBundle`, and
+//! with the `animation` feature, the `Animation` cues start animations from the
+//! `AnimationSet<String, Transform>` of their entity.
+
+#![doc(
+    html_logo_url = "https://amethyst.rs/brand/logo-standard.svg",
+    html_root_url = "https://docs.amethyst.rs/stable"
+)]
+#![deny(
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    rust_2018_compatibility,
+    clippy::all
+)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::new_without_default, clippy::module_name_repetitions)]
+
+#[cfg(feature = "animation")]
+pub use self::system::TimelineAnimationSystem;
+#[cfg(feature = "audio")]
+pub use self::system::TimelineAudioSystem;
+pub use self::{
+    bundle::TimelineBundle,
+    event::TimelineEvent,
+    player::TimelinePlayer,
+    system::TimelineSystem,
+    timeline::{
+        Cue, CueAction, KeyInterpolation, Timeline, TimelineHandle, Track, TrackContent,
+        TransformKey,
+    },
+};
+
+mod bundle;
+mod event;
+mod player;
+mod system;
+mod timeline;
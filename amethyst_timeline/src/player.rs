@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "audio")]
+use amethyst_audio::{AudioTrack, SourceHandle};
+use amethyst_core::ecs::Entity;
+
+use crate::timeline::TimelineHandle;
+
+/// Component playing a [`Timeline`](crate::Timeline), see the [crate documentation](crate).
+///
+/// The tracks of the timeline are bound by name to entities with
+/// [`TimelinePlayer::with_binding`]. The timeline starts playing as soon as it's loaded, unless
+/// the player was paused before.
+///
+/// Seeking doesn't trigger the cues between the previous and the new time, but sets the
+/// visibility of the bound entities as their `Show` and `Hide` cues would have, and stops the
+/// sounds and animations the cues started.
+#[derive(Debug)]
+pub struct TimelinePlayer {
+    timeline: TimelineHandle,
+    bindings: HashMap<String, Entity>,
+    /// Whether the timeline restarts when it ends
+    pub looping: bool,
+    /// Playback speed, `1.0` playing the timeline in real time
+    pub speed: f32,
+    #[cfg(feature = "audio")]
+    sounds: HashMap<String, SourceHandle>,
+    /// Sounds started by the cues, stopped when seeking
+    #[cfg(feature = "audio")]
+    pub(crate) tracks: Vec<AudioTrack>,
+    /// Animations started by the cues with their entity, aborted when seeking
+    #[cfg(feature = "animation")]
+    pub(crate) animations: Vec<(Entity, String)>,
+    playing: bool,
+    finished: bool,
+    pub(crate) time: f32,
+    /// Time from which the cues weren't triggered yet
+    pub(crate) cued: f32,
+    seek: Option<f32>,
+}
+
+impl TimelinePlayer {
+    /// Creates a player playing `timeline` once.
+    #[must_use]
+    pub fn new(timeline: TimelineHandle) -> Self {
+        Self {
+            timeline,
+            bindings: HashMap::new(),
+            looping: false,
+            speed: 1.0,
+            #[cfg(feature = "audio")]
+            sounds: HashMap::new(),
+            #[cfg(feature = "audio")]
+            tracks: Vec::new(),
+            #[cfg(feature = "animation")]
+            animations: Vec::new(),
+            playing: true,
+            finished: false,
+            time: 0.0,
+            cued: 0.0,
+            seek: None,
+        }
+    }
+
+    /// Binds the tracks named `name` to `entity`.
+    #[must_use]
+    pub fn with_binding(mut self, name: impl Into<String>, entity: Entity) -> Self {
+        self.bindings.insert(name.into(), entity);
+        self
+    }
+
+    /// Restart the timeline when it ends.
+    #[must_use]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Sets the playback speed.
+    #[must_use]
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Plays `sound` for the `Sound` cues named `name`.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn with_sound(mut self, name: impl Into<String>, sound: SourceHandle) -> Self {
+        self.sounds.insert(name.into(), sound);
+        self
+    }
+
+    /// The timeline played.
+    #[must_use]
+    pub fn timeline(&self) -> &TimelineHandle {
+        &self.timeline
+    }
+
+    /// The entity bound to the tracks named `name`.
+    #[must_use]
+    pub fn binding(&self, name: &str) -> Option<Entity> {
+        self.bindings.get(name).copied()
+    }
+
+    /// Binds the tracks named `name` to `entity`, replacing their previous entity.
+    pub fn set_binding(&mut self, name: impl Into<String>, entity: Entity) {
+        self.bindings.insert(name.into(), entity);
+    }
+
+    /// The sound played for the `Sound` cues named `name`.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn sound(&self, name: &str) -> Option<&SourceHandle> {
+        self.sounds.get(name)
+    }
+
+    /// Resumes the playback, restarting the timeline if it ended.
+    pub fn play(&mut self) {
+        if self.finished {
+            self.seek(0.0);
+        }
+        self.playing = true;
+    }
+
+    /// Pauses the playback, along with the sounds and animations started by the cues.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Whether the timeline is playing.
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether the timeline reached its end, and isn't looping.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Moves the playback to `time` seconds from the start of the timeline.
+    pub fn seek(&mut self, time: f32) {
+        let time = time.max(0.0);
+        self.seek = Some(time);
+        self.time = time;
+        self.finished = false;
+    }
+
+    /// Time from the start of the timeline, in seconds.
+    #[must_use]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Takes the time of the last seek since the previous frame.
+    pub(crate) fn take_seek(&mut self) -> Option<f32> {
+        self.seek.take()
+    }
+
+    /// Records that the timeline reached its end.
+    pub(crate) fn finish(&mut self, duration: f32) {
+        self.time = duration;
+        self.cued = duration;
+        self.playing = false;
+        self.finished = true;
+    }
+}
@@ -0,0 +1,362 @@
+#[cfg(feature = "animation")]
+use amethyst_animation::{AnimationCommand, AnimationControlSet, AnimationSet, EndControl};
+use amethyst_assets::AssetStorage;
+#[cfg(feature = "audio")]
+use amethyst_audio::{output::OutputWrapper, AudioTrack, Source};
+use amethyst_core::{
+    ecs::{CommandBuffer, Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::{EventChannel, ReaderId},
+    HiddenPropagate, Time, Transform,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    event::TimelineEvent,
+    player::TimelinePlayer,
+    timeline::{Cue, CueAction, Timeline},
+};
+
+/// Plays the [`TimelinePlayer`]s, moving and showing their bound entities and sending their cues
+/// as [`TimelineEvent`]s.
+#[derive(Debug, Default)]
+pub struct TimelineSystem;
+
+impl System for TimelineSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("TimelineSystem")
+                .read_resource::<Time>()
+                .read_resource::<AssetStorage<Timeline>>()
+                .write_resource::<EventChannel<TimelineEvent>>()
+                .with_query(<(Entity, &mut TimelinePlayer)>::query())
+                .with_query(<&mut Transform>::query())
+                .build(
+                    |commands, world, (time, timelines, events), (players, transforms)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("timeline_system");
+
+                        let delta = time.delta_time().as_secs_f32();
+                        let mut moves = Vec::new();
+                        for (entity, player) in players.iter_mut(world) {
+                            let timeline = match timelines.get(player.timeline()) {
+                                Some(timeline) => timeline,
+                                None => continue,
+                            };
+                            let duration = timeline.duration();
+
+                            let mut sample = false;
+                            if let Some(seek) = player.take_seek() {
+                                let seek = seek.min(duration);
+                                player.time = seek;
+                                player.cued = seek;
+                                for (binding, visible) in timeline.visibility(seek) {
+                                    if let Some(target) = player.binding(binding) {
+                                        set_visible(commands, target, visible);
+                                    }
+                                }
+                                events.single_write(TimelineEvent::Seek {
+                                    player: *entity,
+                                    time: seek,
+                                });
+                                sample = true;
+                            }
+
+                            if player.is_playing() {
+                                let mut now = player.time + delta * player.speed.max(0.0);
+                                while now >= duration {
+                                    let cues = timeline.cues(player.cued, f32::INFINITY);
+                                    trigger(commands, events, *entity, player, cues);
+                                    events
+                                        .single_write(TimelineEvent::Finished { player: *entity });
+                                    if !player.looping || duration <= 0.0 {
+                                        player.finish(duration);
+                                        break;
+                                    }
+                                    now -= duration;
+                                    player.cued = 0.0;
+                                }
+                                if !player.is_finished() {
+                                    let cues = timeline.cues(player.cued, now);
+                                    trigger(commands, events, *entity, player, cues);
+                                    player.time = now;
+                                    player.cued = now;
+                                }
+                                sample = true;
+                            }
+
+                            if sample {
+                                for (binding, transform) in timeline.transforms(player.time()) {
+                                    if let Some(target) = player.binding(binding) {
+                                        moves.push((target, transform));
+                                    }
+                                }
+                            }
+                        }
+
+                        for (target, sampled) in moves {
+                            if let Ok(transform) = transforms.get_mut(world, target) {
+                                transform.set_isometry(*sampled.isometry());
+                                *transform.scale_mut() = *sampled.scale();
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Applies the `Show` and `Hide` cues and sends all the cues as events.
+fn trigger(
+    commands: &mut CommandBuffer,
+    events: &mut EventChannel<TimelineEvent>,
+    entity: Entity,
+    player: &TimelinePlayer,
+    cues: Vec<(Option<&str>, &Cue)>,
+) {
+    for (binding, cue) in cues {
+        let target = binding.and_then(|binding| player.binding(binding));
+        match (&cue.action, target) {
+            (CueAction::Show, Some(target)) => set_visible(commands, target, true),
+            (CueAction::Hide, Some(target)) => set_visible(commands, target, false),
+            _ => {}
+        }
+        events.single_write(TimelineEvent::Cue {
+            player: entity,
+            target,
+            action: cue.action.clone(),
+        });
+    }
+}
+
+fn set_visible(commands: &mut CommandBuffer, target: Entity, visible: bool) {
+    if visible {
+        commands.remove_component::<HiddenPropagate>(target);
+    } else {
+        commands.add_component(target, HiddenPropagate::new());
+    }
+}
+
+/// Whether the sounds and animations started by the cues of `player` are paused.
+#[cfg(any(feature = "audio", feature = "animation"))]
+fn is_paused(player: &TimelinePlayer) -> bool {
+    !player.is_playing() && !player.is_finished()
+}
+
+/// Plays the sounds of the `Sound` cues on the output of the `AudioBundle`, pausing them with
+/// their player.
+#[cfg(feature = "audio")]
+#[derive(Debug)]
+pub struct TimelineAudioSystem {
+    reader: ReaderId<TimelineEvent>,
+}
+
+#[cfg(feature = "audio")]
+impl TimelineAudioSystem {
+    /// Creates a new `TimelineAudioSystem`.
+    pub fn new(reader: ReaderId<TimelineEvent>) -> Self {
+        Self { reader }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl System for TimelineAudioSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("TimelineAudioSystem")
+                .read_resource::<EventChannel<TimelineEvent>>()
+                .read_resource::<AssetStorage<Source>>()
+                .read_resource::<OutputWrapper>()
+                .with_query(<&mut TimelinePlayer>::query())
+                .build(
+                    move |_commands, world, (events, sources, wrapper), players| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("timeline_audio_system");
+
+                        for event in events.read(&mut self.reader) {
+                            match event {
+                                TimelineEvent::Cue {
+                                    player,
+                                    action: CueAction::Sound(name),
+                                    ..
+                                } => {
+                                    let (player, output) =
+                                        match (players.get_mut(world, *player), &wrapper.output) {
+                                            (Ok(player), Some(output)) => (player, output),
+                                            _ => continue,
+                                        };
+                                    let source =
+                                        match player.sound(name).and_then(|s| sources.get(s)) {
+                                            Some(source) => source,
+                                            None => {
+                                                log::warn!(
+                                                    "The sound {:?} of the timeline isn't loaded",
+                                                    name
+                                                );
+                                                continue;
+                                            }
+                                        };
+                                    match AudioTrack::new(output, source) {
+                                        Ok(track) => {
+                                            if !is_paused(player) {
+                                                track.play();
+                                            }
+                                            player.tracks.push(track);
+                                        }
+                                        Err(e) => {
+                                            log::error!(
+                                                "Unable to play the sound {:?}: {}",
+                                                name,
+                                                e
+                                            )
+                                        }
+                                    }
+                                }
+                                TimelineEvent::Seek { player, .. } => {
+                                    if let Ok(player) = players.get_mut(world, *player) {
+                                        player.tracks.clear();
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        for player in players.iter_mut(world) {
+                            player.tracks.retain(|track| !track.is_finished());
+                            let paused = is_paused(player);
+                            for track in &player.tracks {
+                                if paused && !track.is_paused() {
+                                    track.pause();
+                                } else if !paused && track.is_paused() {
+                                    track.play();
+                                }
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Starts the animations of the `Animation` cues from the `AnimationSet<String, Transform>` of
+/// their entities, pausing them with their player.
+#[cfg(feature = "animation")]
+#[derive(Debug)]
+pub struct TimelineAnimationSystem {
+    reader: ReaderId<TimelineEvent>,
+}
+
+#[cfg(feature = "animation")]
+impl TimelineAnimationSystem {
+    /// Creates a new `TimelineAnimationSystem`.
+    pub fn new(reader: ReaderId<TimelineEvent>) -> Self {
+        Self { reader }
+    }
+}
+
+#[cfg(feature = "animation")]
+impl System for TimelineAnimationSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("TimelineAnimationSystem")
+                .read_resource::<EventChannel<TimelineEvent>>()
+                .with_query(<(Entity, &mut TimelinePlayer)>::query())
+                .with_query(<&AnimationSet<String, Transform>>::query())
+                .with_query(<&mut AnimationControlSet<String, Transform>>::query())
+                .build(move |commands, world, events, (players, sets, controls)| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("timeline_animation_system");
+
+                    for event in events.read(&mut self.reader) {
+                        match event {
+                            TimelineEvent::Cue {
+                                player,
+                                target: Some(target),
+                                action: CueAction::Animation(id),
+                            } => {
+                                let animation = sets
+                                    .get(world, *target)
+                                    .ok()
+                                    .and_then(|set| set.get(id))
+                                    .cloned();
+                                let animation = match animation {
+                                    Some(animation) => animation,
+                                    None => {
+                                        log::warn!(
+                                            "The animation {:?} of the timeline isn't loaded",
+                                            id
+                                        );
+                                        continue;
+                                    }
+                                };
+                                if let Ok(control_set) = controls.get_mut(world, *target) {
+                                    control_set.add_animation(
+                                        id.clone(),
+                                        &animation,
+                                        EndControl::Normal,
+                                        1.0,
+                                        AnimationCommand::Start,
+                                    );
+                                } else {
+                                    let mut control_set = AnimationControlSet::default();
+                                    control_set.add_animation(
+                                        id.clone(),
+                                        &animation,
+                                        EndControl::Normal,
+                                        1.0,
+                                        AnimationCommand::Start,
+                                    );
+                                    commands.add_component(*target, control_set);
+                                }
+                                if let Ok((_, player)) = players.get_mut(world, *player) {
+                                    player.animations.push((*target, id.clone()));
+                                }
+                            }
+                            TimelineEvent::Seek { player, .. } => {
+                                let animations = match players.get_mut(world, *player) {
+                                    Ok((_, player)) => std::mem::take(&mut player.animations),
+                                    Err(_) => continue,
+                                };
+                                for (target, id) in animations {
+                                    if let Ok(control_set) = controls.get_mut(world, target) {
+                                        control_set.abort(&id);
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    let mut started = Vec::new();
+                    for (entity, player) in players.iter_mut(world) {
+                        let paused = is_paused(player);
+                        started.extend(
+                            player
+                                .animations
+                                .drain(..)
+                                .map(|(target, id)| (*entity, paused, target, id)),
+                        );
+                    }
+                    for (entity, paused, target, id) in started {
+                        let animated = sets.get(world, target).is_ok();
+                        match controls.get_mut(world, target) {
+                            Ok(control_set) if control_set.has_animation(&id) => {
+                                if paused {
+                                    control_set.pause(&id);
+                                } else {
+                                    control_set.start(&id);
+                                }
+                            }
+                            // The control set is added at the end of the frame.
+                            Err(_) if animated => {}
+                            // The animation ended, or its entity was deleted.
+                            _ => continue,
+                        }
+                        if let Ok((_, player)) = players.get_mut(world, entity) {
+                            player.animations.push((target, id));
+                        }
+                    }
+                }),
+        )
+    }
+}
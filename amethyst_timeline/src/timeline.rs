@@ -0,0 +1,329 @@
+//! The timeline asset, made of tracks of keys and cues at absolute times.
+
+use std::cmp::Ordering;
+
+use amethyst_assets::{
+    distill_importer,
+    distill_importer::{typetag, SerdeImportable},
+    register_asset_type, Asset, AssetProcessorSystem, Handle,
+};
+use amethyst_core::{
+    math::{Translation3, UnitQuaternion, Vector3},
+    Transform,
+};
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+/// A handle to a timeline asset.
+pub type TimelineHandle = Handle<Timeline>;
+
+/// A sequence of tracks played together by a [`TimelinePlayer`](crate::TimelinePlayer).
+///
+/// Each track is bound by name to an entity of the player, whose `Transform` it moves or which
+/// its cues target. Times are in seconds from the start of the timeline.
+///
+/// Timelines are loaded from RON files:
+///
+/// ```ron
+/// {
+///     "6f4e6c6a-0b07-4c1a-a4b8-4b4c4b0a4d2e": (
+///         tracks: [
+///             (
+///                 binding: Some("camera"),
+///                 content: Transform([
+///                     (time: 0.0, translation: (0.0, 2.0, 10.0)),
+///                     (time: 4.0, translation: (5.0, 2.0, 5.0), rotation: (0.0, 0.8, 0.0), interpolation: Smooth),
+///                 ]),
+///             ),
+///             (
+///                 binding: Some("hero"),
+///                 content: Cues([
+///                     (time: 1.0, action: Animation("wave")),
+///                     (time: 1.5, action: Sound("hello")),
+///                     (time: 3.0, action: Event("open_door")),
+///                 ]),
+///             ),
+///         ],
+///     ),
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, TypeUuid, SerdeImportable)]
+#[serde(default)]
+#[uuid = "4804c57b-4f16-431b-8791-4527b8c85c51"]
+pub struct Timeline {
+    /// Length of the timeline, the time of its last key or cue when not set.
+    pub duration: Option<f32>,
+    /// Tracks played by the timeline.
+    pub tracks: Vec<Track>,
+}
+
+impl Asset for Timeline {
+    fn name() -> &'static str {
+        "timeline::Timeline"
+    }
+    type Data = Self;
+}
+
+register_asset_type!(Timeline => Timeline; AssetProcessorSystem<Timeline>);
+
+/// A track of a `Timeline`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    /// Name of the entity of the player the track is bound to.
+    #[serde(default)]
+    pub binding: Option<String>,
+    /// Keys or cues of the track.
+    pub content: TrackContent,
+}
+
+/// What a `Track` plays.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TrackContent {
+    /// Keys the `Transform` of the bound entity is moved through, sorted by time.
+    Transform(Vec<TransformKey>),
+    /// Cues triggered when the playback reaches them, sorted by time.
+    Cues(Vec<Cue>),
+}
+
+/// A `Transform` reached by the bound entity of a track at a time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformKey {
+    /// Time of the key.
+    pub time: f32,
+    /// Translation of the entity.
+    pub translation: [f32; 3],
+    /// Rotation of the entity, as euler angles in radians.
+    pub rotation: [f32; 3],
+    /// Scale of the entity.
+    pub scale: [f32; 3],
+    /// How the entity moves from this key to the next one.
+    pub interpolation: KeyInterpolation,
+}
+
+impl Default for TransformKey {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            translation: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: [1.0; 3],
+            interpolation: KeyInterpolation::default(),
+        }
+    }
+}
+
+/// Interpolation between a `TransformKey` and the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyInterpolation {
+    /// The entity stays at the key until the next one.
+    Step,
+    /// The entity moves at a constant speed.
+    Linear,
+    /// The entity eases in and out of the keys.
+    Smooth,
+}
+
+impl Default for KeyInterpolation {
+    fn default() -> Self {
+        KeyInterpolation::Linear
+    }
+}
+
+impl KeyInterpolation {
+    fn apply(self, factor: f32) -> f32 {
+        match self {
+            KeyInterpolation::Step => 0.0,
+            KeyInterpolation::Linear => factor,
+            KeyInterpolation::Smooth => factor * factor * (3.0 - 2.0 * factor),
+        }
+    }
+}
+
+/// An action triggered when the playback reaches a time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cue {
+    /// Time of the cue.
+    pub time: f32,
+    /// Action triggered by the cue.
+    pub action: CueAction,
+}
+
+/// Action of a `Cue`, targeting the bound entity of its track.
+///
+/// Each cue is sent as a [`TimelineEvent::Cue`](crate::TimelineEvent::Cue), so other systems can
+/// react to it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CueAction {
+    /// An event for the game, handled by the systems reading the `TimelineEvent`s.
+    Event(String),
+    /// Shows the entity, removing its `HiddenPropagate`.
+    Show,
+    /// Hides the entity and its children, adding a `HiddenPropagate`.
+    Hide,
+    /// Plays the sound added to the player under this name, with the `audio` feature.
+    Sound(String),
+    /// Starts the animation of this id from the `AnimationSet<String, Transform>` of the entity,
+    /// with the `animation` feature.
+    Animation(String),
+}
+
+impl Timeline {
+    /// Length of the timeline, the time of its last key or cue when it isn't set.
+    #[must_use]
+    pub fn duration(&self) -> f32 {
+        self.duration.unwrap_or_else(|| {
+            self.tracks
+                .iter()
+                .filter_map(|track| {
+                    match track.content {
+                        TrackContent::Transform(ref keys) => keys.last().map(|key| key.time),
+                        TrackContent::Cues(ref cues) => cues.last().map(|cue| cue.time),
+                    }
+                })
+                .fold(0.0, f32::max)
+        })
+    }
+
+    /// Cues at or after `from` and before `to`, with the binding of their track, in the order of
+    /// their times.
+    pub(crate) fn cues(&self, from: f32, to: f32) -> Vec<(Option<&str>, &Cue)> {
+        let mut cues: Vec<_> = self
+            .tracks
+            .iter()
+            .filter_map(|track| {
+                match track.content {
+                    TrackContent::Cues(ref cues) => Some((track.binding.as_deref(), cues)),
+                    TrackContent::Transform(_) => None,
+                }
+            })
+            .flat_map(|(binding, cues)| {
+                cues.iter()
+                    .filter(move |cue| cue.time >= from && cue.time < to)
+                    .map(move |cue| (binding, cue))
+            })
+            .collect();
+        cues.sort_by(|a, b| a.1.time.partial_cmp(&b.1.time).unwrap_or(Ordering::Equal));
+        cues
+    }
+
+    /// Whether the bound entities of the cue tracks are visible just before `time`.
+    ///
+    /// Before the first `Show` or `Hide` cue of a track, the entity is in the opposite state.
+    pub(crate) fn visibility(&self, time: f32) -> Vec<(&str, bool)> {
+        self.tracks
+            .iter()
+            .filter_map(|track| {
+                let cues = match track.content {
+                    TrackContent::Cues(ref cues) => cues,
+                    TrackContent::Transform(_) => return None,
+                };
+                let visibility = cues.iter().filter_map(|cue| {
+                    match cue.action {
+                        CueAction::Show => Some((cue.time, true)),
+                        CueAction::Hide => Some((cue.time, false)),
+                        _ => None,
+                    }
+                });
+                let (_, first) = visibility.clone().next()?;
+                let visible = visibility
+                    .take_while(|(cue_time, _)| *cue_time < time)
+                    .last()
+                    .map_or(!first, |(_, visible)| visible);
+                Some((track.binding.as_deref()?, visible))
+            })
+            .collect()
+    }
+
+    /// `Transform`s of the bound entities of the transform tracks at `time`.
+    pub(crate) fn transforms(&self, time: f32) -> Vec<(&str, Transform)> {
+        self.tracks
+            .iter()
+            .filter_map(|track| {
+                match track.content {
+                    TrackContent::Transform(ref keys) => {
+                        Some((track.binding.as_deref()?, sample(keys, time)?))
+                    }
+                    TrackContent::Cues(_) => None,
+                }
+            })
+            .collect()
+    }
+}
+
+fn rotation(key: &TransformKey) -> UnitQuaternion<f32> {
+    let [x, y, z] = key.rotation;
+    UnitQuaternion::from_euler_angles(x, y, z)
+}
+
+/// Samples the `Transform` of `keys` at `time`, holding the first and last keys outside of them.
+fn sample(keys: &[TransformKey], time: f32) -> Option<Transform> {
+    let next = keys.iter().position(|key| key.time > time);
+    let (from, to, factor) = match next {
+        None => (keys.last()?, keys.last()?, 0.0),
+        Some(0) => (&keys[0], &keys[0], 0.0),
+        Some(index) => {
+            let (from, to) = (&keys[index - 1], &keys[index]);
+            let factor = (time - from.time) / (to.time - from.time);
+            (from, to, from.interpolation.apply(factor))
+        }
+    };
+
+    let translation = Vector3::from(from.translation).lerp(&Vector3::from(to.translation), factor);
+    let scale = Vector3::from(from.scale).lerp(&Vector3::from(to.scale), factor);
+    let rotation = rotation(from).slerp(&rotation(to), factor);
+    Some(Transform::new(
+        Translation3::from(translation),
+        rotation,
+        scale,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, x: f32, interpolation: KeyInterpolation) -> TransformKey {
+        TransformKey {
+            time,
+            translation: [x, 0.0, 0.0],
+            interpolation,
+            ..TransformKey::default()
+        }
+    }
+
+    #[test]
+    fn keys_are_interpolated() {
+        let keys = [
+            key(1.0, 0.0, KeyInterpolation::Linear),
+            key(3.0, 4.0, KeyInterpolation::Step),
+            key(4.0, 8.0, KeyInterpolation::Linear),
+        ];
+        let x = |time| sample(&keys, time).unwrap().translation().x;
+        assert!(x(0.0).abs() < f32::EPSILON);
+        assert!((x(2.0) - 2.0).abs() < f32::EPSILON);
+        assert!((x(3.5) - 4.0).abs() < f32::EPSILON);
+        assert!((x(5.0) - 8.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn cues_and_visibility_follow_time() {
+        let cue = |time, action| Cue { time, action };
+        let timeline = Timeline {
+            duration: None,
+            tracks: vec![Track {
+                binding: Some("door".into()),
+                content: TrackContent::Cues(vec![
+                    cue(1.0, CueAction::Show),
+                    cue(2.0, CueAction::Event("open".into())),
+                    cue(3.0, CueAction::Hide),
+                ]),
+            }],
+        };
+        assert!((timeline.duration() - 3.0).abs() < f32::EPSILON);
+        assert_eq!(timeline.cues(1.0, 3.0).len(), 2);
+        assert_eq!(timeline.visibility(1.0), vec![("door", false)]);
+        assert_eq!(timeline.visibility(2.0), vec![("door", true)]);
+        assert_eq!(timeline.visibility(3.5), vec![("door", false)]);
+    }
+}
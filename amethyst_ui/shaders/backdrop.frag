@@ -0,0 +1,46 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform BackdropViewArgs {
+    uniform vec2 inverse_window_size;
+};
+
+layout(set = 1, binding = 0) uniform sampler2D scene;
+
+layout(location = 0) flat in float in_blur_radius;
+layout(location = 1) flat in float in_darken;
+
+layout(location = 0) out vec4 out_color;
+
+const int SAMPLES = 32;
+const float GOLDEN_ANGLE = 2.39996323;
+
+// Interleaved gradient noise, rotating the sampling pattern per pixel to trade banding for
+// fine grain.
+float noise(vec2 pixel) {
+    return fract(52.9829189 * fract(dot(pixel, vec2(0.06711056, 0.00583715))));
+}
+
+void main() {
+    vec2 uv = gl_FragCoord.xy * inverse_window_size;
+    vec3 color;
+
+    if (in_blur_radius < 0.5) {
+        color = texture(scene, uv).rgb;
+    } else {
+        // Gaussian weighted samples on a spiral filling the blur disk.
+        vec2 radius = in_blur_radius * inverse_window_size;
+        float rotation = noise(gl_FragCoord.xy) * 6.28318531;
+        vec3 sum = vec3(0.0);
+        float total = 0.0;
+        for (int i = 0; i < SAMPLES; i++) {
+            float r = sqrt((float(i) + 0.5) / float(SAMPLES));
+            float theta = float(i) * GOLDEN_ANGLE + rotation;
+            float weight = exp(-2.0 * r * r);
+            sum += texture(scene, uv + vec2(cos(theta), sin(theta)) * r * radius).rgb * weight;
+            total += weight;
+        }
+        color = sum / total;
+    }
+
+    out_color = vec4(color * (1.0 - in_darken), 1.0);
+}
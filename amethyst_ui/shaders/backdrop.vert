@@ -0,0 +1,33 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform BackdropViewArgs {
+    uniform vec2 inverse_window_size;
+};
+
+layout(location = 0) in vec2 coords;
+layout(location = 1) in vec2 dimensions;
+layout(location = 2) in float blur_radius;
+layout(location = 3) in float darken;
+
+layout(location = 0) flat out float out_blur_radius;
+layout(location = 1) flat out float out_darken;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    vec2 pos = positions[gl_VertexIndex];
+
+    out_blur_radius = blur_radius;
+    out_darken = darken;
+
+    vec2 center = coords * inverse_window_size;
+    center.y = 1.0 - center.y;
+    vec2 final_pos = (center + dimensions * inverse_window_size * pos) * 2.0 - vec2(1.0);
+
+    gl_Position = vec4(final_pos, 0.0, 1.0);
+}
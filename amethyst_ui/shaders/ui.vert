@@ -9,10 +9,13 @@ layout(location = 1) in vec2 dimensions;
 layout(location = 2) in vec4 tex_coord_bounds;
 layout(location = 3) in vec4 color;
 layout(location = 4) in vec4 color_bias;
+layout(location = 5) in vec4 clip;
 
 layout(location = 0) out vec2 out_tex_coords;
 layout(location = 1) out vec4 out_color;
 layout(location = 2) out vec4 out_color_bias;
+layout(location = 3) out vec2 out_position;
+layout(location = 4) flat out vec4 out_clip;
 
 const vec2 positions[4] = vec2[](
     vec2(0.5, -0.5), // Right bottom
@@ -28,6 +31,8 @@ void main() {
     out_tex_coords = mix(tex_coord_bounds.xy, tex_coord_bounds.zw, coords_base);
     out_color = color;
     out_color_bias = color_bias;
+    out_position = coords + dimensions * pos;
+    out_clip = clip;
 
     vec2 center = coords * inverse_window_size;
     center.y = 1.0 - center.y; 
@@ -0,0 +1,17 @@
+#version 450
+
+layout(set = 1, binding = 0) uniform sampler2D tex;
+
+layout(location = 0) in vec2 in_tex_coords;
+layout(location = 1) in vec4 in_color;
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    float coverage = texture(tex, in_tex_coords).a;
+    if (coverage == 0.0) {
+        discard;
+    }
+
+    out_color = vec4(in_color.rgb, in_color.a * coverage);
+}
@@ -0,0 +1,43 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(location = 0) in mat4 model;
+layout(location = 4) in vec2 coords;
+layout(location = 5) in vec2 dimensions;
+layout(location = 6) in vec4 tex_coord_bounds;
+layout(location = 7) in vec4 color;
+layout(location = 8) in float billboard;
+
+layout(location = 0) out vec2 out_tex_coords;
+layout(location = 1) out vec4 out_color;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    vec2 pos = positions[gl_VertexIndex];
+
+    // Glyph texture coordinates grow downwards.
+    out_tex_coords = mix(tex_coord_bounds.xy, tex_coord_bounds.zw, vec2(pos.x + 0.5, 0.5 - pos.y));
+    out_color = color;
+
+    vec2 local = coords + dimensions * pos;
+    vec3 world_position;
+    if (billboard > 0.5) {
+        vec3 right = vec3(view[0][0], view[1][0], view[2][0]);
+        vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+        world_position = model[3].xyz + right * local.x + up * local.y;
+    } else {
+        world_position = (model * vec4(local, 0.0, 1.0)).xyz;
+    }
+    gl_Position = proj_view * vec4(world_position, 1.0);
+}
@@ -0,0 +1,458 @@
+//! Accessibility metadata of ui widgets, the tree exposed to screen readers and the
+//! high-contrast and reduced-motion modes.
+
+use std::collections::HashSet;
+
+use amethyst_core::{
+    ecs::{component, Entity, EntityStore, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    transform::{Children, Parent},
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    Interactable, Selected, TextEditing, UiCheckbox, UiImage, UiProgressBar, UiSlider, UiText,
+    UiTransform,
+};
+
+/// What a ui widget is to assistive technologies like screen readers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiRole {
+    /// A widget activated by clicking it.
+    Button,
+    /// A non interactive text.
+    Label,
+    /// A decorative or informative image.
+    Image,
+    /// A text field the user can edit.
+    TextInput,
+    /// A widget selecting a value in a range.
+    Slider,
+    /// A toggle, like a checkbox.
+    CheckBox,
+    /// A container whose content can be scrolled.
+    ScrollArea,
+    /// A container grouping other widgets.
+    Group,
+    /// A bar showing the progress of a task.
+    ProgressBar,
+    /// A role not covered by the other variants.
+    Other(String),
+}
+
+impl UiRole {
+    /// Returns the name of the role announced by screen readers, like `"button"`.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        match self {
+            UiRole::Button => "button",
+            UiRole::Label => "label",
+            UiRole::Image => "image",
+            UiRole::TextInput => "text field",
+            UiRole::Slider => "slider",
+            UiRole::CheckBox => "check box",
+            UiRole::ScrollArea => "scroll area",
+            UiRole::Group => "group",
+            UiRole::ProgressBar => "progress bar",
+            UiRole::Other(role) => role,
+        }
+    }
+}
+
+/// Describes a ui widget to assistive technologies.
+///
+/// Widgets with this component and a `UiTransform` are listed in the [`UiAccessibility`]
+/// resource while they are not hidden. Text fields, sliders, check boxes, progress bars and
+/// interactable elements with a text, as buttons, are listed without it too, with their role
+/// and the text of buttons as name.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UiAccessible {
+    /// Short name announced for the widget, like the text of a button.
+    pub name: String,
+    /// What the widget is.
+    pub role: UiRole,
+    /// Longer description announced on request, like a tooltip.
+    pub description: Option<String>,
+}
+
+impl UiAccessible {
+    /// Creates the description of a widget with given name and role.
+    #[must_use]
+    pub fn new(name: impl Into<String>, role: UiRole) -> Self {
+        Self {
+            name: name.into(),
+            role,
+            description: None,
+        }
+    }
+
+    /// Sets the longer description of the widget.
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// Replacements used to draw a widget while [`UiAccessibility::high_contrast`] is enabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UiHighContrast {
+    /// Image drawn instead of the `UiImage` of the widget.
+    pub image: Option<UiImage>,
+    /// Linear RGBA color the `UiText` of the widget is drawn with.
+    pub text_color: Option<[f32; 4]>,
+}
+
+impl UiHighContrast {
+    /// Creates a component replacing nothing.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draws the given image instead of the `UiImage` of the widget.
+    #[must_use]
+    pub fn with_image(mut self, image: UiImage) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Draws the `UiText` of the widget with the given linear RGBA color.
+    #[must_use]
+    pub fn with_text_color(mut self, color: [f32; 4]) -> Self {
+        self.text_color = Some(color);
+        self
+    }
+}
+
+/// State of a widget announced by screen readers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UiAccessibleState {
+    /// Whether a check box is checked.
+    pub checked: Option<bool>,
+    /// Value of a slider, progress bar or text field.
+    pub value: Option<String>,
+}
+
+/// A widget described to assistive technologies, as listed by [`UiAccessibility::nodes`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibleNode {
+    /// Entity of the widget.
+    pub entity: Entity,
+    /// Closest ancestor of the widget which is accessible too, if any.
+    pub parent: Option<Entity>,
+    /// Metadata of the widget.
+    pub accessible: UiAccessible,
+    /// Current state of the widget.
+    pub state: UiAccessibleState,
+    /// Bounds of the widget in pixels from the bottom left of the screen, as
+    /// `[min_x, min_y, max_x, max_y]`.
+    pub bounds: [f32; 4],
+    /// Whether the widget is selected.
+    pub focused: bool,
+}
+
+impl AccessibleNode {
+    /// Returns the text screen readers announce for the widget, like `"Music, check box,
+    /// checked"`.
+    #[must_use]
+    pub fn announcement(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.accessible.name.is_empty() {
+            parts.push(self.accessible.name.as_str());
+        }
+        parts.push(self.accessible.role.label());
+        match self.state.checked {
+            Some(true) => parts.push("checked"),
+            Some(false) => parts.push("not checked"),
+            None => {}
+        }
+        if let Some(value) = &self.state.value {
+            parts.push(value);
+        }
+        parts.join(", ")
+    }
+}
+
+/// Bridge between the [`UiAccessibility`] resource and a platform accessibility API, like
+/// AccessKit, through which screen readers get the widgets.
+///
+/// Set it with [`UiAccessibility::set_adapter`], the `UiAccessibilitySystem` then calls it when
+/// the accessible widgets change.
+pub trait UiAccessibilityAdapter: Send + Sync {
+    /// Called with the accessible widgets in reading order, whenever one of them changes.
+    fn update(&mut self, nodes: &[AccessibleNode]);
+
+    /// Called when another widget is selected, or none, e.g. to announce it.
+    fn focus_changed(&mut self, node: Option<&AccessibleNode>);
+}
+
+/// Resource exposing the accessible widgets to platform screen-reader bridges, and selecting
+/// the accessibility modes widgets follow.
+///
+/// With `high_contrast`, widgets having a [`UiHighContrast`] component are drawn with its
+/// replacements. With `reduced_motion`, `Blink`ing entities and text cursors stay visible, and
+/// `Pulse` effects aren't shown.
+#[derive(Derivative, Default)]
+#[derivative(Debug)]
+pub struct UiAccessibility {
+    /// Whether widgets are drawn with their high-contrast replacements.
+    pub high_contrast: bool,
+    /// Whether blinking and other animations of widgets are disabled.
+    pub reduced_motion: bool,
+    nodes: Vec<AccessibleNode>,
+    #[derivative(Debug = "ignore")]
+    adapter: Option<Box<dyn UiAccessibilityAdapter>>,
+}
+
+impl UiAccessibility {
+    /// Returns the visible accessible widgets in reading order, top to bottom then left to
+    /// right, as of the last run of the `UiAccessibilitySystem`.
+    #[must_use]
+    pub fn nodes(&self) -> &[AccessibleNode] {
+        &self.nodes
+    }
+
+    /// Returns the selected accessible widget, if any.
+    #[must_use]
+    pub fn focused(&self) -> Option<&AccessibleNode> {
+        self.nodes.iter().find(|node| node.focused)
+    }
+
+    /// Returns the accessible widgets whose closest accessible ancestor is `parent`, or the
+    /// roots of the tree for `None`, in reading order.
+    pub fn children(&self, parent: Option<Entity>) -> impl Iterator<Item = &AccessibleNode> {
+        self.nodes.iter().filter(move |node| node.parent == parent)
+    }
+
+    /// Sets the bridge to the platform accessibility API, which gets the current widgets from
+    /// the next run of the `UiAccessibilitySystem`.
+    pub fn set_adapter(&mut self, adapter: impl UiAccessibilityAdapter + 'static) {
+        self.adapter = Some(Box::new(adapter));
+        self.nodes.clear();
+    }
+}
+
+/// Lists the accessible widgets in the `UiAccessibility` resource, and passes them on to its
+/// adapter.
+#[derive(Debug)]
+pub struct UiAccessibilitySystem;
+
+impl System for UiAccessibilitySystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiAccessibilitySystem")
+                .write_resource::<UiAccessibility>()
+                .with_query(
+                    <(Entity, Option<&UiAccessible>, &UiTransform, Option<&Selected>)>::query()
+                        .filter(
+                            (component::<UiAccessible>()
+                                | component::<TextEditing>()
+                                | component::<UiSlider>()
+                                | component::<UiCheckbox>()
+                                | component::<UiProgressBar>()
+                                | component::<Interactable>())
+                                & !component::<Hidden>()
+                                & !component::<HiddenPropagate>(),
+                        ),
+                )
+                .with_query(<(
+                    Option<&UiText>,
+                    Option<&TextEditing>,
+                    Option<&UiSlider>,
+                    Option<&UiCheckbox>,
+                    Option<&UiProgressBar>,
+                )>::query())
+                .read_component::<Parent>()
+                .read_component::<Children>()
+                .build(|_, world, accessibility, (query, widgets)| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("ui_accessibility_system");
+
+                    let previous = std::mem::take(&mut accessibility.nodes);
+                    for (entity, accessible, transform, selected) in query.iter(world) {
+                        // Buttons are usually named by the text of a child.
+                        let children = world
+                            .entry_ref(*entity)
+                            .ok()
+                            .and_then(|entry| {
+                                entry
+                                    .get_component::<Children>()
+                                    .ok()
+                                    .map(|children| children.0.to_vec())
+                            })
+                            .unwrap_or_default();
+                        let child_text = children.iter().find_map(|child| {
+                            let (text, ..) = widgets.get(world, *child).ok()?;
+                            text.map(|text| text.text.clone())
+                        });
+                        let widget = widgets.get(world, *entity).ok();
+                        let (accessible, state) = match describe(accessible, widget, child_text) {
+                            Some(description) => description,
+                            None => continue,
+                        };
+                        accessibility.nodes.push(AccessibleNode {
+                            entity: *entity,
+                            parent: None,
+                            accessible,
+                            state,
+                            bounds: [
+                                transform.pixel_x - transform.pixel_width / 2.0,
+                                transform.pixel_y - transform.pixel_height / 2.0,
+                                transform.pixel_x + transform.pixel_width / 2.0,
+                                transform.pixel_y + transform.pixel_height / 2.0,
+                            ],
+                            focused: selected.is_some(),
+                        });
+                    }
+
+                    let accessible: HashSet<Entity> =
+                        accessibility.nodes.iter().map(|node| node.entity).collect();
+                    for node in &mut accessibility.nodes {
+                        let mut ancestor = node.entity;
+                        while let Some(parent) = world
+                            .entry_ref(ancestor)
+                            .ok()
+                            .and_then(|entry| entry.get_component::<Parent>().ok().map(|p| p.0))
+                        {
+                            if accessible.contains(&parent) {
+                                node.parent = Some(parent);
+                                break;
+                            }
+                            ancestor = parent;
+                        }
+                    }
+                    accessibility
+                        .nodes
+                        .sort_by(|a, b| reading_order(a.bounds, b.bounds));
+
+                    let UiAccessibility { nodes, adapter, .. } = &mut **accessibility;
+                    if let Some(adapter) = adapter {
+                        if *nodes != previous {
+                            adapter.update(nodes);
+                        }
+                        let focused = nodes.iter().find(|node| node.focused);
+                        let was_focused = previous.iter().find(|node| node.focused);
+                        if focused.map(|node| node.entity) != was_focused.map(|node| node.entity) {
+                            adapter.focus_changed(focused);
+                        }
+                    }
+                }),
+        )
+    }
+}
+
+/// Components of a widget its accessibility metadata and state are read from.
+type WidgetComponents<'a> = (
+    Option<&'a UiText>,
+    Option<&'a TextEditing>,
+    Option<&'a UiSlider>,
+    Option<&'a UiCheckbox>,
+    Option<&'a UiProgressBar>,
+);
+
+/// Returns the metadata of a widget, inferred from its components if it has no `UiAccessible`,
+/// and its state, or `None` for the interactable elements without text which aren't buttons.
+fn describe(
+    accessible: Option<&UiAccessible>,
+    widget: Option<WidgetComponents<'_>>,
+    child_text: Option<String>,
+) -> Option<(UiAccessible, UiAccessibleState)> {
+    let (text, editing, slider, checkbox, progress_bar) =
+        widget.unwrap_or((None, None, None, None, None));
+    let mut state = UiAccessibleState::default();
+    let role = if let Some(checkbox) = checkbox {
+        state.checked = Some(checkbox.checked);
+        UiRole::CheckBox
+    } else if let Some(slider) = slider {
+        state.value = Some(slider.value.to_string());
+        UiRole::Slider
+    } else if let Some(progress_bar) = progress_bar {
+        state.value = Some(format!("{}%", (progress_bar.value * 100.0).round()));
+        UiRole::ProgressBar
+    } else if let (Some(text), Some(_)) = (text, editing) {
+        state.value = Some(if text.password {
+            // Passwords are announced by their length only.
+            "\u{2022}".repeat(text.text.chars().count())
+        } else {
+            text.text.clone()
+        });
+        UiRole::TextInput
+    } else {
+        UiRole::Button
+    };
+    let accessible = match (accessible, role) {
+        (Some(accessible), _) => accessible.clone(),
+        (None, UiRole::Button) => {
+            let name = text.map(|text| text.text.clone()).or(child_text)?;
+            UiAccessible::new(name, UiRole::Button)
+        }
+        (None, role) => UiAccessible::new("", role),
+    };
+    Some((accessible, state))
+}
+
+fn reading_order(a: [f32; 4], b: [f32; 4]) -> std::cmp::Ordering {
+    b[3].partial_cmp(&a[3])
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then(a[0].partial_cmp(&b[0]).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widgets_without_metadata_are_described_from_their_components() {
+        let checkbox = UiCheckbox {
+            checked: true,
+            ..UiCheckbox::default()
+        };
+        let widget = (None, None, None, Some(&checkbox), None);
+        let (accessible, state) = describe(None, Some(widget), None).unwrap();
+        assert_eq!(UiRole::CheckBox, accessible.role);
+        assert_eq!(Some(true), state.checked);
+
+        assert_eq!(None, describe(None, Some((None, None, None, None, None)), None));
+        let (accessible, state) = describe(None, None, Some("Play".into())).unwrap();
+        let node = AccessibleNode {
+            entity: amethyst_core::ecs::World::default().push((0_u8,)),
+            parent: None,
+            accessible,
+            state,
+            bounds: [0.0; 4],
+            focused: true,
+        };
+        assert_eq!("Play, button", node.announcement());
+
+        let (accessible, state) = describe(
+            Some(&UiAccessible::new("Music", UiRole::CheckBox)),
+            Some((None, None, None, Some(&UiCheckbox::default()), None)),
+            None,
+        )
+        .unwrap();
+        assert_eq!("Music", accessible.name);
+        assert_eq!(Some(false), state.checked);
+    }
+
+    #[test]
+    fn widgets_are_read_top_to_bottom_then_left_to_right() {
+        let mut bounds = vec![
+            [100.0, 0.0, 200.0, 50.0],
+            [0.0, 50.0, 100.0, 100.0],
+            [0.0, 0.0, 100.0, 50.0],
+        ];
+        bounds.sort_by(|a, b| reading_order(*a, *b));
+        assert_eq!(
+            bounds,
+            vec![
+                [0.0, 50.0, 100.0, 100.0],
+                [0.0, 0.0, 100.0, 50.0],
+                [100.0, 0.0, 200.0, 50.0],
+            ]
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! Blurring or darkening the scene behind UI panels, e.g. for frosted glass pause menus.
+//!
+//! Entities with a [`UiBackdrop`] and a [`UiTransform`] have the scene behind their rectangle
+//! replaced by a blurred and darkened copy of it by the [`RenderUiBackdrop`] plugin, before the
+//! UI is drawn over it. A semi-transparent [`UiImage`](crate::UiImage) on the same entity then
+//! gives the panel a frosted glass look.
+//!
+//! The backdrop is a post-process sampling the image of the render target the scene is drawn
+//! into, so the scene can't be drawn directly into the target the UI is drawn into. It has to
+//! be drawn into a render target copied into it instead, e.g. the `Target::Scaled` of
+//! `RenderToWindow::with_render_scale` or the `Target::ColorGrading` of `RenderColorGrading`:
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToWindow::from_config(display_config).with_render_scale(1.0))
+//!     .with_plugin(RenderFlat2D::default().with_target(Target::Scaled))
+//!     .with_plugin(RenderUiBackdrop::new(Target::Scaled))
+//!     .with_plugin(RenderUi::default())
+//! ```
+//!
+//! The backdrop is constrained to what the scene target contains: UI elements below a panel
+//! are not blurred by it, and multisampled scene targets are not supported.
+
+use amethyst_assets::prefab::{legion_prefab, register_component_type, serde_diff, SerdeDiff};
+use amethyst_core::{
+    ecs::{component, IntoQuery, Resources, World},
+    Hidden, HiddenPropagate,
+};
+use amethyst_error::Error;
+use amethyst_rendy::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage},
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    rendy::{
+        command::{QueueId, RenderPassEncoder},
+        factory::Factory,
+        graph::{
+            render::{PrepareResult, RenderGroup, RenderGroupDesc},
+            GraphContext, ImageAccess, NodeBuffer, NodeImage,
+        },
+        hal::{
+            self,
+            device::Device,
+            format::Format,
+            pso::{self, ShaderStageFlags},
+        },
+        mesh::{AsVertex, VertexFormat},
+        resource::{
+            DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo, Sampler,
+        },
+        shader::{Shader, SpirvShader},
+    },
+    simple_shader_set,
+    submodules::{DynamicUniform, DynamicVertexBuffer},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+use amethyst_window::ScreenDimensions;
+use derivative::Derivative;
+use glsl_layout::{vec2, Uniform};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use type_uuid::TypeUuid;
+
+use crate::UiTransform;
+
+/// A component blurring and darkening the scene behind a UI element, see the
+/// [module documentation](self).
+#[derive(Clone, Copy, Derivative, PartialEq, Deserialize, Serialize, SerdeDiff, TypeUuid)]
+#[uuid = "8d2e4b7a-1f3c-4a59-b6e0-7c9d2a5f1e84"]
+#[derivative(Debug, Default)]
+#[serde(default)]
+pub struct UiBackdrop {
+    /// Radius of the blur in pixels, `0.0` to not blur.
+    #[derivative(Default(value = "8."))]
+    pub blur_radius: f32,
+    /// Darkening of the scene, from `0.0` keeping it as is to `1.0` making it black.
+    #[derivative(Default(value = "0.3"))]
+    pub darken: f32,
+}
+
+register_component_type!(UiBackdrop);
+
+impl UiBackdrop {
+    /// Blur the scene with given radius in pixels and darken it by given amount.
+    #[must_use]
+    pub fn new(blur_radius: f32, darken: f32) -> Self {
+        Self {
+            blur_radius,
+            darken,
+        }
+    }
+}
+
+/// A [`RenderPlugin`] drawing the [`UiBackdrop`]s, see the [module documentation](self).
+#[derive(Debug)]
+pub struct RenderUiBackdrop {
+    scene: Target,
+    target: Target,
+    order: i32,
+}
+
+impl RenderUiBackdrop {
+    /// Draw backdrops sampling the scene drawn into given render target.
+    #[must_use]
+    pub fn new(scene: Target) -> Self {
+        Self {
+            scene,
+            target: Target::default(),
+            order: i32::from(RenderOrder::Overlay) - 1,
+        }
+    }
+
+    /// Select render target on which the backdrops should be rendered, the target of the UI.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the backdrops at given point of the frame, right before the UI at
+    /// [`RenderOrder::Overlay`] by default.
+    #[must_use]
+    pub fn with_order(mut self, order: impl Into<i32>) -> Self {
+        self.order = order.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderUiBackdrop {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        let scene = self.scene;
+        let order = self.order;
+        plan.extend_target(self.target, move |ctx| {
+            if ctx
+                .target_metadata(scene)
+                .map_or(false, |metadata| metadata.samples() > 1)
+            {
+                log::warn!(
+                    "UI backdrops are not drawn, render target {:?} is multisampled.",
+                    scene
+                );
+                return Ok(());
+            }
+            match ctx.try_get_image(TargetImage::Color(scene, 0))? {
+                Some(image) => {
+                    ctx.add(
+                        order,
+                        DrawUiBackdropDesc::new()
+                            .with_samples(ctx.samples())
+                            .builder()
+                            .with_image(image),
+                    )?;
+                }
+                None => {
+                    log::warn!(
+                        "UI backdrops are not drawn, render target {:?} has no color image.",
+                        scene
+                    );
+                }
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C)]
+struct BackdropArgs {
+    coords: [f32; 2],
+    dimensions: [f32; 2],
+    blur_radius: f32,
+    darken: f32,
+}
+
+impl AsVertex for BackdropArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rg32Sfloat, "coords"),
+            (Format::Rg32Sfloat, "dimensions"),
+            (Format::R32Sfloat, "blur_radius"),
+            (Format::R32Sfloat, "darken"),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Uniform)]
+struct BackdropViewArgs {
+    inverse_window_size: vec2,
+}
+
+lazy_static::lazy_static! {
+    static ref BACKDROP_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../compiled/backdrop.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref BACKDROP_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../compiled/backdrop.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+}
+
+/// Describes drawing [`UiBackdrop`]s, blurring the image of the scene.
+///
+/// The scene image must be the only image attached to the render group builder.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawUiBackdropDesc {
+    samples: u8,
+}
+
+impl DrawUiBackdropDesc {
+    /// Create new `DrawUiBackdrop` pass description
+    #[must_use]
+    pub fn new() -> Self {
+        DrawUiBackdropDesc::default()
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawUiBackdropDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::FRAGMENT_SHADER,
+        }]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .expect("Backdrop scene image does not exist")
+            .clone();
+        let format = image.format();
+        let view = factory
+            .create_image_view(
+                image,
+                ImageViewInfo {
+                    view_kind: hal::image::ViewKind::D2,
+                    format,
+                    swizzle: hal::format::Swizzle::NO,
+                    range: hal::image::SubresourceRange {
+                        aspects: hal::format::Aspects::COLOR,
+                        levels: 0..1,
+                        layers: 0..1,
+                    },
+                },
+            )
+            .map_err(|_| pso::CreationError::Other)?;
+        let sampler = factory
+            .get_sampler(hal::image::SamplerDesc::new(
+                hal::image::Filter::Linear,
+                hal::image::WrapMode::Clamp,
+            ))
+            .map_err(|_| pso::CreationError::Other)?;
+
+        let layout: Handle<DescriptorSetLayout<B>> = factory
+            .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                1,
+                pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Sampled { with_sampler: true },
+                },
+                pso::ShaderStageFlags::FRAGMENT,
+            ))))?
+            .into();
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                ),
+            )));
+        }
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_backdrop_pipeline(
+            factory,
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.samples,
+            vec![env.raw_layout(), layout.raw()],
+        )?;
+
+        Ok(Box::new(DrawUiBackdrop::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            vertex,
+            backdrops: Vec::new(),
+            set,
+            view,
+            sampler,
+        }))
+    }
+}
+
+/// Draws [`UiBackdrop`]s in one instanced draw call.
+#[derive(Debug)]
+pub struct DrawUiBackdrop<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, BackdropViewArgs>,
+    vertex: DynamicVertexBuffer<B, BackdropArgs>,
+    backdrops: Vec<BackdropArgs>,
+    set: Escape<DescriptorSet<B>>,
+    view: Escape<ImageView<B>>,
+    sampler: Handle<Sampler<B>>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawUiBackdrop<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+        let screen_dimensions = resources.get::<ScreenDimensions>().unwrap();
+
+        let mut query = <(&UiBackdrop, &UiTransform)>::query()
+            .filter(!component::<Hidden>() & !component::<HiddenPropagate>());
+        self.backdrops.clear();
+        self.backdrops
+            .extend(query.iter(*world).map(|(backdrop, transform)| {
+                BackdropArgs {
+                    coords: [transform.pixel_x(), transform.pixel_y()],
+                    dimensions: [transform.pixel_width, transform.pixel_height],
+                    blur_radius: backdrop.blur_radius.max(0.0),
+                    darken: backdrop.darken.max(0.0).min(1.0),
+                }
+            }));
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            self.vertex.write(
+                factory,
+                index,
+                self.backdrops.len() as u64,
+                Some(&self.backdrops),
+            );
+
+            let view_args = BackdropViewArgs {
+                inverse_window_size: [
+                    1.0 / screen_dimensions.width() as f32,
+                    1.0 / screen_dimensions.height() as f32,
+                ]
+                .into(),
+            };
+            self.env.write(factory, index, view_args.std140());
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.backdrops.is_empty() {
+            return;
+        }
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline_layout,
+                1,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw(0..4, 0..self.backdrops.len() as u32);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_backdrop_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { BACKDROP_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { BACKDROP_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(BackdropArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleStrip))
+                .with_shaders(simple_shader_set(&shader_vertex, Some(&shader_fragment)))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,211 @@
+//! Bidirectional text, reordered by the Unicode bidirectional algorithm before it's laid out.
+
+use std::ops::Range;
+
+use unicode_bidi::BidiInfo;
+
+/// Text reordered from the logical order it's stored in to the visual order it's laid out in,
+/// from left to right.
+#[derive(Debug)]
+pub(crate) struct VisualText {
+    /// The text in visual order, with a line break inserted at each wrap of the logical text.
+    pub(crate) text: String,
+    /// For each char of `text`, index of the char of the logical text, or `None` for the inserted
+    /// line breaks.
+    pub(crate) logical: Vec<Option<usize>>,
+    /// For each char of the logical text, whether it's laid out right to left.
+    pub(crate) rtl: Vec<bool>,
+}
+
+impl VisualText {
+    /// Reorders each line of `text`, the lines being separated by the line breaks of the text and
+    /// by the byte offsets returned by `wraps`, in increasing order.
+    ///
+    /// Returns `None` when the text has no right-to-left characters, so it's laid out as is.
+    pub(crate) fn new(text: &str, wraps: impl FnOnce() -> Vec<usize>) -> Option<Self> {
+        let info = BidiInfo::new(text, None);
+        if !info.has_rtl() {
+            return None;
+        }
+        let wraps = wraps();
+
+        let char_starts: Vec<usize> = text.char_indices().map(|(byte, _)| byte).collect();
+        let char_index = |byte: usize| {
+            char_starts
+                .binary_search(&byte)
+                .expect("Unreachable: Runs start at char boundaries")
+        };
+        let mut visual = VisualText {
+            text: String::with_capacity(text.len() + wraps.len()),
+            logical: Vec::with_capacity(char_starts.len() + wraps.len()),
+            rtl: vec![false; char_starts.len()],
+        };
+
+        for paragraph in &info.paragraphs {
+            let range = paragraph.range.clone();
+            // The paragraph separator stays at the end of the paragraph.
+            let content = text[range.clone()].trim_end_matches(is_separator);
+            let content_end = range.start + content.len();
+
+            let mut line_start = range.start;
+            let line_ends = wraps
+                .iter()
+                .copied()
+                .filter(|wrap| *wrap > range.start && *wrap < content_end)
+                .chain(Some(content_end));
+            for line_end in line_ends {
+                if line_start != range.start {
+                    visual.text.push('\n');
+                    visual.logical.push(None);
+                }
+                let (levels, runs) = info.visual_runs(paragraph, line_start..line_end);
+                for run in runs {
+                    let rtl = levels[run.start].is_rtl();
+                    for (byte, c) in run_chars(text, run, rtl) {
+                        let index = char_index(byte);
+                        visual.rtl[index] = rtl;
+                        visual.text.push(if rtl { mirror(c) } else { c });
+                        visual.logical.push(Some(index));
+                    }
+                }
+                line_start = line_end;
+            }
+
+            for (byte, c) in text[content_end..range.end].char_indices() {
+                visual.text.push(c);
+                visual.logical.push(Some(char_index(content_end + byte)));
+            }
+        }
+        Some(visual)
+    }
+
+    /// Splits `text` into byte ranges of consecutive chars for which `selected` of their logical
+    /// index is the same.
+    pub(crate) fn spans(&self, selected: impl Fn(usize) -> bool) -> Vec<(Range<usize>, bool)> {
+        let mut spans: Vec<(Range<usize>, bool)> = Vec::new();
+        for ((byte, c), logical) in self.text.char_indices().zip(&self.logical) {
+            let end = byte + c.len_utf8();
+            let is_selected = match logical {
+                Some(index) => selected(*index),
+                None => spans.last().map_or(false, |span| span.1),
+            };
+            match spans.last_mut() {
+                Some(span) if span.1 == is_selected => span.0.end = end,
+                _ => spans.push((byte..end, is_selected)),
+            }
+        }
+        spans
+    }
+}
+
+/// Byte offsets at which `text` was wrapped, from the baselines of its non-whitespace glyphs
+/// laid out in order. Lines are wrapped at the start of their first word, or inside words longer
+/// than a line.
+pub(crate) fn wrap_offsets(text: &str, mut glyph_ys: impl Iterator<Item = f32>) -> Vec<usize> {
+    let mut wraps: Vec<usize> = Vec::new();
+    let mut line_y = None;
+    let mut word_start = 0;
+    for (byte, c) in text.char_indices() {
+        if c.is_whitespace() {
+            word_start = byte + c.len_utf8();
+            continue;
+        }
+        let y = match glyph_ys.next() {
+            Some(y) => y,
+            None => break,
+        };
+        if let Some(line_y) = line_y {
+            let new_line = (y - line_y).abs() > 0.5;
+            if new_line && !text[..word_start].ends_with(is_separator) {
+                let word_wrapped = wraps.last().map_or(word_start > 0, |last| *last < word_start);
+                wraps.push(if word_wrapped { word_start } else { byte });
+            }
+        }
+        line_y = Some(y);
+    }
+    wraps
+}
+
+/// Chars of a run with their byte offsets, reversed for right-to-left runs.
+fn run_chars(text: &str, run: Range<usize>, rtl: bool) -> Vec<(usize, char)> {
+    let start = run.start;
+    let mut chars: Vec<(usize, char)> = text[run]
+        .char_indices()
+        .map(|(offset, c)| (start + offset, c))
+        .collect();
+    if rtl {
+        chars.reverse();
+    }
+    chars
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2029}')
+}
+
+/// Mirrored glyph of the brackets of right-to-left text.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_to_right_text_is_not_reordered() {
+        assert!(VisualText::new("hello", Vec::new).is_none());
+    }
+
+    #[test]
+    fn mixed_lines_are_reordered_per_line() {
+        // "abc " followed by the hebrew letters alef, bet and gimel, wrapped after the space.
+        let text = "abc \u{5d0}\u{5d1}\u{5d2}\nd";
+        let visual = VisualText::new(text, || vec![4]).unwrap();
+        assert_eq!("abc \n\u{5d2}\u{5d1}\u{5d0}\nd", visual.text);
+        assert_eq!(
+            vec![
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                None,
+                Some(6),
+                Some(5),
+                Some(4),
+                Some(7),
+                Some(8),
+            ],
+            visual.logical
+        );
+        assert_eq!(
+            vec![false, false, false, false, true, true, true, false, false],
+            visual.rtl
+        );
+
+        let spans = visual.spans(|index| index >= 5);
+        assert_eq!(
+            vec![(0..5, false), (5..9, true), (9..11, false), (11..13, true)],
+            spans
+        );
+    }
+
+    #[test]
+    fn wraps_are_at_word_starts() {
+        let ys = [0., 0., -10., -10., -20., -20., -30.];
+        assert_eq!(vec![3, 9], wrap_offsets("ab cd\nef gh", ys.iter().copied()));
+        assert_eq!(vec![2], wrap_offsets("abcd", ys.iter().copied()));
+    }
+}
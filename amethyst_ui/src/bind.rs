@@ -0,0 +1,145 @@
+//! Binding of `UiText`s to resources and components.
+
+use std::{fmt::Display, sync::Arc};
+
+use amethyst_core::ecs::{storage::Component, Entity, IntoQuery, Resource, Resources, World};
+use derivative::Derivative;
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::UiText;
+
+type Fetch = Arc<dyn Fn(&World, &Resources) -> Option<String> + Send + Sync>;
+
+/// Sets the `UiText` of its entity to a value fetched from a resource or a component each frame,
+/// so labels such as a frame rate or the health of the player need no system updating them.
+///
+/// The text is left unchanged while the resource or component is missing.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// world.push((
+///     UiText::new(None, String::new(), [1., 1., 1., 1.], 16., LineMode::Single, Anchor::Middle),
+///     UiBind::resource(|fps: &FpsCounter| format!("FPS: {:.0}", fps.sampled_fps())),
+/// ));
+/// world.push((text, UiBind::component_format::<Health>(player, "HP: {}")));
+/// ```
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct UiBind {
+    #[derivative(Debug = "ignore")]
+    fetch: Fetch,
+}
+
+impl UiBind {
+    /// Binds the text to the resource `R`, formatted by `format`.
+    pub fn resource<R, F>(format: F) -> Self
+    where
+        R: Resource,
+        F: Fn(&R) -> String + Send + Sync + 'static,
+    {
+        Self {
+            fetch: Arc::new(move |_, resources| resources.get::<R>().map(|r| format(&r))),
+        }
+    }
+
+    /// Binds the text to the resource `R`, replacing `{}` in `pattern` with its value.
+    #[must_use]
+    pub fn resource_format<R: Resource + Display>(pattern: &str) -> Self {
+        let pattern = pattern.to_string();
+        Self::resource(move |r: &R| pattern.replace("{}", &r.to_string()))
+    }
+
+    /// Binds the text to the component `C` of `entity`, formatted by `format`.
+    pub fn component<C, F>(entity: Entity, format: F) -> Self
+    where
+        C: Component,
+        F: Fn(&C) -> String + Send + Sync + 'static,
+    {
+        Self {
+            fetch: Arc::new(move |world, _| {
+                let entry = world.entry_ref(entity).ok()?;
+                let component = entry.get_component::<C>().ok()?;
+                Some(format(component))
+            }),
+        }
+    }
+
+    /// Binds the text to the component `C` of `entity`, replacing `{}` in `pattern` with its
+    /// value.
+    #[must_use]
+    pub fn component_format<C: Component + Display>(entity: Entity, pattern: &str) -> Self {
+        let pattern = pattern.to_string();
+        Self::component(entity, move |c: &C| pattern.replace("{}", &c.to_string()))
+    }
+
+    /// Fetches the bound value, formatted.
+    fn text(&self, world: &World, resources: &Resources) -> Option<String> {
+        (self.fetch)(world, resources)
+    }
+}
+
+/// Updates the `UiText`s bound with a `UiBind`.
+///
+/// It's automatically registered with the `UiBundle`, as a thread local function since the
+/// resources and components read by the bindings are only known at runtime.
+pub fn ui_bind_tick(world: &mut World, resources: &mut Resources) {
+    #[cfg(feature = "profiler")]
+    profile_scope!("ui_bind_tick");
+
+    let binds: Vec<(Entity, UiBind)> = <(Entity, &UiBind)>::query()
+        .iter(world)
+        .map(|(entity, bind)| (*entity, bind.clone()))
+        .collect();
+    let texts: Vec<(Entity, String)> = binds
+        .into_iter()
+        .filter_map(|(entity, bind)| bind.text(world, resources).map(|text| (entity, text)))
+        .collect();
+
+    let mut query = <&mut UiText>::query();
+    for (entity, text) in texts {
+        if let Ok(ui_text) = query.get_mut(world, entity) {
+            if ui_text.text != text {
+                ui_text.text = text;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Anchor, LineMode};
+
+    #[derive(Debug)]
+    struct Health(u32);
+
+    impl Display for Health {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    #[test]
+    fn bound_texts_follow_values() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let player = world.push((Health(10),));
+        let text =
+            || UiText::new(None, String::new(), [1.; 4], 16., LineMode::Single, Anchor::Middle);
+        let label = world.push((text(), UiBind::component_format::<Health>(player, "HP: {}")));
+        let score = world.push((
+            text(),
+            UiBind::resource(|score: &u32| format!("Score: {}", score)),
+        ));
+
+        ui_bind_tick(&mut world, &mut resources);
+        resources.insert(42_u32);
+        ui_bind_tick(&mut world, &mut resources);
+
+        let mut query = <&UiText>::query();
+        assert_eq!("HP: 10", query.get(&world, label).unwrap().text);
+        assert_eq!("Score: 42", query.get(&world, score).unwrap().text);
+    }
+}
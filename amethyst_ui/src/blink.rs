@@ -1,12 +1,19 @@
-//! Module for the Blink component and `BlinkSystem`.
+//! Module for the `Blink` and `Pulse` components, and their systems.
+
+use std::f32::consts::TAU;
 
 use amethyst_core::{
     ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder, Write},
-    Hidden, Time,
+    math::Vector3,
+    shrev::EventChannel,
+    Hidden, Time, Transform,
 };
+use amethyst_rendy::{palette::Srgba, resources::Tint};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
+use crate::{UiAccessibility, UiTransform};
+
 /// # Blink Component
 /// Periodically adds and removes a `Hidden` Component on the entity this is attached to.
 ///
@@ -16,6 +23,8 @@ use thread_profiler::profile_scope;
 ///
 /// During the second half period, the entity is invisible.
 /// [delay/2, delay]
+///
+/// While [`UiAccessibility::reduced_motion`] is enabled, the entity stays visible.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Blink {
     /// Period of a full blink cycle.
@@ -35,44 +44,307 @@ impl System for BlinkSystem {
         Box::new(
             SystemBuilder::new("BlinkSystem")
                 .read_resource::<Time>()
+                .read_resource::<UiAccessibility>()
                 .with_query(<&mut Hidden>::query())
                 .with_query(<(Entity, Write<Blink>)>::query())
-                .build(move |commands, world, time, (hiddens, blinks)| {
-                    #[cfg(feature = "profiler")]
-                    profile_scope!("blink_system");
+                .build(
+                    move |commands, world, (time, accessibility), (hiddens, blinks)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("blink_system");
 
-                    let abs_sec = time.delta_time().as_secs_f32();
-                    let abs_unscaled_sec = time.delta_real_time().as_secs_f32();
+                        let abs_sec = time.delta_time().as_secs_f32();
+                        let abs_unscaled_sec = time.delta_real_time().as_secs_f32();
 
-                    let (mut blinks_world, mut subworld) = world.split_for_query(blinks);
+                        let (mut blinks_world, mut subworld) = world.split_for_query(blinks);
 
-                    blinks.for_each_mut(&mut blinks_world, |(entity, mut blink)| {
-                        if blink.absolute_time {
-                            blink.timer += abs_unscaled_sec;
-                        } else {
-                            blink.timer += abs_sec;
-                        }
+                        blinks.for_each_mut(&mut blinks_world, |(entity, mut blink)| {
+                            if blink.absolute_time {
+                                blink.timer += abs_unscaled_sec;
+                            } else {
+                                blink.timer += abs_sec;
+                            }
 
-                        // Reset timer because we ended the last cycle.
-                        // Keeps the overflow time.
-                        if blink.timer > blink.delay {
-                            blink.timer -= blink.delay;
-                        }
+                            // Reset timer because we ended the last cycle.
+                            // Keeps the overflow time.
+                            if blink.timer > blink.delay {
+                                blink.timer -= blink.delay;
+                            }
+
+                            // We could cache the division, but that would require a stricter api on Blink.
+                            let on =
+                                !accessibility.reduced_motion && blink.timer < blink.delay / 2.0;
+
+                            match (on, hiddens.get_mut(&mut subworld, *entity).is_ok()) {
+                                (true, false) => {
+                                    commands.add_component(*entity, Hidden);
+                                }
+                                (false, true) => {
+                                    commands.remove_component::<Hidden>(*entity);
+                                }
+                                _ => {}
+                            };
+                        });
+                    },
+                ),
+        )
+    }
+}
+
+/// Effect of a `Pulse`, at its strongest halfway through each cycle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PulseEffect {
+    /// Blends the `Tint` of the entity towards an sRGBA color, e.g. red for damage feedback.
+    ColorFlash([f32; 4]),
+    /// Fades the `Tint` of the entity down to a proportion of its alpha.
+    AlphaPulse {
+        /// Proportion of the alpha left at the strongest.
+        min_alpha: f32,
+    },
+    /// Scales the `UiTransform` size of ui elements, or the `Transform` of other entities, e.g.
+    /// for notification badges.
+    ScalePulse {
+        /// Scale at the strongest.
+        scale: f32,
+    },
+}
+
+/// # Pulse Component
+/// Periodically applies a `PulseEffect` to the ui element or sprite this is attached to, a
+/// smooth generalization of `Blink`.
+///
+/// Pulses with a `count` stop after that many cycles: the entity gets back to how it was before
+/// the pulse, the component is removed and a `PulseFinished` event is sent. Pulses without a
+/// `count` go on until the component is removed.
+///
+/// While [`UiAccessibility::reduced_motion`] is enabled, the effect isn't shown, but the cycles
+/// still run and finish.
+///
+/// ```
+/// # use amethyst::ui::{Pulse, PulseEffect};
+/// let damage_flash = Pulse::new(PulseEffect::ColorFlash([1., 0., 0., 1.]), 0.2).with_count(3);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pulse {
+    /// Effect applied.
+    pub effect: PulseEffect,
+    /// Period of a full pulse cycle, in seconds.
+    pub period: f32,
+    /// Number of cycles before the pulse stops, or `None` to go on.
+    pub count: Option<u32>,
+    /// Whether to use the scaled or unscaled time.
+    pub absolute_time: bool,
+    timer: f32,
+    cycles: u32,
+    base: Option<PulseBase>,
+}
+
+/// State of the entity before the pulse, which the effect is applied to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct PulseBase {
+    tint: [f32; 4],
+    scale: Vector3<f32>,
+    size: (f32, f32),
+}
+
+impl Pulse {
+    /// Creates a pulse repeating `effect` every `period` seconds.
+    #[must_use]
+    pub fn new(effect: PulseEffect, period: f32) -> Self {
+        Self {
+            effect,
+            period,
+            count: None,
+            absolute_time: false,
+            timer: 0.0,
+            cycles: 0,
+            base: None,
+        }
+    }
+
+    /// Stops the pulse after `count` cycles.
+    #[must_use]
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Runs the pulse with the unscaled time, e.g. while the game is paused.
+    #[must_use]
+    pub fn with_absolute_time(mut self) -> Self {
+        self.absolute_time = true;
+        self
+    }
+
+    /// Returns whether all the cycles of the pulse are done.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.count.map_or(false, |count| self.cycles >= count)
+    }
+
+    /// Returns how strongly the effect is applied, from `0.0` at the start of a cycle to `1.0`
+    /// halfway through it.
+    #[must_use]
+    pub fn intensity(&self) -> f32 {
+        if self.is_finished() || self.period <= 0.0 {
+            0.0
+        } else {
+            0.5 - 0.5 * (TAU * self.timer / self.period).cos()
+        }
+    }
+
+    /// Advances the pulse by `delta` seconds.
+    fn advance(&mut self, delta: f32) {
+        if self.is_finished() {
+            return;
+        }
+        if self.period <= 0.0 {
+            self.cycles = self.count.unwrap_or(0);
+            return;
+        }
+        self.timer += delta;
+        while self.timer >= self.period && !self.is_finished() {
+            self.timer -= self.period;
+            self.cycles += 1;
+        }
+    }
+}
 
-                        // We could cache the division, but that would require a stricter api on Blink.
-                        let on = blink.timer < blink.delay / 2.0;
+/// Event sent when a `Pulse` with a `count` is finished.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PulseFinished {
+    /// Entity the pulse was attached to.
+    pub entity: Entity,
+}
+
+/// System updating the `Pulse` components.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug)]
+pub struct PulseSystem;
 
-                        match (on, hiddens.get_mut(&mut subworld, *entity).is_ok()) {
-                            (true, false) => {
-                                commands.add_component(*entity, Hidden);
+impl System for PulseSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("PulseSystem")
+                .read_resource::<Time>()
+                .read_resource::<UiAccessibility>()
+                .write_resource::<EventChannel<PulseFinished>>()
+                .with_query(<(
+                    Entity,
+                    &mut Pulse,
+                    Option<&mut Tint>,
+                    Option<&mut Transform>,
+                    Option<&mut UiTransform>,
+                )>::query())
+                .build(
+                    move |commands, world, (time, accessibility, finished), pulses| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("pulse_system");
+
+                        let abs_sec = time.delta_time().as_secs_f32();
+                        let abs_unscaled_sec = time.delta_real_time().as_secs_f32();
+
+                        for (entity, pulse, tint, transform, ui_transform) in
+                            pulses.iter_mut(world)
+                        {
+                            let uses_tint = match pulse.effect {
+                                PulseEffect::ColorFlash(_) | PulseEffect::AlphaPulse { .. } => true,
+                                PulseEffect::ScalePulse { .. } => false,
+                            };
+                            if uses_tint && tint.is_none() {
+                                commands.add_component(*entity, Tint(Srgba::new(1., 1., 1., 1.)));
+                                continue;
+                            }
+
+                            let base = *pulse.base.get_or_insert_with(|| {
+                                PulseBase {
+                                    tint: tint.as_ref().map_or([1.; 4], |tint| {
+                                        let (r, g, b, a) = tint.0.into_components();
+                                        [r, g, b, a]
+                                    }),
+                                    scale: transform
+                                        .as_ref()
+                                        .map_or_else(|| Vector3::repeat(1.0), |t| *t.scale()),
+                                    size: ui_transform
+                                        .as_ref()
+                                        .map_or((0.0, 0.0), |t| (t.width, t.height)),
+                                }
+                            });
+
+                            pulse.advance(if pulse.absolute_time {
+                                abs_unscaled_sec
+                            } else {
+                                abs_sec
+                            });
+                            let intensity = if accessibility.reduced_motion {
+                                0.0
+                            } else {
+                                pulse.intensity()
+                            };
+
+                            match pulse.effect {
+                                PulseEffect::ColorFlash(color) => {
+                                    if let Some(tint) = tint {
+                                        let mixed = mix(base.tint, color, intensity);
+                                        tint.0 = Srgba::new(mixed[0], mixed[1], mixed[2], mixed[3]);
+                                    }
+                                }
+                                PulseEffect::AlphaPulse { min_alpha } => {
+                                    if let Some(tint) = tint {
+                                        let [r, g, b, a] = base.tint;
+                                        let alpha = a * (1.0 + (min_alpha - 1.0) * intensity);
+                                        tint.0 = Srgba::new(r, g, b, alpha);
+                                    }
+                                }
+                                PulseEffect::ScalePulse { scale } => {
+                                    let factor = 1.0 + (scale - 1.0) * intensity;
+                                    if let Some(ui_transform) = ui_transform {
+                                        ui_transform.width = base.size.0 * factor;
+                                        ui_transform.height = base.size.1 * factor;
+                                    } else if let Some(transform) = transform {
+                                        transform.set_scale(base.scale * factor);
+                                    }
+                                }
                             }
-                            (false, true) => {
-                                commands.remove_component::<Hidden>(*entity);
+
+                            if pulse.is_finished() {
+                                commands.remove_component::<Pulse>(*entity);
+                                finished.single_write(PulseFinished { entity: *entity });
                             }
-                            _ => {}
-                        };
-                    });
-                }),
+                        }
+                    },
+                ),
         )
     }
 }
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counted_pulse_finishes_back_at_rest() {
+        let mut pulse = Pulse::new(PulseEffect::AlphaPulse { min_alpha: 0.0 }, 1.0).with_count(2);
+        pulse.advance(0.5);
+        assert!((pulse.intensity() - 1.0).abs() < 1e-6);
+        pulse.advance(1.0);
+        assert!(!pulse.is_finished());
+        pulse.advance(0.75);
+        assert!(pulse.is_finished());
+        assert_eq!(0.0, pulse.intensity());
+
+        let mut endless = Pulse::new(PulseEffect::ScalePulse { scale: 2.0 }, 1.0);
+        endless.advance(100.25);
+        assert!(!endless.is_finished());
+        assert!((endless.intensity() - 0.5).abs() < 1e-3);
+    }
+}
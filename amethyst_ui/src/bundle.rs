@@ -8,24 +8,35 @@ use amethyst_core::{
     shrev::EventChannel,
 };
 use amethyst_error::Error;
-use amethyst_rendy::types::DefaultBackend;
 use derive_new::new;
 use winit::event::Event;
 
 use crate::{
+    bind::ui_bind_tick,
     button::{ui_button_action_retrigger_event_system, UiButtonSystem},
+    canvas::UiCanvasSystem,
+    creator::ui_widget_spawning_tick,
     drag::DragWidgetSystem,
     event::UiMouseSystem,
-    glyphs::{GlyphTextureData, GlyphTextureProcessorSystem},
+    glyphs::GlyphTextureData,
     layout::UiTransformSystem,
+    modal::UiModalSystem,
+    navigation::UiNavigationSystem,
+    progress_bar::UiProgressBarSystem,
     resize::ResizeSystem,
+    scroll::UiScrollSystem,
     selection::{SelectionKeyboardSystem, SelectionMouseSystem},
     selection_order_cache::CacheSelectionSystem,
+    slider::UiSliderSystem,
     sound::{ui_sound_event_retrigger_system, UiSoundSystem},
+    tabs::UiTabSystem,
     text::TextEditingMouseSystem,
     text_editing::TextEditingInputSystem,
-    BlinkSystem, CachedSelectionOrderResource, UiButtonAction, UiEvent, UiLabel, UiPlaySoundAction,
-    WidgetId, Widgets,
+    toggle::{ui_toggle_retrigger_event_system, UiToggleAction, UiToggleSystem},
+    virtual_keyboard::UiVirtualKeyboardSystem,
+    BlinkSystem, CachedSelectionOrderResource, PulseFinished, PulseSystem, UiAccessibility,
+    UiAccessibilitySystem, UiButtonAction, UiEvent, UiLabel, UiModals, UiNavigation,
+    UiPlaySoundAction, WidgetId, Widgets,
 };
 
 /// UI bundle
@@ -55,11 +66,15 @@ where
         log::debug!("Adding UI Resources");
         resources.insert(EventChannel::<UiButtonAction>::new());
         resources.insert(EventChannel::<UiEvent>::new());
+        resources.insert(EventChannel::<UiToggleAction>::new());
+        resources.insert(EventChannel::<PulseFinished>::new());
         resources.insert(Widgets::<UiLabel, W>::new());
         resources.insert(CachedSelectionOrderResource::default());
+        resources.insert(UiAccessibility::default());
+        resources.insert(UiModals::default());
+        resources.get_or_insert_with(UiNavigation::default);
 
         resources.insert(ProcessingQueue::<GlyphTextureData>::default());
-        builder.add_system(GlyphTextureProcessorSystem::<DefaultBackend>::default());
 
         log::debug!("Creating UI EventChannel Readers");
         let ui_btn_reader = resources
@@ -94,23 +109,61 @@ where
             .get_mut::<EventChannel<UiEvent>>()
             .unwrap()
             .register_reader();
+        let slider_ui_reader = resources
+            .get_mut::<EventChannel<UiEvent>>()
+            .unwrap()
+            .register_reader();
+        let slider_window_reader = resources
+            .get_mut::<EventChannel<Event<'static, ()>>>()
+            .unwrap()
+            .register_reader();
+        let toggle_retrigger_reader = resources
+            .get_mut::<EventChannel<UiEvent>>()
+            .unwrap()
+            .register_reader();
+        let tabs_reader = resources
+            .get_mut::<EventChannel<UiEvent>>()
+            .unwrap()
+            .register_reader();
+        let virtual_keyboard_reader = resources
+            .get_mut::<EventChannel<UiEvent>>()
+            .unwrap()
+            .register_reader();
+        let toggle_reader = resources
+            .get_mut::<EventChannel<UiToggleAction>>()
+            .unwrap()
+            .register_reader();
 
         log::debug!("Adding UI Systems to Dispatcher");
         builder
             .add_system(UiTransformSystem::new())
+            .add_system(UiModalSystem)
             .add_system(UiMouseSystem::new())
             .add_system(UiButtonSystem::new(ui_btn_reader))
             .add_system(ui_button_action_retrigger_event_system(
                 ui_btn_action_retrigger_reader,
             ))
+            .add_system(ui_toggle_retrigger_event_system(toggle_retrigger_reader))
+            .add_system(UiToggleSystem::new(toggle_reader))
+            .add_system(UiTabSystem::new(tabs_reader))
             .add_system(CacheSelectionSystem::<G>::new())
             .add_system(TextEditingMouseSystem::new(text_editing_mouse_reader))
             .add_system(SelectionMouseSystem::<G>::new(selection_mouse_reader))
             .add_system(SelectionKeyboardSystem::<G>::new(selection_keyboard_reader))
+            .add_system(UiNavigationSystem::<G>::new())
             .add_system(TextEditingInputSystem::new(text_editing_input_reader))
+            .add_system(UiVirtualKeyboardSystem::new(virtual_keyboard_reader))
             .add_system(ResizeSystem::new())
             .add_system(DragWidgetSystem::new(drag_widget_reader))
-            .add_system(BlinkSystem);
+            .add_system(UiScrollSystem::new())
+            .add_system(UiSliderSystem::new(slider_ui_reader, slider_window_reader))
+            .add_system(UiProgressBarSystem)
+            .add_system(UiCanvasSystem)
+            .add_system(BlinkSystem)
+            .add_system(PulseSystem)
+            .add_system(UiAccessibilitySystem);
+        builder.add_thread_local_fn(ui_bind_tick);
+        builder.add_thread_local_fn(ui_widget_spawning_tick::<G>);
 
         Ok(())
     }
@@ -15,7 +15,7 @@ use crate::{
     Anchor, FontAsset, Interactable, LineMode, Selectable, Stretch, UiButton, UiButtonAction,
     UiButtonActionRetrigger,
     UiButtonActionType::{self, SetImage, SetTextColor, UnsetTextColor, UnsetTexture},
-    UiImage, UiPlaySoundAction, UiSoundRetrigger, UiText, UiTransform, WidgetId, Widgets,
+    UiImage, UiPlaySoundAction, UiSoundRetrigger, UiText, UiTheme, UiTransform, WidgetId, Widgets,
 };
 
 const DEFAULT_Z: f32 = 1.0;
@@ -24,6 +24,7 @@ const DEFAULT_HEIGHT: f32 = 64.0;
 const DEFAULT_TAB_ORDER: u32 = 9;
 const DEFAULT_BKGD_COLOR: [f32; 4] = [0.82, 0.83, 0.83, 1.0];
 const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const DEFAULT_FONT_SIZE: f32 = 32.;
 
 /// Convenience structure for building a button
 /// Note that since there can only be one `ui_loader` in use, and `WidgetId` of the `UiBundle` and
@@ -41,9 +42,10 @@ pub struct UiButtonBuilder<G, I: WidgetId> {
     anchor: Anchor,
     stretch: Stretch,
     text: String,
-    text_color: [f32; 4],
+    text_color: Option<[f32; 4]>,
     font: Option<Handle<FontAsset>>,
-    font_size: f32,
+    font_size: Option<f32>,
+    class: String,
     line_mode: LineMode,
     align: Anchor,
     image: Option<UiImage>,
@@ -76,9 +78,10 @@ where
             anchor: Anchor::TopLeft,
             stretch: Stretch::NoStretch,
             text: "".to_string(),
-            text_color: DEFAULT_TXT_COLOR,
+            text_color: None,
             font: None,
-            font_size: 32.,
+            font_size: None,
+            class: "button".to_string(),
             line_mode: LineMode::Single,
             align: Anchor::Middle,
             image: None,
@@ -188,13 +191,19 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiButtonBuilder<G, I
 
     /// Set font size
     pub fn with_font_size(mut self, size: f32) -> Self {
-        self.font_size = size;
+        self.font_size = Some(size);
         self
     }
 
     /// Set text color
     pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
-        self.text_color = text_color;
+        self.text_color = Some(text_color);
+        self
+    }
+
+    /// Set the widget class styling the button in the `UiTheme`, `"button"` by default.
+    pub fn with_class<S: ToString>(mut self, class: &S) -> Self {
+        self.class = class.to_string();
         self
     }
 
@@ -358,21 +367,22 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiButtonBuilder<G, I
         let loader = resources
             .get::<DefaultLoader>()
             .expect("Could not get Loader resource");
+        let style = resources
+            .get::<UiTheme>()
+            .map(|theme| theme.style(&self.class))
+            .unwrap_or_default();
         let image = self.image.unwrap_or_else(|| {
+            let color = style.background_color.unwrap_or(DEFAULT_BKGD_COLOR);
             UiImage::Texture(
                 loader.load_from_data(
-                    load_from_srgba(Srgba::new(
-                        DEFAULT_BKGD_COLOR[0],
-                        DEFAULT_BKGD_COLOR[1],
-                        DEFAULT_BKGD_COLOR[2],
-                        DEFAULT_BKGD_COLOR[3],
-                    ))
-                    .into(),
+                    load_from_srgba(Srgba::new(color[0], color[1], color[2], color[3])).into(),
                     (),
                     &asset_storage,
                 ),
             )
         });
+        let font = self.font.or_else(|| style.load_font(&loader));
+        let (x_margin, y_margin) = style.padding.unwrap_or((0., 0.));
 
         world
             .entry(image_entity)
@@ -406,8 +416,8 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiButtonBuilder<G, I
                 )
                 .into_transparent()
                 .with_stretch(Stretch::XY {
-                    x_margin: 0.,
-                    y_margin: 0.,
+                    x_margin,
+                    y_margin,
                     keep_aspect_ratio: false,
                 }),
             );
@@ -415,10 +425,10 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiButtonBuilder<G, I
             .entry(text_entity)
             .expect("Unreachable: Inserting newly created entity")
             .add_component(UiText::new(
-                self.font,
+                font,
                 self.text,
-                self.text_color,
-                self.font_size,
+                self.text_color.or(style.text_color).unwrap_or(DEFAULT_TXT_COLOR),
+                self.font_size.or(style.font_size).unwrap_or(DEFAULT_FONT_SIZE),
                 self.line_mode,
                 self.align,
             ));
@@ -0,0 +1,384 @@
+//! Canvas drawing shapes, images and texts recorded by the game, e.g. for minimaps and graphs.
+
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    transform::{Parent, Transform},
+    HiddenPropagate,
+};
+use amethyst_rendy::Texture;
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{Anchor, FontAsset, LineMode, UiText, UiTransform};
+
+/// Maximum number of quads a line is drawn with.
+const MAX_LINE_QUADS: usize = 1024;
+
+/// A drawing command of a `UiCanvas`.
+///
+/// Positions and sizes are in pixels from the bottom left corner of the canvas, and colors are
+/// sRGB, like the colors of `UiText`s.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiDrawCommand {
+    /// A filled rectangle.
+    Rect {
+        /// Bottom left corner.
+        position: (f32, f32),
+        /// Width and height.
+        size: (f32, f32),
+        /// Color of the rectangle.
+        color: [f32; 4],
+    },
+    /// A line segment.
+    Line {
+        /// Start of the line.
+        from: (f32, f32),
+        /// End of the line.
+        to: (f32, f32),
+        /// Thickness of the line.
+        width: f32,
+        /// Color of the line.
+        color: [f32; 4],
+    },
+    /// A textured rectangle.
+    Image {
+        /// Bottom left corner.
+        position: (f32, f32),
+        /// Width and height.
+        size: (f32, f32),
+        /// The texture.
+        texture: Handle<Texture>,
+        /// Texture coordinates of the part drawn, as `[left, top, right, bottom]`.
+        tex_coords: [f32; 4],
+        /// Color multiplied with the texture.
+        tint: [f32; 4],
+    },
+    /// A single line of text.
+    Text {
+        /// Point at the left end of the text, halfway up.
+        position: (f32, f32),
+        /// The text.
+        text: String,
+        /// Font size of the text.
+        font_size: f32,
+        /// Color of the text.
+        color: [f32; 4],
+    },
+}
+
+/// Component of a ui element drawing the shapes, images and texts recorded into it, for the
+/// graphics that don't fit the other widgets.
+///
+/// The commands are kept until `clear` is called, so a system redrawing the canvas every frame
+/// clears it first. Shapes and images are drawn by the `DrawUi` pass over the `UiImage` of the
+/// element, clipped to its `UiTransform`, and texts are shown by child entities managed by the
+/// `UiCanvasSystem`, drawn above the shapes.
+///
+/// ```
+/// # use amethyst::ui::UiCanvas;
+/// let mut canvas = UiCanvas::new();
+/// canvas.clear();
+/// canvas.rect((0., 0.), (100., 10.), [0.2, 0.2, 0.2, 1.]);
+/// canvas.rect((0., 0.), (75., 10.), [0.8, 0.1, 0.1, 1.]);
+/// canvas.text((4., 5.), "75 / 100", 8., [1., 1., 1., 1.]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UiCanvas {
+    commands: Vec<UiDrawCommand>,
+    /// Font of the texts, or the default font.
+    pub font: Option<Handle<FontAsset>>,
+    labels: Vec<Entity>,
+    shown_labels: usize,
+}
+
+impl UiCanvas {
+    /// Creates an empty canvas.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded commands, in drawing order.
+    #[must_use]
+    pub fn commands(&self) -> &[UiDrawCommand] {
+        &self.commands
+    }
+
+    /// Removes all the commands.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Records a command.
+    pub fn draw(&mut self, command: UiDrawCommand) {
+        self.commands.push(command);
+    }
+
+    /// Draws a filled rectangle of given bottom left corner and size.
+    pub fn rect(&mut self, position: (f32, f32), size: (f32, f32), color: [f32; 4]) {
+        self.draw(UiDrawCommand::Rect {
+            position,
+            size,
+            color,
+        });
+    }
+
+    /// Draws a line segment of given width.
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: [f32; 4]) {
+        self.draw(UiDrawCommand::Line {
+            from,
+            to,
+            width,
+            color,
+        });
+    }
+
+    /// Draws connected line segments through `points`, e.g. a graph.
+    pub fn polyline(&mut self, points: &[(f32, f32)], width: f32, color: [f32; 4]) {
+        for segment in points.windows(2) {
+            self.line(segment[0], segment[1], width, color);
+        }
+    }
+
+    /// Draws a whole texture in a rectangle of given bottom left corner and size.
+    pub fn image(&mut self, position: (f32, f32), size: (f32, f32), texture: Handle<Texture>) {
+        self.draw(UiDrawCommand::Image {
+            position,
+            size,
+            texture,
+            tex_coords: [0., 0., 1., 1.],
+            tint: [1., 1., 1., 1.],
+        });
+    }
+
+    /// Draws a line of text starting at `position`.
+    pub fn text(
+        &mut self,
+        position: (f32, f32),
+        text: impl Into<String>,
+        font_size: f32,
+        color: [f32; 4],
+    ) {
+        self.draw(UiDrawCommand::Text {
+            position,
+            text: text.into(),
+            font_size,
+            color,
+        });
+    }
+
+    /// Returns the quads drawing the shapes and images, for a canvas whose bottom left corner is
+    /// at `origin` in UI coordinates.
+    pub(crate) fn quads(&self, origin: (f32, f32)) -> Vec<CanvasQuad<'_>> {
+        let mut quads = Vec::new();
+        for command in &self.commands {
+            match command {
+                UiDrawCommand::Rect {
+                    position,
+                    size,
+                    color,
+                } => quads.push(CanvasQuad {
+                    center: (
+                        origin.0 + position.0 + size.0 / 2.,
+                        origin.1 + position.1 + size.1 / 2.,
+                    ),
+                    size: *size,
+                    color: *color,
+                    texture: None,
+                }),
+                UiDrawCommand::Line {
+                    from,
+                    to,
+                    width,
+                    color,
+                } => {
+                    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+                    if dx == 0.0 || dy == 0.0 {
+                        quads.push(CanvasQuad {
+                            center: (
+                                origin.0 + (from.0 + to.0) / 2.,
+                                origin.1 + (from.1 + to.1) / 2.,
+                            ),
+                            size: (dx.abs().max(*width), dy.abs().max(*width)),
+                            color: *color,
+                            texture: None,
+                        });
+                        continue;
+                    }
+                    // Slanted lines are drawn with overlapping squares along them.
+                    let length = (dx * dx + dy * dy).sqrt();
+                    let steps = ((length / (width * 0.5)).ceil() as usize).clamp(1, MAX_LINE_QUADS);
+                    for step in 0..=steps {
+                        let t = step as f32 / steps as f32;
+                        quads.push(CanvasQuad {
+                            center: (origin.0 + from.0 + dx * t, origin.1 + from.1 + dy * t),
+                            size: (*width, *width),
+                            color: *color,
+                            texture: None,
+                        });
+                    }
+                }
+                UiDrawCommand::Image {
+                    position,
+                    size,
+                    texture,
+                    tex_coords,
+                    tint,
+                } => quads.push(CanvasQuad {
+                    center: (
+                        origin.0 + position.0 + size.0 / 2.,
+                        origin.1 + position.1 + size.1 / 2.,
+                    ),
+                    size: *size,
+                    color: *tint,
+                    texture: Some((texture, *tex_coords)),
+                }),
+                UiDrawCommand::Text { .. } => {}
+            }
+        }
+        quads
+    }
+}
+
+/// A quad drawn by a `UiCanvas`, in UI coordinates.
+#[derive(Debug)]
+pub(crate) struct CanvasQuad<'a> {
+    pub(crate) center: (f32, f32),
+    pub(crate) size: (f32, f32),
+    /// sRGB color.
+    pub(crate) color: [f32; 4],
+    /// Texture and texture coordinates, or `None` for a solid color.
+    pub(crate) texture: Option<(&'a Handle<Texture>, [f32; 4])>,
+}
+
+/// System showing the texts of the `UiCanvas`es, with child entities reused from frame to frame.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug)]
+pub struct UiCanvasSystem;
+
+impl System for UiCanvasSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiCanvasSystem")
+                .with_query(<(Entity, &mut UiCanvas, &UiTransform)>::query())
+                .with_query(<(&mut UiText, &mut UiTransform)>::query())
+                .build(|commands, world, _, (canvases, labels)| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("ui_canvas_system");
+
+                    let mut updates = Vec::new();
+                    for (canvas_entity, canvas, transform) in canvases.iter_mut(world) {
+                        let texts: Vec<_> = canvas
+                            .commands
+                            .iter()
+                            .filter_map(|command| match command {
+                                UiDrawCommand::Text {
+                                    position,
+                                    text,
+                                    font_size,
+                                    color,
+                                } => Some((*position, text.clone(), *font_size, *color)),
+                                _ => None,
+                            })
+                            .collect();
+                        let shown_labels = texts.len();
+
+                        for index in texts.len()..canvas.shown_labels.min(canvas.labels.len()) {
+                            commands.add_component(canvas.labels[index], HiddenPropagate::new());
+                        }
+                        for (index, (position, text, font_size, color)) in
+                            texts.into_iter().enumerate()
+                        {
+                            let label_transform = UiTransform::new(
+                                format!("{}_canvas_text_{}", transform.id, index),
+                                Anchor::BottomLeft,
+                                Anchor::MiddleLeft,
+                                position.0,
+                                position.1,
+                                1.,
+                                transform.width.max(1.),
+                                font_size * 1.5,
+                            )
+                            .into_transparent();
+                            let label_text = UiText::new(
+                                canvas.font.clone(),
+                                text,
+                                color,
+                                font_size,
+                                LineMode::Single,
+                                Anchor::MiddleLeft,
+                            );
+                            match canvas.labels.get(index) {
+                                Some(label) => {
+                                    if index >= canvas.shown_labels {
+                                        commands.remove_component::<HiddenPropagate>(*label);
+                                    }
+                                    updates.push((*label, label_text, label_transform));
+                                }
+                                None => {
+                                    let label = commands.push((
+                                        label_transform,
+                                        label_text,
+                                        Transform::default(),
+                                        Parent(*canvas_entity),
+                                    ));
+                                    canvas.labels.push(label);
+                                }
+                            }
+                        }
+                        canvas.shown_labels = shown_labels;
+                    }
+
+                    for (label, new_text, new_transform) in updates {
+                        if let Ok((text, transform)) = labels.get_mut(world, label) {
+                            if text.text != new_text.text
+                                || text.font_size != new_text.font_size
+                                || text.color != new_text.color
+                                || text.font != new_text.font
+                            {
+                                text.text = new_text.text;
+                                text.font_size = new_text.font_size;
+                                text.color = new_text.color;
+                                text.font = new_text.font;
+                            }
+                            if transform.local_x != new_transform.local_x
+                                || transform.local_y != new_transform.local_y
+                                || transform.width != new_transform.width
+                                || transform.height != new_transform.height
+                            {
+                                transform.local_x = new_transform.local_x;
+                                transform.local_y = new_transform.local_y;
+                                transform.width = new_transform.width;
+                                transform.height = new_transform.height;
+                            }
+                        }
+                    }
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_are_drawn_with_quads_from_the_origin() {
+        let mut canvas = UiCanvas::new();
+        canvas.rect((10., 20.), (30., 40.), [1.; 4]);
+        canvas.line((0., 0.), (0., 10.), 2., [1.; 4]);
+        canvas.line((0., 0.), (4., 4.), 2., [1.; 4]);
+        canvas.text((0., 0.), "ignored", 10., [1.; 4]);
+
+        let quads = canvas.quads((100., 100.));
+        assert_eq!((125., 140.), quads[0].center);
+        assert_eq!((30., 40.), quads[0].size);
+        assert_eq!((100., 105.), quads[1].center);
+        assert_eq!((2., 10.), quads[1].size);
+        // The slanted line is 5.66 long, so it's covered by 7 squares of 2 pixels.
+        assert_eq!(2 + 7, quads.len());
+        assert_eq!((104., 104.), quads[8].center);
+    }
+}
@@ -0,0 +1,110 @@
+//! Conversions between window, UI and world coordinates.
+//!
+//! The window coordinates are physical pixels from the top left corner of the window, like the
+//! mouse position of the `InputHandler` and the screen positions of the `Camera`. The UI
+//! coordinates are the pixels from the bottom left corner used by the `UiTransform`s, with y going
+//! up.
+
+use amethyst_core::{
+    geometry::Ray,
+    math::{Point2, Point3},
+    Transform,
+};
+use amethyst_input::InputHandler;
+use amethyst_rendy::Camera;
+use amethyst_window::ScreenDimensions;
+
+use crate::UiTransform;
+
+/// Converts a position in the window to UI coordinates.
+#[must_use]
+pub fn window_to_ui(position: (f32, f32), screen_dimensions: &ScreenDimensions) -> (f32, f32) {
+    (position.0, screen_dimensions.height() - position.1)
+}
+
+/// Converts a position in UI coordinates to a position in the window.
+#[must_use]
+pub fn ui_to_window(position: (f32, f32), screen_dimensions: &ScreenDimensions) -> (f32, f32) {
+    (position.0, screen_dimensions.height() - position.1)
+}
+
+/// Returns the position of the mouse in UI coordinates, if it's in the window.
+#[must_use]
+pub fn mouse_ui_position(
+    input: &InputHandler,
+    screen_dimensions: &ScreenDimensions,
+) -> Option<(f32, f32)> {
+    input
+        .mouse_position()
+        .map(|position| window_to_ui(position, screen_dimensions))
+}
+
+/// Converts a position in UI coordinates to a position relative to the center of `transform`,
+/// e.g. to find where a custom widget was clicked.
+#[must_use]
+pub fn ui_to_local(position: (f32, f32), transform: &UiTransform) -> (f32, f32) {
+    (position.0 - transform.pixel_x, position.1 - transform.pixel_y)
+}
+
+/// Returns the ray going out of `camera` through a position in UI coordinates.
+#[must_use]
+pub fn ui_to_world_ray(
+    position: (f32, f32),
+    camera: &Camera,
+    camera_transform: &Transform,
+    screen_dimensions: &ScreenDimensions,
+) -> Ray<f32> {
+    let (x, y) = ui_to_window(position, screen_dimensions);
+    camera.screen_ray(
+        Point2::new(x, y),
+        screen_dimensions.diagonal(),
+        camera_transform,
+    )
+}
+
+/// Returns the world point at `distance` from `camera` under a position in UI coordinates.
+#[must_use]
+pub fn ui_to_world(
+    position: (f32, f32),
+    distance: f32,
+    camera: &Camera,
+    camera_transform: &Transform,
+    screen_dimensions: &ScreenDimensions,
+) -> Point3<f32> {
+    ui_to_world_ray(position, camera, camera_transform, screen_dimensions).at_distance(distance)
+}
+
+/// Converts a world position to UI coordinates, through `camera`, e.g. to show a label over an
+/// entity.
+#[must_use]
+pub fn world_to_ui(
+    position: Point3<f32>,
+    camera: &Camera,
+    camera_transform: &Transform,
+    screen_dimensions: &ScreenDimensions,
+) -> (f32, f32) {
+    let window = camera.world_to_screen(position, screen_dimensions.diagonal(), camera_transform);
+    window_to_ui((window.x, window.y), screen_dimensions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_and_ui_y_axes_are_opposite() {
+        let screen_dimensions = ScreenDimensions::new(800, 600);
+        assert_eq!((10.0, 590.0), window_to_ui((10.0, 10.0), &screen_dimensions));
+        assert_eq!(
+            (10.0, 10.0),
+            ui_to_window(window_to_ui((10.0, 10.0), &screen_dimensions), &screen_dimensions)
+        );
+
+        let transform = UiTransform {
+            pixel_x: 100.0,
+            pixel_y: 50.0,
+            ..UiTransform::default()
+        };
+        assert_eq!((-90.0, 540.0), ui_to_local((10.0, 590.0), &transform));
+    }
+}
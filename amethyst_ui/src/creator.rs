@@ -0,0 +1,1007 @@
+//! Widget trees loaded from RON files.
+//!
+//! A layout file describes a tree of [`UiWidget`]s, tagged with the uuid of the `UiWidget` type:
+//!
+//! ```ron
+//! #![enable(implicit_some)]
+//! {
+//!     "873217bb-4af7-411c-912f-de4ab712a296":
+//!     Container(
+//!         transform: (id: "menu", anchor: Middle, width: 400., height: 300.),
+//!         background: SolidColor(0.03, 0.03, 0.03, 1.0),
+//!         children: [
+//!             Label(
+//!                 transform: (id: "title", y: 100., width: 400., height: 50.),
+//!                 text: (text: "Menu", font: "font/square.ttf", font_size: 40.),
+//!             ),
+//!             Button(
+//!                 transform: (id: "start", width: 300., height: 80., selectable: 1),
+//!                 button: (
+//!                     text: "START GAME",
+//!                     normal_image: SolidColor(0., 0., 0., 1.),
+//!                     hover_image: SolidColor(0.1, 0.1, 0.1, 1.),
+//!                     hover_sound: "audio/boop.ogg",
+//!                 ),
+//!             ),
+//!         ],
+//!     ),
+//! }
+//! ```
+//!
+//! Fonts, textures and sounds are given by their path in the asset directory.
+//!
+//! [`UiCreator::create`] loads a file and returns the entity its root widget is instantiated on
+//! once it's loaded. Modifying the file while the asset daemon is running instantiates the tree
+//! again in place: a widget keeps its entity when a widget with the same id was there before, so
+//! entities found with [`UiFinder`](crate::UiFinder) stay valid, and the widgets that are gone
+//! are deleted.
+//!
+//! # Includes
+//!
+//! Widgets used in several layouts can be defined once in their own file, and included with
+//! `include(path, params)`. `${name}` in the strings of the included file, such as ids, texts and
+//! paths, is replaced by the value of the parameter `name`:
+//!
+//! ```ron
+//! // ui/menu_button.ron
+//! {
+//!     "873217bb-4af7-411c-912f-de4ab712a296":
+//!     Button(
+//!         transform: (id: "${id}", width: 300., height: 80., mouse_reactive: true),
+//!         button: (text: "${text}", font: "font/square.ttf", font_size: 40.),
+//!     ),
+//! }
+//! ```
+//!
+//! ```ron
+//! Container(
+//!     transform: (id: "load_container", y: -100., width: 300., height: 80.),
+//!     children: [
+//!         include("ui/menu_button.ron", {"id": "load", "text": "LOAD GAME"}),
+//!     ],
+//! ),
+//! ```
+//!
+//! Includes are expanded when the layout is instantiated, once all the included files are
+//! loaded. Modifying an included file instantiates the layouts including it again.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    iter::once,
+    marker::PhantomData,
+};
+
+use amethyst_assets::{
+    distill_importer,
+    distill_importer::{typetag, SerdeImportable},
+    register_asset_type, Asset, AssetProcessorSystem, AssetStorage, DefaultLoader, Handle, Loader,
+};
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, Resources, TryRead, World},
+    transform::{Parent, Transform},
+    HiddenPropagate,
+};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+use crate::{
+    Anchor, Draggable, Interactable, LineMode, Selectable, Stretch, TextEditing, UiButtonAction,
+    UiButtonActionRetrigger,
+    UiButtonActionType::{self, SetImage, SetTextColor, UnsetTextColor, UnsetTexture},
+    UiImage, UiPlaySoundAction, UiSoundRetrigger, UiText, UiTransform,
+};
+
+const DEFAULT_BUTTON_COLOR: [f32; 4] = [0.82, 0.83, 0.83, 1.0];
+/// Maximum depth of nested includes, reached when files include each other.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A widget of a layout file, and the widgets it holds.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, SerdeImportable)]
+#[uuid = "873217bb-4af7-411c-912f-de4ab712a296"]
+pub enum UiWidget {
+    /// A widget holding other widgets, drawn with an optional background.
+    Container {
+        /// Transform of the container
+        transform: UiTransformData,
+        /// Background of the container
+        #[serde(default)]
+        background: Option<UiImageData>,
+        /// Widgets held by the container
+        #[serde(default)]
+        children: Vec<UiWidget>,
+    },
+    /// An image.
+    Image {
+        /// Transform of the image
+        transform: UiTransformData,
+        /// The image to draw
+        image: UiImageData,
+    },
+    /// A text.
+    Label {
+        /// Transform of the label
+        transform: UiTransformData,
+        /// The text to draw
+        text: UiTextData,
+    },
+    /// A button, made of an image and a text on a child entity.
+    Button {
+        /// Transform of the button
+        transform: UiTransformData,
+        /// Images, text and sounds of the button
+        button: UiButtonData,
+    },
+    /// The root widget of another layout file, with `${name}` in its strings replaced by the
+    /// value of the parameter `name`.
+    #[serde(alias = "include")]
+    Include(String, HashMap<String, String>),
+}
+
+impl Asset for UiWidget {
+    fn name() -> &'static str {
+        "ui::Widget"
+    }
+    type Data = Self;
+}
+
+register_asset_type!(UiWidget => UiWidget; AssetProcessorSystem<UiWidget>);
+
+impl UiWidget {
+    /// Transform of the widget, `None` for includes.
+    #[must_use]
+    pub fn transform(&self) -> Option<&UiTransformData> {
+        match self {
+            UiWidget::Container { transform, .. }
+            | UiWidget::Image { transform, .. }
+            | UiWidget::Label { transform, .. }
+            | UiWidget::Button { transform, .. } => Some(transform),
+            UiWidget::Include(..) => None,
+        }
+    }
+
+    /// Replaces `${name}` by the value of the parameter `name` in the strings of the widget and
+    /// its children.
+    fn substitute(&mut self, params: &HashMap<String, String>) {
+        if params.is_empty() {
+            return;
+        }
+        self.for_each_string(&mut |string| {
+            if string.contains('$') {
+                for (name, value) in params {
+                    *string = string.replace(&format!("${{{}}}", name), value);
+                }
+            }
+        });
+    }
+
+    fn for_each_string(&mut self, f: &mut dyn FnMut(&mut String)) {
+        match self {
+            UiWidget::Container {
+                transform,
+                background,
+                children,
+            } => {
+                f(&mut transform.id);
+                if let Some(background) = background {
+                    background.for_each_string(f);
+                }
+                for child in children {
+                    child.for_each_string(f);
+                }
+            }
+            UiWidget::Image { transform, image } => {
+                f(&mut transform.id);
+                image.for_each_string(f);
+            }
+            UiWidget::Label { transform, text } => {
+                f(&mut transform.id);
+                f(&mut text.text);
+                if let Some(font) = &mut text.font {
+                    f(font);
+                }
+            }
+            UiWidget::Button { transform, button } => {
+                f(&mut transform.id);
+                f(&mut button.text);
+                let images = vec![
+                    &mut button.normal_image,
+                    &mut button.hover_image,
+                    &mut button.press_image,
+                ];
+                for image in images.into_iter().flatten() {
+                    image.for_each_string(f);
+                }
+                let paths = vec![
+                    &mut button.font,
+                    &mut button.hover_sound,
+                    &mut button.press_sound,
+                    &mut button.release_sound,
+                ];
+                for path in paths.into_iter().flatten() {
+                    f(path);
+                }
+            }
+            UiWidget::Include(path, params) => {
+                f(path);
+                for value in params.values_mut() {
+                    f(value);
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the includes of `widget` by the root widget of the included files, given by `fetch`.
+/// Returns `None` while one of the included files is not loaded.
+fn expand<F>(widget: &UiWidget, fetch: &mut F, depth: usize) -> Option<UiWidget>
+where
+    F: FnMut(&str) -> Option<UiWidget>,
+{
+    match widget {
+        UiWidget::Include(path, _) if depth >= MAX_INCLUDE_DEPTH => {
+            log::error!("Too many nested includes of UI layouts at {:?}", path);
+            Some(UiWidget::Container {
+                transform: UiTransformData::default(),
+                background: None,
+                children: Vec::new(),
+            })
+        }
+        UiWidget::Include(path, params) => {
+            let mut included = fetch(path)?;
+            included.substitute(params);
+            expand(&included, fetch, depth + 1)
+        }
+        UiWidget::Container {
+            transform,
+            background,
+            children,
+        } => {
+            // Expands all the children so that every included file starts loading.
+            let children: Vec<_> = children
+                .iter()
+                .map(|child| expand(child, fetch, depth))
+                .collect();
+            Some(UiWidget::Container {
+                transform: transform.clone(),
+                background: background.clone(),
+                children: children.into_iter().collect::<Option<_>>()?,
+            })
+        }
+        widget => Some(widget.clone()),
+    }
+}
+
+/// Transform of a widget in a layout file, see [`UiTransform`].
+#[derive(Derivative, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct UiTransformData {
+    /// Id of the widget
+    pub id: String,
+    /// X coordinate, relative to the anchor
+    pub x: f32,
+    /// Y coordinate, relative to the anchor
+    pub y: f32,
+    /// Z order, relative to the parent
+    pub z: f32,
+    /// Width of the widget
+    pub width: f32,
+    /// Height of the widget
+    pub height: f32,
+    /// Where the widget sits in its parent
+    pub anchor: Anchor,
+    /// Where the widget sits relative to itself
+    pub pivot: Anchor,
+    /// How the widget fills its parent
+    pub stretch: Stretch,
+    /// Whether the widget stops the events going to the widgets below it
+    #[derivative(Default(value = "true"))]
+    pub opaque: bool,
+    /// Whether the widget gets the events going through it when it isn't opaque
+    pub transparent_target: bool,
+    /// Whether the descendants of the widget are clipped to its rectangle
+    pub clip: bool,
+    /// Whether the widget gets mouse events
+    pub mouse_reactive: bool,
+    /// Order of the widget when selecting with the keyboard, the widget can't be selected
+    /// when this is not set
+    pub selectable: Option<u32>,
+    /// Whether the widget can be dragged with the mouse
+    pub draggable: bool,
+    /// Whether the widget and its children are hidden
+    pub hidden: bool,
+}
+
+impl UiTransformData {
+    fn to_transform(&self) -> UiTransform {
+        let mut transform = UiTransform::new(
+            self.id.clone(),
+            self.anchor,
+            self.pivot,
+            self.x,
+            self.y,
+            self.z,
+            self.width,
+            self.height,
+        )
+        .with_stretch(self.stretch);
+        transform.opaque = self.opaque;
+        transform.transparent_target = self.transparent_target;
+        transform.clip = self.clip;
+        transform
+    }
+}
+
+/// Image of a widget in a layout file, see [`UiImage`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UiImageData {
+    /// A whole texture
+    Texture(String),
+    /// A texture cropped to the given texture coordinates
+    PartialTexture {
+        /// Path of the texture
+        tex: String,
+        /// Left texture coordinate
+        left: f32,
+        /// Right texture coordinate
+        right: f32,
+        /// Bottom texture coordinate
+        bottom: f32,
+        /// Top texture coordinate
+        top: f32,
+    },
+    /// A 9-sliced texture
+    NineSlice {
+        /// X starting position on the texture
+        x_start: u32,
+        /// Y starting position on the texture
+        y_start: u32,
+        /// Width of the image in the texture
+        width: u32,
+        /// Height of the image in the texture
+        height: u32,
+        /// Distance from the left edge of the image for a slice
+        left_dist: u32,
+        /// Distance from the right edge of the image for a slice
+        right_dist: u32,
+        /// Distance from the top edge of the image for a slice
+        top_dist: u32,
+        /// Distance from the bottom edge of the image for a slice
+        bottom_dist: u32,
+        /// Path of the texture
+        tex: String,
+        /// Dimensions of the entire texture
+        texture_dimensions: [u32; 2],
+    },
+    /// A solid color, in sRGBA
+    SolidColor(f32, f32, f32, f32),
+}
+
+impl UiImageData {
+    fn for_each_string(&mut self, f: &mut dyn FnMut(&mut String)) {
+        match self {
+            UiImageData::Texture(tex)
+            | UiImageData::PartialTexture { tex, .. }
+            | UiImageData::NineSlice { tex, .. } => f(tex),
+            UiImageData::SolidColor(..) => {}
+        }
+    }
+
+    fn load(&self, loader: &DefaultLoader) -> UiImage {
+        match self {
+            UiImageData::Texture(tex) => UiImage::Texture(loader.load(tex)),
+            UiImageData::PartialTexture {
+                tex,
+                left,
+                right,
+                bottom,
+                top,
+            } => {
+                UiImage::PartialTexture {
+                    tex: loader.load(tex),
+                    left: *left,
+                    right: *right,
+                    bottom: *bottom,
+                    top: *top,
+                }
+            }
+            UiImageData::NineSlice {
+                x_start,
+                y_start,
+                width,
+                height,
+                left_dist,
+                right_dist,
+                top_dist,
+                bottom_dist,
+                tex,
+                texture_dimensions,
+            } => {
+                UiImage::NineSlice {
+                    x_start: *x_start,
+                    y_start: *y_start,
+                    width: *width,
+                    height: *height,
+                    left_dist: *left_dist,
+                    right_dist: *right_dist,
+                    top_dist: *top_dist,
+                    bottom_dist: *bottom_dist,
+                    tex: loader.load(tex),
+                    texture_dimensions: *texture_dimensions,
+                }
+            }
+            UiImageData::SolidColor(r, g, b, a) => UiImage::SolidColor([*r, *g, *b, *a]),
+        }
+    }
+}
+
+/// Text of a widget in a layout file, see [`UiText`].
+#[derive(Derivative, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct UiTextData {
+    /// The text to draw
+    pub text: String,
+    /// Path of the font, the default font is used when this is not set
+    pub font: Option<String>,
+    /// Size of the font
+    #[derivative(Default(value = "18."))]
+    pub font_size: f32,
+    /// Color of the text, in RGBA
+    #[derivative(Default(value = "[1., 1., 1., 1.]"))]
+    pub color: [f32; 4],
+    /// Whether the text is drawn as a password
+    pub password: bool,
+    /// How lines longer than the widget behave
+    pub line_mode: LineMode,
+    /// Where the text sits in the widget
+    pub align: Anchor,
+    /// Makes the text editable
+    pub editable: Option<TextEditingData>,
+}
+
+impl UiTextData {
+    fn to_text(&self, loader: &DefaultLoader) -> UiText {
+        let mut text = UiText::new(
+            self.font.as_ref().map(|font| loader.load(font)),
+            self.text.clone(),
+            self.color,
+            self.font_size,
+            self.line_mode,
+            self.align,
+        );
+        text.password = self.password;
+        text
+    }
+}
+
+/// Editing settings of a text in a layout file, see [`TextEditing`].
+#[derive(Derivative, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct TextEditingData {
+    /// Maximum number of characters of the text
+    #[derivative(Default(value = "1000"))]
+    pub max_length: usize,
+    /// Color of the selected text
+    #[derivative(Default(value = "[0., 0., 0., 1.]"))]
+    pub selected_text_color: [f32; 4],
+    /// Color of the background of the selected text
+    #[derivative(Default(value = "[1., 1., 1., 1.]"))]
+    pub selected_background_color: [f32; 4],
+    /// Whether the cursor is drawn as a block instead of a line
+    pub use_block_cursor: bool,
+}
+
+/// Images, text and sounds of a button in a layout file, see
+/// [`UiButtonBuilder`](crate::UiButtonBuilder).
+#[derive(Derivative, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct UiButtonData {
+    /// Text of the button
+    pub text: String,
+    /// Path of the font, the default font is used when this is not set
+    pub font: Option<String>,
+    /// Size of the font
+    #[derivative(Default(value = "32."))]
+    pub font_size: f32,
+    /// Color of the text
+    #[derivative(Default(value = "[0., 0., 0., 1.]"))]
+    pub normal_text_color: [f32; 4],
+    /// Image of the button
+    pub normal_image: Option<UiImageData>,
+    /// Image of the button while hovered
+    pub hover_image: Option<UiImageData>,
+    /// Color of the text while hovered
+    pub hover_text_color: Option<[f32; 4]>,
+    /// Image of the button while pressed
+    pub press_image: Option<UiImageData>,
+    /// Color of the text while pressed
+    pub press_text_color: Option<[f32; 4]>,
+    /// Path of the sound played when the button starts being hovered
+    pub hover_sound: Option<String>,
+    /// Path of the sound played when the button is pressed
+    pub press_sound: Option<String>,
+    /// Path of the sound played when the button is released
+    pub release_sound: Option<String>,
+}
+
+/// Loads layout files and instantiates them.
+#[derive(Debug)]
+pub struct UiCreator;
+
+impl UiCreator {
+    /// Loads the layout file at `path` and returns the entity its root widget is instantiated on
+    /// once it's loaded.
+    pub fn create(world: &mut World, resources: &Resources, path: &str) -> Entity {
+        let handle: Handle<UiWidget> = resources
+            .get::<DefaultLoader>()
+            .expect("DefaultLoader can not be retrieved from ECS Resources")
+            .load(path);
+        world.push((handle,))
+    }
+}
+
+/// The entities instantiated from a layout file, with the id of their widget.
+struct UiWidgetInstance {
+    /// Versions of the layout file and of the included files, in the order of `includes`, when
+    /// the layout was instantiated
+    versions: Vec<u32>,
+    /// Handles to the included files, by path
+    includes: BTreeMap<String, Handle<UiWidget>>,
+    entities: Vec<(String, Entity)>,
+}
+
+impl UiWidgetInstance {
+    fn is_up_to_date(&self, version: u32, storage: &AssetStorage<UiWidget>) -> bool {
+        self.versions.first() == Some(&version)
+            && self
+                .includes
+                .values()
+                .map(|include| storage.get_version(include))
+                .eq(self.versions[1..].iter().map(|version| Some(*version)))
+    }
+}
+
+/// Instantiates the layout files of the entities with a `Handle<UiWidget>`, and instantiates them
+/// again when they or the files they include are modified.
+///
+/// It's automatically registered with the `UiBundle`.
+pub fn ui_widget_spawning_tick<G>(world: &mut World, resources: &mut Resources)
+where
+    G: Send + Sync + 'static,
+{
+    let (storage, loader) = match (
+        resources.get::<AssetStorage<UiWidget>>(),
+        resources.get::<DefaultLoader>(),
+    ) {
+        (Some(storage), Some(loader)) => (storage, loader),
+        _ => return,
+    };
+
+    let mut layouts = Vec::new();
+    <(Entity, &Handle<UiWidget>, TryRead<UiWidgetInstance>)>::query().for_each(
+        world,
+        |(entity, handle, instance)| {
+            if let Some((widget, version)) = storage.get_asset_with_version(handle) {
+                if !instance.map_or(false, |instance| instance.is_up_to_date(version, &storage)) {
+                    let (includes, entities) = instance
+                        .map(|instance| (instance.includes.clone(), instance.entities.clone()))
+                        .unwrap_or_default();
+                    layouts.push((*entity, widget.clone(), version, includes, entities));
+                }
+            }
+        },
+    );
+
+    for (root, widget, version, mut previous_includes, previous) in layouts {
+        let mut includes = BTreeMap::new();
+        let expanded = expand(
+            &widget,
+            &mut |path| {
+                let handle = previous_includes
+                    .remove(path)
+                    .or_else(|| includes.get(path).cloned())
+                    .unwrap_or_else(|| loader.load(path));
+                let included = storage.get(&handle).cloned();
+                includes.insert(path.to_string(), handle);
+                included
+            },
+            0,
+        );
+
+        let (versions, entities) = if let Some(expanded) = expanded {
+            log::debug!("Instantiating UI layout on {:?}", root);
+            let entities = instantiate::<G>(world, &loader, &expanded, root, previous);
+            let versions = once(version)
+                .chain(includes.values().filter_map(|include| storage.get_version(include)))
+                .collect();
+            (versions, entities)
+        } else {
+            (Vec::new(), previous)
+        };
+        if let Some(mut entry) = world.entry(root) {
+            entry.add_component(UiWidgetInstance {
+                versions,
+                includes,
+                entities,
+            });
+        }
+    }
+}
+
+/// Instantiates `widget` on `root`, reusing the entities of the `previous` instance that have the
+/// same id and deleting the others. Returns the entities of the new instance.
+fn instantiate<G>(
+    world: &mut World,
+    loader: &DefaultLoader,
+    widget: &UiWidget,
+    root: Entity,
+    previous: Vec<(String, Entity)>,
+) -> Vec<(String, Entity)>
+where
+    G: Send + Sync + 'static,
+{
+    let mut reusable = HashMap::new();
+    for (id, entity) in &previous {
+        if !id.is_empty() {
+            reusable.entry(id.clone()).or_insert(*entity);
+        }
+    }
+    let mut spawner = Spawner::<G> {
+        world,
+        loader,
+        reusable,
+        entities: Vec::new(),
+        _marker: PhantomData,
+    };
+    spawner.spawn(widget, root, None);
+    let entities = spawner.entities;
+
+    let live: HashSet<Entity> = entities.iter().map(|(_, entity)| *entity).collect();
+    for (_, entity) in previous {
+        if !live.contains(&entity) {
+            world.remove(entity);
+        }
+    }
+    entities
+}
+
+struct Spawner<'a, G> {
+    world: &'a mut World,
+    loader: &'a DefaultLoader,
+    reusable: HashMap<String, Entity>,
+    entities: Vec<(String, Entity)>,
+    _marker: PhantomData<G>,
+}
+
+impl<G> Spawner<'_, G>
+where
+    G: Send + Sync + 'static,
+{
+    /// Returns the entity of the previous widget with given id, or a new entity.
+    fn entity(&mut self, id: &str) -> Entity {
+        let entity = match self.reusable.remove(id) {
+            Some(entity) => entity,
+            None => self.world.push(()),
+        };
+        self.entities.push((id.to_string(), entity));
+        entity
+    }
+
+    fn spawn(&mut self, widget: &UiWidget, entity: Entity, parent: Option<Entity>) {
+        let data = match widget.transform() {
+            Some(data) => data,
+            None => return,
+        };
+        self.reset(entity, data.to_transform(), parent);
+        let loader = self.loader;
+        let mut entry = self.world.entry(entity).expect("Widget entity was deleted");
+        if data.mouse_reactive {
+            entry.add_component(Interactable);
+        }
+        if let Some(order) = data.selectable {
+            entry.add_component(Selectable::<G>::new(order));
+        }
+        if data.draggable {
+            entry.add_component(Draggable);
+        }
+        if data.hidden {
+            entry.add_component(HiddenPropagate::new());
+        }
+
+        match widget {
+            UiWidget::Container {
+                background: Some(background),
+                ..
+            } => entry.add_component(background.load(loader)),
+            UiWidget::Container { .. } => {}
+            UiWidget::Image { image, .. } => entry.add_component(image.load(loader)),
+            UiWidget::Label { text, .. } => {
+                entry.add_component(text.to_text(loader));
+                if let Some(editable) = &text.editable {
+                    entry.add_component(TextEditing::new(
+                        editable.max_length,
+                        editable.selected_text_color,
+                        editable.selected_background_color,
+                        editable.use_block_cursor,
+                    ));
+                }
+            }
+            UiWidget::Button { .. } | UiWidget::Include(..) => {}
+        }
+
+        match widget {
+            UiWidget::Container { children, .. } => {
+                for child in children {
+                    if let Some(transform) = child.transform() {
+                        let child_entity = self.entity(&transform.id);
+                        self.spawn(child, child_entity, Some(entity));
+                    }
+                }
+            }
+            UiWidget::Button { button, .. } => self.spawn_button(button, entity, &data.id),
+            _ => {}
+        }
+    }
+
+    fn spawn_button(&mut self, button: &UiButtonData, entity: Entity, id: &str) {
+        let loader = self.loader;
+        let image = button.normal_image.as_ref().map_or_else(
+            || UiImage::SolidColor(DEFAULT_BUTTON_COLOR),
+            |image| image.load(loader),
+        );
+        let action = |event_type: UiButtonActionType| {
+            UiButtonAction {
+                target: entity,
+                event_type,
+            }
+        };
+        let mut retrigger = UiButtonActionRetrigger::default();
+        if let Some(image) = &button.hover_image {
+            let image = image.load(loader);
+            retrigger.on_hover_start.push(action(SetImage(image.clone())));
+            retrigger.on_hover_stop.push(action(UnsetTexture(image)));
+        }
+        if let Some(color) = button.hover_text_color {
+            retrigger.on_hover_start.push(action(SetTextColor(color)));
+            retrigger.on_hover_stop.push(action(UnsetTextColor(color)));
+        }
+        if let Some(image) = &button.press_image {
+            let image = image.load(loader);
+            retrigger.on_click_start.push(action(SetImage(image.clone())));
+            retrigger.on_click_stop.push(action(UnsetTexture(image)));
+        }
+        if let Some(color) = button.press_text_color {
+            retrigger.on_click_start.push(action(SetTextColor(color)));
+            retrigger.on_click_stop.push(action(UnsetTextColor(color)));
+        }
+        let sound = |path: &Option<String>| {
+            path.as_ref()
+                .map(|path| UiPlaySoundAction(loader.load(path)))
+        };
+
+        let mut entry = self.world.entry(entity).expect("Widget entity was deleted");
+        entry.add_component(image);
+        entry.add_component(Interactable);
+        entry.add_component(retrigger);
+        entry.add_component(UiSoundRetrigger {
+            on_click_start: sound(&button.press_sound),
+            on_click_stop: sound(&button.release_sound),
+            on_hover_start: sound(&button.hover_sound),
+            on_hover_stop: None,
+        });
+
+        let text_id = if id.is_empty() {
+            String::new()
+        } else {
+            format!("{}_btn_text", id)
+        };
+        let text_entity = self.entity(&text_id);
+        let transform = UiTransform::new(
+            text_id,
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            0.,
+            0.01,
+            0.,
+            0.,
+        )
+        .into_transparent()
+        .with_stretch(Stretch::XY {
+            x_margin: 0.,
+            y_margin: 0.,
+            keep_aspect_ratio: false,
+        });
+        self.reset(text_entity, transform, Some(entity));
+        let text = UiText::new(
+            button.font.as_ref().map(|font| loader.load(font)),
+            button.text.clone(),
+            button.normal_text_color,
+            button.font_size,
+            LineMode::Single,
+            Anchor::Middle,
+        );
+        self.world
+            .entry(text_entity)
+            .expect("Widget entity was deleted")
+            .add_component(text);
+    }
+
+    /// Removes the components the previous widget of the entity may have had, and sets its
+    /// transform and parent.
+    fn reset(&mut self, entity: Entity, transform: UiTransform, parent: Option<Entity>) {
+        let mut entry = self.world.entry(entity).expect("Widget entity was deleted");
+        entry.remove_component::<UiImage>();
+        entry.remove_component::<UiText>();
+        entry.remove_component::<TextEditing>();
+        entry.remove_component::<Interactable>();
+        entry.remove_component::<Selectable<G>>();
+        entry.remove_component::<Draggable>();
+        entry.remove_component::<HiddenPropagate>();
+        entry.remove_component::<UiButtonActionRetrigger>();
+        entry.remove_component::<UiSoundRetrigger>();
+        entry.add_component(transform);
+        if entry.get_component::<Transform>().is_err() {
+            entry.add_component(Transform::default());
+        }
+        if let Some(parent) = parent {
+            entry.add_component(Parent(parent));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(ron: &str) -> UiWidget {
+        ron::de::from_str(ron).expect("Failed to parse layout")
+    }
+
+    fn find(world: &World, id: &str) -> Option<Entity> {
+        <(Entity, &UiTransform)>::query()
+            .iter(world)
+            .find(|(_, transform)| transform.id == id)
+            .map(|(entity, _)| *entity)
+    }
+
+    #[test]
+    fn parses_layout_file() {
+        let widget = layout(
+            r#"Container(
+                transform: (id: "menu", width: 400., height: 300., mouse_reactive: true),
+                background: SolidColor(0.1, 0.1, 0.1, 1.0),
+                children: [
+                    Label(
+                        transform: (id: "title", anchor: TopMiddle),
+                        text: (text: "Menu", font_size: 40., line_mode: Wrap),
+                    ),
+                    Button(transform: (id: "start"), button: (text: "Start")),
+                ],
+            )"#,
+        );
+        let transform = widget.transform().unwrap();
+        assert_eq!(transform.id, "menu");
+        assert!(transform.opaque && transform.mouse_reactive);
+        match widget {
+            UiWidget::Container {
+                background,
+                children,
+                ..
+            } => {
+                assert_eq!(background, Some(UiImageData::SolidColor(0.1, 0.1, 0.1, 1.0)));
+                assert_eq!(children.len(), 2);
+                match &children[0] {
+                    UiWidget::Label { transform, text } => {
+                        assert_eq!(transform.anchor, Anchor::TopMiddle);
+                        assert_eq!(text.line_mode, LineMode::Wrap);
+                        assert_eq!(text.color, [1., 1., 1., 1.]);
+                    }
+                    other => panic!("Expected a label, got {:?}", other),
+                }
+            }
+            other => panic!("Expected a container, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reinstantiating_keeps_entities_of_same_ids() {
+        let mut world = World::default();
+        let loader = DefaultLoader::default();
+        let root = world.push(());
+
+        let first = layout(
+            r#"Container(
+                transform: (id: "menu"),
+                children: [
+                    Label(transform: (id: "title"), text: (text: "Menu")),
+                    Image(transform: (id: "logo"), image: SolidColor(1., 1., 1., 1.)),
+                    Button(transform: (id: "start"), button: (text: "Start")),
+                ],
+            )"#,
+        );
+        let entities = instantiate::<()>(&mut world, &loader, &first, root, Vec::new());
+        assert_eq!(entities.len(), 5);
+        let title = find(&world, "title").unwrap();
+        let logo = find(&world, "logo").unwrap();
+        let start = find(&world, "start").unwrap();
+        assert!(find(&world, "start_btn_text").is_some());
+
+        let second = layout(
+            r#"Container(
+                transform: (id: "menu"),
+                children: [
+                    Image(transform: (id: "title"), image: SolidColor(1., 0., 0., 1.)),
+                    Button(transform: (id: "start"), button: (text: "Play")),
+                ],
+            )"#,
+        );
+        instantiate::<()>(&mut world, &loader, &second, root, entities);
+        assert_eq!(find(&world, "menu"), Some(root));
+        assert_eq!(find(&world, "title"), Some(title));
+        assert_eq!(find(&world, "start"), Some(start));
+        assert!(world.entry(logo).is_none());
+
+        let entry = world.entry(title).unwrap();
+        assert!(entry.get_component::<UiText>().is_err());
+        assert!(entry.get_component::<UiImage>().is_ok());
+        assert_eq!(entry.get_component::<Parent>().unwrap().0, root);
+        let text = find(&world, "start_btn_text").unwrap();
+        let entry = world.entry(text).unwrap();
+        assert_eq!(entry.get_component::<UiText>().unwrap().text, "Play");
+    }
+
+    #[test]
+    fn expands_includes_with_params() {
+        let files: HashMap<&str, UiWidget> = vec![
+            (
+                "button.ron",
+                layout(r#"Button(transform: (id: "${id}"), button: (text: "${text}"))"#),
+            ),
+            (
+                "buttons.ron",
+                layout(
+                    r#"Container(
+                        transform: (id: "buttons"),
+                        children: [
+                            include("button.ron", {"id": "start", "text": "Start"}),
+                            include("button.ron", {"id": "${second}", "text": "Quit"}),
+                        ],
+                    )"#,
+                ),
+            ),
+            ("loop.ron", layout(r#"include("loop.ron", {})"#)),
+        ]
+        .into_iter()
+        .collect();
+        let mut fetch = |path: &str| files.get(path).cloned();
+
+        let root = layout(r#"include("buttons.ron", {"second": "quit"})"#);
+        let expanded = expand(&root, &mut fetch, 0).unwrap();
+        let children = match &expanded {
+            UiWidget::Container { children, .. } => children,
+            other => panic!("Expected a container, got {:?}", other),
+        };
+        let buttons: Vec<_> = children
+            .iter()
+            .map(|child| {
+                match child {
+                    UiWidget::Button { transform, button } => {
+                        (transform.id.as_str(), button.text.as_str())
+                    }
+                    other => panic!("Expected a button, got {:?}", other),
+                }
+            })
+            .collect();
+        assert_eq!(buttons, vec![("start", "Start"), ("quit", "Quit")]);
+
+        let missing = layout(r#"include("missing.ron", {})"#);
+        assert!(expand(&missing, &mut fetch, 0).is_none());
+        let recursive = layout(r#"include("loop.ron", {})"#);
+        assert!(expand(&recursive, &mut fetch, 0).is_some());
+    }
+}
@@ -12,8 +12,7 @@ use amethyst_window::ScreenDimensions;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    get_parent_pixel_size, targeted_below, Interactable, ScaleMode, UiEvent, UiEventType,
-    UiTransform,
+    get_parent_pixel_size, window_to_ui, ScaleMode, UiEvent, UiEventType, UiTransform,
 };
 
 /// Component that denotes whether a given ui widget is draggable.
@@ -21,6 +20,60 @@ use crate::{
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Draggable;
 
+/// Data carried by a `Draggable` entity, and passed to the `UiDropTarget` it's dropped on with the
+/// `UiEventType::Dropped` event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiDragPayload {
+    /// Kind of the payload, matched against the kinds accepted by the drop targets.
+    pub kind: String,
+    /// The data, e.g. the id of a dragged inventory item.
+    pub data: String,
+}
+
+impl UiDragPayload {
+    /// Creates a payload of given kind and data.
+    pub fn new(kind: impl Into<String>, data: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Component of the entities `Draggable`s can be dropped on, e.g. the slots of an inventory.
+/// Requires `UiTransform` to work.
+///
+/// When a drag stops over drop targets, the topmost one accepting the payload of the dragged
+/// entity receives the `UiEventType::Dropped` event.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiDropTarget {
+    /// Kinds of payloads accepted, or empty to accept any drop, including entities without a
+    /// `UiDragPayload`.
+    pub accepts: Vec<String>,
+}
+
+impl UiDropTarget {
+    /// Creates a drop target accepting any drop.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a drop target accepting the payloads of the given kinds only.
+    pub fn accepting<S: Into<String>>(kinds: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            accepts: kinds.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns whether an entity with the given payload can be dropped on this target.
+    #[must_use]
+    pub fn accepts(&self, payload: Option<&UiDragPayload>) -> bool {
+        self.accepts.is_empty()
+            || payload.map_or(false, |payload| self.accepts.contains(&payload.kind))
+    }
+}
+
 /// System responsible of drag'n drop
 #[derive(Debug)]
 pub struct DragWidgetSystem {
@@ -56,8 +109,9 @@ impl System for DragWidgetSystem {
                 .with_query(<Option<&Parent>>::query())
                 .with_query(<(Entity, Option<&UiTransform>)>::query())
                 .with_query(<&mut UiTransform>::query())
+                .with_query(<&UiDragPayload>::query())
                 .with_query(
-                    <(Entity, &UiTransform, Option<&Interactable>)>::query()
+                    <(Entity, &UiTransform, &UiDropTarget)>::query()
                         .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
                 )
                 .build(
@@ -71,11 +125,14 @@ impl System for DragWidgetSystem {
                         maybe_parent,
                         maybe_ui_transform,
                         ui_transforms,
-                        not_hidden_ui_transforms,
+                        payloads,
+                        drop_targets,
                     )| {
-                        let mouse_pos = input.mouse_position().unwrap_or((0., 0.));
-                        let mouse_pos =
-                            Vector2::new(mouse_pos.0, screen_dimensions.height() - mouse_pos.1);
+                        let mouse_pos = window_to_ui(
+                            input.mouse_position().unwrap_or((0., 0.)),
+                            screen_dimensions,
+                        );
+                        let mouse_pos = Vector2::new(mouse_pos.0, mouse_pos.1);
                         let mut click_stopped: HashSet<Entity> = HashSet::new();
                         let event_reader = &mut self.event_reader;
                         ui_events.read(event_reader).for_each(|event| {
@@ -137,16 +194,30 @@ impl System for DragWidgetSystem {
                         }
 
                         for entity in &click_stopped {
-                            ui_events.single_write(UiEvent::new(
-                                UiEventType::Dropped {
-                                    dropped_on: targeted_below(
-                                        (mouse_pos[0], mouse_pos[1]),
-                                        ui_transforms.get_mut(world, *entity).unwrap().global_z,
-                                        not_hidden_ui_transforms.iter(world),
-                                    ),
-                                },
-                                *entity,
-                            ));
+                            let payload = payloads.get(world, *entity).ok().cloned();
+                            let target = drop_targets
+                                .iter(world)
+                                .filter(|(target, transform, drop_target)| {
+                                    *target != entity
+                                        && transform.position_inside(mouse_pos[0], mouse_pos[1])
+                                        && drop_target.accepts(payload.as_ref())
+                                })
+                                .max_by(|(_, t1, _), (_, t2, _)| {
+                                    t1.global_z
+                                        .partial_cmp(&t2.global_z)
+                                        .expect("Unexpected NaN")
+                                })
+                                .map(|(target, _, _)| *target);
+
+                            let event_type = UiEventType::Dropped {
+                                dragged: *entity,
+                                target,
+                                payload,
+                            };
+                            if let Some(target) = target {
+                                ui_events.single_write(UiEvent::new(event_type.clone(), target));
+                            }
+                            ui_events.single_write(UiEvent::new(event_type, *entity));
 
                             self.record.remove(entity);
                         }
@@ -155,3 +226,20 @@ impl System for DragWidgetSystem {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_targets_accept_their_payload_kinds() {
+        let item = UiDragPayload::new("item", "sword");
+        assert!(UiDropTarget::new().accepts(None));
+        assert!(UiDropTarget::new().accepts(Some(&item)));
+
+        let slot = UiDropTarget::accepting(vec!["item"]);
+        assert!(slot.accepts(Some(&item)));
+        assert!(!slot.accepts(Some(&UiDragPayload::new("spell", "fireball"))));
+        assert!(!slot.accepts(None));
+    }
+}
@@ -11,7 +11,7 @@ use amethyst_window::ScreenDimensions;
 use serde::{Deserialize, Serialize};
 use winit::event::MouseButton;
 
-use crate::transform::UiTransform;
+use crate::{mouse_ui_position, transform::UiTransform, UiDragPayload, UiModals};
 
 /// An event that pertains to a specific `Entity`, for example a `UiEvent` for clicking on a widget
 /// entity.
@@ -45,13 +45,19 @@ pub enum UiEventType {
         new_position: Vector2<f32>,
     },
     /// When stopping to drag a `Draggable` Ui element.
+    ///
+    /// It's sent to the dragged entity, and to the `UiDropTarget` it was dropped on, if any.
     Dropped {
-        /// The entity on which the dragged object was dropped.
-        dropped_on: Option<Entity>,
+        /// The dragged entity.
+        dragged: Entity,
+        /// The `UiDropTarget` accepting the dragged entity under the mouse, if any.
+        target: Option<Entity>,
+        /// The `UiDragPayload` of the dragged entity, if any.
+        payload: Option<UiDragPayload>,
     },
-    /// When the value of a UiText element has been changed by user input.
+    /// When the value of a UiText, UiSlider or UiCheckbox element has been changed by user input.
     ValueChange,
-    /// When the value of a UiText element has been committed by user action.
+    /// When the value of a UiText or UiSlider element has been committed by user action.
     ValueCommit,
     /// When an editable UiText element has gained focus.
     Focus,
@@ -116,6 +122,7 @@ impl System for UiMouseSystem {
                 .write_resource::<EventChannel<UiEvent>>()
                 .read_resource::<InputHandler>()
                 .read_resource::<ScreenDimensions>()
+                .read_resource::<UiModals>()
                 // Interactable entities with an UiTransform, without Hidden or HiddenPropagate
                 .with_query(
                     <(Entity, &UiTransform, Option<&Interactable>)>::query()
@@ -124,17 +131,21 @@ impl System for UiMouseSystem {
                 .build(
                     move |_commands,
                           world,
-                          (events, input, screen_dimensions),
+                          (events, input, screen_dimensions, modals),
                           interactables_entities| {
                         let down = input.mouse_button_is_down(MouseButton::Left);
                         // FIXME: To replace on InputHandler generate OnMouseDown and OnMouseUp events See #2496
                         let click_started = down && !self.was_down;
                         let click_stopped = !down && self.was_down;
-                        if let Some((pos_x, pos_y)) = input.mouse_position() {
-                            let x = pos_x as f32;
-                            let y = screen_dimensions.height() - pos_y as f32;
-
-                            let targets = targeted((x, y), interactables_entities.iter(world));
+                        if let Some((x, y)) = mouse_ui_position(input, screen_dimensions) {
+
+                            // Entities outside of an active modal receive no events.
+                            let targets = targeted(
+                                (x, y),
+                                interactables_entities
+                                    .iter(world)
+                                    .filter(|(entity, _, _)| modals.accepts(**entity)),
+                            );
 
                             for target in targets.difference(&self.last_targets) {
                                 events.single_write(UiEvent::new(UiEventType::HoverStart, *target));
@@ -0,0 +1,361 @@
+//! Automatic layout of the children of a ui element in rows or columns, like the CSS flexbox.
+
+use amethyst_assets::prefab::{legion_prefab, register_component_type, serde_diff, SerdeDiff};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+/// Direction the children of a `UiFlexContainer` are placed in.
+#[derive(Derivative, Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize, SerdeDiff)]
+#[derivative(Default)]
+pub enum FlexDirection {
+    /// From left to right.
+    #[derivative(Default)]
+    Row,
+    /// From right to left.
+    RowReverse,
+    /// From top to bottom.
+    Column,
+    /// From bottom to top.
+    ColumnReverse,
+}
+
+/// Distribution of the free space of a line of a `UiFlexContainer` along its direction.
+#[derive(Derivative, Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize, SerdeDiff)]
+#[derivative(Default)]
+pub enum FlexJustify {
+    /// Children are packed at the start of the line.
+    #[derivative(Default)]
+    Start,
+    /// Children are packed at the end of the line.
+    End,
+    /// Children are packed at the center of the line.
+    Center,
+    /// The free space is put between the children.
+    SpaceBetween,
+    /// The free space is put around each child, so there is half as much at the ends.
+    SpaceAround,
+    /// The free space is evenly put between the children and at the ends.
+    SpaceEvenly,
+}
+
+/// Placement of the children of a `UiFlexContainer` across its direction, within their line.
+#[derive(Derivative, Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize, SerdeDiff)]
+#[derivative(Default)]
+pub enum FlexAlign {
+    /// Children are placed at the start of the line, the top for rows and the left for columns.
+    Start,
+    /// Children are placed at the end of the line.
+    End,
+    /// Children are centered in the line.
+    Center,
+    /// Children are resized to fill the line.
+    #[derivative(Default)]
+    Stretch,
+}
+
+/// Component laying out the children of a ui element in rows or columns, wrapping them in
+/// several lines when they overflow.
+///
+/// The `UiTransformSystem` sets the `UiTransform` of each child to be positioned from the top
+/// left corner of the container in pixels, and resizes it according to the grow and shrink
+/// factors of its [`UiFlexItem`]. The size a child had before being laid out, or set to it
+/// since, is the size it's laid out from.
+#[derive(Clone, Copy, Derivative, PartialEq, Deserialize, Serialize, SerdeDiff, TypeUuid)]
+#[uuid = "ab12cf34-dd2a-4783-8fef-d6dac0b39eb3"]
+#[derivative(Debug, Default)]
+#[serde(default)]
+pub struct UiFlexContainer {
+    /// Direction the children are placed in.
+    pub direction: FlexDirection,
+    /// Whether the children overflowing a line are moved to a new line.
+    pub wrap: bool,
+    /// Distribution of the free space of the lines.
+    pub justify: FlexJustify,
+    /// Placement of the children in their line, unless their `UiFlexItem` overrides it.
+    pub align: FlexAlign,
+    /// Space in pixels between the children, and between the lines.
+    pub gap: f32,
+    /// Space in pixels between the edges of the container and the children.
+    pub padding: f32,
+}
+
+register_component_type!(UiFlexContainer);
+
+/// Component setting how a child of a `UiFlexContainer` is laid out.
+///
+/// Children without it neither grow nor shrink.
+#[derive(Clone, Copy, Derivative, PartialEq, Deserialize, Serialize, SerdeDiff, TypeUuid)]
+#[uuid = "bdd94a70-4e9e-473b-886d-0298794d56b2"]
+#[derivative(Debug, Default)]
+#[serde(default)]
+pub struct UiFlexItem {
+    /// Share of the free space of the line the child grows by.
+    pub grow: f32,
+    /// Share of the overflow of the line the child shrinks by, weighted by its size.
+    pub shrink: f32,
+    /// Size in pixels along the direction of the container to lay the child out from, instead
+    /// of its own size.
+    pub basis: Option<f32>,
+    /// Placement of the child in its line, overriding the one of the container.
+    pub align: Option<FlexAlign>,
+    /// Position of the child among the others, the children of equal order keeping their
+    /// order in the `Children` of the container.
+    pub order: i32,
+}
+
+register_component_type!(UiFlexItem);
+
+impl UiFlexContainer {
+    /// Creates a container placing its children on a single line in given direction.
+    #[must_use]
+    pub fn new(direction: FlexDirection) -> Self {
+        Self {
+            direction,
+            ..Self::default()
+        }
+    }
+
+    /// Moves the children overflowing a line to a new line.
+    #[must_use]
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Sets the distribution of the free space of the lines.
+    #[must_use]
+    pub fn with_justify(mut self, justify: FlexJustify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// Sets the placement of the children in their line.
+    #[must_use]
+    pub fn with_align(mut self, align: FlexAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Sets the space between the children and between the lines.
+    #[must_use]
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Sets the space between the edges of the container and the children.
+    #[must_use]
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Lays out children of given sizes in a container of given size.
+    ///
+    /// Returns the positions from the top left corner of the container and the sizes of the
+    /// children, as `(x, y, width, height)` in pixels.
+    pub(crate) fn layout(
+        &self,
+        width: f32,
+        height: f32,
+        items: &[((f32, f32), UiFlexItem)],
+    ) -> Vec<(f32, f32, f32, f32)> {
+        let row = matches!(
+            self.direction,
+            FlexDirection::Row | FlexDirection::RowReverse
+        );
+        let reverse = matches!(
+            self.direction,
+            FlexDirection::RowReverse | FlexDirection::ColumnReverse
+        );
+        let (main_size, cross_size) = if row {
+            (width, height)
+        } else {
+            (height, width)
+        };
+        let inner_main = (main_size - 2.0 * self.padding).max(0.0);
+        let inner_cross = (cross_size - 2.0 * self.padding).max(0.0);
+
+        // Sizes along and across the direction, from which the children are laid out.
+        let sizes: Vec<(f32, f32)> = items
+            .iter()
+            .map(|((width, height), item)| {
+                let (main, cross) = if row {
+                    (*width, *height)
+                } else {
+                    (*height, *width)
+                };
+                (item.basis.unwrap_or(main), cross)
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by_key(|i| items[*i].1.order);
+
+        let mut lines: Vec<Vec<usize>> = Vec::new();
+        let mut used = 0.0;
+        for i in order {
+            match lines.last_mut() {
+                Some(line) if !self.wrap || used + self.gap + sizes[i].0 <= inner_main => {
+                    used += self.gap + sizes[i].0;
+                    line.push(i);
+                }
+                _ => {
+                    used = sizes[i].0;
+                    lines.push(vec![i]);
+                }
+            }
+        }
+
+        let mut rects = vec![(0.0, 0.0, 0.0, 0.0); items.len()];
+        let mut line_start = 0.0;
+        for line in &lines {
+            #[allow(clippy::cast_precision_loss)]
+            let count = line.len() as f32;
+            let mut mains: Vec<f32> = line.iter().map(|i| sizes[*i].0).collect();
+            let mut free = inner_main - mains.iter().sum::<f32>() - self.gap * (count - 1.0);
+            let grow: f32 = line.iter().map(|i| items[*i].1.grow).sum();
+            let shrink: f32 = line.iter().map(|i| items[*i].1.shrink * sizes[*i].0).sum();
+            if free > 0.0 && grow > 0.0 {
+                for (main, i) in mains.iter_mut().zip(line) {
+                    *main += free * items[*i].1.grow / grow;
+                }
+                free = 0.0;
+            } else if free < 0.0 && shrink > 0.0 {
+                for (main, i) in mains.iter_mut().zip(line) {
+                    *main = (*main + free * items[*i].1.shrink * sizes[*i].0 / shrink).max(0.0);
+                }
+                free = 0.0;
+            }
+            let free = free.max(0.0);
+
+            let (mut position, spacing) = match self.justify {
+                FlexJustify::Start => (0.0, self.gap),
+                FlexJustify::End => (free, self.gap),
+                FlexJustify::Center => (free / 2.0, self.gap),
+                FlexJustify::SpaceBetween if count > 1.0 => (0.0, self.gap + free / (count - 1.0)),
+                FlexJustify::SpaceBetween => (0.0, self.gap),
+                FlexJustify::SpaceAround => (free / count / 2.0, self.gap + free / count),
+                FlexJustify::SpaceEvenly => (free / (count + 1.0), self.gap + free / (count + 1.0)),
+            };
+            let line_cross = if self.wrap {
+                line.iter().map(|i| sizes[*i].1).fold(0.0, f32::max)
+            } else {
+                inner_cross
+            };
+
+            for (main, i) in mains.into_iter().zip(line) {
+                let mut cross = sizes[*i].1;
+                let offset = match items[*i].1.align.unwrap_or(self.align) {
+                    FlexAlign::Start => 0.0,
+                    FlexAlign::End => line_cross - cross,
+                    FlexAlign::Center => (line_cross - cross) / 2.0,
+                    FlexAlign::Stretch => {
+                        cross = line_cross;
+                        0.0
+                    }
+                };
+                let main_position = if reverse {
+                    inner_main - position - main
+                } else {
+                    position
+                };
+                let (main_position, cross_position) = (
+                    self.padding + main_position,
+                    self.padding + line_start + offset,
+                );
+                rects[*i] = if row {
+                    (main_position, cross_position, main, cross)
+                } else {
+                    (cross_position, main_position, cross, main)
+                };
+                position += main + spacing;
+            }
+            line_start += line_cross + self.gap;
+        }
+        rects
+    }
+}
+
+impl UiFlexItem {
+    /// Creates an item growing and shrinking by given factors.
+    #[must_use]
+    pub fn new(grow: f32, shrink: f32) -> Self {
+        Self {
+            grow,
+            shrink,
+            ..Self::default()
+        }
+    }
+
+    /// Lays the child out from given size along the direction of the container.
+    #[must_use]
+    pub fn with_basis(mut self, basis: f32) -> Self {
+        self.basis = Some(basis);
+        self
+    }
+
+    /// Sets the placement of the child in its line.
+    #[must_use]
+    pub fn with_align(mut self, align: FlexAlign) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Sets the position of the child among the others.
+    #[must_use]
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_space_is_shared_by_growing_children() {
+        let container = UiFlexContainer::new(FlexDirection::Row)
+            .with_gap(10.0)
+            .with_align(FlexAlign::Center);
+        let rects = container.layout(
+            200.0,
+            50.0,
+            &[
+                ((50.0, 20.0), UiFlexItem::default()),
+                ((30.0, 50.0), UiFlexItem::new(1.0, 0.0)),
+            ],
+        );
+        assert_eq!(
+            rects,
+            vec![(0.0, 15.0, 50.0, 20.0), (60.0, 0.0, 140.0, 50.0)]
+        );
+    }
+
+    #[test]
+    fn overflowing_children_wrap() {
+        let container = UiFlexContainer::new(FlexDirection::Row)
+            .with_wrap(true)
+            .with_justify(FlexJustify::End)
+            .with_align(FlexAlign::Start);
+        let item = UiFlexItem::default();
+        let rects = container.layout(
+            100.0,
+            100.0,
+            &[
+                ((60.0, 20.0), item),
+                ((30.0, 10.0), item),
+                ((50.0, 30.0), item),
+            ],
+        );
+        assert_eq!(
+            rects,
+            vec![
+                (10.0, 0.0, 60.0, 20.0),
+                (70.0, 0.0, 30.0, 10.0),
+                (50.0, 20.0, 50.0, 30.0)
+            ]
+        );
+    }
+}
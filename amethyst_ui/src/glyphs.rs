@@ -1,16 +1,24 @@
 //! Module containing the system managing glyphbrush state for visible UI Text components.
 
-use std::{collections::HashMap, marker::PhantomData, ops::Deref};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    ops::{Deref, Range},
+};
 
 use amethyst_assets::{
     AssetHandle, AssetStorage, DefaultLoader, Handle, LoadHandle, Loader, ProcessingQueue,
     ProcessingState,
 };
 use amethyst_core::{
-    ecs::{component, Entity, IntoQuery, ParallelRunnable, Resources, System, SystemBuilder},
+    ecs::{
+        component, DispatcherBuilder, Entity, IntoQuery, ParallelRunnable, Resources, System,
+        SystemBuilder,
+    },
     Hidden, HiddenPropagate,
 };
 use amethyst_rendy::{
+    palette,
     rendy::{
         command::QueueId,
         factory::{Factory, ImageState},
@@ -21,8 +29,10 @@ use amethyst_rendy::{
     Backend, Texture,
 };
 use glyph_brush::{
-    rusttype::Scale, BrushAction, BrushError, BuiltInLineBreaker, FontId, GlyphBrush,
-    GlyphBrushBuilder, GlyphCruncher, Layout, LineBreak, LineBreaker, SectionText, VariedSection,
+    rusttype::{Font, GlyphId, Scale},
+    BrushAction, BrushError, BuiltInLineBreaker, FontId, GlyphBrush, GlyphBrushBuilder,
+    GlyphCruncher, HorizontalAlign, Layout, LineBreak, LineBreaker, SectionText, VariedSection,
+    VerticalAlign,
 };
 use log::debug;
 use serde::Deserialize;
@@ -30,8 +40,13 @@ use type_uuid::TypeUuid;
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    format::FontData, get_default_font, pass::UiArgs, text::CachedGlyph, FontAsset, LineMode,
-    Selected, TextEditing, UiText, UiTransform,
+    bidi::{wrap_offsets, VisualText},
+    format::FontData,
+    get_default_font,
+    pass::{UiArgs, NO_CLIP},
+    text::CachedGlyph,
+    world_text::{WorldGlyphs, WorldText},
+    FontAsset, LineMode, Selected, TextEditing, UiText, UiTransform,
 };
 
 #[derive(Debug)]
@@ -61,6 +76,22 @@ impl UiGlyphsResource {
     }
 }
 
+/// Adds the systems laying out glyphs and their resources, unless already added by another
+/// plugin drawing text.
+pub(crate) fn add_glyph_systems<B: Backend>(
+    resources: &mut Resources,
+    builder: &mut DispatcherBuilder,
+) {
+    if resources.contains::<UiGlyphsResource>() {
+        return;
+    }
+    resources.insert(UiGlyphsResource::new(resources));
+
+    builder
+        .add_system(GlyphTextureProcessorSystem::<B>::default())
+        .add_system(UiGlyphsSystem::<B>::default());
+}
+
 #[derive(Debug, Clone)]
 pub struct UiGlyphs {
     pub(crate) sel_vertices: Vec<UiArgs>,
@@ -87,7 +118,7 @@ impl FontState {
 }
 
 #[derive(Debug, Hash, Clone, Copy)]
-enum CustomLineBreaker {
+pub(crate) enum CustomLineBreaker {
     BuiltIn(BuiltInLineBreaker),
     None,
 }
@@ -101,6 +132,26 @@ impl LineBreaker for CustomLineBreaker {
     }
 }
 
+/// Returns the layout of the glyphs of a text in `line_mode`.
+pub(crate) fn text_layout(
+    line_mode: LineMode,
+    h_align: HorizontalAlign,
+    v_align: VerticalAlign,
+) -> Layout<CustomLineBreaker> {
+    match line_mode {
+        LineMode::Single => Layout::SingleLine {
+            line_breaker: CustomLineBreaker::None,
+            h_align,
+            v_align,
+        },
+        LineMode::Wrap => Layout::Wrap {
+            line_breaker: CustomLineBreaker::BuiltIn(BuiltInLineBreaker::UnicodeLineBreaker),
+            h_align,
+            v_align,
+        },
+    }
+}
+
 /// Manages the text editing cursor create, deletion and position.
 #[derive(Debug)]
 pub struct UiGlyphsSystem<B: Backend> {
@@ -200,7 +251,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                         Entity,
                         &UiTransform,
                         &mut UiText,
-                        Option<&TextEditing>,
+                        Option<&mut TextEditing>,
                         Option<&Tint>,
                     )>::query()
                     .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
@@ -217,6 +268,11 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                 )
                 .with_query(<(Entity, &mut UiGlyphs)>::query())
                 .with_query(<(Entity, &mut Selected)>::query())
+                .with_query(
+                    <(Entity, &WorldText, Option<&Tint>)>::query()
+                        .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
+                )
+                .with_query(<(Entity, &mut WorldGlyphs)>::query())
                 .build(
                     move |commands,
                           world,
@@ -234,6 +290,8 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                         not_hidden_glyphs_query_with_editing,
                         glyphs_query,
                         selected_query,
+                        world_texts_query,
+                        world_glyphs_query,
                     )| {
                         let queue = **fetch_queue.deref();
 
@@ -284,16 +342,90 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                         (font_lookup.id(), font_asset)
                                     {
                                         let tint_color = tint.map_or([1., 1., 1., 1.], |t| {
-                                            let (r, g, b, a) = t.0.into_components();
+                                            let (r, g, b, a) = t.0.into_linear().into_components();
                                             [r, g, b, a]
                                         });
 
-                                        let base_color = mul_blend(&ui_text.color, &tint_color);
+                                        let base_color = mul_blend(
+                                            &srgba_to_linear(&ui_text.color),
+                                            &tint_color,
+                                        );
 
                                         let scale = Scale::uniform(ui_text.font_size);
 
-                                        let text = match (ui_text.password, editing) {
-                                            (false, None) => {
+                                        let layout = text_layout(
+                                            ui_text.line_mode,
+                                            ui_text.align.horizontal_align(),
+                                            ui_text.align.vertical_align(),
+                                        );
+
+                                        // Right-to-left text is laid out in visual order, each of
+                                        // its wrapped lines being reordered on its own.
+                                        let visual = if ui_text.password {
+                                            None
+                                        } else {
+                                            VisualText::new(&ui_text.text, || {
+                                                match ui_text.line_mode {
+                                                    LineMode::Single => Vec::new(),
+                                                    LineMode::Wrap => {
+                                                        let logical = VariedSection {
+                                                            screen_position: (0., 0.),
+                                                            bounds: (
+                                                                transform.pixel_width,
+                                                                f32::INFINITY,
+                                                            ),
+                                                            z: 0.,
+                                                            layout: Layout::default(),
+                                                            text: vec![SectionText {
+                                                                text: &ui_text.text,
+                                                                scale,
+                                                                color: base_color,
+                                                                font_id,
+                                                            }],
+                                                        };
+                                                        let ys = self
+                                                            .glyph_brush
+                                                            .glyphs_custom_layout(&logical, &layout)
+                                                            .map(|g| g.position().y);
+                                                        wrap_offsets(&ui_text.text, ys)
+                                                    }
+                                                }
+                                            })
+                                        };
+
+                                        let text = match (
+                                            ui_text.password,
+                                            editing.as_deref(),
+                                            visual.as_ref(),
+                                        ) {
+                                            (_, sel, Some(visual)) => {
+                                                visual_sections(
+                                                    visual,
+                                                    sel.and_then(|sel| {
+                                                        selection_span(sel, &ui_text.text)
+                                                    }),
+                                                    &ui_text.text,
+                                                )
+                                                .into_iter()
+                                                .map(|(range, selected)| {
+                                                    SectionText {
+                                                        text: &visual.text[range],
+                                                        scale,
+                                                        color: match sel {
+                                                            Some(sel) if selected => mul_blend(
+                                                                &srgba_to_linear(
+                                                                    &sel.selected_text_color,
+                                                                ),
+                                                                &tint_color,
+                                                            ),
+                                                            _ => base_color,
+                                                        },
+                                                        font_id,
+                                                    }
+                                                })
+                                                .collect()
+                                            }
+                                            (false, None, None) => {
                                                 vec![SectionText {
                                                     text: &ui_text.text,
                                                     scale,
@@ -301,7 +433,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                     font_id,
                                                 }]
                                             }
-                                            (false, Some(sel)) => {
+                                            (false, Some(sel), None) => {
                                                 if let Some((start, end)) =
                                                     selection_span(sel, &ui_text.text)
                                                 {
@@ -316,7 +448,9 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                             text: &ui_text.text[start..end],
                                                             scale,
                                                             color: mul_blend(
-                                                                &sel.selected_text_color,
+                                                                &srgba_to_linear(
+                                                                    &sel.selected_text_color,
+                                                                ),
                                                                 &tint_color,
                                                             ),
                                                             font_id,
@@ -337,7 +471,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                     }]
                                                 }
                                             }
-                                            (true, None) => {
+                                            (true, None, _) => {
                                                 let string_len =
                                                     ui_text.text.graphemes(true).count();
                                                 password_sections(string_len)
@@ -351,7 +485,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                     })
                                                     .collect()
                                             }
-                                            (true, Some(sel)) => {
+                                            (true, Some(sel), _) => {
                                                 let string_len =
                                                     ui_text.text.graphemes(true).count();
                                                 let pos = sel.cursor_position;
@@ -366,7 +500,9 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                     (
                                                         to_end,
                                                         mul_blend(
-                                                            &sel.selected_text_color,
+                                                            &srgba_to_linear(
+                                                                &sel.selected_text_color,
+                                                            ),
                                                             &tint_color,
                                                         ),
                                                     ),
@@ -390,23 +526,33 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                             }
                                         };
 
-                                        let layout = match ui_text.line_mode {
-                                            LineMode::Single => {
-                                                Layout::SingleLine {
-                                                    line_breaker: CustomLineBreaker::None,
-                                                    h_align: ui_text.align.horizontal_align(),
-                                                    v_align: ui_text.align.vertical_align(),
-                                                }
+                                        let mut fallbacks = Vec::new();
+                                        for handle in &ui_text.fallback_fonts {
+                                            let fallback_id = font_id(
+                                                &mut self.fonts_map,
+                                                &mut self.glyph_brush,
+                                                font_storage,
+                                                handle,
+                                            );
+                                            if let (Some(fallback_id), Some(fallback)) =
+                                                (fallback_id, font_storage.get(handle))
+                                            {
+                                                fallbacks.push((fallback_id, &fallback.0));
                                             }
-                                            LineMode::Wrap => {
-                                                Layout::Wrap {
-                                                    line_breaker: CustomLineBreaker::BuiltIn(
-                                                        BuiltInLineBreaker::UnicodeLineBreaker,
-                                                    ),
-                                                    h_align: ui_text.align.horizontal_align(),
-                                                    v_align: ui_text.align.vertical_align(),
+                                        }
+                                        let text = if fallbacks.is_empty() {
+                                            text
+                                        } else {
+                                            fallback_sections(text, |c| {
+                                                if has_glyph(&font_asset, c) {
+                                                    None
+                                                } else {
+                                                    fallbacks
+                                                        .iter()
+                                                        .find(|(_, font)| has_glyph(font, c))
+                                                        .map(|(fallback_id, _)| *fallback_id)
                                                 }
-                                            }
+                                            })
                                         };
 
                                         let next_z = self
@@ -417,7 +563,14 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
 
                                         self.glyph_entity_cache.insert(next_z, *entity);
 
-                                        let section = VariedSection {
+                                        // Wrapped editable texts scroll vertically, so all their
+                                        // lines are laid out and the pass clips them instead.
+                                        let scroll = editing
+                                            .as_ref()
+                                            .filter(|_| ui_text.line_mode == LineMode::Wrap)
+                                            .map(|editing| editing.scroll);
+
+                                        let mut section = VariedSection {
                                             // Needs a recenter because we are using [-0.5,0.5] for the mesh
                                             // instead of the expected [0,1]
                                             screen_position: (
@@ -427,9 +580,15 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                 // invert y because layout calculates it in reverse
                                                 -(transform.pixel_y
                                                     + transform.pixel_height
-                                                        * ui_text.align.norm_offset().1),
+                                                        * ui_text.align.norm_offset().1)
+                                                    - scroll.unwrap_or(0.),
+                                            ),
+                                            bounds: (
+                                                transform.pixel_width,
+                                                scroll.map_or(transform.pixel_height, |_| {
+                                                    f32::INFINITY
+                                                }),
                                             ),
-                                            bounds: (transform.pixel_width, transform.pixel_height),
                                             // There is no other way to inject some glyph metadata than using Z.
                                             // Fortunately depth is not required, so this slot is instead used to
                                             // distinguish computed glyphs indented to be used for various entities
@@ -459,18 +618,27 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                     x: pos.x,
                                                     y: -pos.y,
                                                     advance_width,
+                                                    rtl: false,
                                                 }
                                             });
 
+                                        let laid_out = visual
+                                            .as_ref()
+                                            .map_or(ui_text.text.as_str(), |visual| {
+                                                visual.text.as_str()
+                                            });
                                         let mut last_cached_glyph: Option<CachedGlyph> = None;
-                                        let all_glyphs = ui_text.text.chars().filter_map(|c| {
+                                        let all_glyphs = laid_out.chars().map(|c| {
                                             if c.is_whitespace() {
-                                                let (x, y) = last_cached_glyph.map_or((0.0, 0.0), |last_cached_glyph| {
-                                                    let x = last_cached_glyph.x
-                                                        + last_cached_glyph.advance_width;
-                                                    let y = last_cached_glyph.y;
-                                                    (x, y)
-                                                });
+                                                let (x, y) = last_cached_glyph.map_or(
+                                                    (0.0, 0.0),
+                                                    |last_cached_glyph| {
+                                                        let x = last_cached_glyph.x
+                                                            + last_cached_glyph.advance_width;
+                                                        let y = last_cached_glyph.y;
+                                                        (x, y)
+                                                    },
+                                                );
 
                                                 let advance_width = font_asset
                                                     .glyph(c)
@@ -482,6 +650,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                     x,
                                                     y,
                                                     advance_width,
+                                                    rtl: false,
                                                 };
                                                 last_cached_glyph = Some(cached_glyph);
                                             } else {
@@ -489,13 +658,109 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                             }
                                             last_cached_glyph
                                         });
-                                        ui_text.cached_glyphs.extend(all_glyphs);
+                                        match &visual {
+                                            None => {
+                                                ui_text.cached_glyphs.extend(all_glyphs.flatten())
+                                            }
+                                            Some(visual) => {
+                                                // Glyphs are cached in the logical order of the
+                                                // text, which the cursor moves through.
+                                                let mut logical_glyphs =
+                                                    vec![None; visual.rtl.len()];
+                                                let visual_glyphs = all_glyphs.zip(&visual.logical);
+                                                for (glyph, index) in visual_glyphs {
+                                                    if let Some(index) = *index {
+                                                        logical_glyphs[index] =
+                                                            glyph.map(|glyph| CachedGlyph {
+                                                                rtl: visual.rtl[index],
+                                                                ..glyph
+                                                            });
+                                                    }
+                                                }
+                                                ui_text
+                                                    .cached_glyphs
+                                                    .extend(logical_glyphs.into_iter().flatten());
+                                            }
+                                        }
+
+                                        if let (Some(editing), Some(current)) = (editing, scroll) {
+                                            let v_metrics = font_asset.v_metrics(scale);
+                                            let scroll = scroll_to_cursor(
+                                                current,
+                                                &ui_text.cached_glyphs,
+                                                editing.cursor_position as usize,
+                                                transform,
+                                                (v_metrics.ascent, v_metrics.descent),
+                                            );
+                                            let delta = scroll - current;
+                                            if delta.abs() > f32::EPSILON {
+                                                editing.scroll = scroll;
+                                                section.screen_position.1 -= delta;
+                                                for glyph in &mut ui_text.cached_glyphs {
+                                                    glyph.y += delta;
+                                                }
+                                            }
+                                        }
 
                                         self.glyph_brush.queue_custom_layout(section, &layout);
                                     }
                                 },
                             );
 
+                            // World texts are laid out around the origin, in pixels, and placed
+                            // in the world when drawn.
+                            let mut world_text_entities = Vec::new();
+                            for (entity, world_text, tint) in world_texts_query.iter(&else_world) {
+                                let font_handle =
+                                    world_text.font.as_ref().unwrap_or(&glyphs_res.default_font);
+                                let font_id = match font_id(
+                                    &mut self.fonts_map,
+                                    &mut self.glyph_brush,
+                                    font_storage,
+                                    font_handle,
+                                ) {
+                                    Some(font_id) => font_id,
+                                    None => continue,
+                                };
+
+                                let tint_color = tint.map_or([1., 1., 1., 1.], |t| {
+                                    let (r, g, b, a) = t.0.into_linear().into_components();
+                                    [r, g, b, a]
+                                });
+
+                                let next_z = self
+                                    .glyph_entity_cache
+                                    .keys()
+                                    .last()
+                                    .map_or(0, |val| val + 1);
+                                self.glyph_entity_cache.insert(next_z, *entity);
+                                world_text_entities.push(*entity);
+
+                                let section = VariedSection {
+                                    screen_position: (0., 0.),
+                                    bounds: (f32::INFINITY, f32::INFINITY),
+                                    z: next_z as f32,
+                                    layout: glyph_brush::Layout::default(),
+                                    text: vec![SectionText {
+                                        text: &world_text.text,
+                                        scale: Scale::uniform(world_text.font_size),
+                                        color: mul_blend(
+                                            &srgba_to_linear(&world_text.color),
+                                            &tint_color,
+                                        ),
+                                        font_id,
+                                    }],
+                                };
+                                let layout = Layout::Wrap {
+                                    line_breaker: CustomLineBreaker::BuiltIn(
+                                        BuiltInLineBreaker::UnicodeLineBreaker,
+                                    ),
+                                    h_align: world_text.align.horizontal_align(),
+                                    v_align: world_text.align.vertical_align(),
+                                };
+                                self.glyph_brush.queue_custom_layout(section, &layout);
+                            }
+
                             loop {
                                 let action = self.glyph_brush.process_queued(
                                     |rect, data| unsafe {
@@ -605,6 +870,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                 tex_coord_bounds: tex_coord_bounds.into(),
                                                 color: glyph.color.into(),
                                                 color_bias: [1., 1., 1., 0.].into(),
+                                                clip: NO_CLIP.into(),
                                             },
                                         )
                                     },
@@ -688,11 +954,13 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                         let tint_color =
                                                             tint.map_or([1., 1., 1., 1.], |t| {
                                                                 let (r, g, b, a) =
-                                                                    t.0.into_components();
+                                                                    t.0.into_linear()
+                                                                        .into_components();
                                                                 [r, g, b, a]
                                                             });
-                                                        let bg_color =
-                                                            editing.selected_background_color;
+                                                        let bg_color = srgba_to_linear(
+                                                            &editing.selected_background_color,
+                                                        );
                                                         let bg_color = if selected_query
                                                             .get_mut(&mut selected_world, *entity)
                                                             .is_ok()
@@ -729,6 +997,7 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                                     color: bg_color.into(),
                                                                     color_bias: [1., 1., 1., 0.]
                                                                         .into(),
+                                                                    clip: NO_CLIP.into(),
                                                                 }
                                                             });
 
@@ -755,6 +1024,38 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
                                                 }
                                             },
                                         );
+
+                                        for (_, world_glyphs) in
+                                            world_glyphs_query.iter_mut(&mut else_world)
+                                        {
+                                            world_glyphs.vertices.clear();
+                                        }
+                                        for entity in &world_text_entities {
+                                            let len = vertices[glyph_ctr..]
+                                                .iter()
+                                                .take_while(|(id, _)| {
+                                                    self.glyph_entity_cache.get(id).unwrap()
+                                                        == entity
+                                                })
+                                                .count();
+                                            let entity_verts = vertices[glyph_ctr..glyph_ctr + len]
+                                                .iter()
+                                                .map(|v| v.1);
+                                            glyph_ctr += len;
+
+                                            if let Ok((_, world_glyphs)) =
+                                                world_glyphs_query.get_mut(&mut else_world, *entity)
+                                            {
+                                                world_glyphs.vertices.extend(entity_verts);
+                                            } else {
+                                                commands.add_component(
+                                                    *entity,
+                                                    WorldGlyphs {
+                                                        vertices: entity_verts.collect(),
+                                                    },
+                                                );
+                                            }
+                                        }
                                         break;
                                     }
                                     Ok(BrushAction::ReDraw) => {
@@ -808,6 +1109,63 @@ impl<B: Backend> System for UiGlyphsSystem<B> {
     }
 }
 
+/// Whether `font` has a glyph for `c`, rather than drawing it with its missing glyph.
+fn has_glyph(font: &Font<'_>, c: char) -> bool {
+    c.is_whitespace() || font.glyph(c).id() != GlyphId(0)
+}
+
+/// Splits `sections` into runs of characters drawn with the same font, `font_of` returning the
+/// fallback font of the characters missing from the font of their section.
+fn fallback_sections<'a>(
+    sections: Vec<SectionText<'a>>,
+    font_of: impl Fn(char) -> Option<FontId>,
+) -> Vec<SectionText<'a>> {
+    let mut runs = Vec::with_capacity(sections.len());
+    for section in sections {
+        let mut start = 0;
+        let mut run_font = section.font_id;
+        for (index, c) in section.text.char_indices() {
+            let font = font_of(c).unwrap_or(section.font_id);
+            if font != run_font {
+                if index > start {
+                    runs.push(SectionText {
+                        text: &section.text[start..index],
+                        font_id: run_font,
+                        ..section
+                    });
+                }
+                start = index;
+                run_font = font;
+            }
+        }
+        runs.push(SectionText {
+            text: &section.text[start..],
+            font_id: run_font,
+            ..section
+        });
+    }
+    runs
+}
+
+/// Id of a font in the glyph brush, adding it once loaded.
+fn font_id(
+    fonts_map: &mut HashMap<LoadHandle, FontState>,
+    glyph_brush: &mut GlyphBrush<'static, (u32, UiArgs)>,
+    font_storage: &AssetStorage<FontAsset>,
+    font_handle: &Handle<FontAsset>,
+) -> Option<FontId> {
+    let state = fonts_map
+        .entry(font_handle.load_handle())
+        .or_insert(FontState::NotFound);
+    if let FontState::NotFound = state {
+        if let Some(font) = font_storage.get(font_handle) {
+            log::debug!("Adding font to glyph brush.");
+            *state = FontState::Ready(glyph_brush.add_font(font.0.clone()));
+        }
+    }
+    state.id()
+}
+
 fn update_cursor_position(
     glyph_data: &mut UiGlyphs,
     ui_text: &UiText,
@@ -816,9 +1174,9 @@ fn update_cursor_position(
     offset: f32,
 ) {
     glyph_data.cursor_pos = if let Some(glyph) = ui_text.cached_glyphs.get(pos) {
-        (glyph.x, glyph.y + offset)
+        (glyph.leading_x(), glyph.y + offset)
     } else if let Some(glyph) = ui_text.cached_glyphs.last() {
-        (glyph.x + glyph.advance_width, glyph.y + offset)
+        (glyph.trailing_x(), glyph.y + offset)
     } else {
         (
             transform.pixel_x() + transform.pixel_width * ui_text.align.norm_offset().0,
@@ -827,6 +1185,43 @@ fn update_cursor_position(
     };
 }
 
+/// Scroll of a wrapped editable text keeping the cursor before the glyph at `index` inside its
+/// `transform`, from its `glyphs` laid out with the scroll `current`.
+///
+/// The lines are kept over the whole height of the transform when they are taller than it.
+fn scroll_to_cursor(
+    current: f32,
+    glyphs: &[CachedGlyph],
+    index: usize,
+    transform: &UiTransform,
+    (ascent, descent): (f32, f32),
+) -> f32 {
+    let min_y = transform.pixel_y - transform.pixel_height / 2.;
+    let max_y = transform.pixel_y + transform.pixel_height / 2.;
+    // Baselines of the first and last lines, without scroll.
+    let (highest, lowest) = match glyphs.iter().map(|glyph| glyph.y - current).fold(
+        None,
+        |bounds: Option<(f32, f32)>, y| {
+            Some(bounds.map_or((y, y), |(high, low)| (high.max(y), low.min(y))))
+        },
+    ) {
+        Some(bounds) => bounds,
+        None => return 0.,
+    };
+    let min_scroll = (max_y - (highest + ascent)).min(0.);
+    let max_scroll = (min_y - (lowest + descent)).max(0.);
+
+    let cursor = glyphs
+        .get(index)
+        .or_else(|| glyphs.last())
+        .map_or(lowest, |glyph| glyph.y - current);
+    current
+        .max(min_y - descent - cursor)
+        .min(max_y - ascent - cursor)
+        .max(min_scroll)
+        .min(max_scroll)
+}
+
 fn selection_span(editing: &TextEditing, string: &str) -> Option<(usize, usize)> {
     if editing.highlight_vector == 0 {
         return None;
@@ -849,10 +1244,32 @@ fn selection_span(editing: &TextEditing, string: &str) -> Option<(usize, usize)>
     }
 }
 
+/// Byte ranges of the `visual` text of `string`, split at the bounds of the byte span `selection`
+/// of `string`, and whether they're selected.
+fn visual_sections(
+    visual: &VisualText,
+    selection: Option<(usize, usize)>,
+    string: &str,
+) -> Vec<(Range<usize>, bool)> {
+    let selected = selection.map(|(start, end)| {
+        let start_char = string[..start].chars().count();
+        start_char..start_char + string[start..end].chars().count()
+    });
+    visual.spans(|index| selected.as_ref().map_or(false, |chars| chars.contains(&index)))
+}
+
 fn mul_blend(a: &[f32; 4], b: &[f32; 4]) -> [f32; 4] {
     [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
 }
 
+/// Text colors are authored in sRGB, the vertex colors are blended in linear space.
+pub(crate) fn srgba_to_linear(color: &[f32; 4]) -> [f32; 4] {
+    let (r, g, b, a) = palette::Srgba::new(color[0], color[1], color[2], color[3])
+        .into_linear()
+        .into_components();
+    [r, g, b, a]
+}
+
 const PASSWORD_STR: &str = "\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}\u{2022}";
 const PASSWORD_STR_GRAPHEMES: usize = 16; // 3 bytes per grapheme
 fn password_sections(len: usize) -> impl Iterator<Item = &'static str> {
@@ -862,3 +1279,37 @@ fn password_sections(len: usize) -> impl Iterator<Item = &'static str> {
         .take(full_chunks)
         .chain(Some(&PASSWORD_STR[0..last_len * 3]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_characters_are_split_into_fallback_runs() {
+        let section = |text| {
+            SectionText {
+                text,
+                font_id: FontId(0),
+                ..SectionText::default()
+            }
+        };
+        let runs = fallback_sections(vec![section("ab日本c"), section("日")], |c| {
+            if c.is_ascii() {
+                None
+            } else {
+                Some(FontId(1))
+            }
+        });
+
+        let runs: Vec<_> = runs.iter().map(|run| (run.text, run.font_id)).collect();
+        assert_eq!(
+            vec![
+                ("ab", FontId(0)),
+                ("日本", FontId(1)),
+                ("c", FontId(0)),
+                ("日", FontId(1)),
+            ],
+            runs
+        );
+    }
+}
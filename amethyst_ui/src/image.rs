@@ -1,5 +1,7 @@
 use amethyst_assets::Handle;
-use amethyst_rendy::{SpriteRender, Texture};
+use amethyst_rendy::{sprite::SpriteBorders, SpriteRender, Texture};
+
+use crate::glyphs::srgba_to_linear;
 
 /// Image used UI widgets, often as background.
 #[derive(Debug, Clone, PartialEq)]
@@ -44,27 +46,271 @@ pub enum UiImage {
         /// Dimensions of the entire texture
         texture_dimensions: [u32; 2],
     },
+    /// An image backed by a Sprite, split into nine slices so that its borders keep their size
+    /// while the rest stretches over the widget
+    NineSliceSprite {
+        /// Sprite to render
+        sprite: SpriteRender,
+        /// Borders of the sprite in pixels
+        borders: SpriteBorders,
+    },
+    /// An image backed by a whole texture, split into nine slices so that its borders keep their
+    /// size while the rest stretches over the widget
+    NinePatch {
+        /// Texture handle
+        texture: Handle<Texture>,
+        /// Borders of the texture in pixels
+        borders: SpriteBorders,
+    },
     /// An image entirely covered by single solid color
-    /// This tuple takes linear RGBA. You can convert rgba to linear rgba like so:
+    /// This tuple takes sRGBA, like the colors of `UiText`, e.g. picked in an image editor:
     ///
     /// ```
-    /// use amethyst::{renderer::palette::Srgba, ui::UiImage};
+    /// use amethyst::ui::UiImage;
     ///
-    /// let your_red: f32 = 255.;
-    /// let your_green: f32 = 160.;
-    /// let your_blue: f32 = 122.;
-    /// let your_alpha: f32 = 1.0;
+    /// let (red, green, blue) = (255., 160., 122.);
     ///
-    /// let (r, g, b, a) = Srgba::new(
-    ///     your_red / 255.,
-    ///     your_green / 255.,
-    ///     your_blue / 255.,
-    ///     your_alpha,
-    /// )
-    /// .into_linear()
-    /// .into_components();
-    ///
-    /// UiImage::SolidColor([r, g, b, a]);
+    /// UiImage::SolidColor([red / 255., green / 255., blue / 255., 1.0]);
     /// ```
     SolidColor([f32; 4]),
+    /// A gradient between two sRGBA colors, from the left to the right of the widget, or from the
+    /// bottom to the top if `vertical`. The colors are interpolated in linear space.
+    LinearGradient {
+        /// Color at the left or bottom edge
+        start: [f32; 4],
+        /// Color at the right or top edge
+        end: [f32; 4],
+        /// Whether the gradient goes up instead of right
+        vertical: bool,
+    },
+    /// A gradient between two sRGBA colors, from the center of the widget to its edges. The colors
+    /// are interpolated in linear space.
+    RadialGradient {
+        /// Color at the center
+        center: [f32; 4],
+        /// Color at the edges and beyond, in the corners
+        edge: [f32; 4],
+    },
+    /// A rectangle with rounded corners and a border, in sRGBA, whose corners and border keep
+    /// their size while the rest stretches over the widget.
+    RoundedRect {
+        /// Color inside the border
+        color: [f32; 4],
+        /// Radius of the corners in pixels
+        radius: f32,
+        /// Color of the border
+        border_color: [f32; 4],
+        /// Width of the border in pixels, `0.0` for none
+        border_width: f32,
+    },
+}
+
+/// Number of texels of the gradient textures along their gradient.
+const GRADIENT_SIZE: u32 = 128;
+/// Maximum size of the corners of the `UiImage::RoundedRect` textures, in pixels.
+const MAX_CORNER_SIZE: u32 = 256;
+
+/// Texture drawing a procedural `UiImage`, generated by the `DrawUi` pass.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProceduralTexture {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// sRGB pixels, from the top left corner row by row.
+    pub(crate) pixels: Vec<[u8; 4]>,
+}
+
+impl UiImage {
+    /// Returns a key identifying the texture of a procedural image, equal for the images drawn
+    /// with the same texture, or `None` for the other images.
+    pub(crate) fn procedural_key(&self) -> Option<Vec<u32>> {
+        let (kind, floats): (u32, Vec<f32>) = match self {
+            UiImage::LinearGradient {
+                start,
+                end,
+                vertical,
+            } => (u32::from(*vertical), start.iter().chain(end).copied().collect()),
+            UiImage::RadialGradient { center, edge } => {
+                (2, center.iter().chain(edge).copied().collect())
+            }
+            UiImage::RoundedRect {
+                color,
+                radius,
+                border_color,
+                border_width,
+            } => {
+                let mut floats: Vec<f32> = color.iter().chain(border_color).copied().collect();
+                floats.extend_from_slice(&[*radius, *border_width]);
+                (3, floats)
+            }
+            _ => return None,
+        };
+        Some(
+            std::iter::once(kind)
+                .chain(floats.into_iter().map(f32::to_bits))
+                .collect(),
+        )
+    }
+
+    /// Generates the texture of a procedural image, or returns `None` for the other images.
+    pub(crate) fn procedural_texture(&self) -> Option<ProceduralTexture> {
+        match self {
+            UiImage::LinearGradient {
+                start,
+                end,
+                vertical,
+            } => {
+                let (start, end) = (srgba_to_linear(start), srgba_to_linear(end));
+                let texel = |index: u32| {
+                    to_srgb8(lerp(&start, &end, index as f32 / (GRADIENT_SIZE - 1) as f32))
+                };
+                Some(if *vertical {
+                    // Rows go from the top to the bottom.
+                    ProceduralTexture {
+                        width: 1,
+                        height: GRADIENT_SIZE,
+                        pixels: (0..GRADIENT_SIZE).rev().map(texel).collect(),
+                    }
+                } else {
+                    ProceduralTexture {
+                        width: GRADIENT_SIZE,
+                        height: 1,
+                        pixels: (0..GRADIENT_SIZE).map(texel).collect(),
+                    }
+                })
+            }
+            UiImage::RadialGradient { center, edge } => {
+                let (center, edge) = (srgba_to_linear(center), srgba_to_linear(edge));
+                let half = GRADIENT_SIZE as f32 / 2.0;
+                let pixels = (0..GRADIENT_SIZE * GRADIENT_SIZE)
+                    .map(|index| {
+                        let x = (index % GRADIENT_SIZE) as f32 + 0.5 - half;
+                        let y = (index / GRADIENT_SIZE) as f32 + 0.5 - half;
+                        let distance = (x * x + y * y).sqrt() / half;
+                        to_srgb8(lerp(&center, &edge, distance.min(1.0)))
+                    })
+                    .collect();
+                Some(ProceduralTexture {
+                    width: GRADIENT_SIZE,
+                    height: GRADIENT_SIZE,
+                    pixels,
+                })
+            }
+            UiImage::RoundedRect {
+                color,
+                radius,
+                border_color,
+                border_width,
+            } => {
+                let (color, border_color) = (srgba_to_linear(color), srgba_to_linear(border_color));
+                let corner = rounded_rect_corner(*radius, *border_width);
+                let radius = radius.max(0.0);
+                let border_width = border_width.max(0.0);
+                let size = corner * 2 + 2;
+                let half = size as f32 / 2.0;
+                let radius = radius.min(half);
+                let pixels = (0..size * size)
+                    .map(|index| {
+                        // Signed distance from the edge of the rectangle, negative inside.
+                        let x = ((index % size) as f32 + 0.5 - half).abs() - (half - radius);
+                        let y = ((index / size) as f32 + 0.5 - half).abs() - (half - radius);
+                        let distance = (x.max(0.0).powi(2) + y.max(0.0).powi(2)).sqrt()
+                            + x.max(y).min(0.0)
+                            - radius;
+                        let fill = (-distance - border_width + 0.5).clamp(0.0, 1.0);
+                        let mut texel = lerp(&border_color, &color, fill);
+                        // Transparent texels keep the color of the edge, so it doesn't darken
+                        // when filtered.
+                        texel[3] *= (0.5 - distance).clamp(0.0, 1.0);
+                        to_srgb8(texel)
+                    })
+                    .collect();
+                Some(ProceduralTexture {
+                    width: size,
+                    height: size,
+                    pixels,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the size of the corner slices of the texture of a `UiImage::RoundedRect`, one more
+/// pixel than the corner so its antialiased edge isn't stretched.
+pub(crate) fn rounded_rect_corner(radius: f32, border_width: f32) -> u32 {
+    (radius.max(border_width).max(0.0).ceil() as u32 + 1).min(MAX_CORNER_SIZE)
+}
+
+fn lerp(a: &[f32; 4], b: &[f32; 4], t: f32) -> [f32; 4] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+/// Converts a linear RGBA color to 8 bits sRGB.
+fn to_srgb8(color: [f32; 4]) -> [u8; 4] {
+    let encode = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    let byte = |c: f32| (c * 255.0).round() as u8;
+    [
+        byte(encode(color[0])),
+        byte(encode(color[1])),
+        byte(encode(color[2])),
+        byte(color[3].clamp(0.0, 1.0)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounded_rect_texture_has_transparent_corners_and_a_border() {
+        let image = UiImage::RoundedRect {
+            color: [0., 0., 1., 1.],
+            radius: 4.,
+            border_color: [1., 0., 0., 1.],
+            border_width: 2.,
+        };
+        let texture = image.procedural_texture().unwrap();
+        assert_eq!(5, rounded_rect_corner(4., 2.));
+        assert_eq!((12, 12), (texture.width, texture.height));
+        let texel = |x: u32, y: u32| texture.pixels[(y * texture.width + x) as usize];
+        assert_eq!(0, texel(0, 0)[3]);
+        assert_eq!([255, 0, 0, 255], texel(6, 0));
+        assert_eq!([0, 0, 255, 255], texel(6, 6));
+
+        let gradient = UiImage::LinearGradient {
+            start: [0., 0., 0., 1.],
+            end: [1., 1., 1., 1.],
+            vertical: true,
+        };
+        let texture = gradient.procedural_texture().unwrap();
+        assert_eq!([255, 255, 255, 255], texture.pixels[0]);
+        assert_eq!([0, 0, 0, 255], texture.pixels[GRADIENT_SIZE as usize - 1]);
+        assert_ne!(image.procedural_key(), gradient.procedural_key());
+        assert_eq!(None, UiImage::SolidColor([1.; 4]).procedural_key());
+    }
+
+    #[test]
+    fn procedural_colors_are_srgb() {
+        let image = UiImage::RoundedRect {
+            color: [0.6, 0.6, 0.6, 1.],
+            radius: 4.,
+            border_color: [0.6, 0.6, 0.6, 1.],
+            border_width: 0.,
+        };
+        let texture = image.procedural_texture().unwrap();
+        let center = (texture.height / 2 * texture.width + texture.width / 2) as usize;
+        assert_eq!([153, 153, 153, 255], texture.pixels[center]);
+    }
 }
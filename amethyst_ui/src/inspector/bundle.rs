@@ -0,0 +1,76 @@
+use amethyst_core::{
+    ecs::{storage::Component, DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::EventChannel,
+    Transform,
+};
+use amethyst_error::Error;
+use serde::{de::DeserializeOwned, Serialize};
+use winit::event::{Event, VirtualKeyCode};
+
+use super::{system, Inspector, InspectorRegistry};
+
+/// Adds the entity inspector overlay, see the [module documentation](super).
+///
+/// `Transform` is registered by default. Requires the `UiBundle`.
+#[derive(Debug)]
+pub struct InspectorBundle {
+    toggle_key: VirtualKeyCode,
+    registry: InspectorRegistry,
+}
+
+impl Default for InspectorBundle {
+    fn default() -> Self {
+        let mut registry = InspectorRegistry::default();
+        registry.register::<Transform>("Transform");
+        Self {
+            toggle_key: VirtualKeyCode::F12,
+            registry,
+        }
+    }
+}
+
+impl InspectorBundle {
+    /// Creates the bundle with `Transform` registered, toggled with `F12`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the key opening and closing the inspector.
+    #[must_use]
+    pub fn with_toggle_key(mut self, key: VirtualKeyCode) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    /// Registers components of type `T` under given name in the [`InspectorRegistry`], showing
+    /// them in the inspector.
+    #[must_use]
+    pub fn with_component<T>(mut self, name: impl Into<String>) -> Self
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.registry.register::<T>(name);
+        self
+    }
+}
+
+impl SystemBundle for InspectorBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources
+            .get_or_default::<InspectorRegistry>()
+            .merge(self.registry.clone());
+        resources.get_or_default::<Inspector>();
+
+        let reader = resources
+            .get_mut_or_default::<EventChannel<Event<'static, ()>>>()
+            .register_reader();
+        builder.add_thread_local_fn(system::inspector(reader, self.toggle_key));
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! In-game overlay for inspecting and editing entities while debugging.
+//!
+//! Add the [`InspectorBundle`] and press `F12` to open a panel listing the entities of the world.
+//! The components of the selected entity registered in the [`InspectorRegistry`] are shown with
+//! their fields, simple values can be edited while the game runs:
+//!
+//! * `PageUp` / `PageDown` select the previous or next entity,
+//! * `Up` / `Down` select a field,
+//! * `Left` / `Right` decrease or increase a number, holding `LShift` for bigger steps,
+//! * `Return` toggles a boolean.
+//!
+//! The listed entities can be narrowed with [`Inspector::set_filter`], matching entity names and
+//! component names.
+
+use amethyst_core::ecs::Entity;
+
+pub use self::{
+    bundle::InspectorBundle,
+    registry::{InspectorField, InspectorRegistration, InspectorRegistry, InspectorValue},
+};
+
+mod bundle;
+mod registry;
+mod system;
+
+/// Resource controlling the inspector overlay.
+#[derive(Debug, Clone, Default)]
+pub struct Inspector {
+    visible: bool,
+    filter: String,
+    selected: Option<Entity>,
+    field: usize,
+    edits: Vec<InspectorEdit>,
+}
+
+/// Change of a component field requested through the [`Inspector`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectorEdit {
+    /// Entity owning the component.
+    pub entity: Entity,
+    /// Registered name of the component.
+    pub component: String,
+    /// Path of the field, as listed by [`InspectorRegistry::fields`].
+    pub path: String,
+    /// New value of the field.
+    pub value: InspectorValue,
+}
+
+impl Inspector {
+    /// Whether the overlay is shown.
+    #[must_use]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the overlay.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Toggles the visibility of the overlay.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Text the names of listed entities or of one of their components must contain.
+    #[must_use]
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Only lists entities whose name or one of whose registered components contains `filter`,
+    /// ignoring case. An empty filter lists all entities.
+    pub fn set_filter(&mut self, filter: impl Into<String>) {
+        self.filter = filter.into();
+    }
+
+    /// The entity whose components are shown.
+    #[must_use]
+    pub fn selected(&self) -> Option<Entity> {
+        self.selected
+    }
+
+    /// Shows the components of given entity.
+    pub fn select(&mut self, entity: Entity) {
+        if self.selected != Some(entity) {
+            self.selected = Some(entity);
+            self.field = 0;
+        }
+    }
+
+    /// Sets a field of a component, applied by the inspector during the next frame.
+    pub fn edit(&mut self, edit: InspectorEdit) {
+        self.edits.push(edit);
+    }
+}
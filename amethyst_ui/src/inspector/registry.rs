@@ -0,0 +1,554 @@
+use std::{
+    any::{type_name, TypeId},
+    fmt::{self, Display},
+};
+
+use amethyst_core::ecs::{storage::Component, world::EntryRef, Entity, World};
+use amethyst_error::{format_err, Error, ResultExt};
+use ron::{value::Float, Number, Value};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Simple value of a component field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InspectorValue {
+    /// A boolean.
+    Bool(bool),
+    /// An integer number.
+    Integer(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    Text(String),
+}
+
+impl Display for InspectorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InspectorValue::Bool(value) => write!(f, "{}", value),
+            InspectorValue::Integer(value) => write!(f, "{}", value),
+            InspectorValue::Float(value) => write!(f, "{:.3}", value),
+            InspectorValue::Text(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl InspectorValue {
+    fn into_ron(self) -> Value {
+        match self {
+            InspectorValue::Bool(value) => Value::Bool(value),
+            InspectorValue::Integer(value) => Value::Number(Number::Integer(value)),
+            InspectorValue::Float(value) => Value::Number(Number::Float(Float::new(value))),
+            InspectorValue::Text(value) => Value::String(value),
+        }
+    }
+}
+
+/// Field of a component, addressed by a path such as `translation[1]` or `stats.health`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectorField {
+    /// Path of the field inside the component.
+    pub path: String,
+    /// Current value of the field, its variant being the type of the field.
+    pub value: InspectorValue,
+}
+
+/// Metadata and accessors of a registered component type.
+#[derive(Debug, Clone)]
+pub struct InspectorRegistration {
+    name: String,
+    type_name: &'static str,
+    type_id: TypeId,
+    has: fn(&EntryRef<'_>) -> bool,
+    read: fn(&EntryRef<'_>) -> Option<Result<Value, Error>>,
+    write: fn(&mut World, Entity, Value) -> Result<(), Error>,
+}
+
+impl InspectorRegistration {
+    /// Name the type was registered under.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Full Rust name of the type.
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// `TypeId` of the type.
+    #[must_use]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+/// Resource listing the component types shown by the inspector, accessed by name.
+///
+/// Components are inspected through their `serde` implementations, as a `ron::Value`: they are
+/// serialized to find their fields, addressed by paths like `translation[1]` or `stats.health`,
+/// and an edited field is written back by deserializing the modified data and replacing the
+/// component.
+///
+/// ```
+/// use amethyst_core::ecs::World;
+/// use amethyst_ui::{InspectorRegistry, InspectorValue};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Health {
+///     current: u32,
+///     max: u32,
+/// }
+///
+/// let mut registry = InspectorRegistry::default();
+/// registry.register::<Health>("Health");
+///
+/// let mut world = World::default();
+/// let entity = world.push((Health {
+///     current: 3,
+///     max: 10,
+/// },));
+/// registry
+///     .set_field(
+///         &mut world,
+///         entity,
+///         "Health",
+///         "current",
+///         InspectorValue::Integer(10),
+///     )
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InspectorRegistry {
+    types: Vec<InspectorRegistration>,
+}
+
+impl InspectorRegistry {
+    /// Registers a component type under given name, replacing a previous registration with the
+    /// same name.
+    pub fn register<T>(&mut self, name: impl Into<String>)
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.insert(InspectorRegistration {
+            name: name.into(),
+            type_name: type_name::<T>(),
+            type_id: TypeId::of::<T>(),
+            has: has::<T>,
+            read: read::<T>,
+            write: write::<T>,
+        });
+    }
+
+    /// Adds the registrations of another registry, replacing registrations with the same names.
+    pub fn merge(&mut self, other: InspectorRegistry) {
+        for registration in other.types {
+            self.insert(registration);
+        }
+    }
+
+    fn insert(&mut self, registration: InspectorRegistration) {
+        self.types
+            .retain(|existing| existing.name != registration.name);
+        self.types.push(registration);
+    }
+
+    /// Names of all registered component types.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.types
+            .iter()
+            .map(|registration| registration.name.as_str())
+    }
+
+    /// Registration of the type with given name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&InspectorRegistration> {
+        self.types
+            .iter()
+            .find(|registration| registration.name == name)
+    }
+
+    /// Registration of type `T`.
+    #[must_use]
+    pub fn get_by_type<T: 'static>(&self) -> Option<&InspectorRegistration> {
+        self.types
+            .iter()
+            .find(|registration| registration.type_id == TypeId::of::<T>())
+    }
+
+    /// Names of the registered components the entity has.
+    #[must_use]
+    pub fn components_of(&self, world: &World, entity: Entity) -> Vec<&str> {
+        world.entry_ref(entity).map_or_else(
+            |_| Vec::new(),
+            |entry| {
+                self.types
+                    .iter()
+                    .filter(|registration| (registration.has)(&entry))
+                    .map(|registration| registration.name.as_str())
+                    .collect()
+            },
+        )
+    }
+
+    /// Serialized data of a component of the entity.
+    ///
+    /// # Errors
+    /// Results in an error if the component isn't registered, the entity doesn't have it, or it
+    /// can't be serialized.
+    pub fn serialize(
+        &self,
+        world: &World,
+        entity: Entity,
+        component: &str,
+    ) -> Result<Value, Error> {
+        let registration = self.registration(component)?;
+        let entry = world
+            .entry_ref(entity)
+            .map_err(|_| format_err!("Entity {:?} does not exist", entity))?;
+        (registration.read)(&entry)
+            .ok_or_else(|| format_err!("Entity {:?} has no {}", entity, component))?
+    }
+
+    /// Adds a component deserialized from `data` to the entity, replacing an existing one.
+    ///
+    /// # Errors
+    /// Results in an error if the component isn't registered, the entity doesn't exist, or the
+    /// data can't be deserialized.
+    pub fn deserialize(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &str,
+        data: Value,
+    ) -> Result<(), Error> {
+        (self.registration(component)?.write)(world, entity, data)
+            .with_context(|_| format_err!("Failed to write {}", component))
+    }
+
+    /// Simple fields of a component of the entity, with struct fields sorted by name.
+    ///
+    /// # Errors
+    /// Results in an error if the component isn't registered, the entity doesn't have it, or it
+    /// can't be serialized.
+    pub fn fields(
+        &self,
+        world: &World,
+        entity: Entity,
+        component: &str,
+    ) -> Result<Vec<InspectorField>, Error> {
+        let value = self.serialize(world, entity, component)?;
+        let mut fields = Vec::new();
+        flatten(String::new(), &value, &mut fields);
+        Ok(fields)
+    }
+
+    /// Sets a field of a component of the entity.
+    ///
+    /// Numbers can be set to both integers and floating point values, other fields only accept
+    /// values of the same type.
+    ///
+    /// # Errors
+    /// Results in an error if there is no such field, the value has a different type, or the
+    /// modified component can't be deserialized.
+    pub fn set_field(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &str,
+        path: &str,
+        value: InspectorValue,
+    ) -> Result<(), Error> {
+        self.set_fields(world, entity, component, vec![(path.to_owned(), value)])
+    }
+
+    /// Sets several fields of a component of the entity at once. Either all fields are set or
+    /// none.
+    ///
+    /// # Errors
+    /// Results in an error if one of the fields can't be set, see [`InspectorRegistry::set_field`].
+    pub fn set_fields(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &str,
+        values: impl IntoIterator<Item = (String, InspectorValue)>,
+    ) -> Result<(), Error> {
+        let mut data = self.serialize(world, entity, component)?;
+        for (path, value) in values {
+            set(&mut data, &path, value)
+                .with_context(|_| format_err!("Failed to set {}.{}", component, path))?;
+        }
+        self.deserialize(world, entity, component, data)
+    }
+
+    fn registration(&self, component: &str) -> Result<&InspectorRegistration, Error> {
+        self.get(component)
+            .ok_or_else(|| format_err!("Component {} is not registered", component))
+    }
+}
+
+fn has<T: Component>(entry: &EntryRef<'_>) -> bool {
+    entry.get_component::<T>().is_ok()
+}
+
+fn read<T: Component + Serialize>(entry: &EntryRef<'_>) -> Option<Result<Value, Error>> {
+    let component = entry.get_component::<T>().ok()?;
+    Some(
+        ron::ser::to_string(component)
+            .map_err(|e| format_err!("Failed to serialize component: {}", e))
+            .and_then(|data| {
+                ron::de::from_str(&data)
+                    .map_err(|e| format_err!("Failed to read serialized component: {}", e))
+            }),
+    )
+}
+
+fn write<T: Component + DeserializeOwned>(
+    world: &mut World,
+    entity: Entity,
+    value: Value,
+) -> Result<(), Error> {
+    let component: T = value
+        .into_rust()
+        .map_err(|e| format_err!("Failed to deserialize component: {}", e))?;
+    world
+        .entry(entity)
+        .ok_or_else(|| format_err!("Entity {:?} does not exist", entity))?
+        .add_component(component);
+    Ok(())
+}
+
+fn set(data: &mut Value, path: &str, value: InspectorValue) -> Result<(), Error> {
+    let field = field_mut(data, path).ok_or_else(|| format_err!("There is no such field"))?;
+    let value = match (&*field, value) {
+        (Value::Number(Number::Float(_)), InspectorValue::Integer(value)) => {
+            InspectorValue::Float(value as f64)
+        }
+        (Value::Number(Number::Integer(_)), InspectorValue::Float(value))
+            if value.fract() == 0.0 =>
+        {
+            InspectorValue::Integer(value as i64)
+        }
+        (_, value) => value,
+    };
+    let value = value.into_ron();
+    if std::mem::discriminant(&*field) != std::mem::discriminant(&value) {
+        return Err(format_err!(
+            "Can't set it to {:?}, it has type {:?}",
+            value,
+            field
+        ));
+    }
+    *field = value;
+    Ok(())
+}
+
+fn flatten(path: String, value: &Value, fields: &mut Vec<InspectorField>) {
+    let value = match value {
+        Value::Bool(value) => InspectorValue::Bool(*value),
+        Value::Number(Number::Integer(value)) => InspectorValue::Integer(*value),
+        Value::Number(Number::Float(value)) => InspectorValue::Float(value.get()),
+        Value::String(value) => InspectorValue::Text(value.clone()),
+        Value::Option(Some(value)) => return flatten(path, value, fields),
+        Value::Seq(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten(format!("{}[{}]", path, index), item, fields);
+            }
+            return;
+        }
+        Value::Map(map) => {
+            for (key, item) in map.iter() {
+                let key = match key {
+                    Value::String(key) => key.clone(),
+                    other => format!("{:?}", other),
+                };
+                let path = if path.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten(path, item, fields);
+            }
+            return;
+        }
+        Value::Char(_) | Value::Option(None) | Value::Unit => return,
+    };
+    fields.push(InspectorField { path, value });
+}
+
+fn field_mut<'a>(mut value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    for segment in path.split('.') {
+        let (key, indices) = segment.split_at(segment.find('[').unwrap_or_else(|| segment.len()));
+        if !key.is_empty() {
+            value = match unwrap_option(value) {
+                Value::Map(map) => {
+                    map.iter_mut()
+                        .find(|(k, _)| matches!(k, Value::String(k) if k == key))
+                        .map(|(_, v)| v)?
+                }
+                _ => return None,
+            };
+        }
+        for index in indices.split('[').skip(1) {
+            let index: usize = index.strip_suffix(']')?.parse().ok()?;
+            value = match unwrap_option(value) {
+                Value::Seq(items) => items.get_mut(index)?,
+                _ => return None,
+            };
+        }
+    }
+    Some(unwrap_option(value))
+}
+
+fn unwrap_option(mut value: &mut Value) -> &mut Value {
+    while let Value::Option(Some(inner)) = value {
+        value = inner;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Stats {
+        health: i32,
+        speed: f32,
+        name: String,
+        alive: bool,
+        position: [f32; 2],
+        target: Option<Target>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Target {
+        distance: f32,
+    }
+
+    fn setup() -> (World, Entity, InspectorRegistry) {
+        let mut world = World::default();
+        let entity = world.push((Stats {
+            health: 10,
+            speed: 1.5,
+            name: "goblin".into(),
+            alive: true,
+            position: [0.5, -2.5],
+            target: Some(Target { distance: 4.5 }),
+        },));
+        let mut registry = InspectorRegistry::default();
+        registry.register::<Stats>("Stats");
+        (world, entity, registry)
+    }
+
+    #[test]
+    fn registrations_hold_type_metadata() {
+        let (_, _, registry) = setup();
+
+        let registration = registry.get_by_type::<Stats>().unwrap();
+        assert_eq!(registration.name(), "Stats");
+        assert!(registration.type_name().ends_with("Stats"));
+        assert!(registry.get("Target").is_none());
+    }
+
+    #[test]
+    fn lists_simple_fields() {
+        let (world, entity, registry) = setup();
+
+        assert_eq!(registry.components_of(&world, entity), vec!["Stats"]);
+        let fields = registry.fields(&world, entity, "Stats").unwrap();
+        let paths: Vec<_> = fields.iter().map(|field| field.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "alive",
+                "health",
+                "name",
+                "position[0]",
+                "position[1]",
+                "speed",
+                "target.distance"
+            ]
+        );
+        assert_eq!(fields[1].value, InspectorValue::Integer(10));
+        assert_eq!(fields[2].value, InspectorValue::Text("goblin".into()));
+    }
+
+    #[test]
+    fn edits_fields() {
+        let (mut world, entity, registry) = setup();
+
+        registry
+            .set_field(
+                &mut world,
+                entity,
+                "Stats",
+                "health",
+                InspectorValue::Integer(3),
+            )
+            .unwrap();
+        registry
+            .set_fields(
+                &mut world,
+                entity,
+                "Stats",
+                vec![
+                    ("alive".to_owned(), InspectorValue::Bool(false)),
+                    ("position[1]".to_owned(), InspectorValue::Float(7.25)),
+                    ("target.distance".to_owned(), InspectorValue::Integer(2)),
+                ],
+            )
+            .unwrap();
+
+        let entry = world.entry_ref(entity).unwrap();
+        let stats = entry.get_component::<Stats>().unwrap();
+        assert_eq!(stats.health, 3);
+        assert!(!stats.alive);
+        assert_eq!(stats.position, [0.5, 7.25]);
+        assert_eq!(stats.target, Some(Target { distance: 2.0 }));
+    }
+
+    #[test]
+    fn rejects_invalid_edits() {
+        let (mut world, entity, registry) = setup();
+
+        assert!(registry
+            .set_field(&mut world, entity, "Stats", "alive", InspectorValue::Integer(1))
+            .is_err());
+        assert!(registry
+            .set_field(
+                &mut world,
+                entity,
+                "Stats",
+                "missing",
+                InspectorValue::Integer(1)
+            )
+            .is_err());
+        assert!(registry
+            .set_field(
+                &mut world,
+                entity,
+                "Other",
+                "health",
+                InspectorValue::Integer(1)
+            )
+            .is_err());
+        assert!(registry
+            .set_fields(
+                &mut world,
+                entity,
+                "Stats",
+                vec![
+                    ("health".to_owned(), InspectorValue::Integer(1)),
+                    ("alive".to_owned(), InspectorValue::Integer(1)),
+                ],
+            )
+            .is_err());
+        let entry = world.entry_ref(entity).unwrap();
+        assert_eq!(entry.get_component::<Stats>().unwrap().health, 10);
+    }
+}
@@ -0,0 +1,294 @@
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, Resources, World},
+    shrev::{EventChannel, ReaderId},
+    Named,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use super::{Inspector, InspectorEdit, InspectorField, InspectorRegistry, InspectorValue};
+use crate::{Anchor, LineMode, UiImage, UiText, UiTransform};
+
+/// Number of entities listed around the selected one.
+const LISTED_ENTITIES: usize = 12;
+
+/// Creates the thread local function applying edits and drawing the inspector panel.
+pub(super) fn inspector(
+    mut reader: ReaderId<Event<'static, ()>>,
+    toggle_key: VirtualKeyCode,
+) -> impl FnMut(&mut World, &mut Resources) {
+    let mut panel: Option<[Entity; 2]> = None;
+    let mut shift = false;
+
+    move |world, resources| {
+        #[cfg(feature = "profiler")]
+        profile_scope!("inspector");
+
+        let mut inspector = resources.get_mut::<Inspector>().unwrap();
+        let registry = resources.get::<InspectorRegistry>().unwrap();
+
+        let mut keys = Vec::new();
+        for event in resources
+            .get::<EventChannel<Event<'static, ()>>>()
+            .unwrap()
+            .read(&mut reader)
+        {
+            if let Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } = event
+            {
+                if *key == VirtualKeyCode::LShift {
+                    shift = *state == ElementState::Pressed;
+                } else if *state == ElementState::Pressed {
+                    keys.push(*key);
+                }
+            }
+        }
+
+        for edit in inspector.edits.drain(..) {
+            apply(world, &registry, edit);
+        }
+
+        if keys.contains(&toggle_key) {
+            inspector.toggle();
+        }
+        if !inspector.visible {
+            if let Some(panel) = panel.take() {
+                for entity in &panel {
+                    world.remove(*entity);
+                }
+            }
+            return;
+        }
+
+        let entities = list_entities(world, &registry, &inspector.filter, panel);
+        let mut position = inspector
+            .selected
+            .and_then(|selected| entities.iter().position(|(entity, _)| *entity == selected))
+            .unwrap_or(0);
+        for key in &keys {
+            match key {
+                VirtualKeyCode::PageUp => position = position.saturating_sub(1),
+                VirtualKeyCode::PageDown => position += 1,
+                _ => {}
+            }
+        }
+        position = position.min(entities.len().saturating_sub(1));
+        match entities.get(position) {
+            Some((entity, _)) => inspector.select(*entity),
+            None => inspector.selected = None,
+        }
+
+        if let Some(selected) = inspector.selected {
+            let fields = fields(world, &registry, selected);
+            for key in &keys {
+                match key {
+                    VirtualKeyCode::Up => inspector.field = inspector.field.saturating_sub(1),
+                    VirtualKeyCode::Down => inspector.field += 1,
+                    _ => {}
+                }
+            }
+            inspector.field = inspector.field.min(fields.len().saturating_sub(1));
+
+            if let Some((component, field)) = fields.get(inspector.field) {
+                for key in &keys {
+                    if let Some(value) = adjust(&field.value, *key, shift) {
+                        apply(
+                            world,
+                            &registry,
+                            InspectorEdit {
+                                entity: selected,
+                                component: component.clone(),
+                                path: field.path.clone(),
+                                value,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        let text = describe(world, &registry, &inspector, &entities, position);
+        let [_, text_entity] = *panel.get_or_insert_with(|| create_panel(world));
+        if let Some(mut entry) = world.entry(text_entity) {
+            if let Ok(ui_text) = entry.get_component_mut::<UiText>() {
+                if ui_text.text != text {
+                    ui_text.text = text;
+                }
+            }
+        }
+    }
+}
+
+fn apply(world: &mut World, registry: &InspectorRegistry, edit: InspectorEdit) {
+    if let Err(e) = registry.set_field(world, edit.entity, &edit.component, &edit.path, edit.value)
+    {
+        log::warn!("Inspector failed to edit {:?}: {}", edit.entity, e);
+    }
+}
+
+/// Entities matching the filter, with their display names.
+fn list_entities(
+    world: &World,
+    registry: &InspectorRegistry,
+    filter: &str,
+    panel: Option<[Entity; 2]>,
+) -> Vec<(Entity, String)> {
+    let filter = filter.to_lowercase();
+    <Entity>::query()
+        .iter(world)
+        .copied()
+        .filter(|entity| !panel.map_or(false, |panel| panel.contains(entity)))
+        .filter_map(|entity| {
+            let name = world
+                .entry_ref(entity)
+                .ok()
+                .and_then(|entry| entry.get_component::<Named>().ok().map(|n| n.0.to_string()));
+            let matches = filter.is_empty()
+                || name
+                    .as_ref()
+                    .map_or(false, |name| name.to_lowercase().contains(&filter))
+                || registry
+                    .components_of(world, entity)
+                    .iter()
+                    .any(|component| component.to_lowercase().contains(&filter));
+            if matches {
+                let label = name.map_or_else(
+                    || format!("{:?}", entity),
+                    |name| format!("{:?} {}", entity, name),
+                );
+                Some((entity, label))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Fields of all registered components of the entity, with their component names.
+fn fields(
+    world: &World,
+    registry: &InspectorRegistry,
+    entity: Entity,
+) -> Vec<(String, InspectorField)> {
+    registry
+        .components_of(world, entity)
+        .into_iter()
+        .flat_map(|component| {
+            registry
+                .fields(world, entity, component)
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |field| (component.to_owned(), field))
+        })
+        .collect()
+}
+
+/// The value a key press changes the field to.
+fn adjust(value: &InspectorValue, key: VirtualKeyCode, shift: bool) -> Option<InspectorValue> {
+    let sign = match key {
+        VirtualKeyCode::Left => -1.0,
+        VirtualKeyCode::Right => 1.0,
+        VirtualKeyCode::Return => {
+            return match value {
+                InspectorValue::Bool(value) => Some(InspectorValue::Bool(!value)),
+                _ => None,
+            };
+        }
+        _ => return None,
+    };
+    match value {
+        InspectorValue::Integer(value) => {
+            Some(InspectorValue::Integer(
+                value + sign as i64 * if shift { 10 } else { 1 },
+            ))
+        }
+        InspectorValue::Float(value) => {
+            Some(InspectorValue::Float(
+                value + sign * if shift { 1.0 } else { 0.1 },
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn describe(
+    world: &World,
+    registry: &InspectorRegistry,
+    inspector: &Inspector,
+    entities: &[(Entity, String)],
+    position: usize,
+) -> String {
+    let mut text = format!("Inspector: {} entities", entities.len());
+    if !inspector.filter.is_empty() {
+        text += &format!(" matching {:?}", inspector.filter);
+    }
+    text += "\nPgUp/PgDn: entity, Up/Down: field, Left/Right/Return: edit\n\n";
+
+    let start = position.saturating_sub(LISTED_ENTITIES / 2);
+    for (index, (_, label)) in entities
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(LISTED_ENTITIES)
+    {
+        let cursor = if index == position { ">" } else { " " };
+        text += &format!("{} {}\n", cursor, label);
+    }
+
+    if let Some(selected) = inspector.selected {
+        let mut current = None;
+        for (index, (component, field)) in fields(world, registry, selected).iter().enumerate() {
+            if current != Some(component.clone()) {
+                text += &format!("\n[{}]\n", component);
+                current = Some(component.clone());
+            }
+            let cursor = if index == inspector.field { ">" } else { " " };
+            text += &format!("{} {} = {}\n", cursor, field.path, field.value);
+        }
+    }
+    text
+}
+
+/// Spawns the background and text entities of the panel.
+fn create_panel(world: &mut World) -> [Entity; 2] {
+    let transform = |id: &str, z| {
+        UiTransform::new(
+            id.into(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            10.,
+            -10.,
+            z,
+            480.,
+            720.,
+        )
+    };
+    let background = world.push((
+        transform("inspector_background", 1000.),
+        UiImage::SolidColor([0.0, 0.0, 0.0, 0.75]),
+    ));
+    let text = world.push((
+        transform("inspector_text", 1001.),
+        UiText::new(
+            None,
+            String::new(),
+            [1.0, 1.0, 1.0, 1.0],
+            14.,
+            LineMode::Wrap,
+            Anchor::TopLeft,
+        ),
+    ));
+    [background, text]
+}
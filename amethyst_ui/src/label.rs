@@ -1,20 +1,21 @@
 use std::marker::PhantomData;
 
-use amethyst_assets::{register_asset_type, Asset, AssetProcessorSystem, Handle};
+use amethyst_assets::{register_asset_type, Asset, AssetProcessorSystem, DefaultLoader, Handle};
 use amethyst_core::{
     ecs::{Entity, Resources, World},
     transform::{Parent, Transform},
 };
 
 use crate::{
-    define_widget, Anchor, FontAsset, LineMode, Selectable, Stretch, UiText, UiTransform, WidgetId,
-    Widgets,
+    define_widget, Anchor, FontAsset, LineMode, Selectable, Stretch, UiText, UiTheme, UiTransform,
+    WidgetId, Widgets,
 };
 
 const DEFAULT_Z: f32 = 1.0;
 const DEFAULT_WIDTH: f32 = 128.0;
 const DEFAULT_HEIGHT: f32 = 64.0;
 const DEFAULT_TXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const DEFAULT_FONT_SIZE: f32 = 32.;
 
 use amethyst_assets::{
     distill_importer,
@@ -54,9 +55,10 @@ pub struct UiLabelBuilder<G, I: WidgetId> {
     anchor: Anchor,
     stretch: Stretch,
     text: String,
-    text_color: [f32; 4],
+    text_color: Option<[f32; 4]>,
     font: Option<Handle<FontAsset>>,
-    font_size: f32,
+    font_size: Option<f32>,
+    class: String,
     line_mode: LineMode,
     align: Anchor,
     parent: Option<Entity>,
@@ -79,9 +81,10 @@ where
             anchor: Anchor::TopLeft,
             stretch: Stretch::NoStretch,
             text: "".to_string(),
-            text_color: DEFAULT_TXT_COLOR,
+            text_color: None,
             font: None,
-            font_size: 32.,
+            font_size: None,
+            class: "label".to_string(),
             line_mode: LineMode::Single,
             align: Anchor::Middle,
             parent: None,
@@ -157,7 +160,13 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiLabelBuilder<G, I>
 
     /// Set text color
     pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
-        self.text_color = text_color;
+        self.text_color = Some(text_color);
+        self
+    }
+
+    /// Set the widget class styling the label in the `UiTheme`, `"label"` by default.
+    pub fn with_class<S: ToString>(mut self, class: &S) -> Self {
+        self.class = class.to_string();
         self
     }
 
@@ -169,7 +178,7 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiLabelBuilder<G, I>
 
     /// Set font size
     pub fn with_font_size(mut self, size: f32) -> Self {
-        self.font_size = size;
+        self.font_size = Some(size);
         self
     }
 
@@ -206,6 +215,16 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiLabelBuilder<G, I>
         let text_entity = world.push(());
         let widget = UiLabel::new(text_entity);
 
+        let style = resources
+            .get::<UiTheme>()
+            .map(|theme| theme.style(&self.class))
+            .unwrap_or_default();
+        let font = self.font.take().or_else(|| {
+            resources
+                .get::<DefaultLoader>()
+                .and_then(|loader| style.load_font(&loader))
+        });
+
         let id = {
             let widget = widget.clone();
             let mut label_widgets = resources.get_mut::<Widgets<UiLabel, I>>().unwrap();
@@ -237,10 +256,10 @@ impl<'a, G: PartialEq + Send + Sync + 'static, I: WidgetId> UiLabelBuilder<G, I>
         );
 
         text_entry.add_component(UiText::new(
-            self.font,
+            font,
             self.text,
-            self.text_color,
-            self.font_size,
+            self.text_color.or(style.text_color).unwrap_or(DEFAULT_TXT_COLOR),
+            self.font_size.or(style.font_size).unwrap_or(DEFAULT_FONT_SIZE),
             self.line_mode,
             self.align,
         ));
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use amethyst_assets::prefab::{serde_diff, SerdeDiff};
 use amethyst_core::{
@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
-use super::UiTransform;
+use super::{UiFlexContainer, UiFlexItem, UiTransform};
 
 /// Indicates if the position and margins should be calculated in pixel or
 /// relative to their parent size.
@@ -118,6 +118,96 @@ pub enum Stretch {
         /// Keep the aspect ratio by adding more margin to one axis when necessary
         keep_aspect_ratio: bool,
     },
+    /// Takes a proportion of the parent's size on both axes, e.g. `0.5` for half of it.
+    Percent {
+        /// The proportion of the parent's width
+        width: f32,
+        /// The proportion of the parent's height
+        height: f32,
+    },
+    /// Takes the largest size of a given aspect ratio fitting in a proportion of the parent's
+    /// size, e.g. for a video or a map scaling with the window without being distorted.
+    AspectRatio {
+        /// The ratio of the width to the height
+        ratio: f32,
+        /// The proportion of the parent's width it can take
+        width: f32,
+        /// The proportion of the parent's height it can take
+        height: f32,
+    },
+}
+
+impl Stretch {
+    /// Returns the size in pixels of an element of size `size` stretched in a parent of size
+    /// `parent_size`.
+    pub(crate) fn stretched_size(self, size: (f32, f32), parent_size: (f32, f32)) -> (f32, f32) {
+        match self {
+            Stretch::NoStretch => size,
+            Stretch::X { x_margin } => (parent_size.0 - x_margin * 2.0, size.1),
+            Stretch::Y { y_margin } => (size.0, parent_size.1 - y_margin * 2.0),
+            Stretch::XY {
+                keep_aspect_ratio: false,
+                x_margin,
+                y_margin,
+            } => (parent_size.0 - x_margin * 2.0, parent_size.1 - y_margin * 2.0),
+            Stretch::XY {
+                keep_aspect_ratio: true,
+                x_margin,
+                y_margin,
+            } => {
+                let scale = f32::min(
+                    (parent_size.0 - x_margin * 2.0) / size.0,
+                    (parent_size.1 - y_margin * 2.0) / size.1,
+                );
+
+                (size.0 * scale, size.1 * scale)
+            }
+            Stretch::Percent { width, height } => (parent_size.0 * width, parent_size.1 * height),
+            Stretch::AspectRatio {
+                ratio,
+                width,
+                height,
+            } => {
+                let available = (parent_size.0 * width, parent_size.1 * height);
+                if available.0 / available.1 > ratio {
+                    (available.1 * ratio, available.1)
+                } else {
+                    (available.0, available.0 / ratio)
+                }
+            }
+        }
+    }
+}
+
+/// Layer of a `UiTransform`, drawn and clicked above the elements of all the lower layers,
+/// whatever their Z order and position in the hierarchy.
+///
+/// Elements without a layer are on the layer of their parent, and root elements on
+/// `UiLayer::DEFAULT`. Within a layer, the Z order applies.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+pub struct UiLayer(pub i32);
+
+impl UiLayer {
+    /// Layer below the default one, e.g. for backgrounds.
+    pub const BACKGROUND: UiLayer = UiLayer(-100);
+    /// Layer of the elements without a layer.
+    pub const DEFAULT: UiLayer = UiLayer(0);
+    /// Layer of popups and menus.
+    pub const POPUP: UiLayer = UiLayer(100);
+    /// Layer of tooltips, above popups.
+    pub const TOOLTIP: UiLayer = UiLayer(200);
+
+    /// Z order separating two consecutive layers, which the Z orders within a layer should stay
+    /// well below.
+    pub const DEPTH: f32 = 1000.0;
+
+    /// Returns the Z order added to the elements of this layer.
+    #[must_use]
+    pub fn z_offset(self) -> f32 {
+        self.0 as f32 * Self::DEPTH
+    }
 }
 
 /// Manages the `Parent` component on entities having `UiTransform`
@@ -127,6 +217,15 @@ pub enum Stretch {
 pub struct UiTransformSystem {
     screen_size: (f32, f32),
     modified_last_iter: HashSet<Entity>,
+    /// Sizes of the children of the flex containers, before and after being laid out.
+    flex_sizes: HashMap<Entity, FlexSize>,
+}
+
+/// Size of a child of a `UiFlexContainer`, from which it's laid out, and as laid out.
+#[derive(Debug, Clone, Copy)]
+struct FlexSize {
+    natural: (f32, f32),
+    laid_out: (f32, f32),
 }
 
 impl UiTransformSystem {
@@ -135,6 +234,7 @@ impl UiTransformSystem {
         Self {
             screen_size: (0.0, 0.0),
             modified_last_iter: HashSet::default(),
+            flex_sizes: HashMap::new(),
         }
     }
 }
@@ -156,6 +256,8 @@ impl System for UiTransformSystem {
                     <(Entity, &mut UiTransform)>::query()
                         .filter(!component::<Parent>() & !component::<Children>()),
                 )
+                .with_query(<(&UiFlexContainer, &UiTransform, &Children)>::query())
+                .with_query(<(&UiTransform, Option<&UiFlexItem>)>::query())
                 .build(
                     move |_commands,
                           world,
@@ -168,6 +270,8 @@ impl System for UiTransformSystem {
                         transform_with_children_query,
                         transform_with_parent_query,
                         transform_isolated_query,
+                        flex_containers_query,
+                        flex_items_query,
                     )| {
                         #[cfg(feature = "profiler")]
                         profile_scope!("ui_transform_system");
@@ -183,6 +287,84 @@ impl System for UiTransformSystem {
                             modified_entities.insert(*e);
                         });
 
+                        // Children moved by their flex container are processed below.
+                        let containers: Vec<(UiFlexContainer, f32, f32, Children)> =
+                            flex_containers_query
+                                .iter(world)
+                                .map(|(container, transform, children)| {
+                                    (
+                                        *container,
+                                        transform.pixel_width,
+                                        transform.pixel_height,
+                                        children.clone(),
+                                    )
+                                })
+                                .collect();
+                        let mut flex_sizes = HashMap::new();
+                        for (container, width, height, children) in containers {
+                            let items: Vec<(Entity, (f32, f32), UiFlexItem)> = children
+                                .0
+                                .iter()
+                                .filter_map(|child| {
+                                    let (transform, item) =
+                                        flex_items_query.get(world, *child).ok()?;
+                                    let size = (transform.width, transform.height);
+                                    let natural = match self.flex_sizes.get(child) {
+                                        Some(flex_size)
+                                            if flex_size.laid_out == size
+                                                && transform.scale_mode == ScaleMode::Pixel =>
+                                        {
+                                            flex_size.natural
+                                        }
+                                        _ if transform.scale_mode == ScaleMode::Percent => {
+                                            (size.0 * width, size.1 * height)
+                                        }
+                                        _ => size,
+                                    };
+                                    Some((*child, natural, item.copied().unwrap_or_default()))
+                                })
+                                .collect();
+                            let layout = container.layout(
+                                width,
+                                height,
+                                &items
+                                    .iter()
+                                    .map(|(_, natural, item)| (*natural, *item))
+                                    .collect::<Vec<_>>(),
+                            );
+                            for ((child, natural, _), (x, y, width, height)) in
+                                items.into_iter().zip(layout)
+                            {
+                                flex_sizes.insert(
+                                    child,
+                                    FlexSize {
+                                        natural,
+                                        laid_out: (width, height),
+                                    },
+                                );
+                                let laid_out = flex_items_query
+                                    .get(world, child)
+                                    .map_or(true, |(transform, _)| {
+                                        flex_laid_out(transform, x, y, width, height)
+                                    });
+                                if laid_out {
+                                    continue;
+                                }
+                                if let Ok(transform) = all_transforms_query.get_mut(world, child) {
+                                    transform.anchor = Anchor::TopLeft;
+                                    transform.pivot = Anchor::TopLeft;
+                                    transform.stretch = Stretch::NoStretch;
+                                    transform.scale_mode = ScaleMode::Pixel;
+                                    transform.local_x = x;
+                                    transform.local_y = -y;
+                                    transform.width = width;
+                                    transform.height = height;
+                                    modified_entities.insert(child);
+                                }
+                            }
+                        }
+                        self.flex_sizes = flex_sizes;
+
                         let current_screen_size =
                             (screen_dimensions.width(), screen_dimensions.height());
 
@@ -258,48 +440,90 @@ impl System for UiTransformSystem {
                                 + parent_transform_copy.pixel_width * norm.0;
                             transform.pixel_y = parent_transform_copy.pixel_y
                                 + parent_transform_copy.pixel_height * norm.1;
-                            transform.global_z = parent_transform_copy.global_z + transform.local_z;
+                            let layer =
+                                transform.layer.unwrap_or(parent_transform_copy.global_layer);
+                            transform.global_z = parent_transform_copy.global_z
+                                + transform.local_z
+                                + layer.z_offset()
+                                - parent_transform_copy.global_layer.z_offset();
+                            transform.global_layer = layer;
 
-                            let new_size = match transform.stretch {
-                                Stretch::NoStretch => (transform.width, transform.height),
-                                Stretch::X { x_margin } => {
-                                    (
-                                        parent_transform_copy.pixel_width - x_margin * 2.0,
-                                        transform.height,
-                                    )
-                                }
-                                Stretch::Y { y_margin } => {
-                                    (
-                                        transform.width,
-                                        parent_transform_copy.pixel_height - y_margin * 2.0,
-                                    )
-                                }
-                                Stretch::XY {
-                                    keep_aspect_ratio: false,
-                                    x_margin,
-                                    y_margin,
-                                } => {
-                                    (
-                                        parent_transform_copy.pixel_width - x_margin * 2.0,
-                                        parent_transform_copy.pixel_height - y_margin * 2.0,
-                                    )
+                            let new_size = transform.stretch.stretched_size(
+                                (transform.width, transform.height),
+                                (
+                                    parent_transform_copy.pixel_width,
+                                    parent_transform_copy.pixel_height,
+                                ),
+                            );
+                            transform.width = new_size.0;
+                            transform.height = new_size.1;
+                            match transform.scale_mode {
+                                ScaleMode::Pixel => {
+                                    transform.pixel_x += transform.local_x;
+                                    transform.pixel_y += transform.local_y;
+                                    transform.pixel_width = transform.width;
+                                    transform.pixel_height = transform.height;
                                 }
-                                Stretch::XY {
-                                    keep_aspect_ratio: true,
-                                    x_margin,
-                                    y_margin,
-                                } => {
-                                    let scale = f32::min(
-                                        (parent_transform_copy.pixel_width - x_margin * 2.0)
-                                            / transform.width,
-                                        (parent_transform_copy.pixel_height - y_margin * 2.0)
-                                            / transform.height,
-                                    );
-
-                                    (transform.width * scale, transform.height * scale)
+                                ScaleMode::Percent => {
+                                    transform.pixel_x +=
+                                        transform.local_x * parent_transform_copy.pixel_width;
+                                    transform.pixel_y +=
+                                        transform.local_y * parent_transform_copy.pixel_height;
+                                    transform.pixel_width =
+                                        transform.width * parent_transform_copy.pixel_width;
+                                    transform.pixel_height =
+                                        transform.height * parent_transform_copy.pixel_height;
                                 }
-                            };
-                            transform.width = new_size.0;
+                            }
+                            let pivot_norm = transform.pivot.norm_offset();
+                            transform.pixel_x += transform.pixel_width * -pivot_norm.0;
+                            transform.pixel_y += transform.pixel_height * -pivot_norm.1;
+                        }
+
+                        self.modified_last_iter.clear();
+                        for e in &modified_entities {
+                            self.modified_last_iter.insert(*e);
+                        }
+
+                        for (e, _) in &modified_children {
+                            self.modified_last_iter.insert(*e);
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Whether the transform is already laid out at the given position and size by its flex
+/// container.
+#[allow(clippy::float_cmp)]
+fn flex_laid_out(transform: &UiTransform, x: f32, y: f32, width: f32, height: f32) -> bool {
+    transform.anchor == Anchor::TopLeft
+        && transform.pivot == Anchor::TopLeft
+        && transform.stretch == Stretch::NoStretch
+        && transform.scale_mode == ScaleMode::Pixel
+        && transform.local_x == x
+        && transform.local_y == -y
+        && transform.width == width
+        && transform.height == height
+}
+
+fn process_root_iter<'a, I>(iter: I, screen_dim: &ScreenDimensions)
+where
+    I: Iterator<Item = &'a mut UiTransform>,
+{
+    for transform in iter {
+        let norm = transform.anchor.norm_offset();
+        transform.pixel_x = screen_dim.width() / 2.0 + screen_dim.width() * norm.0;
+        transform.pixel_y = screen_dim.height() / 2.0 + screen_dim.height() * norm.1;
+        transform.global_layer = transform.layer.unwrap_or_default();
+        transform.global_z = transform.local_z + transform.global_layer.z_offset();
+
+        let new_size = transform.stretch.stretched_size(
+            (transform.width, transform.height),
+            (screen_dim.width(), screen_dim.height()),
+        );
+        transform.width = new_size.0;
                             transform.height = new_size.1;
                             match transform.scale_mode {
                                 ScaleMode::Pixel => {
@@ -338,6 +562,20 @@ impl System for UiTransformSystem {
     }
 }
 
+/// Whether the transform is already laid out at the given position and size by its flex
+/// container.
+#[allow(clippy::float_cmp)]
+fn flex_laid_out(transform: &UiTransform, x: f32, y: f32, width: f32, height: f32) -> bool {
+    transform.anchor == Anchor::TopLeft
+        && transform.pivot == Anchor::TopLeft
+        && transform.stretch == Stretch::NoStretch
+        && transform.scale_mode == ScaleMode::Pixel
+        && transform.local_x == x
+        && transform.local_y == -y
+        && transform.width == width
+        && transform.height == height
+}
+
 fn process_root_iter<'a, I>(iter: I, screen_dim: &ScreenDimensions)
 where
     I: Iterator<Item = &'a mut UiTransform>,
@@ -346,7 +584,8 @@ where
         let norm = transform.anchor.norm_offset();
         transform.pixel_x = screen_dim.width() / 2.0 + screen_dim.width() * norm.0;
         transform.pixel_y = screen_dim.height() / 2.0 + screen_dim.height() * norm.1;
-        transform.global_z = transform.local_z;
+        transform.global_layer = transform.layer.unwrap_or_default();
+        transform.global_z = transform.local_z + transform.global_layer.z_offset();
 
         let new_size = match transform.stretch {
             Stretch::NoStretch => (transform.width, transform.height),
@@ -396,3 +635,46 @@ where
         transform.pixel_y += transform.pixel_height * -pivot_norm.1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_are_above_the_z_order_of_lower_layers() {
+        let screen_dimensions = ScreenDimensions::new(800, 600);
+        let transform = |id: &str, z: f32| {
+            UiTransform::new(id.into(), Anchor::Middle, Anchor::Middle, 0., 0., z, 10., 10.)
+        };
+        let mut popup = transform("popup", 1.).with_layer(UiLayer::POPUP);
+        let mut hud = transform("hud", 50.);
+        process_root_iter(vec![&mut popup, &mut hud].into_iter(), &screen_dimensions);
+
+        assert_eq!(UiLayer::POPUP, popup.global_layer);
+        assert_eq!(UiLayer::DEFAULT, hud.global_layer);
+        assert!(popup.global_z > hud.global_z);
+    }
+    #[test]
+    fn percent_and_aspect_ratio_stretches_follow_the_parent_size() {
+        let percent = Stretch::Percent {
+            width: 0.5,
+            height: 0.25,
+        };
+        assert_eq!((400., 150.), percent.stretched_size((10., 10.), (800., 600.)));
+
+        let aspect_ratio = Stretch::AspectRatio {
+            ratio: 2.,
+            width: 1.,
+            height: 1.,
+        };
+        assert_eq!((800., 400.), aspect_ratio.stretched_size((10., 10.), (800., 600.)));
+        assert_eq!((600., 300.), aspect_ratio.stretched_size((10., 10.), (600., 800.)));
+
+        let screen_dimensions = ScreenDimensions::new(1000, 400);
+        let mut transform =
+            UiTransform::new("video".into(), Anchor::Middle, Anchor::Middle, 0., 0., 0., 1., 1.)
+                .with_stretch(aspect_ratio);
+        process_root_iter(std::iter::once(&mut transform), &screen_dimensions);
+        assert_eq!((800., 400.), (transform.pixel_width, transform.pixel_height));
+    }
+}
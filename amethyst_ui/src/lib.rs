@@ -19,14 +19,30 @@
 )]
 
 pub use self::{
+    accessibility::{
+        AccessibleNode, UiAccessibility, UiAccessibilityAdapter, UiAccessibilitySystem,
+        UiAccessible, UiAccessibleState, UiHighContrast, UiRole,
+    },
+    backdrop::{DrawUiBackdrop, DrawUiBackdropDesc, RenderUiBackdrop, UiBackdrop},
     blink::*,
+    bind::{ui_bind_tick, UiBind},
     bundle::{AudioUiBundle, UiBundle},
     button::{
         UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionType, UiButtonBuilder,
     },
-    drag::{DragWidgetSystem, Draggable},
+    canvas::{UiCanvas, UiCanvasSystem, UiDrawCommand},
+    coordinates::{
+        mouse_ui_position, ui_to_local, ui_to_window, ui_to_world, ui_to_world_ray, window_to_ui,
+        world_to_ui,
+    },
+    creator::{
+        ui_widget_spawning_tick, TextEditingData, UiButtonData, UiCreator, UiImageData,
+        UiTextData, UiTransformData, UiWidget,
+    },
+    drag::{DragWidgetSystem, Draggable, UiDragPayload, UiDropTarget},
     event::{targeted, targeted_below, Interactable, TargetedEvent, UiEvent, UiEventType},
     event_retrigger::{EventReceiver, EventRetrigger},
+    flex::{FlexAlign, FlexDirection, FlexJustify, UiFlexContainer, UiFlexItem},
     font::{
         default::get_default_font,
         systemfont::{default_system_font, get_all_font_handles, list_system_font_families},
@@ -34,37 +50,78 @@ pub use self::{
     format::{FontAsset, TtfFormat},
     glyphs::UiGlyphsSystem,
     image::UiImage,
+    inspector::{
+        Inspector, InspectorBundle, InspectorEdit, InspectorField, InspectorRegistry,
+        InspectorValue,
+    },
     label::{UiLabel, UiLabelBuilder},
-    layout::{Anchor, ScaleMode, Stretch},
+    layout::{Anchor, ScaleMode, Stretch, UiLayer},
+    modal::{UiModal, UiModalSystem, UiModals},
+    navigation::{
+        UiNavigation, UiNavigationDirection, UiNavigationNeighbors, UiNavigationOrder,
+        UiNavigationSystem,
+    },
     pass::{DrawUi, DrawUiDesc, RenderUi},
+    progress_bar::{UiProgressBar, UiProgressBarSystem, UiProgressBinding, UiProgressBindingSystem},
     resize::{ResizeSystem, UiResize},
+    scroll::{UiScrollArea, UiScrollSystem},
     selection::{Selectable, Selected, SelectionKeyboardSystem, SelectionMouseSystem},
     selection_order_cache::{CacheSelectionSystem, CachedSelectionOrderResource},
+    slider::{UiSlider, UiSliderSystem},
     sound::{UiPlaySoundAction, UiSoundRetrigger, UiSoundSystem},
-    text::{LineMode, TextEditing, TextEditingMouseSystem, UiText},
+    tabs::{UiTab, UiTabGroup, UiTabSystem, UiTabs, UiTabsBuilder},
+    text::{measure_text, LineMode, TextEditing, TextEditingMouseSystem, UiText},
     text_editing::TextEditingInputSystem,
+    theme::{UiStyle, UiTheme},
+    toggle::{
+        ui_toggle_retrigger_event_system, UiCheckbox, UiRadioGroup, UiToggleAction, UiToggleSystem,
+    },
     transform::{get_parent_pixel_size, UiFinder, UiTransform},
+    virtual_keyboard::{
+        UiKey, UiKeyboard, UiKeyboardBuilder, UiKeyboardLayout, UiVirtualKey, UiVirtualKeyboard,
+        UiVirtualKeyboardSystem,
+    },
     widgets::{Widget, WidgetId, Widgets},
+    world_text::{DrawWorldText, DrawWorldTextDesc, RenderWorldText, WorldText, WorldTextMode},
 };
 
+mod accessibility;
+mod backdrop;
+mod bidi;
+mod bind;
 mod blink;
 mod bundle;
 mod button;
+mod canvas;
+mod coordinates;
+mod creator;
 mod drag;
 mod event;
 mod event_retrigger;
+mod flex;
 mod font;
 mod format;
 mod glyphs;
 mod image;
+pub mod inspector;
 mod label;
 mod layout;
+mod modal;
+mod navigation;
 mod pass;
+mod progress_bar;
 mod resize;
+mod scroll;
 mod selection;
 mod selection_order_cache;
+mod slider;
 mod sound;
+mod tabs;
 mod text;
 mod text_editing;
+mod theme;
+mod toggle;
 mod transform;
+mod virtual_keyboard;
 mod widgets;
+mod world_text;
@@ -0,0 +1,180 @@
+//! Modal widgets capturing the input of the ui while they are shown.
+
+use std::collections::{HashMap, HashSet};
+
+use amethyst_core::{
+    ecs::{component, Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::EventChannel,
+    transform::Children,
+    Hidden, HiddenPropagate,
+};
+use amethyst_input::InputHandler;
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{Selected, UiEvent, UiEventType, UiTransform};
+
+/// Makes a ui widget modal: while it's shown, the widgets outside of it and its descendants
+/// receive no mouse events, and `Tab` only cycles through the `Selectable`s inside it.
+///
+/// When several modals are shown, the topmost one is active.
+#[derive(Clone, Copy, Debug, Derivative, PartialEq, Eq, Serialize, Deserialize)]
+#[derivative(Default)]
+pub struct UiModal {
+    /// Whether the `InputHandler` actions and axes are blocked while the modal is active, so
+    /// gameplay doesn't react to the input meant for the modal.
+    #[derivative(Default(value = "true"))]
+    pub block_actions: bool,
+}
+
+impl UiModal {
+    /// Creates a modal blocking the `InputHandler` actions while it's active.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the `InputHandler` actions are blocked while the modal is active.
+    #[must_use]
+    pub fn with_block_actions(mut self, block_actions: bool) -> Self {
+        self.block_actions = block_actions;
+        self
+    }
+}
+
+/// Resource tracking the active [`UiModal`], updated by the [`UiModalSystem`].
+#[derive(Debug, Default)]
+pub struct UiModals {
+    active: Option<Entity>,
+    members: HashSet<Entity>,
+}
+
+impl UiModals {
+    /// Returns the entity of the active modal, if any.
+    #[must_use]
+    pub fn active(&self) -> Option<Entity> {
+        self.active
+    }
+
+    /// Returns whether a modal is active.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Returns whether an entity receives input: when no modal is active, or when it's the
+    /// active modal or one of its descendants.
+    #[must_use]
+    pub fn accepts(&self, entity: Entity) -> bool {
+        self.active.is_none() || self.members.contains(&entity)
+    }
+}
+
+/// Finds the active `UiModal`, blurs the widgets selected outside of it and blocks the
+/// `InputHandler` actions while it's active.
+#[derive(Debug)]
+pub struct UiModalSystem;
+
+impl System for UiModalSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiModalSystem")
+                .write_resource::<UiModals>()
+                .write_resource::<InputHandler>()
+                .write_resource::<EventChannel<UiEvent>>()
+                .with_query(
+                    <(Entity, &UiModal, &UiTransform)>::query()
+                        .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
+                )
+                .with_query(<(Entity, &Children)>::query())
+                .with_query(<(Entity, &Selected)>::query())
+                .build(
+                    |commands,
+                     world,
+                     (modals, input, ui_events),
+                     (modal_query, children_query, selected_query)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("ui_modal_system");
+
+                        let active = modal_query
+                            .iter(world)
+                            .max_by(|(_, _, a), (_, _, b)| {
+                                a.global_z
+                                    .partial_cmp(&b.global_z)
+                                    .unwrap_or(std::cmp::Ordering::Equal)
+                            })
+                            .map(|(entity, modal, _)| (*entity, *modal));
+
+                        if active.map(|(entity, _)| entity) != modals.active {
+                            input.set_actions_blocked(
+                                active.map_or(false, |(_, modal)| modal.block_actions),
+                            );
+                        }
+                        modals.active = active.map(|(entity, _)| entity);
+                        modals.members = match modals.active {
+                            Some(root) => {
+                                let children = children_query
+                                    .iter(world)
+                                    .map(|(entity, children)| (*entity, children.0.to_vec()))
+                                    .collect();
+                                descendants(root, &children)
+                            }
+                            None => HashSet::new(),
+                        };
+
+                        // Focus is trapped in the modal.
+                        for (entity, _) in selected_query.iter(world) {
+                            if !modals.accepts(*entity) {
+                                ui_events.single_write(UiEvent::new(UiEventType::Blur, *entity));
+                                commands.remove_component::<Selected>(*entity);
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// The entity `root` and all of its descendants.
+fn descendants(root: Entity, children: &HashMap<Entity, Vec<Entity>>) -> HashSet<Entity> {
+    let mut members = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(entity) = stack.pop() {
+        if members.insert(entity) {
+            if let Some(children) = children.get(&entity) {
+                stack.extend(children);
+            }
+        }
+    }
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::ecs::World;
+
+    use super::*;
+
+    #[test]
+    fn only_the_modal_tree_accepts_input() {
+        let mut world = World::default();
+        let entities: Vec<Entity> = (0..5).map(|_| world.push(())).collect();
+        let mut modals = UiModals::default();
+        assert!(modals.accepts(entities[4]));
+
+        let children = vec![
+            (entities[0], vec![entities[1], entities[2]]),
+            (entities[2], vec![entities[3]]),
+        ]
+        .into_iter()
+        .collect();
+        modals.active = Some(entities[0]);
+        modals.members = descendants(entities[0], &children);
+
+        assert!(modals.accepts(entities[0]));
+        assert!(modals.accepts(entities[3]));
+        assert!(!modals.accepts(entities[4]));
+    }
+}
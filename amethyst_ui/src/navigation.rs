@@ -0,0 +1,441 @@
+//! Focus navigation between `Selectable` widgets with the keyboard or a gamepad.
+
+use std::{borrow::Cow, collections::HashSet, marker::PhantomData};
+
+use amethyst_core::{
+    ecs::{component, Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::EventChannel,
+    Hidden, HiddenPropagate,
+};
+use amethyst_input::InputHandler;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    CachedSelectionOrderResource, Selectable, Selected, UiEvent, UiEventType, UiModals,
+    UiTransform,
+};
+
+/// Direction the focus is moved in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UiNavigationDirection {
+    /// Towards the top of the screen.
+    Up,
+    /// Towards the bottom of the screen.
+    Down,
+    /// Towards the left of the screen.
+    Left,
+    /// Towards the right of the screen.
+    Right,
+}
+
+impl UiNavigationDirection {
+    /// Unit vector of the direction, in the coordinates of `UiTransform` where y points up.
+    fn vector(self) -> [f32; 2] {
+        match self {
+            UiNavigationDirection::Up => [0., 1.],
+            UiNavigationDirection::Down => [0., -1.],
+            UiNavigationDirection::Left => [-1., 0.],
+            UiNavigationDirection::Right => [1., 0.],
+        }
+    }
+}
+
+/// How the focus is moved in a direction from a widget without an explicit neighbor there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiNavigationOrder {
+    /// To the closest `Selectable` widget in that direction on screen.
+    Spatial,
+    /// Through the `Selectable::order`: up and left go to the previous widget, down and right to
+    /// the next one.
+    Selectable,
+}
+
+/// Explicit neighbors of a `Selectable` widget, which the focus moves to instead of the widgets
+/// found by the `UiNavigationOrder`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UiNavigationNeighbors {
+    /// Widget focused when moving up.
+    pub up: Option<Entity>,
+    /// Widget focused when moving down.
+    pub down: Option<Entity>,
+    /// Widget focused when moving left.
+    pub left: Option<Entity>,
+    /// Widget focused when moving right.
+    pub right: Option<Entity>,
+}
+
+impl UiNavigationNeighbors {
+    /// Creates neighbors for no direction.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the widget focused when moving in `direction`.
+    #[must_use]
+    pub fn with(mut self, direction: UiNavigationDirection, neighbor: Entity) -> Self {
+        *self.get_mut(direction) = Some(neighbor);
+        self
+    }
+
+    /// Returns the widget focused when moving in `direction`, if any.
+    #[must_use]
+    pub fn get(&self, direction: UiNavigationDirection) -> Option<Entity> {
+        match direction {
+            UiNavigationDirection::Up => self.up,
+            UiNavigationDirection::Down => self.down,
+            UiNavigationDirection::Left => self.left,
+            UiNavigationDirection::Right => self.right,
+        }
+    }
+
+    fn get_mut(&mut self, direction: UiNavigationDirection) -> &mut Option<Entity> {
+        match direction {
+            UiNavigationDirection::Up => &mut self.up,
+            UiNavigationDirection::Down => &mut self.down,
+            UiNavigationDirection::Left => &mut self.left,
+            UiNavigationDirection::Right => &mut self.right,
+        }
+    }
+}
+
+/// Resource configuring the `InputHandler` actions navigating the ui, read by the
+/// `UiNavigationSystem`.
+///
+/// The actions are unbound by default: bind them to the arrow keys or the d-pad of a gamepad
+/// in the `Bindings` of the `InputHandler`. Unlike other actions, they still navigate while a
+/// `UiModal` blocks the actions. Binding `Tab` to `next` would move the focus twice, as the
+/// `SelectionKeyboardSystem` already handles it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UiNavigation {
+    /// Action moving the focus up.
+    pub up: Cow<'static, str>,
+    /// Action moving the focus down.
+    pub down: Cow<'static, str>,
+    /// Action moving the focus left.
+    pub left: Cow<'static, str>,
+    /// Action moving the focus right.
+    pub right: Cow<'static, str>,
+    /// Action moving the focus to the next widget in `Selectable::order`.
+    pub next: Cow<'static, str>,
+    /// Action moving the focus to the previous widget in `Selectable::order`.
+    pub previous: Cow<'static, str>,
+    /// Action clicking the focused widgets, pressing buttons.
+    pub confirm: Cow<'static, str>,
+    /// How the focus moves in the directions.
+    pub order: UiNavigationOrder,
+}
+
+impl Default for UiNavigation {
+    fn default() -> Self {
+        Self {
+            up: Cow::Borrowed("ui_up"),
+            down: Cow::Borrowed("ui_down"),
+            left: Cow::Borrowed("ui_left"),
+            right: Cow::Borrowed("ui_right"),
+            next: Cow::Borrowed("ui_next"),
+            previous: Cow::Borrowed("ui_previous"),
+            confirm: Cow::Borrowed("ui_confirm"),
+            order: UiNavigationOrder::Spatial,
+        }
+    }
+}
+
+impl UiNavigation {
+    /// Creates the navigation with the actions `ui_up`, `ui_down`, `ui_left`, `ui_right`,
+    /// `ui_next`, `ui_previous` and `ui_confirm`, moving spatially.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the focus moves in the directions.
+    #[must_use]
+    pub fn with_order(mut self, order: UiNavigationOrder) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+/// Input of the navigation, pressed during a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum NavigationInput {
+    Move(UiNavigationDirection),
+    Next,
+    Previous,
+    Confirm,
+}
+
+impl NavigationInput {
+    const ALL: [NavigationInput; 7] = [
+        NavigationInput::Move(UiNavigationDirection::Up),
+        NavigationInput::Move(UiNavigationDirection::Down),
+        NavigationInput::Move(UiNavigationDirection::Left),
+        NavigationInput::Move(UiNavigationDirection::Right),
+        NavigationInput::Next,
+        NavigationInput::Previous,
+        NavigationInput::Confirm,
+    ];
+
+    fn action(self, navigation: &UiNavigation) -> &str {
+        match self {
+            NavigationInput::Move(UiNavigationDirection::Up) => &navigation.up,
+            NavigationInput::Move(UiNavigationDirection::Down) => &navigation.down,
+            NavigationInput::Move(UiNavigationDirection::Left) => &navigation.left,
+            NavigationInput::Move(UiNavigationDirection::Right) => &navigation.right,
+            NavigationInput::Next => &navigation.next,
+            NavigationInput::Previous => &navigation.previous,
+            NavigationInput::Confirm => &navigation.confirm,
+        }
+    }
+}
+
+/// Whether a binding of `action` is down, even while the actions are blocked.
+fn action_held(input: &InputHandler, action: &str) -> bool {
+    input
+        .bindings
+        .action_bindings(action)
+        .any(|combination| combination.iter().all(|button| input.button_is_down(*button)))
+}
+
+/// System moving `Selected` between the `Selectable` widgets and clicking them with the actions
+/// of the `UiNavigation` resource.
+///
+/// The directions and the confirm action are ignored while a widget consuming the inputs, like
+/// an editable text, is selected. While a `UiModal` is active, only the widgets inside it are
+/// navigated.
+#[derive(Debug)]
+pub struct UiNavigationSystem<G> {
+    held: HashSet<NavigationInput>,
+    phantom: PhantomData<G>,
+}
+
+impl<G> UiNavigationSystem<G> {
+    /// Creates a new `UiNavigationSystem`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            held: HashSet::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<G> System for UiNavigationSystem<G>
+where
+    G: Send + Sync + 'static + PartialEq,
+{
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiNavigationSystem")
+                .read_resource::<UiNavigation>()
+                .read_resource::<InputHandler>()
+                .read_resource::<CachedSelectionOrderResource>()
+                .read_resource::<UiModals>()
+                .write_resource::<EventChannel<UiEvent>>()
+                .with_query(
+                    <(
+                        Entity,
+                        &Selectable<G>,
+                        &UiTransform,
+                        Option<&UiNavigationNeighbors>,
+                    )>::query()
+                    .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
+                )
+                .with_query(<(Entity, &Selected)>::query())
+                .build(
+                    move |commands,
+                          world,
+                          (navigation, input, cached, modals, ui_events),
+                          (selectable_query, selected_query)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("ui_navigation_system");
+
+                        let held = NavigationInput::ALL
+                            .iter()
+                            .copied()
+                            .filter(|nav| action_held(input, nav.action(navigation)))
+                            .collect::<HashSet<_>>();
+                        let pressed = held
+                            .difference(&self.held)
+                            .copied()
+                            .collect::<Vec<_>>();
+                        self.held = held;
+                        if pressed.is_empty() {
+                            return;
+                        }
+
+                        let selectables = selectable_query
+                            .iter(world)
+                            .filter(|(entity, ..)| modals.accepts(**entity))
+                            .map(|(entity, selectable, transform, neighbors)| {
+                                let center = [transform.pixel_x, transform.pixel_y];
+                                (*entity, selectable.consumes_inputs, center, neighbors.copied())
+                            })
+                            .collect::<Vec<_>>();
+                        let order = cached
+                            .cache
+                            .iter()
+                            .map(|(_, entity)| *entity)
+                            .filter(|entity| selectables.iter().any(|s| s.0 == *entity))
+                            .collect::<Vec<_>>();
+                        let selected = selected_query
+                            .iter(world)
+                            .map(|(entity, _)| *entity)
+                            .collect::<Vec<_>>();
+                        // The focus moves from the last selected widget in the selection order.
+                        let current = order
+                            .iter()
+                            .rev()
+                            .find(|entity| selected.contains(entity))
+                            .and_then(|entity| selectables.iter().find(|s| s.0 == *entity));
+                        let consumes_inputs = selectables
+                            .iter()
+                            .any(|s| s.1 && selected.contains(&s.0));
+
+                        for nav in pressed {
+                            let tab =
+                                matches!(nav, NavigationInput::Next | NavigationInput::Previous);
+                            if consumes_inputs && !tab {
+                                continue;
+                            }
+                            let target = match (nav, current) {
+                                (NavigationInput::Confirm, _) => {
+                                    for entity in &selected {
+                                        for event_type in &[
+                                            UiEventType::ClickStart,
+                                            UiEventType::ClickStop,
+                                            UiEventType::Click,
+                                        ] {
+                                            ui_events.single_write(UiEvent::new(
+                                                event_type.clone(),
+                                                *entity,
+                                            ));
+                                        }
+                                    }
+                                    continue;
+                                }
+                                (_, None) => order.first().copied(),
+                                (NavigationInput::Move(direction), Some(current)) => {
+                                    current.3.and_then(|n| n.get(direction)).or_else(|| {
+                                        match navigation.order {
+                                            UiNavigationOrder::Spatial => spatial_neighbor(
+                                                current.2,
+                                                direction,
+                                                selectables
+                                                    .iter()
+                                                    .filter(|s| s.0 != current.0)
+                                                    .map(|s| (s.0, s.2)),
+                                            ),
+                                            UiNavigationOrder::Selectable => {
+                                                let forward = matches!(
+                                                    direction,
+                                                    UiNavigationDirection::Down
+                                                        | UiNavigationDirection::Right
+                                                );
+                                                cycle(&order, current.0, forward)
+                                            }
+                                        }
+                                    })
+                                }
+                                (NavigationInput::Next, Some(current)) => {
+                                    cycle(&order, current.0, true)
+                                }
+                                (NavigationInput::Previous, Some(current)) => {
+                                    cycle(&order, current.0, false)
+                                }
+                            };
+
+                            if let Some(target) = target {
+                                for entity in &selected {
+                                    ui_events
+                                        .single_write(UiEvent::new(UiEventType::Blur, *entity));
+                                    commands.remove_component::<Selected>(*entity);
+                                }
+                                commands.add_component(target, Selected);
+                                ui_events.single_write(UiEvent::new(UiEventType::Focus, target));
+                            }
+                            // Selection changes are applied at the end of the frame.
+                            break;
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// The entity after or before `current` in `order`, wrapping around.
+fn cycle(order: &[Entity], current: Entity, forward: bool) -> Option<Entity> {
+    let index = order.iter().position(|entity| *entity == current)?;
+    let next = if forward {
+        (index + 1) % order.len()
+    } else {
+        (index + order.len() - 1) % order.len()
+    };
+    Some(order[next])
+}
+
+/// The closest of the `candidates` in `direction` from the point `from`, weighing the distance
+/// across the direction twice as much as along it so widgets in line are preferred.
+fn spatial_neighbor(
+    from: [f32; 2],
+    direction: UiNavigationDirection,
+    candidates: impl Iterator<Item = (Entity, [f32; 2])>,
+) -> Option<Entity> {
+    let [dx, dy] = direction.vector();
+    candidates
+        .filter_map(|(entity, [x, y])| {
+            let (offset_x, offset_y) = (x - from[0], y - from[1]);
+            let along = offset_x * dx + offset_y * dy;
+            let across = (offset_x * dy - offset_y * dx).abs();
+            if along > f32::EPSILON {
+                Some((entity, along + 2. * across))
+            } else {
+                None
+            }
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("Unexpected NaN!"))
+        .map(|(entity, _)| entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::ecs::World;
+
+    use super::*;
+
+    #[test]
+    fn spatial_neighbor_prefers_widgets_in_line() {
+        let mut world = World::default();
+        let entities: Vec<Entity> = (0..3).map(|_| world.push(())).collect();
+        let candidates = vec![
+            (entities[0], [100., 0.]),
+            (entities[1], [60., 50.]),
+            (entities[2], [-10., 0.]),
+        ];
+
+        let right = |from| {
+            spatial_neighbor(
+                from,
+                UiNavigationDirection::Right,
+                candidates.iter().copied(),
+            )
+        };
+        assert_eq!(Some(entities[0]), right([0., 0.]));
+        assert_eq!(Some(entities[1]), right([0., 40.]));
+        assert_eq!(
+            Some(entities[2]),
+            spatial_neighbor(
+                [0., 0.],
+                UiNavigationDirection::Left,
+                candidates.iter().copied()
+            )
+        );
+        assert_eq!(
+            None,
+            spatial_neighbor([0., 0.], UiNavigationDirection::Down, candidates.into_iter())
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 use amethyst_assets::{AssetStorage, DefaultLoader, Handle, Loader, ProcessingQueue};
 use amethyst_core::{
-    ecs::{component, DispatcherBuilder, Entity, IntoQuery, Resources, World},
+    ecs::{component, DispatcherBuilder, Entity, EntityStore, IntoQuery, Resources, World},
+    transform::Parent,
     Hidden, HiddenPropagate,
 };
 use amethyst_error::Error;
@@ -26,15 +30,15 @@ use amethyst_rendy::{
         },
         mesh::{AsVertex, VertexFormat},
         shader::{Shader, SpirvShader},
-        texture::palette::load_from_srgba,
+        texture::{palette::load_from_srgba, pixel::Rgba8Srgb, TextureBuilder},
     },
     resources::Tint,
     simple_shader_set,
-    sprite::Sprites,
+    sprite::{Sprite, SpriteBorders, Sprites, TextureCoordinates},
     submodules::{DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub},
     system::GraphAuxData,
     types::{Backend, Texture, TextureData},
-    ChangeDetection, SpriteSheet,
+    util, ChangeDetection, SpriteSheet,
 };
 use amethyst_window::ScreenDimensions;
 use derivative::Derivative;
@@ -43,11 +47,16 @@ use glsl_layout::{vec2, vec4, Uniform};
 use thread_profiler::profile_scope;
 
 use crate::{
-    glyphs::{UiGlyphs, UiGlyphsResource},
-    Selected, TextEditing, UiImage, UiTransform,
+    glyphs::{srgba_to_linear, UiGlyphs, UiGlyphsResource},
+    image::rounded_rect_corner,
+    Selected, TextEditing, UiAccessibility, UiCanvas, UiHighContrast, UiImage, UiScrollArea,
+    UiTransform,
 };
 
 /// A [`RenderPlugin`] for rendering UI elements.
+///
+/// The UI is laid out over the whole window, on top of every split screen viewport. Text placed in
+/// the world is drawn per viewport by [`RenderWorldText`](crate::RenderWorldText).
 #[derive(Debug, Default)]
 pub struct RenderUi {
     target: Target,
@@ -69,9 +78,7 @@ impl<B: Backend> RenderPlugin<B> for RenderUi {
         resources: &mut Resources,
         builder: &mut DispatcherBuilder,
     ) -> Result<(), Error> {
-        resources.insert(UiGlyphsResource::new(resources));
-
-        builder.add_system(crate::glyphs::UiGlyphsSystem::<B>::default());
+        crate::glyphs::add_glyph_systems::<B>(resources, builder);
         Ok(())
     }
 
@@ -87,7 +94,10 @@ impl<B: Backend> RenderPlugin<B> for RenderUi {
         _resources: &Resources,
     ) -> Result<(), Error> {
         plan.extend_target(self.target, |ctx| {
-            ctx.add(RenderOrder::Overlay, DrawUiDesc::new().builder())?;
+            ctx.add(
+                RenderOrder::Overlay,
+                DrawUiDesc::new().with_samples(ctx.samples()).builder(),
+            )?;
             Ok(())
         });
         Ok(())
@@ -102,8 +112,13 @@ pub(crate) struct UiArgs {
     pub(crate) tex_coord_bounds: vec4,
     pub(crate) color: vec4,
     pub(crate) color_bias: vec4,
+    /// Pixel bounds outside of which the element is discarded, as `[min_x, min_y, max_x, max_y]`.
+    pub(crate) clip: vec4,
 }
 
+/// Clip bounds of elements outside of any clipping `UiTransform` or `UiScrollArea`.
+pub(crate) const NO_CLIP: [f32; 4] = [f32::MIN, f32::MIN, f32::MAX, f32::MAX];
+
 impl AsVertex for UiArgs {
     fn vertex() -> VertexFormat {
         VertexFormat::new((
@@ -112,6 +127,7 @@ impl AsVertex for UiArgs {
             (Format::Rgba32Sfloat, "tex_coord_bounds"),
             (Format::Rgba32Sfloat, "color"),
             (Format::Rgba32Sfloat, "color_bias"),
+            (Format::Rgba32Sfloat, "clip"),
         ))
     }
 }
@@ -137,7 +153,9 @@ lazy_static::lazy_static! {
 
 /// A UI drawing pass that draws UI elements and text in screen-space
 #[derive(Clone, Debug, Default, PartialEq)]
-pub struct DrawUiDesc;
+pub struct DrawUiDesc {
+    samples: u8,
+}
 
 impl DrawUiDesc {
     /// Create new `DrawUI` pass description
@@ -145,6 +163,13 @@ impl DrawUiDesc {
     pub fn new() -> Self {
         DrawUiDesc::default()
     }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawUiDesc {
@@ -172,6 +197,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawUiDesc {
             subpass,
             framebuffer_width,
             framebuffer_height,
+            self.samples,
             vec![env.raw_layout(), textures.raw_layout()],
         )?;
 
@@ -197,6 +223,7 @@ impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawUiDesc {
             cached_draw_order: CachedDrawOrder::default(),
             batches: Default::default(),
             white_tex,
+            procedural_textures: HashMap::new(),
         }))
     }
 }
@@ -213,6 +240,8 @@ pub struct DrawUi<B: Backend> {
     change: ChangeDetection,
     cached_draw_order: CachedDrawOrder,
     white_tex: Handle<Texture>,
+    /// Textures of the procedural `UiImage`s drawn last frame, by `UiImage::procedural_key`.
+    procedural_textures: HashMap<Vec<u32>, Handle<Texture>>,
 }
 
 #[derive(Clone, Debug, Derivative)]
@@ -338,10 +367,33 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawUi<B> {
             Option<&UiGlyphs>,
             Option<&Selected>,
             Option<&TextEditing>,
+            Option<&UiHighContrast>,
         )>::query()
         .filter(!component::<Hidden>() & !component::<HiddenPropagate>());
 
+        let (high_contrast, reduced_motion) = resources
+            .get::<UiAccessibility>()
+            .map_or((false, false), |accessibility| {
+                (accessibility.high_contrast, accessibility.reduced_motion)
+            });
+
+        let mut canvases = <&UiCanvas>::query();
+
+        let clip_areas: HashMap<Entity, [f32; 4]> =
+            <(Entity, &UiTransform, Option<&UiScrollArea>)>::query()
+                .iter(*world)
+                .filter(|(_, transform, scroll_area)| transform.clip || scroll_area.is_some())
+                .map(|(entity, transform, _)| (*entity, bounds(transform)))
+                .collect();
+
+        let mut used_procedural_textures = HashSet::new();
+
         for &(_z, entity) in &self.cached_draw_order.cache {
+            let clip = if clip_areas.is_empty() {
+                NO_CLIP
+            } else {
+                clip_bounds(*world, entity, &clip_areas)
+            };
             let (
                 transform,
                 maybe_tint,
@@ -349,22 +401,48 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawUi<B> {
                 maybe_glyph,
                 maybe_selected,
                 maybe_txt_editing,
+                maybe_high_contrast,
             ) = query
                 .get(*world, entity)
                 .expect("Unreachable: Entity is guaranteed to be present based on earlier actions");
 
             let tint = maybe_tint.map(|t| {
-                let (r, g, b, a) = t.0.into_components();
+                let (r, g, b, a) = t.0.into_linear().into_components();
                 [r, g, b, a]
             });
 
+            let maybe_high_contrast = maybe_high_contrast.filter(|_| high_contrast);
+            let maybe_image = maybe_high_contrast
+                .and_then(|high_contrast| high_contrast.image.as_ref())
+                .or(maybe_image);
+            let text_color = maybe_high_contrast.and_then(|high_contrast| high_contrast.text_color);
+
             if let Some(image) = maybe_image {
+                let procedural = match image.procedural_key() {
+                    Some(key) => {
+                        let texture = match self.procedural_textures.get(&key) {
+                            Some(texture) => Some(texture.clone()),
+                            None => {
+                                let texture = load_procedural_texture(image, resources);
+                                if let Some(texture) = &texture {
+                                    self.procedural_textures.insert(key.clone(), texture.clone());
+                                }
+                                texture
+                            }
+                        };
+                        used_procedural_textures.insert(key);
+                        texture
+                    }
+                    None => None,
+                };
                 let this_changed = render_image(
                     factory,
                     aux,
                     transform,
                     image,
+                    procedural.as_ref(),
                     &tint,
+                    clip,
                     white_tex_id,
                     &mut self.textures,
                     &mut self.batches,
@@ -372,16 +450,38 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawUi<B> {
                 changed = changed || this_changed;
             };
 
+            if let Ok(canvas) = canvases.get(*world, entity) {
+                let this_changed = render_canvas(
+                    factory,
+                    aux,
+                    transform,
+                    canvas,
+                    intersect(clip, bounds(transform)),
+                    white_tex_id,
+                    &mut self.textures,
+                    &mut self.batches,
+                );
+                changed = changed || this_changed;
+            }
+
             if let Some(glyph_data) = maybe_glyph {
+                // Editable texts scroll their lines within their own bounds.
+                let clip = if maybe_txt_editing.is_some() {
+                    intersect(clip, bounds(transform))
+                } else {
+                    clip
+                };
                 if !glyph_data.sel_vertices.is_empty() {
-                    self.batches
-                        .insert(white_tex_id, glyph_data.sel_vertices.iter().copied());
+                    self.batches.insert(
+                        white_tex_id,
+                        glyph_data.sel_vertices.iter().map(|v| with_clip(*v, clip)),
+                    );
                 }
 
                 // blinking cursor
                 if maybe_selected.is_some() {
                     if let Some(editing) = maybe_txt_editing {
-                        let blink_on = editing.cursor_blink_timer < 0.25;
+                        let blink_on = reduced_motion || editing.cursor_blink_timer < 0.25;
                         let (w, h) = match (blink_on, editing.use_block_cursor) {
                             // use degenerate quad, but still insert so batches will not change
                             (false, false) => (0., 0.),
@@ -418,18 +518,29 @@ impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawUi<B> {
                                 tex_coord_bounds: [0., 0., 1., 1.].into(),
                                 color: tint.unwrap_or([1., 1., 1., 1.]).into(),
                                 color_bias: [0., 0., 0., 0.].into(),
+                                clip: clip.into(),
                             }),
                         );
                     }
                 }
 
                 if !glyph_data.vertices.is_empty() {
-                    self.batches
-                        .insert(glyph_tex_id, glyph_data.vertices.iter().copied());
+                    self.batches.insert(
+                        glyph_tex_id,
+                        glyph_data.vertices.iter().map(|v| {
+                            let mut v = with_clip(*v, clip);
+                            if let Some(color) = text_color {
+                                v.color = color.into();
+                            }
+                            v
+                        }),
+                    );
                 }
             }
         }
 
+        self.procedural_textures
+            .retain(|key, _| used_procedural_textures.contains(key));
         self.textures.maintain(factory, resources);
         changed = changed || self.batches.changed();
 
@@ -496,6 +607,7 @@ fn build_ui_pipeline<B: Backend>(
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
+    samples: u8,
     layouts: Vec<&B::DescriptorSetLayout>,
 ) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
     let pipeline_layout = unsafe {
@@ -516,6 +628,7 @@ fn build_ui_pipeline<B: Backend>(
                 .with_layout(&pipeline_layout)
                 .with_subpass(subpass)
                 .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_multisampling(util::multisampling(samples))
                 .with_blend_targets(vec![pso::ColorBlendDesc {
                     mask: pso::ColorMask::ALL,
                     blend: Some(pso::BlendState::ALPHA),
@@ -539,23 +652,117 @@ fn build_ui_pipeline<B: Backend>(
     }
 }
 
+/// Pixel bounds of a ui element, as `[min_x, min_y, max_x, max_y]`.
+fn bounds(transform: &UiTransform) -> [f32; 4] {
+    [
+        transform.pixel_x - transform.pixel_width / 2.0,
+        transform.pixel_y - transform.pixel_height / 2.0,
+        transform.pixel_x + transform.pixel_width / 2.0,
+        transform.pixel_y + transform.pixel_height / 2.0,
+    ]
+}
+
+/// Intersection of the bounds of the clipping ancestors of the entity.
+fn clip_bounds<E: EntityStore>(
+    world: &E,
+    entity: Entity,
+    clip_areas: &HashMap<Entity, [f32; 4]>,
+) -> [f32; 4] {
+    let mut clip = NO_CLIP;
+    let mut query = <&Parent>::query();
+    let mut current = entity;
+    while let Ok(parent) = query.get(world, current) {
+        current = parent.0;
+        if let Some(area) = clip_areas.get(&current) {
+            clip = intersect(clip, *area);
+        }
+    }
+    clip
+}
+
+/// Intersection of two bounds, as `[min_x, min_y, max_x, max_y]`.
+fn intersect(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        a[0].max(b[0]),
+        a[1].max(b[1]),
+        a[2].min(b[2]),
+        a[3].min(b[3]),
+    ]
+}
+
+fn with_clip(mut args: UiArgs, clip: [f32; 4]) -> UiArgs {
+    args.clip = clip.into();
+    args
+}
+
 fn mul_blend(a: &[f32; 4], b: &[f32; 4]) -> [f32; 4] {
     [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
 }
 
+fn render_canvas<B: Backend>(
+    factory: &Factory<B>,
+    aux: &GraphAuxData,
+    transform: &UiTransform,
+    canvas: &UiCanvas,
+    clip: [f32; 4],
+    white_tex_id: TextureId,
+    textures: &mut TextureSub<B>,
+    batches: &mut OrderedOneLevelBatch<TextureId, UiArgs>,
+) -> bool {
+    let canvas_bounds = bounds(transform);
+    let mut changed = false;
+    for quad in canvas.quads((canvas_bounds[0], canvas_bounds[1])) {
+        let [r, g, b, a] = quad.color;
+        let (r, g, b, a) = palette::Srgba::new(r, g, b, a)
+            .into_linear()
+            .into_components();
+        let (tex_id, tex_coords) = match quad.texture {
+            Some((texture, tex_coords)) => {
+                match textures.insert(
+                    factory,
+                    aux.resources,
+                    texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                ) {
+                    Some((tex_id, this_changed)) => {
+                        changed = changed || this_changed;
+                        (tex_id, tex_coords)
+                    }
+                    None => continue,
+                }
+            }
+            None => (white_tex_id, [0., 0., 1., 1.]),
+        };
+        batches.insert(
+            tex_id,
+            Some(UiArgs {
+                coords: [quad.center.0, quad.center.1].into(),
+                dimensions: [quad.size.0, quad.size.1].into(),
+                tex_coord_bounds: tex_coords.into(),
+                color: [r, g, b, a].into(),
+                color_bias: [0., 0., 0., 0.].into(),
+                clip: clip.into(),
+            }),
+        );
+    }
+    changed
+}
+
 fn render_image<B: Backend>(
     factory: &Factory<B>,
     aux: &GraphAuxData,
     transform: &UiTransform,
     raw_image: &UiImage,
+    procedural: Option<&Handle<Texture>>,
     tint: &Option<[f32; 4]>,
+    clip: [f32; 4],
     white_tex_id: TextureId,
     textures: &mut TextureSub<B>,
     batches: &mut OrderedOneLevelBatch<TextureId, UiArgs>,
 ) -> bool {
     let color = match (raw_image, tint.as_ref()) {
-        (UiImage::SolidColor(color), Some(t)) => mul_blend(color, t),
-        (UiImage::SolidColor(color), None) => *color,
+        (UiImage::SolidColor(color), Some(t)) => mul_blend(&srgba_to_linear(color), t),
+        (UiImage::SolidColor(color), None) => srgba_to_linear(color),
         (_, Some(t)) => *t,
         (_, None) => [1., 1., 1., 1.],
     };
@@ -593,6 +800,7 @@ fn render_image<B: Backend>(
         tex_coord_bounds: tex_coords.into(),
         color: color.into(),
         color_bias: [0., 0., 0., 0.].into(),
+        clip: clip.into(),
     };
 
     match raw_image {
@@ -705,9 +913,167 @@ fn render_image<B: Backend>(
                 false
             }
         }
+        UiImage::NineSliceSprite { sprite, borders } => {
+            let sprite_sheets = aux.resources.get::<AssetStorage<SpriteSheet>>().unwrap();
+            let sprites_storage = aux.resources.get::<AssetStorage<Sprites>>().unwrap();
+            let loaded = sprite_sheets
+                .get(&sprite.sprite_sheet)
+                .and_then(|sprite_sheet| {
+                    let sprites = sprites_storage.get(&sprite_sheet.sprites)?.build_sprites();
+                    Some((sprite_sheet, sprites.get(sprite.sprite_number)?.clone()))
+                });
+            if let Some((sprite_sheet, source)) = loaded {
+                if let Some((tex_id, this_changed)) = textures.insert(
+                    factory,
+                    aux.resources,
+                    &sprite_sheet.texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                ) {
+                    let source = Sprite {
+                        offsets: [0.0; 2],
+                        ..source
+                    };
+                    batch_slices(args, transform, &source, borders, tex_id, batches);
+                    this_changed
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
+        UiImage::NinePatch { texture, borders } => {
+            let size = aux
+                .resources
+                .get::<AssetStorage<Texture>>()
+                .unwrap()
+                .get(texture)
+                .and_then(B::unwrap_texture)
+                .map(|texture| texture.image().kind().extent());
+            if let Some(size) = size {
+                if let Some((tex_id, this_changed)) = textures.insert(
+                    factory,
+                    aux.resources,
+                    texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                ) {
+                    let source = Sprite {
+                        width: size.width as f32,
+                        height: size.height as f32,
+                        offsets: [0.0; 2],
+                        tex_coords: TextureCoordinates {
+                            left: 0.0,
+                            right: 1.0,
+                            bottom: 1.0,
+                            top: 0.0,
+                        },
+                    };
+                    batch_slices(args, transform, &source, borders, tex_id, batches);
+                    this_changed
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
         UiImage::SolidColor(_) => {
             batches.insert(white_tex_id, Some(args));
             false
         }
+        UiImage::LinearGradient { .. }
+        | UiImage::RadialGradient { .. }
+        | UiImage::RoundedRect { .. } => {
+            let inserted = procedural.and_then(|texture| {
+                textures.insert(
+                    factory,
+                    aux.resources,
+                    texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                )
+            });
+            if let Some((tex_id, this_changed)) = inserted {
+                if let UiImage::RoundedRect {
+                    radius,
+                    border_width,
+                    ..
+                } = raw_image
+                {
+                    let corner = rounded_rect_corner(*radius, *border_width) as f32;
+                    let source = Sprite {
+                        width: corner * 2.0 + 2.0,
+                        height: corner * 2.0 + 2.0,
+                        offsets: [0.0; 2],
+                        tex_coords: TextureCoordinates {
+                            left: 0.0,
+                            right: 1.0,
+                            bottom: 1.0,
+                            top: 0.0,
+                        },
+                    };
+                    let borders = SpriteBorders::new(corner, corner, corner, corner);
+                    batch_slices(args, transform, &source, &borders, tex_id, batches);
+                } else {
+                    batches.insert(tex_id, Some(args));
+                }
+                this_changed
+            } else {
+                false
+            }
+        }
+    }
+}
+
+/// Loads the texture drawing a procedural `UiImage`, which is ready to be drawn from the next
+/// frames.
+fn load_procedural_texture(image: &UiImage, resources: &Resources) -> Option<Handle<Texture>> {
+    let texture = image.procedural_texture()?;
+    let loader = resources.get::<DefaultLoader>()?;
+    let queue = resources.get::<ProcessingQueue<TextureData>>()?;
+    let builder = TextureBuilder::new()
+        .with_kind(hal::image::Kind::D2(texture.width, texture.height, 1, 1))
+        .with_view_kind(hal::image::ViewKind::D2)
+        .with_data_width(texture.width)
+        .with_data_height(texture.height)
+        .with_sampler_info(hal::image::SamplerDesc::new(
+            hal::image::Filter::Linear,
+            hal::image::WrapMode::Clamp,
+        ))
+        .with_data(
+            texture
+                .pixels
+                .into_iter()
+                .map(|repr| Rgba8Srgb { repr })
+                .collect::<Vec<_>>(),
+        );
+    Some(loader.load_from_data(TextureData(builder), (), &queue))
+}
+
+/// Batches the nine slices of `source` covering `transform`, its borders keeping their size.
+fn batch_slices(
+    args: UiArgs,
+    transform: &UiTransform,
+    source: &Sprite,
+    borders: &SpriteBorders,
+    tex_id: TextureId,
+    batches: &mut OrderedOneLevelBatch<TextureId, UiArgs>,
+) {
+    let slices = borders.slice(source, transform.pixel_width, transform.pixel_height);
+    for slice in &slices {
+        let mut temp_args = args;
+        temp_args.tex_coord_bounds = [
+            slice.tex_coords.left,
+            slice.tex_coords.top,
+            slice.tex_coords.right,
+            slice.tex_coords.bottom,
+        ]
+        .into();
+        temp_args.dimensions = [slice.width, slice.height].into();
+        temp_args.coords = [
+            transform.pixel_x() - slice.offsets[0],
+            transform.pixel_y() - slice.offsets[1],
+        ]
+        .into();
+        batches.insert(tex_id, Some(temp_args));
     }
 }
@@ -0,0 +1,229 @@
+use std::marker::PhantomData;
+
+use amethyst_assets::ProgressCounter;
+use amethyst_core::ecs::{Entity, IntoQuery, ParallelRunnable, Resource, System, SystemBuilder};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::UiTransform;
+
+/// Component of a ui element showing a progress by filling it.
+///
+/// The entity is the background of the bar. The fill is a child of it, anchored to its
+/// `MiddleLeft` (or `BottomMiddle` when vertical) with the same pivot and a `Pixel` scale mode,
+/// and resized by the `UiProgressBarSystem` to cover the fraction of the bar set in `value`.
+///
+/// To track a resource without writing a system, add a `UiProgressBinding` to the bar and a
+/// `UiProgressBindingSystem` to the dispatcher.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UiProgressBar {
+    /// Filled fraction of the bar, from `0.0` to `1.0`.
+    pub value: f32,
+    /// Whether the bar fills from bottom to top instead of left to right.
+    pub vertical: bool,
+    /// Child resized to show the value.
+    #[serde(skip)]
+    pub fill: Option<Entity>,
+}
+
+impl UiProgressBar {
+    /// Creates an empty horizontal progress bar.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            value: 0.0,
+            vertical: false,
+            fill: None,
+        }
+    }
+
+    /// Sets the initial value.
+    #[must_use]
+    pub fn with_value(mut self, value: f32) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Makes the bar fill from bottom to top.
+    #[must_use]
+    pub fn into_vertical(mut self) -> Self {
+        self.vertical = true;
+        self
+    }
+
+    /// Sets the child resized to show the value.
+    #[must_use]
+    pub fn with_fill(mut self, fill: Entity) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    /// Filled fraction of the bar, clamped between `0.0` and `1.0`.
+    #[must_use]
+    pub fn fraction(&self) -> f32 {
+        if self.value.is_nan() {
+            0.0
+        } else {
+            self.value.max(0.0).min(1.0)
+        }
+    }
+}
+
+impl Default for UiProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// System resizing the fills of the `UiProgressBar`s to their values.
+#[derive(Debug)]
+pub struct UiProgressBarSystem;
+
+impl System for UiProgressBarSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiProgressBarSystem")
+                .with_query(<(&UiProgressBar, &UiTransform)>::query())
+                .with_query(<&mut UiTransform>::query())
+                .build(|_commands, world, (), (bars, transforms)| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("ui_progress_bar_system");
+
+                    let fills: Vec<(Entity, bool, f32)> = bars
+                        .iter(world)
+                        .filter_map(|(bar, transform)| {
+                            bar.fill.map(|fill| {
+                                let length = if bar.vertical {
+                                    transform.pixel_height
+                                } else {
+                                    transform.pixel_width
+                                };
+                                (fill, bar.vertical, bar.fraction() * length)
+                            })
+                        })
+                        .collect();
+
+                    for (fill, vertical, length) in fills {
+                        if let Ok(transform) = transforms.get_mut(world, fill) {
+                            let current = if vertical {
+                                &mut transform.height
+                            } else {
+                                &mut transform.width
+                            };
+                            if (*current - length).abs() > f32::EPSILON {
+                                *current = length;
+                            }
+                        }
+                    }
+                }),
+        )
+    }
+}
+
+/// Binds the value of a `UiProgressBar` to the resource `R`, read by a
+/// `UiProgressBindingSystem<R>`.
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug, Default)]
+pub struct UiProgressBinding<R> {
+    marker: PhantomData<R>,
+}
+
+impl<R> UiProgressBinding<R> {
+    /// Creates a binding to the resource `R`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+/// System setting the value of the `UiProgressBar`s with a `UiProgressBinding<R>` from the
+/// resource `R`.
+///
+/// The default system for `ProgressCounter` tracks the fraction of the assets done loading, so
+/// a loading screen only needs to load its assets with the counter inserted as a resource. Other
+/// resources, such as a newtype around an `f32`, are read with the function given to `new`.
+///
+/// The resource `R` must be inserted before the system runs.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct UiProgressBindingSystem<R> {
+    #[derivative(Debug = "ignore")]
+    progress: fn(&R) -> f32,
+}
+
+impl<R: Resource> UiProgressBindingSystem<R> {
+    /// Creates a system reading the progress from the resource `R` with `progress`.
+    #[must_use]
+    pub fn new(progress: fn(&R) -> f32) -> Self {
+        Self { progress }
+    }
+}
+
+impl Default for UiProgressBindingSystem<ProgressCounter> {
+    fn default() -> Self {
+        Self::new(counter_progress)
+    }
+}
+
+impl<R: Resource> System for UiProgressBindingSystem<R> {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        let progress = self.progress;
+        Box::new(
+            SystemBuilder::new(format!(
+                "UiProgressBindingSystem<{}>",
+                std::any::type_name::<R>()
+            ))
+            .read_resource::<R>()
+            .with_query(<(&mut UiProgressBar, &UiProgressBinding<R>)>::query())
+            .build(move |_commands, world, resource, bars| {
+                #[cfg(feature = "profiler")]
+                profile_scope!("ui_progress_binding_system");
+
+                let value = progress(resource);
+                for (bar, _) in bars.iter_mut(world) {
+                    bar.value = value;
+                }
+            }),
+        )
+    }
+}
+
+/// Fraction of the assets tracked by the counter which are done loading, failed or not.
+fn counter_progress(counter: &ProgressCounter) -> f32 {
+    if counter.num_assets() == 0 {
+        1.0
+    } else {
+        #[allow(clippy::cast_precision_loss)]
+        let done = (counter.num_assets() - counter.num_loading()) as f32;
+        #[allow(clippy::cast_precision_loss)]
+        let total = counter.num_assets() as f32;
+        done / total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_assets::Progress;
+
+    use super::*;
+
+    #[test]
+    fn counter_progress_follows_loading() {
+        let mut counter = ProgressCounter::new();
+        assert!((counter_progress(&counter) - 1.0).abs() < f32::EPSILON);
+
+        (&mut counter).add_assets(4);
+        let _tracker = (&mut counter).create_tracker();
+        assert!((counter_progress(&counter) - 0.75).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn fraction_is_clamped() {
+        assert!((UiProgressBar::new().with_value(1.5).fraction() - 1.0).abs() < f32::EPSILON);
+        assert!(UiProgressBar::new().with_value(-0.5).fraction().abs() < f32::EPSILON);
+    }
+}
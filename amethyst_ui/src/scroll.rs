@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use amethyst_core::{
+    ecs::{component, Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    math::Vector2,
+    transform::Parent,
+    Hidden, HiddenPropagate, Time,
+};
+use amethyst_input::{InputHandler, ScrollMomentum};
+use amethyst_window::ScreenDimensions;
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use winit::event::MouseButton;
+
+use crate::{mouse_ui_position, ScaleMode, UiModals, UiTransform};
+
+/// Component making a ui element a viewport over its children, which are clipped to its bounds
+/// when rendered and can be scrolled with the mouse wheel or by dragging the area.
+///
+/// The scrollable content is the bounding box of the direct children of the entity, so a long
+/// list is usually a single child holding the items, larger than the area.
+/// Requires `UiTransform` to work.
+#[derive(Clone, Debug)]
+pub struct UiScrollArea {
+    /// Whether the content can be scrolled horizontally.
+    pub horizontal: bool,
+    /// Whether the content can be scrolled vertically.
+    pub vertical: bool,
+    /// Distance in pixels the content is scrolled by per line of the mouse wheel.
+    pub wheel_step: f32,
+    /// Whether the content can be scrolled by dragging it with the left mouse button.
+    pub draggable: bool,
+    /// Smoothing and momentum of the scroll with the mouse wheel, or `None` to scroll right away.
+    pub momentum: Option<ScrollMomentum>,
+    scroll: Vector2<f32>,
+    applied: Vector2<f32>,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+}
+
+impl Default for UiScrollArea {
+    fn default() -> Self {
+        Self {
+            horizontal: false,
+            vertical: true,
+            wheel_step: 40.0,
+            draggable: true,
+            momentum: None,
+            scroll: Vector2::zeros(),
+            applied: Vector2::zeros(),
+            min: Vector2::zeros(),
+            max: Vector2::zeros(),
+        }
+    }
+}
+
+impl UiScrollArea {
+    /// Creates a vertically scrolling area.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the axes the content can be scrolled along.
+    #[must_use]
+    pub fn with_axes(mut self, horizontal: bool, vertical: bool) -> Self {
+        self.horizontal = horizontal;
+        self.vertical = vertical;
+        self
+    }
+
+    /// Sets the distance in pixels the content is scrolled by per line of the mouse wheel.
+    #[must_use]
+    pub fn with_wheel_step(mut self, wheel_step: f32) -> Self {
+        self.wheel_step = wheel_step;
+        self
+    }
+
+    /// Sets whether the content can be scrolled by dragging it.
+    #[must_use]
+    pub fn with_draggable(mut self, draggable: bool) -> Self {
+        self.draggable = draggable;
+        self
+    }
+
+    /// Smooths the scroll with the mouse wheel and keeps it going after the wheel stops.
+    #[must_use]
+    pub fn with_momentum(mut self, momentum: ScrollMomentum) -> Self {
+        self.momentum = Some(momentum);
+        self
+    }
+
+    /// Returns the distance in pixels the content is scrolled by, from its initial position.
+    ///
+    /// A positive x moves the content to the right, and a positive y moves it up, revealing the
+    /// content below the area.
+    #[must_use]
+    pub fn scroll_position(&self) -> Vector2<f32> {
+        self.scroll
+    }
+
+    /// Scrolls the content to the given position, clamped to the content by the
+    /// `UiScrollSystem`.
+    pub fn set_scroll_position(&mut self, position: Vector2<f32>) {
+        self.scroll = position;
+        if let Some(momentum) = &mut self.momentum {
+            momentum.stop();
+        }
+    }
+
+    /// Returns the range of the scroll position allowed by the size of the content, as computed
+    /// by the `UiScrollSystem`.
+    #[must_use]
+    pub fn scroll_range(&self) -> (Vector2<f32>, Vector2<f32>) {
+        (self.min, self.max)
+    }
+
+    fn scroll_by(&mut self, delta: Vector2<f32>) {
+        if self.horizontal {
+            self.scroll.x += delta.x;
+        }
+        if self.vertical {
+            self.scroll.y += delta.y;
+        }
+    }
+}
+
+/// Scrolls the content of `UiScrollArea`s with the mouse and moves their children accordingly.
+#[derive(Debug)]
+pub struct UiScrollSystem {
+    was_down: bool,
+    dragging: Option<(Entity, Vector2<f32>)>,
+}
+
+impl UiScrollSystem {
+    /// Creates a new `UiScrollSystem`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            was_down: false,
+            dragging: None,
+        }
+    }
+}
+
+/// Bounds of a ui element in pixels, as `(min, max)`.
+fn bounds(transform: &UiTransform) -> (Vector2<f32>, Vector2<f32>) {
+    let half = Vector2::new(transform.pixel_width, transform.pixel_height) / 2.0;
+    let center = Vector2::new(transform.pixel_x, transform.pixel_y);
+    (center - half, center + half)
+}
+
+/// Range of offsets keeping the area covered by the content where it is larger, and the
+/// content inside the area where it is smaller.
+fn scroll_range(
+    area: (Vector2<f32>, Vector2<f32>),
+    content: (Vector2<f32>, Vector2<f32>),
+) -> (Vector2<f32>, Vector2<f32>) {
+    (
+        (area.1 - content.1).inf(&Vector2::zeros()),
+        (area.0 - content.0).sup(&Vector2::zeros()),
+    )
+}
+
+impl System for UiScrollSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiScrollSystem")
+                .read_resource::<InputHandler>()
+                .read_resource::<ScreenDimensions>()
+                .read_resource::<UiModals>()
+                .read_resource::<Time>()
+                .with_query(
+                    <(Entity, &mut UiScrollArea, &UiTransform)>::query()
+                        .filter(!component::<Hidden>() & !component::<HiddenPropagate>()),
+                )
+                .with_query(<(&Parent, &mut UiTransform)>::query())
+                .build(
+                    move |_commands,
+                          world,
+                          (input, screen_dimensions, modals, time),
+                          (areas, children)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("ui_scroll_system");
+
+                        let mouse = mouse_ui_position(input, screen_dimensions)
+                            .map(|(x, y)| Vector2::new(x, y));
+                        let down = input.mouse_button_is_down(MouseButton::Left);
+                        let click_started = down && !self.was_down;
+                        self.was_down = down;
+                        if !down {
+                            self.dragging = None;
+                        }
+
+                        // The topmost area under the mouse receives the wheel and drag input.
+                        let hovered = mouse.and_then(|mouse| {
+                            areas
+                                .iter_mut(world)
+                                .filter(|(entity, _, transform)| {
+                                    modals.accepts(**entity)
+                                        && transform.position_inside(mouse.x, mouse.y)
+                                })
+                                .max_by(|(_, _, a), (_, _, b)| {
+                                    a.global_z
+                                        .partial_cmp(&b.global_z)
+                                        .unwrap_or(std::cmp::Ordering::Equal)
+                                })
+                                .map(|(entity, area, _)| (*entity, area.draggable))
+                        });
+
+                        let (wheel_x, wheel_y) = input.scroll_delta();
+                        let wheel = Vector2::new(wheel_x, wheel_y);
+                        let mut input_delta = HashMap::new();
+                        if let Some((entity, draggable)) = hovered {
+                            if wheel != Vector2::zeros() {
+                                input_delta.insert(entity, wheel);
+                            }
+                            if click_started && draggable {
+                                self.dragging = mouse.map(|mouse| (entity, mouse));
+                            }
+                        }
+                        if let (Some((entity, last)), Some(mouse)) = (&mut self.dragging, mouse) {
+                            input_delta.insert(*entity, mouse - *last);
+                            *last = mouse;
+                        }
+
+                        let mut content = HashMap::new();
+                        for (parent, transform) in children.iter_mut(world) {
+                            let (min, max) = bounds(transform);
+                            content
+                                .entry(parent.0)
+                                .and_modify(
+                                    |(content_min, content_max): &mut (
+                                        Vector2<f32>,
+                                        Vector2<f32>,
+                                    )| {
+                                        *content_min = content_min.inf(&min);
+                                        *content_max = content_max.sup(&max);
+                                    },
+                                )
+                                .or_insert((min, max));
+                        }
+
+                        let mut moved = HashMap::new();
+                        for (entity, area, transform) in areas.iter_mut(world) {
+                            let delta = input_delta.get(entity).copied();
+                            if self.dragging.map(|(e, _)| e) == Some(*entity) {
+                                if let Some(momentum) = &mut area.momentum {
+                                    momentum.stop();
+                                }
+                                area.scroll_by(delta.unwrap_or_else(Vector2::zeros));
+                            } else {
+                                let wheel = delta.unwrap_or_else(Vector2::zeros);
+                                let wheel = match &mut area.momentum {
+                                    Some(momentum) => {
+                                        let (x, y) = momentum.update(
+                                            (wheel.x, wheel.y),
+                                            time.delta_real_time().as_secs_f32(),
+                                        );
+                                        Vector2::new(x, y)
+                                    }
+                                    None => wheel,
+                                };
+                                // The mouse wheel moves the view, so the content the other way.
+                                let step = -area.wheel_step * wheel;
+                                let delta = if area.vertical || step.y == 0.0 {
+                                    step
+                                } else {
+                                    // Horizontal areas scroll with the usual vertical wheel.
+                                    Vector2::new(-step.y, 0.0)
+                                };
+                                area.scroll_by(delta);
+                            }
+
+                            let (min, max) = match content.get(entity) {
+                                Some(&(content_min, content_max)) => {
+                                    // The content bounds are those of the scroll applied so far.
+                                    scroll_range(
+                                        bounds(transform),
+                                        (content_min - area.applied, content_max - area.applied),
+                                    )
+                                }
+                                None => (Vector2::zeros(), Vector2::zeros()),
+                            };
+                            area.min = min;
+                            area.max = max;
+                            if !area.horizontal {
+                                area.scroll.x = 0.0;
+                            }
+                            if !area.vertical {
+                                area.scroll.y = 0.0;
+                            }
+                            area.scroll = area.scroll.sup(&min).inf(&max);
+
+                            let change = area.scroll - area.applied;
+                            if change != Vector2::zeros() {
+                                area.applied = area.scroll;
+                                moved.insert(
+                                    *entity,
+                                    (change, transform.pixel_width, transform.pixel_height),
+                                );
+                            }
+                        }
+
+                        if moved.is_empty() {
+                            return;
+                        }
+                        for (parent, transform) in children.iter_mut(world) {
+                            if let Some(&(change, width, height)) = moved.get(&parent.0) {
+                                let (scale_x, scale_y) = match transform.scale_mode {
+                                    ScaleMode::Pixel => (1.0, 1.0),
+                                    ScaleMode::Percent => (width, height),
+                                };
+                                transform.local_x += change.x / scale_x;
+                                transform.local_y += change.y / scale_y;
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn larger_content_scrolls_within_its_bounds() {
+        let area = (Vector2::new(0.0, 0.0), Vector2::new(100.0, 100.0));
+        // Content overflowing at the bottom and on the right.
+        let content = (Vector2::new(0.0, -300.0), Vector2::new(150.0, 100.0));
+        let (min, max) = scroll_range(area, content);
+        assert_eq!(min, Vector2::new(-50.0, 0.0));
+        assert_eq!(max, Vector2::new(0.0, 300.0));
+    }
+
+    #[test]
+    fn smaller_content_does_not_scroll() {
+        let area = (Vector2::new(0.0, 0.0), Vector2::new(100.0, 100.0));
+        let content = (Vector2::new(10.0, 10.0), Vector2::new(90.0, 90.0));
+        assert_eq!(
+            scroll_range(area, content),
+            (Vector2::zeros(), Vector2::zeros())
+        );
+    }
+}
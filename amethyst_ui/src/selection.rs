@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{collections::HashSet, marker::PhantomData};
 
 use amethyst_core::{
     ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
@@ -9,7 +9,7 @@ use derive_new::new;
 use serde::{Deserialize, Serialize};
 use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 
-use crate::{CachedSelectionOrderResource, UiEvent, UiEventType};
+use crate::{CachedSelectionOrderResource, UiEvent, UiEventType, UiModals};
 
 // TODO: If none selected and there is a Selectable in the World, select the lower ordered one automatically?
 
@@ -76,9 +76,10 @@ where
             .read_resource::<CachedSelectionOrderResource>()
             .write_resource::<EventChannel<UiEvent>>()
             .read_resource::<InputHandler>()
+            .read_resource::<UiModals>()
             .with_query(<(Entity, &mut Selected)>::query())
             .build(move |commands, world,
-                         ( window_events, cached,ui_events, inputs),
+                         ( window_events, cached,ui_events, inputs, modals),
                          selected_query| {
                 /*
                        Algorithm in use:
@@ -116,6 +117,12 @@ where
                         ..
                     } = *event
                     {
+                        // While a modal is active, only the selectables inside it are cycled through.
+                        let cached = CachedSelectionOrderResource {
+                            cached: HashSet::new(),
+                            cache: cached.cache.iter().filter(|(_, e)| modals.accepts(*e)).copied().collect(),
+                        };
+
                         // Get index of highest selected ui element
                         let highest = cached.highest_order_selected_index(selected_query.iter_mut(world));
 
@@ -0,0 +1,305 @@
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::{EventChannel, ReaderId},
+};
+use amethyst_input::InputHandler;
+use amethyst_window::ScreenDimensions;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use winit::event::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::{mouse_ui_position, Selected, UiEvent, UiEventType, UiTransform};
+
+/// Component of a ui element selecting a value in a range by dragging a handle along it.
+///
+/// The entity is the track of the slider and needs a `UiTransform` and an `Interactable` to be
+/// clicked. The handle is a child of the track, anchored to its `MiddleLeft` (or `BottomMiddle`
+/// when vertical) with a `Pixel` scale mode, and moved along it by the `UiSliderSystem`.
+///
+/// Clicking or dragging the track moves the value under the mouse. When the slider is
+/// `Selected`, the arrow keys move the value by a step, and `Home` and `End` to the bounds.
+/// Each change sends a `UiEventType::ValueChange` targeting the slider, and a
+/// `UiEventType::ValueCommit` when the drag ends or after a key press.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UiSlider {
+    /// Value at the left (or bottom) of the track.
+    pub min: f32,
+    /// Value at the right (or top) of the track.
+    pub max: f32,
+    /// Interval the value is snapped to from `min`, or `0.0` for a continuous value.
+    pub step: f32,
+    /// Current value, clamped and snapped by the `UiSliderSystem`.
+    pub value: f32,
+    /// Whether the track goes from bottom to top instead of left to right.
+    pub vertical: bool,
+    /// Handle moved along the track to show the value.
+    #[serde(skip)]
+    pub handle: Option<Entity>,
+}
+
+impl UiSlider {
+    /// Creates a horizontal continuous slider between `min` and `max`, set to `min`.
+    #[must_use]
+    pub fn new(min: f32, max: f32) -> Self {
+        Self {
+            min,
+            max,
+            step: 0.0,
+            value: min,
+            vertical: false,
+            handle: None,
+        }
+    }
+
+    /// Snaps the value to multiples of `step` from `min`.
+    #[must_use]
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Sets the initial value.
+    #[must_use]
+    pub fn with_value(mut self, value: f32) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Makes the track go from bottom to top.
+    #[must_use]
+    pub fn into_vertical(mut self) -> Self {
+        self.vertical = true;
+        self
+    }
+
+    /// Sets the handle moved along the track.
+    #[must_use]
+    pub fn with_handle(mut self, handle: Entity) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Position of the value along the track, from `0.0` at `min` to `1.0` at `max`.
+    #[must_use]
+    pub fn fraction(&self) -> f32 {
+        if (self.max - self.min).abs() > f32::EPSILON {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        }
+    }
+
+    /// Clamps the value to the range and snaps it to a step.
+    fn snapped(&self, value: f32) -> f32 {
+        let (low, high) = if self.min <= self.max {
+            (self.min, self.max)
+        } else {
+            (self.max, self.min)
+        };
+        let value = if self.step > 0.0 {
+            self.min + ((value - self.min) / self.step).round() * self.step
+        } else {
+            value
+        };
+        value.max(low).min(high)
+    }
+
+    /// Distance the value moves by per key press.
+    fn key_step(&self) -> f32 {
+        if self.step > 0.0 {
+            self.step
+        } else {
+            (self.max - self.min) / 10.0
+        }
+    }
+}
+
+/// Key press moving the value of the selected sliders.
+#[derive(Clone, Copy, Debug)]
+enum SliderKey {
+    Decrease,
+    Increase,
+    Min,
+    Max,
+}
+
+/// System handling the mouse and keyboard interaction with `UiSlider`s and moving their handles.
+#[derive(Debug)]
+pub struct UiSliderSystem {
+    ui_reader_id: ReaderId<UiEvent>,
+    window_reader_id: ReaderId<Event<'static, ()>>,
+    dragging: Option<Entity>,
+}
+
+impl UiSliderSystem {
+    /// Creates a new `UiSliderSystem`.
+    pub fn new(
+        ui_reader_id: ReaderId<UiEvent>,
+        window_reader_id: ReaderId<Event<'static, ()>>,
+    ) -> Self {
+        Self {
+            ui_reader_id,
+            window_reader_id,
+            dragging: None,
+        }
+    }
+}
+
+impl System for UiSliderSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiSliderSystem")
+                .write_resource::<EventChannel<UiEvent>>()
+                .read_resource::<EventChannel<Event<'static, ()>>>()
+                .read_resource::<InputHandler>()
+                .read_resource::<ScreenDimensions>()
+                .with_query(<(Entity, &mut UiSlider, &UiTransform, Option<&Selected>)>::query())
+                .with_query(<&mut UiTransform>::query())
+                .build(
+                    move |_commands,
+                          world,
+                          (ui_events, window_events, input, screen_dimensions),
+                          (sliders, transforms)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("ui_slider_system");
+
+                        let click_starts: Vec<Entity> = ui_events
+                            .read(&mut self.ui_reader_id)
+                            .filter(|event| event.event_type == UiEventType::ClickStart)
+                            .map(|event| event.target)
+                            .collect();
+                        let keys: Vec<SliderKey> = window_events
+                            .read(&mut self.window_reader_id)
+                            .filter_map(|event| {
+                                match *event {
+                                    Event::WindowEvent {
+                                        event:
+                                            WindowEvent::KeyboardInput {
+                                                input:
+                                                    KeyboardInput {
+                                                        state: ElementState::Pressed,
+                                                        virtual_keycode: Some(key),
+                                                        ..
+                                                    },
+                                                ..
+                                            },
+                                        ..
+                                    } => {
+                                        match key {
+                                            VirtualKeyCode::Left | VirtualKeyCode::Down => {
+                                                Some(SliderKey::Decrease)
+                                            }
+                                            VirtualKeyCode::Right | VirtualKeyCode::Up => {
+                                                Some(SliderKey::Increase)
+                                            }
+                                            VirtualKeyCode::Home => Some(SliderKey::Min),
+                                            VirtualKeyCode::End => Some(SliderKey::Max),
+                                            _ => None,
+                                        }
+                                    }
+                                    _ => None,
+                                }
+                            })
+                            .collect();
+
+                        let mouse = mouse_ui_position(input, screen_dimensions);
+                        let released = !input.mouse_button_is_down(MouseButton::Left);
+
+                        let mut handles = Vec::new();
+                        for (entity, slider, transform, selected) in sliders.iter_mut(world) {
+                            let clicked = click_starts
+                                .iter()
+                                .any(|target| *target == *entity || Some(*target) == slider.handle);
+                            if clicked {
+                                self.dragging = Some(*entity);
+                            }
+
+                            let mut value = slider.snapped(slider.value);
+                            let mut commit = false;
+                            if self.dragging == Some(*entity) {
+                                if let Some((x, y)) = mouse {
+                                    let fraction = if slider.vertical {
+                                        (y - transform.pixel_y) / transform.pixel_height + 0.5
+                                    } else {
+                                        (x - transform.pixel_x) / transform.pixel_width + 0.5
+                                    };
+                                    let fraction = fraction.max(0.0).min(1.0);
+                                    value = slider
+                                        .snapped(slider.min + (slider.max - slider.min) * fraction);
+                                }
+                                if released {
+                                    self.dragging = None;
+                                    commit = true;
+                                }
+                            } else if selected.is_some() {
+                                for key in &keys {
+                                    value = slider.snapped(match key {
+                                        SliderKey::Decrease => value - slider.key_step(),
+                                        SliderKey::Increase => value + slider.key_step(),
+                                        SliderKey::Min => slider.min,
+                                        SliderKey::Max => slider.max,
+                                    });
+                                    commit = true;
+                                }
+                            }
+
+                            #[allow(clippy::float_cmp)]
+                            let changed = value != slider.value;
+                            slider.value = value;
+                            if changed {
+                                ui_events
+                                    .single_write(UiEvent::new(UiEventType::ValueChange, *entity));
+                            }
+                            if commit {
+                                ui_events
+                                    .single_write(UiEvent::new(UiEventType::ValueCommit, *entity));
+                            }
+
+                            if let Some(handle) = slider.handle {
+                                let length = if slider.vertical {
+                                    transform.pixel_height
+                                } else {
+                                    transform.pixel_width
+                                };
+                                handles.push((handle, slider.vertical, slider.fraction() * length));
+                            }
+                        }
+
+                        for (handle, vertical, position) in handles {
+                            if let Ok(transform) = transforms.get_mut(world, handle) {
+                                let current = if vertical {
+                                    &mut transform.local_y
+                                } else {
+                                    &mut transform.local_x
+                                };
+                                if (*current - position).abs() > f32::EPSILON {
+                                    *current = position;
+                                }
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_clamped_and_snapped() {
+        let slider = UiSlider::new(0.0, 10.0).with_step(2.5);
+        assert!((slider.snapped(3.5) - 2.5).abs() < f32::EPSILON);
+        assert!((slider.snapped(4.0) - 5.0).abs() < f32::EPSILON);
+        assert!((slider.snapped(12.0) - 10.0).abs() < f32::EPSILON);
+        assert!((slider.snapped(-1.0)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn fraction_follows_value() {
+        let slider = UiSlider::new(-1.0, 1.0).with_value(0.5);
+        assert!((slider.fraction() - 0.75).abs() < f32::EPSILON);
+    }
+}
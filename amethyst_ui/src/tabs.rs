@@ -0,0 +1,336 @@
+//! Tab containers, showing the panel of the selected tab button.
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, Resources, System, SystemBuilder, World},
+    shrev::{EventChannel, ReaderId},
+    transform::{Parent, Transform},
+    HiddenPropagate,
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{Anchor, Stretch, UiEvent, UiEventType, UiTransform, Widget, WidgetId, Widgets};
+
+const DEFAULT_Z: f32 = 1.0;
+const DEFAULT_WIDTH: f32 = 512.0;
+const DEFAULT_HEIGHT: f32 = 384.0;
+
+/// A tab of a `UiTabGroup`: the button selecting it and the panel shown while it's selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UiTab {
+    /// Entity clicked to select the tab, which needs an `Interactable`.
+    pub button: Entity,
+    /// Entity shown while the tab is selected, and hidden with a `HiddenPropagate` otherwise.
+    pub panel: Entity,
+}
+
+/// Component of a tab container, showing the panel of the selected tab and hiding the others.
+///
+/// Clicking the button of a tab selects it, and sends a `UiEventType::ValueChange` targeting the
+/// container. Setting `selected` changes the tab without sending an event.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UiTabGroup {
+    /// Tabs of the container, in order.
+    pub tabs: Vec<UiTab>,
+    /// Index of the selected tab.
+    pub selected: usize,
+}
+
+impl UiTabGroup {
+    /// Creates a container with the given tabs, the first one selected.
+    #[must_use]
+    pub fn new(tabs: Vec<UiTab>) -> Self {
+        Self { tabs, selected: 0 }
+    }
+
+    /// Returns the selected tab, if any.
+    #[must_use]
+    pub fn selected_tab(&self) -> Option<&UiTab> {
+        self.tabs.get(self.selected)
+    }
+}
+
+/// System selecting the tabs of the `UiTabGroup`s when their buttons are clicked, and showing
+/// their panels.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug)]
+pub struct UiTabSystem {
+    event_reader: ReaderId<UiEvent>,
+    shown: HashMap<Entity, usize>,
+}
+
+impl UiTabSystem {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self {
+            event_reader,
+            shown: HashMap::new(),
+        }
+    }
+}
+
+impl System for UiTabSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiTabSystem")
+                .write_resource::<EventChannel<UiEvent>>()
+                .with_query(<(Entity, &mut UiTabGroup)>::query())
+                .build(move |commands, world, ui_events, groups| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("ui_tab_system");
+
+                    let clicks: Vec<Entity> = ui_events
+                        .read(&mut self.event_reader)
+                        .filter(|event| event.event_type == UiEventType::Click)
+                        .map(|event| event.target)
+                        .collect();
+
+                    let mut changes = Vec::new();
+                    for (entity, group) in groups.iter_mut(world) {
+                        for target in &clicks {
+                            if let Some(index) =
+                                group.tabs.iter().position(|tab| tab.button == *target)
+                            {
+                                if group.selected != index {
+                                    group.selected = index;
+                                    changes.push(*entity);
+                                }
+                            }
+                        }
+
+                        if self.shown.get(entity) != Some(&group.selected) {
+                            self.shown.insert(*entity, group.selected);
+                            for (index, tab) in group.tabs.iter().enumerate() {
+                                if index == group.selected {
+                                    commands.remove_component::<HiddenPropagate>(tab.panel);
+                                } else {
+                                    commands.add_component(tab.panel, HiddenPropagate::new());
+                                }
+                            }
+                        }
+                    }
+
+                    for entity in changes {
+                        ui_events.single_write(UiEvent::new(UiEventType::ValueChange, entity));
+                    }
+                }),
+        )
+    }
+}
+
+/// A tab container widget, whose `container` entity holds the `UiTabGroup`.
+#[derive(Clone, Debug)]
+pub struct UiTabs {
+    /// Entity of the container.
+    pub container: Entity,
+    /// Tabs of the container, in order.
+    pub tabs: Vec<UiTab>,
+}
+
+impl Widget for UiTabs {}
+
+/// Convenience structure for building a tab container from existing tab buttons and panels.
+///
+/// The buttons and panels become children of the container. For a `UiButton`, the tab button is
+/// its `image_entity`, which receives the clicks.
+#[derive(Debug, Clone)]
+pub struct UiTabsBuilder<I: WidgetId> {
+    id: Option<I>,
+    x: f32,
+    y: f32,
+    z: f32,
+    width: f32,
+    height: f32,
+    anchor: Anchor,
+    stretch: Stretch,
+    parent: Option<Entity>,
+    tabs: Vec<UiTab>,
+    selected: usize,
+    _phantom: PhantomData<I>,
+}
+
+impl<I: WidgetId> Default for UiTabsBuilder<I> {
+    fn default() -> Self {
+        UiTabsBuilder {
+            id: None,
+            x: 0.,
+            y: 0.,
+            z: DEFAULT_Z,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            anchor: Anchor::Middle,
+            stretch: Stretch::NoStretch,
+            parent: None,
+            tabs: Vec::new(),
+            selected: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<I: WidgetId> UiTabsBuilder<I> {
+    /// Construct a new `UiTabsBuilder` without tabs.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an ID for this widget. The type of this ID will determine which `Widgets`
+    /// resource this widget will be added to, see [`Widgets`](struct.Widgets.html).
+    #[must_use]
+    pub fn with_id(mut self, id: I) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Adds a tab selected by clicking `button` and showing `panel`.
+    #[must_use]
+    pub fn with_tab(mut self, button: Entity, panel: Entity) -> Self {
+        self.tabs.push(UiTab { button, panel });
+        self
+    }
+
+    /// Sets the index of the tab initially selected.
+    #[must_use]
+    pub fn with_selected(mut self, selected: usize) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Set the container size
+    #[must_use]
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the container position
+    #[must_use]
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Provide a Z position, i.e UI layer
+    #[must_use]
+    pub fn with_layer(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Add an anchor to the container.
+    #[must_use]
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Stretch the container.
+    #[must_use]
+    pub fn with_stretch(mut self, stretch: Stretch) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
+    /// Add a parent to the container.
+    #[must_use]
+    pub fn with_parent(mut self, parent: Entity) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Build this with the `World` and `Resources`.
+    pub fn build_from_world_and_resources(
+        self,
+        world: &mut World,
+        resources: &mut Resources,
+    ) -> (I, UiTabs) {
+        let container = world.push(());
+        let widget = UiTabs {
+            container,
+            tabs: self.tabs.clone(),
+        };
+
+        let id = {
+            let widget = widget.clone();
+
+            if !resources.contains::<Widgets<UiTabs, I>>() {
+                resources.insert(Widgets::<UiTabs, I>::new());
+            }
+
+            let mut tabs_widgets = resources.get_mut::<Widgets<UiTabs, I>>().unwrap();
+            if let Some(id) = self.id {
+                let added_id = id.clone();
+                tabs_widgets.add_with_id(id, widget);
+                added_id
+            } else {
+                tabs_widgets.add(widget)
+            }
+        };
+
+        let mut container_entry = world
+            .entry(container)
+            .expect("Unreachable: Inserting newly created entity");
+        container_entry.add_component(
+            UiTransform::new(
+                format!("{}_tabs", id),
+                self.anchor,
+                Anchor::Middle,
+                self.x,
+                self.y,
+                self.z,
+                self.width,
+                self.height,
+            )
+            .with_stretch(self.stretch),
+        );
+        container_entry.add_component(UiTabGroup {
+            tabs: self.tabs.clone(),
+            selected: self.selected,
+        });
+        container_entry.add_component(Transform::default());
+        if let Some(parent) = self.parent {
+            container_entry.add_component(Parent(parent));
+        }
+
+        for tab in &self.tabs {
+            for entity in &[tab.button, tab.panel] {
+                if let Some(mut entry) = world.entry(*entity) {
+                    entry.add_component(Parent(container));
+                }
+            }
+        }
+
+        (id, widget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selected_tab_is_in_range() {
+        let mut world = World::default();
+        let entities: Vec<Entity> = (0..4).map(|_| world.push(())).collect();
+        let mut group = UiTabGroup::new(vec![
+            UiTab {
+                button: entities[0],
+                panel: entities[1],
+            },
+            UiTab {
+                button: entities[2],
+                panel: entities[3],
+            },
+        ]);
+        assert_eq!(Some(entities[1]), group.selected_tab().map(|tab| tab.panel));
+
+        group.selected = 2;
+        assert_eq!(None, group.selected_tab());
+    }
+}
@@ -11,13 +11,18 @@ use amethyst_core::{
 };
 use amethyst_window::ScreenDimensions;
 use derivative::Derivative;
+use glyph_brush::{
+    rusttype::Scale, GlyphPositioner, HorizontalAlign, SectionGeometry, SectionText,
+    VerticalAlign,
+};
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 use winit::event::{ElementState, Event, MouseButton, WindowEvent};
 
 use super::{FontAsset, Selected};
-use crate::Anchor;
+use crate::{glyphs::text_layout, window_to_ui, Anchor};
 
 /// How lines should behave when they are longer than the maximum line length.
 #[derive(Debug, Derivative, Clone, Copy, Eq, PartialEq, Deserialize, Serialize, SerdeDiff)]
@@ -41,13 +46,17 @@ pub struct UiText {
     /// The height of a line of text in pixels.
     #[derivative(Default(value = "18."))]
     pub font_size: f32,
-    /// The color of the rendered text, using a range of 0.0 to 1.0 per channel.
+    /// The sRGB color of the rendered text, using a range of 0.0 to 1.0 per channel.
     #[derivative(Default(value = "[1., 1., 1., 1.]"))]
     pub color: [f32; 4],
     /// The font used for rendering.
     #[serde(skip)]
     #[serde_diff(opaque)]
     pub font: Option<Handle<FontAsset>>,
+    /// Fonts drawing the characters missing from `font`, tried in order, e.g. CJK or emoji fonts.
+    #[serde(skip)]
+    #[serde_diff(opaque)]
+    pub fallback_fonts: Vec<Handle<FontAsset>>,
     /// If true this will be rendered as dots instead of the text.
     pub password: bool,
     /// How the text should handle new lines.
@@ -67,6 +76,28 @@ pub(crate) struct CachedGlyph {
     pub(crate) x: f32,
     pub(crate) y: f32,
     pub(crate) advance_width: f32,
+    /// Whether the glyph is part of right-to-left text, so starts at its right edge.
+    pub(crate) rtl: bool,
+}
+
+impl CachedGlyph {
+    /// X coordinate of the edge the glyph starts at, where the cursor is drawn before it.
+    pub(crate) fn leading_x(&self) -> f32 {
+        if self.rtl {
+            self.x + self.advance_width
+        } else {
+            self.x
+        }
+    }
+
+    /// X coordinate of the edge the glyph ends at, where the cursor is drawn after it.
+    pub(crate) fn trailing_x(&self) -> f32 {
+        if self.rtl {
+            self.x
+        } else {
+            self.x + self.advance_width
+        }
+    }
 }
 
 impl UiText {
@@ -76,7 +107,7 @@ impl UiText {
     ///
     /// * `font`: An optional handle to a `Font` asset, `None` will use default font
     /// * `text`: The glyphs to render
-    /// * `color`: RGBA color with a maximum of 1.0 and a minimum of 0.0 for each channel
+    /// * `color`: sRGB RGBA color with a maximum of 1.0 and a minimum of 0.0 for each channel
     /// * `font_size`: A uniform scale applied to the glyphs
     /// * `line_mode`: Text mode allowing single line or multiple lines
     /// * `align`: Text alignment within its `UiTransform`
@@ -99,10 +130,84 @@ impl UiText {
             ..UiText::default()
         }
     }
+
+    /// Adds a font drawing the characters missing from the fonts before it.
+    ///
+    /// Emojis are drawn in the color of the text, from the outlines of the font: color bitmap
+    /// emoji fonts aren't supported.
+    #[must_use]
+    pub fn with_fallback_font(mut self, font: Handle<FontAsset>) -> Self {
+        self.fallback_fonts.push(font);
+        self
+    }
+
+    /// Returns the width and height in pixels of the text once laid out with `font`, wrapping
+    /// at `max_width` in `LineMode::Wrap`.
+    ///
+    /// See `measure_text`.
+    #[must_use]
+    pub fn measure(&self, font: &FontAsset, max_width: f32) -> (f32, f32) {
+        if self.password {
+            let dots = "\u{2022}".repeat(self.text.graphemes(true).count());
+            measure_text(font, &dots, self.font_size, self.line_mode, max_width)
+        } else {
+            measure_text(font, &self.text, self.font_size, self.line_mode, max_width)
+        }
+    }
+}
+
+/// Returns the width and height in pixels `text` takes once laid out with `font`, so a widget can
+/// be sized to fit it before it's first drawn.
+///
+/// In `LineMode::Wrap` the lines wrap at `max_width`, which `LineMode::Single` ignores. The height
+/// covers whole lines, so an empty text is one line high.
+#[must_use]
+pub fn measure_text(
+    font: &FontAsset,
+    text: &str,
+    font_size: f32,
+    line_mode: LineMode,
+    max_width: f32,
+) -> (f32, f32) {
+    let scale = Scale::uniform(font_size);
+    let v_metrics = font.0.v_metrics(scale);
+    let bounds = match line_mode {
+        LineMode::Single => f32::INFINITY,
+        LineMode::Wrap => max_width,
+    };
+    let glyphs = text_layout(line_mode, HorizontalAlign::Left, VerticalAlign::Top)
+        .calculate_glyphs(
+            &[font.0.clone()],
+            &SectionGeometry {
+                screen_position: (0., 0.),
+                bounds: (bounds, f32::INFINITY),
+            },
+            &[SectionText {
+                text,
+                scale,
+                ..SectionText::default()
+            }],
+        );
+
+    let (width, last_baseline) = glyphs.iter().fold(
+        (0.0_f32, v_metrics.ascent),
+        |(width, last_baseline), (glyph, _, _)| {
+            let position = glyph.position();
+            (
+                width.max(position.x + glyph.unpositioned().h_metrics().advance_width),
+                last_baseline.max(position.y),
+            )
+        },
+    );
+    (width, last_baseline - v_metrics.descent)
 }
 
 /// If this component is attached to an entity with a `UiText` then that `UiText` is editable.
 /// This component also controls how that editing works.
+///
+/// A text with `LineMode::Wrap` is edited over multiple lines: shift + return inserts a line
+/// break, and when the lines don't fit in the `UiTransform`, the text scrolls vertically to keep
+/// the cursor visible.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct TextEditing {
     /// The current editing cursor position, specified in terms of glyphs, not characters.
@@ -111,9 +216,9 @@ pub struct TextEditing {
     pub max_length: usize,
     /// The amount and direction of glyphs highlighted relative to the cursor.
     pub highlight_vector: isize,
-    /// The color of the text itself when highlighted.
+    /// The sRGB color of the text itself when highlighted.
     pub selected_text_color: [f32; 4],
-    /// The text background color when highlighted.
+    /// The sRGB text background color when highlighted.
     pub selected_background_color: [f32; 4],
     /// If this is true the text will use a block cursor for editing.  Otherwise this uses a
     /// standard line cursor.  This is not recommended if your font is not monospace.
@@ -125,6 +230,9 @@ pub struct TextEditing {
     /// is greater than or equal to 1.0 / CURSOR_BLINK_RATE it should be reset to 0.  When the
     /// player types it should be reset to 0.
     pub(crate) cursor_blink_timer: f32,
+
+    /// Distance in pixels the lines of a wrapped text are scrolled up by.
+    pub(crate) scroll: f32,
 }
 
 impl TextEditing {
@@ -144,6 +252,7 @@ impl TextEditing {
             selected_background_color,
             use_block_cursor,
             cursor_blink_timer: 0.0,
+            scroll: 0.0,
         }
     }
 }
@@ -215,10 +324,9 @@ impl System for TextEditingMouseSystem {
                                     event: WindowEvent::CursorMoved { position, .. },
                                     ..
                                 } => {
-                                    // TODO : Check why the hidpi has been deleted ? let hidpi = screen_dimensions.hidpi_factor() as f32;
-                                    self.mouse_position = (
-                                        position.x as f32,
-                                        (screen_dimensions.height() - position.y as f32),
+                                    self.mouse_position = window_to_ui(
+                                        (position.x as f32, position.y as f32),
+                                        screen_dimensions,
                                     );
                                     if self.left_mouse_button_pressed {
                                         moved_while_pressed = true;
@@ -291,7 +399,8 @@ fn should_advance_to_end(mouse_x: f32, text_editing: &mut TextEditing, text: &mu
     let len = text.cached_glyphs.len() as isize;
     if cursor_pos + 1 == len {
         if let Some(last_glyph) = text.cached_glyphs.last() {
-            if mouse_x - last_glyph.x > last_glyph.advance_width / 2.0 {
+            let past_middle = mouse_x - last_glyph.x > last_glyph.advance_width / 2.0;
+            if past_middle != last_glyph.rtl {
                 return true;
             }
         }
@@ -306,7 +415,7 @@ fn closest_glyph_index_to_mouse(mouse_x: f32, mouse_y: f32, glyphs: &[CachedGlyp
         .enumerate()
         .min_by(|(_, g1), (_, g2)| {
             let dist = |g: &CachedGlyph| {
-                let dx = g.x - mouse_x;
+                let dx = g.leading_x() - mouse_x;
                 let dy = g.y - mouse_y;
                 dx * dx + dy * dy
             };
@@ -314,3 +423,28 @@ fn closest_glyph_index_to_mouse(mouse_x: f32, mouse_y: f32, glyphs: &[CachedGlyp
         })
         .map_or(0, |(i, _)| i) as isize
 }
+
+#[cfg(test)]
+mod tests {
+    use glyph_brush::rusttype::Font;
+
+    use super::*;
+
+    #[test]
+    fn measured_text_wraps_at_max_width() {
+        let font = FontAsset(
+            Font::from_bytes(include_bytes!("./font/square.ttf").to_vec()).unwrap(),
+        );
+        let text = "one two three four";
+
+        let (width, height) = measure_text(&font, text, 20., LineMode::Single, 100.);
+        assert!(width > 100.);
+        let (empty_width, line_height) = measure_text(&font, "", 20., LineMode::Single, 100.);
+        assert_eq!(0., empty_width);
+        assert!((height - line_height).abs() < 1e-3);
+
+        let (wrapped_width, wrapped_height) = measure_text(&font, text, 20., LineMode::Wrap, 100.);
+        assert!(wrapped_width < width);
+        assert!(wrapped_height > 1.5 * line_height);
+    }
+}
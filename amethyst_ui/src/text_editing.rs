@@ -11,12 +11,13 @@ use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 use unicode_segmentation::UnicodeSegmentation;
 use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 
-use crate::{LineMode, Selected, TextEditing, UiEvent, UiEventType, UiText};
+use crate::{text::CachedGlyph, LineMode, Selected, TextEditing, UiEvent, UiEventType, UiText};
 /// System managing the keyboard inputs for the editable text fields.
 /// ## Features
 /// * Adds and removes text.
 /// * Moves selection cursor.
 /// * Grows and shrinks selected text zone.
+/// * Moves the cursor between the lines of wrapped texts, with the arrow keys, `Home` and `End`.
 #[derive(Debug)]
 pub struct TextEditingInputSystem {
     /// A reader for winit events.
@@ -92,25 +93,42 @@ impl System for TextEditingInputSystem {
                                         ..
                                     },
                                     ..
-                                } => match v_keycode {
-                                    VirtualKeyCode::Home | VirtualKeyCode::Up => {
-                                        focused_edit.highlight_vector = if inputs.modifiers.shift() {
-                                            focused_edit.cursor_position
-                                        } else {
+                                } => match logical_key(v_keycode, focused_edit, focused_text) {
+                                    VirtualKeyCode::Home | VirtualKeyCode::End => {
+                                        let glyph_len = focused_text.text.graphemes(true).count() as isize;
+                                        let home = v_keycode == VirtualKeyCode::Home;
+                                        // Wrapped texts move along the visual line, unless ctrl is held.
+                                        let position = if focused_text.line_mode == LineMode::Wrap
+                                            && !ctrl_or_cmd(&inputs.modifiers)
+                                        {
+                                            let (start, end) = line_bounds(
+                                                &focused_text.cached_glyphs,
+                                                focused_edit.cursor_position as usize,
+                                            );
+                                            if home { start as isize } else { end as isize }
+                                        } else if home {
                                             0
+                                        } else {
+                                            glyph_len
                                         };
-                                        focused_edit.cursor_position = 0;
-                                        focused_edit.cursor_blink_timer = 0.0;
+                                        move_cursor(focused_edit, position.min(glyph_len), inputs.modifiers.shift());
                                     }
-                                    VirtualKeyCode::End | VirtualKeyCode::Down => {
+                                    VirtualKeyCode::Up | VirtualKeyCode::Down => {
                                         let glyph_len = focused_text.text.graphemes(true).count() as isize;
-                                        focused_edit.highlight_vector = if inputs.modifiers.shift() {
-                                            focused_edit.cursor_position - glyph_len
+                                        let up = v_keycode == VirtualKeyCode::Up;
+                                        let neighbor = if focused_text.line_mode == LineMode::Wrap {
+                                            line_neighbor(
+                                                &focused_text.cached_glyphs,
+                                                focused_edit.cursor_position as usize,
+                                                up,
+                                            )
                                         } else {
-                                            0
+                                            None
                                         };
-                                        focused_edit.cursor_position = glyph_len;
-                                        focused_edit.cursor_blink_timer = 0.0;
+                                        // Past the first or last line, the cursor goes to the start or end.
+                                        let position = neighbor
+                                            .map_or(if up { 0 } else { glyph_len }, |i| i as isize);
+                                        move_cursor(focused_edit, position.min(glyph_len), inputs.modifiers.shift());
                                     }
                                     VirtualKeyCode::Back => {
                                         if !delete_highlighted(focused_edit, focused_text)
@@ -331,6 +349,102 @@ fn ctrl_or_cmd(modifiers: &KeyboardModifiersState) -> bool {
         || (cfg!(not(target_os = "macos")) && modifiers.ctrl())
 }
 
+/// Moves the cursor to `position`, extending the highlighted text from its other end when
+/// `extend` is true.
+pub(crate) fn move_cursor(edit: &mut TextEditing, position: isize, extend: bool) {
+    edit.highlight_vector = if extend {
+        edit.cursor_position + edit.highlight_vector - position
+    } else {
+        0
+    };
+    edit.cursor_position = position;
+    edit.cursor_blink_timer = 0.0;
+}
+
+/// Arrow key moving the cursor in the logical order of the text: left and right are swapped in
+/// right-to-left text, where the previous glyph is on the right of the cursor.
+fn logical_key(key: VirtualKeyCode, edit: &TextEditing, text: &UiText) -> VirtualKeyCode {
+    let rtl = text
+        .cached_glyphs
+        .get(edit.cursor_position as usize)
+        .or_else(|| text.cached_glyphs.last())
+        .map_or(false, |glyph| glyph.rtl);
+    match key {
+        VirtualKeyCode::Left if rtl => VirtualKeyCode::Right,
+        VirtualKeyCode::Right if rtl => VirtualKeyCode::Left,
+        key => key,
+    }
+}
+
+/// Glyphs closer than this vertically are on the same line.
+const LINE_EPSILON: f32 = 0.5;
+
+/// Position of the cursor placed before the glyph at `index`, or after the last glyph.
+fn cursor_point(glyphs: &[CachedGlyph], index: usize) -> Option<(f32, f32)> {
+    glyphs
+        .get(index)
+        .map(|glyph| (glyph.leading_x(), glyph.y))
+        .or_else(|| glyphs.last().map(|glyph| (glyph.trailing_x(), glyph.y)))
+}
+
+/// Cursor positions at the start and end of the visual line of the cursor at `index`.
+///
+/// The end of a line is before its last glyph, the line break or the space it was wrapped at,
+/// except for the last line which ends after its last glyph.
+fn line_bounds(glyphs: &[CachedGlyph], index: usize) -> (usize, usize) {
+    let index = index.min(glyphs.len());
+    let y = match cursor_point(glyphs, index) {
+        Some((_, y)) => y,
+        None => return (0, 0),
+    };
+    let on_line = |glyph: &CachedGlyph| (glyph.y - y).abs() < LINE_EPSILON;
+    let start = glyphs[..index]
+        .iter()
+        .rposition(|glyph| !on_line(glyph))
+        .map_or(0, |i| i + 1);
+    let end = glyphs[index..]
+        .iter()
+        .position(|glyph| !on_line(glyph))
+        .map_or(glyphs.len(), |i| index + i - 1);
+    (start, end.max(start))
+}
+
+/// Cursor position on the line above or below the cursor at `index`, closest to it
+/// horizontally, or `None` when there is no such line.
+fn line_neighbor(glyphs: &[CachedGlyph], index: usize, up: bool) -> Option<usize> {
+    let (x, y) = cursor_point(glyphs, index.min(glyphs.len()))?;
+    // Baseline of the closest line in that direction.
+    let line_y = glyphs
+        .iter()
+        .map(|glyph| glyph.y)
+        .filter(|glyph_y| {
+            if up {
+                *glyph_y > y + LINE_EPSILON
+            } else {
+                *glyph_y < y - LINE_EPSILON
+            }
+        })
+        .min_by(|a, b| {
+            (a - y)
+                .abs()
+                .partial_cmp(&(b - y).abs())
+                .expect("Unexpected NaN!")
+        })?;
+
+    let (start, end) = line_bounds(
+        glyphs,
+        glyphs
+            .iter()
+            .position(|glyph| (glyph.y - line_y).abs() < LINE_EPSILON)?,
+    );
+    (start..=end).min_by(|a, b| {
+        let distance = |i: &usize| cursor_point(glyphs, *i).map_or(f32::MAX, |p| (p.0 - x).abs());
+        distance(a)
+            .partial_cmp(&distance(b))
+            .expect("Unexpected NaN!")
+    })
+}
+
 fn read_highlighted<'a>(edit: &TextEditing, text: &'a UiText) -> &'a str {
     let range = highlighted_bytes(edit, text);
     &text.text[range]
@@ -345,7 +459,7 @@ fn extract_highlighted(edit: &mut TextEditing, text: &mut UiText) -> String {
 }
 
 /// Removes the highlighted text and returns true if anything was deleted..
-fn delete_highlighted(edit: &mut TextEditing, text: &mut UiText) -> bool {
+pub(crate) fn delete_highlighted(edit: &mut TextEditing, text: &mut UiText) -> bool {
     if edit.highlight_vector != 0 {
         let range = highlighted_bytes(edit, text);
         edit.cursor_position = range.start as isize;
@@ -357,7 +471,7 @@ fn delete_highlighted(edit: &mut TextEditing, text: &mut UiText) -> bool {
 }
 
 // Gets the byte index of the cursor.
-fn cursor_byte_index(edit: &TextEditing, text: &UiText) -> usize {
+pub(crate) fn cursor_byte_index(edit: &TextEditing, text: &UiText) -> usize {
     text.text
         .grapheme_indices(true)
         .nth(edit.cursor_position as usize)
@@ -387,8 +501,8 @@ fn highlighted_bytes(edit: &TextEditing, text: &UiText) -> Range<usize> {
 
 fn should_skip_char(input: char) -> bool {
     // Ignore obsolete control characters, and tab characters we can't render
-    // properly anyways.  Also ignore newline characters since we don't
-    // support multi-line text at the moment.
+    // properly anyways.  Also ignore newline characters, which are inserted
+    // with shift + return in wrapped texts instead.
     input < '\u{20}'
         // Ignore delete character too
         || input == '\u{7F}'
@@ -398,3 +512,45 @@ fn should_skip_char(input: char) -> bool {
         || ('\u{F0000}'..='\u{FFFFF}').contains(&input)
         || ('\u{100000}'..='\u{10FFFF}').contains(&input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two lines of three glyphs of width 10, the first ending with a line break.
+    fn two_lines() -> Vec<CachedGlyph> {
+        let glyph = |x, y| {
+            CachedGlyph {
+                x,
+                y,
+                advance_width: 10.0,
+                rtl: false,
+            }
+        };
+        vec![
+            glyph(0.0, 20.0),
+            glyph(10.0, 20.0),
+            glyph(20.0, 20.0),
+            glyph(0.0, 0.0),
+            glyph(10.0, 0.0),
+            glyph(20.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn lines_end_before_their_break_except_the_last() {
+        let glyphs = two_lines();
+        assert_eq!(line_bounds(&glyphs, 1), (0, 2));
+        assert_eq!(line_bounds(&glyphs, 4), (3, 6));
+        assert_eq!(line_bounds(&glyphs, 6), (3, 6));
+    }
+
+    #[test]
+    fn cursor_moves_to_the_closest_position_of_the_next_line() {
+        let glyphs = two_lines();
+        assert_eq!(line_neighbor(&glyphs, 1, false), Some(4));
+        assert_eq!(line_neighbor(&glyphs, 6, true), Some(2));
+        assert_eq!(line_neighbor(&glyphs, 1, true), None);
+        assert_eq!(line_neighbor(&glyphs, 4, false), None);
+    }
+}
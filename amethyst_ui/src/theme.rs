@@ -0,0 +1,125 @@
+//! Themes styling the widgets created with the builders.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use amethyst_assets::{DefaultLoader, Handle, Loader};
+use amethyst_error::{format_err, Error, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::FontAsset;
+
+/// Style of a class of widgets in a [`UiTheme`]. Unset fields fall back to the default style of
+/// the theme, and then to the defaults of the builders.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiStyle {
+    /// Path of the font asset of the text.
+    pub font: Option<String>,
+    /// Size of the text.
+    pub font_size: Option<f32>,
+    /// Color of the text.
+    pub text_color: Option<[f32; 4]>,
+    /// Color of the background, for widgets which have one.
+    pub background_color: Option<[f32; 4]>,
+    /// Horizontal and vertical space between the background and the text, for widgets which
+    /// have a background.
+    pub padding: Option<(f32, f32)>,
+}
+
+impl UiStyle {
+    /// Returns this style, with the unset fields taken from `fallback`.
+    #[must_use]
+    pub fn or(&self, fallback: &UiStyle) -> UiStyle {
+        UiStyle {
+            font: self.font.clone().or_else(|| fallback.font.clone()),
+            font_size: self.font_size.or(fallback.font_size),
+            text_color: self.text_color.or(fallback.text_color),
+            background_color: self.background_color.or(fallback.background_color),
+            padding: self.padding.or(fallback.padding),
+        }
+    }
+
+    /// Loads the font of the style, if it's set.
+    #[must_use]
+    pub fn load_font(&self, loader: &DefaultLoader) -> Option<Handle<FontAsset>> {
+        self.font.as_ref().map(|path| loader.load(path))
+    }
+}
+
+/// Resource styling the widgets created with the `UiButtonBuilder`, `UiLabelBuilder` and other
+/// widget builders after it's inserted, so restyling a whole ui only means changing the theme.
+///
+/// Each builder has a widget class, such as `"button"` or `"label"` by default, and uses the style
+/// of its class. Values set on a builder take precedence over the theme.
+///
+/// # Examples
+///
+/// A RON theme file:
+///
+/// ```ron
+/// (
+///     default: (
+///         font: Some("font/square.ttf"),
+///         text_color: Some((0.9, 0.9, 0.9, 1.0)),
+///     ),
+///     classes: {
+///         "button": (
+///             background_color: Some((0.2, 0.2, 0.3, 1.0)),
+///             padding: Some((8.0, 4.0)),
+///         ),
+///         "title": (font_size: Some(48.0)),
+///     },
+/// )
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiTheme {
+    /// Style of all the widgets.
+    pub default: UiStyle,
+    /// Styles of the widget classes, over the default style.
+    pub classes: HashMap<String, UiStyle>,
+}
+
+impl UiTheme {
+    /// Loads a theme from a RON file.
+    ///
+    /// # Errors
+    /// Results in an error if the file can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .with_context(|_| format_err!("Failed to read {}", path.display()))?;
+        ron::de::from_str(&data)
+            .map_err(|e| format_err!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Returns the style of a widget class, over the default style.
+    #[must_use]
+    pub fn style(&self, class: &str) -> UiStyle {
+        match self.classes.get(class) {
+            Some(style) => style.or(&self.default),
+            None => self.default.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_style_falls_back_to_default() {
+        let theme: UiTheme = ron::de::from_str(
+            "(
+                default: (font_size: Some(16.0), text_color: Some((1.0, 1.0, 1.0, 1.0))),
+                classes: {\"title\": (font_size: Some(48.0))},
+            )",
+        )
+        .unwrap();
+
+        let title = theme.style("title");
+        assert_eq!(Some(48.0), title.font_size);
+        assert_eq!(Some([1.0, 1.0, 1.0, 1.0]), title.text_color);
+        assert_eq!(theme.default, theme.style("button"));
+    }
+}
@@ -0,0 +1,212 @@
+//! Checkboxes and radio buttons, toggled when clicked.
+
+use amethyst_core::{
+    ecs::{maybe_changed, Entity, IntoQuery, ParallelRunnable, System, SystemBuilder},
+    shrev::{EventChannel, ReaderId},
+};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    event::{UiEvent, UiEventType},
+    event_retrigger::{EventRetrigger, EventRetriggerSystem},
+    EventReceiver, UiImage,
+};
+
+/// Provides an `EventRetriggerSystem` that will handle incoming `UiEvent`s
+/// and trigger `UiToggleAction`s for entities with attached `UiCheckbox` components.
+pub fn ui_toggle_retrigger_event_system(
+    reader_id: ReaderId<UiEvent>,
+) -> EventRetriggerSystem<UiCheckbox> {
+    EventRetriggerSystem::<UiCheckbox>::new(reader_id)
+}
+
+/// Action toggling the `UiCheckbox` of the target entity in the `UiToggleSystem`.
+#[derive(Debug, Clone)]
+pub struct UiToggleAction {
+    /// The entity of the `UiCheckbox`
+    pub target: Entity,
+}
+
+/// A checkbox, toggled when its entity is clicked. Requires an `Interactable` to be clicked.
+///
+/// When `group` is set, it's a radio button of the group instead: clicking it checks it and
+/// unchecks the other radio buttons of the group. Each change sends a
+/// `UiEventType::ValueChange` targeting the checkbox, and the group for a radio button.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UiCheckbox {
+    /// Whether the checkbox is checked.
+    pub checked: bool,
+    /// Entity of the `UiRadioGroup` this radio button belongs to.
+    pub group: Option<Entity>,
+    /// Image set on the entity while checked.
+    pub checked_image: Option<UiImage>,
+    /// Image set on the entity while unchecked.
+    pub unchecked_image: Option<UiImage>,
+}
+
+impl UiCheckbox {
+    /// Creates an unchecked checkbox.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an unchecked radio button of the given `UiRadioGroup`.
+    #[must_use]
+    pub fn radio(group: Entity) -> Self {
+        Self {
+            group: Some(group),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the initial state.
+    #[must_use]
+    pub fn with_checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Sets the images of the entity while checked and unchecked.
+    #[must_use]
+    pub fn with_images(mut self, checked: UiImage, unchecked: UiImage) -> Self {
+        self.checked_image = Some(checked);
+        self.unchecked_image = Some(unchecked);
+        self
+    }
+
+    fn image(&self) -> Option<&UiImage> {
+        if self.checked {
+            self.checked_image.as_ref()
+        } else {
+            self.unchecked_image.as_ref()
+        }
+    }
+}
+
+impl EventRetrigger for UiCheckbox {
+    type In = UiEvent;
+    type Out = UiToggleAction;
+
+    fn apply<R>(&self, event: &Self::In, out: &mut R)
+    where
+        R: EventReceiver<Self::Out>,
+    {
+        if event.event_type == UiEventType::Click {
+            out.receive_one(&UiToggleAction {
+                target: event.target,
+            });
+        }
+    }
+}
+
+/// Group of radio buttons, at most one of which is checked.
+///
+/// Radio buttons are `UiCheckbox`es created with `UiCheckbox::radio` and the entity of the group.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UiRadioGroup {
+    selected: Option<Entity>,
+}
+
+impl UiRadioGroup {
+    /// Creates a group with no radio button checked.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the radio button last checked by the user.
+    #[must_use]
+    pub fn selected(&self) -> Option<Entity> {
+        self.selected
+    }
+}
+
+/// This system toggles checkboxes and radio buttons when they are clicked, and sets their images.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug)]
+pub struct UiToggleSystem {
+    event_reader: ReaderId<UiToggleAction>,
+}
+
+impl UiToggleSystem {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiToggleAction>) -> Self {
+        Self { event_reader }
+    }
+}
+
+impl System for UiToggleSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiToggleSystem")
+                .read_resource::<EventChannel<UiToggleAction>>()
+                .write_resource::<EventChannel<UiEvent>>()
+                .with_query(<(Entity, &mut UiCheckbox)>::query())
+                .with_query(<&mut UiRadioGroup>::query())
+                .with_query(
+                    <(&UiCheckbox, &mut UiImage)>::query().filter(maybe_changed::<UiCheckbox>()),
+                )
+                .build(
+                    move |_commands,
+                          world,
+                          (toggle_actions, ui_events),
+                          (checkboxes, groups, images)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("ui_toggle_system");
+
+                        for action in toggle_actions.read(&mut self.event_reader) {
+                            let group = match checkboxes.get_mut(world, action.target) {
+                                Ok((_, checkbox)) => {
+                                    if checkbox.group.is_none() {
+                                        checkbox.checked = !checkbox.checked;
+                                        ui_events.single_write(UiEvent::new(
+                                            UiEventType::ValueChange,
+                                            action.target,
+                                        ));
+                                        continue;
+                                    }
+                                    if checkbox.checked {
+                                        continue;
+                                    }
+                                    checkbox.group
+                                }
+                                Err(_) => continue,
+                            };
+
+                            checkboxes.for_each_mut(world, |(entity, checkbox)| {
+                                if checkbox.group != group {
+                                    return;
+                                }
+                                let checked = *entity == action.target;
+                                if checkbox.checked != checked {
+                                    checkbox.checked = checked;
+                                    ui_events.single_write(UiEvent::new(
+                                        UiEventType::ValueChange,
+                                        *entity,
+                                    ));
+                                }
+                            });
+                            if let Some(group) = group {
+                                if let Ok(radio_group) = groups.get_mut(world, group) {
+                                    radio_group.selected = Some(action.target);
+                                }
+                                ui_events
+                                    .single_write(UiEvent::new(UiEventType::ValueChange, group));
+                            }
+                        }
+
+                        images.for_each_mut(world, |(checkbox, image)| {
+                            if let Some(checkbox_image) = checkbox.image() {
+                                if *image != *checkbox_image {
+                                    *image = checkbox_image.clone();
+                                }
+                            }
+                        });
+                    },
+                ),
+        )
+    }
+}
@@ -7,7 +7,7 @@ use amethyst_window::ScreenDimensions;
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 
-use super::{Anchor, ScaleMode, Stretch};
+use super::{Anchor, ScaleMode, Stretch, UiLayer};
 
 /// Utility lookup for finding UI entities based on `UiTransform` id
 #[derive(Debug)]
@@ -55,6 +55,9 @@ pub struct UiTransform {
     /// Z order.
     #[serde(alias = "z")]
     pub local_z: f32,
+    /// Layer drawn and clicked above the lower layers, or `None` for the layer of the parent.
+    #[serde_diff(opaque)]
+    pub layer: Option<UiLayer>,
     /// The width of this UI element.
     pub width: f32,
     /// The height of this UI element.
@@ -71,6 +74,10 @@ pub struct UiTransform {
     #[doc(hidden)]
     #[serde(alias = "z")]
     pub global_z: f32,
+    /// Layer set by the `UiTransformSystem`, inherited from the parent without a `layer`.
+    #[doc(hidden)]
+    #[serde_diff(opaque)]
+    pub global_layer: UiLayer,
     /// Width in pixels, used for rendering.  Duplicate of `width` if `scale_mode == ScaleMode::Pixel`.
     #[doc(hidden)]
     #[serde(alias = "width")]
@@ -88,6 +95,9 @@ pub struct UiTransform {
     /// Allows transparent (opaque = false) transforms to still be targeted by the events that pass
     /// through them.
     pub transparent_target: bool,
+    /// Restricts the rendering of the descendants of this element, images and glyphs, to its
+    /// rectangle.
+    pub clip: bool,
 }
 
 register_component_type!(UiTransform);
@@ -114,16 +124,19 @@ impl UiTransform {
             local_x: x,
             local_y: y,
             local_z: z,
+            layer: None,
             width,
             height,
             pixel_x: x,
             pixel_y: y,
             global_z: z,
+            global_layer: UiLayer::DEFAULT,
             pixel_width: width,
             pixel_height: height,
             scale_mode: ScaleMode::Pixel,
             opaque: true,
             transparent_target: false,
+            clip: false,
         }
     }
     /// Checks if the input position is in the `UiTransform` rectangle.
@@ -160,6 +173,14 @@ impl UiTransform {
         self
     }
 
+    /// Clips the rendering of the descendants of this ui element to its rectangle, e.g. for the
+    /// viewport of a minimap.
+    #[must_use]
+    pub fn into_clipping(mut self) -> Self {
+        self.clip = true;
+        self
+    }
+
     /// Adds stretching to this ui element so it can fill its parent.
     #[must_use]
     pub fn with_stretch(mut self, stretch: Stretch) -> Self {
@@ -167,6 +188,14 @@ impl UiTransform {
         self
     }
 
+    /// Puts this ui element and its descendants without a layer on `layer`, e.g.
+    /// `UiLayer::POPUP`, so they're drawn and clicked above the lower layers.
+    #[must_use]
+    pub fn with_layer(mut self, layer: UiLayer) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
     /// Returns the global x coordinate of this `UiTransform` as computed by the `UiTransformSystem`.
     #[must_use]
     pub fn pixel_x(&self) -> f32 {
@@ -0,0 +1,630 @@
+//! On-screen keyboard typing into the focused editable text, for touch screens and gamepads.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::{Entity, IntoQuery, ParallelRunnable, Resources, System, SystemBuilder, World},
+    shrev::{EventChannel, ReaderId},
+    transform::{Parent, Transform},
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    text_editing::{cursor_byte_index, delete_highlighted, move_cursor},
+    Anchor, FontAsset, LineMode, Stretch, TextEditing, UiButton, UiButtonBuilder, UiEvent,
+    UiEventType, UiText, UiTransform, Widget, WidgetId, Widgets,
+};
+
+const DEFAULT_Z: f32 = 1.0;
+const DEFAULT_WIDTH: f32 = 640.0;
+const DEFAULT_HEIGHT: f32 = 256.0;
+const DEFAULT_FONT_SIZE: f32 = 24.;
+const DEFAULT_TAB_ORDER: u32 = 100;
+
+/// A key of a `UiKeyboardLayout`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiKey {
+    /// Types a character, in upper case while shift is active.
+    Char(char),
+    /// Types a space.
+    Space,
+    /// Deletes the highlighted text, or the character before the cursor.
+    Backspace,
+    /// Inserts a line break in texts with `LineMode::Wrap`.
+    NewLine,
+    /// Commits the text, sending a `UiEventType::ValueCommit`.
+    Enter,
+    /// Types the next character in upper case.
+    Shift,
+    /// Moves the cursor to the previous character.
+    Left,
+    /// Moves the cursor to the next character.
+    Right,
+}
+
+impl UiKey {
+    /// Returns the text shown on the key.
+    #[must_use]
+    pub fn label(&self, shift: bool) -> String {
+        match self {
+            UiKey::Char(c) if shift => c.to_uppercase().collect(),
+            UiKey::Char(c) => c.to_string(),
+            UiKey::Space => "Space".to_string(),
+            UiKey::Backspace => "Back".to_string(),
+            UiKey::NewLine => "Line".to_string(),
+            UiKey::Enter => "Enter".to_string(),
+            UiKey::Shift => "Shift".to_string(),
+            UiKey::Left => "<".to_string(),
+            UiKey::Right => ">".to_string(),
+        }
+    }
+
+    /// Returns the width of the key, relative to the width of a character key.
+    #[must_use]
+    pub fn width(&self) -> f32 {
+        match self {
+            UiKey::Char(_) | UiKey::Left | UiKey::Right => 1.0,
+            UiKey::Space => 4.0,
+            _ => 1.5,
+        }
+    }
+}
+
+/// Rows of keys of a virtual keyboard, from top to bottom.
+///
+/// It can be deserialized, e.g. from RON, to provide the layouts of other languages:
+/// ```ron
+/// (rows: [[Char('a'), Char('b')], [Shift, Space, Backspace, Enter]])
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UiKeyboardLayout {
+    /// Keys of each row, from left to right.
+    pub rows: Vec<Vec<UiKey>>,
+}
+
+impl Default for UiKeyboardLayout {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+impl UiKeyboardLayout {
+    /// Creates a layout whose rows have a character key per character of `rows`.
+    #[must_use]
+    pub fn from_rows(rows: &[&str]) -> Self {
+        Self {
+            rows: rows
+                .iter()
+                .map(|row| row.chars().map(UiKey::Char).collect())
+                .collect(),
+        }
+    }
+
+    /// Adds a row of keys below the others.
+    #[must_use]
+    pub fn with_row(mut self, row: Vec<UiKey>) -> Self {
+        self.rows.push(row);
+        self
+    }
+
+    /// The US QWERTY layout, with digits and the usual punctuation.
+    #[must_use]
+    pub fn qwerty() -> Self {
+        let mut layout = Self::from_rows(&["1234567890", "qwertyuiop", "asdfghjkl'"]);
+        let mut bottom = vec![UiKey::Shift];
+        bottom.extend("zxcvbnm,.?".chars().map(UiKey::Char));
+        bottom.push(UiKey::Backspace);
+        layout.rows.push(bottom);
+        layout.with_row(vec![
+            UiKey::Left,
+            UiKey::Right,
+            UiKey::Space,
+            UiKey::NewLine,
+            UiKey::Enter,
+        ])
+    }
+
+    /// A numeric keypad.
+    #[must_use]
+    pub fn numeric() -> Self {
+        Self::from_rows(&["789", "456", "123"]).with_row(vec![
+            UiKey::Char('0'),
+            UiKey::Char('.'),
+            UiKey::Backspace,
+            UiKey::Enter,
+        ])
+    }
+
+    /// Returns the width of the widest row, relative to the width of a character key.
+    fn width(&self) -> f32 {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(UiKey::width).sum::<f32>())
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Component of a virtual keyboard, typing into the `target` text when its keys are clicked.
+///
+/// The target is the last focused entity with a `TextEditing`, and it can be set manually, e.g.
+/// to type into a text field without focusing it first. Typing sends the same `UiEvent`s as a
+/// physical keyboard, targeting the edited text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UiVirtualKeyboard {
+    /// The edited text, whose entity has a `UiText` and a `TextEditing`.
+    pub target: Option<Entity>,
+    /// Whether the next character is typed in upper case.
+    pub shift: bool,
+    keys: Vec<Entity>,
+}
+
+impl UiVirtualKeyboard {
+    /// Returns the entities of the keys, which receive the clicks.
+    #[must_use]
+    pub fn keys(&self) -> &[Entity] {
+        &self.keys
+    }
+}
+
+/// Component of a key of a `UiVirtualKeyboard`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UiVirtualKey {
+    /// The key.
+    pub key: UiKey,
+    /// Entity of the keyboard.
+    pub keyboard: Entity,
+    /// Entity of the `UiText` showing the label of the key.
+    pub label: Entity,
+}
+
+/// System typing into the targets of the `UiVirtualKeyboard`s when their keys are clicked.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug)]
+pub struct UiVirtualKeyboardSystem {
+    event_reader: ReaderId<UiEvent>,
+}
+
+impl UiVirtualKeyboardSystem {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self { event_reader }
+    }
+}
+
+impl System for UiVirtualKeyboardSystem {
+    fn build(mut self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("UiVirtualKeyboardSystem")
+                .write_resource::<EventChannel<UiEvent>>()
+                .with_query(<&mut UiVirtualKeyboard>::query())
+                .with_query(<&UiVirtualKey>::query())
+                .with_query(<(&mut UiText, &mut TextEditing)>::query())
+                .with_query(<&mut UiText>::query())
+                .build(
+                    move |_, world, ui_events, (keyboards, keys, editables, labels)| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("ui_virtual_keyboard_system");
+
+                        let events: Vec<UiEvent> =
+                            ui_events.read(&mut self.event_reader).cloned().collect();
+
+                        let mut emitted = Vec::new();
+                        for event in events {
+                            match event.event_type {
+                                UiEventType::Focus => {
+                                    if editables.get_mut(world, event.target).is_ok() {
+                                        for keyboard in keyboards.iter_mut(world) {
+                                            keyboard.target = Some(event.target);
+                                        }
+                                    }
+                                }
+                                UiEventType::Click => {
+                                    let key = match keys.get(world, event.target) {
+                                        Ok(key) => key.clone(),
+                                        Err(_) => continue,
+                                    };
+                                    let (target, shift) = match keyboards
+                                        .get_mut(world, key.keyboard)
+                                    {
+                                        Ok(keyboard) => (keyboard.target, keyboard.shift),
+                                        Err(_) => continue,
+                                    };
+
+                                    let mut new_shift = shift;
+                                    if key.key == UiKey::Shift {
+                                        new_shift = !shift;
+                                    } else if let Some(target) = target {
+                                        if let Ok((text, edit)) = editables.get_mut(world, target)
+                                        {
+                                            if let Some(event_type) =
+                                                type_key(&key.key, shift, text, edit)
+                                            {
+                                                emitted.push(UiEvent::new(event_type, target));
+                                            }
+                                            if let UiKey::Char(_) = key.key {
+                                                new_shift = false;
+                                            }
+                                        }
+                                    }
+                                    if new_shift == shift {
+                                        continue;
+                                    }
+
+                                    let key_entities = match keyboards
+                                        .get_mut(world, key.keyboard)
+                                    {
+                                        Ok(keyboard) => {
+                                            keyboard.shift = new_shift;
+                                            keyboard.keys.clone()
+                                        }
+                                        Err(_) => continue,
+                                    };
+                                    let relabeled: Vec<(Entity, String)> = key_entities
+                                        .iter()
+                                        .filter_map(|entity| keys.get(world, *entity).ok())
+                                        .map(|key| (key.label, key.key.label(new_shift)))
+                                        .collect();
+                                    for (label, text) in relabeled {
+                                        if let Ok(label) = labels.get_mut(world, label) {
+                                            label.text = text;
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        for event in emitted {
+                            ui_events.single_write(event);
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Types `key` into an editable text, and returns the event to send, if any.
+fn type_key(
+    key: &UiKey,
+    shift: bool,
+    text: &mut UiText,
+    edit: &mut TextEditing,
+) -> Option<UiEventType> {
+    let glyph_len = text.text.graphemes(true).count() as isize;
+    edit.cursor_blink_timer = 0.0;
+    match key {
+        UiKey::Char(_) | UiKey::Space | UiKey::NewLine => {
+            let typed = match key {
+                UiKey::Char(_) => key.label(shift),
+                UiKey::NewLine if text.line_mode == LineMode::Wrap => "\n".to_string(),
+                UiKey::NewLine => return None,
+                _ => " ".to_string(),
+            };
+            let deleted = delete_highlighted(edit, text);
+            if text.text.graphemes(true).count() >= edit.max_length {
+                return if deleted {
+                    Some(UiEventType::ValueChange)
+                } else {
+                    None
+                };
+            }
+            let index = cursor_byte_index(edit, text);
+            text.text.insert_str(index, &typed);
+            edit.cursor_position += 1;
+            Some(UiEventType::ValueChange)
+        }
+        UiKey::Backspace => {
+            if delete_highlighted(edit, text) {
+                return Some(UiEventType::ValueChange);
+            }
+            if edit.cursor_position == 0 {
+                return None;
+            }
+            let (byte, len) = text
+                .text
+                .grapheme_indices(true)
+                .nth(edit.cursor_position as usize - 1)
+                .map(|i| (i.0, i.1.len()))?;
+            text.text.drain(byte..(byte + len));
+            edit.cursor_position -= 1;
+            Some(UiEventType::ValueChange)
+        }
+        UiKey::Enter => Some(UiEventType::ValueCommit),
+        UiKey::Left => {
+            move_cursor(edit, (edit.cursor_position - 1).max(0), false);
+            None
+        }
+        UiKey::Right => {
+            move_cursor(edit, (edit.cursor_position + 1).min(glyph_len), false);
+            None
+        }
+        UiKey::Shift => None,
+    }
+}
+
+/// A virtual keyboard widget, whose `container` entity holds the `UiVirtualKeyboard`.
+#[derive(Clone, Debug)]
+pub struct UiKeyboard {
+    /// Entity of the container.
+    pub container: Entity,
+    /// Buttons of the keys, row by row.
+    pub keys: Vec<UiButton>,
+}
+
+impl Widget for UiKeyboard {}
+
+/// Convenience structure for building a virtual keyboard, with a button per key of its layout.
+///
+/// The key buttons are `Selectable`, so the keys can be chosen with a gamepad through the
+/// `UiNavigationSystem`, and their size is set to fit the rows of the layout in the keyboard.
+#[derive(Debug, Clone)]
+pub struct UiKeyboardBuilder<G, I: WidgetId> {
+    id: Option<I>,
+    x: f32,
+    y: f32,
+    z: f32,
+    width: f32,
+    height: f32,
+    anchor: Anchor,
+    stretch: Stretch,
+    parent: Option<Entity>,
+    layout: UiKeyboardLayout,
+    font: Option<Handle<FontAsset>>,
+    font_size: f32,
+    tab_order: u32,
+    target: Option<Entity>,
+    _phantom: PhantomData<G>,
+}
+
+impl<G, I: WidgetId> Default for UiKeyboardBuilder<G, I> {
+    fn default() -> Self {
+        UiKeyboardBuilder {
+            id: None,
+            x: 0.,
+            y: 0.,
+            z: DEFAULT_Z,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            anchor: Anchor::BottomMiddle,
+            stretch: Stretch::NoStretch,
+            parent: None,
+            layout: UiKeyboardLayout::default(),
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            tab_order: DEFAULT_TAB_ORDER,
+            target: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<G: PartialEq + Send + Sync + 'static, I: WidgetId> UiKeyboardBuilder<G, I> {
+    /// Construct a new `UiKeyboardBuilder` with the QWERTY layout.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an ID for this widget. The type of this ID will determine which `Widgets`
+    /// resource this widget will be added to, see [`Widgets`](struct.Widgets.html).
+    #[must_use]
+    pub fn with_id(mut self, id: I) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Sets the layout of the keys.
+    #[must_use]
+    pub fn with_layout(mut self, layout: UiKeyboardLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets the text edited until another editable text is focused.
+    #[must_use]
+    pub fn with_target(mut self, target: Entity) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Sets the font of the key labels.
+    #[must_use]
+    pub fn with_font(mut self, font: Handle<FontAsset>) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of the key labels.
+    #[must_use]
+    pub fn with_font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Sets the tab order of the first key, the others following it.
+    #[must_use]
+    pub fn with_tab_order(mut self, tab_order: u32) -> Self {
+        self.tab_order = tab_order;
+        self
+    }
+
+    /// Set the keyboard size
+    #[must_use]
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the keyboard position
+    #[must_use]
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Provide a Z position, i.e UI layer
+    #[must_use]
+    pub fn with_layer(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Add an anchor to the keyboard.
+    #[must_use]
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Stretch the keyboard.
+    #[must_use]
+    pub fn with_stretch(mut self, stretch: Stretch) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
+    /// Add a parent to the keyboard.
+    #[must_use]
+    pub fn with_parent(mut self, parent: Entity) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Build this with the `World` and `Resources`.
+    pub fn build_from_world_and_resources(
+        self,
+        world: &mut World,
+        resources: &mut Resources,
+    ) -> (I, UiKeyboard) {
+        let container = world.push(());
+
+        let key_width = self.width / self.layout.width().max(1.0);
+        let key_height = self.height / self.layout.rows.len().max(1) as f32;
+        let mut buttons = Vec::new();
+        let mut virtual_keys = Vec::new();
+        for (row_index, row) in self.layout.rows.iter().enumerate() {
+            let row_width: f32 = row.iter().map(|key| key.width() * key_width).sum();
+            let mut x = (self.width - row_width) / 2.;
+            let y = -(row_index as f32 + 0.5) * key_height;
+            for key in row {
+                let width = key.width() * key_width;
+                let mut builder = UiButtonBuilder::<G, I>::new(&key.label(false))
+                    .with_parent(container)
+                    .with_anchor(Anchor::TopLeft)
+                    .with_position(x + width / 2., y)
+                    .with_layer(self.z)
+                    .with_size(width, key_height)
+                    .with_font_size(self.font_size)
+                    .with_tab_order(self.tab_order + buttons.len() as u32)
+                    .with_class(&"key");
+                if let Some(font) = self.font.clone() {
+                    builder = builder.with_font(font);
+                }
+                let (_, button) = builder.build_from_world_and_resources(world, resources);
+                virtual_keys.push((button.image_entity, key.clone(), button.text_entity));
+                buttons.push(button);
+                x += width;
+            }
+        }
+
+        let widget = UiKeyboard {
+            container,
+            keys: buttons,
+        };
+
+        let id = {
+            let widget = widget.clone();
+
+            if !resources.contains::<Widgets<UiKeyboard, I>>() {
+                resources.insert(Widgets::<UiKeyboard, I>::new());
+            }
+
+            let mut keyboard_widgets = resources.get_mut::<Widgets<UiKeyboard, I>>().unwrap();
+            if let Some(id) = self.id {
+                let added_id = id.clone();
+                keyboard_widgets.add_with_id(id, widget);
+                added_id
+            } else {
+                keyboard_widgets.add(widget)
+            }
+        };
+
+        let mut container_entry = world
+            .entry(container)
+            .expect("Unreachable: Inserting newly created entity");
+        container_entry.add_component(
+            UiTransform::new(
+                format!("{}_keyboard", id),
+                self.anchor,
+                Anchor::Middle,
+                self.x,
+                self.y,
+                self.z,
+                self.width,
+                self.height,
+            )
+            .with_stretch(self.stretch),
+        );
+        container_entry.add_component(UiVirtualKeyboard {
+            target: self.target,
+            shift: false,
+            keys: virtual_keys.iter().map(|(entity, _, _)| *entity).collect(),
+        });
+        container_entry.add_component(Transform::default());
+        if let Some(parent) = self.parent {
+            container_entry.add_component(Parent(parent));
+        }
+
+        for (entity, key, label) in virtual_keys {
+            world
+                .entry(entity)
+                .expect("Unreachable: Inserting newly created entity")
+                .add_component(UiVirtualKey {
+                    key,
+                    keyboard: container,
+                    label,
+                });
+        }
+
+        (id, widget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_edit_the_text_at_the_cursor() {
+        let mut text = UiText::new(
+            None,
+            "ac".to_string(),
+            [1.; 4],
+            10.,
+            LineMode::Single,
+            Anchor::Middle,
+        );
+        let mut edit = TextEditing::new(3, [1.; 4], [0.; 4], false);
+        edit.cursor_position = 1;
+
+        let changed = type_key(&UiKey::Char('b'), true, &mut text, &mut edit);
+        assert_eq!(Some(UiEventType::ValueChange), changed);
+        assert_eq!("aBc", text.text);
+        assert_eq!(None, type_key(&UiKey::Char('d'), false, &mut text, &mut edit));
+        assert_eq!(None, type_key(&UiKey::NewLine, false, &mut text, &mut edit));
+
+        type_key(&UiKey::Right, false, &mut text, &mut edit);
+        type_key(&UiKey::Backspace, false, &mut text, &mut edit);
+        assert_eq!("aB", text.text);
+        assert_eq!(2, edit.cursor_position);
+        let committed = type_key(&UiKey::Enter, false, &mut text, &mut edit);
+        assert_eq!(Some(UiEventType::ValueCommit), committed);
+    }
+}
@@ -0,0 +1,524 @@
+//! Text drawn in world space, e.g. damage numbers and name tags.
+//!
+//! Entities with a [`WorldText`] and a `Transform` have their text laid out by the
+//! [`UiGlyphsSystem`](crate::UiGlyphsSystem) like [`UiText`](crate::UiText)s, and drawn at their
+//! position in the world by the [`RenderWorldText`] plugin. The text either faces the camera or
+//! lies in the XY plane of the entity, see [`WorldTextMode`].
+//!
+//! ```ignore
+//! RenderingBundle::<DefaultBackend>::new()
+//!     .with_plugin(RenderToWindow::from_config(display_config))
+//!     .with_plugin(RenderShaded3D::default())
+//!     .with_plugin(RenderWorldText::default())
+//! ```
+
+use amethyst_assets::{
+    prefab::{legion_prefab, register_component_type, serde_diff, SerdeDiff},
+    Handle,
+};
+use amethyst_core::{
+    ecs::{component, DispatcherBuilder, IntoQuery, Resources, World},
+    math::{convert, Matrix4},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use amethyst_error::Error;
+use amethyst_rendy::{
+    batch::OneLevelBatch,
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    camera::Viewport,
+    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    rendy::{
+        command::{QueueId, RenderPassEncoder},
+        factory::Factory,
+        graph::{
+            render::{PrepareResult, RenderGroup, RenderGroupDesc},
+            GraphContext, NodeBuffer, NodeImage,
+        },
+        hal::{
+            self,
+            device::Device,
+            format::Format,
+            pso::{self, ShaderStageFlags},
+        },
+        mesh::{AsVertex, Model, VertexFormat},
+        shader::{Shader, SpirvShader},
+    },
+    simple_shader_set,
+    submodules::{DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
+    system::GraphAuxData,
+    types::Backend,
+    util,
+};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+use type_uuid::TypeUuid;
+
+use crate::{
+    glyphs::{add_glyph_systems, UiGlyphsResource},
+    pass::UiArgs,
+    Anchor, FontAsset,
+};
+
+/// How a [`WorldText`] is oriented in the world.
+#[derive(Debug, Derivative, Clone, Copy, Eq, PartialEq, Deserialize, Serialize, SerdeDiff)]
+#[derivative(Default)]
+pub enum WorldTextMode {
+    /// The text faces the camera at the position of the entity, ignoring its rotation.
+    #[derivative(Default)]
+    Billboard,
+    /// The text lies in the XY plane of the entity, facing its Z axis.
+    Plane,
+}
+
+/// A component drawing text at the position of its entity, see the
+/// [module documentation](self).
+#[derive(Clone, Derivative, Deserialize, Serialize, SerdeDiff, TypeUuid)]
+#[uuid = "5c0f6e1a-93b4-4d27-8a5e-2f7b1c9d4e63"]
+#[derivative(Debug, Default)]
+#[serde(default)]
+pub struct WorldText {
+    /// The string rendered by this.
+    pub text: String,
+    /// The height of a line of text in pixels of the glyph cache, determining its sharpness.
+    #[derivative(Default(value = "32."))]
+    pub font_size: f32,
+    /// World units per pixel of the glyph cache, determining the size of the text in the world.
+    #[derivative(Default(value = "0.01"))]
+    pub scale: f32,
+    /// The sRGB color of the rendered text, using a range of 0.0 to 1.0 per channel.
+    #[derivative(Default(value = "[1., 1., 1., 1.]"))]
+    pub color: [f32; 4],
+    /// The font used for rendering.
+    #[serde(skip)]
+    #[serde_diff(opaque)]
+    pub font: Option<Handle<FontAsset>>,
+    /// Point of the text placed at the position of the entity.
+    pub align: Anchor,
+    /// How the text is oriented in the world.
+    pub mode: WorldTextMode,
+}
+
+register_component_type!(WorldText);
+
+impl WorldText {
+    /// Initializes a new `WorldText` facing the camera, with the default font.
+    #[must_use]
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Render the text with given font, `None` using the default one.
+    #[must_use]
+    pub fn with_font(mut self, font: Option<Handle<FontAsset>>) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Rasterize the text at given size in pixels, and draw it at `scale` world units per pixel.
+    #[must_use]
+    pub fn with_size(mut self, font_size: f32, scale: f32) -> Self {
+        self.font_size = font_size;
+        self.scale = scale;
+        self
+    }
+
+    /// Render the text with given color.
+    #[must_use]
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Place given point of the text at the position of the entity.
+    #[must_use]
+    pub fn with_align(mut self, align: Anchor) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Orient the text in the world as specified.
+    #[must_use]
+    pub fn with_mode(mut self, mode: WorldTextMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// Glyphs of a `WorldText` laid out by the `UiGlyphsSystem`, in pixels relative to the entity.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WorldGlyphs {
+    pub(crate) vertices: Vec<UiArgs>,
+}
+
+/// A [`RenderPlugin`] drawing [`WorldText`]s, see the [module documentation](self).
+///
+/// The text is drawn once per split screen camera when rendering to the window, see [`Viewport`].
+#[derive(Debug)]
+pub struct RenderWorldText {
+    target: Target,
+    order: i32,
+    viewports: Vec<Viewport>,
+}
+
+impl Default for RenderWorldText {
+    fn default() -> Self {
+        Self {
+            target: Target::default(),
+            order: RenderOrder::AfterTransparent.into(),
+            viewports: Vec::new(),
+        }
+    }
+}
+
+impl RenderWorldText {
+    /// Select render target on which the text should be rendered.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the text at given point of the frame, [`RenderOrder::AfterTransparent`] by default.
+    #[must_use]
+    pub fn with_order(mut self, order: impl Into<i32>) -> Self {
+        self.order = order.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderWorldText {
+    fn on_build<'a, 'b>(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        add_glyph_systems::<B>(resources, builder);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World, _resources: &Resources) -> bool {
+        self.viewports != Viewport::of_target(world, self.target)
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        self.viewports = Viewport::of_target(world, self.target);
+        let indices = Viewport::instances(&self.viewports);
+        let target = self.target;
+        let order = self.order;
+        plan.extend_target(self.target, move |ctx| {
+            for viewport in indices {
+                ctx.add(
+                    order,
+                    DrawWorldTextDesc::new()
+                        .with_target(target)
+                        .with_viewport(viewport)
+                        .with_samples(ctx.samples())
+                        .builder(),
+                )?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C)]
+struct WorldTextArgs {
+    model: [[f32; 4]; 4],
+    coords: [f32; 2],
+    dimensions: [f32; 2],
+    tex_coord_bounds: [f32; 4],
+    color: [f32; 4],
+    billboard: f32,
+}
+
+impl AsVertex for WorldTextArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::Rg32Sfloat, "coords"),
+            (Format::Rg32Sfloat, "dimensions"),
+            (Format::Rgba32Sfloat, "tex_coord_bounds"),
+            (Format::Rgba32Sfloat, "color"),
+            (Format::R32Sfloat, "billboard"),
+        ))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref WORLD_TEXT_VERTEX: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../compiled/world_text.vert.spv"),
+        ShaderStageFlags::VERTEX,
+        "main",
+    ).unwrap();
+
+    static ref WORLD_TEXT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
+        include_bytes!("../compiled/world_text.frag.spv"),
+        ShaderStageFlags::FRAGMENT,
+        "main",
+    ).unwrap();
+}
+
+/// Describes drawing [`WorldText`]s as seen by the camera of a render target.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawWorldTextDesc {
+    target: Target,
+    viewport: Option<usize>,
+    samples: u8,
+}
+
+impl DrawWorldTextDesc {
+    /// Create new `DrawWorldText` pass description
+    #[must_use]
+    pub fn new() -> Self {
+        DrawWorldTextDesc::default()
+    }
+
+    /// Draw the text as seen by the camera of given render target.
+    #[must_use]
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Draw the text as seen by the camera of the viewport with given index, limited to its
+    /// region of the target. See [`Viewport::cameras`].
+    #[must_use]
+    pub fn with_viewport(mut self, viewport: Option<usize>) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Build pipelines for a target with given number of samples per pixel.
+    #[must_use]
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, GraphAuxData> for DrawWorldTextDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &GraphAuxData,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, GraphAuxData>>, pso::CreationError> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = FlatEnvironmentSub::new(factory)?
+            .with_target(self.target)
+            .with_viewport(self.viewport);
+        let textures = TextureSub::new(factory)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let (pipeline, pipeline_layout) = build_world_text_pipeline(
+            factory,
+            subpass,
+            Viewport::pixel_rect_of(
+                aux.world,
+                self.viewport,
+                framebuffer_width,
+                framebuffer_height,
+            ),
+            self.samples,
+            vec![env.raw_layout(), textures.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawWorldText::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            vertex,
+            glyphs: OneLevelBatch::default(),
+        }))
+    }
+}
+
+/// Draws [`WorldText`]s in one instanced draw call.
+#[derive(Debug)]
+pub struct DrawWorldText<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, WorldTextArgs>,
+    glyphs: OneLevelBatch<TextureId, WorldTextArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, GraphAuxData> for DrawWorldText<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &GraphAuxData,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let GraphAuxData { world, resources } = aux;
+
+        self.env.process(factory, index, world, resources);
+        self.glyphs.clear_inner();
+
+        let glyphs_res = resources.get::<UiGlyphsResource>().unwrap();
+        let glyph_tex_id = glyphs_res.glyph_tex().and_then(|tex| {
+            self.textures
+                .insert(factory, resources, tex, hal::image::Layout::General)
+        });
+
+        if let Some((glyph_tex_id, _)) = glyph_tex_id {
+            let mut query = <(&WorldText, &WorldGlyphs, &Transform)>::query()
+                .filter(!component::<Hidden>() & !component::<HiddenPropagate>());
+            let glyphs = query.iter(*world).flat_map(|(text, glyphs, transform)| {
+                let model: [[f32; 4]; 4] =
+                    convert::<_, Matrix4<f32>>(*transform.global_matrix()).into();
+                let billboard = match text.mode {
+                    WorldTextMode::Billboard => 1.0,
+                    WorldTextMode::Plane => 0.0,
+                };
+                let scale = text.scale;
+                glyphs.vertices.iter().map(move |glyph| {
+                    let coords: [f32; 2] = glyph.coords.into();
+                    let dimensions: [f32; 2] = glyph.dimensions.into();
+                    WorldTextArgs {
+                        model,
+                        coords: [coords[0] * scale, coords[1] * scale],
+                        dimensions: [dimensions[0] * scale, dimensions[1] * scale],
+                        tex_coord_bounds: glyph.tex_coord_bounds.into(),
+                        color: glyph.color.into(),
+                        billboard,
+                    }
+                })
+            });
+            self.glyphs.insert(glyph_tex_id, glyphs);
+        }
+
+        self.textures.maintain(factory, resources);
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            self.glyphs.prune();
+            self.vertex.write(
+                factory,
+                index,
+                self.glyphs.count() as u64,
+                self.glyphs.data(),
+            );
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &GraphAuxData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        for (&tex, range) in self.glyphs.iter() {
+            if self.textures.loaded(tex) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &GraphAuxData) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_world_text_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    subpass: hal::pass::Subpass<'_, B>,
+    viewport: pso::Rect,
+    samples: u8,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), pso::CreationError> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { WORLD_TEXT_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { WORLD_TEXT_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(WorldTextArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(pso::Primitive::TriangleStrip))
+                .with_shaders(simple_shader_set(&shader_vertex, Some(&shader_fragment)))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_viewport_rect(viewport)
+                .with_multisampling(util::multisampling(samples))
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::ALPHA),
+                }])
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: false,
+                }),
+        )
+        .build(factory, None);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
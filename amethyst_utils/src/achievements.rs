@@ -0,0 +1,535 @@
+//! Statistics and achievements defined in RON, updated through events and saved locally.
+//!
+//! The stats and achievements of a game are described by [`AchievementDefinitions`], usually
+//! loaded from a RON file shipped with the game:
+//!
+//! ```ron
+//! (
+//!     stats: [
+//!         (id: "enemies_defeated"),
+//!         (id: "best_score", kind: Max),
+//!     ],
+//!     achievements: [
+//!         (
+//!             id: "first_blood",
+//!             name: "First blood",
+//!             requirement: Some((stat: "enemies_defeated", value: 1)),
+//!         ),
+//!         (id: "secret_room", name: "Explorer"),
+//!     ],
+//! )
+//! ```
+//!
+//! Systems update the progress by writing [`StatEvent`]s. Achievements with a requirement unlock
+//! when their stat reaches its value, the others when unlocked explicitly. Every unlock is
+//! announced with an [`AchievementUnlocked`] event, and the progress is saved to a local file.
+//!
+//! Platform integrations, such as Steam, mirror the progress by implementing
+//! [`AchievementBackend`] and being added to the [`AchievementsBundle`], so game code only ever
+//! deals with the events:
+//!
+//! ```ignore
+//! let game_data = DispatcherBuilder::default().add_bundle(
+//!     AchievementsBundle::new("config/achievements.ron")
+//!         .with_save_path("saves/achievements.ron")
+//!         .with_backend(SteamBackend::new()),
+//! );
+//!
+//! // In a system:
+//! stat_events.single_write(StatEvent::Add {
+//!     stat: "enemies_defeated".into(),
+//!     amount: 1,
+//! });
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBuilder, SystemBundle, World},
+    shrev::EventChannel,
+};
+use amethyst_error::{format_err, Error, ResultExt};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// How a stat combines the values reported for it.
+#[derive(Derivative, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derivative(Default)]
+pub enum StatKind {
+    /// Values are added up, as for the number of enemies defeated.
+    #[derivative(Default)]
+    Counter,
+    /// Only the highest value set is kept, as for a best score.
+    Max,
+}
+
+/// Definition of a stat tracked by [`Achievements`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatDefinition {
+    /// Identifier of the stat in events and saved progress.
+    pub id: String,
+    /// How the values of the stat are combined.
+    #[serde(default)]
+    pub kind: StatKind,
+}
+
+/// Value a stat must reach for an achievement to unlock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatRequirement {
+    /// Identifier of the stat.
+    pub stat: String,
+    /// Value the stat must reach.
+    pub value: i64,
+}
+
+/// Definition of an achievement tracked by [`Achievements`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AchievementDefinition {
+    /// Identifier of the achievement in events, saved progress and backends.
+    pub id: String,
+    /// Name shown to the player.
+    #[serde(default)]
+    pub name: String,
+    /// Description shown to the player.
+    #[serde(default)]
+    pub description: String,
+    /// Stat value unlocking the achievement. Without it, the achievement is only unlocked by
+    /// [`StatEvent::Unlock`].
+    #[serde(default)]
+    pub requirement: Option<StatRequirement>,
+}
+
+/// Stats and achievements of a game.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AchievementDefinitions {
+    /// The stats.
+    #[serde(default)]
+    pub stats: Vec<StatDefinition>,
+    /// The achievements.
+    #[serde(default)]
+    pub achievements: Vec<AchievementDefinition>,
+}
+
+impl AchievementDefinitions {
+    /// Loads the definitions from a RON file.
+    ///
+    /// # Errors
+    /// Results in an error if the file can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .with_context(|_| format_err!("Failed to read {}", path.display()))?;
+        ron::de::from_str(&data)
+            .map_err(|e| format_err!("Failed to parse {}: {}", path.display(), e))
+    }
+}
+
+/// Progress saved to disk.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Progress {
+    stats: BTreeMap<String, i64>,
+    unlocked: BTreeSet<String>,
+}
+
+/// Resource holding the values of the stats and the unlocked achievements.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug)]
+pub struct Achievements {
+    definitions: AchievementDefinitions,
+    progress: Progress,
+    save_path: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl Achievements {
+    /// Creates the resource with all stats at zero and no achievement unlocked, without saving
+    /// the progress.
+    #[must_use]
+    pub fn new(definitions: AchievementDefinitions) -> Self {
+        Self {
+            definitions,
+            progress: Progress::default(),
+            save_path: None,
+            dirty: false,
+        }
+    }
+
+    /// Creates the resource with the progress saved at `save_path`, if there is any, and saves the
+    /// progress there.
+    ///
+    /// # Errors
+    /// Results in an error if the saved progress exists but can't be read or parsed.
+    pub fn load(
+        definitions: AchievementDefinitions,
+        save_path: impl Into<PathBuf>,
+    ) -> Result<Self, Error> {
+        let save_path = save_path.into();
+        let progress = if save_path.exists() {
+            let data = fs::read_to_string(&save_path)
+                .with_context(|_| format_err!("Failed to read {}", save_path.display()))?;
+            ron::de::from_str(&data)
+                .map_err(|e| format_err!("Failed to parse {}: {}", save_path.display(), e))?
+        } else {
+            Progress::default()
+        };
+        Ok(Self {
+            definitions,
+            progress,
+            save_path: Some(save_path),
+            dirty: false,
+        })
+    }
+
+    /// The definitions of the stats and achievements.
+    #[must_use]
+    pub fn definitions(&self) -> &AchievementDefinitions {
+        &self.definitions
+    }
+
+    /// The value of a stat, zero until it's first updated.
+    #[must_use]
+    pub fn stat(&self, id: &str) -> i64 {
+        self.progress.stats.get(id).copied().unwrap_or(0)
+    }
+
+    /// Whether an achievement is unlocked.
+    #[must_use]
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.progress.unlocked.contains(id)
+    }
+
+    /// Identifiers of the unlocked achievements.
+    pub fn unlocked(&self) -> impl Iterator<Item = &str> {
+        self.progress.unlocked.iter().map(String::as_str)
+    }
+
+    /// Adds `amount` to a stat, returning the achievements it unlocked.
+    pub fn add_stat(&mut self, id: &str, amount: i64) -> Vec<String> {
+        self.update_stat(id, |_, value| value + amount)
+    }
+
+    /// Sets a stat, returning the achievements it unlocked. `Max` stats keep their value if it's
+    /// higher.
+    pub fn set_stat(&mut self, id: &str, value: i64) -> Vec<String> {
+        self.update_stat(id, |kind, current| {
+            match kind {
+                StatKind::Counter => value,
+                StatKind::Max => current.max(value),
+            }
+        })
+    }
+
+    /// Unlocks an achievement, returning whether it wasn't already.
+    pub fn unlock(&mut self, id: &str) -> bool {
+        if !self.definitions.achievements.iter().any(|a| a.id == id) {
+            log::warn!("Unknown achievement `{}`", id);
+            return false;
+        }
+        let unlocked = self.progress.unlocked.insert(id.to_string());
+        self.dirty |= unlocked;
+        unlocked
+    }
+
+    /// Writes the progress to the save path, if there is one.
+    ///
+    /// # Errors
+    /// Results in an error if the file can't be written.
+    pub fn save(&mut self) -> Result<(), Error> {
+        let path = match &self.save_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let data = ron::ser::to_string_pretty(&self.progress, ron::ser::PrettyConfig::default())
+            .map_err(|e| format_err!("Failed to serialize achievements: {}", e))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)
+            .with_context(|_| format_err!("Failed to write {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn update_stat(&mut self, id: &str, update: impl FnOnce(StatKind, i64) -> i64) -> Vec<String> {
+        let kind = match self.definitions.stats.iter().find(|stat| stat.id == id) {
+            Some(stat) => stat.kind,
+            None => {
+                log::warn!("Unknown stat `{}`", id);
+                return Vec::new();
+            }
+        };
+        let current = self.stat(id);
+        let value = update(kind, current);
+        if value == current {
+            return Vec::new();
+        }
+        self.progress.stats.insert(id.to_string(), value);
+        self.dirty = true;
+
+        let progress = &mut self.progress;
+        self.definitions
+            .achievements
+            .iter()
+            .filter(|achievement| {
+                achievement
+                    .requirement
+                    .as_ref()
+                    .map_or(false, |req| req.stat == id && value >= req.value)
+            })
+            .filter(|achievement| progress.unlocked.insert(achievement.id.clone()))
+            .map(|achievement| achievement.id.clone())
+            .collect()
+    }
+}
+
+/// Event updating [`Achievements`], sent through an `EventChannel<StatEvent>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatEvent {
+    /// Adds to a stat.
+    Add {
+        /// Identifier of the stat.
+        stat: String,
+        /// Amount added.
+        amount: i64,
+    },
+    /// Sets a stat, see [`Achievements::set_stat`].
+    Set {
+        /// Identifier of the stat.
+        stat: String,
+        /// New value.
+        value: i64,
+    },
+    /// Unlocks an achievement.
+    Unlock(String),
+}
+
+/// Event sent through an `EventChannel<AchievementUnlocked>` when an achievement unlocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AchievementUnlocked {
+    /// Identifier of the achievement.
+    pub id: String,
+}
+
+/// Mirrors the progress of [`Achievements`] to a platform, such as Steam.
+pub trait AchievementBackend: Send + Sync {
+    /// Called once when the bundle is loaded, with the progress saved locally, to catch up with
+    /// the progress made while the platform was unavailable.
+    fn synchronize(&mut self, _achievements: &Achievements) {}
+
+    /// Called when a stat changes.
+    fn stat_changed(&mut self, _stat: &str, _value: i64) {}
+
+    /// Called when an achievement unlocks.
+    fn achievement_unlocked(&mut self, achievement: &str);
+
+    /// Called after the progress is saved locally, for platforms that batch their updates.
+    fn flush(&mut self) {}
+}
+
+/// Loads [`Achievements`], updates them from [`StatEvent`]s, sends [`AchievementUnlocked`]
+/// events, saves the progress and mirrors it to the backends.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct AchievementsBundle {
+    definitions_path: PathBuf,
+    save_path: Option<PathBuf>,
+    save_interval: Duration,
+    #[derivative(Debug = "ignore")]
+    backends: Vec<Box<dyn AchievementBackend>>,
+}
+
+impl AchievementsBundle {
+    /// Creates the bundle loading the definitions from `definitions_path`. The progress isn't
+    /// persisted unless a save path is set.
+    #[must_use]
+    pub fn new(definitions_path: impl Into<PathBuf>) -> Self {
+        Self {
+            definitions_path: definitions_path.into(),
+            save_path: None,
+            save_interval: Duration::from_secs(5),
+            backends: Vec::new(),
+        }
+    }
+
+    /// Loads the progress from `save_path` and saves it there.
+    #[must_use]
+    pub fn with_save_path(mut self, save_path: impl Into<PathBuf>) -> Self {
+        self.save_path = Some(save_path.into());
+        self
+    }
+
+    /// Sets how often the progress is saved when it changed. The default is every 5 seconds.
+    #[must_use]
+    pub fn with_save_interval(mut self, save_interval: Duration) -> Self {
+        self.save_interval = save_interval;
+        self
+    }
+
+    /// Adds a backend mirroring the progress.
+    #[must_use]
+    pub fn with_backend(mut self, backend: impl AchievementBackend + 'static) -> Self {
+        self.backends.push(Box::new(backend));
+        self
+    }
+}
+
+impl SystemBundle for AchievementsBundle {
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        let definitions = AchievementDefinitions::load(&self.definitions_path)?;
+        let achievements = match &self.save_path {
+            Some(save_path) => Achievements::load(definitions, save_path.clone())?,
+            None => Achievements::new(definitions),
+        };
+        let mut backends = std::mem::take(&mut self.backends);
+        for backend in &mut backends {
+            backend.synchronize(&achievements);
+        }
+        resources.insert(achievements);
+        resources.insert(EventChannel::<AchievementUnlocked>::new());
+        let mut reader = resources
+            .get_mut_or_default::<EventChannel<StatEvent>>()
+            .register_reader();
+
+        let save_interval = self.save_interval;
+        let mut last_save = Instant::now();
+        builder.add_system(move || {
+            SystemBuilder::new("achievements_system")
+                .read_resource::<EventChannel<StatEvent>>()
+                .write_resource::<Achievements>()
+                .write_resource::<EventChannel<AchievementUnlocked>>()
+                .build(
+                    move |_, _, (stat_events, achievements, unlocked_events), _| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("achievements_system");
+
+                        for event in stat_events.read(&mut reader) {
+                            let (unlocked, stat) = match event {
+                                StatEvent::Add { stat, amount } => {
+                                    (achievements.add_stat(stat, *amount), Some(stat))
+                                }
+                                StatEvent::Set { stat, value } => {
+                                    (achievements.set_stat(stat, *value), Some(stat))
+                                }
+                                StatEvent::Unlock(id) if achievements.unlock(id) => {
+                                    (vec![id.clone()], None)
+                                }
+                                StatEvent::Unlock(_) => continue,
+                            };
+                            if let Some(stat) = stat {
+                                let value = achievements.stat(stat);
+                                for backend in &mut backends {
+                                    backend.stat_changed(stat, value);
+                                }
+                            }
+                            for id in unlocked {
+                                for backend in &mut backends {
+                                    backend.achievement_unlocked(&id);
+                                }
+                                unlocked_events.single_write(AchievementUnlocked { id });
+                            }
+                        }
+
+                        if !achievements.dirty || last_save.elapsed() < save_interval {
+                            return;
+                        }
+                        last_save = Instant::now();
+                        match achievements.save() {
+                            Ok(()) => {
+                                for backend in &mut backends {
+                                    backend.flush();
+                                }
+                            }
+                            Err(e) => log::warn!("Failed to save achievements: {}", e),
+                        }
+                    },
+                )
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definitions() -> AchievementDefinitions {
+        ron::de::from_str(
+            r#"(
+                stats: [
+                    (id: "kills"),
+                    (id: "score", kind: Max),
+                ],
+                achievements: [
+                    (id: "first_blood", requirement: Some((stat: "kills", value: 1))),
+                    (id: "veteran", requirement: Some((stat: "kills", value: 10))),
+                    (id: "high_score", requirement: Some((stat: "score", value: 1000))),
+                    (id: "secret"),
+                ],
+            )"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn stats_unlock_achievements_once() {
+        let mut achievements = Achievements::new(definitions());
+
+        assert_eq!(achievements.add_stat("kills", 1), vec!["first_blood"]);
+        assert!(achievements.add_stat("kills", 5).is_empty());
+        assert_eq!(achievements.set_stat("kills", 12), vec!["veteran"]);
+        assert!(achievements.set_stat("kills", 0).is_empty());
+        assert!(achievements.add_stat("kills", 10).is_empty());
+        assert_eq!(achievements.stat("kills"), 10);
+        assert!(achievements.add_stat("unknown", 1).is_empty());
+
+        assert!(achievements.unlock("secret"));
+        assert!(!achievements.unlock("secret"));
+        assert!(!achievements.unlock("unknown"));
+        assert_eq!(
+            achievements.unlocked().collect::<Vec<_>>(),
+            vec!["first_blood", "secret", "veteran"]
+        );
+    }
+
+    #[test]
+    fn max_stats_keep_highest_value() {
+        let mut achievements = Achievements::new(definitions());
+
+        assert!(achievements.set_stat("score", 800).is_empty());
+        assert!(achievements.set_stat("score", 300).is_empty());
+        assert_eq!(achievements.stat("score"), 800);
+        assert_eq!(achievements.set_stat("score", 1200), vec!["high_score"]);
+    }
+
+    #[test]
+    fn progress_is_saved_and_loaded() {
+        let dir = std::env::temp_dir().join("amethyst_achievements_save");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("achievements.ron");
+
+        let mut achievements = Achievements::load(definitions(), path.clone()).unwrap();
+        achievements.add_stat("kills", 3);
+        achievements.unlock("secret");
+        achievements.save().unwrap();
+
+        let loaded = Achievements::load(definitions(), path.clone()).unwrap();
+        assert_eq!(loaded.stat("kills"), 3);
+        assert!(loaded.is_unlocked("first_blood"));
+        assert!(loaded.is_unlocked("secret"));
+        assert!(!loaded.is_unlocked("veteran"));
+    }
+}
@@ -0,0 +1,456 @@
+//! Periodic and event triggered saves of the game, rotated over several slots so a crash while
+//! saving never corrupts the only save.
+//!
+//! The [`AutosaveBundle`] captures a snapshot of the game on the main thread with a function
+//! given by the game, then serializes and writes it on the `ArcThreadPool`. Saves happen every
+//! interval and whenever an [`AutosaveTrigger`] event is sent, for instance when the player
+//! reaches a checkpoint:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct SaveGame {
+//!     level: u32,
+//!     position: [f32; 2],
+//! }
+//!
+//! let game_data = DispatcherBuilder::default().add_bundle(
+//!     AutosaveBundle::new("saves", |_world, resources| {
+//!         let progress = resources.get::<Progress>()?;
+//!         Some(SaveGame {
+//!             level: progress.level,
+//!             position: progress.position,
+//!         })
+//!     })
+//!     .with_interval(Duration::from_secs(120))
+//!     .with_slots(3),
+//! );
+//!
+//! // When loading the game:
+//! let save: Option<SaveGame> = AutosaveSlots::new("saves", "autosave", 3).load_latest()?;
+//! ```
+//!
+//! Every save goes to the slot holding the oldest save, through a temporary file renamed over the
+//! slot once fully written. Saves start with a sequence number and a checksum of their data, so
+//! [`AutosaveSlots::load_latest`] skips files that are truncated or corrupted and falls back to
+//! the previous save.
+
+use std::{
+    fs::{self, File},
+    hash::Hasher,
+    io::Write,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    shrev::{EventChannel, ReaderId},
+    ArcThreadPool,
+};
+use amethyst_error::{format_err, Error, ResultExt};
+use derivative::Derivative;
+use fnv::FnvHasher;
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+const MAGIC: &str = "amethyst-autosave";
+
+/// Save slots of the autosaves, files in a directory named after the saves and the slot number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutosaveSlots {
+    directory: PathBuf,
+    name: String,
+    slots: usize,
+}
+
+/// A valid save read from a slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotSave {
+    /// Number of the slot.
+    pub slot: usize,
+    /// Number incremented with every save, the highest being the latest save.
+    pub sequence: u64,
+    /// Serialized data of the save.
+    pub data: String,
+}
+
+impl AutosaveSlots {
+    /// Creates `slots` slots in `directory`, at least one, their files named `name.<slot>.ron`.
+    #[must_use]
+    pub fn new(directory: impl Into<PathBuf>, name: impl Into<String>, slots: usize) -> Self {
+        Self {
+            directory: directory.into(),
+            name: name.into(),
+            slots: slots.max(1),
+        }
+    }
+
+    /// Path of the file of a slot.
+    #[must_use]
+    pub fn path(&self, slot: usize) -> PathBuf {
+        self.directory.join(format!("{}.{}.ron", self.name, slot))
+    }
+
+    /// Reads the save of a slot, `None` if the slot is empty, or its file incomplete or corrupted.
+    #[must_use]
+    pub fn read(&self, slot: usize) -> Option<SlotSave> {
+        let contents = fs::read_to_string(self.path(slot)).ok()?;
+        let mut parts = contents.splitn(4, '\n');
+        if parts.next()? != MAGIC {
+            return None;
+        }
+        let sequence = parts.next()?.parse().ok()?;
+        let checksum = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let data = parts.next()?;
+        if self::checksum(data) != checksum {
+            log::warn!("Autosave {} is corrupted", self.path(slot).display());
+            return None;
+        }
+        Some(SlotSave {
+            slot,
+            sequence,
+            data: data.to_string(),
+        })
+    }
+
+    /// The most recent valid save of all slots.
+    #[must_use]
+    pub fn latest(&self) -> Option<SlotSave> {
+        (0..self.slots)
+            .filter_map(|slot| self.read(slot))
+            .max_by_key(|save| save.sequence)
+    }
+
+    /// Deserializes the most recent valid save, `None` if there is none.
+    ///
+    /// # Errors
+    /// Results in an error if the save doesn't match `T`.
+    pub fn load_latest<T: DeserializeOwned>(&self) -> Result<Option<T>, Error> {
+        self.latest()
+            .map(|save| {
+                ron::de::from_str(&save.data).map_err(|e| {
+                    format_err!("Autosave {} doesn't match its type: {}", save.slot, e)
+                })
+            })
+            .transpose()
+    }
+
+    /// The sequence numbers of the valid saves of all slots, `None` for the empty slots.
+    #[must_use]
+    pub fn sequences(&self) -> Vec<Option<u64>> {
+        (0..self.slots)
+            .map(|slot| self.read(slot).map(|save| save.sequence))
+            .collect()
+    }
+
+    /// The slot the next save goes to: the first one without a valid save, or else the one with
+    /// the oldest save.
+    #[must_use]
+    pub fn next_slot(&self) -> usize {
+        oldest_slot(&self.sequences())
+    }
+
+    /// Writes a save to a slot, replacing its file only once the save is entirely on disk.
+    ///
+    /// # Errors
+    /// Results in an error if the file can't be written.
+    pub fn write(&self, slot: usize, sequence: u64, data: &str) -> Result<PathBuf, Error> {
+        fs::create_dir_all(&self.directory).with_context(|_| {
+            format_err!("Failed to create directory {}", self.directory.display())
+        })?;
+        let path = self.path(slot);
+        let temp_path = path.with_extension("ron.tmp");
+        {
+            let mut file = File::create(&temp_path)
+                .with_context(|_| format_err!("Failed to create {}", temp_path.display()))?;
+            write!(
+                file,
+                "{}\n{}\n{:016x}\n{}",
+                MAGIC,
+                sequence,
+                checksum(data),
+                data
+            )
+            .and_then(|_| file.sync_all())
+            .with_context(|_| format_err!("Failed to write {}", temp_path.display()))?;
+        }
+        fs::rename(&temp_path, &path)
+            .with_context(|_| format_err!("Failed to replace {}", path.display()))?;
+        // The rename only survives a crash once the directory itself is on disk.
+        #[cfg(unix)]
+        File::open(&self.directory)
+            .and_then(|directory| directory.sync_all())
+            .with_context(|_| format_err!("Failed to sync {}", self.directory.display()))?;
+        Ok(path)
+    }
+}
+
+/// The first slot without a save in `sequences`, or else the one with the oldest save.
+fn oldest_slot(sequences: &[Option<u64>]) -> usize {
+    sequences
+        .iter()
+        .position(Option::is_none)
+        .or_else(|| {
+            sequences
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, sequence)| *sequence)
+                .map(|(slot, _)| slot)
+        })
+        .unwrap_or(0)
+}
+
+fn checksum(data: &str) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(data.as_bytes());
+    hasher.finish()
+}
+
+/// Event sent through an `EventChannel<AutosaveTrigger>` to save the game at the next frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AutosaveTrigger;
+
+/// Event sent through an `EventChannel<AutosaveEvent>` when an autosave finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutosaveEvent {
+    /// The game was saved.
+    Saved {
+        /// Slot the game was saved to.
+        slot: usize,
+        /// Path of the file of the slot.
+        path: PathBuf,
+    },
+    /// The game couldn't be saved, the previous saves being left untouched.
+    Failed {
+        /// Slot the game was being saved to.
+        slot: usize,
+        /// Description of the error.
+        error: String,
+    },
+}
+
+/// Function capturing a snapshot of the game to save, `None` to skip the save.
+type Capture<T> = Box<dyn FnMut(&World, &Resources) -> Option<T>>;
+
+/// Saves snapshots of type `T` of the game to [`AutosaveSlots`] at an interval and on
+/// [`AutosaveTrigger`] events, sending [`AutosaveEvent`]s.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct AutosaveBundle<T> {
+    directory: PathBuf,
+    name: String,
+    slots: usize,
+    interval: Option<Duration>,
+    #[derivative(Debug = "ignore")]
+    capture: Option<Capture<T>>,
+    marker: PhantomData<T>,
+}
+
+impl<T> AutosaveBundle<T> {
+    /// Creates the bundle saving to `directory` the snapshots returned by `capture`.
+    ///
+    /// By default, the saves are named `autosave`, rotated over 3 slots and made every 5 minutes.
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        capture: impl FnMut(&World, &Resources) -> Option<T> + 'static,
+    ) -> Self {
+        Self {
+            directory: directory.into(),
+            name: "autosave".into(),
+            slots: 3,
+            interval: Some(Duration::from_secs(300)),
+            capture: Some(Box::new(capture)),
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the name of the save files.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the number of slots the saves are rotated over.
+    #[must_use]
+    pub fn with_slots(mut self, slots: usize) -> Self {
+        self.slots = slots;
+        self
+    }
+
+    /// Sets the interval between saves, `None` to only save on [`AutosaveTrigger`] events.
+    #[must_use]
+    pub fn with_interval(mut self, interval: impl Into<Option<Duration>>) -> Self {
+        self.interval = interval.into();
+        self
+    }
+}
+
+impl<T> SystemBundle for AutosaveBundle<T>
+where
+    T: Serialize + Send + 'static,
+{
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        let capture = self
+            .capture
+            .take()
+            .ok_or_else(|| format_err!("AutosaveBundle can only be loaded once"))?;
+        let reader = resources
+            .get_mut_or_default::<EventChannel<AutosaveTrigger>>()
+            .register_reader();
+        resources.get_or_insert_with(EventChannel::<AutosaveEvent>::new);
+
+        // The slots are only read once, the saves keep track of them afterwards.
+        let slots = AutosaveSlots::new(self.directory.clone(), self.name.clone(), self.slots);
+        let sequences = slots.sequences();
+        let mut autosave = Autosave {
+            sequence: sequences.iter().flatten().copied().max().unwrap_or(0),
+            sequences,
+            slots,
+            interval: self.interval,
+            last_save: Instant::now(),
+            pending: false,
+            in_flight: false,
+            capture,
+            reader,
+            results: mpsc::channel(),
+        };
+        builder.add_thread_local_fn(move |world, resources| autosave.run(world, resources));
+        Ok(())
+    }
+}
+
+type SaveResult = (usize, u64, Result<PathBuf, Error>);
+
+struct Autosave<T> {
+    slots: AutosaveSlots,
+    /// Sequence numbers of the saves in the slots.
+    sequences: Vec<Option<u64>>,
+    sequence: u64,
+    interval: Option<Duration>,
+    last_save: Instant,
+    pending: bool,
+    in_flight: bool,
+    capture: Capture<T>,
+    reader: ReaderId<AutosaveTrigger>,
+    results: (Sender<SaveResult>, Receiver<SaveResult>),
+}
+
+impl<T> Autosave<T>
+where
+    T: Serialize + Send + 'static,
+{
+    fn run(&mut self, world: &mut World, resources: &mut Resources) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("autosave");
+
+        for (slot, sequence, result) in self.results.1.try_iter() {
+            self.in_flight = false;
+            let event = match result {
+                Ok(path) => {
+                    self.sequences[slot] = Some(sequence);
+                    AutosaveEvent::Saved { slot, path }
+                }
+                Err(e) => {
+                    log::error!("Failed to autosave: {}", e);
+                    AutosaveEvent::Failed {
+                        slot,
+                        error: e.to_string(),
+                    }
+                }
+            };
+            if let Some(mut events) = resources.get_mut::<EventChannel<AutosaveEvent>>() {
+                events.single_write(event);
+            }
+        }
+
+        if let Some(triggers) = resources.get::<EventChannel<AutosaveTrigger>>() {
+            self.pending |= triggers.read(&mut self.reader).count() > 0;
+        }
+        if let Some(interval) = self.interval {
+            self.pending |= self.last_save.elapsed() >= interval;
+        }
+        if !self.pending || self.in_flight {
+            return;
+        }
+        self.pending = false;
+        self.last_save = Instant::now();
+
+        let snapshot = match (self.capture)(world, resources) {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        self.sequence += 1;
+        self.in_flight = true;
+        let slot = oldest_slot(&self.sequences);
+        let (slots, sequence, sender) = (self.slots.clone(), self.sequence, self.results.0.clone());
+        let save = move || {
+            let result = ron::ser::to_string(&snapshot)
+                .map_err(|e| format_err!("Failed to serialize autosave: {}", e))
+                .and_then(|data| slots.write(slot, sequence, &data));
+            let _ = sender.send((slot, sequence, result));
+        };
+        match resources.get::<ArcThreadPool>() {
+            Some(pool) => pool.spawn(save),
+            None => save(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(name: &str) -> AutosaveSlots {
+        let dir = std::env::temp_dir().join(format!("amethyst_autosave_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        AutosaveSlots::new(dir, "save", 3)
+    }
+
+    #[test]
+    fn saves_rotate_over_slots() {
+        let slots = slots("rotate");
+        for sequence in 1..=5 {
+            let slot = slots.next_slot();
+            slots.write(slot, sequence, &sequence.to_string()).unwrap();
+        }
+
+        let mut sequences: Vec<_> = (0..3)
+            .map(|slot| slots.read(slot).unwrap().sequence)
+            .collect();
+        sequences.sort_unstable();
+        assert_eq!(sequences, vec![3, 4, 5]);
+        assert_eq!(slots.load_latest::<u64>().unwrap(), Some(5));
+    }
+
+    #[test]
+    fn corrupted_saves_are_skipped() {
+        let slots = slots("corrupted");
+        slots.write(0, 1, "(level: 1)").unwrap();
+        slots.write(1, 2, "(level: 2)").unwrap();
+        let contents = fs::read_to_string(slots.path(1)).unwrap();
+        fs::write(slots.path(1), contents.replace("2)", "3)")).unwrap();
+        fs::write(slots.path(2), &contents[..contents.len() / 2]).unwrap();
+
+        assert_eq!(slots.read(1), None);
+        assert_eq!(slots.read(2), None);
+        assert_eq!(slots.latest().unwrap().data, "(level: 1)");
+        assert_eq!(slots.next_slot(), 1);
+    }
+
+    #[test]
+    fn oldest_slot_prefers_empty_slots() {
+        assert_eq!(oldest_slot(&[Some(4), None, Some(2)]), 1);
+        assert_eq!(oldest_slot(&[Some(4), Some(5), Some(2)]), 2);
+    }
+}
@@ -1,6 +1,8 @@
-//! `CircularBuffer`
+//! `CircularBuffer` and its lock-free variant `ConcurrentCircularBuffer`.
 
-use std::collections::VecDeque;
+use std::collections::{vec_deque, VecDeque};
+
+use crossbeam_queue::ArrayQueue;
 
 /// A `CircularBuffer` that drops the oldest element inserted when full.
 /// # Example
@@ -59,4 +61,133 @@ impl<A> CircularBuffer<A> {
     pub fn capacity(&self) -> usize {
         self.cap
     }
+
+    /// Iterates over the values, from the oldest to the newest.
+    pub fn iter(&self) -> vec_deque::Iter<'_, A> {
+        self.queue.iter()
+    }
+
+    /// Returns the newest value.
+    #[must_use]
+    pub fn latest(&self) -> Option<&A> {
+        self.queue.back()
+    }
+
+    /// Returns the mean of the values mapped by `value`, or `None` when the buffer is empty.
+    pub fn mean_by(&self, value: impl Fn(&A) -> f64) -> Option<f64> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        Some(self.queue.iter().map(value).sum::<f64>() / self.queue.len() as f64)
+    }
+
+    /// Iterates over the moving average of the values mapped by `value`: the means of every
+    /// `window` consecutive values, from the oldest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use amethyst::utils::circular_buffer::CircularBuffer;
+    /// let mut buf = CircularBuffer::<u32>::new(4);
+    /// for value in &[1, 3, 5, 1] {
+    ///     buf.push(*value);
+    /// }
+    /// let averages: Vec<f64> = buf.moving_average_by(2, |v| f64::from(*v)).collect();
+    /// assert_eq!(averages, vec![2.0, 4.0, 3.0]);
+    /// ```
+    pub fn moving_average_by<'a>(
+        &'a self,
+        window: usize,
+        value: impl Fn(&A) -> f64 + 'a,
+    ) -> impl Iterator<Item = f64> + 'a {
+        let window = window.max(1);
+        let mut sum = 0.0;
+        self.queue.iter().enumerate().filter_map(move |(index, elem)| {
+            sum += value(elem);
+            if index >= window {
+                sum -= value(&self.queue[index - window]);
+            }
+            if index + 1 >= window {
+                Some(sum / window as f64)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Moves the values of a `ConcurrentCircularBuffer` into this buffer, dropping the oldest
+    /// values when full.
+    pub fn extend_from(&mut self, buffer: &ConcurrentCircularBuffer<A>) {
+        while let Some(elem) = buffer.pop() {
+            self.push(elem);
+        }
+    }
+}
+
+/// A bounded buffer shared between threads, which drops the oldest element inserted when full.
+///
+/// It's lock-free, so systems running in parallel can push to it from a shared resource
+/// without waiting for each other, to collect per-thread debug statistics for instance. The
+/// values are then popped by a single consumer, usually into a `CircularBuffer` with
+/// `CircularBuffer::extend_from`.
+///
+/// # Example
+///
+/// ```
+/// # use amethyst::utils::circular_buffer::{CircularBuffer, ConcurrentCircularBuffer};
+/// let shared = ConcurrentCircularBuffer::<u32>::new(2);
+/// assert!(shared.push(1).is_none());
+/// assert!(shared.push(2).is_none());
+/// assert_eq!(shared.push(3), Some(1));
+///
+/// let mut buf = CircularBuffer::new(8);
+/// buf.extend_from(&shared);
+/// assert_eq!(buf.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+/// assert!(shared.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentCircularBuffer<A> {
+    queue: ArrayQueue<A>,
+}
+
+impl<A> ConcurrentCircularBuffer<A> {
+    /// Creates a new `ConcurrentCircularBuffer` with fixed size.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero.
+    #[must_use]
+    pub fn new(size: usize) -> Self {
+        ConcurrentCircularBuffer {
+            queue: ArrayQueue::new(size),
+        }
+    }
+
+    /// Adds a value to the buffer, from any thread.
+    /// Returns the popped value if the buffer is full.
+    pub fn push(&self, elem: A) -> Option<A> {
+        self.queue.force_push(elem)
+    }
+
+    /// Removes the oldest value of the buffer.
+    pub fn pop(&self) -> Option<A> {
+        self.queue.pop()
+    }
+
+    /// Returns the number of values in the buffer.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns whether the buffer is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Returns the capacity of the buffer.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
 }
@@ -1,9 +1,12 @@
 //! Util Resources
 
+use std::time::Duration;
+
 use amethyst_core::{
     ecs::{
         DispatcherBuilder, ParallelRunnable, Resources, System, SystemBuilder, SystemBundle, World,
     },
+    shrev::EventChannel,
     Time,
 };
 use amethyst_error::Error;
@@ -25,19 +28,25 @@ use crate::circular_buffer::CircularBuffer;
 /// `sampled_fps` will return the averaged framerate. This gives a better approximation of the "felt"
 /// framerate by the user.
 ///
+/// Hitches don't show in averages, so the frame times of the last `history` frames are also kept
+/// for `frame_time_percentile`, e.g. `p99_frame_time`. With a frame budget, the `FpsCounterSystem`
+/// sends a [`FrameSpike`] through an `EventChannel<FrameSpike>` for each frame taking longer.
+///
 /// # Example
 /// ```
+/// # use std::time::Duration;
 /// # use amethyst::utils::fps_counter::FpsCounter;
 /// # use amethyst::core::ecs::{World, Resources};
 /// # let mut world = World::default();
 /// let mut resources = Resources::default();
-/// let counter = FpsCounter::new(2);
+/// let counter = FpsCounter::new(2).with_budget(Duration::from_millis(20));
 /// resources.insert(counter);
 /// ```
 #[derive(Debug)]
 pub struct FpsCounter {
     buf: CircularBuffer<u64>,
-    sum: u64,
+    history: CircularBuffer<u64>,
+    budget: Option<Duration>,
 }
 
 impl Default for FpsCounter {
@@ -47,28 +56,96 @@ impl Default for FpsCounter {
 }
 
 impl FpsCounter {
+    /// Number of frames whose times are kept for the percentiles, by default.
+    pub const DEFAULT_HISTORY: usize = 600;
+
     /// Creates a new `FpsCounter` that calculates the average fps over samplesize values.
     #[must_use]
     pub fn new(samplesize: usize) -> FpsCounter {
         FpsCounter {
             buf: CircularBuffer::<u64>::new(samplesize),
-            sum: 0,
+            history: CircularBuffer::<u64>::new(Self::DEFAULT_HISTORY),
+            budget: None,
         }
     }
 
+    /// Keeps the frame times of the last `frames` frames for the percentiles.
+    #[must_use]
+    pub fn with_history(mut self, frames: usize) -> Self {
+        self.history = CircularBuffer::new(frames);
+        self
+    }
+
+    /// Sets the frame budget, over which frames are reported as `FrameSpike`s.
+    #[must_use]
+    pub fn with_budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sets the frame budget, or `None` to stop reporting spikes.
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget = budget;
+    }
+
+    /// Returns the frame budget.
+    #[must_use]
+    pub fn budget(&self) -> Option<Duration> {
+        self.budget
+    }
+
     /// Add a new delta time value.
     pub fn push(&mut self, elem: u64) {
-        self.sum += elem;
-        if let Some(front) = self.buf.push(elem) {
-            self.sum -= front;
+        self.buf.push(elem);
+        self.history.push(elem);
+    }
+
+    /// Get the time of this frame.
+    #[must_use]
+    pub fn frame_time(&self) -> Option<Duration> {
+        self.buf.latest().map(|nanos| Duration::from_nanos(*nanos))
+    }
+
+    /// Returns whether this frame took longer than the budget.
+    #[must_use]
+    pub fn is_spike(&self) -> bool {
+        match (self.frame_time(), self.budget) {
+            (Some(time), Some(budget)) => time > budget,
+            _ => false,
+        }
+    }
+
+    /// Get the frame time under which `percentile` percent of the kept frames ran, using the
+    /// nearest rank. Returns `None` before the first frame.
+    #[must_use]
+    pub fn frame_time_percentile(&self, percentile: f32) -> Option<Duration> {
+        let mut times: Vec<u64> = self.history.iter().copied().collect();
+        if times.is_empty() {
+            return None;
         }
+        times.sort_unstable();
+        let rank = (f64::from(percentile.clamp(0.0, 100.0)) * times.len() as f64 / 100.0).ceil();
+        let rank = rank as usize;
+        Some(Duration::from_nanos(times[rank.max(1) - 1]))
+    }
+
+    /// Get the frame time under which 95% of the kept frames ran.
+    #[must_use]
+    pub fn p95_frame_time(&self) -> Option<Duration> {
+        self.frame_time_percentile(95.0)
+    }
+
+    /// Get the frame time under which 99% of the kept frames ran.
+    #[must_use]
+    pub fn p99_frame_time(&self) -> Option<Duration> {
+        self.frame_time_percentile(99.0)
     }
 
     /// Get the fps of the this frame.
     #[must_use]
     pub fn frame_fps(&self) -> f32 {
-        if let Some(back) = self.buf.queue().back() {
-            return 1.0e9 / *back as f32;
+        if let Some(latest) = self.buf.latest() {
+            return 1.0e9 / *latest as f32;
         }
         0.0
     }
@@ -76,13 +153,25 @@ impl FpsCounter {
     /// Get the average fps over the samplesize frames.
     #[must_use]
     pub fn sampled_fps(&self) -> f32 {
-        if self.sum == 0 || self.buf.queue().is_empty() {
-            return 0.0;
+        match self.buf.mean_by(|nanos| *nanos as f64) {
+            Some(mean) if mean > 0.0 => (1.0e9 / mean) as f32,
+            _ => 0.0,
         }
-        1.0e9 * self.buf.queue().len() as f32 / self.sum as f32
     }
 }
 
+/// Event sent through an `EventChannel<FrameSpike>` when a frame takes longer than the budget of
+/// the `FpsCounter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameSpike {
+    /// Number of the frame, as counted by `Time::frame_number`.
+    pub frame_number: u64,
+    /// Time the frame took.
+    pub frame_time: Duration,
+    /// Budget of the `FpsCounter` when the frame ran.
+    pub budget: Duration,
+}
+
 /// Add this system to your game to automatically push FPS values
 /// to the [`FpsCounter`](../resources/struct.FpsCounter.html) resource with id 0
 struct FpsCounterSystem;
@@ -93,11 +182,23 @@ impl System for FpsCounterSystem {
             SystemBuilder::new("fps_counter_system")
                 .read_resource::<Time>()
                 .write_resource::<FpsCounter>()
-                .build(move |_, _, (time, counter), _| {
+                .write_resource::<EventChannel<FrameSpike>>()
+                .build(move |_, _, (time, counter, spikes), _| {
                     #[cfg(feature = "profiler")]
                     profile_scope!("fps_counter_system");
 
                     counter.push(time.delta_real_time().as_nanos() as u64);
+                    if counter.is_spike() {
+                        if let (Some(frame_time), Some(budget)) =
+                            (counter.frame_time(), counter.budget())
+                        {
+                            spikes.single_write(FrameSpike {
+                                frame_number: time.frame_number(),
+                                frame_time,
+                                budget,
+                            });
+                        }
+                    }
                     //Enable this to debug performance engine wide.
                     log::debug!(
                         "Cur FPS: {}, Sampled: {}",
@@ -109,7 +210,8 @@ impl System for FpsCounterSystem {
     }
 }
 
-/// Automatically adds a `FpsCounterSystem` and a [`FpsCounter`] resource with the specified sample size.
+/// Automatically adds a `FpsCounterSystem`, a [`FpsCounter`] resource with the specified sample
+/// size and the `EventChannel<FrameSpike>`.
 #[derive(Default, Debug)]
 pub struct FpsCounterBundle {
     samplesize: Option<usize>,
@@ -142,7 +244,31 @@ impl SystemBundle for FpsCounterBundle {
             self.samplesize
                 .map_or_else(FpsCounter::default, FpsCounter::new),
         );
+        resources.get_or_insert_with(EventChannel::<FrameSpike>::new);
         builder.add_system(FpsCounterSystem);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_and_spikes_use_the_frame_times() {
+        let mut counter = FpsCounter::new(4)
+            .with_history(100)
+            .with_budget(Duration::from_millis(20));
+        assert_eq!(None, counter.p95_frame_time());
+        for frame in 1..=100 {
+            counter.push(Duration::from_millis(frame).as_nanos() as u64);
+        }
+        assert_eq!(Some(Duration::from_millis(95)), counter.p95_frame_time());
+        assert_eq!(Some(Duration::from_millis(99)), counter.p99_frame_time());
+        assert_eq!(Some(Duration::from_millis(1)), counter.frame_time_percentile(0.0));
+        assert!(counter.is_spike());
+
+        counter.push(Duration::from_millis(16).as_nanos() as u64);
+        assert!(!counter.is_spike());
+    }
+}
@@ -16,11 +16,14 @@
 
 pub use self::app_root_dir::*;
 
+pub mod achievements;
 pub mod app_root_dir;
 pub mod auto_fov;
+pub mod autosave;
 pub mod circular_buffer;
 pub mod fps_counter;
 pub mod ortho_camera;
 pub mod removal;
+pub mod settings;
 pub mod tag;
 pub mod time_destroy;
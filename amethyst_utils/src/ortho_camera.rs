@@ -1,8 +1,8 @@
 //! Provides a automatically resized orthographic camera.
 
 use amethyst_core::{
-    ecs::{IntoQuery, Runnable, SystemBuilder, Write},
-    Axis2,
+    ecs::{IntoQuery, Read, Runnable, SystemBuilder, Write},
+    Axis2, Transform,
 };
 use amethyst_rendy::camera::Camera;
 use amethyst_window::ScreenDimensions;
@@ -69,11 +69,52 @@ impl Default for CameraOrthoWorldCoordinates {
     }
 }
 
+/// Extents of the level the view of a `CameraOrtho` is kept inside of.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Copy)]
+pub struct CameraOrthoBounds {
+    /// Left x coordinate
+    pub left: f32,
+    /// Right x coordinate
+    pub right: f32,
+    /// Bottom y coordinate
+    pub bottom: f32,
+    /// Top y coordinate
+    pub top: f32,
+}
+
+impl CameraOrthoBounds {
+    /// Creates bounds of given extents.
+    #[must_use]
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        Self {
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+}
+
+/// Clamps `position` so that `view`, relative to it, stays within `bounds`, or centers the view
+/// on the bounds when it's larger.
+fn clamp_axis(position: f32, view: (f32, f32), bounds: (f32, f32)) -> f32 {
+    let (view_min, view_max) = (view.0.min(view.1), view.0.max(view.1));
+    let (min, max) = (bounds.0.min(bounds.1), bounds.0.max(bounds.1));
+    if view_max - view_min >= max - min {
+        (min + max - view_min - view_max) / 2.0
+    } else {
+        position.clamp(min - view_min, max - view_max)
+    }
+}
+
 /// `Component` attached to the camera's entity that allows automatically adjusting the camera's matrix according
 /// to preferences in the "mode" and `world_coordinates` fields.
 /// It adjusts the camera so that the camera's world coordinates are always visible.
 /// You must add the `CameraOrthoSystem` to your dispatcher for this to take effect (no dependencies required).
 ///
+/// With `world_bounds`, the system built by `build_camera_bounds_system` also keeps the view of the
+/// camera inside the level, e.g. while it follows a player.
+///
 /// # Example
 ///
 /// ```
@@ -95,6 +136,10 @@ pub struct CameraOrtho {
     pub mode: CameraNormalizeMode,
     /// The world coordinates that this camera will keep visible as the window size changes
     pub world_coordinates: CameraOrthoWorldCoordinates,
+    /// The extents the view of this camera is kept inside of, if any
+    #[new(default)]
+    #[serde(default)]
+    pub world_bounds: Option<CameraOrthoBounds>,
     #[new(default)]
     aspect_ratio_cache: f32,
 }
@@ -106,10 +151,33 @@ impl CameraOrtho {
         CameraOrtho {
             mode,
             world_coordinates: CameraOrthoWorldCoordinates::default(),
+            world_bounds: None,
             aspect_ratio_cache: 0.0,
         }
     }
 
+    /// Keeps the view of the camera inside `bounds`.
+    #[must_use]
+    pub fn with_world_bounds(mut self, bounds: CameraOrthoBounds) -> Self {
+        self.world_bounds = Some(bounds);
+        self
+    }
+
+    /// Returns the position of a camera at `position` moved so that its view stays inside the
+    /// `world_bounds`. Along axes where the view is larger than the bounds, it's centered on them.
+    #[must_use]
+    pub fn clamp_position(&self, position: (f32, f32), window_aspect_ratio: f32) -> (f32, f32) {
+        let bounds = match self.world_bounds {
+            Some(bounds) => bounds,
+            None => return position,
+        };
+        let offsets = self.camera_offsets(window_aspect_ratio);
+        (
+            clamp_axis(position.0, (offsets.0, offsets.1), (bounds.left, bounds.right)),
+            clamp_axis(position.1, (offsets.2, offsets.3), (bounds.bottom, bounds.top)),
+        )
+    }
+
     /// Get the camera matrix offsets according to the specified options.
     #[must_use]
     pub fn camera_offsets(&self, window_aspect_ratio: f32) -> (f32, f32, f32, f32) {
@@ -264,10 +332,43 @@ pub fn build_camera_normalize_system() -> impl Runnable {
         })
 }
 
+/// System that moves the cameras with a `CameraOrtho` having `world_bounds` so that their view
+/// stays inside the bounds.
+///
+/// It changes the translation of the `Transform`, so it's meant for cameras without a parent, and
+/// should run after the systems moving and zooming the cameras, and before the `TransformBundle`.
+#[must_use]
+pub fn build_camera_bounds_system() -> impl Runnable {
+    SystemBuilder::new("camera_bounds_system")
+        .read_resource::<ScreenDimensions>()
+        .with_query(<(Read<CameraOrtho>, Write<Transform>)>::query())
+        .build(move |_, subworld, dimensions, query| {
+            #[cfg(feature = "profiler")]
+            profile_scope!("camera_bounds_system");
+
+            let aspect = dimensions.aspect_ratio();
+
+            for (ortho_camera, transform) in query.iter_mut(subworld) {
+                if ortho_camera.world_bounds.is_none() {
+                    continue;
+                }
+                let translation = transform.translation();
+                let position = (translation.x, translation.y);
+                let clamped = ortho_camera.clamp_position(position, aspect);
+                if clamped != position {
+                    transform.set_translation_x(clamped.0);
+                    transform.set_translation_y(clamped.1);
+                }
+            }
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::Axis2;
-    use crate::ortho_camera::{CameraNormalizeMode, CameraOrtho, CameraOrthoWorldCoordinates};
+    use crate::ortho_camera::{
+        CameraNormalizeMode, CameraOrtho, CameraOrthoBounds, CameraOrthoWorldCoordinates,
+    };
 
     // TODO: Disabled until someone fixes the formula (if possible).
     /*#[test]
@@ -392,6 +493,7 @@ mod test {
                 near: 0.1,
                 far: 2000.,
             },
+            world_bounds: None,
             aspect_ratio_cache: 0.0,
         };
         assert_eq!((0.0, 1.0, 1.5, -0.5), cam.camera_offsets(aspect));
@@ -410,6 +512,7 @@ mod test {
                 near: 0.1,
                 far: 2000.,
             },
+            world_bounds: None,
             aspect_ratio_cache: 0.0,
         };
         assert_eq!((0.0, 2.0, 0.0, 2.0), cam.camera_offsets(aspect));
@@ -428,6 +531,7 @@ mod test {
                 near: 0.1,
                 far: 2000.,
             },
+            world_bounds: None,
             aspect_ratio_cache: 0.0,
         };
         assert_eq!((-1.0, 3.0, 0.0, 2.0), cam.camera_offsets(aspect));
@@ -446,8 +550,29 @@ mod test {
                 near: 0.1,
                 far: 2000.,
             },
+            world_bounds: None,
             aspect_ratio_cache: 0.0,
         };
         assert_eq!((0.0, 2.0, -1.0, 3.0), cam.camera_offsets(aspect));
     }
+
+    #[test]
+    fn view_is_clamped_to_bounds() {
+        let cam = CameraOrtho::new(
+            CameraNormalizeMode::Contain,
+            CameraOrthoWorldCoordinates {
+                left: -50.,
+                right: 50.,
+                bottom: -50.,
+                top: 50.,
+                near: 0.1,
+                far: 2000.,
+            },
+        )
+        .with_world_bounds(CameraOrthoBounds::new(0., 1000., 0., 150.));
+        assert_eq!((500.0, 75.0), cam.clamp_position((500.0, 75.0), 1.0));
+        assert_eq!((50.0, 100.0), cam.clamp_position((-20.0, 400.0), 1.0));
+        // The view is 200 high, so it's centered vertically on the 150 high bounds.
+        assert_eq!((950.0, 75.0), cam.clamp_position((990.0, 0.0), 0.5));
+    }
 }
@@ -0,0 +1,444 @@
+//! Game settings loaded from layered RON files, reloaded when the files change.
+//!
+//! [`Settings`] combines the defaults of a settings type with any number of RON files, each
+//! overriding the values of the previous ones: typically the settings shipped with the game
+//! followed by the settings of the player. Only the values a file contains are overridden, so a
+//! player file can be as small as `(volume: 0.5)`.
+//!
+//! Menus edit the settings through [`Settings::edit`] and persist them with [`Settings::save`],
+//! which writes the values differing from the lower layers to the last file. The
+//! [`SettingsBundle`] watches the files and reloads the settings when they change on disk.
+//! Systems react to changes by reading [`SettingsChanged`] events:
+//!
+//! ```ignore
+//! #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+//! struct AudioSettings {
+//!     volume: f32,
+//!     muted: bool,
+//! }
+//!
+//! let game_data = DispatcherBuilder::default().add_bundle(SettingsBundle::<AudioSettings>::new(
+//!     vec!["config/audio.ron", "saves/audio.ron"],
+//! ));
+//!
+//! // In a menu:
+//! settings.edit().volume = 0.5;
+//! settings.save()?;
+//! ```
+//!
+//! The files are merged on their RON data before being deserialized, so structs in them must be
+//! written without their names, as in `(volume: 0.5)`.
+
+use std::{
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBuilder, SystemBundle, World},
+    shrev::EventChannel,
+};
+use amethyst_error::{format_err, Error, ResultExt};
+use ron::Value;
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Resource holding settings of type `T`, loaded from layered files.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug)]
+pub struct Settings<T> {
+    value: T,
+    layers: Vec<Layer>,
+    version: u64,
+    edited: bool,
+}
+
+#[derive(Debug)]
+struct Layer {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl<T> Settings<T>
+where
+    T: Default + Serialize + DeserializeOwned,
+{
+    /// Loads the settings from the default value of `T`, overridden by the files at `paths` in
+    /// order. Files that don't exist are skipped.
+    ///
+    /// # Errors
+    /// Results in an error if a file can't be read or parsed, or the merged data doesn't match `T`.
+    pub fn load<P: Into<PathBuf>>(paths: impl IntoIterator<Item = P>) -> Result<Self, Error> {
+        let mut settings = Self {
+            value: T::default(),
+            layers: paths
+                .into_iter()
+                .map(|path| {
+                    Layer {
+                        path: path.into(),
+                        modified: None,
+                    }
+                })
+                .collect(),
+            version: 0,
+            edited: false,
+        };
+        settings.reload()?;
+        Ok(settings)
+    }
+
+    /// The current settings.
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Changes the settings, announcing the change to systems during the next frame.
+    pub fn edit(&mut self) -> &mut T {
+        self.edited = true;
+        self.version += 1;
+        &mut self.value
+    }
+
+    /// Number incremented every time the settings are edited or reloaded.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Path of the file the settings are saved to, the last layer.
+    #[must_use]
+    pub fn save_path(&self) -> Option<&Path> {
+        self.layers.last().map(|layer| layer.path.as_path())
+    }
+
+    /// Writes the settings differing from the lower layers to the last file.
+    ///
+    /// # Errors
+    /// Results in an error if there are no files or the last one can't be written.
+    pub fn save(&mut self) -> Result<(), Error> {
+        let (last, lower) = self
+            .layers
+            .split_last_mut()
+            .ok_or_else(|| format_err!("Settings have no file to save to"))?;
+        let mut base = to_value(&T::default())?;
+        merge(&mut base, merged(lower)?);
+        let overrides = diff(&base, &to_value(&self.value)?)
+            .unwrap_or_else(|| Value::Map(ron::value::Map::new()));
+        let data = ron::ser::to_string_pretty(&overrides, ron::ser::PrettyConfig::default())
+            .map_err(|e| format_err!("Failed to serialize settings: {}", e))?;
+
+        if let Some(parent) = last.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&last.path, data)
+            .with_context(|_| format_err!("Failed to write {}", last.path.display()))?;
+        last.modified = modified(&last.path);
+        Ok(())
+    }
+
+    /// Reloads the settings if one of the files changed since they were last read, returning
+    /// whether they were reloaded.
+    ///
+    /// # Errors
+    /// Results in an error if a changed file can't be read or parsed, keeping the current settings.
+    pub fn reload_if_modified(&mut self) -> Result<bool, Error> {
+        if self
+            .layers
+            .iter()
+            .any(|layer| modified(&layer.path) != layer.modified)
+        {
+            self.reload()?;
+            self.version += 1;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn reload(&mut self) -> Result<(), Error> {
+        for layer in &mut self.layers {
+            layer.modified = modified(&layer.path);
+        }
+        let mut value = to_value(&T::default())?;
+        merge(&mut value, merged(&self.layers)?);
+        self.value = value
+            .into_rust()
+            .map_err(|e| format_err!("Settings don't match their type: {}", e))?;
+        Ok(())
+    }
+}
+
+impl<T> std::ops::Deref for Settings<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// What caused a [`SettingsChanged`] event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsSource {
+    /// The settings were changed through [`Settings::edit`].
+    Edit,
+    /// One of the files changed on disk.
+    File,
+}
+
+/// Event sent through an `EventChannel<SettingsChanged<T>>` when settings of type `T` change.
+#[derive(Debug, Clone)]
+pub struct SettingsChanged<T> {
+    /// The new settings.
+    pub settings: T,
+    /// What changed the settings.
+    pub source: SettingsSource,
+}
+
+/// Loads [`Settings`] of type `T`, reloads them when their files change, and sends
+/// [`SettingsChanged`] events.
+#[derive(Debug)]
+pub struct SettingsBundle<T> {
+    paths: Vec<PathBuf>,
+    poll_interval: Duration,
+    marker: PhantomData<T>,
+}
+
+impl<T> SettingsBundle<T> {
+    /// Creates the bundle loading settings from the files at `paths`, later files overriding
+    /// earlier ones. Settings are saved to the last file.
+    #[must_use]
+    pub fn new<P: Into<PathBuf>>(paths: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            poll_interval: Duration::from_secs(1),
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets how often the files are checked for changes. The default is once per second.
+    #[must_use]
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+}
+
+impl<T> SystemBundle for SettingsBundle<T>
+where
+    T: Default + Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn load(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        resources.insert(Settings::<T>::load(self.paths.clone())?);
+        resources.insert(EventChannel::<SettingsChanged<T>>::new());
+
+        let poll_interval = self.poll_interval;
+        let mut last_poll = Instant::now();
+        builder.add_system(move || {
+            SystemBuilder::new("settings_system")
+                .write_resource::<Settings<T>>()
+                .write_resource::<EventChannel<SettingsChanged<T>>>()
+                .build(move |_, _, (settings, channel), _| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("settings_system");
+
+                    if settings.edited {
+                        settings.edited = false;
+                        channel.single_write(SettingsChanged {
+                            settings: settings.value.clone(),
+                            source: SettingsSource::Edit,
+                        });
+                    }
+
+                    if last_poll.elapsed() < poll_interval {
+                        return;
+                    }
+                    last_poll = Instant::now();
+                    match settings.reload_if_modified() {
+                        Ok(true) => {
+                            channel.single_write(SettingsChanged {
+                                settings: settings.value.clone(),
+                                source: SettingsSource::File,
+                            });
+                        }
+                        Ok(false) => {}
+                        Err(e) => log::warn!("Failed to reload settings: {}", e),
+                    }
+                })
+        });
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, Error> {
+    ron::ser::to_string(value)
+        .map_err(|e| format_err!("Failed to serialize settings: {}", e))
+        .and_then(|data| {
+            ron::de::from_str(&data).map_err(|e| format_err!("Failed to read settings: {}", e))
+        })
+}
+
+/// Data of the existing files of the layers, merged in order.
+fn merged(layers: &[Layer]) -> Result<Value, Error> {
+    let mut value = Value::Map(ron::value::Map::new());
+    for layer in layers {
+        if !layer.path.exists() {
+            continue;
+        }
+        let data = fs::read_to_string(&layer.path)
+            .with_context(|_| format_err!("Failed to read {}", layer.path.display()))?;
+        let layer_value = ron::de::from_str(&data)
+            .map_err(|e| format_err!("Failed to parse {}: {}", layer.path.display(), e))?;
+        merge(&mut value, layer_value);
+    }
+    Ok(value)
+}
+
+/// Overrides `base` with `overlay`, recursing into maps so only the values `overlay` contains
+/// are replaced.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Map(base), Value::Map(overlay)) => {
+            for (key, value) in overlay.iter() {
+                match base.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, existing)) => merge(existing, value.clone()),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// The parts of `value` that differ from `base`, `None` if there are none.
+fn diff(base: &Value, value: &Value) -> Option<Value> {
+    match (base, value) {
+        (Value::Map(base), Value::Map(map)) => {
+            let changed: ron::value::Map = map
+                .iter()
+                .filter_map(|(key, item)| {
+                    match base.iter().find(|(k, _)| *k == key) {
+                        Some((_, base_item)) => diff(base_item, item),
+                        None => Some(item.clone()),
+                    }
+                    .map(|item| (key.clone(), item))
+                })
+                .collect();
+            if changed.is_empty() {
+                None
+            } else {
+                Some(Value::Map(changed))
+            }
+        }
+        (base, value) if base == value => None,
+        (_, value) => Some(value.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+    struct TestSettings {
+        volume: f32,
+        muted: bool,
+        name: String,
+        window: Window,
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+    struct Window {
+        width: u32,
+        height: u32,
+    }
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("amethyst_settings_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let dir = dir("layers");
+        fs::write(
+            dir.join("game.ron"),
+            "(volume: 1.0, name: \"game\", window: (width: 800, height: 600))",
+        )
+        .unwrap();
+        fs::write(dir.join("user.ron"), "(muted: true, window: (width: 1920))").unwrap();
+
+        let settings =
+            Settings::<TestSettings>::load(vec![dir.join("game.ron"), dir.join("user.ron")])
+                .unwrap();
+
+        assert_eq!(
+            *settings.get(),
+            TestSettings {
+                volume: 1.0,
+                muted: true,
+                name: "game".into(),
+                window: Window {
+                    width: 1920,
+                    height: 600,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn save_writes_overrides_only() {
+        let dir = dir("save");
+        fs::write(dir.join("game.ron"), "(volume: 1.0, name: \"game\")").unwrap();
+        let paths = vec![dir.join("game.ron"), dir.join("user").join("user.ron")];
+
+        let mut settings = Settings::<TestSettings>::load(paths.clone()).unwrap();
+        settings.edit().window.height = 720;
+        settings.edit().volume = 0.25;
+        settings.save().unwrap();
+
+        let saved: Value = ron::de::from_str(&fs::read_to_string(&paths[1]).unwrap()).unwrap();
+        let expected: Value = ron::de::from_str("(volume: 0.25, window: (height: 720))").unwrap();
+        assert_eq!(saved, expected);
+        assert!(!settings.reload_if_modified().unwrap());
+
+        let reloaded = Settings::<TestSettings>::load(paths).unwrap();
+        assert_eq!(reloaded.get(), settings.get());
+    }
+
+    #[test]
+    fn missing_files_use_defaults() {
+        let dir = dir("missing");
+        let settings = Settings::<TestSettings>::load(vec![dir.join("none.ron")]).unwrap();
+
+        assert_eq!(*settings.get(), TestSettings::default());
+        assert_eq!(settings.save_path(), Some(dir.join("none.ron").as_path()));
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let dir = dir("invalid");
+        fs::write(dir.join("game.ron"), "(volume: \"loud\")").unwrap();
+
+        assert!(Settings::<TestSettings>::load(vec![dir.join("game.ron")]).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! Decoding of the compressed frames of videos.
+
+use std::{collections::HashMap, fmt, time::Duration};
+
+use amethyst_error::{format_err, Error};
+
+/// A decoded frame of a video.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoFrame {
+    /// Width of the frame in pixels
+    pub width: u32,
+    /// Height of the frame in pixels
+    pub height: u32,
+    /// Time the frame is shown at, from the start of the video
+    pub timestamp: Duration,
+    /// sRGB RGBA pixels, row by row from the top
+    pub pixels: Vec<u8>,
+}
+
+/// Decoder of the frames of a video codec.
+///
+/// Decoders run on the thread decoding their video, ahead of its playback.
+pub trait VideoDecoder: Send {
+    /// Decodes a compressed frame shown at `timestamp`.
+    fn send(&mut self, packet: &[u8], timestamp: Duration) -> Result<(), Error>;
+
+    /// Takes the next decoded frame, if there is one.
+    fn receive(&mut self) -> Result<Option<VideoFrame>, Error>;
+
+    /// Drops the frames being decoded, before decoding from another position.
+    fn flush(&mut self);
+}
+
+/// Creates a [`VideoDecoder`].
+pub type CreateDecoder = fn() -> Result<Box<dyn VideoDecoder>, Error>;
+
+/// Resource holding the decoders of the video codecs, by their Matroska codec id, e.g. `V_AV1`
+/// or `V_VP9`.
+///
+/// The AV1 decoder is registered with the `av1` feature. Other codecs can be supported by
+/// registering their decoder with [`VideoCodecs::insert`].
+pub struct VideoCodecs {
+    codecs: HashMap<String, CreateDecoder>,
+}
+
+impl Default for VideoCodecs {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut codecs = Self {
+            codecs: HashMap::new(),
+        };
+        #[cfg(feature = "av1")]
+        codecs.insert("V_AV1", || Ok(Box::new(Av1Decoder::new()?)));
+        codecs
+    }
+}
+
+impl VideoCodecs {
+    /// Decode the videos of the codec `codec_id` with the decoders created by `create`.
+    pub fn insert(&mut self, codec_id: &str, create: CreateDecoder) {
+        self.codecs.insert(codec_id.to_string(), create);
+    }
+
+    /// Whether the videos of the codec `codec_id` can be decoded.
+    #[must_use]
+    pub fn supports(&self, codec_id: &str) -> bool {
+        self.codecs.contains_key(codec_id)
+    }
+
+    /// Whether no codec has a decoder, so that no video can be played.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+
+    /// Creates a decoder for the codec `codec_id`.
+    pub(crate) fn create(&self, codec_id: &str) -> Result<Box<dyn VideoDecoder>, Error> {
+        let create = self
+            .codecs
+            .get(codec_id)
+            .ok_or_else(|| format_err!("No decoder for the video codec {}", codec_id))?;
+        create()
+    }
+}
+
+impl fmt::Debug for VideoCodecs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VideoCodecs")
+            .field("codecs", &self.codecs.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Planes of an 8-bit Y'CbCr image.
+#[derive(Debug)]
+pub(crate) struct YuvImage<'a> {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) y: &'a [u8],
+    pub(crate) y_stride: usize,
+    /// The chroma planes, absent for monochrome images
+    pub(crate) uv: Option<(&'a [u8], &'a [u8])>,
+    pub(crate) uv_stride: usize,
+    /// Horizontal and vertical chroma subsampling, as right shifts of the luma coordinates
+    pub(crate) uv_shift: (usize, usize),
+}
+
+impl YuvImage<'_> {
+    /// Converts the limited range image to sRGB RGBA pixels, with the BT.709 color matrix for HD
+    /// images and the BT.601 one for SD images.
+    pub(crate) fn to_rgba(&self) -> Vec<u8> {
+        let (r_v, g_u, g_v, b_u) = if self.height >= 720 {
+            (1.793, 0.213, 0.533, 2.112)
+        } else {
+            (1.596, 0.392, 0.813, 2.017)
+        };
+
+        let mut pixels = Vec::with_capacity(self.width * self.height * 4);
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let y = 1.164 * (f32::from(self.y[row * self.y_stride + column]) - 16.0);
+                let (u, v) = self.uv.map_or((0.0, 0.0), |(u, v)| {
+                    let index =
+                        (row >> self.uv_shift.1) * self.uv_stride + (column >> self.uv_shift.0);
+                    (f32::from(u[index]) - 128.0, f32::from(v[index]) - 128.0)
+                });
+                pixels.extend_from_slice(&[
+                    clamp(y + r_v * v),
+                    clamp(y - g_u * u - g_v * v),
+                    clamp(y + b_u * u),
+                    255,
+                ]);
+            }
+        }
+        pixels
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn clamp(value: f32) -> u8 {
+    value.round().max(0.0).min(255.0) as u8
+}
+
+/// Decoder of AV1 videos, using dav1d.
+#[cfg(feature = "av1")]
+pub struct Av1Decoder {
+    decoder: dav1d::Decoder,
+    frames: std::collections::VecDeque<VideoFrame>,
+}
+
+#[cfg(feature = "av1")]
+impl Av1Decoder {
+    /// Creates a decoder.
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            decoder: dav1d::Decoder::new(),
+            frames: std::collections::VecDeque::new(),
+        })
+    }
+
+    /// Takes the pictures dav1d finished decoding.
+    fn take_pictures(&mut self) -> Result<(), Error> {
+        use dav1d::{PixelLayout, PlanarImageComponent as Component};
+
+        loop {
+            let picture = match self.decoder.get_picture() {
+                Ok(picture) => picture,
+                Err(dav1d::Error::Again) => return Ok(()),
+                Err(e) => return Err(format_err!("Unable to decode AV1 video: {}", e)),
+            };
+            if picture.bit_depth() != 8 {
+                return Err(format_err!(
+                    "Unsupported AV1 video with {} bits per component",
+                    picture.bit_depth()
+                ));
+            }
+
+            let y = picture.plane(Component::Y);
+            let u = picture.plane(Component::U);
+            let v = picture.plane(Component::V);
+            let image = YuvImage {
+                width: picture.width() as usize,
+                height: picture.height() as usize,
+                y: y.as_ref(),
+                y_stride: picture.stride(Component::Y) as usize,
+                uv: match picture.pixel_layout() {
+                    PixelLayout::I400 => None,
+                    _ => Some((u.as_ref(), v.as_ref())),
+                },
+                uv_stride: picture.stride(Component::U) as usize,
+                uv_shift: match picture.pixel_layout() {
+                    PixelLayout::I420 => (1, 1),
+                    PixelLayout::I422 => (1, 0),
+                    _ => (0, 0),
+                },
+            };
+
+            #[allow(clippy::cast_sign_loss)]
+            self.frames.push_back(VideoFrame {
+                width: picture.width(),
+                height: picture.height(),
+                timestamp: Duration::from_nanos(picture.timestamp().unwrap_or(0).max(0) as u64),
+                pixels: image.to_rgba(),
+            });
+        }
+    }
+}
+
+#[cfg(feature = "av1")]
+impl VideoDecoder for Av1Decoder {
+    fn send(&mut self, packet: &[u8], timestamp: Duration) -> Result<(), Error> {
+        #[allow(clippy::cast_possible_truncation)]
+        let timestamp = Some(timestamp.as_nanos() as i64);
+        let mut result = self
+            .decoder
+            .send_data(packet.to_vec(), None, timestamp, None);
+        // dav1d holds the data back until the pictures it decoded are taken.
+        while let Err(dav1d::Error::Again) = result {
+            self.take_pictures()?;
+            result = self.decoder.send_pending_data();
+        }
+        result.map_err(|e| format_err!("Unable to decode AV1 video: {}", e))
+    }
+
+    fn receive(&mut self) -> Result<Option<VideoFrame>, Error> {
+        if self.frames.is_empty() {
+            self.take_pictures()?;
+        }
+        Ok(self.frames.pop_front())
+    }
+
+    fn flush(&mut self) {
+        self.decoder.flush();
+        self.frames.clear();
+    }
+}
+
+#[cfg(feature = "av1")]
+impl fmt::Debug for Av1Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Av1Decoder")
+            .field("frames", &self.frames.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_limited_range_to_rgba() {
+        // Black and white luma, with neutral subsampled chroma.
+        let image = YuvImage {
+            width: 2,
+            height: 2,
+            y: &[16, 235, 235, 16],
+            y_stride: 2,
+            uv: Some((&[128], &[128])),
+            uv_stride: 1,
+            uv_shift: (1, 1),
+        };
+
+        assert_eq!(
+            image.to_rgba(),
+            vec![0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn converts_chroma() {
+        // Pure red in BT.601
+        let image = YuvImage {
+            width: 1,
+            height: 1,
+            y: &[81],
+            y_stride: 1,
+            uv: Some((&[90], &[240])),
+            uv_stride: 1,
+            uv_shift: (0, 0),
+        };
+        let pixels = image.to_rgba();
+
+        assert!(pixels[0] >= 250);
+        assert!(pixels[1] <= 5);
+        assert!(pixels[2] <= 5);
+    }
+
+    #[test]
+    fn unknown_codecs_are_not_supported() {
+        let codecs = VideoCodecs::default();
+        assert!(!codecs.supports("V_THEORA"));
+        assert!(codecs.create("V_THEORA").is_err());
+    }
+
+    #[test]
+    fn default_codecs_follow_features() {
+        assert_eq!(VideoCodecs::default().is_empty(), !cfg!(feature = "av1"));
+    }
+}
@@ -0,0 +1,26 @@
+use amethyst_assets::Format;
+use amethyst_error::Error;
+use serde::{Deserialize, Serialize};
+use type_uuid::TypeUuid;
+
+/// The bytes of a video file.
+#[derive(Clone, Debug, Serialize, Deserialize, TypeUuid)]
+#[uuid = "a8078c62-756e-4609-b243-4f85b86aae94"]
+pub struct VideoData(pub Vec<u8>);
+amethyst_assets::register_asset_type!(VideoData => crate::Video; amethyst_assets::AssetProcessorSystem<crate::Video>);
+
+/// Loads videos from WebM files.
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, TypeUuid)]
+#[uuid = "004a25ba-219b-45a6-9add-7f8409f5330c"]
+pub struct WebmFormat;
+
+amethyst_assets::register_importer!(".webm", WebmFormat);
+impl Format<VideoData> for WebmFormat {
+    fn name(&self) -> &'static str {
+        "WEBM"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<VideoData, Error> {
+        Ok(VideoData(bytes))
+    }
+}
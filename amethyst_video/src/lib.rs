@@ -0,0 +1,62 @@
+//! Playback of video files into textures, for cutscenes and in-world screens.
+//!
+//! Add the [`RenderVideo`] plugin to the `RenderingBundle`, load a [`Video`] asset and add a
+//! [`VideoPlayer`] to an entity. Once the video starts, the player holds the texture its frames
+//! are streamed into, to be shown by a material, a sprite or an UI image:
+//!
+//! ```ignore
+//! let video = loader.load("video/intro.webm");
+//! world.push((VideoPlayer::new(video),));
+//!
+//! // Later, once `player.texture()` returns a texture.
+//! commands.add_component(entity, UiImage::Texture(texture.clone()));
+//! ```
+//!
+//! Videos are read from WebM files. Their frames are decoded on a thread of their own by the
+//! decoder registered for their codec in the [`VideoCodecs`] resource, AV1 being supported with
+//! the opt-in `av1` feature (`video-av1` of `amethyst`), which links to the system `dav1d`
+//! library. No decoder is built in without it, and [`RenderVideo`] fails to build unless one is
+//! registered.
+//!
+//! With the `audio` feature, a soundtrack loaded with `amethyst_audio` is played along the video
+//! and drives its playback, so both stay in sync:
+//!
+//! ```ignore
+//! let player = VideoPlayer::new(loader.load("video/intro.webm"))
+//!     .with_audio(loader.load("video/intro.ogg"));
+//! ```
+
+#![doc(
+    html_logo_url = "https://amethyst.rs/brand/logo-standard.svg",
+    html_root_url = "https://docs.amethyst.rs/stable"
+)]
+#![deny(
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    rust_2018_compatibility,
+    clippy::all
+)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::new_without_default, clippy::module_name_repetitions)]
+
+#[cfg(feature = "av1")]
+pub use self::decoder::Av1Decoder;
+#[cfg(feature = "audio")]
+pub use self::system::VideoAudioSystem;
+pub use self::{
+    decoder::{VideoCodecs, VideoDecoder, VideoFrame},
+    formats::{VideoData, WebmFormat},
+    player::VideoPlayer,
+    plugin::RenderVideo,
+    system::VideoPlayerSystem,
+    video::{Video, VideoHandle},
+};
+
+mod decoder;
+mod formats;
+mod player;
+mod plugin;
+mod stream;
+mod system;
+mod video;
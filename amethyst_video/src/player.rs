@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use amethyst_assets::Handle;
+#[cfg(feature = "audio")]
+use amethyst_audio::{AudioTrack, SourceHandle};
+use amethyst_rendy::Texture;
+
+use crate::{decoder::VideoFrame, stream::Stream, video::Video};
+
+/// Component playing a video into a texture, see the [crate documentation](crate).
+///
+/// The video starts playing as soon as it's loaded, unless the player was paused before.
+///
+/// With the `audio` feature, a soundtrack can be played along the video with
+/// [`VideoPlayer::with_audio`]. Once it's loaded and the `AudioBundle` provides an output, the
+/// video follows the playback of the soundtrack, and falls back to the game time when the
+/// soundtrack ends first.
+#[derive(Debug)]
+pub struct VideoPlayer {
+    video: Handle<Video>,
+    /// Whether the video restarts when it ends
+    pub looping: bool,
+    /// Volume of the soundtrack, between 0.0 and 1.0
+    #[cfg(feature = "audio")]
+    pub volume: f32,
+    #[cfg(feature = "audio")]
+    audio: Option<SourceHandle>,
+    #[cfg(feature = "audio")]
+    audio_failed: bool,
+    #[cfg(feature = "audio")]
+    pub(crate) track: Option<AudioTrack>,
+    playing: bool,
+    finished: bool,
+    failed: bool,
+    /// Whether the stream reached the end of the video since the last seek
+    ended: bool,
+    position: Duration,
+    seek: Option<Duration>,
+    pub(crate) texture: Option<(Handle<Texture>, u32, u32)>,
+    pub(crate) stream: Option<Stream>,
+    /// Last frame shown, until it's uploaded to the texture
+    pub(crate) frame: Option<VideoFrame>,
+}
+
+impl VideoPlayer {
+    /// Creates a player playing `video` once.
+    #[must_use]
+    pub fn new(video: Handle<Video>) -> Self {
+        Self {
+            video,
+            looping: false,
+            #[cfg(feature = "audio")]
+            volume: 1.0,
+            #[cfg(feature = "audio")]
+            audio: None,
+            #[cfg(feature = "audio")]
+            audio_failed: false,
+            #[cfg(feature = "audio")]
+            track: None,
+            playing: true,
+            finished: false,
+            failed: false,
+            ended: false,
+            position: Duration::from_secs(0),
+            seek: None,
+            texture: None,
+            stream: None,
+            frame: None,
+        }
+    }
+
+    /// Restart the video when it ends.
+    #[must_use]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Plays `audio` in sync with the video, usually its soundtrack exported to an Ogg file.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn with_audio(mut self, audio: SourceHandle) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    /// Sets the volume of the soundtrack.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// The soundtrack played along the video.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn audio(&self) -> Option<&SourceHandle> {
+        self.audio.as_ref()
+    }
+
+    /// The video played.
+    #[must_use]
+    pub fn video(&self) -> &Handle<Video> {
+        &self.video
+    }
+
+    /// The texture the frames are streamed into, once the video started.
+    ///
+    /// The texture is replaced when the size of the frames changes.
+    #[must_use]
+    pub fn texture(&self) -> Option<&Handle<Texture>> {
+        self.texture.as_ref().map(|(texture, _, _)| texture)
+    }
+
+    /// Resumes the playback, restarting the video if it ended.
+    pub fn play(&mut self) {
+        if self.finished {
+            self.seek(Duration::from_secs(0));
+        }
+        self.playing = true;
+    }
+
+    /// Pauses the playback.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Whether the video is playing.
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether the video reached its end, and isn't looping.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Whether the video couldn't be played, the cause being logged.
+    #[must_use]
+    pub fn has_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Moves the playback to `position` from the start of the video.
+    pub fn seek(&mut self, position: Duration) {
+        self.seek = Some(position);
+        self.position = position;
+        self.finished = false;
+    }
+
+    /// Time from the start of the video.
+    #[must_use]
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// Length of the video, when it's started and its file tells it.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.stream.as_ref().and_then(|stream| stream.duration)
+    }
+
+    /// Starts streaming the video.
+    pub(crate) fn start(&mut self, stream: Stream) {
+        self.stream = Some(stream);
+    }
+
+    /// Records that the video couldn't be played.
+    pub(crate) fn fail(&mut self) {
+        self.failed = true;
+        self.playing = false;
+    }
+
+    /// Whether the soundtrack is waiting for an audio track to be played on.
+    #[cfg(feature = "audio")]
+    pub(crate) fn needs_track(&self) -> bool {
+        self.audio.is_some() && self.track.is_none() && !self.audio_failed && self.stream.is_some()
+    }
+
+    /// Starts playing the soundtrack on `track`, from the current position.
+    #[cfg(feature = "audio")]
+    pub(crate) fn start_audio(&mut self, mut track: AudioTrack) {
+        if let Err(e) = track.seek(self.position) {
+            self.fail_audio(&e);
+            return;
+        }
+        self.track = Some(track);
+    }
+
+    /// Records that the soundtrack couldn't be played, the video playing without it.
+    #[cfg(feature = "audio")]
+    pub(crate) fn fail_audio(&mut self, error: &dyn std::error::Error) {
+        log::error!("Unable to play the soundtrack of the video: {}", error);
+        self.audio_failed = true;
+        self.track = None;
+    }
+
+    /// Advances the playback by `delta`, keeping the frame to show.
+    pub(crate) fn advance(&mut self, delta: Duration) {
+        // Taken while advancing, for the soundtrack to be updated along.
+        let mut stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => return,
+        };
+        if let Some(position) = self.seek.take() {
+            stream.seek(position);
+            self.ended = false;
+            #[cfg(feature = "audio")]
+            self.seek_audio(position);
+        }
+        if self.playing {
+            self.position += delta;
+        }
+        #[cfg(feature = "audio")]
+        self.sync_audio();
+
+        let (frame, ended) = stream.advance(self.position);
+        if frame.is_some() {
+            self.frame = frame;
+        }
+        self.ended |= ended;
+        if self.ended && self.playing {
+            if self.looping {
+                self.position = Duration::from_secs(0);
+                self.ended = false;
+                stream.seek(self.position);
+                #[cfg(feature = "audio")]
+                self.seek_audio(self.position);
+            } else {
+                self.playing = false;
+                self.finished = true;
+                #[cfg(feature = "audio")]
+                self.sync_audio();
+            }
+        }
+        self.stream = Some(stream);
+    }
+
+    /// Moves the soundtrack to `position`.
+    #[cfg(feature = "audio")]
+    fn seek_audio(&mut self, position: Duration) {
+        if let Some(Err(e)) = self.track.as_mut().map(|track| track.seek(position)) {
+            self.fail_audio(&e);
+        }
+    }
+
+    /// Applies the state of the player to the soundtrack, and follows its playback.
+    #[cfg(feature = "audio")]
+    fn sync_audio(&mut self) {
+        let track = match self.track.as_mut() {
+            Some(track) => track,
+            None => return,
+        };
+        #[allow(clippy::float_cmp)]
+        let volume_changed = track.volume() != self.volume;
+        if volume_changed {
+            track.set_volume(self.volume);
+        }
+        if self.playing && track.is_paused() {
+            track.play();
+        } else if !self.playing && !track.is_paused() {
+            track.pause();
+        }
+        // The video waits for the soundtrack while it's late, e.g. when it starts.
+        if self.playing && !track.is_finished() {
+            self.position = track.position();
+        }
+    }
+}
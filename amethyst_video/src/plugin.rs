@@ -0,0 +1,53 @@
+#[cfg(feature = "audio")]
+use amethyst_audio::output::OutputWrapper;
+use amethyst_core::ecs::{DispatcherBuilder, Resources, World};
+use amethyst_error::{format_err, Error};
+use amethyst_rendy::{bundle::RenderPlan, Backend, Factory, RenderPlugin};
+
+#[cfg(feature = "audio")]
+use crate::system::VideoAudioSystem;
+use crate::{decoder::VideoCodecs, system::VideoPlayerSystem};
+
+/// A [`RenderPlugin`] playing the [`VideoPlayer`](crate::VideoPlayer)s into textures.
+///
+/// It inserts the default [`VideoCodecs`] unless they're already present. With the `audio`
+/// feature, it also starts the soundtracks of the players on the output of the `AudioBundle`.
+///
+/// Building the `RenderingBundle` fails when no decoder is registered in the [`VideoCodecs`],
+/// as no video could be played: enable the `av1` feature or insert the `VideoCodecs` with the
+/// decoders of your codecs before adding the bundle.
+#[derive(Default, Debug)]
+pub struct RenderVideo;
+
+impl<B: Backend> RenderPlugin<B> for RenderVideo {
+    fn on_build(
+        &mut self,
+        _world: &mut World,
+        resources: &mut Resources,
+        builder: &mut DispatcherBuilder,
+    ) -> Result<(), Error> {
+        if resources.get_or_default::<VideoCodecs>().is_empty() {
+            return Err(format_err!(
+                "No video codec has a decoder: enable the `av1` feature of `amethyst_video` or \
+                 insert `VideoCodecs` with a decoder before adding `RenderVideo`"
+            ));
+        }
+        #[cfg(feature = "audio")]
+        {
+            resources.get_or_default::<OutputWrapper>();
+            builder.add_system(VideoAudioSystem);
+        }
+        builder.add_system(VideoPlayerSystem::<B>::default());
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        _plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+        _resources: &Resources,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
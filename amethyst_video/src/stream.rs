@@ -0,0 +1,179 @@
+//! Streaming of the frames of a video, decoded on a thread of its own.
+
+use std::{io::Cursor, sync::Arc, thread, time::Duration};
+
+use amethyst_error::{format_err, Error};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TryRecvError};
+use matroska_demuxer::{Frame, MatroskaFile, TrackType};
+
+use crate::decoder::{VideoCodecs, VideoDecoder, VideoFrame};
+
+/// Number of frames decoded ahead of the playback.
+const BUFFERED_FRAMES: usize = 4;
+
+type Demuxer = MatroskaFile<Cursor<Arc<[u8]>>>;
+
+/// Message of the decoding thread, tagged with the seek it follows.
+enum Message {
+    Frame(u32, VideoFrame),
+    End(u32),
+}
+
+/// Request to decode from the given position, tagged with the number of seeks.
+struct Seek(u32, Duration);
+
+/// Frames of a video being decoded.
+///
+/// The decoding thread stops when the stream is dropped.
+#[derive(Debug)]
+pub(crate) struct Stream {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) duration: Option<Duration>,
+    seeks: u32,
+    seek_sender: Sender<Seek>,
+    messages: Receiver<Message>,
+    next: Option<VideoFrame>,
+}
+
+impl Stream {
+    /// Starts decoding the first video track of the WebM file `bytes`.
+    pub(crate) fn open(bytes: Arc<[u8]>, codecs: &VideoCodecs) -> Result<Self, Error> {
+        let demuxer = MatroskaFile::open(Cursor::new(bytes))
+            .map_err(|e| format_err!("Unable to read the video file: {}", e))?;
+        let track = demuxer
+            .tracks()
+            .iter()
+            .find(|track| track.track_type() == TrackType::Video)
+            .ok_or_else(|| format_err!("The video file has no video track"))?;
+        let video = track
+            .video()
+            .ok_or_else(|| format_err!("The video track has no video settings"))?;
+        #[allow(clippy::cast_possible_truncation)]
+        let (width, height) = (
+            video.pixel_width().get() as u32,
+            video.pixel_height().get() as u32,
+        );
+        let decoder = codecs.create(track.codec_id())?;
+        let track = track.track_number().get();
+
+        // Nanoseconds per tick of the timestamps
+        let scale = demuxer.info().timestamp_scale().get();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let duration = demuxer
+            .info()
+            .duration()
+            .map(|ticks| Duration::from_nanos((ticks * scale as f64) as u64));
+
+        let (seek_sender, seeks) = unbounded();
+        let (message_sender, messages) = bounded(BUFFERED_FRAMES);
+        thread::Builder::new()
+            .name("video decoder".to_string())
+            .spawn(move || {
+                if let Err(e) = decode(demuxer, decoder, track, scale, &seeks, &message_sender) {
+                    log::error!("Unable to decode video: {}", e);
+                }
+            })
+            .map_err(|e| format_err!("Unable to start decoding the video: {}", e))?;
+
+        Ok(Self {
+            width,
+            height,
+            duration,
+            seeks: 0,
+            seek_sender,
+            messages,
+            next: None,
+        })
+    }
+
+    /// Decodes the video from `position`.
+    pub(crate) fn seek(&mut self, position: Duration) {
+        self.seeks = self.seeks.wrapping_add(1);
+        self.next = None;
+        // The decoding thread only stops when the stream is dropped.
+        let _ = self.seek_sender.send(Seek(self.seeks, position));
+    }
+
+    /// Returns the last frame shown at `position` not returned yet, and whether the video ended
+    /// before `position`.
+    pub(crate) fn advance(&mut self, position: Duration) -> (Option<VideoFrame>, bool) {
+        let mut shown = None;
+        loop {
+            if let Some(next) = self.next.take() {
+                if next.timestamp > position {
+                    self.next = Some(next);
+                    return (shown, false);
+                }
+                shown = Some(next);
+            }
+            match self.messages.try_recv() {
+                Ok(Message::Frame(seeks, frame)) if seeks == self.seeks => self.next = Some(frame),
+                Ok(Message::End(seeks)) if seeks == self.seeks => return (shown, true),
+                // Left from before a seek
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => return (shown, false),
+                Err(TryRecvError::Disconnected) => return (shown, true),
+            }
+        }
+    }
+}
+
+/// Decodes the frames of `track`, until the stream is dropped.
+fn decode(
+    mut demuxer: Demuxer,
+    mut decoder: Box<dyn VideoDecoder>,
+    track: u64,
+    scale: u64,
+    seeks: &Receiver<Seek>,
+    messages: &Sender<Message>,
+) -> Result<(), Error> {
+    let mut frame = Frame::default();
+    let mut current = 0;
+    let mut ended = false;
+    loop {
+        // Wait for a seek at the end of the video.
+        let seek = if ended {
+            match seeks.recv() {
+                Ok(seek) => Some(seek),
+                Err(_) => return Ok(()),
+            }
+        } else {
+            match seeks.try_recv() {
+                Ok(seek) => Some(seek),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        };
+        if let Some(Seek(seek, position)) = seek {
+            #[allow(clippy::cast_possible_truncation)]
+            demuxer
+                .seek(position.as_nanos() as u64 / scale)
+                .map_err(|e| format_err!("Unable to seek in the video file: {}", e))?;
+            decoder.flush();
+            current = seek;
+            ended = false;
+        }
+
+        let has_frame = demuxer
+            .next_frame(&mut frame)
+            .map_err(|e| format_err!("Unable to read the video file: {}", e))?;
+        if has_frame {
+            if frame.track != track {
+                continue;
+            }
+            decoder.send(&frame.data, Duration::from_nanos(frame.timestamp * scale))?;
+        }
+        while let Some(decoded) = decoder.receive()? {
+            if messages.send(Message::Frame(current, decoded)).is_err() {
+                return Ok(());
+            }
+        }
+        if !has_frame {
+            if messages.send(Message::End(current)).is_err() {
+                return Ok(());
+            }
+            ended = true;
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::marker::PhantomData;
+
+use amethyst_assets::{AssetStorage, DefaultLoader, Loader, ProcessingQueue};
+#[cfg(feature = "audio")]
+use amethyst_audio::{output::OutputWrapper, AudioTrack, Source};
+use amethyst_core::{
+    ecs::{IntoQuery, ParallelRunnable, System, SystemBuilder},
+    Time,
+};
+use amethyst_rendy::{
+    rendy::{
+        command::QueueId,
+        factory::{Factory, ImageState},
+        hal,
+        texture::{pixel::Rgba8Srgb, TextureBuilder},
+    },
+    types::TextureData,
+    Backend, Texture,
+};
+use derivative::Derivative;
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{decoder::VideoCodecs, player::VideoPlayer, stream::Stream, video::Video};
+
+/// Plays the [`VideoPlayer`]s, streaming their frames into their textures.
+#[derive(Debug, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct VideoPlayerSystem<B> {
+    _marker: PhantomData<B>,
+}
+
+impl<B: Backend> System for VideoPlayerSystem<B> {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("VideoPlayerSystem")
+                .read_resource::<Time>()
+                .read_resource::<AssetStorage<Video>>()
+                .read_resource::<VideoCodecs>()
+                .read_resource::<DefaultLoader>()
+                .read_resource::<ProcessingQueue<TextureData>>()
+                .read_resource::<AssetStorage<Texture>>()
+                .read_resource::<QueueId>()
+                .write_resource::<Factory<B>>()
+                .with_query(<&mut VideoPlayer>::query())
+                .build(
+                    |_commands,
+                     world,
+                     (time, videos, codecs, loader, texture_queue, textures, queue, factory),
+                     query| {
+                        #[cfg(feature = "profiler")]
+                        profile_scope!("video_player_system");
+
+                        for player in query.iter_mut(world) {
+                            if player.stream.is_none() && !player.has_failed() {
+                                if let Some(video) = videos.get(player.video()) {
+                                    match Stream::open(video.bytes.clone(), codecs) {
+                                        Ok(stream) => player.start(stream),
+                                        Err(e) => {
+                                            log::error!("Unable to play video: {}", e);
+                                            player.fail();
+                                        }
+                                    }
+                                }
+                            }
+
+                            player.advance(time.delta_time());
+                            let (width, height) = match &player.frame {
+                                Some(frame) => (frame.width, frame.height),
+                                None => continue,
+                            };
+
+                            let resized = player
+                                .texture
+                                .as_ref()
+                                .map_or(true, |(_, w, h)| (*w, *h) != (width, height));
+                            if resized {
+                                let builder = TextureBuilder::new()
+                                    .with_kind(hal::image::Kind::D2(width, height, 1, 1))
+                                    .with_view_kind(hal::image::ViewKind::D2)
+                                    .with_data_width(width)
+                                    .with_data_height(height)
+                                    .with_data(vec![
+                                        Rgba8Srgb {
+                                            repr: [0, 0, 0, 255]
+                                        };
+                                        (width * height) as usize
+                                    ]);
+                                let texture =
+                                    loader.load_from_data(TextureData(builder), (), texture_queue);
+                                player.texture = Some((texture, width, height));
+                            }
+                            let (texture, _, _) = player.texture.as_ref().unwrap();
+
+                            // The frame waits until the texture is created.
+                            let image = match textures.get(texture).and_then(B::unwrap_texture) {
+                                Some(texture) => texture.image().clone(),
+                                None => continue,
+                            };
+                            let frame = player.frame.take().unwrap();
+                            let state = ImageState {
+                                queue: **queue,
+                                stage: hal::pso::PipelineStage::VERTEX_SHADER
+                                    | hal::pso::PipelineStage::FRAGMENT_SHADER,
+                                access: hal::image::Access::SHADER_READ,
+                                layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                            };
+                            let uploaded = unsafe {
+                                factory.upload_image(
+                                    image,
+                                    width,
+                                    height,
+                                    hal::image::SubresourceLayers {
+                                        aspects: hal::format::Aspects::COLOR,
+                                        level: 0,
+                                        layers: 0..1,
+                                    },
+                                    hal::image::Offset::ZERO,
+                                    hal::image::Extent {
+                                        width,
+                                        height,
+                                        depth: 1,
+                                    },
+                                    &frame.pixels,
+                                    state,
+                                    state,
+                                )
+                            };
+                            if let Err(e) = uploaded {
+                                log::error!("Unable to upload video frame: {}", e);
+                            }
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// Starts the soundtracks of the [`VideoPlayer`]s on the audio output, once they're loaded.
+///
+/// Videos play without their soundtrack while there's no output, e.g. without the
+/// `AudioBundle`.
+#[cfg(feature = "audio")]
+#[derive(Debug)]
+pub struct VideoAudioSystem;
+
+#[cfg(feature = "audio")]
+impl System for VideoAudioSystem {
+    fn build(self) -> Box<dyn ParallelRunnable> {
+        Box::new(
+            SystemBuilder::new("VideoAudioSystem")
+                .read_resource::<AssetStorage<Source>>()
+                .read_resource::<OutputWrapper>()
+                .with_query(<&mut VideoPlayer>::query())
+                .build(|_commands, world, (sources, wrapper), query| {
+                    #[cfg(feature = "profiler")]
+                    profile_scope!("video_audio_system");
+
+                    let output = match &wrapper.output {
+                        Some(output) => output,
+                        None => return,
+                    };
+                    for player in query.iter_mut(world) {
+                        if !player.needs_track() {
+                            continue;
+                        }
+                        let source = match player.audio().and_then(|audio| sources.get(audio)) {
+                            Some(source) => source,
+                            None => continue,
+                        };
+                        match AudioTrack::new(output, source) {
+                            Ok(track) => player.start_audio(track),
+                            Err(e) => player.fail_audio(&e),
+                        }
+                    }
+                }),
+        )
+    }
+}
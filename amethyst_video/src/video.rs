@@ -0,0 +1,44 @@
+//! Provides structures used to load video files.
+use std::sync::Arc;
+
+use amethyst_assets::{Asset, AssetStorage, Handle, LoadHandle, ProcessableAsset, ProcessingState};
+use amethyst_error::Error;
+use type_uuid::TypeUuid;
+
+use crate::formats::VideoData;
+
+/// A handle to a video asset.
+pub type VideoHandle = Handle<Video>;
+
+/// A loaded video file
+#[derive(Clone, Debug, PartialEq, Eq, TypeUuid)]
+#[uuid = "85824230-4248-47c9-949c-e8b48013248b"]
+pub struct Video {
+    /// The bytes of this video, shared with the threads decoding it.
+    pub bytes: Arc<[u8]>,
+}
+
+impl AsRef<[u8]> for Video {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Asset for Video {
+    fn name() -> &'static str {
+        "video::Video"
+    }
+    type Data = VideoData;
+}
+
+impl ProcessableAsset for Video {
+    fn process(
+        data: VideoData,
+        _: &mut AssetStorage<Video>,
+        _: &LoadHandle,
+    ) -> Result<ProcessingState<VideoData, Video>, Error> {
+        Ok(ProcessingState::Loaded(Video {
+            bytes: data.0.into(),
+        }))
+    }
+}
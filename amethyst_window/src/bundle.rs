@@ -1,9 +1,19 @@
 use amethyst_config::{Config, ConfigError};
-use amethyst_core::ecs::{DispatcherBuilder, Resources, SystemBundle, World};
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Resources, SystemBundle, World},
+    EventChannel, Lifecycle,
+};
 use amethyst_error::Error;
-use winit::event_loop::EventLoop;
+use winit::{event::Event, event_loop::EventLoop};
 
-use crate::{DisplayConfig, EventLoopSystem, ScreenDimensions, WindowSystem};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::EventLoopSystem;
+use crate::{
+    DisplayConfig, EventHookAction, EventHooks, ScreenDimensions, SecondaryWindowEvent,
+    SecondaryWindows, WindowSystem,
+};
+#[cfg(target_arch = "wasm32")]
+use crate::{WebEventLoop, WebEventSystem, WebEvents};
 
 /// Screen width used in predefined display configuration.
 #[cfg(feature = "test-support")]
@@ -14,16 +24,34 @@ pub const SCREEN_HEIGHT: u32 = 600;
 
 /// Bundle providing easy initializing of the appropriate `Window`, `WindowSystem` `EventLoop` and
 /// `EventLoopSystem` constructs used for creating the rendering window of amethyst with `winit`
+///
+/// On the web, the window is a canvas appended to the body of the page, and the event loop is
+/// inserted as the `WebEventLoop` resource for the application to run, with `WebEventSystem`
+/// instead of `EventLoopSystem`.
 #[derive(Debug)]
 pub struct WindowBundle {
     config: DisplayConfig,
+    hooks: EventHooks,
 }
 
 impl WindowBundle {
     /// Builds a new window bundle from a loaded `DisplayConfig`.
     #[must_use]
     pub fn from_config(config: DisplayConfig) -> Self {
-        WindowBundle { config }
+        WindowBundle {
+            config,
+            hooks: EventHooks::new(),
+        }
+    }
+
+    /// Adds a hook called on the raw `winit` events, see [`EventHooks`].
+    #[must_use]
+    pub fn with_event_hook<F>(mut self, name: impl Into<String>, hook: F) -> Self
+    where
+        F: FnMut(&Event<'_, ()>) -> EventHookAction + Send + Sync + 'static,
+    {
+        self.hooks.add(name, hook);
+        self
     }
 
     /// Builds a new window bundle by loading the `DisplayConfig` from `path`.
@@ -70,15 +98,33 @@ impl SystemBundle for WindowBundle {
             .into_window_builder(&event_loop)
             .build(&event_loop)
             .expect("Unable to create window");
+        #[cfg(target_arch = "wasm32")]
+        crate::web::attach_canvas(&window);
 
         let (width, height) = window.inner_size().into();
 
         resources.insert(ScreenDimensions::new(width, height));
         resources.insert(window);
+        resources
+            .get_or_default::<EventHooks>()
+            .append(&mut self.hooks);
+        resources.get_or_default::<SecondaryWindows>();
+        resources.get_or_default::<EventChannel<SecondaryWindowEvent>>();
+        resources.get_or_default::<Lifecycle>();
 
+        #[cfg(not(target_arch = "wasm32"))]
         builder
             .add_system(WindowSystem)
-            .add_thread_local(EventLoopSystem { event_loop });
+            .add_platform_thread_local(EventLoopSystem { event_loop });
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            resources.insert(WebEventLoop::new(event_loop));
+            resources.get_or_default::<WebEvents>();
+            builder
+                .add_system(WindowSystem)
+                .add_platform_thread_local(WebEventSystem);
+        }
 
         Ok(())
     }
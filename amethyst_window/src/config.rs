@@ -57,6 +57,15 @@ pub struct DisplayConfig {
     /// window.
     #[serde(default)]
     pub transparent: bool,
+    /// Number of samples per pixel used for multisample anti-aliasing of the window output.
+    /// Valid values are `1` (disabled), `2`, `4` and `8`. When the requested count is not
+    /// supported by the device, the highest supported count below it is used instead.
+    #[serde(default = "default_multisampling")]
+    pub multisampling: u8,
+    /// Name of the graphics adapter to render with, matched case-insensitively against part of
+    /// the adapter names, e.g. `"nvidia"`. Defaults to `None`, which prefers a discrete GPU.
+    #[serde(default)]
+    pub adapter: Option<String>,
 
     /// A programmatically loaded window icon; not present in serialization.
     /// Takes precedence over `icon`.
@@ -102,6 +111,8 @@ impl Default for DisplayConfig {
             multitouch: false,
             resizable: default_resizable(),
             transparent: false,
+            multisampling: default_multisampling(),
+            adapter: None,
             loaded_icon: None,
         }
     }
@@ -123,6 +134,10 @@ fn default_resizable() -> bool {
     true
 }
 
+fn default_multisampling() -> u8 {
+    1
+}
+
 impl DisplayConfig {
     /// Creates a `winit::WindowBuilder` using the values set in the `DisplayConfig`.
     ///
@@ -0,0 +1,88 @@
+use std::fmt;
+
+use winit::event::Event;
+
+/// What happens to an event after an [`EventHooks`] hook saw it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventHookAction {
+    /// Pass the event to the following hooks and the `Event` channel.
+    Forward,
+    /// Hide the event from the following hooks and the `Event` channel.
+    Consume,
+}
+
+type Hook = Box<dyn FnMut(&Event<'_, ()>) -> EventHookAction + Send + Sync>;
+
+/// Resource holding callbacks run on the raw `winit` events, including the ones the engine doesn't
+/// forward such as user events and suspend/resume notifications on mobile.
+///
+/// Hooks are called by `EventLoopSystem` while it polls the event loop, in the order they were
+/// added. Window and device events not consumed by a hook are written to the
+/// `EventChannel<Event<'static, ()>>` afterwards, so hooks always see an event before
+/// `InputSystem` and the states do, and a consumed event never reaches them.
+///
+/// Hooks receive the events by reference only, they can't alter the control flow of the event
+/// loop.
+#[derive(Default)]
+pub struct EventHooks {
+    hooks: Vec<(String, Hook)>,
+}
+
+impl EventHooks {
+    /// Creates an empty set of hooks.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook called after the already added ones. A hook with the same name is replaced,
+    /// keeping its position.
+    pub fn add<F>(&mut self, name: impl Into<String>, hook: F)
+    where
+        F: FnMut(&Event<'_, ()>) -> EventHookAction + Send + Sync + 'static,
+    {
+        self.insert(name.into(), Box::new(hook));
+    }
+
+    fn insert(&mut self, name: String, hook: Hook) {
+        match self.hooks.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = hook,
+            None => self.hooks.push((name, hook)),
+        }
+    }
+
+    /// Removes the hook with given name, returning whether there was one.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.hooks.len();
+        self.hooks.retain(|(n, _)| n != name);
+        self.hooks.len() != len
+    }
+
+    /// Names of the hooks in calling order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.hooks.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Calls the hooks on `event` until one consumes it.
+    pub(crate) fn dispatch(&mut self, event: &Event<'_, ()>) -> EventHookAction {
+        for (_, hook) in &mut self.hooks {
+            if hook(event) == EventHookAction::Consume {
+                return EventHookAction::Consume;
+            }
+        }
+        EventHookAction::Forward
+    }
+
+    /// Moves the hooks of `other` after these ones.
+    pub(crate) fn append(&mut self, other: &mut EventHooks) {
+        for (name, hook) in other.hooks.drain(..) {
+            self.insert(name, hook);
+        }
+    }
+}
+
+impl fmt::Debug for EventHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
@@ -17,18 +17,26 @@
 
 mod bundle;
 mod config;
+mod hooks;
 mod monitor;
 mod resources;
 mod system;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod windows;
 
-pub use winit::window::Window;
+pub use winit::window::{Window, WindowId};
 
 #[cfg(feature = "test-support")]
 pub use crate::bundle::{SCREEN_HEIGHT, SCREEN_WIDTH};
+#[cfg(target_arch = "wasm32")]
+pub use crate::web::{WebEventLoop, WebEventSystem, WebEvents};
 pub use crate::{
     bundle::WindowBundle,
     config::DisplayConfig,
+    hooks::{EventHookAction, EventHooks},
     monitor::{MonitorIdent, MonitorsAccess},
     resources::ScreenDimensions,
     system::*,
+    windows::{SecondaryWindowEvent, SecondaryWindows},
 };
@@ -1,17 +1,20 @@
 use amethyst_core::{
     dispatcher::{System, ThreadLocalSystem},
     ecs::{systems::ParallelRunnable, Runnable, SystemBuilder},
-    EventChannel,
+    EventChannel, Lifecycle,
 };
+use winit::{dpi::Size, event::Event, window::Window};
+#[cfg(not(target_arch = "wasm32"))]
 use winit::{
-    dpi::Size,
-    event::Event,
     event_loop::{ControlFlow, EventLoop},
     platform::run_return::EventLoopExtRunReturn,
-    window::Window,
 };
 
-use crate::resources::ScreenDimensions;
+use crate::{
+    hooks::{EventHookAction, EventHooks},
+    resources::ScreenDimensions,
+    windows::{SecondaryWindowEvent, SecondaryWindows},
+};
 
 /// Manages window dimensions
 #[derive(Debug)]
@@ -48,34 +51,89 @@ impl System for WindowSystem {
         )
     }
 }
+#[cfg(not(target_arch = "wasm32"))]
 /// System that polls the window events and pushes them to appropriate event channels.
 ///
 /// This system must be active for any `GameState` to receive
 /// any `StateEvent::Window` event into it's `handle_event` method.
+///
+/// The [`EventHooks`] are called on every event before it is pushed. Events of the
+/// [`SecondaryWindows`] are pushed to their own channel, which this system also opens and closes.
+///
+/// `Event::Suspended` and `Event::Resumed` update the [`Lifecycle`] resource and are pushed too.
+/// The system is added to the platform stage of the dispatcher, so it keeps polling events while
+/// the application is suspended.
 #[derive(Debug)]
 pub struct EventLoopSystem {
     pub(crate) event_loop: EventLoop<()>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl ThreadLocalSystem<'static> for EventLoopSystem {
     fn build(mut self) -> Box<dyn Runnable> {
         let mut events = Vec::with_capacity(128);
+        let mut window_events = Vec::new();
 
         Box::new(
             SystemBuilder::new("EventsLoopSystem")
                 .write_resource::<EventChannel<Event<'static, ()>>>()
-                .build(move |_commands, _world, event_channel, _query| {
-                    self.event_loop.run_return(|event, _, flow| {
-                        match event {
-                            Event::WindowEvent { .. } | Event::DeviceEvent { .. } => {
-                                events.push(event.to_static().unwrap());
+                .write_resource::<EventChannel<SecondaryWindowEvent>>()
+                .write_resource::<EventHooks>()
+                .write_resource::<SecondaryWindows>()
+                .write_resource::<Lifecycle>()
+                .build(
+                    move |_commands,
+                          _world,
+                          (event_channel, window_event_channel, hooks, windows, lifecycle),
+                          _query| {
+                        windows.update(&self.event_loop);
+                        self.event_loop.run_return(|event, _, flow| {
+                            *flow = ControlFlow::Exit;
+                            if hooks.dispatch(&event) == EventHookAction::Consume {
+                                return;
                             }
-                            _ => {}
-                        }
-                        *flow = ControlFlow::Exit;
-                    });
-                    event_channel.drain_vec_write(&mut events);
-                }),
+                            route_event(event, windows, lifecycle, &mut events, &mut window_events);
+                        });
+                        event_channel.drain_vec_write(&mut events);
+                        window_event_channel.drain_vec_write(&mut window_events);
+                    },
+                ),
         )
     }
 }
+
+/// Pushes `event` to `events` or `window_events`, updating `lifecycle` on suspend and resume.
+pub(crate) fn route_event(
+    event: Event<'_, ()>,
+    windows: &SecondaryWindows,
+    lifecycle: &mut Lifecycle,
+    events: &mut Vec<Event<'static, ()>>,
+    window_events: &mut Vec<SecondaryWindowEvent>,
+) {
+    match event {
+        Event::WindowEvent { window_id, event } => {
+            match windows.id_of(window_id) {
+                Some(window) => {
+                    if let Some(event) = event.to_static() {
+                        window_events.push(SecondaryWindowEvent { window, event });
+                    }
+                }
+                None => {
+                    events.extend(Event::WindowEvent { window_id, event }.to_static());
+                }
+            }
+        }
+        Event::DeviceEvent { .. } => {
+            events.push(event.to_static().unwrap());
+        }
+        Event::Suspended => {
+            *lifecycle = Lifecycle::Suspended;
+            events.push(Event::Suspended);
+        }
+        Event::Resumed => {
+            *lifecycle = Lifecycle::Running;
+            events.push(Event::Resumed);
+        }
+        _ => {}
+    }
+}
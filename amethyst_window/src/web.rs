@@ -0,0 +1,132 @@
+use amethyst_core::{
+    dispatcher::ThreadLocalSystem,
+    ecs::{Runnable, SystemBuilder},
+    EventChannel, Lifecycle,
+};
+use winit::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::web::WindowExtWebSys,
+    window::Window,
+};
+
+use crate::{
+    hooks::{EventHookAction, EventHooks},
+    system::route_event,
+    windows::{SecondaryWindowEvent, SecondaryWindows},
+};
+
+/// Resource holding the event loop on the web, inserted by `WindowBundle` in place of
+/// `EventLoopSystem`.
+///
+/// Browsers don't let the engine poll for events, they call it back instead. The application
+/// takes this resource and gives its frames to [`WebEventLoop::run`], which runs them on every
+/// animation frame, after queueing the events received since the previous one for
+/// [`WebEventSystem`].
+#[derive(Debug)]
+pub struct WebEventLoop {
+    event_loop: EventLoop<()>,
+}
+
+impl WebEventLoop {
+    pub(crate) fn new(event_loop: EventLoop<()>) -> Self {
+        Self { event_loop }
+    }
+
+    /// Hands the control to the browser, calling `frame` on every animation frame with the events
+    /// received since the previous frame. The loop stops when `frame` returns `false`.
+    ///
+    /// This function never returns.
+    pub fn run<F>(self, mut frame: F) -> !
+    where
+        F: 'static + FnMut(&mut Vec<Event<'static, ()>>) -> bool,
+    {
+        let mut events = Vec::with_capacity(128);
+        self.event_loop.run(move |event, _, flow| {
+            match event {
+                Event::MainEventsCleared => {
+                    if !frame(&mut events) {
+                        *flow = ControlFlow::Exit;
+                        return;
+                    }
+                }
+                event => events.extend(event.to_static()),
+            }
+            *flow = ControlFlow::Poll;
+        })
+    }
+}
+
+/// Resource queueing the events received from the browser until [`WebEventSystem`] runs.
+#[derive(Debug, Default)]
+pub struct WebEvents {
+    events: Vec<Event<'static, ()>>,
+}
+
+impl WebEvents {
+    /// Queues `events`, leaving the vector empty.
+    pub fn append(&mut self, events: &mut Vec<Event<'static, ()>>) {
+        self.events.append(events);
+    }
+}
+
+/// System pushing the events queued in [`WebEvents`] to the appropriate event channels, the
+/// web counterpart of `EventLoopSystem`.
+///
+/// [`SecondaryWindows`] can't be opened in a browser, so their requests are ignored.
+#[derive(Debug)]
+pub struct WebEventSystem;
+
+impl ThreadLocalSystem<'static> for WebEventSystem {
+    fn build(self) -> Box<dyn Runnable> {
+        let mut events = Vec::with_capacity(128);
+        let mut window_events = Vec::new();
+
+        Box::new(
+            SystemBuilder::new("WebEventSystem")
+                .write_resource::<WebEvents>()
+                .write_resource::<EventChannel<Event<'static, ()>>>()
+                .write_resource::<EventChannel<SecondaryWindowEvent>>()
+                .write_resource::<EventHooks>()
+                .read_resource::<SecondaryWindows>()
+                .write_resource::<Lifecycle>()
+                .build(
+                    move |_commands,
+                          _world,
+                          (
+                        queue,
+                        event_channel,
+                        window_event_channel,
+                        hooks,
+                        windows,
+                        lifecycle,
+                    ),
+                          _query| {
+                        for event in queue.events.drain(..) {
+                            if hooks.dispatch(&event) == EventHookAction::Consume {
+                                continue;
+                            }
+                            route_event(event, windows, lifecycle, &mut events, &mut window_events);
+                        }
+                        event_channel.drain_vec_write(&mut events);
+                        window_event_channel.drain_vec_write(&mut window_events);
+                    },
+                ),
+        )
+    }
+}
+
+/// Appends the canvas of `window` to the body of the page.
+pub(crate) fn attach_canvas(window: &Window) {
+    let body = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.body());
+    match body {
+        Some(body) => {
+            if body.append_child(&window.canvas()).is_err() {
+                log::error!("Unable to append the window canvas to the page");
+            }
+        }
+        None => log::error!("Unable to find the body of the page to append the window canvas to"),
+    }
+}
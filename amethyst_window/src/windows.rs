@@ -0,0 +1,221 @@
+use winit::{
+    event::WindowEvent,
+    event_loop::EventLoop,
+    window::{Window, WindowId},
+};
+
+use crate::DisplayConfig;
+
+/// Resource managing OS windows opened in addition to the main [`Window`], e.g. for tools or a
+/// second screen.
+///
+/// Windows are opened and closed by `EventLoopSystem` on its next run. Their events are not
+/// written to the `Event` channel read by `InputSystem` and the states, but to
+/// `EventChannel<SecondaryWindowEvent>` tagged with the id of the window. Closing a window is up
+/// to the game, usually when it receives `WindowEvent::CloseRequested`.
+///
+/// Every name a window is opened with gets an id, kept when the window is closed and reopened.
+/// With the renderer, the `RenderToSecondaryWindows` plugin presents the render target
+/// `Target::Window(id)` to the window with that id. Closed windows are then kept alive until
+/// the render graph presenting to them is rebuilt, see
+/// [`keep_closed_until_released`](Self::keep_closed_until_released).
+#[derive(Debug, Default)]
+pub struct SecondaryWindows {
+    windows: Slots<Window>,
+    names: Vec<String>,
+    pending: Vec<(u32, DisplayConfig)>,
+    closing: Vec<u32>,
+    keep_closed: bool,
+}
+
+impl SecondaryWindows {
+    /// Opens a window configured by `config`, replacing the window with the same name, and
+    /// returns its id.
+    pub fn open(&mut self, name: impl Into<String>, config: DisplayConfig) -> u32 {
+        let name = name.into();
+        let id = match self.id(&name) {
+            Some(id) => id,
+            None => {
+                self.names.push(name);
+                (self.names.len() - 1) as u32
+            }
+        };
+        self.pending.retain(|(i, _)| *i != id);
+        self.pending.push((id, config));
+        id
+    }
+
+    /// Closes the window with given name.
+    pub fn close(&mut self, name: &str) {
+        if let Some(id) = self.id(name) {
+            self.pending.retain(|(i, _)| *i != id);
+            self.closing.push(id);
+        }
+    }
+
+    /// The id of the windows opened with given name, whether one is open or not.
+    #[must_use]
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(|index| index as u32)
+    }
+
+    /// The name of the windows with given id.
+    #[must_use]
+    pub fn name(&self, id: u32) -> Option<&str> {
+        self.names.get(id as usize).map(String::as_str)
+    }
+
+    /// The open window with given name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Window> {
+        let id = self.id(name)?;
+        self.windows
+            .open
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, window)| window)
+    }
+
+    /// The open windows with their ids, in opening order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Window)> {
+        self.windows.open.iter().map(|(id, window)| (*id, window))
+    }
+
+    /// The id of the open window with given window id.
+    #[must_use]
+    pub fn id_of(&self, window_id: WindowId) -> Option<u32> {
+        self.windows
+            .open
+            .iter()
+            .find(|(_, window)| window.id() == window_id)
+            .map(|(id, _)| *id)
+    }
+
+    /// Keeps the windows closed or replaced alive until [`release_closed`](Self::release_closed)
+    /// is called, instead of dropping them on the next run of `EventLoopSystem`.
+    ///
+    /// Used by the renderer, the surface presenting to a window must be destroyed before it.
+    pub fn keep_closed_until_released(&mut self) {
+        self.keep_closed = true;
+    }
+
+    /// Drops the windows closed or replaced since the last call.
+    pub fn release_closed(&mut self) {
+        self.windows.closed.clear();
+    }
+
+    /// Applies the requested openings and closings.
+    pub(crate) fn update(&mut self, event_loop: &EventLoop<()>) {
+        for id in self.closing.drain(..) {
+            self.windows.remove(id);
+        }
+        for (id, config) in self.pending.drain(..) {
+            match config.into_window_builder(event_loop).build(event_loop) {
+                Ok(window) => self.windows.insert(id, window),
+                Err(e) => log::error!("Unable to open window {:?}: {}", self.names[id as usize], e),
+            }
+        }
+        if !self.keep_closed {
+            self.release_closed();
+        }
+    }
+}
+
+/// Open windows with their ids, and the closed ones not released yet.
+#[derive(Debug)]
+struct Slots<W> {
+    open: Vec<(u32, W)>,
+    closed: Vec<W>,
+}
+
+impl<W> Default for Slots<W> {
+    fn default() -> Self {
+        Self {
+            open: Vec::new(),
+            closed: Vec::new(),
+        }
+    }
+}
+
+impl<W> Slots<W> {
+    /// Adds an open window, closing the window with the same id.
+    fn insert(&mut self, id: u32, window: W) {
+        self.remove(id);
+        self.open.push((id, window));
+    }
+
+    /// Closes the window with given id.
+    fn remove(&mut self, id: u32) {
+        if let Some(index) = self.open.iter().position(|(i, _)| *i == id) {
+            let (_, window) = self.open.remove(index);
+            self.closed.push(window);
+        }
+    }
+}
+
+/// Event of a window managed by [`SecondaryWindows`].
+#[derive(Debug)]
+pub struct SecondaryWindowEvent {
+    /// Id of the window, see [`SecondaryWindows::id`]
+    pub window: u32,
+    /// The event
+    pub event: WindowEvent<'static>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_cancels_pending_open() {
+        let mut windows = SecondaryWindows::default();
+        let tools = windows.open("tools", DisplayConfig::default());
+        let map = windows.open(format!("map {}", 1), DisplayConfig::default());
+        windows.close("tools");
+
+        assert_eq!(
+            windows.pending.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![map]
+        );
+        assert_eq!(windows.closing, vec![tools]);
+    }
+
+    #[test]
+    fn reopened_name_keeps_its_id() {
+        let mut windows = SecondaryWindows::default();
+        let tools = windows.open("tools", DisplayConfig::default());
+        let map = windows.open(String::from("map"), DisplayConfig::default());
+        windows.close("tools");
+
+        assert_ne!(tools, map);
+        assert_eq!(windows.open("tools", DisplayConfig::default()), tools);
+        assert_eq!(windows.id("map"), Some(map));
+        assert_eq!(windows.name(map), Some("map"));
+        assert_eq!(windows.id("missing"), None);
+    }
+
+    #[test]
+    fn closed_window_is_kept_until_released() {
+        let mut slots = Slots::default();
+        slots.insert(0, 1);
+        slots.insert(1, 2);
+        slots.remove(0);
+
+        assert_eq!(slots.open, vec![(1, 2)]);
+        assert_eq!(slots.closed, vec![1]);
+    }
+
+    #[test]
+    fn reopened_window_replaces_previous_one() {
+        let mut slots = Slots::default();
+        slots.insert(0, 1);
+        slots.insert(0, 2);
+        slots.remove(5);
+
+        assert_eq!(slots.open, vec![(0, 2)]);
+        assert_eq!(slots.closed, vec![1]);
+    }
+}
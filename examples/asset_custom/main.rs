@@ -2,9 +2,9 @@ use amethyst::{
     assets::{
         distill_importer,
         distill_importer::{typetag, SerdeImportable},
-        register_asset_type, Asset, AssetProcessorSystem, AssetStorage, DefaultLoader, Format,
-        Handle, Loader, LoaderBundle, TypeUuid,
+        AssetStorage, DefaultLoader, Format, Handle, Loader, LoaderBundle, TypeUuid,
     },
+    derive::Asset,
     error::{format_err, Error, ResultExt},
     prelude::*,
     renderer::{types::DefaultBackend, RenderingBundle},
@@ -13,7 +13,7 @@ use amethyst::{
 use serde::{Deserialize, Serialize};
 
 /// Custom asset representing an energy blast.
-#[derive(Clone, Debug, Default, Deserialize, Serialize, TypeUuid, SerdeImportable)]
+#[derive(Asset, Clone, Debug, Default, Deserialize, Serialize, SerdeImportable)]
 #[uuid = "a016abff-623d-48cf-a6e4-e76e069fe843"]
 pub struct EnergyBlast {
     /// How much HP to subtract.
@@ -22,14 +22,6 @@ pub struct EnergyBlast {
     pub mp_damage: u32,
 }
 
-impl Asset for EnergyBlast {
-    fn name() -> &'static str {
-        "EnergyBlast"
-    }
-
-    type Data = Self;
-}
-
 pub struct LoadingState {
     /// Handle to the energy blast.
     energy_blast_handle: Option<Handle<EnergyBlast>>,
@@ -59,9 +51,6 @@ impl Format<EnergyBlast> for MyLangFormat {
     }
 }
 
-use amethyst::assets as amethyst_assets;
-register_asset_type!(EnergyBlast => EnergyBlast; AssetProcessorSystem<EnergyBlast>);
-
 impl SimpleState for LoadingState {
     fn on_start(&mut self, data: StateData<'_, GameData>) {
         let loader = data.resources.get::<DefaultLoader>().unwrap();
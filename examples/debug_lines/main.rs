@@ -15,7 +15,7 @@ use amethyst::{
     prelude::*,
     renderer::{
         camera::Camera,
-        debug_drawing::{DebugLines, DebugLinesComponent, DebugLinesParams},
+        debug_drawing::{DebugLineStyle, DebugLines, DebugLinesComponent, DebugLinesParams},
         palette::Srgba,
         plugins::{RenderDebugLines, RenderSkybox, RenderToWindow},
         types::DefaultBackend,
@@ -49,6 +49,21 @@ impl System for ExampleLinesSystem {
                         Point3::new(0.0, 0.0, 0.2),
                         Srgba::new(0.5, 0.05, 0.65, 1.0),
                     );
+
+                    // A thick dashed box around the pendulum, drawn over the grid
+                    debug_lines_resource.draw_styled(
+                        DebugLineStyle::new()
+                            .with_width(3.0)
+                            .with_dashes(8.0, 4.0)
+                            .with_depth_test(false),
+                        |lines| {
+                            lines.draw_aabb(
+                                Point3::new(t, 0.15, 0.5),
+                                Vector3::new(0.05, 0.15, 0.05),
+                                Srgba::new(1.0, 0.8, 0.0, 1.0),
+                            );
+                        },
+                    );
                 }),
         )
     }
@@ -64,7 +79,10 @@ impl SimpleState for ExampleState {
         // Setup debug lines as a resource
         resources.insert(DebugLines::new());
         // Configure width of lines. Optional step
-        resources.insert(DebugLinesParams { line_width: 2.0 });
+        resources.insert(DebugLinesParams {
+            line_width: 2.0,
+            ..Default::default()
+        });
 
         // Setup debug lines as a component and add lines to render axes & grid
         let mut debug_lines_component = DebugLinesComponent::with_capacity(100);
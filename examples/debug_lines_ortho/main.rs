@@ -57,7 +57,10 @@ impl SimpleState for ExampleState {
         // Setup debug lines as a resource
         resources.insert(DebugLines::new());
         // Configure width of lines. Optional step
-        resources.insert(DebugLinesParams { line_width: 2.0 });
+        resources.insert(DebugLinesParams {
+            line_width: 2.0,
+            ..Default::default()
+        });
 
         // Setup debug lines as a component and add lines to render axis&grid
         let mut debug_lines_component = DebugLinesComponent::new();
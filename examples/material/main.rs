@@ -10,12 +10,13 @@ use amethyst::{
         light::{Light, PointLight},
         mtl::{Material, MaterialDefaults},
         palette::{LinSrgba, Srgb},
-        plugins::{RenderPbr3D, RenderToWindow},
+        plugins::{RenderPbr3D, RenderToWindow, RenderTonemap},
         rendy::{
             hal::command::ClearColor,
             mesh::{Normal, Position, Tangent, TexCoord},
             texture::palette::load_from_linear_rgba,
         },
+        resources::TonemapOperator,
         shape::Shape,
         types::{DefaultBackend, MeshData, TextureData},
         Mesh, RenderingBundle, Texture,
@@ -142,10 +143,13 @@ fn main() -> amethyst::Result<()> {
         .add_bundle(TransformBundle)
         .add_bundle(
             RenderingBundle::<DefaultBackend>::new()
+                .with_plugin(RenderToWindow::from_config_path(display_config_path)?)
                 .with_plugin(
-                    RenderToWindow::from_config_path(display_config_path)?.with_clear(ClearColor {
-                        float32: [0.34, 0.36, 0.52, 1.0],
-                    }),
+                    RenderTonemap::default()
+                        .with_operator(TonemapOperator::Aces)
+                        .with_clear(ClearColor {
+                            float32: [0.34, 0.36, 0.52, 1.0],
+                        }),
                 )
                 .with_plugin(RenderPbr3D::default()),
         );
@@ -89,13 +89,15 @@ impl System for SpamSystem {
 
                         for event in event.read(&mut self.reader) {
                             match event {
-                                NetworkSimulationEvent::Message(_addr, payload) => {
+                                NetworkSimulationEvent::Message(_addr, payload, _metadata) => {
                                     info!("Payload: {:?}", payload)
                                 }
                                 NetworkSimulationEvent::Connect(addr) => {
                                     info!("New client connection: {}", addr)
                                 }
-                                NetworkSimulationEvent::Disconnect(addr) => {
+                                NetworkSimulationEvent::Disconnect(addr)
+                                | NetworkSimulationEvent::Timeout(addr)
+                                | NetworkSimulationEvent::ConnectionLost(addr) => {
                                     info!("Server Disconnected: {}", addr)
                                 }
                                 NetworkSimulationEvent::RecvError(e) => {
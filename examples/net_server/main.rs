@@ -80,7 +80,7 @@ impl System for SpamReceiveSystem {
                 .build(move |_commands, _world, (channel, net), _query| {
                     for event in channel.read(&mut self.reader) {
                         match event {
-                            NetworkSimulationEvent::Message(addr, payload) => {
+                            NetworkSimulationEvent::Message(addr, payload, _metadata) => {
                                 info!("{}: {:?}", addr, payload);
                                 // In a typical client/server simulation, both the client and the server will
                                 // be exchanging messages at a constant rate. Laminar makes use of this by
@@ -91,7 +91,9 @@ impl System for SpamReceiveSystem {
                             NetworkSimulationEvent::Connect(addr) => {
                                 info!("New client connection: {}", addr)
                             }
-                            NetworkSimulationEvent::Disconnect(addr) => {
+                            NetworkSimulationEvent::Disconnect(addr)
+                            | NetworkSimulationEvent::Timeout(addr)
+                            | NetworkSimulationEvent::ConnectionLost(addr) => {
                                 info!("Client Disconnected: {}", addr);
                             }
                             NetworkSimulationEvent::RecvError(e) => {
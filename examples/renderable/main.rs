@@ -12,6 +12,7 @@ use amethyst::{
         math::{UnitQuaternion, Vector3},
         timing::Time,
         transform::{Transform, TransformBundle},
+        Tracked,
     },
     ecs::{Entity, System},
     input::{get_key, is_close_requested, is_key_down, ElementState, InputBundle, VirtualKeyCode},
@@ -123,7 +124,7 @@ impl SimpleState for Example {
                     w.exec(
                         |(mut state, mut color): (
                             Write<'_, DemoState>,
-                            Write<'_, AmbientColor>,
+                            Write<'_, Tracked<AmbientColor>>,
                         )| {
                             if state.ambient_light {
                                 state.ambient_light = false;
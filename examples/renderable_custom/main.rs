@@ -12,6 +12,7 @@ use amethyst::{
         math::{UnitQuaternion, Vector3},
         timing::Time,
         transform::{Transform, TransformBundle},
+        Tracked,
     },
     ecs::{
         prelude::{Entity, Read, ReadStorage, System, Write, WriteStorage},
@@ -127,7 +128,7 @@ impl SimpleState for Example {
                     w.exec(
                         |(mut state, mut color): (
                             Write<'_, DemoState>,
-                            Write<'_, AmbientColor>,
+                            Write<'_, Tracked<AmbientColor>>,
                         )| {
                             if state.ambient_light {
                                 state.ambient_light = false;
@@ -48,8 +48,8 @@ impl SimpleState for Example {
                 .with_position(0.0, -256.0)
                 .with_size(64.0 * 6.0, 64.0)
                 .with_anchor(Anchor::TopMiddle)
-                .with_image(UiImage::SolidColor([0.8, 0.6, 0.3, 1.0]))
-                .with_hover_image(UiImage::SolidColor([0.1, 0.1, 0.1, 0.5]))
+                .with_image(UiImage::SolidColor([0.91, 0.8, 0.58, 1.0]))
+                .with_hover_image(UiImage::SolidColor([0.35, 0.35, 0.35, 0.5]))
                 .build_from_world(&world);
 
         // initialize the scene with an object, a light and a camera.
@@ -1,15 +1,15 @@
 //! The core engine framework.
 
 use std::{
-    env,
     marker::PhantomData,
+    net::SocketAddr,
     path::{Path, PathBuf},
-    sync::Arc,
     time::Duration,
 };
 
 use derivative::Derivative;
 use log::{debug, info, log_enabled, trace, Level};
+#[cfg(not(no_threading))]
 use rayon::ThreadPoolBuilder;
 #[cfg(feature = "profiler")]
 use thread_profiler::{profile_scope, register_thread_with_profiler, write_profile};
@@ -17,12 +17,16 @@ use winit::event::{Event, WindowEvent};
 
 #[cfg(feature = "asset-daemon")]
 use crate::assets::AssetDaemon;
+#[cfg(not(no_threading))]
+use crate::core::ArcThreadPool;
+#[cfg(target_arch = "wasm32")]
+use crate::window::{WebEventLoop, WebEvents};
 use crate::{
-    assets::{DefaultLoader, Source},
+    assets::{DefaultLoader, LoaderSource, Source},
     core::{
         frame_limiter::{FrameLimiter, FrameRateLimitConfig, FrameRateLimitStrategy},
         shrev::{EventChannel, ReaderId},
-        ArcThreadPool, EventReader, Stopwatch, Time,
+        EventReader, Random, Stopwatch, Time,
     },
     ecs::{Resource, Resources, World},
     error::Error,
@@ -67,7 +71,7 @@ where
     data: T,
     #[cfg(feature = "asset-daemon")]
     #[derivative(Debug = "ignore")]
-    asset_daemon: AssetDaemon,
+    asset_daemon: Option<AssetDaemon>,
 }
 
 /// An Application is the root object of the game engine. It binds the OS
@@ -225,6 +229,7 @@ where
     ///
     /// See the example supplied in the
     /// [`new`](struct.Application.html#examples) method.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn run(mut self) {
         #[cfg(feature = "sentry")]
         let _sentry_guard = option_env!("SENTRY_DSN").map_or(None, |dsn| {
@@ -237,28 +242,76 @@ where
         self.resources.get_mut::<Stopwatch>().unwrap().start();
 
         while self.states.is_running() {
-            self.advance_frame();
-            {
-                #[cfg(feature = "profiler")]
-                profile_scope!("frame_limiter wait");
-                self.resources.get_mut::<FrameLimiter>().unwrap().wait();
+            self.run_frame();
+        }
+        self.shutdown();
+    }
+
+    /// Run the gameloop until the game state indicates that the game is no
+    /// longer running.
+    ///
+    /// In the browser, the frames are run on every animation frame by the `WebEventLoop` inserted
+    /// by `WindowBundle`, and this function never returns. Without a window, the frames are run
+    /// right away like on other platforms.
+    #[cfg(target_arch = "wasm32")]
+    pub fn run(mut self)
+    where
+        Self: 'static,
+    {
+        self.initialize();
+
+        self.resources.get_mut::<Stopwatch>().unwrap().start();
+
+        match self.resources.remove::<WebEventLoop>() {
+            Some(event_loop) => {
+                event_loop.run(move |events| {
+                    self.resources
+                        .get_mut::<WebEvents>()
+                        .unwrap()
+                        .append(events);
+                    self.run_frame();
+                    if self.states.is_running() {
+                        return true;
+                    }
+                    self.shutdown();
+                    false
+                });
             }
-            {
-                let mut stopwatch = self.resources.get_mut::<Stopwatch>().unwrap();
-                let elapsed = stopwatch.elapsed();
-                let mut time = self.resources.get_mut::<Time>().unwrap();
-                time.advance_frame(elapsed);
-                stopwatch.stop();
-                stopwatch.restart();
+            None => {
+                while self.states.is_running() {
+                    self.run_frame();
+                }
+                self.shutdown();
             }
         }
-        self.shutdown();
+    }
+
+    /// Advances a frame, waits for the frame limiter and updates the time.
+    fn run_frame(&mut self) {
+        self.advance_frame();
+        // The browser paces the animation frames itself, and can't block.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("frame_limiter wait");
+            self.resources.get_mut::<FrameLimiter>().unwrap().wait();
+        }
+        {
+            let mut stopwatch = self.resources.get_mut::<Stopwatch>().unwrap();
+            let elapsed = stopwatch.elapsed();
+            let mut time = self.resources.get_mut::<Time>().unwrap();
+            time.advance_frame(elapsed);
+            stopwatch.stop();
+            stopwatch.restart();
+        }
     }
 
     /// Sets up the application.
     fn initialize(&mut self) {
         #[cfg(feature = "asset-daemon")]
-        self.asset_daemon.start_on_new_thread();
+        if let Some(asset_daemon) = &mut self.asset_daemon {
+            asset_daemon.start_on_new_thread();
+        }
 
         #[cfg(feature = "profiler")]
         profile_scope!("initialize");
@@ -377,7 +430,9 @@ where
     /// Cleans up after the quit signal is received.
     fn shutdown(&mut self) {
         #[cfg(feature = "asset-daemon")]
-        self.asset_daemon.stop_and_join();
+        if let Some(asset_daemon) = &mut self.asset_daemon {
+            asset_daemon.stop_and_join();
+        }
 
         info!("Engine is shutting down");
         self.data.dispose(&mut self.world, &mut self.resources);
@@ -502,7 +557,8 @@ where
             info!("Rustc git commit: {}", hash);
         }
 
-        let thread_count: Option<usize> = env::var("AMETHYST_NUM_THREADS")
+        #[cfg(not(no_threading))]
+        let thread_count: Option<usize> = std::env::var("AMETHYST_NUM_THREADS")
             .as_ref()
             .map(|s| {
                 s.as_str()
@@ -514,27 +570,33 @@ where
         let world = World::default();
         let mut resources = Resources::default();
 
-        let thread_pool_builder = ThreadPoolBuilder::new();
-        #[cfg(feature = "profiler")]
-        let thread_pool_builder = thread_pool_builder.start_handler(|_index| {
-            register_thread_with_profiler();
-        });
-        let pool: ArcThreadPool;
-        if let Some(thread_count) = thread_count {
-            debug!("Running Amethyst with fixed thread pool: {}", thread_count);
-            pool = thread_pool_builder
-                .num_threads(thread_count)
-                .build()
-                .map(Arc::new)?;
-        } else {
-            pool = thread_pool_builder.build().map(Arc::new)?;
+        // Without threads, e.g. in the browser, the systems run one after the other on the main
+        // thread and there is no pool to provide.
+        #[cfg(not(no_threading))]
+        {
+            let thread_pool_builder = ThreadPoolBuilder::new();
+            #[cfg(feature = "profiler")]
+            let thread_pool_builder = thread_pool_builder.start_handler(|_index| {
+                register_thread_with_profiler();
+            });
+            let pool: ArcThreadPool;
+            if let Some(thread_count) = thread_count {
+                debug!("Running Amethyst with fixed thread pool: {}", thread_count);
+                pool = thread_pool_builder
+                    .num_threads(thread_count)
+                    .build()
+                    .map(std::sync::Arc::new)?;
+            } else {
+                pool = thread_pool_builder.build().map(std::sync::Arc::new)?;
+            }
+            resources.insert(pool);
         }
-        resources.insert(pool);
         resources.insert(EventChannel::<Event<'static, ()>>::with_capacity(2000));
         //resources.insert(EventChannel::<UiEvent>::with_capacity(40));
         resources.insert(FrameLimiter::default());
         resources.insert(Stopwatch::default());
         resources.insert(Time::default());
+        resources.insert(Random::from_entropy());
 
         let asset_dirs = vec![path.as_ref().to_path_buf()];
 
@@ -746,6 +808,22 @@ where
         self
     }
 
+    /// Seeds the [`Random`] resource, making its number streams deterministic.
+    ///
+    /// By default streams are seeded from system entropy.
+    ///
+    /// # Parameters
+    ///
+    /// `seed`: the master seed every random number stream is derived from.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `ApplicationBuilder` after modifying it.
+    pub fn with_random_seed(mut self, seed: u64) -> Self {
+        self.resources.insert(Random::with_seed(seed));
+        self
+    }
+
     /// Sets the duration between fixed updates, defaults to one sixtieth of a second.
     ///
     /// # Parameters
@@ -781,6 +859,39 @@ where
         self
     }
 
+    /// Reads the assets from a read-only asset pack instead of importing them with the asset
+    /// daemon, which isn't started.
+    ///
+    /// The pack is precomputed from the asset database with the distill `pack` command, so
+    /// shipped games don't import any asset on their first launch.
+    ///
+    /// # Parameters
+    ///
+    /// `path`: Path to the asset pack.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `ApplicationBuilder` after modifying it.
+    pub fn with_asset_pack<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.resources.insert(LoaderSource::Pack(path.into()));
+        self
+    }
+
+    /// Sets the loopback address the in-process asset daemon listens at, `127.0.0.1:9999` by
+    /// default. Games or tests running side by side must use different addresses.
+    ///
+    /// # Parameters
+    ///
+    /// `address`: Socket address of the asset daemon.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `ApplicationBuilder` after modifying it.
+    pub fn with_asset_daemon_address(mut self, address: SocketAddr) -> Self {
+        self.resources.insert(LoaderSource::Daemon(address));
+        self
+    }
+
     /// Build an `Application` object using the `ApplicationBuilder` as configured.
     ///
     /// # Returns
@@ -817,6 +928,17 @@ where
         #[cfg(feature = "profiler")]
         profile_scope!("new");
 
+        // The daemon runs in-process and listens at the address the loader connects to, unless
+        // the assets are read from a pack.
+        #[cfg(feature = "asset-daemon")]
+        let asset_daemon = match self.resources.get::<LoaderSource>().map(|s| s.clone()) {
+            Some(LoaderSource::Pack(_)) => None,
+            Some(LoaderSource::Daemon(address)) => {
+                Some(AssetDaemon::new(self.asset_dirs.clone()).with_address(address))
+            }
+            None => Some(AssetDaemon::new(self.asset_dirs.clone())),
+        };
+
         let data = init.build(&mut self.world, &mut self.resources)?;
 
         let event_reader_id = self
@@ -843,7 +965,7 @@ where
             event_reader_id,
             trans_reader_id,
             #[cfg(feature = "asset-daemon")]
-            asset_daemon: AssetDaemon::new(self.asset_dirs),
+            asset_daemon,
         })
     }
 }
@@ -76,6 +76,18 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::new_without_default, clippy::module_name_repetitions)]
 
+// Browsers provide neither threads, sockets nor direct audio device access.
+#[cfg(all(target_arch = "wasm32", feature = "asset-daemon"))]
+compile_error!("the feature \"asset-daemon\" is not supported when building for wasm32");
+#[cfg(all(target_arch = "wasm32", feature = "audio"))]
+compile_error!("the feature \"audio\" is not supported when building for wasm32");
+#[cfg(all(target_arch = "wasm32", feature = "network"))]
+compile_error!("the feature \"network\" is not supported when building for wasm32");
+#[cfg(all(target_arch = "wasm32", feature = "timeline"))]
+compile_error!("the feature \"timeline\" is not supported when building for wasm32");
+#[cfg(all(target_arch = "wasm32", feature = "video"))]
+compile_error!("the feature \"video\" is not supported when building for wasm32");
+
 #[cfg(feature = "animation")]
 pub use amethyst_animation as animation;
 pub use amethyst_assets as assets;
@@ -85,6 +97,8 @@ pub use amethyst_config as config;
 pub use amethyst_controls as controls;
 pub use amethyst_core as core;
 pub use amethyst_derive as derive;
+#[cfg(feature = "dialogue")]
+pub use amethyst_dialogue as dialogue;
 pub use amethyst_error as error;
 #[cfg(feature = "gltf")]
 pub use amethyst_gltf as gltf;
@@ -97,10 +111,14 @@ pub use amethyst_network as network;
 pub use amethyst_rendy as renderer;
 #[cfg(feature = "tiles")]
 pub use amethyst_tiles as tiles;
+#[cfg(feature = "timeline")]
+pub use amethyst_timeline as timeline;
 #[cfg(feature = "ui")]
 pub use amethyst_ui as ui;
 #[cfg(feature = "utils")]
 pub use amethyst_utils as utils;
+#[cfg(feature = "video")]
+pub use amethyst_video as video;
 pub use amethyst_window as window;
 pub use winit;
 
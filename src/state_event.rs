@@ -17,7 +17,8 @@ use crate::{
 #[derive(Clone, Debug, EventReader)]
 #[reader(StateEventReader)]
 pub enum StateEvent {
-    /// Events sent by the winit window.
+    /// Events sent by the winit window, including `Event::Suspended` and `Event::Resumed` when
+    /// the platform moves the application to the background and back.
     Window(Event<'static, ()>),
     /// Events sent by the ui system.
     #[cfg(feature = "ui")]